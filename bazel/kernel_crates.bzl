@@ -19,6 +19,9 @@ def crate_label(package):
 # external packages in @crates.
 KERNEL_CRATES = {
     "abi": struct(deps = [], crates = ["bitflags"]),
+    "cmdline": struct(deps = ["device", "ext2", "vfs"], crates = ["thiserror"]),
+    "console": struct(deps = [], crates = []),
+    "cpio": struct(deps = ["abi"], crates = ["thiserror"]),
     "devfs": struct(
         deps = ["abi", "device", "vfs"],
         crates = ["spin", "thiserror"],
@@ -26,15 +29,24 @@ KERNEL_CRATES = {
     "device": struct(deps = [], crates = ["spin", "thiserror", "x86_64"]),
     "elfloader": struct(deps = [], crates = ["thiserror", "zerocopy"]),
     "ext2": struct(deps = ["device"], crates = ["bitflags", "spin"]),
+    "fat": struct(deps = ["abi", "device", "vfs"], crates = ["thiserror"]),
+    "input": struct(deps = ["abi"], crates = []),
+    "iso9660": struct(deps = ["abi", "device", "vfs"], crates = ["thiserror"]),
+    "keyboard": struct(deps = ["abi"], crates = ["bitflags"]),
     "log": struct(deps = [], crates = ["conquer-once", "spin", "tracing", "tracing-core"]),
     "memapi": struct(deps = [], crates = ["x86_64"]),
+    "mouse": struct(deps = ["abi"], crates = ["bitflags"]),
     "park": struct(deps = [], crates = ["thiserror"]),
     "pci": struct(deps = ["memapi"], crates = ["spin", "thiserror", "x86_64"]),
     "physical_memory": struct(deps = [], crates = ["thiserror", "x86_64"]),
+    "procfs": struct(deps = ["abi", "vfs"], crates = []),
     "syscall": struct(
         deps = ["abi", "vfs"],
         crates = ["spin", "thiserror", "tracing", "x86_64"],
     ),
+    "sysfs": struct(deps = ["abi", "pci", "vfs"], crates = []),
+    "tmpfs": struct(deps = ["abi", "vfs"], crates = ["spin"]),
+    "tty": struct(deps = ["abi"], crates = []),
     "vfs": struct(deps = ["abi"], crates = ["spin", "thiserror"]),
     "virtual_memory": struct(deps = [], crates = ["thiserror", "tracing", "x86_64"]),
 }
@@ -1,8 +1,10 @@
 """Bootable image assembly.
 
-Both rules shell out to host tools, `mke2fs` from e2fsprogs and `xorriso`. They
-are host prerequisites rather than Bazel toolchains, so a machine missing either
-fails the build with that tool's own "not found" message.
+ext2 images are built by //tools/mkext2 through the kernel's own ext2 crate, so
+they come out byte for byte the same on every host. The other rules shell out
+to host tools, `mkfs.vfat` from dosfstools, `mcopy` from mtools and `xorriso`.
+They are host prerequisites rather than Bazel toolchains, so a machine missing
+one fails the build with that tool's own "not found" message.
 """
 
 # Runs a command, discarding its output unless it fails. Both streams are merged
@@ -26,12 +28,11 @@ def _stage(dest, src_path):
         'cp {} "$root/{}"'.format(src_path, dest),
     ]
 
-def _ext2_image_impl(ctx):
-    out = ctx.actions.declare_file(ctx.label.name + ".img")
-
+def _stage_tree(ctx):
+    """Returns the inputs and commands that stage the tree of an image in `$root`."""
     inputs = []
 
-    # mke2fs ships in sbin, which is absent from the default action PATH.
+    # mkfs.vfat ships in sbin, which is absent from the default action PATH.
     cmds = [
         "set -eu",
         'export PATH="$PATH:/usr/sbin:/sbin"',
@@ -53,16 +54,55 @@ def _ext2_image_impl(ctx):
         inputs.append(staged)
         cmds.extend(_stage(dest, staged.path))
 
-    cmds.append('mke2fs -q -d "$root" -m 5 -t ext2 {} {}'.format(out.path, ctx.attr.image_size))
+    return inputs, cmds
 
-    ctx.actions.run_shell(
+_TREE_ATTRS = {
+    "contents": attr.string_dict(
+        doc = "Destination path inside the image to literal file content.",
+    ),
+    "empty_dirs": attr.string_list(
+        doc = "Directories to create inside the image with no contents.",
+    ),
+    "files": attr.label_keyed_string_dict(
+        allow_files = True,
+        doc = "Single-file target to its destination path inside the image.",
+    ),
+}
+
+def _ext2_image_impl(ctx):
+    out = ctx.actions.declare_file(ctx.label.name + ".img")
+
+    # The tree is described in a manifest instead of being staged, see
+    # //tools/mkext2 for the format.
+    manifest = ctx.actions.declare_file(ctx.label.name + ".manifest")
+    inputs = [manifest]
+    entries = ["dir\t{}".format(directory) for directory in ctx.attr.empty_dirs]
+
+    for target, dest in ctx.attr.files.items():
+        staged = _single_file(target)
+        inputs.append(staged)
+        entries.append("file\t{}\t{}".format(dest, staged.path))
+
+    for dest, content in ctx.attr.contents.items():
+        staged = ctx.actions.declare_file("{}.contents/{}".format(ctx.label.name, dest))
+        ctx.actions.write(staged, content)
+        inputs.append(staged)
+        entries.append("file\t{}\t{}".format(dest, staged.path))
+
+    ctx.actions.write(manifest, "".join([entry + "\n" for entry in entries]))
+
+    args = ctx.actions.args()
+    args.add("--manifest", manifest)
+    args.add("--size", ctx.attr.image_size)
+    args.add(out)
+
+    ctx.actions.run(
         outputs = [out],
         inputs = inputs,
-        command = "\n".join(cmds),
+        executable = ctx.executable._mkext2,
+        arguments = [args],
         mnemonic = "Ext2Image",
         progress_message = "Building ext2 image %{output}",
-        # The host e2fsprogs install has to stay reachable from the action.
-        execution_requirements = {"no-sandbox": "1"},
     )
 
     return [DefaultInfo(files = depset([out]))]
@@ -70,23 +110,130 @@ def _ext2_image_impl(ctx):
 ext2_image = rule(
     implementation = _ext2_image_impl,
     doc = "Builds an ext2 filesystem image from staged files, literal contents, and empty dirs.",
-    attrs = {
-        "contents": attr.string_dict(
-            doc = "Destination path inside the image to literal file content.",
-        ),
-        "empty_dirs": attr.string_list(
-            doc = "Directories to create inside the image with no contents.",
-        ),
-        "files": attr.label_keyed_string_dict(
-            allow_files = True,
-            doc = "Single-file target to its destination path inside the image.",
-        ),
+    attrs = dict(_TREE_ATTRS, **{
         # Not `size`: Bazel reserves that attribute name for test targets.
         "image_size": attr.string(
             default = "64M",
-            doc = "Filesystem size passed to mke2fs.",
+            doc = "Filesystem size, in bytes or with a K, M or G suffix.",
         ),
-    },
+        "_mkext2": attr.label(
+            default = "//tools/mkext2",
+            executable = True,
+            cfg = "exec",
+        ),
+    }),
+)
+
+def _fat_image_impl(ctx):
+    out = ctx.actions.declare_file(ctx.label.name + ".img")
+    inputs, cmds = _stage_tree(ctx)
+
+    mkfs = [
+        "mkfs.vfat",
+        "-C",
+        "-F {}".format(ctx.attr.fat_size),
+        # A fixed volume id keeps the image reproducible.
+        "-i {}".format(ctx.attr.volume_id),
+        "-n {}".format(ctx.attr.label),
+    ]
+    if ctx.attr.sectors_per_cluster:
+        mkfs.append("-s {}".format(ctx.attr.sectors_per_cluster))
+    mkfs.extend([out.path, str(ctx.attr.image_size_kib)])
+    cmds.append("{} >/dev/null".format(" ".join(mkfs)))
+
+    # mcopy refuses images whose geometry doesn't look like a real disk.
+    cmds.append("export MTOOLS_SKIP_CHECK=1")
+    cmds.append(
+        'find "$root" -mindepth 1 -maxdepth 1 -exec mcopy -s -i {} {{}} ::/ \\;'.format(out.path),
+    )
+
+    ctx.actions.run_shell(
+        outputs = [out],
+        inputs = inputs,
+        command = "\n".join(cmds),
+        mnemonic = "FatImage",
+        progress_message = "Building FAT image %{output}",
+        # The host dosfstools and mtools installs have to stay reachable from
+        # the action.
+        execution_requirements = {"no-sandbox": "1"},
+    )
+
+    return [DefaultInfo(files = depset([out]))]
+
+fat_image = rule(
+    implementation = _fat_image_impl,
+    doc = "Builds a FAT12, FAT16 or FAT32 filesystem image from staged files, literal contents, and empty dirs.",
+    attrs = dict(_TREE_ATTRS, **{
+        "fat_size": attr.int(
+            default = 32,
+            values = [12, 16, 32],
+            doc = "FAT entry width passed to mkfs.vfat -F.",
+        ),
+        "image_size_kib": attr.int(
+            default = 65536,
+            doc = "Filesystem size in KiB passed to mkfs.vfat.",
+        ),
+        "label": attr.string(
+            default = "MUFFIN",
+            doc = "Volume label passed to mkfs.vfat -n.",
+        ),
+        "sectors_per_cluster": attr.int(
+            doc = "Cluster size in sectors passed to mkfs.vfat -s. 0 lets mkfs.vfat pick.",
+        ),
+        "volume_id": attr.string(
+            default = "12345678",
+            doc = "Volume id in hex passed to mkfs.vfat -i.",
+        ),
+    }),
+)
+
+def _iso9660_image_impl(ctx):
+    out = ctx.actions.declare_file(ctx.label.name + ".iso")
+    inputs, cmds = _stage_tree(ctx)
+    cmds.insert(1, _QUIET_FN)
+
+    for dest, target in ctx.attr.symlinks.items():
+        cmds.append('mkdir -p "$(dirname "$root/{}")"'.format(dest))
+        cmds.append('ln -s "{}" "$root/{}"'.format(target, dest))
+
+    # Staged files inherit the read-only, sometimes executable modes of Bazel
+    # outputs. Rock Ridge records the modes, so give them the ones of a
+    # checkout.
+    cmds.append('find "$root" -type d -exec chmod 0755 {} +')
+    cmds.append('find "$root" -type f -exec chmod 0644 {} +')
+
+    xorriso = ["quiet xorriso -as mkisofs", "-volid {}".format(ctx.attr.label)]
+    if ctx.attr.rock_ridge:
+        xorriso.append("-R")
+    xorriso.extend(['"$root"', "-o", out.path])
+    cmds.append(" ".join(xorriso))
+
+    ctx.actions.run_shell(
+        outputs = [out],
+        inputs = inputs,
+        command = "\n".join(cmds),
+        mnemonic = "Iso9660Image",
+        progress_message = "Building ISO9660 image %{output}",
+    )
+
+    return [DefaultInfo(files = depset([out]))]
+
+iso9660_image = rule(
+    implementation = _iso9660_image_impl,
+    doc = "Builds an ISO9660 filesystem image from staged files, literal contents, empty dirs, and symlinks.",
+    attrs = dict(_TREE_ATTRS, **{
+        "label": attr.string(
+            default = "MUFFIN",
+            doc = "Volume id passed to xorriso -volid.",
+        ),
+        "rock_ridge": attr.bool(
+            default = True,
+            doc = "Whether to record long names, modes and symlinks with Rock Ridge.",
+        ),
+        "symlinks": attr.string_dict(
+            doc = "Destination path inside the image to symlink target. Needs Rock Ridge.",
+        ),
+    }),
 )
 
 def _limine_iso_impl(ctx):
@@ -114,8 +261,17 @@ def _limine_iso_impl(ctx):
     for f in ctx.files._efi:
         cmds.append('cp {} "$root/EFI/BOOT/{}"'.format(f.path, f.basename))
 
+    inputs = [ctx.file.kernel, ctx.file.limine_conf] + ctx.files._bios + ctx.files._efi
+    for target, dest in ctx.attr.files.items():
+        staged = _single_file(target)
+        inputs.append(staged)
+        cmds.extend(_stage(dest, staged.path))
+
     cmds.append(" ".join([
         "quiet xorriso -as mkisofs",
+        # Rock Ridge keeps names and modes intact for the kernel, which
+        # mounts the image at /boot.
+        "-R",
         "-b boot/limine/limine-bios-cd.bin",
         "-no-emul-boot",
         "-boot-load-size 4",
@@ -135,7 +291,7 @@ def _limine_iso_impl(ctx):
 
     ctx.actions.run_shell(
         outputs = [out],
-        inputs = [ctx.file.kernel, ctx.file.limine_conf] + ctx.files._bios + ctx.files._efi,
+        inputs = inputs,
         tools = [limine],
         command = "\n".join(cmds),
         mnemonic = "LimineIso",
@@ -148,6 +304,10 @@ limine_iso = rule(
     implementation = _limine_iso_impl,
     doc = "Builds a hybrid BIOS/UEFI bootable Limine ISO around a kernel ELF.",
     attrs = {
+        "files": attr.label_keyed_string_dict(
+            allow_files = True,
+            doc = "Single-file target to its destination path inside the image, for assets that the kernel reads from /boot.",
+        ),
         "kernel": attr.label(
             allow_single_file = True,
             mandatory = True,
@@ -0,0 +1,459 @@
+macro_rules! key_codes {
+    ($($(#[$m:meta])* $name:ident = $val:literal),*,) => {
+        /// A key on a keyboard or a button, numbered like the `KEY_*` and
+        /// `BTN_*` codes of Linux input devices. The codes up to
+        /// [`KeyCode::F12`] are also the scancode set 1 make codes of the
+        /// keys.
+        #[repr(u16)]
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        pub enum KeyCode {
+            $(
+                $(#[$m])*
+                $name = $val,
+            )*
+        }
+
+        impl KeyCode {
+            #[must_use]
+            pub const fn number(self) -> u16 {
+                self as u16
+            }
+
+            #[must_use]
+            pub const fn from_number(number: u16) -> Option<Self> {
+                match number {
+                    $($val => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+key_codes! {
+    Esc = 1,
+    Num1 = 2,
+    Num2 = 3,
+    Num3 = 4,
+    Num4 = 5,
+    Num5 = 6,
+    Num6 = 7,
+    Num7 = 8,
+    Num8 = 9,
+    Num9 = 10,
+    Num0 = 11,
+    Minus = 12,
+    Equal = 13,
+    Backspace = 14,
+    Tab = 15,
+    Q = 16,
+    W = 17,
+    E = 18,
+    R = 19,
+    T = 20,
+    Y = 21,
+    U = 22,
+    I = 23,
+    O = 24,
+    P = 25,
+    LeftBrace = 26,
+    RightBrace = 27,
+    Enter = 28,
+    LeftCtrl = 29,
+    A = 30,
+    S = 31,
+    D = 32,
+    F = 33,
+    G = 34,
+    H = 35,
+    J = 36,
+    K = 37,
+    L = 38,
+    Semicolon = 39,
+    Apostrophe = 40,
+    Grave = 41,
+    LeftShift = 42,
+    Backslash = 43,
+    Z = 44,
+    X = 45,
+    C = 46,
+    V = 47,
+    B = 48,
+    N = 49,
+    M = 50,
+    Comma = 51,
+    Dot = 52,
+    Slash = 53,
+    RightShift = 54,
+    KpAsterisk = 55,
+    LeftAlt = 56,
+    Space = 57,
+    CapsLock = 58,
+    F1 = 59,
+    F2 = 60,
+    F3 = 61,
+    F4 = 62,
+    F5 = 63,
+    F6 = 64,
+    F7 = 65,
+    F8 = 66,
+    F9 = 67,
+    F10 = 68,
+    NumLock = 69,
+    ScrollLock = 70,
+    Kp7 = 71,
+    Kp8 = 72,
+    Kp9 = 73,
+    KpMinus = 74,
+    Kp4 = 75,
+    Kp5 = 76,
+    Kp6 = 77,
+    KpPlus = 78,
+    Kp1 = 79,
+    Kp2 = 80,
+    Kp3 = 81,
+    Kp0 = 82,
+    KpDot = 83,
+    /// The additional key between left shift and Z on ISO keyboards.
+    Key102nd = 86,
+    F11 = 87,
+    F12 = 88,
+    KpEnter = 96,
+    RightCtrl = 97,
+    KpSlash = 98,
+    SysRq = 99,
+    RightAlt = 100,
+    Home = 102,
+    Up = 103,
+    PageUp = 104,
+    Left = 105,
+    Right = 106,
+    End = 107,
+    Down = 108,
+    PageDown = 109,
+    Insert = 110,
+    Delete = 111,
+    Pause = 119,
+    LeftMeta = 125,
+    RightMeta = 126,
+    Compose = 127,
+    BtnLeft = 0x110,
+    BtnRight = 0x111,
+    BtnMiddle = 0x112,
+    /// The back button of a mouse.
+    BtnSide = 0x113,
+    /// The forward button of a mouse.
+    BtnExtra = 0x114,
+    /// A tablet or touchscreen is touched.
+    BtnTouch = 0x14a,
+}
+
+/// Key and button codes are below this, which sizes
+/// [`InputCapabilities::keys`].
+pub const KEY_CODE_LIMIT: usize = 0x300;
+
+/// The type of an [`InputEvent`], which says what its code and value mean.
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EventType {
+    /// Ends a batch of events that happened at the same time, or reports
+    /// that events were dropped. The code is [`SYN_REPORT`] or
+    /// [`SYN_DROPPED`].
+    Sync = 0,
+    /// The code is a [`KeyCode`], the value [`KEY_RELEASED`] or
+    /// [`KEY_PRESSED`].
+    Key = 1,
+    /// The code is a [`RelativeAxis`], the value how far it moved.
+    Relative = 2,
+    /// The code is an [`AbsoluteAxis`], the value its new position.
+    Absolute = 3,
+}
+
+impl EventType {
+    #[must_use]
+    pub const fn number(self) -> u16 {
+        self as u16
+    }
+
+    #[must_use]
+    pub const fn from_number(number: u16) -> Option<Self> {
+        match number {
+            0 => Some(Self::Sync),
+            1 => Some(Self::Key),
+            2 => Some(Self::Relative),
+            3 => Some(Self::Absolute),
+            _ => None,
+        }
+    }
+}
+
+/// A [`EventType::Sync`] code that ends a batch of events.
+pub const SYN_REPORT: u16 = 0;
+/// A [`EventType::Sync`] code that tells that the reader fell behind and
+/// events were dropped. The events up to the next [`SYN_REPORT`] are
+/// incomplete.
+pub const SYN_DROPPED: u16 = 3;
+
+/// [`InputEvent::value`] of a released key.
+pub const KEY_RELEASED: i32 = 0;
+/// [`InputEvent::value`] of a pressed key.
+pub const KEY_PRESSED: i32 = 1;
+
+/// An axis that reports movement, like the `REL_*` codes of Linux.
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RelativeAxis {
+    X = 0,
+    Y = 1,
+    /// A horizontal scroll wheel.
+    HWheel = 6,
+    /// A vertical scroll wheel, positive away from the user.
+    Wheel = 8,
+}
+
+/// An axis that reports positions, like the `ABS_*` codes of Linux.
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AbsoluteAxis {
+    X = 0,
+    Y = 1,
+}
+
+/// Relative axis codes are below this.
+pub const RELATIVE_AXIS_LIMIT: usize = 0x10;
+/// Absolute axis codes are below this.
+pub const ABSOLUTE_AXIS_LIMIT: usize = 0x40;
+
+/// An event of an input device, as read from `/dev/input/eventN`, laid out
+/// like `struct input_event` of Linux but with a single time field.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct InputEvent {
+    /// When the event happened, in nanoseconds since boot.
+    pub time: u64,
+    /// An [`EventType`] number.
+    pub kind: u16,
+    /// What `kind` makes of it, like a [`KeyCode`] number.
+    pub code: u16,
+    pub value: i32,
+}
+
+impl InputEvent {
+    pub const SIZE: usize = size_of::<Self>();
+
+    #[must_use]
+    pub const fn new(time: u64, kind: EventType, code: u16, value: i32) -> Self {
+        Self {
+            time,
+            kind: kind.number(),
+            code,
+            value,
+        }
+    }
+
+    #[must_use]
+    pub const fn kind(self) -> Option<EventType> {
+        EventType::from_number(self.kind)
+    }
+
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.time.to_ne_bytes());
+        bytes[8..10].copy_from_slice(&self.kind.to_ne_bytes());
+        bytes[10..12].copy_from_slice(&self.code.to_ne_bytes());
+        bytes[12..16].copy_from_slice(&self.value.to_ne_bytes());
+        bytes
+    }
+
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let mut time = [0; 8];
+        time.copy_from_slice(&bytes[0..8]);
+        Self {
+            time: u64::from_ne_bytes(time),
+            kind: u16::from_ne_bytes([bytes[8], bytes[9]]),
+            code: u16::from_ne_bytes([bytes[10], bytes[11]]),
+            value: i32::from_ne_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        }
+    }
+}
+
+/// The events that an input device can report, read with
+/// `InputGetCapabilities`. Each field is a bitmap, indexed by the
+/// [`EventType`], [`KeyCode`], [`RelativeAxis`] and [`AbsoluteAxis`]
+/// numbers.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct InputCapabilities {
+    pub keys: [u64; KEY_CODE_LIMIT / 64],
+    pub absolute: u64,
+    pub relative: u32,
+    pub types: u32,
+}
+
+impl InputCapabilities {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Adds `kind` with `code`. Codes beyond the limit of their type are
+    /// ignored, and so is the code of [`EventType::Sync`].
+    pub fn insert(&mut self, kind: EventType, code: u16) {
+        let code = usize::from(code);
+        self.types |= 1 << kind.number();
+        match kind {
+            EventType::Sync => {}
+            EventType::Key if code < KEY_CODE_LIMIT => self.keys[code / 64] |= 1 << (code % 64),
+            EventType::Relative if code < RELATIVE_AXIS_LIMIT => self.relative |= 1 << code,
+            EventType::Absolute if code < ABSOLUTE_AXIS_LIMIT => self.absolute |= 1 << code,
+            _ => {}
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, kind: EventType, code: u16) -> bool {
+        let code = usize::from(code);
+        self.types & (1 << kind.number()) != 0
+            && match kind {
+                EventType::Sync => true,
+                EventType::Key => {
+                    code < KEY_CODE_LIMIT && self.keys[code / 64] & (1 << (code % 64)) != 0
+                }
+                EventType::Relative => {
+                    code < RELATIVE_AXIS_LIMIT && self.relative & (1 << code) != 0
+                }
+                EventType::Absolute => {
+                    code < ABSOLUTE_AXIS_LIMIT && self.absolute & (1 << code) != 0
+                }
+            }
+    }
+
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        let (keys, rest) = bytes.split_at_mut(KEY_CODE_LIMIT / 8);
+        for (dst, word) in keys.as_chunks_mut::<8>().0.iter_mut().zip(self.keys) {
+            *dst = word.to_ne_bytes();
+        }
+        rest[0..8].copy_from_slice(&self.absolute.to_ne_bytes());
+        rest[8..12].copy_from_slice(&self.relative.to_ne_bytes());
+        rest[12..16].copy_from_slice(&self.types.to_ne_bytes());
+        bytes
+    }
+
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let (keys, rest) = bytes.split_at(KEY_CODE_LIMIT / 8);
+        let mut capabilities = Self::default();
+        for (word, src) in capabilities.keys.iter_mut().zip(keys.as_chunks::<8>().0) {
+            *word = u64::from_ne_bytes(*src);
+        }
+        capabilities.absolute = u64::from_ne_bytes(rest[0..8].try_into().unwrap());
+        capabilities.relative = u32::from_ne_bytes(rest[8..12].try_into().unwrap());
+        capabilities.types = u32::from_ne_bytes(rest[12..16].try_into().unwrap());
+        capabilities
+    }
+}
+
+/// The range of an absolute axis, read with `InputGetAbsInfo`. The caller
+/// sets `axis`, the device fills in the rest.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct AbsInfo {
+    /// An [`AbsoluteAxis`] number.
+    pub axis: u32,
+    /// The last reported position.
+    pub value: i32,
+    pub minimum: i32,
+    pub maximum: i32,
+}
+
+impl AbsInfo {
+    pub const SIZE: usize = size_of::<Self>();
+
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.axis.to_ne_bytes());
+        bytes[4..8].copy_from_slice(&self.value.to_ne_bytes());
+        bytes[8..12].copy_from_slice(&self.minimum.to_ne_bytes());
+        bytes[12..16].copy_from_slice(&self.maximum.to_ne_bytes());
+        bytes
+    }
+
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+        Self {
+            axis: u32::from_ne_bytes(word(0)),
+            value: i32::from_ne_bytes(word(4)),
+            minimum: i32::from_ne_bytes(word(8)),
+            maximum: i32::from_ne_bytes(word(12)),
+        }
+    }
+}
+
+/// Size of the buffer that `InputGetName` fills with the name of a device,
+/// padded with NUL bytes.
+pub const INPUT_NAME_SIZE: usize = 64;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_code_number_round_trips() {
+        for number in 0..KEY_CODE_LIMIT as u16 {
+            if let Some(key) = KeyCode::from_number(number) {
+                assert_eq!(key.number(), number, "{key:?} should round-trip");
+            }
+        }
+        assert_eq!(KeyCode::from_number(30), Some(KeyCode::A));
+        assert_eq!(KeyCode::from_number(0x110), Some(KeyCode::BtnLeft));
+        assert_eq!(KeyCode::from_number(84), None);
+    }
+
+    #[test]
+    fn input_event_bytes_round_trip() {
+        let event = InputEvent::new(
+            1_234_567_890,
+            EventType::Relative,
+            RelativeAxis::Wheel as u16,
+            -1,
+        );
+        assert_eq!(event.kind(), Some(EventType::Relative));
+        assert_eq!(
+            InputEvent::from_bytes(&event.to_bytes()),
+            event,
+            "input event should survive a to_bytes/from_bytes round-trip"
+        );
+    }
+
+    #[test]
+    fn input_capabilities() {
+        let mut capabilities = InputCapabilities::default();
+        capabilities.insert(EventType::Key, KeyCode::BtnTouch.number());
+        capabilities.insert(EventType::Absolute, AbsoluteAxis::Y as u16);
+        capabilities.insert(EventType::Key, KEY_CODE_LIMIT as u16);
+
+        assert!(capabilities.contains(EventType::Key, KeyCode::BtnTouch.number()));
+        assert!(!capabilities.contains(EventType::Key, KeyCode::BtnLeft.number()));
+        assert!(capabilities.contains(EventType::Absolute, AbsoluteAxis::Y as u16));
+        assert!(!capabilities.contains(EventType::Relative, RelativeAxis::X as u16));
+        assert_eq!(
+            InputCapabilities::from_bytes(&capabilities.to_bytes()),
+            capabilities,
+            "capabilities should survive a to_bytes/from_bytes round-trip"
+        );
+    }
+
+    #[test]
+    fn abs_info_bytes_round_trip() {
+        let info = AbsInfo {
+            axis: 1,
+            value: 100,
+            minimum: 0,
+            maximum: 32767,
+        };
+        assert_eq!(AbsInfo::from_bytes(&info.to_bytes()), info);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{ENOTTY, Errno};
+use crate::{AbsInfo, ENOTTY, Errno, INPUT_NAME_SIZE, InputCapabilities, Termios, WinSize};
 
 /// A device control request, the typed form of the raw request number
 /// passed to the ioctl syscall.
@@ -7,6 +7,43 @@ use crate::{ENOTTY, Errno};
 pub enum IoctlRequest {
     /// Fills an [`FbScreenInfo`] with the framebuffer geometry.
     FbGetScreenInfo = 1,
+    /// Attaches a loop block device, on `/dev/loop-control`, to the
+    /// regular file that is open under the file descriptor in the
+    /// argument, a native-endian `i32`. Returns the `N` of the new
+    /// `/dev/blkN`.
+    LoopAttach = 2,
+    /// Detaches the loop block device `/dev/blkN`, on `/dev/loop-control`,
+    /// where `N` is the argument, a native-endian `u32`.
+    LoopDetach = 3,
+    /// Fills a [`Termios`] with the attributes of a terminal.
+    TcGetAttr = 4,
+    /// Changes the attributes of a terminal to the [`Termios`] in the
+    /// argument. Takes effect right away, input that was already received
+    /// is kept.
+    TcSetAttr = 5,
+    /// Returns the foreground process group of the caller's controlling
+    /// terminal. The argument is unused.
+    TcGetPgrp = 6,
+    /// Makes the process group in the argument, a native-endian `u64`, the
+    /// foreground process group of the caller's controlling terminal.
+    TcSetPgrp = 7,
+    /// Fills a [`WinSize`] with the window size of a terminal.
+    TcGetWinSize = 8,
+    /// Changes the window size of a terminal to the [`WinSize`] in the
+    /// argument. A change sends `SIGWINCH` to the foreground process group.
+    TcSetWinSize = 9,
+    /// Returns the `N` of the `/dev/pts/N` that belongs to a pseudo-terminal
+    /// master opened through `/dev/ptmx`. The argument is unused.
+    PtyGetNumber = 10,
+    /// Fills the argument, [`INPUT_NAME_SIZE`] bytes, with the name of an
+    /// input device, padded with NUL bytes. Returns the length of the name.
+    InputGetName = 11,
+    /// Fills an [`InputCapabilities`] with the events that an input device
+    /// can report.
+    InputGetCapabilities = 12,
+    /// Fills the [`AbsInfo`] in the argument with the range of the absolute
+    /// axis that its `axis` names.
+    InputGetAbsInfo = 13,
 }
 
 impl IoctlRequest {
@@ -16,6 +53,14 @@ impl IoctlRequest {
     pub const fn arg_size(self) -> usize {
         match self {
             Self::FbGetScreenInfo => FbScreenInfo::SIZE,
+            Self::LoopAttach | Self::LoopDetach => 4,
+            Self::TcGetAttr | Self::TcSetAttr => Termios::SIZE,
+            Self::TcGetPgrp | Self::TcSetPgrp => 8,
+            Self::TcGetWinSize | Self::TcSetWinSize => WinSize::SIZE,
+            Self::PtyGetNumber => 4,
+            Self::InputGetName => INPUT_NAME_SIZE,
+            Self::InputGetCapabilities => InputCapabilities::SIZE,
+            Self::InputGetAbsInfo => AbsInfo::SIZE,
         }
     }
 
@@ -32,6 +77,18 @@ impl TryFrom<usize> for IoctlRequest {
     fn try_from(value: usize) -> Result<Self, Self::Error> {
         match value {
             1 => Ok(Self::FbGetScreenInfo),
+            2 => Ok(Self::LoopAttach),
+            3 => Ok(Self::LoopDetach),
+            4 => Ok(Self::TcGetAttr),
+            5 => Ok(Self::TcSetAttr),
+            6 => Ok(Self::TcGetPgrp),
+            7 => Ok(Self::TcSetPgrp),
+            8 => Ok(Self::TcGetWinSize),
+            9 => Ok(Self::TcSetWinSize),
+            10 => Ok(Self::PtyGetNumber),
+            11 => Ok(Self::InputGetName),
+            12 => Ok(Self::InputGetCapabilities),
+            13 => Ok(Self::InputGetAbsInfo),
             _ => Err(ENOTTY),
         }
     }
@@ -78,12 +135,27 @@ mod tests {
 
     #[test]
     fn ioctl_request_number_round_trips() {
-        let request = IoctlRequest::FbGetScreenInfo;
-        assert_eq!(
-            IoctlRequest::try_from(request.number()),
-            Ok(request),
-            "request number should round-trip through TryFrom"
-        );
+        for request in [
+            IoctlRequest::FbGetScreenInfo,
+            IoctlRequest::LoopAttach,
+            IoctlRequest::LoopDetach,
+            IoctlRequest::TcGetAttr,
+            IoctlRequest::TcSetAttr,
+            IoctlRequest::TcGetPgrp,
+            IoctlRequest::TcSetPgrp,
+            IoctlRequest::TcGetWinSize,
+            IoctlRequest::TcSetWinSize,
+            IoctlRequest::PtyGetNumber,
+            IoctlRequest::InputGetName,
+            IoctlRequest::InputGetCapabilities,
+            IoctlRequest::InputGetAbsInfo,
+        ] {
+            assert_eq!(
+                IoctlRequest::try_from(request.number()),
+                Ok(request),
+                "request number should round-trip through TryFrom"
+            );
+        }
     }
 
     #[test]
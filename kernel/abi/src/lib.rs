@@ -3,24 +3,32 @@
 
 mod errno;
 mod fcntl;
+mod input;
 mod ioctl;
 mod limits;
 mod mman;
+mod mount;
+mod poll;
 mod signal;
 mod stat;
 mod sys_types;
 mod syscall;
+mod termios;
 mod time;
 
 pub mod gfx;
 
 pub use errno::*;
 pub use fcntl::*;
+pub use input::*;
 pub use ioctl::*;
 pub use limits::*;
 pub use mman::*;
+pub use mount::*;
+pub use poll::*;
 pub use signal::*;
 pub use stat::*;
 pub use sys_types::*;
 pub use syscall::*;
+pub use termios::*;
 pub use time::*;
@@ -0,0 +1,29 @@
+use bitflags::bitflags;
+
+use crate::StrSlice;
+
+bitflags! {
+    /// Flags for mount
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct MountFlags: u32 {
+        /// Files on the mount can't be written to.
+        const READ_ONLY = 0x1;
+        /// Files on the mount can't be executed.
+        const NOEXEC = 0x8;
+    }
+}
+
+/// The arguments of the mount syscall, which don't fit into registers.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct MountArgs<'a> {
+    /// The name of the file system driver, such as `ext2`.
+    pub fs_type: StrSlice<'a>,
+    /// The block device to mount, such as `/dev/blk0`. Empty for file
+    /// systems that don't need a device.
+    pub source: StrSlice<'a>,
+    /// The directory to mount at.
+    pub target: StrSlice<'a>,
+    /// The bits of [`MountFlags`].
+    pub flags: u32,
+}
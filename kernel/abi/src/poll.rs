@@ -0,0 +1,32 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// What a [`PollFd`] waits for and what happened, as passed to `poll`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct PollEvents: i16 {
+        /// Data can be read without blocking.
+        const POLLIN = 0x1;
+        /// Urgent data can be read. No file has any.
+        const POLLPRI = 0x2;
+        /// Data can be written without blocking.
+        const POLLOUT = 0x4;
+        /// An error happened on the file. Reported even if not asked for.
+        const POLLERR = 0x8;
+        /// The device was disconnected. Reported even if not asked for.
+        const POLLHUP = 0x10;
+        /// The file descriptor is not open. Reported even if not asked for.
+        const POLLNVAL = 0x20;
+    }
+}
+
+/// POSIX `struct pollfd`, one entry of the array that `poll` waits on.
+/// A negative `fd` is skipped.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct PollFd {
+    pub fd: i32,
+    /// The [`PollEvents`] to wait for.
+    pub events: i16,
+    /// The [`PollEvents`] that happened, filled in by `poll`.
+    pub revents: i16,
+}
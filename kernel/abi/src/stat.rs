@@ -1,13 +1,30 @@
+/// Mask of the file type bits in [`Stat::mode`].
+pub const S_IFMT: u32 = 0o170_000;
+pub const S_IFSOCK: u32 = 0o140_000;
+pub const S_IFLNK: u32 = 0o120_000;
+pub const S_IFREG: u32 = 0o100_000;
+pub const S_IFBLK: u32 = 0o060_000;
+pub const S_IFDIR: u32 = 0o040_000;
+pub const S_IFCHR: u32 = 0o020_000;
+pub const S_IFIFO: u32 = 0o010_000;
+
 /// File metadata copied out to userspace by the fstat syscall.
 ///
 /// This is the ring 3 wire layout. A field may only be appended, and ring 0 and
 /// ring 3 must be rebuilt together when one is.
 ///
-/// `kernel_vfs::Stat` is the only source the kernel fills this from and it
-/// holds nothing but the size. Another field has to reach that struct, and
-/// every filesystem behind it, before it can appear here.
+/// `kernel_vfs::Stat` is the only source the kernel fills this from. Another
+/// field has to reach that struct, and every filesystem behind it, before it
+/// can appear here.
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub struct Stat {
     pub size: u64,
+    /// The number of the node within its file system.
+    pub ino: u64,
+    /// The file type (`S_IF*`) and permission bits.
+    pub mode: u32,
+    pub nlink: u32,
+    /// The number of 512 byte blocks allocated for the file.
+    pub blocks: u64,
 }
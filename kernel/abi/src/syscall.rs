@@ -66,4 +66,13 @@ n! {
     SYS_EXE_PATH = 51,
     SYS_NANOSLEEP = 52,
     SYS_EXECVE = 53,
+    SYS_READLINK = 54,
+    SYS_MOUNT = 55,
+    SYS_UMOUNT = 56,
+    SYS_MOUNT_TABLE = 57,
+    SYS_GETPPID = 58,
+    SYS_GETPGID = 59,
+    SYS_SETPGID = 60,
+    SYS_GETSID = 61,
+    SYS_SETSID = 62,
 }
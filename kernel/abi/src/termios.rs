@@ -0,0 +1,229 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// Input processing of a terminal.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct InputFlags: u32 {
+        /// Translate NL to CR on input.
+        const INLCR = 0o100;
+        /// Ignore CR on input.
+        const IGNCR = 0o200;
+        /// Translate CR to NL on input, unless CR is ignored.
+        const ICRNL = 0o400;
+    }
+}
+
+bitflags! {
+    /// Output processing of a terminal.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct OutputFlags: u32 {
+        /// Enables output processing. Without it, the other output flags
+        /// have no effect.
+        const OPOST = 0o1;
+        /// Translate NL to CR-NL on output.
+        const ONLCR = 0o4;
+    }
+}
+
+bitflags! {
+    /// Line discipline of a terminal.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct LocalFlags: u32 {
+        /// Generate signals for the INTR, QUIT and SUSP characters.
+        const ISIG = 0o1;
+        /// Canonical mode. Input is collected and edited line by line, and
+        /// a read returns at most one line.
+        const ICANON = 0o2;
+        /// Echo input characters.
+        const ECHO = 0o10;
+        /// Visually erase the last character on ERASE, and the whole line
+        /// on KILL, in canonical mode.
+        const ECHOE = 0o20;
+        /// Echo NL after KILL in canonical mode.
+        const ECHOK = 0o40;
+        /// Echo NL in canonical mode, even if ECHO is not set.
+        const ECHONL = 0o100;
+        /// Send [`Signal::TerminalOutput`](crate::Signal::TerminalOutput)
+        /// to background process groups that write to the terminal.
+        const TOSTOP = 0o400;
+    }
+}
+
+/// Index of the interrupt character in [`Termios::cc`], which sends
+/// [`Signal::Interrupt`](crate::Signal::Interrupt).
+pub const VINTR: usize = 0;
+/// Index of the quit character in [`Termios::cc`], which sends
+/// [`Signal::Quit`](crate::Signal::Quit).
+pub const VQUIT: usize = 1;
+/// Index of the erase character in [`Termios::cc`].
+pub const VERASE: usize = 2;
+/// Index of the kill character in [`Termios::cc`], which erases the line.
+pub const VKILL: usize = 3;
+/// Index of the end-of-file character in [`Termios::cc`].
+pub const VEOF: usize = 4;
+/// Index of the read timeout in [`Termios::cc`] for non-canonical mode,
+/// in tenths of a second.
+pub const VTIME: usize = 5;
+/// Index of the minimum number of bytes for a non-canonical read in
+/// [`Termios::cc`].
+pub const VMIN: usize = 6;
+/// Index of the suspend character in [`Termios::cc`], which sends
+/// [`Signal::TerminalStop`](crate::Signal::TerminalStop).
+pub const VSUSP: usize = 7;
+/// Number of control characters in [`Termios::cc`].
+pub const NCCS: usize = 8;
+
+/// Terminal attributes, read with `tcgetattr` and changed with `tcsetattr`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Termios {
+    pub iflag: InputFlags,
+    pub oflag: OutputFlags,
+    pub lflag: LocalFlags,
+    /// The control characters, indexed by `VINTR`, `VQUIT` and friends.
+    pub cc: [u8; NCCS],
+}
+
+impl Default for Termios {
+    /// The settings of a freshly opened terminal: canonical mode with echo
+    /// and signals, and CR-NL translation in both directions.
+    fn default() -> Self {
+        let mut cc = [0; NCCS];
+        cc[VINTR] = 0x03; // ^C
+        cc[VQUIT] = 0x1c; // ^\
+        cc[VERASE] = 0x7f; // DEL
+        cc[VKILL] = 0x15; // ^U
+        cc[VEOF] = 0x04; // ^D
+        cc[VTIME] = 0;
+        cc[VMIN] = 1;
+        cc[VSUSP] = 0x1a; // ^Z
+        Self {
+            iflag: InputFlags::ICRNL,
+            oflag: OutputFlags::OPOST | OutputFlags::ONLCR,
+            lflag: LocalFlags::ISIG
+                | LocalFlags::ICANON
+                | LocalFlags::ECHO
+                | LocalFlags::ECHOE
+                | LocalFlags::ECHOK,
+            cc,
+        }
+    }
+}
+
+impl Termios {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Turns off input and output processing, line editing, echo and
+    /// signal characters, like `cfmakeraw`. Reads return as soon as a
+    /// single byte is available.
+    pub fn make_raw(&mut self) {
+        self.iflag = InputFlags::empty();
+        self.oflag = OutputFlags::empty();
+        self.lflag = LocalFlags::empty();
+        self.cc[VMIN] = 1;
+        self.cc[VTIME] = 0;
+    }
+
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.iflag.bits().to_ne_bytes());
+        bytes[4..8].copy_from_slice(&self.oflag.bits().to_ne_bytes());
+        bytes[8..12].copy_from_slice(&self.lflag.bits().to_ne_bytes());
+        bytes[12..12 + NCCS].copy_from_slice(&self.cc);
+        bytes
+    }
+
+    /// Unknown flag bits are dropped.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let word =
+            |i: usize| u32::from_ne_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let mut cc = [0; NCCS];
+        cc.copy_from_slice(&bytes[12..12 + NCCS]);
+        Self {
+            iflag: InputFlags::from_bits_truncate(word(0)),
+            oflag: OutputFlags::from_bits_truncate(word(4)),
+            lflag: LocalFlags::from_bits_truncate(word(8)),
+            cc,
+        }
+    }
+}
+
+/// The size of a terminal window, read with `TcGetWinSize` and changed with
+/// `TcSetWinSize`. The kernel only stores it, the pixel sizes may be 0.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct WinSize {
+    pub rows: u16,
+    pub cols: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
+impl WinSize {
+    pub const SIZE: usize = size_of::<Self>();
+
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..2].copy_from_slice(&self.rows.to_ne_bytes());
+        bytes[2..4].copy_from_slice(&self.cols.to_ne_bytes());
+        bytes[4..6].copy_from_slice(&self.xpixel.to_ne_bytes());
+        bytes[6..8].copy_from_slice(&self.ypixel.to_ne_bytes());
+        bytes
+    }
+
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        Self {
+            rows: u16::from_ne_bytes([bytes[0], bytes[1]]),
+            cols: u16::from_ne_bytes([bytes[2], bytes[3]]),
+            xpixel: u16::from_ne_bytes([bytes[4], bytes[5]]),
+            ypixel: u16::from_ne_bytes([bytes[6], bytes[7]]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn termios_bytes_round_trip() {
+        let mut termios = Termios::default();
+        termios.lflag.remove(LocalFlags::ECHO);
+        termios.cc[VMIN] = 0;
+        assert_eq!(
+            Termios::from_bytes(&termios.to_bytes()),
+            termios,
+            "termios should survive a to_bytes/from_bytes round-trip"
+        );
+    }
+
+    #[test]
+    fn termios_make_raw() {
+        let mut termios = Termios::default();
+        termios.make_raw();
+        assert!(
+            !termios.lflag.contains(LocalFlags::ICANON),
+            "raw mode should not be canonical"
+        );
+        assert_eq!(termios.cc[VMIN], 1, "raw reads should wait for one byte");
+    }
+
+    #[test]
+    fn winsize_bytes_round_trip() {
+        let size = WinSize {
+            rows: 24,
+            cols: 80,
+            xpixel: 640,
+            ypixel: 384,
+        };
+        assert_eq!(
+            WinSize::from_bytes(&size.to_bytes()),
+            size,
+            "window size should survive a to_bytes/from_bytes round-trip"
+        );
+    }
+}
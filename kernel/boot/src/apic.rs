@@ -1,13 +1,18 @@
+use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
 
+use acpi::platform::interrupt::{InterruptSourceOverride, Polarity, TriggerMode};
 use acpi::{InterruptModel, PlatformInfo};
 use conquer_once::spin::OnceCell;
 use spin::Mutex;
+use x2apic::ioapic::{IrqFlags, IrqMode, RedirectionTableEntry};
 use x86_64::PhysAddr;
 use x86_64::instructions::port::Port;
 use x86_64::structures::paging::{Page, PageTableFlags, PhysFrame, Size4KiB};
 
 use crate::acpi::acpi_tables;
+use crate::arch::idt::InterruptIndex;
+use crate::mcore::context::ExecutionContext;
 use crate::mem::address_space::AddressSpace;
 use crate::mem::virt::{OwnedSegment, VirtualMemoryAllocator, VirtualMemoryHigherHalf};
 
@@ -17,6 +22,63 @@ pub fn io_apic() -> &'static Mutex<IoApic> {
     IO_APIC.get().expect("IOAPIC not initialized")
 }
 
+/// The interrupt source overrides of the MADT.
+static OVERRIDES: OnceCell<Vec<InterruptSourceOverride>> = OnceCell::uninit();
+
+/// Routes the ISA interrupt `irq` to `vector` on the calling CPU and unmasks
+/// it. The IO APIC must be initialized.
+pub fn route_isa_irq(irq: u8, vector: InterruptIndex) {
+    // ISA interrupts are edge triggered and active high
+    route_irq(irq, vector, IrqFlags::empty());
+}
+
+/// Routes the legacy interrupt of a PCI function to `vector` on the calling
+/// CPU and unmasks it. `line` is the interrupt line that the firmware wrote
+/// into the configuration space of the function.
+///
+/// Without the `_PRT` of the ACPI namespace, the line is taken as the IO
+/// APIC input. Firmware gives the lines that PCI uses an interrupt source
+/// override where they deviate from PCI's level triggered, active low
+/// interrupts, like QEMU does for its active high ones.
+pub fn route_pci_irq(line: u8, vector: InterruptIndex) {
+    let Some(isa) = OVERRIDES
+        .get()
+        .and_then(|overrides| overrides.iter().find(|o| o.isa_source == line))
+    else {
+        route_irq(
+            line,
+            vector,
+            IrqFlags::LEVEL_TRIGGERED | IrqFlags::LOW_ACTIVE,
+        );
+        return;
+    };
+
+    let mut flags = IrqFlags::empty();
+    if !matches!(isa.trigger_mode, TriggerMode::Edge) {
+        flags |= IrqFlags::LEVEL_TRIGGERED;
+    }
+    if !matches!(isa.polarity, Polarity::ActiveHigh) {
+        flags |= IrqFlags::LOW_ACTIVE;
+    }
+    let input = u8::try_from(isa.global_system_interrupt).expect("invalid IO APIC input");
+    route_irq(input, vector, flags);
+}
+
+fn route_irq(input: u8, vector: InterruptIndex, flags: IrqFlags) {
+    let lapic_id = ExecutionContext::load().lapic_id();
+    let mut io_apic = io_apic().lock();
+    unsafe {
+        // the entry is written masked and unmasked once it is complete
+        let mut entry = RedirectionTableEntry::default();
+        entry.set_mode(IrqMode::Fixed);
+        entry.set_flags(flags | IrqFlags::MASKED);
+        entry.set_vector(vector.as_u8());
+        entry.set_dest(u8::try_from(lapic_id).expect("invalid lapic id"));
+        io_apic.set_table_entry(input, entry);
+        io_apic.enable_irq(input);
+    }
+}
+
 pub struct IoApic {
     _segment: OwnedSegment<'static>,
     inner: x2apic::ioapic::IoApic,
@@ -49,6 +111,8 @@ pub fn init() {
         panic!("Unsupported interrupt model");
     };
 
+    OVERRIDES.init_once(|| apic.interrupt_source_overrides.iter().copied().collect());
+
     let apics = apic.io_apics;
     assert_eq!(
         apics.len(),
@@ -13,19 +13,29 @@ use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, Pag
 use x86_64::structures::paging::{Page, Size4KiB};
 use x86_64::{PrivilegeLevel, VirtAddr};
 
-use crate::U64Ext;
 use crate::arch::{gdt, signal};
+use crate::driver::{ps2, virtio};
 use crate::mcore::context::ExecutionContext;
 use crate::mcore::mtask::process::mem::{MemoryRegion, PageInError};
 use crate::mcore::mtask::task::Task;
 use crate::mcore::mtask::wait::wake_expired_sleepers;
 use crate::syscall::dispatch_syscall;
+use crate::{U64Ext, serial};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum InterruptIndex {
     /// 32
     Timer = 0x20,
+    /// 33, routed from IRQ 1 (PS/2 keyboard) by the IO APIC
+    Keyboard = 0x21,
+    /// 34, routed from the PCI interrupt lines of the virtio-input devices
+    /// by the IO APIC
+    VirtioInput = 0x22,
+    /// 35, routed from IRQ 12 (PS/2 mouse) by the IO APIC
+    Mouse = 0x23,
+    /// 36, routed from IRQ 4 (COM1) by the IO APIC
+    Serial = 0x24,
     /// 49
     LapicErr = 0x31,
     Syscall = 0x80,
@@ -80,6 +90,10 @@ pub fn create_idt() -> InterruptDescriptorTable {
             timer_interrupt_handler as *mut fn()
         ));
     }
+    idt[InterruptIndex::Keyboard.as_u8()].set_handler_fn(keyboard_interrupt_handler);
+    idt[InterruptIndex::VirtioInput.as_u8()].set_handler_fn(virtio_input_interrupt_handler);
+    idt[InterruptIndex::Mouse.as_u8()].set_handler_fn(mouse_interrupt_handler);
+    idt[InterruptIndex::Serial.as_u8()].set_handler_fn(serial_interrupt_handler);
     idt[InterruptIndex::LapicErr.as_u8()].set_handler_fn(lapic_err_interrupt_handler);
     idt[InterruptIndex::Spurious.as_u8()].set_handler_fn(spurious_interrupt_handler);
 
@@ -192,6 +206,38 @@ pub extern "sysv64" fn timer_interrupt_handler_impl(
     }
 }
 
+extern "x86-interrupt" fn keyboard_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    ps2::receive_pending();
+
+    unsafe {
+        end_of_interrupt();
+    }
+}
+
+extern "x86-interrupt" fn virtio_input_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    virtio::input::receive_pending();
+
+    unsafe {
+        end_of_interrupt();
+    }
+}
+
+extern "x86-interrupt" fn mouse_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    ps2::receive_pending();
+
+    unsafe {
+        end_of_interrupt();
+    }
+}
+
+extern "x86-interrupt" fn serial_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    serial::receive_pending();
+
+    unsafe {
+        end_of_interrupt();
+    }
+}
+
 extern "x86-interrupt" fn lapic_err_interrupt_handler(stack_frame: InterruptStackFrame) {
     panic!("EXCEPTION: LAPIC ERROR\n{:#?}", stack_frame);
 }
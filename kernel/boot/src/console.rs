@@ -0,0 +1,138 @@
+//! The text console on the framebuffer that Limine set up.
+//!
+//! During boot, the console mirrors the log, so that a machine without a
+//! serial line still shows how far it got. Afterwards it is the screen of
+//! the `tty0` terminal, and only a panic brings the log back onto it.
+
+use core::fmt::Write;
+use core::slice;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use conquer_once::spin::OnceCell;
+use kernel_abi::WinSize;
+use kernel_console::{Console, Framebuffer, PixelFormat};
+use limine::framebuffer::MemoryModel;
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::U64Ext;
+use crate::limine::FRAMEBUFFER_REQUEST;
+
+static CONSOLE: OnceCell<Mutex<Console<'static>>> = OnceCell::uninit();
+
+/// Whether log records are shown on the console.
+static LOG_MIRROR: AtomicBool = AtomicBool::new(true);
+
+/// Whether a panic took over the console, see [`mirror_panic`].
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// The size of the console, for the window size of its terminal.
+static SIZE: OnceCell<WinSize> = OnceCell::uninit();
+
+/// Sets up the console on the first framebuffer, if it has 32 bit pixels
+/// with 8 bit channels. Needs no heap, so that it can run before the log
+/// is initialized.
+pub fn init() {
+    let Some(fb) = FRAMEBUFFER_REQUEST
+        .get_response()
+        .and_then(|response| response.framebuffers().next())
+    else {
+        return;
+    };
+    let channels = [
+        fb.red_mask_size(),
+        fb.green_mask_size(),
+        fb.blue_mask_size(),
+    ];
+    if fb.memory_model() != MemoryModel::RGB || fb.bpp() != 32 || channels != [8; 3] {
+        return;
+    }
+    let format = PixelFormat {
+        red_shift: fb.red_mask_shift(),
+        green_shift: fb.green_mask_shift(),
+        blue_shift: fb.blue_mask_shift(),
+    };
+    let (width, height, pitch) = (
+        fb.width().into_usize(),
+        fb.height().into_usize(),
+        fb.pitch().into_usize(),
+    );
+    // Safety: Limine maps the framebuffer into the higher half, and the
+    // kernel keeps it mapped. Without a GPU, `/dev/fb0` hands the same pixels
+    // to user space, which can only garble what the console shows.
+    let buf = unsafe { slice::from_raw_parts_mut(fb.addr(), pitch * height) };
+    let Some(console) = Framebuffer::new(buf, width, height, pitch, format).and_then(Console::new)
+    else {
+        return;
+    };
+    SIZE.init_once(|| WinSize {
+        rows: u16::try_from(console.rows()).unwrap_or(u16::MAX),
+        cols: u16::try_from(console.columns()).unwrap_or(u16::MAX),
+        xpixel: u16::try_from(width).unwrap_or(u16::MAX),
+        ypixel: u16::try_from(height).unwrap_or(u16::MAX),
+    });
+    CONSOLE.init_once(|| Mutex::new(console));
+}
+
+/// The size of the console in characters and pixels, or `None` if there is
+/// no console.
+#[must_use]
+pub fn size() -> Option<WinSize> {
+    SIZE.get().copied()
+}
+
+/// Shows `bytes` on the console, escape sequences included. Does nothing
+/// without a console.
+pub fn write_bytes(bytes: &[u8]) {
+    if let Some(console) = CONSOLE.get() {
+        interrupts::without_interrupts(|| console.lock().write(bytes));
+    }
+}
+
+/// Starts or stops showing log records on the console.
+pub fn set_log_mirror(enabled: bool) {
+    LOG_MIRROR.store(enabled, Relaxed);
+}
+
+/// Shows the log on the console for the rest of a panic. The panic may have
+/// struck while the console was drawing, so from now on a log record skips
+/// the console while it is locked instead of waiting for it forever.
+pub fn mirror_panic() {
+    PANICKING.store(true, Relaxed);
+    LOG_MIRROR.store(true, Relaxed);
+}
+
+/// Runs `f` with a writer that shows one log record on the console, or
+/// with `None` while the console doesn't mirror the log.
+pub(crate) fn with_log<R>(f: impl FnOnce(Option<&mut dyn Write>) -> R) -> R {
+    let Some(console) = CONSOLE.get().filter(|_| LOG_MIRROR.load(Relaxed)) else {
+        return f(None);
+    };
+    interrupts::without_interrupts(|| {
+        let console = if PANICKING.load(Relaxed) {
+            console.try_lock()
+        } else {
+            Some(console.lock())
+        };
+        match console {
+            Some(mut console) => f(Some(&mut LogWriter(&mut console))),
+            None => f(None),
+        }
+    })
+}
+
+/// Starts a new line at a line feed, which the log doesn't do itself.
+struct LogWriter<'a, 'b>(&'a mut Console<'b>);
+
+impl Write for LogWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.0.write(b"\r\n");
+            }
+            self.0.write(line.as_bytes());
+        }
+        Ok(())
+    }
+}
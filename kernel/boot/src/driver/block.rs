@@ -1,49 +1,207 @@
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::error::Error;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use kernel_devfs::BlockDeviceFile;
-use kernel_device::RegisterDeviceError;
-use kernel_device::block::BlockDevice;
-use kernel_vfs::path::AbsoluteOwnedPath;
+use kernel_device::block::{BlockDevice, Partition, PartitionDevice, read_partitions};
+use kernel_device::{Device, RegisterDeviceError};
+use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
 use spin::RwLock;
+use tracing::{info, warn};
 
+use crate::driver::KernelDeviceId;
 use crate::file::devfs::devfs;
 
 pub type BlockDeviceHandle = Arc<RwLock<dyn BlockDevice<Error = Box<dyn Error>> + Send + Sync>>;
 
-static BLOCK_DEVICES: RwLock<BTreeMap<u64, BlockDeviceHandle>> = RwLock::new(BTreeMap::new());
+/// The whole block devices by the `N` in their name `blkN`, with their
+/// kernel device id.
+static BLOCK_DEVICES: RwLock<BTreeMap<u64, (KernelDeviceId, BlockDeviceHandle)>> =
+    RwLock::new(BTreeMap::new());
+/// The partitions of the devices in [`BLOCK_DEVICES`], by device id and
+/// partition number.
+static PARTITIONS: RwLock<BTreeMap<(u64, usize), (Partition, BlockDeviceHandle)>> =
+    RwLock::new(BTreeMap::new());
 static BLOCK_DEVICE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
+/// A block device as listed by [`BlockDevices::all`], which is either a whole
+/// device or a partition of one.
+pub struct BlockDeviceEntry {
+    /// The name of the device in devfs, like `blk0` or `blk0p1`.
+    pub name: String,
+    pub device: BlockDeviceHandle,
+    pub partition: Option<Partition>,
+}
+
+impl BlockDeviceEntry {
+    /// The devfs path of the device, like `/dev/blk0p1`.
+    #[must_use]
+    pub fn path(&self) -> AbsoluteOwnedPath {
+        AbsoluteOwnedPath::try_from(format!("/dev/{}", self.name).as_str()).unwrap()
+    }
+}
+
 pub struct BlockDevices;
 
 impl BlockDevices {
-    // TODO: add documentation
+    /// Registers `device` as `/dev/blkN`, and each partition in its partition
+    /// table as `/dev/blkNpM`.
+    ///
+    /// A partition table that can't be read is logged and otherwise ignored,
+    /// so that the whole device stays usable.
+    ///
+    /// Returns the `N` of the new device, which is its id in this registry.
     #[allow(clippy::missing_errors_doc)]
     #[allow(clippy::missing_panics_doc)]
-    pub fn register_block_device<D>(device: Arc<RwLock<D>>) -> Result<(), RegisterDeviceError>
+    pub fn register_block_device<D>(device: Arc<RwLock<D>>) -> Result<u64, RegisterDeviceError>
     where
-        D: BlockDevice<Error = Box<dyn Error>> + Send + Sync + 'static,
+        D: BlockDevice<Error = Box<dyn Error>> + Device<KernelDeviceId> + Send + Sync + 'static,
     {
         let id = BLOCK_DEVICE_COUNTER.fetch_add(1, Relaxed);
-        let _ = BLOCK_DEVICES.write().insert(id, device.clone());
+        let kernel_id = device.read().id();
+        let _ = BLOCK_DEVICES
+            .write()
+            .insert(id, (kernel_id, device.clone()));
+        register_file(&format!("blk{id}"), device.clone());
+
+        match read_partitions(&device) {
+            Ok(partitions) => {
+                for partition in partitions {
+                    Self::register_partition(id, device.clone(), partition);
+                }
+            }
+            Err(e) => warn!("can't read the partition table of blk{id}: {e}"),
+        }
 
-        let path = AbsoluteOwnedPath::try_from(format!("/blk{id}").as_ref()).unwrap();
-        devfs()
+        Ok(id)
+    }
+
+    /// Removes the block device with the given id and its partitions, so
+    /// that they can't be opened or mounted anymore. Users that already
+    /// hold the device keep it alive until they let go of it.
+    ///
+    /// Returns whether there was such a device.
+    pub fn unregister_block_device(id: u64) -> bool {
+        if BLOCK_DEVICES.write().remove(&id).is_none() {
+            return false;
+        }
+        unregister_file(&format!("blk{id}"));
+
+        let mut partitions = PARTITIONS.write();
+        let numbers = partitions
+            .range((id, 0)..=(id, usize::MAX))
+            .map(|((_, number), _)| *number)
+            .collect::<Vec<_>>();
+        for number in numbers {
+            partitions.remove(&(id, number));
+            unregister_file(&format!("blk{id}p{number}"));
+        }
+        true
+    }
+
+    fn register_partition(id: u64, device: BlockDeviceHandle, partition: Partition) {
+        let name = format!("blk{id}p{}", partition.number);
+        info!(
+            "{name}: {} sectors at sector {}",
+            partition.sector_count, partition.start
+        );
+        let partition_device = Arc::new(RwLock::new(PartitionDevice::new(device, &partition)));
+        register_file(&name, partition_device.clone());
+        PARTITIONS
             .write()
-            .register_file(path.as_ref(), {
-                move || Ok(BlockDeviceFile::new(device.clone()))
+            .insert((id, partition.number), (partition, partition_device));
+    }
+
+    /// Returns all block devices, each whole device followed by its
+    /// partitions.
+    pub fn all() -> Vec<BlockDeviceEntry> {
+        let partitions = PARTITIONS.read();
+        BLOCK_DEVICES
+            .read()
+            .iter()
+            .flat_map(|(id, (_, device))| {
+                let disk = BlockDeviceEntry {
+                    name: format!("blk{id}"),
+                    device: device.clone(),
+                    partition: None,
+                };
+                let parts = partitions.range((*id, 0)..=(*id, usize::MAX)).map(
+                    |((id, number), (partition, device))| BlockDeviceEntry {
+                        name: format!("blk{id}p{number}"),
+                        device: device.clone(),
+                        partition: Some(partition.clone()),
+                    },
+                );
+                core::iter::once(disk).chain(parts).collect::<Vec<_>>()
             })
-            .unwrap();
+            .collect()
+    }
+
+    /// Returns the whole block devices with the `N` of their name `blkN`
+    /// and their kernel device id.
+    pub fn disks() -> Vec<(u64, KernelDeviceId, BlockDeviceHandle)> {
+        BLOCK_DEVICES
+            .read()
+            .iter()
+            .map(|(id, (kernel_id, device))| (*id, *kernel_id, device.clone()))
+            .collect()
+    }
 
-        Ok(())
+    /// Returns the partitions of the block device with the given id.
+    pub fn partitions(id: u64) -> Vec<Partition> {
+        PARTITIONS
+            .read()
+            .range((id, 0)..=(id, usize::MAX))
+            .map(|(_, (partition, _))| partition.clone())
+            .collect()
     }
 
     pub fn by_id(id: u64) -> Option<BlockDeviceHandle> {
-        BLOCK_DEVICES.read().get(&id).cloned()
+        BLOCK_DEVICES
+            .read()
+            .get(&id)
+            .map(|(_, device)| device.clone())
+    }
+
+    /// Returns partition `number` of the block device with the given id.
+    pub fn partition(id: u64, number: usize) -> Option<BlockDeviceHandle> {
+        PARTITIONS
+            .read()
+            .get(&(id, number))
+            .map(|(_, device)| device.clone())
     }
+
+    /// Returns the block device with the given devfs path, such as
+    /// `/dev/blk0` or `/dev/blk0p1`.
+    pub fn by_path(path: &AbsolutePath) -> Option<BlockDeviceHandle> {
+        let name = path.strip_prefix("/dev/blk")?;
+        match name.split_once('p') {
+            Some((id, number)) => Self::partition(id.parse().ok()?, number.parse().ok()?),
+            None => Self::by_id(name.parse().ok()?),
+        }
+    }
+}
+
+fn register_file<D>(name: &str, device: Arc<RwLock<D>>)
+where
+    D: BlockDevice<Error = Box<dyn Error>> + Send + Sync + 'static,
+{
+    let path = AbsoluteOwnedPath::try_from(format!("/{name}").as_ref()).unwrap();
+    devfs()
+        .write()
+        .register_file(path.as_ref(), {
+            move || Ok(BlockDeviceFile::new(device.clone()))
+        })
+        .unwrap();
+}
+
+fn unregister_file(name: &str) {
+    let path = AbsoluteOwnedPath::try_from(format!("/{name}").as_ref()).unwrap();
+    let _ = devfs().write().unregister_file(path.as_ref());
 }
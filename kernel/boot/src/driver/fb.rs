@@ -0,0 +1,161 @@
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+use core::slice;
+
+use kernel_abi::{FbScreenInfo, IoctlRequest};
+use kernel_devfs::DevFile;
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{
+    FsyncError, IoctlError, MmapError, MmapRegion, ReadError, Stat, StatError, WriteError,
+};
+use spin::RwLock;
+
+use crate::driver::KernelDeviceId;
+use crate::file::devfs::devfs;
+
+static FRAMEBUFFERS: RwLock<Vec<Framebuffer>> = RwLock::new(Vec::new());
+
+/// A framebuffer as listed by [`Framebuffers::all`].
+#[derive(Debug, Copy, Clone)]
+pub struct Framebuffer {
+    /// The `N` in the device name `fbN`.
+    pub number: u32,
+    pub id: KernelDeviceId,
+    pub info: FbScreenInfo,
+}
+
+pub struct Framebuffers;
+
+impl Framebuffers {
+    /// Records a framebuffer with the mode `info` and returns its number,
+    /// which the driver names its device file after.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn register(id: KernelDeviceId, info: FbScreenInfo) -> u32 {
+        let mut framebuffers = FRAMEBUFFERS.write();
+        let number = u32::try_from(framebuffers.len()).unwrap();
+        framebuffers.push(Framebuffer { number, id, info });
+        number
+    }
+
+    pub fn all() -> Vec<Framebuffer> {
+        FRAMEBUFFERS.read().clone()
+    }
+}
+
+/// Shows what was written to a framebuffer, for a device that doesn't scan
+/// the bytes out by itself.
+pub type Flush = Arc<dyn Fn() -> Result<(), FsyncError> + Send + Sync>;
+
+/// Registers `/dev/fbN` for the framebuffer with the number `number`, whose
+/// `len` bytes start at `ptr`. `fsync` calls `flush`, if there is one.
+///
+/// # Safety
+/// `ptr` must point to `len` bytes that stay mapped for the kernel's
+/// lifetime.
+///
+/// # Panics
+/// Panics if the device file can't be registered.
+pub unsafe fn register_device_file(
+    number: u32,
+    ptr: NonNull<u8>,
+    len: usize,
+    info: FbScreenInfo,
+    flush: Option<Flush>,
+) {
+    let file = FbDevFile {
+        ptr,
+        len,
+        info,
+        flush,
+    };
+    let path = format!("/fb{number}");
+    devfs()
+        .write()
+        .register_file(AbsolutePath::try_new(&path).unwrap(), move || {
+            Ok(file.clone())
+        })
+        .expect("should be able to register the framebuffer device file");
+}
+
+/// Device file backing `/dev/fbN`. Holds a raw pointer to the HHDM-mapped
+/// framebuffer bytes so `mmap` can hand them out.
+#[derive(Clone)]
+struct FbDevFile {
+    ptr: NonNull<u8>,
+    len: usize,
+    info: FbScreenInfo,
+    flush: Option<Flush>,
+}
+
+// SAFETY: `ptr` aliases a framebuffer that lives for the kernel's lifetime;
+// access is gated behind `&mut self` on this `DevFile`, which `DevFs`
+// serializes with the lock of each open file.
+unsafe impl Send for FbDevFile {}
+unsafe impl Sync for FbDevFile {}
+
+impl FbDevFile {
+    fn as_slice_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DevFile for FbDevFile {
+    fn read(&mut self, buf: &mut [u8], offset: usize) -> Result<usize, ReadError> {
+        if offset >= self.len {
+            return Err(ReadError::EndOfFile);
+        }
+        let n = buf.len().min(self.len - offset);
+        let fb = self.as_slice_mut();
+        buf[..n].copy_from_slice(&fb[offset..offset + n]);
+        Ok(n)
+    }
+
+    fn write(&mut self, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
+        if offset >= self.len {
+            return Err(WriteError::WriteFailed);
+        }
+        let n = buf.len().min(self.len - offset);
+        let fb = self.as_slice_mut();
+        fb[offset..offset + n].copy_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.size = self.len;
+        Ok(())
+    }
+
+    fn mmap(&mut self) -> Result<MmapRegion, MmapError> {
+        Ok(MmapRegion::contiguous(self.ptr, self.len))
+    }
+
+    fn fsync(&mut self) -> Result<(), FsyncError> {
+        self.flush.as_ref().map_or(Ok(()), |flush| flush())
+    }
+
+    fn ioctl(&mut self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
+        match request {
+            IoctlRequest::FbGetScreenInfo => {
+                let Ok(arg) = <&mut [u8; FbScreenInfo::SIZE]>::try_from(arg) else {
+                    return Err(IoctlError::InvalidArgument);
+                };
+                *arg = self.info.to_bytes();
+                Ok(0)
+            }
+            IoctlRequest::LoopAttach
+            | IoctlRequest::LoopDetach
+            | IoctlRequest::TcGetAttr
+            | IoctlRequest::TcSetAttr
+            | IoctlRequest::TcGetPgrp
+            | IoctlRequest::TcSetPgrp
+            | IoctlRequest::TcGetWinSize
+            | IoctlRequest::TcSetWinSize
+            | IoctlRequest::PtyGetNumber
+            | IoctlRequest::InputGetName
+            | IoctlRequest::InputGetCapabilities
+            | IoctlRequest::InputGetAbsInfo => Err(IoctlError::NotSupported),
+        }
+    }
+}
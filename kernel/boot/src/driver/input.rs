@@ -0,0 +1,177 @@
+//! Input devices, which show up as `/dev/input/eventN`.
+//!
+//! A driver registers its device with a [`DeviceInfo`] and reports events
+//! through the returned [`InputDevice`]. Every open file of a device gets
+//! the events that arrive after it was opened, as [`InputEvent`]s.
+
+use alloc::format;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::Relaxed;
+
+use kernel_abi::{AbsInfo, EventType, InputEvent, IoctlRequest, PollEvents, SYN_REPORT};
+use kernel_devfs::DevFile;
+use kernel_input::{DeviceInfo, EventQueue};
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{IoctlError, ReadError, Stat, StatError, WriteError};
+use spin::Mutex;
+use tracing::info;
+
+use crate::file::INPUT_WAITERS;
+use crate::file::devfs::devfs;
+use crate::hpet::hpet;
+
+/// The `N` of the next `/dev/input/eventN`. Devices are never removed.
+static NEXT_NUMBER: AtomicU32 = AtomicU32::new(0);
+
+pub fn init() {
+    devfs()
+        .write()
+        .register_directory(AbsolutePath::try_new("/input").unwrap())
+        .expect("should be able to register /dev/input");
+}
+
+/// Makes a device available as the next free `/dev/input/eventN`.
+pub fn register(info: DeviceInfo) -> Arc<InputDevice> {
+    let number = NEXT_NUMBER.fetch_add(1, Relaxed);
+    let device = Arc::new(InputDevice {
+        number,
+        info: Mutex::new(info),
+        readers: Mutex::new(Vec::new()),
+    });
+
+    let path = format!("/input/event{number}");
+    devfs()
+        .write()
+        .register_file(AbsolutePath::try_new(&path).unwrap(), {
+            let device = device.clone();
+            move || Ok(EventFile::open(device.clone()))
+        })
+        .expect("should be able to register an input device");
+    info!(name = device.info.lock().name(), "registered /dev{path}");
+    device
+}
+
+pub struct InputDevice {
+    number: u32,
+    info: Mutex<DeviceInfo>,
+    /// The event queues of the open files.
+    readers: Mutex<Vec<Weak<Mutex<EventQueue>>>>,
+}
+
+impl InputDevice {
+    #[must_use]
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Queues an event for every reader. Readers are woken by
+    /// [`InputDevice::sync`], which ends the batch. Task context only.
+    pub fn report(&self, kind: EventType, code: u16, value: i32) {
+        let event = InputEvent::new(hpet().read().elapsed_ns(), kind, code, value);
+        self.info.lock().update(&event);
+        self.readers.lock().retain(|reader| {
+            let Some(queue) = reader.upgrade() else {
+                return false;
+            };
+            queue.lock().push(event);
+            true
+        });
+    }
+
+    /// Ends a batch of events that happened at the same time and wakes the
+    /// readers.
+    pub fn sync(&self) {
+        self.report(EventType::Sync, SYN_REPORT, 0);
+        INPUT_WAITERS.wake_all();
+    }
+}
+
+/// An open `/dev/input/eventN`.
+struct EventFile {
+    device: Arc<InputDevice>,
+    queue: Arc<Mutex<EventQueue>>,
+}
+
+impl EventFile {
+    fn open(device: Arc<InputDevice>) -> Self {
+        let queue = Arc::new(Mutex::new(EventQueue::new()));
+        device.readers.lock().push(Arc::downgrade(&queue));
+        Self { device, queue }
+    }
+}
+
+impl DevFile for EventFile {
+    /// Reads whole events only, and returns [`ReadError::WouldBlock`] while
+    /// there are none. A buffer too small for a single event is an error.
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        if buf.len() < InputEvent::SIZE {
+            return Err(ReadError::ReadFailed);
+        }
+        let mut queue = self.queue.lock();
+        if queue.is_empty() {
+            return Err(ReadError::WouldBlock);
+        }
+        Ok(queue.read(buf))
+    }
+
+    fn write(&mut self, _: &[u8], _: usize) -> Result<usize, WriteError> {
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.size = 0;
+        Ok(())
+    }
+
+    fn ioctl(&mut self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
+        let info = self.device.info.lock();
+        match request {
+            IoctlRequest::InputGetName => {
+                let (name, len) = info.name_bytes();
+                copy_arg(arg, &name)?;
+                Ok(len)
+            }
+            IoctlRequest::InputGetCapabilities => {
+                copy_arg(arg, &info.capabilities().to_bytes())?;
+                Ok(0)
+            }
+            IoctlRequest::InputGetAbsInfo => {
+                let bytes = <&[u8; AbsInfo::SIZE]>::try_from(&*arg)
+                    .map_err(|_| IoctlError::InvalidArgument)?;
+                let abs = info
+                    .abs_info(AbsInfo::from_bytes(bytes).axis)
+                    .ok_or(IoctlError::InvalidArgument)?;
+                copy_arg(arg, &abs.to_bytes())?;
+                Ok(0)
+            }
+            IoctlRequest::FbGetScreenInfo
+            | IoctlRequest::LoopAttach
+            | IoctlRequest::LoopDetach
+            | IoctlRequest::TcGetAttr
+            | IoctlRequest::TcSetAttr
+            | IoctlRequest::TcGetPgrp
+            | IoctlRequest::TcSetPgrp
+            | IoctlRequest::TcGetWinSize
+            | IoctlRequest::TcSetWinSize
+            | IoctlRequest::PtyGetNumber => Err(IoctlError::NotSupported),
+        }
+    }
+
+    fn poll(&mut self, events: PollEvents) -> PollEvents {
+        if self.queue.lock().is_empty() {
+            PollEvents::empty()
+        } else {
+            events & PollEvents::POLLIN
+        }
+    }
+}
+
+fn copy_arg(arg: &mut [u8], bytes: &[u8]) -> Result<(), IoctlError> {
+    if arg.len() != bytes.len() {
+        return Err(IoctlError::InvalidArgument);
+    }
+    arg.copy_from_slice(bytes);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use alloc::sync::Arc;
+use core::ptr::NonNull;
+
+use kernel_abi::FbScreenInfo;
+use kernel_device::Device;
+use kernel_device::raw::RawDevice;
+use spin::rwlock::RwLock;
+use tracing::{info, warn};
+use x86_64::VirtAddr;
+use x86_64::structures::paging::PhysFrame;
+use x86_64::structures::paging::frame::PhysFrameRangeInclusive;
+
+use crate::driver::KernelDeviceId;
+use crate::driver::fb::{self, Framebuffers};
+use crate::driver::raw::RawDevices;
+use crate::limine::FRAMEBUFFER_REQUEST;
+use crate::mem::address_space::AddressSpace;
+use crate::{U64Ext, UsizeExt};
+
+/// Registers the framebuffer that the firmware set up and Limine handed
+/// over as `/dev/fbN`, unless a GPU driver already registered one. The mode
+/// is whatever the firmware picked, and the pixels show without a flush.
+///
+/// Must run after the PCI drivers are initialized.
+///
+/// # Panics
+/// Panics if the framebuffer is not mapped into the kernel address space.
+pub fn init() {
+    if !Framebuffers::all().is_empty() {
+        return;
+    }
+    let Some(fb) = FRAMEBUFFER_REQUEST
+        .get_response()
+        .and_then(|response| response.framebuffers().next())
+    else {
+        return;
+    };
+    let (Ok(width), Ok(height), Ok(pitch)) = (
+        u32::try_from(fb.width()),
+        u32::try_from(fb.height()),
+        u32::try_from(fb.pitch()),
+    ) else {
+        warn!("firmware framebuffer is too large");
+        return;
+    };
+    let info = FbScreenInfo {
+        width,
+        height,
+        pitch,
+        bpp: u32::from(fb.bpp()),
+    };
+    let len = fb.pitch().into_usize() * fb.height().into_usize();
+    let ptr = NonNull::new(fb.addr()).expect("framebuffer pointer should be non-null");
+
+    let virtual_addr = VirtAddr::from_ptr(ptr.as_ptr());
+    let start = AddressSpace::kernel()
+        .translate(virtual_addr)
+        .expect("framebuffer should be mapped into kernel space");
+    let end = AddressSpace::kernel()
+        .translate(virtual_addr + len.into_u64() - 1)
+        .expect("framebuffer should be mapped into kernel space");
+    let physical_memory = PhysFrameRangeInclusive {
+        start: PhysFrame::containing_address(start),
+        end: PhysFrame::containing_address(end),
+    };
+
+    let id = KernelDeviceId::new();
+    let number = Framebuffers::register(id, info);
+    info!(
+        "using the firmware framebuffer at {}x{} as fb{number}",
+        info.width, info.height
+    );
+
+    // Safety: the kernel keeps the framebuffer mapped. The bytes are scanned
+    // out as they are, so unlike with a GPU there is nothing to flush.
+    unsafe { fb::register_device_file(number, ptr, len, info, None) };
+
+    let device = LimineFbRawDevice {
+        id,
+        physical_memory,
+    };
+    RawDevices::register_raw_device(Arc::new(RwLock::new(device)))
+        .expect("should be able to register the framebuffer raw device");
+}
+
+#[derive(Debug, Clone)]
+pub struct LimineFbRawDevice {
+    id: KernelDeviceId,
+    physical_memory: PhysFrameRangeInclusive,
+}
+
+impl Device<KernelDeviceId> for LimineFbRawDevice {
+    fn id(&self) -> KernelDeviceId {
+        self.id
+    }
+}
+
+impl RawDevice<KernelDeviceId> for LimineFbRawDevice {
+    fn physical_memory(&self) -> PhysFrameRangeInclusive {
+        self.physical_memory
+    }
+}
@@ -0,0 +1,200 @@
+//! Loop block devices, which make a regular file usable as a block device,
+//! so that a file system image that is stored in a file can be mounted.
+//!
+//! Devices are attached and detached with ioctls on `/dev/loop-control`
+//! and show up as the next free `/dev/blkN`, with their partitions.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use core::error::Error;
+use core::ffi::c_int;
+use core::fmt::{Debug, Formatter};
+
+use kernel_abi::{IoctlRequest, S_IFMT, S_IFREG};
+use kernel_devfs::DevFile;
+use kernel_device::Device;
+use kernel_device::block::BlockDevice;
+use kernel_vfs::node::VfsNode;
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{IoctlError, ReadError, Stat, StatError, WriteError};
+use spin::{Mutex, RwLock};
+use thiserror::Error;
+use tracing::info;
+
+use crate::driver::KernelDeviceId;
+use crate::driver::block::BlockDevices;
+use crate::file::devfs::devfs;
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::process::fd::FdNum;
+
+const SECTOR_SIZE: usize = 512;
+
+/// The ids of the block devices that are loop devices, which are the only
+/// ones that can be detached.
+static LOOP_DEVICES: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
+
+pub fn init() {
+    devfs()
+        .write()
+        .register_file(AbsolutePath::try_new("/loop-control").unwrap(), || {
+            Ok(LoopControl)
+        })
+        .expect("should be able to register /dev/loop-control");
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+enum LoopDeviceError {
+    #[error("the backing file ended in the middle of a sector")]
+    ShortRead,
+    #[error("the backing file didn't take the whole sector")]
+    ShortWrite,
+}
+
+/// A block device whose sectors are stored in a file. A trailing part of
+/// the file that doesn't fill a whole sector isn't part of the device.
+#[derive(Clone)]
+pub struct LoopDevice {
+    id: KernelDeviceId,
+    node: VfsNode,
+    sector_count: usize,
+}
+
+impl Debug for LoopDevice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LoopDevice")
+            .field("id", &self.id)
+            .field("path", &self.node.path())
+            .field("sector_count", &self.sector_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Device<KernelDeviceId> for LoopDevice {
+    fn id(&self) -> KernelDeviceId {
+        self.id
+    }
+}
+
+impl BlockDevice for LoopDevice {
+    type Error = Box<dyn Error>;
+
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn sector_count(&self) -> usize {
+        self.sector_count
+    }
+
+    fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let offset = sector_index * SECTOR_SIZE;
+        let mut done = 0;
+        while done < buf.len() {
+            match self.node.read(&mut buf[done..], offset + done) {
+                Ok(0) | Err(ReadError::EndOfFile) => return Err(LoopDeviceError::ShortRead.into()),
+                Ok(n) => done += n,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(done)
+    }
+
+    fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        let offset = sector_index * SECTOR_SIZE;
+        let mut done = 0;
+        while done < buf.len() {
+            match self.node.write(&buf[done..], offset + done)? {
+                0 => return Err(LoopDeviceError::ShortWrite.into()),
+                n => done += n,
+            }
+        }
+        Ok(done)
+    }
+}
+
+/// The file behind `/dev/loop-control`, which only supports the loop
+/// ioctls.
+struct LoopControl;
+
+impl DevFile for LoopControl {
+    fn read(&mut self, _buf: &mut [u8], _offset: usize) -> Result<usize, ReadError> {
+        Err(ReadError::NotReadable)
+    }
+
+    fn write(&mut self, _buf: &[u8], _offset: usize) -> Result<usize, WriteError> {
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, _stat: &mut Stat) -> Result<(), StatError> {
+        Ok(())
+    }
+
+    fn ioctl(&mut self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
+        let arg = <[u8; 4]>::try_from(&*arg).map_err(|_| IoctlError::InvalidArgument)?;
+        match request {
+            IoctlRequest::LoopAttach => {
+                let id = attach(c_int::from_ne_bytes(arg))?;
+                usize::try_from(id).map_err(|_| IoctlError::InvalidArgument)
+            }
+            IoctlRequest::LoopDetach => {
+                detach(u64::from(u32::from_ne_bytes(arg)))?;
+                Ok(0)
+            }
+            IoctlRequest::FbGetScreenInfo
+            | IoctlRequest::TcGetAttr
+            | IoctlRequest::TcSetAttr
+            | IoctlRequest::TcGetPgrp
+            | IoctlRequest::TcSetPgrp
+            | IoctlRequest::TcGetWinSize
+            | IoctlRequest::TcSetWinSize
+            | IoctlRequest::PtyGetNumber
+            | IoctlRequest::InputGetName
+            | IoctlRequest::InputGetCapabilities
+            | IoctlRequest::InputGetAbsInfo => Err(IoctlError::NotSupported),
+        }
+    }
+}
+
+/// Attaches a loop device to the regular file that the calling process has
+/// open under `fd` and returns the id of the new block device.
+fn attach(fd: c_int) -> Result<u64, IoctlError> {
+    let node = ExecutionContext::load()
+        .current_process()
+        .file_descriptors()
+        .read()
+        .get(&FdNum::from(fd))
+        .map(|fd| VfsNode::clone(fd.file_description()))
+        .ok_or(IoctlError::InvalidArgument)?;
+
+    // device files can't back a loop device, which also rules out a loop
+    // device on top of itself
+    let mut stat = Stat::default();
+    node.stat(&mut stat)
+        .map_err(|_| IoctlError::InvalidArgument)?;
+    if stat.mode & S_IFMT != S_IFREG {
+        return Err(IoctlError::InvalidArgument);
+    }
+
+    let path = node.path().to_owned();
+    let device = LoopDevice {
+        id: KernelDeviceId::new(),
+        node,
+        sector_count: stat.size / SECTOR_SIZE,
+    };
+    let id = BlockDevices::register_block_device(Arc::new(RwLock::new(device)))
+        .map_err(|_| IoctlError::InvalidArgument)?;
+    LOOP_DEVICES.lock().insert(id);
+    info!("attached blk{id} to {}", path.as_str());
+    Ok(id)
+}
+
+fn detach(id: u64) -> Result<(), IoctlError> {
+    if !LOOP_DEVICES.lock().remove(&id) {
+        return Err(IoctlError::InvalidArgument);
+    }
+    BlockDevices::unregister_block_device(id);
+    info!("detached blk{id}");
+    Ok(())
+}
@@ -5,7 +5,12 @@ use core::sync::atomic::Ordering::Relaxed;
 use kernel_device::DeviceId;
 
 pub mod block;
+pub mod fb;
+pub mod input;
+pub mod limine_fb;
+pub mod loopback;
 pub mod pci;
+pub mod ps2;
 pub mod raw;
 pub mod virtio;
 
@@ -29,6 +34,11 @@ impl KernelDeviceId {
         static COUNTER: AtomicU64 = AtomicU64::new(0);
         Self(COUNTER.fetch_add(1, Relaxed))
     }
+
+    #[must_use]
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
 }
 
 impl DeviceId for KernelDeviceId {}
@@ -1,11 +1,13 @@
 use alloc::boxed::Box;
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::error::Error;
 
-use kernel_pci::PciAddress;
 use kernel_pci::config::{ConfigKey, ConfigurationAccess, PortCam, ReadConfig, WriteConfig};
+use kernel_pci::{Bar, PciAddress};
 use linkme::distributed_slice;
+use spin::RwLock;
 use tracing::{Level, debug, error, instrument, trace};
 use virtio_drivers::transport::pci::bus::DeviceFunction;
 
@@ -26,6 +28,68 @@ pub struct PciDriverDescriptor {
     pub init: fn(PciAddress, Box<dyn ConfigurationAccess>) -> Result<(), Box<dyn Error>>,
 }
 
+/// Every PCI function that [`init`] found, in the order of their addresses.
+static PCI_DEVICES: RwLock<Vec<PciDevice>> = RwLock::new(Vec::new());
+
+/// A PCI function as it was found during enumeration, and what happened
+/// when a driver was initialized for it.
+#[derive(Debug, Clone)]
+pub struct PciDevice {
+    pub address: PciAddress,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub revision_id: u8,
+    pub subsystem_vendor_id: u16,
+    pub subsystem_id: u16,
+    pub bars: Vec<Bar>,
+    /// The name of the driver that matched the function, if any.
+    pub driver: Option<&'static str>,
+    pub status: PciDeviceStatus,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PciDeviceStatus {
+    /// No driver matched the function.
+    Unbound,
+    Bound,
+    /// The driver failed to initialize the function, with its error.
+    Failed(String),
+}
+
+impl PciDevice {
+    /// Reads the configuration of the function at `address`, before a
+    /// driver touches it.
+    fn read<C: ConfigurationAccess>(address: PciAddress, cam: &C) -> Self {
+        Self {
+            address,
+            vendor_id: address.vendor_id(cam),
+            device_id: address.device_id(cam),
+            class: address.class(cam),
+            subclass: address.subclass(cam),
+            prog_if: address.prog_if(cam),
+            revision_id: address.revision_id(cam),
+            subsystem_vendor_id: address.subsystem_vendor_id(cam),
+            subsystem_id: address.subsystem_id(cam),
+            bars: address.bars(cam),
+            driver: None,
+            status: PciDeviceStatus::Unbound,
+        }
+    }
+}
+
+pub struct PciDevices;
+
+impl PciDevices {
+    /// Returns every PCI function that was found, including the ones that
+    /// no driver took or whose driver failed.
+    pub fn all() -> Vec<PciDevice> {
+        PCI_DEVICES.read().clone()
+    }
+}
+
 /// # Panics
 ///
 /// Panics if there are multiple specific or multiple generic drivers that would match
@@ -41,6 +105,11 @@ pub fn init() {
     let cam = unsafe { PortCam::new() };
 
     unsafe { iterate_all(&cam) }.for_each(|addr| {
+        let mut device = PciDevice::read(addr, &cam);
+        // Legacy interrupts are level triggered and shared between functions,
+        // so one that no driver acknowledges would keep its line asserted
+        // forever. A driver that handles them allows them again.
+        addr.set_legacy_interrupts(&cam, false);
         let driver = PCI_DRIVERS
             .iter()
             .fold(None, |res: Option<&PciDriverDescriptor>, driver| {
@@ -70,13 +139,19 @@ pub fn init() {
         if let Some(driver) = driver {
             debug!("found driver {} for device {}", driver.name, addr);
             let device_string = addr.to_string();
-            if let Err(e) = (driver.init)(addr, Box::new(cam.clone())) {
-                error!(
-                    "failed to init driver {} for device {}: {}",
-                    driver.name, device_string, e
-                );
-            }
+            device.driver = Some(driver.name);
+            device.status = match (driver.init)(addr, Box::new(cam.clone())) {
+                Ok(()) => PciDeviceStatus::Bound,
+                Err(e) => {
+                    error!(
+                        "failed to init driver {} for device {}: {}",
+                        driver.name, device_string, e
+                    );
+                    PciDeviceStatus::Failed(e.to_string())
+                }
+            };
         }
+        PCI_DEVICES.write().push(device);
     });
 }
 
@@ -0,0 +1,505 @@
+//! The i8042 PS/2 controller, the keyboard on its first port and the mouse
+//! on its second one.
+//!
+//! The interrupt handlers only queue the bytes that the devices send, each
+//! into its own ring. One kernel task decodes the keyboard bytes into key
+//! events, which it reports through an input device, and types the keys
+//! into the console terminal. Another one decodes the mouse packets into
+//! motion and button events of a second input device.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::ffi::c_void;
+use core::ptr;
+
+use bitflags::bitflags;
+use conquer_once::spin::OnceCell;
+use kernel_abi::{EventType, KeyCode};
+use kernel_input::DeviceInfo;
+use kernel_keyboard::{Keymap, ScancodeDecoder, ScancodeSet};
+use kernel_mouse::{Mouse, Protocol};
+use spin::Mutex;
+use thiserror::Error;
+use tracing::{Level, info, instrument, warn};
+use x86_64::instructions::interrupts;
+use x86_64::instructions::port::Port;
+
+use crate::apic::route_isa_irq;
+use crate::arch::idt::InterruptIndex;
+use crate::driver::input::{self, InputDevice};
+use crate::file::INPUT_WAITERS;
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
+use crate::mcore::mtask::task::Task;
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::ring::RxRing;
+use crate::tty;
+
+/// ISA IRQ of the first PS/2 port.
+const KEYBOARD_IRQ: u8 = 1;
+/// ISA IRQ of the second PS/2 port.
+const MOUSE_IRQ: u8 = 12;
+
+/// How often to poll the status register before giving up on the
+/// controller. Each poll is a port access of about a microsecond.
+const POLL_BUDGET: usize = 100_000;
+
+static CONTROLLER: Mutex<Controller> = Mutex::new(Controller::new());
+
+/// The keyboard task waits here for bytes to arrive.
+static KEYBOARD_WAITERS: WaitQueue = WaitQueue::new();
+
+/// Bytes that the keyboard sent but that weren't decoded yet.
+static KEYBOARD_RECEIVED: Mutex<RxRing> = Mutex::new(RxRing::new());
+
+/// The mouse task waits here for bytes to arrive.
+static MOUSE_WAITERS: WaitQueue = WaitQueue::new();
+
+/// Bytes that the mouse sent but that weren't decoded yet.
+static MOUSE_RECEIVED: Mutex<RxRing> = Mutex::new(RxRing::new());
+
+static KEYBOARD: OnceCell<Keyboard> = OnceCell::uninit();
+
+static MOUSE: OnceCell<PointingDevice> = OnceCell::uninit();
+
+struct Keyboard {
+    /// The scancode set that arrives at the data port.
+    set: ScancodeSet,
+    device: Arc<InputDevice>,
+}
+
+struct PointingDevice {
+    /// The packet format that the mouse was switched to.
+    protocol: Protocol,
+    device: Arc<InputDevice>,
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Status: u8 {
+        /// A byte can be read from the data port.
+        const OUTPUT_FULL = 1 << 0;
+        /// The controller hasn't consumed the last byte written yet.
+        const INPUT_FULL = 1 << 1;
+        /// The byte in the data port came from the second port.
+        const AUX_DATA = 1 << 5;
+    }
+}
+
+bitflags! {
+    /// The controller configuration byte.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Config: u8 {
+        const KEYBOARD_IRQ = 1 << 0;
+        const AUX_IRQ = 1 << 1;
+        const KEYBOARD_CLOCK_DISABLED = 1 << 4;
+        const AUX_CLOCK_DISABLED = 1 << 5;
+        /// The controller translates scancode set 2 into set 1.
+        const TRANSLATION = 1 << 6;
+    }
+}
+
+mod command {
+    pub const READ_CONFIG: u8 = 0x20;
+    pub const WRITE_CONFIG: u8 = 0x60;
+    pub const DISABLE_AUX: u8 = 0xa7;
+    pub const ENABLE_AUX: u8 = 0xa8;
+    pub const TEST_AUX: u8 = 0xa9;
+    pub const SELF_TEST: u8 = 0xaa;
+    pub const TEST_KEYBOARD: u8 = 0xab;
+    pub const DISABLE_KEYBOARD: u8 = 0xad;
+    pub const ENABLE_KEYBOARD: u8 = 0xae;
+    /// Sends the next data byte to the second port.
+    pub const WRITE_AUX: u8 = 0xd4;
+
+    pub const SELF_TEST_PASSED: u8 = 0x55;
+    pub const PORT_TEST_PASSED: u8 = 0x00;
+
+    /// Sent to the keyboard, not the controller.
+    pub const ENABLE_SCANNING: u8 = 0xf4;
+    pub const ACK: u8 = 0xfa;
+
+    /// Sent to the mouse, not the controller.
+    pub const GET_ID: u8 = 0xf2;
+    pub const SET_SAMPLE_RATE: u8 = 0xf3;
+    pub const ENABLE_REPORTING: u8 = 0xf4;
+    pub const SET_DEFAULTS: u8 = 0xf6;
+    /// Samples per second after [`SET_DEFAULTS`].
+    pub const DEFAULT_SAMPLE_RATE: u8 = 100;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+enum ControllerError {
+    #[error("there is no controller")]
+    NotPresent,
+    #[error("the controller didn't respond")]
+    Timeout,
+    #[error("the controller failed its self test with {0:#x}")]
+    SelfTest(u8),
+    #[error("the controller has no second port")]
+    NoAuxPort,
+    #[error("the {0} port failed its test with {1:#x}")]
+    PortTest(&'static str, u8),
+    #[error("the {0} answered {1:#x} instead of an acknowledgement")]
+    NoAck(&'static str, u8),
+}
+
+struct Controller {
+    data: Port<u8>,
+    /// Status when read, command when written.
+    command: Port<u8>,
+}
+
+impl Controller {
+    const fn new() -> Self {
+        Self {
+            data: Port::new(0x60),
+            command: Port::new(0x64),
+        }
+    }
+
+    fn status(&mut self) -> Status {
+        Status::from_bits_retain(unsafe { self.command.read() })
+    }
+
+    fn wait_for(&mut self, ready: impl Fn(Status) -> bool) -> Result<(), ControllerError> {
+        (0..POLL_BUDGET)
+            .any(|_| ready(self.status()))
+            .then_some(())
+            .ok_or(ControllerError::Timeout)
+    }
+
+    fn write_command(&mut self, command: u8) -> Result<(), ControllerError> {
+        self.wait_for(|status| !status.contains(Status::INPUT_FULL))?;
+        unsafe { self.command.write(command) };
+        Ok(())
+    }
+
+    fn write_data(&mut self, byte: u8) -> Result<(), ControllerError> {
+        self.wait_for(|status| !status.contains(Status::INPUT_FULL))?;
+        unsafe { self.data.write(byte) };
+        Ok(())
+    }
+
+    fn read_data(&mut self) -> Result<u8, ControllerError> {
+        self.wait_for(|status| status.contains(Status::OUTPUT_FULL))?;
+        Ok(unsafe { self.data.read() })
+    }
+
+    fn read_config(&mut self) -> Result<Config, ControllerError> {
+        self.write_command(command::READ_CONFIG)?;
+        self.read_data().map(Config::from_bits_retain)
+    }
+
+    fn write_config(&mut self, config: Config) -> Result<(), ControllerError> {
+        self.write_command(command::WRITE_CONFIG)?;
+        self.write_data(config.bits())
+    }
+
+    /// Drops whatever is waiting in the data port.
+    fn flush(&mut self) {
+        while self.status().contains(Status::OUTPUT_FULL) {
+            unsafe { self.data.read() };
+        }
+    }
+
+    /// Writes a byte to the mouse and waits for it to acknowledge.
+    fn write_aux(&mut self, byte: u8) -> Result<(), ControllerError> {
+        self.write_command(command::WRITE_AUX)?;
+        self.write_data(byte)?;
+        match self.read_data()? {
+            command::ACK => Ok(()),
+            answer => Err(ControllerError::NoAck("mouse", answer)),
+        }
+    }
+
+    /// Tests the controller and disables both ports and their interrupts.
+    /// Returns the configuration to build on.
+    fn init(&mut self) -> Result<Config, ControllerError> {
+        // without a controller, the status port floats
+        if self.status().bits() == 0xff {
+            return Err(ControllerError::NotPresent);
+        }
+
+        self.write_command(command::DISABLE_KEYBOARD)?;
+        self.write_command(command::DISABLE_AUX)?;
+        self.flush();
+
+        let mut config = self.read_config()?;
+        config.remove(Config::KEYBOARD_IRQ | Config::AUX_IRQ);
+        self.write_config(config)?;
+
+        self.write_command(command::SELF_TEST)?;
+        match self.read_data()? {
+            command::SELF_TEST_PASSED => {}
+            result => return Err(ControllerError::SelfTest(result)),
+        }
+        // some controllers reset their configuration in the self test
+        self.write_config(config)?;
+        Ok(config)
+    }
+
+    /// Enables the keyboard and its interrupt in `config`. Returns the
+    /// scancode set that the keyboard bytes arrive in.
+    fn init_keyboard(&mut self, config: &mut Config) -> Result<ScancodeSet, ControllerError> {
+        self.write_command(command::TEST_KEYBOARD)?;
+        match self.read_data()? {
+            command::PORT_TEST_PASSED => {}
+            result => return Err(ControllerError::PortTest("keyboard", result)),
+        }
+
+        self.write_command(command::ENABLE_KEYBOARD)?;
+        self.write_data(command::ENABLE_SCANNING)?;
+        match self.read_data()? {
+            command::ACK => {}
+            answer => return Err(ControllerError::NoAck("keyboard", answer)),
+        }
+
+        config.remove(Config::KEYBOARD_CLOCK_DISABLED);
+        config.insert(Config::KEYBOARD_IRQ);
+        self.write_config(*config)?;
+
+        Ok(if config.contains(Config::TRANSLATION) {
+            ScancodeSet::One
+        } else {
+            ScancodeSet::Two
+        })
+    }
+
+    /// Enables the mouse and its interrupt in `config`, with the scroll
+    /// wheel and extra buttons if it has them. Must run while the keyboard
+    /// port is disabled, so that every byte that arrives is the mouse's.
+    /// Returns the packet format that the mouse sends.
+    fn init_mouse(&mut self, config: &mut Config) -> Result<Protocol, ControllerError> {
+        // a controller with a single port ignores the command, and the
+        // clock of the second port stays disabled
+        self.write_command(command::ENABLE_AUX)?;
+        if self.read_config()?.contains(Config::AUX_CLOCK_DISABLED) {
+            return Err(ControllerError::NoAuxPort);
+        }
+        self.write_command(command::DISABLE_AUX)?;
+
+        self.write_command(command::TEST_AUX)?;
+        match self.read_data()? {
+            command::PORT_TEST_PASSED => {}
+            result => return Err(ControllerError::PortTest("mouse", result)),
+        }
+
+        // if this fails, writing `config` disables the port again
+        self.write_command(command::ENABLE_AUX)?;
+        let protocol = self.identify_mouse()?;
+        self.write_aux(command::ENABLE_REPORTING)?;
+
+        config.remove(Config::AUX_CLOCK_DISABLED);
+        config.insert(Config::AUX_IRQ);
+        self.write_config(*config)?;
+        Ok(protocol)
+    }
+
+    /// Resets the mouse to its defaults and tries to unlock the scroll
+    /// wheel and then the extra buttons. A mouse that doesn't know a
+    /// sequence of sample rates keeps its packet format.
+    fn identify_mouse(&mut self) -> Result<Protocol, ControllerError> {
+        self.write_aux(command::SET_DEFAULTS)?;
+        let mut protocol = Protocol::Standard;
+        for (rates, next) in [
+            (Protocol::WHEEL_SAMPLE_RATES, Protocol::Wheel),
+            (Protocol::FIVE_BUTTON_SAMPLE_RATES, Protocol::FiveButton),
+        ] {
+            for rate in rates {
+                self.set_sample_rate(rate)?;
+            }
+            self.write_aux(command::GET_ID)?;
+            if Protocol::from_id(self.read_data()?) != Some(next) {
+                break;
+            }
+            protocol = next;
+        }
+        self.set_sample_rate(command::DEFAULT_SAMPLE_RATE)?;
+        Ok(protocol)
+    }
+
+    fn set_sample_rate(&mut self, rate: u8) -> Result<(), ControllerError> {
+        self.write_aux(command::SET_SAMPLE_RATE)?;
+        self.write_aux(rate)
+    }
+}
+
+/// Brings up the keyboard and the mouse, if there is a PS/2 controller,
+/// and registers them as input devices. Machines without one, like most
+/// that boot with UEFI only, just have neither.
+#[instrument(name = "init ps2", level = Level::TRACE)]
+pub fn init() {
+    let (keyboard, mouse) = match interrupts::without_interrupts(|| {
+        let mut controller = CONTROLLER.lock();
+        let mut config = controller.init()?;
+        // the mouse first, while the keyboard can't send anything
+        let mouse = controller.init_mouse(&mut config);
+        let keyboard = controller.init_keyboard(&mut config);
+        Ok::<_, ControllerError>((keyboard, mouse))
+    }) {
+        Ok(devices) => devices,
+        Err(e) => {
+            warn!("no PS/2 controller: {e}");
+            return;
+        }
+    };
+    match keyboard {
+        Ok(set) => init_keyboard(set),
+        Err(e) => warn!("no PS/2 keyboard: {e}"),
+    }
+    match mouse {
+        Ok(protocol) => init_mouse(protocol),
+        Err(e) => warn!("no PS/2 mouse: {e}"),
+    }
+    // a byte that arrived before the IRQs were unmasked holds the line
+    // until it is read, like on the serial port
+    receive_pending();
+}
+
+fn init_keyboard(set: ScancodeSet) {
+    info!(?set, "PS/2 keyboard enabled");
+    let info = (1..KeyCode::BtnLeft.number())
+        .filter(|&code| KeyCode::from_number(code).is_some())
+        .fold(
+            DeviceInfo::new(String::from("PS/2 keyboard")),
+            |info, code| info.with_event(EventType::Key, code),
+        );
+    let device = input::register(info);
+    KEYBOARD.init_once(|| Keyboard { set, device });
+
+    let task = Task::create_new(Process::root(), keyboard_input, ptr::null_mut())
+        .expect("should be able to create the keyboard input task");
+    info!(id = %task.id(), "keyboard input task created");
+    GlobalTaskQueue::enqueue(Box::pin(task));
+
+    route_isa_irq(KEYBOARD_IRQ, InterruptIndex::Keyboard);
+}
+
+fn init_mouse(protocol: Protocol) {
+    info!(?protocol, "PS/2 mouse enabled");
+    let name = match protocol {
+        Protocol::Standard => "PS/2 mouse",
+        Protocol::Wheel => "PS/2 wheel mouse",
+        Protocol::FiveButton => "PS/2 five button mouse",
+    };
+    let mut info = DeviceInfo::new(String::from(name));
+    for &axis in protocol.axes() {
+        info = info.with_event(EventType::Relative, axis as u16);
+    }
+    for button in protocol.buttons() {
+        info = info.with_event(EventType::Key, button.number());
+    }
+    let device = input::register(info);
+    MOUSE.init_once(|| PointingDevice { protocol, device });
+
+    let task = Task::create_new(Process::root(), mouse_input, ptr::null_mut())
+        .expect("should be able to create the mouse input task");
+    info!(id = %task.id(), "mouse input task created");
+    GlobalTaskQueue::enqueue(Box::pin(task));
+
+    route_isa_irq(MOUSE_IRQ, InterruptIndex::Mouse);
+}
+
+/// Moves everything that the controller holds into the ring of the device
+/// that sent it and wakes the task of that device.
+/// Called from the keyboard and mouse interrupt handlers and once when the
+/// IRQs get routed.
+pub(crate) fn receive_pending() {
+    let mut keyboard = false;
+    let mut mouse = false;
+    interrupts::without_interrupts(|| {
+        let mut controller = CONTROLLER.lock();
+        loop {
+            let status = controller.status();
+            if !status.contains(Status::OUTPUT_FULL) {
+                break;
+            }
+            let byte = unsafe { controller.data.read() };
+            if status.contains(Status::AUX_DATA) {
+                MOUSE_RECEIVED.lock().push(byte);
+                mouse = true;
+            } else {
+                KEYBOARD_RECEIVED.lock().push(byte);
+                keyboard = true;
+            }
+        }
+    });
+    if keyboard {
+        KEYBOARD_WAITERS.wake_all();
+    }
+    if mouse {
+        MOUSE_WAITERS.wake_all();
+    }
+}
+
+extern "C" fn keyboard_input(_: *mut c_void) {
+    let keyboard = KEYBOARD
+        .get()
+        .expect("the keyboard should be registered before the task starts");
+    let mut decoder = ScancodeDecoder::new(keyboard.set);
+    let mut keymap = Keymap::new();
+    let tty = tty::console();
+    let mut buf = [0_u8; 64];
+    loop {
+        let generation = KEYBOARD_WAITERS.generation();
+        let mut typed = false;
+        loop {
+            let n = interrupts::without_interrupts(|| KEYBOARD_RECEIVED.lock().pop_into(&mut buf));
+            if n == 0 {
+                break;
+            }
+            for event in buf[..n].iter().filter_map(|&byte| decoder.feed(byte)) {
+                keyboard
+                    .device
+                    .report(EventType::Key, event.key.number(), event.value());
+                keyboard.device.sync();
+                keymap.translate(event, |bytes| {
+                    tty.receive(bytes);
+                    typed = true;
+                });
+            }
+        }
+        if typed {
+            INPUT_WAITERS.wake_all();
+        }
+        // the root process is never reaped, so the outcome is always ready
+        let _ = Process::root().park_current_task_on(
+            None,
+            |waker| KEYBOARD_WAITERS.register(waker),
+            || KEYBOARD_WAITERS.generation() != generation,
+        );
+    }
+}
+
+extern "C" fn mouse_input(_: *mut c_void) {
+    let pointing = MOUSE
+        .get()
+        .expect("the mouse should be registered before the task starts");
+    let mut mouse = Mouse::new(pointing.protocol);
+    let mut buf = [0_u8; 64];
+    loop {
+        let generation = MOUSE_WAITERS.generation();
+        loop {
+            let n = interrupts::without_interrupts(|| MOUSE_RECEIVED.lock().pop_into(&mut buf));
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                let reported = mouse.feed(byte, |kind, code, value| {
+                    pointing.device.report(kind, code, value);
+                });
+                if reported {
+                    pointing.device.sync();
+                }
+            }
+        }
+        // the root process is never reaped, so the outcome is always ready
+        let _ = Process::root().park_current_task_on(
+            None,
+            |waker| MOUSE_WAITERS.register(waker),
+            || MOUSE_WAITERS.generation() != generation,
+        );
+    }
+}
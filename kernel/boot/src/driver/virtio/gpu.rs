@@ -3,18 +3,13 @@ use alloc::sync::Arc;
 use core::error::Error;
 use core::fmt::{Debug, Formatter};
 use core::ptr::NonNull;
-use core::slice;
 
-use kernel_abi::{FbScreenInfo, IoctlRequest};
-use kernel_devfs::DevFile;
+use kernel_abi::FbScreenInfo;
 use kernel_device::Device;
 use kernel_device::raw::RawDevice;
 use kernel_pci::PciAddress;
 use kernel_pci::config::ConfigurationAccess;
-use kernel_vfs::path::AbsolutePath;
-use kernel_vfs::{
-    FsyncError, IoctlError, MmapError, MmapRegion, ReadError, Stat, StatError, WriteError,
-};
+use kernel_vfs::FsyncError;
 use linkme::distributed_slice;
 use spin::Mutex;
 use spin::rwlock::RwLock;
@@ -26,10 +21,10 @@ use x86_64::structures::paging::{PhysFrame, Size4KiB};
 
 use crate::UsizeExt;
 use crate::driver::KernelDeviceId;
+use crate::driver::fb::{self, Flush, Framebuffers};
 use crate::driver::pci::{PCI_DRIVERS, PciDriverDescriptor, PciDriverType};
 use crate::driver::raw::RawDevices;
 use crate::driver::virtio::hal::{HalImpl, transport};
-use crate::file::devfs::devfs;
 use crate::mem::address_space::AddressSpace;
 
 #[distributed_slice(PCI_DRIVERS)]
@@ -65,26 +60,25 @@ fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<()
     let physical_memory = PhysFrameRangeInclusive { start, end };
 
     let gpu_arc = Arc::new(Mutex::new(gpu));
+    let id = KernelDeviceId::new();
+    let info = FbScreenInfo {
+        width,
+        height,
+        pitch: width * 4,
+        bpp: 32,
+    };
+    let number = Framebuffers::register(id, info);
 
-    {
+    let flush: Flush = {
         let gpu_arc = gpu_arc.clone();
-        let fb_addr = fb_ptr.as_ptr() as usize;
-        devfs()
-            .write()
-            .register_file(AbsolutePath::try_new("/fb0").unwrap(), move || {
-                Ok(FbDevFile {
-                    gpu: gpu_arc.clone(),
-                    ptr: NonNull::new(fb_addr as *mut u8).unwrap(),
-                    len: logical_len,
-                    width,
-                    height,
-                })
-            })
-            .expect("should be able to register /dev/fb0");
-    }
+        Arc::new(move || gpu_arc.lock().flush().map_err(|_| FsyncError::Failed))
+    };
+    // Safety: the GPU driver owns the framebuffer, and the raw device keeps
+    // the driver alive for the kernel's lifetime
+    unsafe { fb::register_device_file(number, fb_ptr, logical_len, info, Some(flush)) };
 
     let device = VirtIoRawDevice {
-        id: KernelDeviceId::new(),
+        id,
         _inner: gpu_arc,
         physical_memory,
     };
@@ -95,86 +89,6 @@ fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<()
     Ok(())
 }
 
-/// Device file backing `/dev/fb0`. Holds a shared handle to the underlying
-/// GPU so `fsync` can flush it, and a raw pointer to the HHDM-mapped
-/// framebuffer bytes so `mmap` can hand them out.
-struct FbDevFile {
-    gpu: Arc<Mutex<VirtIOGpu<HalImpl, PciTransport>>>,
-    ptr: NonNull<u8>,
-    len: usize,
-    width: u32,
-    height: u32,
-}
-
-// SAFETY: `ptr` aliases a framebuffer that lives for the kernel's lifetime;
-// access is gated behind `&mut self` on this `DevFile`, which `DevFs` already
-// serializes via its open-file map.
-unsafe impl Send for FbDevFile {}
-unsafe impl Sync for FbDevFile {}
-
-impl FbDevFile {
-    fn as_slice_mut(&mut self) -> &mut [u8] {
-        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
-    }
-}
-
-impl DevFile for FbDevFile {
-    fn read(&mut self, buf: &mut [u8], offset: usize) -> Result<usize, ReadError> {
-        if offset >= self.len {
-            return Err(ReadError::EndOfFile);
-        }
-        let n = buf.len().min(self.len - offset);
-        let fb = self.as_slice_mut();
-        buf[..n].copy_from_slice(&fb[offset..offset + n]);
-        Ok(n)
-    }
-
-    fn write(&mut self, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
-        if offset >= self.len {
-            return Err(WriteError::WriteFailed);
-        }
-        let n = buf.len().min(self.len - offset);
-        let fb = self.as_slice_mut();
-        fb[offset..offset + n].copy_from_slice(&buf[..n]);
-        Ok(n)
-    }
-
-    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
-        stat.size = self.len;
-        Ok(())
-    }
-
-    fn mmap(&mut self) -> Result<MmapRegion, MmapError> {
-        Ok(MmapRegion {
-            ptr: self.ptr,
-            len: self.len,
-        })
-    }
-
-    fn fsync(&mut self) -> Result<(), FsyncError> {
-        self.gpu.lock().flush().map_err(|_| FsyncError::Failed)?;
-        Ok(())
-    }
-
-    fn ioctl(&mut self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
-        match request {
-            IoctlRequest::FbGetScreenInfo => {
-                let Ok(arg) = <&mut [u8; FbScreenInfo::SIZE]>::try_from(arg) else {
-                    return Err(IoctlError::InvalidArgument);
-                };
-                let info = FbScreenInfo {
-                    width: self.width,
-                    height: self.height,
-                    pitch: self.width * 4,
-                    bpp: 32,
-                };
-                *arg = info.to_bytes();
-                Ok(0)
-            }
-        }
-    }
-}
-
 #[derive(Clone)]
 pub struct VirtIoRawDevice {
     id: KernelDeviceId,
@@ -0,0 +1,219 @@
+//! virtio-input devices, like QEMU's `virtio-keyboard-pci` and
+//! `virtio-tablet-pci`.
+//!
+//! A device describes itself in its configuration space and sends evdev
+//! events, which are the same as the kernel's [`InputEvent`]s. The interrupt
+//! handler only acknowledges the devices. A kernel task takes the events
+//! from their queues, reports them through an input device, and types the
+//! keys into the console terminal.
+//!
+//! [`InputEvent`]: kernel_abi::InputEvent
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::ffi::c_void;
+use core::ptr;
+
+use conquer_once::spin::OnceCell;
+use kernel_abi::{EventType, KEY_RELEASED, KeyCode, SYN_REPORT};
+use kernel_input::DeviceInfo;
+use kernel_keyboard::{KeyEvent, Keymap};
+use kernel_pci::PciAddress;
+use kernel_pci::config::ConfigurationAccess;
+use linkme::distributed_slice;
+use spin::Mutex;
+use spin::rwlock::RwLock;
+use thiserror::Error;
+use tracing::info;
+use virtio_drivers::device::input::{InputEvent as VirtioEvent, VirtIOInput};
+use virtio_drivers::transport::InterruptStatus;
+use virtio_drivers::transport::pci::PciTransport;
+use x86_64::instructions::interrupts;
+
+use crate::apic::route_pci_irq;
+use crate::arch::idt::InterruptIndex;
+use crate::driver::input::{self, InputDevice};
+use crate::driver::pci::{PCI_DRIVERS, PciDriverDescriptor, PciDriverType};
+use crate::driver::virtio::hal::{HalImpl, transport};
+use crate::file::INPUT_WAITERS;
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
+use crate::mcore::mtask::task::Task;
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::tty;
+
+#[distributed_slice(PCI_DRIVERS)]
+static VIRTIO_INPUT: PciDriverDescriptor = PciDriverDescriptor {
+    name: "virtio-input",
+    typ: PciDriverType::Specific,
+    probe: virtio_probe,
+    init: virtio_init,
+};
+
+/// The devices that the interrupt handler acknowledges and the task reads.
+/// Written with interrupts disabled only.
+static DEVICES: RwLock<Vec<Arc<VirtioInput>>> = RwLock::new(Vec::new());
+
+/// The input task waits here for the devices to interrupt.
+static WAITERS: WaitQueue = WaitQueue::new();
+
+/// Initialized once the input task was started, with the first device.
+static TASK: OnceCell<()> = OnceCell::uninit();
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+enum InterruptError {
+    #[error("the device has no interrupt pin")]
+    NoPin,
+    #[error("the firmware didn't assign an interrupt line")]
+    NoLine,
+}
+
+struct VirtioInput {
+    inner: Mutex<VirtIOInput<HalImpl, PciTransport>>,
+    device: Arc<InputDevice>,
+    /// Used by the input task only.
+    keymap: Mutex<Keymap>,
+}
+
+fn virtio_probe(addr: PciAddress, cam: &dyn ConfigurationAccess) -> bool {
+    addr.vendor_id(cam) == 0x1af4 && addr.device_id(cam) == 0x1052
+}
+
+#[allow(clippy::needless_pass_by_value)] // signature is required like this
+fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<(), Box<dyn Error>> {
+    if addr.interrupt_pin(&*cam) == 0 {
+        return Err(InterruptError::NoPin.into());
+    }
+    let line = match addr.interrupt_line(&*cam) {
+        0xff => return Err(InterruptError::NoLine.into()),
+        line => line,
+    };
+    addr.set_legacy_interrupts(&*cam, true);
+
+    let transport = transport(addr, cam);
+    let mut inner = VirtIOInput::<HalImpl, _>::new(transport)?;
+    let info = device_info(&mut inner)?;
+    let input = Arc::new(VirtioInput {
+        inner: Mutex::new(inner),
+        device: input::register(info),
+        keymap: Mutex::new(Keymap::new()),
+    });
+    info!(
+        number = input.device.number(),
+        line, "virtio-input device enabled"
+    );
+
+    interrupts::without_interrupts(|| DEVICES.write().push(input));
+    TASK.init_once(|| {
+        let task = Task::create_new(Process::root(), virtio_input, ptr::null_mut())
+            .expect("should be able to create the virtio-input task");
+        info!(id = %task.id(), "virtio-input task created");
+        GlobalTaskQueue::enqueue(Box::pin(task));
+    });
+    // events that arrived until now hold the line, so they aren't lost
+    route_pci_irq(line, InterruptIndex::VirtioInput);
+    Ok(())
+}
+
+/// Reads the name of the device and the events that it reports from its
+/// configuration space.
+fn device_info(
+    inner: &mut VirtIOInput<HalImpl, PciTransport>,
+) -> Result<DeviceInfo, virtio_drivers::Error> {
+    let name = inner
+        .name()
+        .unwrap_or_else(|_| String::from("virtio input device"));
+    let mut info = DeviceInfo::new(name);
+    for kind in [EventType::Key, EventType::Relative, EventType::Absolute] {
+        let kind_number = u8::try_from(kind.number()).expect("event types fit into a byte");
+        for code in codes(&inner.ev_bits(kind_number)?) {
+            info = match kind {
+                EventType::Absolute => {
+                    let axis = u8::try_from(code).expect("codes of a bitmap fit into a byte");
+                    let abs = inner.abs_info(axis)?;
+                    info.with_absolute_axis(code, abs.min.cast_signed(), abs.max.cast_signed())
+                }
+                _ => info.with_event(kind, code),
+            };
+        }
+    }
+    Ok(info)
+}
+
+/// The codes whose bits are set in a bitmap of the configuration space.
+fn codes(bitmap: &[u8]) -> impl Iterator<Item = u16> {
+    (0..bitmap.len() * 8)
+        .filter(|&bit| bitmap[bit / 8] & (1 << (bit % 8)) != 0)
+        .filter_map(|bit| u16::try_from(bit).ok())
+}
+
+impl VirtioInput {
+    /// Reports `event` and passes the bytes that a key types to `typed`.
+    /// Events of types that the input layer doesn't know, like LEDs, are
+    /// dropped.
+    fn receive(&self, event: VirtioEvent, typed: impl FnMut(&[u8])) {
+        let Some(kind) = EventType::from_number(event.event_type) else {
+            return;
+        };
+        let value = event.value.cast_signed();
+        match kind {
+            EventType::Sync if event.code == SYN_REPORT => self.device.sync(),
+            // the input layer reports dropped events itself
+            EventType::Sync => {}
+            EventType::Key => {
+                self.device.report(kind, event.code, value);
+                if let Some(key) = KeyCode::from_number(event.code) {
+                    // a value of 2 repeats a key that is held down
+                    let event = KeyEvent::new(key, value != KEY_RELEASED);
+                    self.keymap.lock().translate(event, typed);
+                }
+            }
+            EventType::Relative | EventType::Absolute => {
+                self.device.report(kind, event.code, value);
+            }
+        }
+    }
+}
+
+/// Acknowledges the interrupt of every device and wakes the input task if
+/// one of them has events. Called from the interrupt handler.
+pub(crate) fn receive_pending() {
+    let mut received = false;
+    for input in DEVICES.read().iter() {
+        let status = input.inner.lock().ack_interrupt();
+        received |= status.contains(InterruptStatus::QUEUE_INTERRUPT);
+    }
+    if received {
+        WAITERS.wake_all();
+    }
+}
+
+extern "C" fn virtio_input(_: *mut c_void) {
+    let tty = tty::console();
+    loop {
+        let generation = WAITERS.generation();
+        let mut typed = false;
+        for input in DEVICES.read().iter() {
+            while let Some(event) =
+                interrupts::without_interrupts(|| input.inner.lock().pop_pending_event())
+            {
+                input.receive(event, |bytes| {
+                    tty.receive(bytes);
+                    typed = true;
+                });
+            }
+        }
+        if typed {
+            INPUT_WAITERS.wake_all();
+        }
+        // the root process is never reaped, so the outcome is always ready
+        let _ = Process::root().park_current_task_on(
+            None,
+            |waker| WAITERS.register(waker),
+            || WAITERS.generation() != generation,
+        );
+    }
+}
@@ -1,3 +1,4 @@
 mod block;
 mod gpu;
 mod hal;
+pub mod input;
@@ -0,0 +1,93 @@
+use alloc::sync::Arc;
+use core::slice;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use kernel_abi::MountFlags;
+use kernel_device::block::MemoryBlockDevice;
+use kernel_iso9660::Iso9660Fs;
+use kernel_vfs::fs::FileSystem;
+use kernel_vfs::path::AbsolutePath;
+use limine::file::File;
+use spin::RwLock;
+use tracing::{info, warn};
+
+use crate::U64Ext;
+use crate::driver::block::{BlockDeviceEntry, BlockDeviceHandle, BlockDevices};
+use crate::file::mount;
+use crate::file::registry::CreateFileSystemError;
+use crate::limine::MODULE_REQUEST;
+
+/// The string of the Limine module that holds an ISO9660 image to mount at
+/// `/boot` instead of the boot medium, set with `module_string: boot` in
+/// `limine.conf`.
+pub const MODULE_STRING: &str = "boot";
+
+/// The sector size of a CD, which every ISO9660 image is a multiple of.
+const SECTOR_SIZE: usize = 2048;
+
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+fn module() -> Option<&'static File> {
+    MODULE_REQUEST
+        .get_response()?
+        .modules()
+        .iter()
+        .find(|module| module.string().to_bytes() == MODULE_STRING.as_bytes())
+        .copied()
+}
+
+/// Creates the file system that the `iso9660` driver mounts, on `source`,
+/// or on the image in the `boot` module without one.
+pub(super) fn create(
+    source: Option<BlockDeviceHandle>,
+) -> Result<Arc<RwLock<dyn FileSystem>>, CreateFileSystemError> {
+    if let Some(device) = source {
+        let fs =
+            Iso9660Fs::try_new(device).map_err(|_| CreateFileSystemError::InvalidFileSystem)?;
+        return Ok(Arc::new(RwLock::new(fs)) as _);
+    }
+
+    let module = module().ok_or(CreateFileSystemError::NeedsDevice)?;
+    if TAKEN.swap(true, Relaxed) {
+        return Err(CreateFileSystemError::Unavailable);
+    }
+    // SAFETY: Limine loads modules into memory that the kernel never hands
+    // out, and `TAKEN` makes sure that we create this slice only once.
+    let image = unsafe { slice::from_raw_parts_mut(module.addr(), module.size().into_usize()) };
+    let device = MemoryBlockDevice::try_new(SECTOR_SIZE, image)
+        .ok_or(CreateFileSystemError::InvalidFileSystem)?;
+    let fs = Iso9660Fs::try_new(device).map_err(|_| CreateFileSystemError::InvalidFileSystem)?;
+    Ok(Arc::new(RwLock::new(fs)) as _)
+}
+
+/// Mounts the boot medium read-only at `/boot`: the image in the `boot`
+/// module if the bootloader passed one, and the first block device with an
+/// ISO9660 file system otherwise. The boot medium is optional, so failures
+/// are only logged.
+pub fn mount_boot() {
+    let target = AbsolutePath::try_new("/boot").unwrap();
+    let source = if module().is_some() {
+        None
+    } else if let Some(entry) = BlockDevices::all().iter().find(|entry| is_iso9660(entry)) {
+        Some(entry.path())
+    } else {
+        info!("no boot medium to mount at /boot");
+        return;
+    };
+
+    let name = source.as_ref().map_or("boot module", |path| path.as_str());
+    info!("mounting {name} (iso9660) at /boot");
+    if let Err(e) = mount(
+        "iso9660",
+        source.as_ref().map(AsRef::as_ref),
+        target,
+        MountFlags::READ_ONLY,
+    ) {
+        warn!("can't mount {name} at /boot: {e}");
+    }
+}
+
+fn is_iso9660(entry: &BlockDeviceEntry) -> bool {
+    Iso9660Fs::read_volume_descriptor(&entry.device).is_ok()
+}
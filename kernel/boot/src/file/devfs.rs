@@ -1,11 +1,11 @@
-use core::fmt::Write;
-
 use conquer_once::spin::OnceCell;
-use kernel_devfs::{ArcLockedDevFs, Null, Serial};
+use kernel_devfs::ArcLockedDevFs;
+use kernel_vfs::OpenError;
 use kernel_vfs::path::AbsolutePath;
 use tracing::{Level, instrument};
 
-use crate::serial_print;
+use crate::mcore::context::ExecutionContext;
+use crate::tty::{self, TtyFile};
 
 static DEVFS: OnceCell<ArcLockedDevFs> = OnceCell::uninit();
 
@@ -19,36 +19,35 @@ pub fn init() {
     let devfs = ArcLockedDevFs::new();
     {
         let mut guard = devfs.write();
-        guard
-            .register_file(AbsolutePath::try_new("/serial").unwrap(), || {
-                Ok(Serial::<SerialWrite>::default())
-            })
-            .expect("should be able to register serial file");
-
-        // TODO: implement proper STDIO
-        guard
-            .register_file(AbsolutePath::try_new("/stdin").unwrap(), || Ok(Null))
-            .expect("should be able to register stdin");
-        guard
-            .register_file(AbsolutePath::try_new("/stdout").unwrap(), || {
-                Ok(Serial::<SerialWrite>::default())
-            })
-            .expect("should be able to register stdout");
-        guard
-            .register_file(AbsolutePath::try_new("/stderr").unwrap(), || {
-                Ok(Serial::<SerialWrite>::default())
-            })
-            .expect("should be able to register stderr");
+        for path in ["/serial", "/ttyS0"] {
+            guard
+                .register_file(AbsolutePath::try_new(path).unwrap(), || {
+                    Ok(TtyFile::open(tty::serial().clone()))
+                })
+                .expect("should be able to register the serial terminal");
+        }
+
+        if tty::framebuffer().is_some() {
+            guard
+                .register_file(AbsolutePath::try_new("/tty0").unwrap(), || {
+                    let tty = tty::framebuffer().expect("the console terminal should exist");
+                    Ok(TtyFile::open(tty.clone()))
+                })
+                .expect("should be able to register the console terminal");
+        }
+
+        // These resolve to the controlling terminal of whoever opens them.
+        for path in ["/tty", "/stdin", "/stdout", "/stderr"] {
+            guard
+                .register_file(AbsolutePath::try_new(path).unwrap(), || {
+                    ExecutionContext::load()
+                        .current_process()
+                        .controlling_terminal()
+                        .map(TtyFile::new)
+                        .ok_or(OpenError::NotFound)
+                })
+                .expect("should be able to register the controlling terminal");
+        }
     }
     DEVFS.init_once(|| devfs);
 }
-
-#[derive(Default)]
-struct SerialWrite;
-
-impl Write for SerialWrite {
-    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        serial_print!("{s}");
-        Ok(())
-    }
-}
@@ -1,22 +1,22 @@
-use alloc::borrow::ToOwned;
 use alloc::collections::BTreeMap;
-use alloc::sync::Arc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use kernel_device::block::BlockDevice;
-use kernel_ext2::{Ext2Fs, Inode, InodeAddress, Type};
-use kernel_vfs::fs::{FileSystem, FsHandle};
-use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, Path};
+use kernel_ext2::{Directory, Ext2Fs, Inode, InodeAddress, ROOT_DIR_INODE_ADDRESS, SymLink, Type};
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use kernel_vfs::path::OwnedPath;
 use kernel_vfs::{
-    CloseError, FsError, FsyncError, OpenError, ReadError, Stat, StatError, WriteError,
+    CloseError, FsError, FsyncError, OpenError, ReadError, ReadlinkError, Stat, StatError,
+    WriteError,
 };
-use spin::RwLock;
 
 pub struct VirtualExt2Fs<T> {
     ext2fs: Ext2Fs<T>,
-    handles: BTreeMap<FsHandle, Arc<(AbsoluteOwnedPath, RwLock<VirtualExt2Inode>)>>,
+    handles: BTreeMap<FsHandle, VirtualExt2Inode>,
 }
 
 impl<T> From<Ext2Fs<T>> for VirtualExt2Fs<T> {
@@ -46,29 +46,58 @@ impl<T> FileSystem for VirtualExt2Fs<T>
 where
     T: BlockDevice + Send + Sync,
 {
-    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
-        static FS_COUNTER: AtomicU64 = AtomicU64::new(0);
-
-        // instead of creating a new inode, check whether we already have that inode open behind another handle
-        if let Some(v) = self.handles.values().find(|v| path == v.0.as_ref()) {
-            let handle = FsHandle::from(FS_COUNTER.fetch_add(1, Relaxed));
-            self.handles.insert(handle, v.clone());
-            return Ok(handle);
-        }
+    fn root(&self) -> FsNodeId {
+        FsNodeId::from(u64::from(ROOT_DIR_INODE_ADDRESS.get()))
+    }
 
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let dir = Directory::try_from(self.read_node(dir)?).map_err(|_| OpenError::NotDirectory)?;
         let (found_num, found) = self
-            .find_inode(path)
+            .find_and_resolve_entry(&dir, |entry| entry.name() == Some(name))
             .map_err(|_| OpenError::NotFound)?
             .ok_or(OpenError::NotFound)?;
+        let kind = match found.typ() {
+            Type::Directory => FsNodeKind::Directory,
+            Type::SymLink => FsNodeKind::Symlink,
+            _ => FsNodeKind::File,
+        };
+        Ok((FsNodeId::from(u64::from(found_num.get())), kind))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        let dir = Directory::try_from(self.read_node(dir)?).map_err(|_| OpenError::NotDirectory)?;
+        let entries = self.list_dir(&dir).map_err(|_| OpenError::NotFound)?;
+        Ok(entries
+            .iter()
+            .filter_map(|entry| entry.name())
+            .filter(|name| *name != "." && *name != "..")
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        static FS_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let (found_num, found) = self.read_node(node)?;
 
         let handle = FsHandle::from(FS_COUNTER.fetch_add(1, Relaxed));
-        let inode = VirtualExt2Inode::try_new(found_num, found).unwrap();
+        let inode = VirtualExt2Inode::try_new(found_num, found).ok_or(OpenError::NotFound)?;
 
-        self.handles
-            .insert(handle, Arc::new((path.to_owned(), RwLock::new(inode))));
+        self.handles.insert(handle, inode);
         Ok(handle)
     }
 
+    fn readlink(&mut self, node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        let link =
+            SymLink::try_from(self.read_node(node)?).map_err(|_| ReadlinkError::NotSymlink)?;
+        let target = self
+            .read_symlink(&link)
+            .map_err(|_| ReadlinkError::ReadFailed)?;
+        String::from_utf8(target)
+            .map(OwnedPath::new)
+            .map_err(|_| ReadlinkError::ReadFailed)
+    }
+
     fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
         if self.handles.remove(&handle).is_none() {
             Err(CloseError::NotOpen)
@@ -83,10 +112,9 @@ where
         buf: &mut [u8],
         offset: usize,
     ) -> Result<usize, ReadError> {
-        let inode = &self.handles.get(&handle).ok_or(FsError::InvalidHandle)?.1;
+        let inode = self.handles.get(&handle).ok_or(FsError::InvalidHandle)?;
 
-        let guard = inode.read();
-        match &guard.inner {
+        match &inode.inner {
             Inner::RegularFile(file) => self
                 .ext2fs
                 .read_from_file(file, offset, buf)
@@ -101,19 +129,21 @@ where
         _buf: &[u8],
         _offset: usize,
     ) -> Result<usize, WriteError> {
-        todo!("write support for ext2");
+        // the driver can't write yet, so every file is read-only
+        Err(WriteError::NotWritable)
     }
 
     fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
-        let inode = &self.handles.get(&handle).ok_or(FsError::InvalidHandle)?.1;
-
-        let guard = inode.read();
-        match &guard.inner {
-            Inner::RegularFile(file) => {
-                stat.size = file.len();
-            }
-            _ => todo!(),
-        }
+        let inode = self.handles.get(&handle).ok_or(FsError::InvalidHandle)?;
+
+        let raw = inode.inner.as_ref();
+        *stat = Stat {
+            size: raw.len(),
+            ino: u64::from(inode.inode_num.get()),
+            mode: u32::from(raw.typ().bits() | raw.perm().bits()),
+            nlink: u32::from(raw.num_hard_links()),
+            blocks: u64::from(raw.num_disk_sectors()),
+        };
         Ok(())
     }
 
@@ -126,53 +156,17 @@ impl<T> VirtualExt2Fs<T>
 where
     T: BlockDevice + Send + Sync,
 {
-    fn find_inode(&self, path: &Path) -> Result<Option<(InodeAddress, Inode)>, kernel_ext2::Error> {
-        let root_inode = self.ext2fs.read_root_inode()?;
-        self.find_inode_from(path, root_inode.into_inner())
-    }
-
-    fn find_inode_from(
-        &self,
-        path: &Path,
-        starting_point: (InodeAddress, Inode),
-    ) -> Result<Option<(InodeAddress, Inode)>, kernel_ext2::Error> {
-        let components = path.filenames();
-
-        let (mut current_num, mut current) = starting_point;
-        for component in components {
-            match component {
-                "/" => {
-                    todo!("absolute path")
-                }
-                "." => {} // do nothing,
-                ".." => {
-                    todo!("parent dir");
-                }
-                v => {
-                    let x = current.typ();
-                    if x != Type::Directory {
-                        todo!("symlink support")
-                    }
-                    // x is a directory
-                    let found_entry = self
-                        .list_dir(&current)?
-                        .into_iter()
-                        .find(|entry| entry.name() == Some(v));
-                    if let Some(found_entry) = found_entry {
-                        (current_num, current) = self.resolve_dir_entry(found_entry)?;
-                    } else {
-                        return Ok(None);
-                    }
-                }
-            }
-        }
-
-        Ok(Some((current_num, current)))
+    fn read_node(&self, node: FsNodeId) -> Result<(InodeAddress, Inode), OpenError> {
+        let addr = u32::try_from(node.get())
+            .ok()
+            .and_then(InodeAddress::new)
+            .ok_or(OpenError::NotFound)?;
+        self.read_inode(addr).map_err(|_| OpenError::NotFound)
     }
 }
 
 pub struct VirtualExt2Inode {
-    _inode_num: InodeAddress,
+    inode_num: InodeAddress,
     inner: Inner,
 }
 
@@ -186,10 +180,7 @@ impl VirtualExt2Inode {
             Type::Directory => Inner::Directory((inode_num, inode).try_into().unwrap()),
             _ => return None,
         };
-        Some(Self {
-            _inode_num: inode_num,
-            inner,
-        })
+        Some(Self { inode_num, inner })
     }
 }
 
@@ -0,0 +1,164 @@
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+use core::{slice, str};
+
+use kernel_cpio::{CpioArchive, CpioError, Entry, EntryKind};
+use kernel_device::block::MemoryBlockDevice;
+use kernel_ext2::Ext2Fs;
+use kernel_tmpfs::TmpFs;
+use kernel_vfs::fs::{FileSystem, FsNodeId, FsNodeKind};
+use kernel_vfs::path::Path;
+use kernel_vfs::{CloseError, CreateError, OpenError, WriteError};
+use limine::file::File;
+use spin::RwLock;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::U64Ext;
+use crate::driver::block::BlockDeviceHandle;
+use crate::file::ext2::VirtualExt2Fs;
+use crate::file::registry::CreateFileSystemError;
+use crate::file::tmpfs::{FramePageAllocator, TMPFS_SIZE};
+use crate::limine::MODULE_REQUEST;
+
+/// The string of the Limine module that holds the initramfs, set with
+/// `module_string: initramfs` in `limine.conf`.
+pub const MODULE_STRING: &str = "initramfs";
+
+/// The sector size of an ext2 image in memory. Ext2 block sizes are a
+/// multiple of this, so every image is a whole number of sectors.
+const SECTOR_SIZE: usize = 512;
+
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+fn module() -> Option<&'static File> {
+    MODULE_REQUEST
+        .get_response()?
+        .modules()
+        .iter()
+        .find(|module| module.string().to_bytes() == MODULE_STRING.as_bytes())
+        .copied()
+}
+
+/// Returns whether the bootloader passed an initramfs that hasn't been
+/// mounted yet.
+#[must_use]
+pub fn is_available() -> bool {
+    module().is_some() && !TAKEN.load(Relaxed)
+}
+
+/// Creates the file system that the `initramfs` driver mounts.
+///
+/// A cpio archive in the `newc` format is unpacked into a fresh tmpfs,
+/// anything else is mounted as an ext2 image in place. Either way, the
+/// initramfs can only be mounted once, because an ext2 image is modified
+/// in place.
+pub(super) fn create(
+    _: Option<BlockDeviceHandle>,
+) -> Result<Arc<RwLock<dyn FileSystem>>, CreateFileSystemError> {
+    let module = module().ok_or(CreateFileSystemError::Unavailable)?;
+    if TAKEN.swap(true, Relaxed) {
+        return Err(CreateFileSystemError::Unavailable);
+    }
+    // SAFETY: Limine loads modules into memory that the kernel never hands
+    // out, and `TAKEN` makes sure that we create this slice only once.
+    let image = unsafe { slice::from_raw_parts_mut(module.addr(), module.size().into_usize()) };
+
+    if CpioArchive::is_cpio(image) {
+        info!("unpacking cpio initramfs of {} bytes", image.len());
+        let mut fs = TmpFs::new(FramePageAllocator, TMPFS_SIZE + image.len());
+        unpack(&mut fs, CpioArchive::new(image)).map_err(|e| {
+            warn!("can't unpack initramfs: {e}");
+            CreateFileSystemError::InvalidFileSystem
+        })?;
+        Ok(Arc::new(RwLock::new(fs)) as _)
+    } else {
+        info!("using initramfs of {} bytes as ext2 image", image.len());
+        let device = MemoryBlockDevice::try_new(SECTOR_SIZE, image)
+            .ok_or(CreateFileSystemError::InvalidFileSystem)?;
+        let fs = Ext2Fs::try_new(device).map_err(|_| CreateFileSystemError::InvalidFileSystem)?;
+        Ok(Arc::new(RwLock::new(VirtualExt2Fs::from(fs))) as _)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+enum UnpackError {
+    #[error(transparent)]
+    Cpio(#[from] CpioError),
+    #[error(transparent)]
+    Create(#[from] CreateError),
+    #[error(transparent)]
+    Open(#[from] OpenError),
+    #[error(transparent)]
+    Write(#[from] WriteError),
+    #[error(transparent)]
+    Close(#[from] CloseError),
+}
+
+fn unpack(fs: &mut impl FileSystem, archive: CpioArchive<'_>) -> Result<(), UnpackError> {
+    for entry in archive.entries() {
+        let entry = entry?;
+        unpack_entry(fs, &entry).inspect_err(|e| {
+            warn!("can't unpack initramfs entry {}: {e}", entry.name());
+        })?;
+    }
+    Ok(())
+}
+
+fn unpack_entry(fs: &mut impl FileSystem, entry: &Entry<'_>) -> Result<(), UnpackError> {
+    if entry.name().split('/').any(|component| component == "..") {
+        warn!(
+            "skipping initramfs entry {} outside of the root",
+            entry.name()
+        );
+        return Ok(());
+    }
+    let mut components = entry
+        .name()
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".");
+    // the entry for the root directory itself has no components
+    let Some(name) = components.next_back() else {
+        return Ok(());
+    };
+    let mut dir = fs.root();
+    for component in components {
+        dir = directory(fs, dir, component)?;
+    }
+
+    match entry.kind() {
+        EntryKind::Directory => {
+            directory(fs, dir, name)?;
+        }
+        EntryKind::File => {
+            let node = fs.create(dir, name, FsNodeKind::File)?;
+            let handle = fs.open(node)?;
+            let written = fs.write(handle, entry.data(), 0);
+            fs.close(handle)?;
+            written?;
+        }
+        EntryKind::Symlink => {
+            let target = str::from_utf8(entry.data()).map_err(|_| CpioError::InvalidName)?;
+            fs.symlink(dir, name, Path::new(target))?;
+        }
+        EntryKind::Other => {
+            warn!(
+                "skipping initramfs entry {} of unsupported type",
+                entry.name()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the directory `name` in `dir`, creating it if it doesn't exist,
+/// because archives don't need to list the parents of their entries.
+fn directory(fs: &mut impl FileSystem, dir: FsNodeId, name: &str) -> Result<FsNodeId, CreateError> {
+    match fs.lookup(dir, name) {
+        Ok((node, FsNodeKind::Directory)) => Ok(node),
+        Ok(_) => Err(CreateError::NotDirectory),
+        Err(OpenError::NotFound) => fs.create(dir, name, FsNodeKind::Directory),
+        Err(e) => Err(e.into()),
+    }
+}
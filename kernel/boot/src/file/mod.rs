@@ -1,19 +1,35 @@
+use alloc::borrow::ToOwned;
 use core::ops::Deref;
 use core::sync::atomic::{AtomicU64, Ordering};
 
-use kernel_vfs::Vfs;
+use kernel_abi::{MountFlags, O_NONBLOCK};
 use kernel_vfs::node::VfsNode;
 use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{MountError, MountOptions, Vfs};
 use spin::RwLock;
+use thiserror::Error;
 use tracing::{Level, instrument};
 
-use crate::file::devfs::devfs;
+use crate::driver::block::BlockDevices;
+use crate::file::registry::{CreateFileSystemError, FileSystems};
+use crate::mcore::mtask::wait::WaitQueue;
 
+pub mod boot;
 pub mod devfs;
 pub mod ext2;
+pub mod initramfs;
+pub mod procfs;
+pub mod registry;
+pub mod root;
+pub mod sysfs;
+pub mod tmpfs;
 
 static VFS: RwLock<Vfs> = RwLock::new(Vfs::new());
 
+/// Readers that found a device without input wait here. Every device that
+/// receives input wakes the whole queue, and the woken readers retry.
+pub static INPUT_WAITERS: WaitQueue = WaitQueue::new();
+
 #[must_use]
 pub fn vfs() -> &'static RwLock<Vfs> {
     &VFS
@@ -22,24 +38,90 @@ pub fn vfs() -> &'static RwLock<Vfs> {
 #[instrument(name = "init filesystem", level = Level::DEBUG)]
 pub fn init() {
     devfs::init();
+    registry::init();
+
+    mount(
+        "devfs",
+        None,
+        AbsolutePath::try_new("/dev").unwrap(),
+        MountFlags::empty(),
+    )
+    .expect("should be able to mount devfs");
+    mount(
+        "tmpfs",
+        None,
+        AbsolutePath::try_new("/tmp").unwrap(),
+        MountFlags::empty(),
+    )
+    .expect("should be able to mount tmpfs");
+    mount(
+        "proc",
+        None,
+        AbsolutePath::try_new("/proc").unwrap(),
+        MountFlags::READ_ONLY,
+    )
+    .expect("should be able to mount procfs");
+    mount(
+        "sysfs",
+        None,
+        AbsolutePath::try_new("/sys").unwrap(),
+        MountFlags::READ_ONLY,
+    )
+    .expect("should be able to mount sysfs");
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum MountFsError {
+    #[error("unknown file system type")]
+    UnknownFileSystem,
+    #[error("no such block device")]
+    NoSuchDevice,
+    #[error(transparent)]
+    CreateFileSystem(#[from] CreateFileSystemError),
+    #[error(transparent)]
+    Mount(#[from] MountError),
+}
 
-    VFS.write()
-        .mount(AbsolutePath::try_new("/dev").unwrap(), devfs().clone())
-        .expect("should be able to mount devfs");
+/// Creates a file system with the driver registered as `fs_type` and mounts
+/// it at `target`. `source` is the path of the block device that the file
+/// system lives on, such as `/dev/blk0`.
+///
+/// # Errors
+/// Returns an error if there is no driver or block device with the given
+/// name, or if the file system can't be created or mounted.
+pub fn mount(
+    fs_type: &str,
+    source: Option<&AbsolutePath>,
+    target: &AbsolutePath,
+    flags: MountFlags,
+) -> Result<(), MountFsError> {
+    let create = FileSystems::by_name(fs_type).ok_or(MountFsError::UnknownFileSystem)?;
+    let device = source
+        .map(|source| BlockDevices::by_path(source).ok_or(MountFsError::NoSuchDevice))
+        .transpose()?;
+    let fs = create(device)?;
+    VFS.write().mount_with(
+        target,
+        fs,
+        MountOptions {
+            fs_type: fs_type.into(),
+            source: source.map(ToOwned::to_owned),
+            flags,
+        },
+    )?;
+    Ok(())
 }
 
 #[derive(Debug)]
 pub struct OpenFileDescription {
     position: AtomicU64,
+    status_flags: i32,
     node: VfsNode,
 }
 
 impl From<VfsNode> for OpenFileDescription {
     fn from(node: VfsNode) -> Self {
-        Self {
-            position: AtomicU64::new(0),
-            node,
-        }
+        Self::new(node, 0)
     }
 }
 
@@ -48,6 +130,7 @@ impl Clone for OpenFileDescription {
         let position = self.position.load(Ordering::Relaxed);
         Self {
             position: AtomicU64::new(position),
+            status_flags: self.status_flags,
             node: self.node.clone(),
         }
     }
@@ -62,7 +145,23 @@ impl Deref for OpenFileDescription {
 }
 
 impl OpenFileDescription {
+    /// Creates a description for `node`. Of `oflag`, only the file status
+    /// flags like `O_NONBLOCK` are kept.
+    #[must_use]
+    pub fn new(node: VfsNode, oflag: i32) -> Self {
+        Self {
+            position: AtomicU64::new(0),
+            status_flags: oflag & O_NONBLOCK,
+            node,
+        }
+    }
+
     pub fn position(&self) -> &AtomicU64 {
         &self.position
     }
+
+    #[must_use]
+    pub fn is_nonblocking(&self) -> bool {
+        self.status_flags & O_NONBLOCK != 0
+    }
 }
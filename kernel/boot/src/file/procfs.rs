@@ -0,0 +1,160 @@
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::sync::atomic::Ordering::Relaxed;
+use core::time::Duration;
+
+use kernel_abi::{MountFlags, ProcessId};
+use kernel_procfs::{
+    CpuInfo, FdInfo, Kernel, MemoryInfo, MemoryMap, MemoryMapKind, MountInfo, ProcessInfo,
+    ProcessState,
+};
+use raw_cpuid::CpuId;
+use x86_64::structures::paging::{PageSize, Size4KiB};
+
+use crate::U64Ext;
+use crate::file::vfs;
+use crate::hpet::hpet_maybe;
+use crate::limine::MP_REQUEST;
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::process::mem::MemoryRegion;
+use crate::mcore::mtask::process::tree::process_tree;
+use crate::mem::phys::PhysicalMemory;
+
+/// The kernel state that the `proc` file system shows.
+pub struct KernelState;
+
+impl Kernel for KernelState {
+    fn processes(&self) -> Vec<ProcessId> {
+        process_tree().read().processes.keys().copied().collect()
+    }
+
+    fn process(&self, pid: ProcessId) -> Option<ProcessInfo> {
+        let process = process_tree().read().processes.get(&pid).cloned()?;
+        Some(process_info(&process))
+    }
+
+    fn current_process(&self) -> Option<ProcessId> {
+        ExecutionContext::try_load().map(ExecutionContext::pid)
+    }
+
+    fn memory(&self) -> MemoryInfo {
+        let frame_size = Size4KiB::SIZE.into_usize();
+        PhysicalMemory::frame_counts().map_or(MemoryInfo { total: 0, free: 0 }, |counts| {
+            MemoryInfo {
+                total: counts.usable * frame_size,
+                free: counts.free * frame_size,
+            }
+        })
+    }
+
+    fn cpus(&self) -> Vec<CpuInfo> {
+        // every CPU is the same model, so the one that we run on describes
+        // them all
+        let cpuid = CpuId::new();
+        let vendor = cpuid
+            .get_vendor_info()
+            .map_or_else(String::new, |vendor| vendor.as_str().to_string());
+        let model = cpuid
+            .get_processor_brand_string()
+            .map_or_else(String::new, |brand| brand.as_str().trim().to_string());
+
+        // SAFETY: the request is only written to during boot, before any
+        // process runs
+        let response = unsafe {
+            #[allow(static_mut_refs)]
+            MP_REQUEST.get_response()
+        };
+        response
+            .map(|response| response.cpus())
+            .unwrap_or_default()
+            .iter()
+            .map(|cpu| CpuInfo {
+                id: cpu.id,
+                lapic_id: cpu.lapic_id,
+                vendor: vendor.clone(),
+                model: model.clone(),
+            })
+            .collect()
+    }
+
+    fn uptime(&self) -> Duration {
+        hpet_maybe().map_or(Duration::ZERO, |hpet| {
+            Duration::from_nanos(hpet.read().elapsed_ns())
+        })
+    }
+
+    fn mounts(&self) -> Vec<MountInfo> {
+        vfs()
+            .read()
+            .mounts()
+            .iter()
+            .map(|mount| MountInfo {
+                source: mount.source().map(ToOwned::to_owned),
+                mount_point: mount.mount_point().to_owned(),
+                fs_type: mount.fs_type().to_string(),
+                read_only: mount.flags().contains(MountFlags::READ_ONLY),
+            })
+            .collect()
+    }
+}
+
+fn process_info(process: &Arc<Process>) -> ProcessInfo {
+    let (state, pending_signals, blocked_signals) = {
+        let signals = process.signals_read();
+        let state = if process.exit_outcome().is_some() {
+            ProcessState::Zombie
+        } else if signals.stopped() {
+            ProcessState::Stopped
+        } else {
+            ProcessState::Running
+        };
+        (state, signals.sigpending(), signals.blocked())
+    };
+
+    let fds = process
+        .file_descriptors()
+        .read()
+        .values()
+        .map(|fd| FdInfo {
+            num: c_int::from(fd.num()),
+            path: fd.file_description().path().to_owned(),
+        })
+        .collect();
+
+    let maps = process
+        .memory_regions()
+        .snapshot()
+        .iter()
+        .map(|region| MemoryMap {
+            start: region.addr().as_u64(),
+            size: region.size(),
+            kind: match **region {
+                MemoryRegion::Lazy(_) => MemoryMapKind::Anonymous,
+                MemoryRegion::Mapped(_) => MemoryMapKind::Mapped,
+                MemoryRegion::FileBacked(_) => MemoryMapKind::File,
+                MemoryRegion::Shared(_) => MemoryMapKind::Shared,
+            },
+            path: region.node().map(|node| node.path().to_owned()),
+            offset: region.file_offset(),
+        })
+        .collect();
+
+    ProcessInfo {
+        pid: process.pid(),
+        ppid: process.ppid(),
+        name: process.name().to_string(),
+        state,
+        pending_signals,
+        blocked_signals,
+        cmdline: process.cmdline(),
+        executable: process.executable_path(),
+        cwd: process.current_working_directory().read().clone(),
+        fds,
+        maps,
+        page_faults: process.telemetry().page_faults.load(Relaxed),
+    }
+}
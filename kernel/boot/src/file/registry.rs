@@ -0,0 +1,99 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use kernel_ext2::Ext2Fs;
+use kernel_fat::FatFs;
+use kernel_procfs::ProcFs;
+use kernel_sysfs::SysFs;
+use kernel_tmpfs::TmpFs;
+use kernel_vfs::fs::FileSystem;
+use spin::RwLock;
+use thiserror::Error;
+
+use crate::driver::block::BlockDeviceHandle;
+use crate::file::devfs::devfs;
+use crate::file::ext2::VirtualExt2Fs;
+use crate::file::procfs::KernelState;
+use crate::file::sysfs::DeviceState;
+use crate::file::tmpfs::{FramePageAllocator, TMPFS_SIZE};
+use crate::file::{boot, initramfs};
+
+/// Creates a file system instance, optionally on top of a block device.
+pub type CreateFileSystem =
+    fn(Option<BlockDeviceHandle>) -> Result<Arc<RwLock<dyn FileSystem>>, CreateFileSystemError>;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum CreateFileSystemError {
+    #[error("the file system needs a source device")]
+    NeedsDevice,
+    #[error("the device does not contain a valid file system")]
+    InvalidFileSystem,
+    #[error("the source of the file system is not available")]
+    Unavailable,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum RegisterFileSystemError {
+    #[error("a file system driver with that name is already registered")]
+    AlreadyRegistered,
+}
+
+static FILE_SYSTEMS: RwLock<BTreeMap<&'static str, CreateFileSystem>> =
+    RwLock::new(BTreeMap::new());
+
+/// The registry of file system drivers that can be mounted by name.
+pub struct FileSystems;
+
+impl FileSystems {
+    /// Registers the driver `create` under the file system type `name`.
+    ///
+    /// # Errors
+    /// Returns an error if a driver is already registered under `name`.
+    pub fn register(
+        name: &'static str,
+        create: CreateFileSystem,
+    ) -> Result<(), RegisterFileSystemError> {
+        let mut guard = FILE_SYSTEMS.write();
+        if guard.contains_key(name) {
+            return Err(RegisterFileSystemError::AlreadyRegistered);
+        }
+        guard.insert(name, create);
+        Ok(())
+    }
+
+    pub fn by_name(name: &str) -> Option<CreateFileSystem> {
+        FILE_SYSTEMS.read().get(name).copied()
+    }
+}
+
+pub(super) fn init() {
+    FileSystems::register("devfs", |_| Ok(Arc::new(RwLock::new(devfs().clone())) as _))
+        .expect("should be able to register devfs");
+    FileSystems::register("ext2", |source| {
+        let device = source.ok_or(CreateFileSystemError::NeedsDevice)?;
+        let fs = Ext2Fs::try_new(device).map_err(|_| CreateFileSystemError::InvalidFileSystem)?;
+        Ok(Arc::new(RwLock::new(VirtualExt2Fs::from(fs))) as _)
+    })
+    .expect("should be able to register ext2");
+    FileSystems::register("iso9660", boot::create).expect("should be able to register iso9660");
+    FileSystems::register("proc", |_| {
+        Ok(Arc::new(RwLock::new(ProcFs::new(KernelState))) as _)
+    })
+    .expect("should be able to register proc");
+    FileSystems::register("sysfs", |_| {
+        Ok(Arc::new(RwLock::new(SysFs::new(DeviceState))) as _)
+    })
+    .expect("should be able to register sysfs");
+    FileSystems::register("vfat", |source| {
+        let device = source.ok_or(CreateFileSystemError::NeedsDevice)?;
+        let fs = FatFs::try_new(device).map_err(|_| CreateFileSystemError::InvalidFileSystem)?;
+        Ok(Arc::new(RwLock::new(fs)) as _)
+    })
+    .expect("should be able to register vfat");
+    FileSystems::register("tmpfs", |_| {
+        Ok(Arc::new(RwLock::new(TmpFs::new(FramePageAllocator, TMPFS_SIZE))) as _)
+    })
+    .expect("should be able to register tmpfs");
+    FileSystems::register("initramfs", initramfs::create)
+        .expect("should be able to register initramfs");
+}
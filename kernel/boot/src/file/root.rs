@@ -0,0 +1,130 @@
+use alloc::format;
+use alloc::string::String;
+
+use kernel_abi::MountFlags;
+use kernel_cmdline::{InvalidRootError, RootSource};
+use kernel_device::block::{BlockDevice, Partition};
+use kernel_ext2::{Ext2Fs, Ext2FsId};
+use kernel_vfs::path::{AbsoluteOwnedPath, ROOT};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::cmdline::cmdline;
+use crate::driver::block::{BlockDeviceEntry, BlockDevices};
+use crate::file::{MountFsError, initramfs, mount};
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum MountRootError {
+    #[error(transparent)]
+    InvalidRoot(#[from] InvalidRootError),
+    #[error("no block device matches root={0}")]
+    NotFound(String),
+    #[error("can't mount {0}: {1}")]
+    Mount(String, MountFsError),
+}
+
+/// The root file system when there's no `root=`: the initramfs if the
+/// bootloader passed one, and `/dev/blk0` otherwise.
+fn fallback() -> RootSource {
+    if initramfs::is_available() {
+        RootSource::Initramfs
+    } else {
+        RootSource::device(0)
+    }
+}
+
+/// Mounts the root file system that `root=`, `rootfstype=` and `ro`/`rw` on
+/// the kernel cmdline select.
+///
+/// # Errors
+/// Returns an error if `root=` is invalid, or if the root file system can't
+/// be found or mounted.
+pub fn mount_root() -> Result<(), MountRootError> {
+    let cmdline = cmdline();
+    let root = cmdline.root();
+    let source = root
+        .map(RootSource::try_from)
+        .transpose()?
+        .unwrap_or_else(fallback);
+    let flags = if cmdline.read_only() {
+        MountFlags::READ_ONLY
+    } else {
+        MountFlags::empty()
+    };
+
+    let (fs_type, device) = match source {
+        RootSource::Initramfs => ("initramfs", None),
+        RootSource::Device(path) => ("ext2", Some(path)),
+        RootSource::Uuid(uuid) => (
+            "ext2",
+            Some(find(root, |entry| ext2_uuid(entry) == Some(uuid))?),
+        ),
+        RootSource::PartUuid(guid) => (
+            "ext2",
+            Some(find(root, |entry| {
+                entry.partition.as_ref().and_then(Partition::guid) == Some(guid)
+            })?),
+        ),
+        RootSource::PartLabel(label) => (
+            "ext2",
+            Some(find(root, |entry| {
+                entry.partition.as_ref().and_then(Partition::label) == Some(label.as_str())
+            })?),
+        ),
+    };
+    let fs_type = cmdline.rootfstype().unwrap_or(fs_type);
+    let name = device.as_ref().map_or(fs_type, |path| path.as_str());
+
+    info!("mounting {name} ({fs_type}) at /");
+    mount(fs_type, device.as_ref().map(AsRef::as_ref), ROOT, flags)
+        .map_err(|e| MountRootError::Mount(name.into(), e))
+}
+
+/// Returns the path of the first block device that `matches`.
+fn find(
+    root: Option<&str>,
+    matches: impl Fn(&BlockDeviceEntry) -> bool,
+) -> Result<AbsoluteOwnedPath, MountRootError> {
+    BlockDevices::all()
+        .iter()
+        .find(|entry| matches(entry))
+        .map(BlockDeviceEntry::path)
+        .ok_or_else(|| MountRootError::NotFound(root.unwrap_or_default().into()))
+}
+
+fn ext2_uuid(entry: &BlockDeviceEntry) -> Option<Ext2FsId> {
+    Ext2Fs::read_superblock(&entry.device)
+        .ok()
+        .map(|superblock| superblock.fsid())
+}
+
+/// Logs every block device, along with its partition GUID and label and the
+/// UUID of the ext2 file system on it, so that a failed boot shows what
+/// `root=` could have been.
+pub fn log_block_devices() {
+    let devices = BlockDevices::all();
+    if devices.is_empty() {
+        error!("there are no block devices");
+    }
+    for entry in devices {
+        let mut line = format!(
+            "/dev/{}: {} bytes",
+            entry.name,
+            entry.device.sector_count() * entry.device.sector_size()
+        );
+        if let Some(guid) = entry.partition.as_ref().and_then(Partition::guid) {
+            line += &format!(", PARTUUID={guid}");
+        }
+        if let Some(label) = entry.partition.as_ref().and_then(Partition::label) {
+            line += &format!(", PARTLABEL={label}");
+        }
+        match ext2_uuid(&entry) {
+            Some(uuid) => line += &format!(", ext2 UUID={uuid}"),
+            None => line += ", no ext2 file system",
+        }
+        error!("{line}");
+    }
+    if initramfs::is_available() {
+        error!("initramfs: passed by the bootloader");
+    }
+}
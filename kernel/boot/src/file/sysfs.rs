@@ -0,0 +1,79 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use kernel_sysfs::{
+    BlockDeviceInfo, Devices, DriverStatus, FramebufferInfo, PartitionInfo, PciDeviceInfo,
+};
+
+use crate::driver::block::BlockDevices;
+use crate::driver::fb::Framebuffers;
+use crate::driver::pci::{PciDeviceStatus, PciDevices};
+
+/// The devices that the `sysfs` file system shows.
+pub struct DeviceState;
+
+impl Devices for DeviceState {
+    fn pci_devices(&self) -> Vec<PciDeviceInfo> {
+        PciDevices::all()
+            .into_iter()
+            .map(|device| PciDeviceInfo {
+                address: device.address,
+                vendor_id: device.vendor_id,
+                device_id: device.device_id,
+                class: device.class,
+                subclass: device.subclass,
+                prog_if: device.prog_if,
+                revision_id: device.revision_id,
+                subsystem_vendor_id: device.subsystem_vendor_id,
+                subsystem_id: device.subsystem_id,
+                bars: device.bars,
+                driver: device.driver.map(ToString::to_string),
+                status: match device.status {
+                    PciDeviceStatus::Unbound => DriverStatus::Unbound,
+                    PciDeviceStatus::Bound => DriverStatus::Bound,
+                    PciDeviceStatus::Failed(error) => DriverStatus::Failed(error),
+                },
+            })
+            .collect()
+    }
+
+    fn block_devices(&self) -> Vec<BlockDeviceInfo> {
+        BlockDevices::disks()
+            .into_iter()
+            .map(|(number, id, device)| {
+                let (sector_size, sector_count) = {
+                    let device = device.read();
+                    (device.sector_size(), device.sector_count())
+                };
+                BlockDeviceInfo {
+                    number,
+                    device_id: id.as_u64(),
+                    sector_size,
+                    sector_count,
+                    partitions: BlockDevices::partitions(number)
+                        .into_iter()
+                        .map(|partition| PartitionInfo {
+                            number: partition.number,
+                            start: partition.start,
+                            sector_count: partition.sector_count,
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    fn framebuffers(&self) -> Vec<FramebufferInfo> {
+        Framebuffers::all()
+            .into_iter()
+            .map(|fb| FramebufferInfo {
+                number: fb.number,
+                device_id: fb.id.as_u64(),
+                width: fb.info.width,
+                height: fb.info.height,
+                stride: fb.info.pitch,
+                bits_per_pixel: fb.info.bpp,
+            })
+            .collect()
+    }
+}
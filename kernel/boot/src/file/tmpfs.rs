@@ -0,0 +1,40 @@
+use core::ptr::NonNull;
+
+use kernel_tmpfs::PageAllocator;
+use x86_64::structures::paging::{PageSize, PhysFrame, Size4KiB};
+use x86_64::{PhysAddr, VirtAddr};
+
+use crate::limine::HHDM_REQUEST;
+use crate::mem::phys::PhysicalMemory;
+use crate::{U64Ext, UsizeExt};
+
+/// The size limit of a tmpfs, such as the one at `/tmp`.
+pub const TMPFS_SIZE: usize = 64 * 1024 * 1024;
+
+/// Backs tmpfs pages with physical frames, accessed through the HHDM.
+pub struct FramePageAllocator;
+
+impl FramePageAllocator {
+    fn hhdm_offset() -> u64 {
+        HHDM_REQUEST
+            .get_response()
+            .expect("should have a HHDM response")
+            .offset()
+    }
+}
+
+impl PageAllocator for FramePageAllocator {
+    fn allocate(&self) -> Option<NonNull<u8>> {
+        let frame = PhysicalMemory::allocate_frame::<Size4KiB>()?;
+        let addr = VirtAddr::new(frame.start_address().as_u64() + Self::hhdm_offset());
+        let page = NonNull::new(addr.as_mut_ptr::<u8>())?;
+        // SAFETY: the frame was just allocated and is mapped through the HHDM
+        unsafe { page.write_bytes(0, Size4KiB::SIZE.into_usize()) };
+        Some(page)
+    }
+
+    unsafe fn deallocate(&self, page: NonNull<u8>) {
+        let phys = PhysAddr::new(page.addr().get().into_u64() - Self::hhdm_offset());
+        PhysicalMemory::deallocate_frame(PhysFrame::<Size4KiB>::containing_address(phys));
+    }
+}
@@ -7,7 +7,7 @@ use ::limine::firmware_type::FirmwareType;
 use conquer_once::spin::OnceCell;
 use tracing::{Level, info, span};
 
-use crate::driver::pci;
+use crate::driver::{input, limine_fb, loopback, pci, ps2};
 use crate::limine::{BOOT_TIME, EXECUTABLE_CMDLINE_REQUEST, FIRMWARE_TYPE_REQUEST};
 
 mod acpi;
@@ -15,6 +15,7 @@ mod apic;
 mod arch;
 pub mod backtrace;
 pub mod cmdline;
+pub mod console;
 pub mod driver;
 pub mod file;
 pub mod hpet;
@@ -22,10 +23,12 @@ pub mod limine;
 mod log;
 pub mod mcore;
 pub mod mem;
+mod ring;
 pub mod serial;
 pub mod sse;
 pub mod syscall;
 pub mod time;
+pub mod tty;
 
 static BOOT_TIME_SECONDS: OnceCell<u64> = OnceCell::uninit();
 
@@ -39,6 +42,7 @@ pub fn init() {
     init_boot_time();
 
     cmdline::init();
+    console::init();
     log::init();
     log_boot_environment();
     mem::init();
@@ -49,8 +53,14 @@ pub fn init() {
     span!(Level::DEBUG, "kinit2").in_scope(|| {
         backtrace::init();
         mcore::init();
+        tty::init();
         file::init();
+        input::init();
+        ps2::init();
         pci::init();
+        limine_fb::init();
+        loopback::init();
+        tty::pty::init();
     });
 
     info!("kernel initialized");
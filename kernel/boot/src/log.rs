@@ -6,7 +6,7 @@ use x86_64::instructions::interrupts;
 use crate::hpet::hpet_maybe;
 use crate::limine::EXECUTABLE_CMDLINE_REQUEST;
 use crate::mcore::context::ExecutionContext;
-use crate::serial;
+use crate::{console, serial};
 
 pub(crate) fn init() {
     let text = EXECUTABLE_CMDLINE_REQUEST
@@ -25,7 +25,8 @@ pub(crate) fn init() {
 }
 
 /// The kernel services behind the `kernel_log` subscriber: HPET time, CPU
-/// identity, interrupt-safe critical sections, and the serial port sink.
+/// identity, interrupt-safe critical sections, and the serial port sink,
+/// which the framebuffer console mirrors during boot.
 struct KernelEnvironment;
 
 impl Environment for KernelEnvironment {
@@ -39,7 +40,12 @@ impl Environment for KernelEnvironment {
     }
 
     fn with_sink(f: impl FnOnce(&mut dyn Write)) {
-        serial::with_serial(|serial| f(serial));
+        serial::with_serial(|serial| {
+            console::with_log(|console| match console {
+                Some(console) => f(&mut Tee(serial, console)),
+                None => f(serial),
+            });
+        });
     }
 
     fn write_flow_label(out: &mut dyn Write) {
@@ -55,3 +61,13 @@ impl Environment for KernelEnvironment {
         Some(f(&mut ctx.current_task().span_stack().lock()))
     }
 }
+
+/// Writes to both of its writers.
+struct Tee<'a>(&'a mut dyn Write, &'a mut dyn Write);
+
+impl Write for Tee<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0.write_str(s)?;
+        self.1.write_str(s)
+    }
+}
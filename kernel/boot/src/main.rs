@@ -1,16 +1,16 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
+use alloc::string::String;
+
 use kernel::cmdline::cmdline;
-use kernel::driver::block::BlockDevices;
-use kernel::file::ext2::VirtualExt2Fs;
-use kernel::file::vfs;
 use kernel::limine::BASE_REVISION;
-use kernel::mcore;
 use kernel::mcore::mtask::process::Process;
-use kernel_ext2::Ext2Fs;
-use kernel_vfs::path::{AbsolutePath, ROOT};
-use tracing::{Level, span};
+use kernel::{console, file, mcore, serial};
+use kernel_vfs::path::AbsolutePath;
+use tracing::{Level, error, span};
 
 #[unsafe(export_name = "kernel_main")]
 unsafe extern "C" fn main() -> ! {
@@ -19,25 +19,28 @@ unsafe extern "C" fn main() -> ! {
     kernel::init();
 
     span!(Level::INFO, "mounting root filesystem").in_scope(|| {
-        let root_block_device = BlockDevices::by_id(0).expect("should have block device with id 0");
-        vfs()
-            .write()
-            .mount(
-                ROOT,
-                VirtualExt2Fs::from(
-                    Ext2Fs::try_new(root_block_device).expect("should be able to create ext2fs"),
-                ),
-            )
-            .expect("should be able to mount ext2fs at /");
+        if let Err(e) = file::root::mount_root() {
+            error!("can't mount the root file system: {e}");
+            file::root::log_block_devices();
+            halt();
+        }
+        file::boot::mount_boot();
     });
 
     span!(Level::INFO, "starting init process").in_scope(|| {
-        let init = cmdline()
-            .init()
-            .expect("should have init argument on cmdline");
-        let path = AbsolutePath::try_new(init).expect("init path should be absolute");
-        Process::create_from_executable(Process::root(), path)
-            .expect("should be able to create process from executable");
+        let Some(init) = cmdline().init() else {
+            error!("no init= on the kernel cmdline");
+            halt();
+        };
+        let Ok(path) = AbsolutePath::try_new(init) else {
+            error!("init={init} is not an absolute path");
+            halt();
+        };
+        let args = cmdline().init_args().map(String::from).collect();
+        if let Err(e) = Process::create_from_executable_with_args(Process::root(), path, args) {
+            error!("can't start {path}: {e}");
+            halt();
+        }
     });
 
     // TODO: start this from init through some kind of "autostart"
@@ -45,9 +48,21 @@ unsafe extern "C" fn main() -> ! {
     Process::create_from_executable(Process::root(), path)
         .expect("should be able to create process from executable");
 
+    // the screen belongs to the console terminal from now on
+    console::set_log_mirror(false);
+
     mcore::exit_bootstrap()
 }
 
+/// Stops the boot after an error that was already logged, without the noise
+/// of a panic.
+fn halt() -> ! {
+    x86_64::instructions::interrupts::disable();
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
 #[panic_handler]
 #[cfg(not(test))]
 fn rust_panic(info: &core::panic::PanicInfo) -> ! {
@@ -61,6 +76,11 @@ fn rust_panic(info: &core::panic::PanicInfo) -> ! {
 fn handle_panic(info: &core::panic::PanicInfo) {
     use tracing::error;
 
+    // the panic may have struck in the middle of a log record, with the
+    // serial port or the console locked
+    // Safety: this is the panic handler, which never returns
+    unsafe { serial::force_unlock() };
+    console::mirror_panic();
     if let Some(location) = info.location() {
         error!(
             "kernel panicked at {}:{}:{}:",
@@ -13,16 +13,16 @@ use x86_64::registers::segmentation::Segment;
 use x86_64::structures::paging::PhysFrame;
 use x86_64::{PhysAddr, VirtAddr};
 
-use crate::apic::io_apic;
+use crate::apic::{io_apic, route_isa_irq};
 use crate::arch::gdt::create_gdt_and_tss;
-use crate::arch::idt::create_idt;
+use crate::arch::idt::{InterruptIndex, create_idt};
 use crate::limine::MP_REQUEST;
 use crate::mcore::context::ExecutionContext;
 use crate::mcore::mtask::process::Process;
 use crate::mcore::mtask::scheduler::cleanup::TaskCleanup;
 use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
 use crate::mcore::mtask::task::Task;
-use crate::sse;
+use crate::{serial, sse};
 
 pub mod context;
 mod lapic;
@@ -58,6 +58,7 @@ pub fn init() {
 
     // then call the `cpu_init` function on the bootstrap CPU
     unsafe { cpu_init_and_return(resp.cpus()[0]) };
+    init_interrupts();
     interrupts::enable();
 
     install_idle_task();
@@ -100,8 +101,6 @@ unsafe extern "C" fn cpu_init_and_return(cpu: &limine::mp::Cpu) {
     }
 
     sse::init();
-
-    init_interrupts();
 }
 
 unsafe extern "C" fn cpu_init(cpu: &limine::mp::Cpu) -> ! {
@@ -134,23 +133,19 @@ pub fn exit_bootstrap() -> ! {
     Task::exit_current()
 }
 
+/// Programs the IO APIC, which is shared by all CPUs, so only the bootstrap
+/// CPU calls this. Device interrupts are delivered to the calling CPU.
 fn init_interrupts() {
-    let mut io_apic = io_apic().lock();
+    /// ISA IRQ of the first serial port.
+    const COM1_IRQ: u8 = 4;
+
     unsafe {
         const OFFSET: u8 = 32;
-        io_apic.init(OFFSET);
-
-        // TODO: redirect interrupt vectors
-
-        // for vector in 0..u8::MAX - OFFSET {
-        //     let mut entry = RedirectionTableEntry::default();
-        //     entry.set_mode(IrqMode::Fixed);
-        //     entry.set_flags(IrqFlags::LEVEL_TRIGGERED | IrqFlags::LOW_ACTIVE);
-        //     entry.set_vector(vector);
-        //     entry.set_dest(u8::try_from(lapic_id).expect("invalid lapic id"));
-        //
-        //     io_apic.set_table_entry(vector, entry);
-        //     io_apic.enable_irq(vector);
-        // }
+        io_apic().lock().init(OFFSET);
     }
+    route_isa_irq(COM1_IRQ, InterruptIndex::Serial);
+
+    // Input that arrived before the IRQ was unmasked raised no edge that
+    // the IO APIC could see, and would hold the line until it is read.
+    serial::receive_pending();
 }
@@ -3,6 +3,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::alloc::Layout;
 
+use kernel_abi::MountFlags;
 use kernel_elfloader::{
     ElfFile, ElfHeader, ElfParseError, ElfType, ProgramHeaderFlags, ProgramHeaderType,
 };
@@ -24,6 +25,8 @@ use crate::{U64Ext, UsizeExt};
 
 #[derive(Debug, Error)]
 pub enum LoadExecutableError {
+    #[error("the executable is on a noexec mount")]
+    NoExec,
     #[error("failed to stat the executable")]
     Stat,
     #[error("failed to read the executable")]
@@ -90,6 +93,13 @@ struct ValidatedTls {
 
 /// Runs every static check on the executable without touching the process.
 pub fn validate(node: &VfsNode) -> Result<ValidatedExecutable, LoadExecutableError> {
+    if node
+        .mount()
+        .is_some_and(|mount| mount.flags().contains(MountFlags::NOEXEC))
+    {
+        return Err(LoadExecutableError::NoExec);
+    }
+
     let stat = {
         let mut stat = Stat::default();
         node.stat(&mut stat)
@@ -52,6 +52,12 @@ impl MemoryRegions {
         })
     }
 
+    /// Returns the regions as they are right now, in the order that they
+    /// were added.
+    pub fn snapshot(&self) -> Vec<Arc<MemoryRegion>> {
+        interrupts::without_interrupts(|| self.regions.lock().clone())
+    }
+
     pub fn is_memory_region_at_address(&self, addr: VirtAddr) -> bool {
         interrupts::without_interrupts(|| self.regions.lock().iter().any(|r| r.contains(addr)))
     }
@@ -163,6 +169,27 @@ impl MemoryRegion {
         }
     }
 
+    /// The file that backs the region, if any.
+    pub fn node(&self) -> Option<&VfsNode> {
+        match self {
+            MemoryRegion::Lazy(_) | MemoryRegion::Mapped(_) => None,
+            MemoryRegion::FileBacked(file_backed_memory_region) => {
+                Some(&file_backed_memory_region.node)
+            }
+            MemoryRegion::Shared(shared_memory_region) => Some(&shared_memory_region.node),
+        }
+    }
+
+    /// The offset into [`MemoryRegion::node`] that the region starts at.
+    pub fn file_offset(&self) -> usize {
+        match self {
+            MemoryRegion::FileBacked(file_backed_memory_region) => {
+                file_backed_memory_region.file_offset
+            }
+            MemoryRegion::Lazy(_) | MemoryRegion::Mapped(_) | MemoryRegion::Shared(_) => 0,
+        }
+    }
+
     pub fn contains(&self, addr: VirtAddr) -> bool {
         self.addr() <= addr && self.addr() + self.size().into_u64() > addr
     }
@@ -335,18 +362,18 @@ impl FileBackedMemoryRegion {
     }
 }
 
-/// Owns the virtual reservation for a device-backed shared mapping and keeps
-/// the backing device file open.
+/// Owns the virtual reservation for a file-backed shared mapping, such as a
+/// framebuffer or a tmpfs file, and keeps the backing file open.
 ///
-/// It deliberately holds no [`OwnedPhysicalMemory`]. The device owns those
-/// frames, so dropping this region must release only the virtual range and
-/// never the frames. Deallocating them would hand live device memory back to
-/// the frame allocator and corrupt the device.
+/// It deliberately holds no [`OwnedPhysicalMemory`]. The device or file system
+/// owns those frames, so dropping this region must release only the virtual
+/// range and never the frames. Deallocating them would hand live memory back
+/// to the frame allocator while the file still uses it.
 #[derive(Debug)]
 pub struct SharedMemoryRegion {
     segment: OwnedSegment<'static>,
     size: usize,
-    _node: VfsNode,
+    node: VfsNode,
 }
 
 impl SharedMemoryRegion {
@@ -354,7 +381,7 @@ impl SharedMemoryRegion {
         Self {
             segment,
             size,
-            _node: node,
+            node,
         }
     }
 }
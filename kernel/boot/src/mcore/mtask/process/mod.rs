@@ -8,15 +8,15 @@ use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::ffi::c_void;
 use core::fmt::{Debug, Formatter};
+use core::iter;
 use core::ops::{Deref, DerefMut};
-use core::ptr;
 use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use conquer_once::spin::OnceCell;
-use kernel_abi::ProcessId;
+use kernel_abi::{ProcessId, Signal};
 use kernel_memapi::{Guarded, Location, MemoryApi, UserAccessible};
 use kernel_syscall::exec::build_initial_stack;
-use kernel_syscall::signal::SignalState;
+use kernel_syscall::signal::{Disposition, SignalState};
 use kernel_vfs::OpenError;
 use kernel_vfs::node::VfsNode;
 use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
@@ -24,7 +24,7 @@ use kernel_virtual_memory::VirtualMemoryManager;
 use spin::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use thiserror::Error;
 use tracing::field::Empty;
-use tracing::{Level, Span, debug, instrument};
+use tracing::{Level, Span, debug, info, instrument};
 use x86_64::VirtAddr;
 use x86_64::registers::control::{Cr0, Cr0Flags};
 use x86_64::registers::rflags::RFlags;
@@ -45,6 +45,7 @@ use crate::mcore::mtask::wait::{
 use crate::mem::address_space::AddressSpace;
 use crate::mem::memapi::{LowerHalfAllocation, LowerHalfMemoryApi, Writable};
 use crate::mem::virt::VirtualMemoryAllocator;
+use crate::tty::{self, Tty};
 use crate::{U64Ext, UsizeExt};
 
 pub(crate) mod elf;
@@ -105,7 +106,7 @@ pub type SignalsWriteGuard<'a> = RwLockWriteGuard<'a, Signals>;
 #[derive(Debug, Copy, Clone)]
 pub enum ExitOutcome {
     Exited(usize),
-    Signaled(kernel_abi::Signal),
+    Signaled(Signal),
 }
 
 #[must_use]
@@ -138,6 +139,17 @@ impl SoleLiveTask<'_> {
     }
 }
 
+/// The process group and session of a process, which `setpgid` and `setsid`
+/// change.
+#[derive(Clone)]
+struct JobControl {
+    process_group_id: ProcessId,
+    session_id: ProcessId,
+    /// What `/dev/tty` and the standard streams open. The root process and
+    /// new session leaders have none.
+    controlling_terminal: Option<Arc<Tty>>,
+}
+
 pub struct Process {
     pid: ProcessId,
     name: String,
@@ -145,6 +157,8 @@ pub struct Process {
     ppid: RwLock<ProcessId>,
 
     executable_path: RwLock<Option<AbsoluteOwnedPath>>,
+    /// The arguments that the current image was started with.
+    cmdline: RwLock<Vec<Vec<u8>>>,
     executable_segments: RwLock<Vec<LowerHalfAllocation<Writable>>>,
     current_working_directory: RwLock<AbsoluteOwnedPath>,
 
@@ -170,6 +184,7 @@ pub struct Process {
     reap_active: AtomicBool,
 
     file_descriptors: RwLock<BTreeMap<FdNum, FileDescriptor>>,
+    job_control: RwLock<JobControl>,
 
     exit_outcome: OnceCell<ExitOutcome>,
 }
@@ -183,6 +198,7 @@ impl Process {
                 name: "root".to_string(),
                 ppid: RwLock::new(pid),
                 executable_path: RwLock::new(None),
+                cmdline: RwLock::new(vec![]),
                 executable_segments: RwLock::new(vec![]),
                 current_working_directory: RwLock::new(ROOT.to_owned()),
                 address_space: None,
@@ -200,6 +216,11 @@ impl Process {
                 }),
                 reap_active: AtomicBool::new(false),
                 file_descriptors: RwLock::new(BTreeMap::new()),
+                job_control: RwLock::new(JobControl {
+                    process_group_id: pid,
+                    session_id: pid,
+                    controlling_terminal: None,
+                }),
                 exit_outcome: OnceCell::uninit(),
             });
             process_tree().write().processes.insert(pid, root.clone());
@@ -215,12 +236,28 @@ impl Process {
         let pid = new_process_id();
         let parent_pid = parent.pid;
         let address_space = AddressSpace::new();
+        let job_control = if Arc::ptr_eq(parent, Process::root()) {
+            // The kernel starts processes like a shell starts jobs: each in
+            // a process group of its own, in the kernel's session on the
+            // serial terminal. The first of them that runs while no other
+            // one does gets the terminal's input.
+            let tty = tty::serial().clone();
+            tty.claim_foreground(pid);
+            JobControl {
+                process_group_id: pid,
+                session_id: parent.pid,
+                controlling_terminal: Some(tty),
+            }
+        } else {
+            parent.job_control.read().clone()
+        };
 
         let process = Self {
             pid,
             name,
             ppid: RwLock::new(parent_pid),
             executable_path: RwLock::new(executable_path.map(|x| x.as_ref().to_owned())),
+            cmdline: RwLock::new(vec![]),
             executable_segments: RwLock::new(vec![]),
             current_working_directory: RwLock::new(parent.current_working_directory.read().clone()),
             address_space: Some(address_space),
@@ -242,6 +279,7 @@ impl Process {
             }),
             reap_active: AtomicBool::new(false),
             file_descriptors: RwLock::new(BTreeMap::new()),
+            job_control: RwLock::new(job_control),
             exit_outcome: OnceCell::uninit(),
         };
 
@@ -250,14 +288,24 @@ impl Process {
         res
     }
 
+    pub fn create_unscheduled(
+        parent: &Arc<Process>,
+        path: impl AsRef<AbsolutePath>,
+    ) -> Result<(Arc<Self>, Task), CreateProcessError> {
+        Self::create_unscheduled_with_args(parent, path, Vec::new())
+    }
+
+    /// Like [`Process::create_unscheduled`], but passes `args` to the
+    /// executable after `argv[0]`, which is always the executable path.
     #[instrument(
         level = Level::INFO,
         skip_all,
         fields(path = %path.as_ref(), ppid = %parent.pid(), pid = Empty)
     )]
-    pub fn create_unscheduled(
+    pub fn create_unscheduled_with_args(
         parent: &Arc<Process>,
         path: impl AsRef<AbsolutePath>,
+        args: Vec<String>,
     ) -> Result<(Arc<Self>, Task), CreateProcessError> {
         let path = path.as_ref();
         let node = vfs()
@@ -269,7 +317,11 @@ impl Process {
         let process = Self::create_new(parent, path.to_string(), Some(path));
         Span::current().record("pid", process.pid.as_u64());
 
-        let kstack = HigherHalfStack::allocate(16, trampoline, ptr::null_mut(), Task::exit)?;
+        // the trampoline takes the arguments back out of the box
+        let args = Box::into_raw(Box::new(args));
+        let kstack = HigherHalfStack::allocate(16, trampoline, args.cast(), Task::exit)
+            // SAFETY: the task never runs, so nothing else owns the box
+            .inspect_err(|_| drop(unsafe { Box::from_raw(args) }))?;
         let main_task = Task::create_with_stack(&process, kstack);
 
         Ok((process, main_task))
@@ -281,7 +333,17 @@ impl Process {
         parent: &Arc<Process>,
         path: impl AsRef<AbsolutePath>,
     ) -> Result<Arc<Self>, CreateProcessError> {
-        let (process, main_task) = Self::create_unscheduled(parent, path)?;
+        Self::create_from_executable_with_args(parent, path, Vec::new())
+    }
+
+    /// Like [`Process::create_from_executable`], but passes `args` to the
+    /// executable after `argv[0]`.
+    pub fn create_from_executable_with_args(
+        parent: &Arc<Process>,
+        path: impl AsRef<AbsolutePath>,
+        args: Vec<String>,
+    ) -> Result<Arc<Self>, CreateProcessError> {
+        let (process, main_task) = Self::create_unscheduled_with_args(parent, path, args)?;
         GlobalTaskQueue::enqueue(Box::pin(main_task));
 
         Ok(process)
@@ -303,6 +365,36 @@ impl Process {
         &self.file_descriptors
     }
 
+    pub fn process_group_id(&self) -> ProcessId {
+        self.job_control.read().process_group_id
+    }
+
+    pub fn set_process_group_id(&self, pgid: ProcessId) {
+        self.job_control.write().process_group_id = pgid;
+    }
+
+    pub fn session_id(&self) -> ProcessId {
+        self.job_control.read().session_id
+    }
+
+    pub fn controlling_terminal(&self) -> Option<Arc<Tty>> {
+        self.job_control.read().controlling_terminal.clone()
+    }
+
+    pub fn set_controlling_terminal(&self, tty: Arc<Tty>) {
+        self.job_control.write().controlling_terminal = Some(tty);
+    }
+
+    /// Makes this process the leader of a new session and process group,
+    /// which loses the controlling terminal.
+    pub fn create_session(&self) {
+        *self.job_control.write() = JobControl {
+            process_group_id: self.pid,
+            session_id: self.pid,
+            controlling_terminal: None,
+        };
+    }
+
     pub fn address_space(&self) -> &AddressSpace {
         self.address_space
             .as_ref()
@@ -334,6 +426,16 @@ impl Process {
         *self.executable_path.write() = Some(path);
     }
 
+    /// The arguments that the current image was started with, starting
+    /// with `argv[0]`. This is empty for the root process.
+    pub fn cmdline(&self) -> Vec<Vec<u8>> {
+        self.cmdline.read().clone()
+    }
+
+    pub(crate) fn set_cmdline(&self, argv: &[&[u8]]) {
+        *self.cmdline.write() = argv.iter().map(|arg| arg.to_vec()).collect();
+    }
+
     pub fn telemetry(&self) -> &Telemetry {
         &self.telemetry
     }
@@ -354,6 +456,36 @@ impl Process {
         self.signals.try_write()
     }
 
+    /// Generates `signo` for this process: records it as pending, resumes a
+    /// stopped process on `Continue` or `Kill` and wakes interruptible
+    /// sleepers so that they see the signal.
+    pub fn deliver_signal(&self, signo: Signal) {
+        let pid = self.pid;
+        let mut guard = self.signals_write();
+        let effect = guard.deliver(signo);
+        // Log stop and terminate outcomes at generation time. The victim may
+        // consume the signal at a timer tick, and the timer handler must not
+        // touch the serial lock, so this is the only safe place to record it.
+        // A blocked default-terminate signal logs early while it stays
+        // pending, which is acceptable for a single-user kernel.
+        match guard.disposition(signo) {
+            Disposition::DefaultStop => {
+                info!("stopping process {pid} on signal {}", signo.name());
+            }
+            Disposition::DefaultTerminate => {
+                info!("terminating process on signal {} (pid {pid})", signo.name());
+                self.set_exit_outcome(ExitOutcome::Signaled(signo));
+            }
+            Disposition::Ignore | Disposition::Handler(_) => {}
+        }
+        if effect.resume_tasks {
+            info!("continuing process {pid}");
+            guard.resume_stopped_tasks();
+        }
+        // Spurious wakes are by design, the woken task re-checks its condition.
+        self.wake_interruptible();
+    }
+
     fn register_interruptible_waker(&self, tid: TaskId, waker: TaskWaker) {
         self.interruptible_wakers.lock().push((tid, waker));
     }
@@ -377,6 +509,18 @@ impl Process {
     pub fn park_current_task(
         &self,
         deadline_ns: Option<u64>,
+        should_wake: impl FnMut() -> bool,
+    ) -> ParkOutcome {
+        self.park_current_task_on(deadline_ns, |_| {}, should_wake)
+    }
+
+    /// Like [`Process::park_current_task`], but hands a waker for every park
+    /// to `register`, so that an event source like a
+    /// [`WaitQueue`](crate::mcore::mtask::wait::WaitQueue) can wake the task.
+    pub fn park_current_task_on(
+        &self,
+        deadline_ns: Option<u64>,
+        mut register: impl FnMut(TaskWaker),
         mut should_wake: impl FnMut() -> bool,
     ) -> ParkOutcome {
         let ctx = ExecutionContext::load();
@@ -400,6 +544,7 @@ impl Process {
                 sleep_until(deadline_ns, waker.clone());
             }
             self.register_interruptible_waker(tid, waker.clone());
+            register(waker.clone());
             if should_wake() || self.reap_requested_for(tid) {
                 wake(&waker);
             }
@@ -550,7 +695,10 @@ const INITIAL_FX_IMAGE: [u8; 512] = {
     image
 };
 
-extern "C" fn trampoline(_arg: *mut c_void) {
+extern "C" fn trampoline(arg: *mut c_void) {
+    // SAFETY: `create_unscheduled_with_args` passes a boxed `Vec<String>`,
+    // and the trampoline runs once per task
+    let args = unsafe { Box::from_raw(arg.cast::<Vec<String>>()) };
     let ctx = ExecutionContext::load();
     let current_task = ctx.scheduler().current_task();
     let current_process = current_task.process().clone();
@@ -567,48 +715,36 @@ extern "C" fn trampoline(_arg: *mut c_void) {
     {
         let mut guard = current_process.file_descriptors.write();
 
-        let devnull = vfs()
+        let tty = vfs()
             .read()
-            .open(AbsolutePath::try_new("/dev/null").unwrap())
-            .expect("should be able to open /dev/null");
-        let devnull_ofd = Arc::new(OpenFileDescription::from(devnull));
-        guard.insert(
-            0.into(),
-            FileDescriptor::new(0.into(), FileDescriptorFlags::empty(), devnull_ofd.clone()),
-        );
-
-        let devserial = vfs()
-            .read()
-            .open(AbsolutePath::try_new("/dev/serial").unwrap())
-            .expect("should be able to open /dev/serial");
-        let devserial_ofd = Arc::new(OpenFileDescription::from(devserial));
-        guard.insert(
-            1.into(),
-            FileDescriptor::new(
-                1.into(),
-                FileDescriptorFlags::empty(),
-                devserial_ofd.clone(),
-            ),
-        );
-        guard.insert(
-            2.into(),
-            FileDescriptor::new(
-                2.into(),
-                FileDescriptorFlags::empty(),
-                devserial_ofd.clone(),
-            ),
-        );
+            .open(AbsolutePath::try_new("/dev/tty").unwrap())
+            .expect("should be able to open the controlling terminal");
+        let tty_ofd = Arc::new(OpenFileDescription::from(tty));
+        for fd in 0..3 {
+            guard.insert(
+                fd.into(),
+                FileDescriptor::new(fd.into(), FileDescriptorFlags::empty(), tty_ofd.clone()),
+            );
+        }
     }
 
+    let argv = iter::once(executable_path.as_str())
+        .chain(args.iter().map(String::as_str))
+        .map(str::as_bytes)
+        .collect::<Vec<_>>();
+    current_process.set_cmdline(&argv);
     let (entry, rsp) = setup_user_image(
         &current_process,
         current_task,
         &validated,
         &node,
-        &[executable_path.as_str().as_bytes()],
+        &argv,
         &[],
     )
     .expect("should be able to load executable");
+    // `iretq` never returns, so nothing would drop these otherwise
+    drop(argv);
+    drop(args);
 
     let sel = ctx.selectors();
 
@@ -1,6 +1,7 @@
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use kernel_park::{ParkTicket, ParkingLot, Reservation, UnparkTicket, Waker};
 use spin::Mutex;
@@ -92,3 +93,55 @@ pub fn wake_expired_sleepers() {
         }
     }
 }
+
+/// Tasks waiting for an event that an interrupt handler may signal, such as
+/// a device receiving data.
+///
+/// Waiters snapshot [`WaitQueue::generation`] before checking their
+/// condition and consider themselves woken once it changes, so a wakeup
+/// between the check and the registration is never lost.
+pub struct WaitQueue {
+    generation: AtomicU64,
+    wakers: Mutex<Vec<TaskWaker>>,
+}
+
+impl WaitQueue {
+    pub const fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Task context. Drops wakers that already fired, so tasks that were
+    /// woken by something else don't pile up.
+    pub fn register(&self, waker: TaskWaker) {
+        interrupts::without_interrupts(|| {
+            let mut wakers = self.wakers.lock();
+            wakers.retain(|w| !w.is_spent());
+            wakers.push(waker);
+        });
+    }
+
+    /// Wakes every registered task. Never allocates, so this is legal in
+    /// interrupt context.
+    pub fn wake_all(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        interrupts::without_interrupts(|| {
+            let mut wakers = self.wakers.lock();
+            for waker in wakers.drain(..) {
+                wake(&waker);
+            }
+        });
+    }
+}
+
+impl Default for WaitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -109,7 +109,12 @@ fn make_mapping_recursive() -> (VirtAddr, PhysFrame) {
         .unwrap()
         .entries()
         .iter()
-        .filter(|e| e.entry_type == EntryType::BOOTLOADER_RECLAIMABLE)
+        // the framebuffer keeps its address, since the console draws on it
+        // from before the switch
+        .filter(|e| {
+            e.entry_type == EntryType::BOOTLOADER_RECLAIMABLE
+                || e.entry_type == EntryType::FRAMEBUFFER
+        })
         .for_each(|e| {
             remap(
                 &mut current_pt,
@@ -189,6 +189,30 @@ impl PhysicalMemory {
     {
         allocator().lock().deallocate_frames(range);
     }
+
+    /// Returns the number of usable and of free 4 KiB frames.
+    ///
+    /// Returns `None` while the stage 1 allocator is active, because it
+    /// doesn't track which frames are free.
+    ///
+    /// Acquires the allocator's spinlock, so do not call with interrupts disabled.
+    #[must_use]
+    pub fn frame_counts() -> Option<FrameCounts> {
+        match &*allocator().lock() {
+            MultiStageAllocator::Stage1(_) => None,
+            MultiStageAllocator::Stage2(a) => Some(FrameCounts {
+                usable: a.usable_frames(),
+                free: a.free_frames(),
+            }),
+        }
+    }
+}
+
+/// The numbers of 4 KiB frames in [`PhysicalMemory::frame_counts`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FrameCounts {
+    pub usable: usize,
+    pub free: usize,
 }
 
 unsafe impl x86_64::structures::paging::FrameAllocator<Size4KiB> for PhysicalMemory {
@@ -0,0 +1,39 @@
+/// How many bytes a [`RxRing`] holds.
+const RX_RING_SIZE: usize = 4096;
+
+/// Bytes that a device received in an interrupt handler and that weren't
+/// read yet.
+pub struct RxRing {
+    buf: [u8; RX_RING_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl RxRing {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; RX_RING_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Drops the byte if the ring is full, the reader is too slow anyway.
+    pub fn push(&mut self, byte: u8) {
+        if self.len == RX_RING_SIZE {
+            return;
+        }
+        self.buf[(self.head + self.len) % RX_RING_SIZE] = byte;
+        self.len += 1;
+    }
+
+    pub fn pop_into(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.len);
+        for dst in &mut buf[..n] {
+            *dst = self.buf[self.head];
+            self.head = (self.head + 1) % RX_RING_SIZE;
+        }
+        self.len -= n;
+        n
+    }
+}
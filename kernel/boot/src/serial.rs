@@ -1,6 +1,10 @@
 use conquer_once::spin::Lazy;
 use spin::Mutex;
 use uart_16550::SerialPort;
+use x86_64::instructions::interrupts;
+
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::ring::RxRing;
 
 static SERIAL1: Lazy<Mutex<SerialPort>> = Lazy::new(|| {
     let mut serial_port = unsafe { SerialPort::new(0x3F8) };
@@ -8,16 +12,66 @@ static SERIAL1: Lazy<Mutex<SerialPort>> = Lazy::new(|| {
     Mutex::new(serial_port)
 });
 
+/// The serial terminal waits here for bytes to arrive.
+pub static RECEIVE_WAITERS: WaitQueue = WaitQueue::new();
+
+/// Bytes that arrived on the serial line but weren't read yet.
+static RECEIVED: Mutex<RxRing> = Mutex::new(RxRing::new());
+
 /// Runs `f` while holding the serial lock with interrupts disabled.
 ///
 /// One lock acquisition covers a whole log record so that no deadlock can occur
 /// when we want to print something in an interrupt handler.
 pub(crate) fn with_serial<R>(f: impl FnOnce(&mut SerialPort) -> R) -> R {
-    use x86_64::instructions::interrupts;
-
     interrupts::without_interrupts(|| f(&mut SERIAL1.lock()))
 }
 
+/// Releases the serial lock for the panic handler, which may run while it
+/// is held, for example when drawing a log record on the console panicked.
+/// Output of other CPUs can interleave with the panic message from then on.
+///
+/// # Safety
+/// Only the panic handler may call this, since it never returns to the
+/// code that held the lock.
+pub unsafe fn force_unlock() {
+    if SERIAL1.is_locked() {
+        // Safety: the caller guarantees that the holder never resumes
+        unsafe { SERIAL1.force_unlock() };
+    }
+}
+
+/// Moves everything the UART has received into the ring and wakes the
+/// serial terminal.
+/// Called from the COM1 interrupt handler and once when the IRQ gets routed.
+pub(crate) fn receive_pending() {
+    let mut received = false;
+    with_serial(|serial| {
+        let mut ring = RECEIVED.lock();
+        while let Ok(byte) = serial.try_receive() {
+            ring.push(byte);
+            received = true;
+        }
+    });
+    if received {
+        RECEIVE_WAITERS.wake_all();
+    }
+}
+
+/// Moves received bytes into `buf` without blocking and returns how many
+/// there were.
+pub fn read_received(buf: &mut [u8]) -> usize {
+    interrupts::without_interrupts(|| RECEIVED.lock().pop_into(buf))
+}
+
+/// Sends `bytes` as they are, without any translation.
+pub fn write_bytes(bytes: &[u8]) {
+    with_serial(|serial| {
+        for &byte in bytes {
+            serial.send_raw(byte);
+        }
+    });
+}
+
 #[doc(hidden)]
 pub fn internal_print(args: core::fmt::Arguments) {
     use core::fmt::Write;
@@ -1,29 +1,39 @@
+use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::sync::atomic::Ordering::Relaxed;
 
 use kernel_abi::{
-    EBADF, EINVAL, EIO, ENODEV, ENOMEM, ENOTTY, Errno, IoctlRequest, ProtFlags, Stat,
+    EAGAIN, EBADF, EBUSY, EEXIST, EINTR, EINVAL, EIO, EISDIR, ELOOP, ENODEV, ENOENT, ENOMEM,
+    ENOSPC, ENOTDIR, ENOTEMPTY, ENOTTY, EPERM, EROFS, Errno, IoctlRequest, MountFlags, PollEvents,
+    ProtFlags, Stat,
 };
-use kernel_syscall::access::{CwdAccess, FileAccess};
+use kernel_syscall::access::{CwdAccess, FileAccess, MountAccess};
+use kernel_vfs::fs::FsNodeKind;
 use kernel_vfs::node::VfsNode;
-use kernel_vfs::path::AbsolutePath;
-use kernel_vfs::{FsyncError, IoctlError, MmapError, Stat as VfsStat};
+use kernel_vfs::path::{AbsolutePath, OwnedPath};
+use kernel_vfs::{
+    CreateError, FsyncError, IoctlError, MmapError, MountError, OpenError, ReadError,
+    ReadlinkError, Stat as VfsStat, UnmountError, WriteError,
+};
 use spin::rwlock::RwLock;
 use x86_64::VirtAddr;
 use x86_64::structures::paging::{PageSize, PageTableFlags, PhysFrame, Size4KiB};
 
-use crate::file::{OpenFileDescription, vfs};
+use crate::file::{self, INPUT_WAITERS, MountFsError, OpenFileDescription, vfs};
 use crate::mcore::context::ExecutionContext;
-use crate::mcore::mtask::process::Process;
 use crate::mcore::mtask::process::fd::{FdNum, FileDescriptor, FileDescriptorFlags};
 use crate::mcore::mtask::process::mem::{
     FileBackedMemoryRegion, LazyMemoryRegion, MemoryRegion, SharedMemoryRegion,
 };
+use crate::mcore::mtask::process::{ParkOutcome, Process};
 use crate::mcore::mtask::task::Task;
 use crate::mem::address_space::AddressSpace;
 use crate::mem::virt::VirtualMemoryAllocator;
 use crate::{U64Ext, UsizeExt};
 
+mod job;
 mod mem;
 mod signal;
 
@@ -60,18 +70,49 @@ impl FileAccess for KernelAccess<'_> {
     type FileInfo = FileInfo;
     type Fd = FdNum;
     type OpenError = ();
-    type ReadError = ();
-    type WriteError = ();
+    type ReadError = Errno;
+    type WriteError = Errno;
     type CloseError = ();
 
-    fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo> {
-        Some(FileInfo {
-            node: vfs().read().open(path).ok()?,
+    fn file_info(&self, path: &AbsolutePath) -> Result<Self::FileInfo, Errno> {
+        let node = vfs().read().open(path).map_err(|e| match e {
+            OpenError::NotFound => ENOENT,
+            OpenError::NotDirectory => ENOTDIR,
+            OpenError::TooManySymlinks => ELOOP,
+            OpenError::IsDirectory => EISDIR,
+            OpenError::Io => EIO,
+        })?;
+        Ok(FileInfo { node })
+    }
+
+    fn create(&self, path: &AbsolutePath) -> Result<(), Errno> {
+        vfs()
+            .read()
+            .create(path, FsNodeKind::File)
+            .map_err(|e| match e {
+                CreateError::NotFound => ENOENT,
+                CreateError::NotDirectory => ENOTDIR,
+                CreateError::TooManySymlinks => ELOOP,
+                CreateError::AlreadyExists => EEXIST,
+                CreateError::NoSpace => ENOSPC,
+                CreateError::ReadOnly => EROFS,
+                CreateError::NotSupported => EPERM,
+                CreateError::Io => EIO,
+            })
+    }
+
+    fn readlink(&self, path: &AbsolutePath) -> Result<OwnedPath, Errno> {
+        vfs().read().readlink(path).map_err(|e| match e {
+            ReadlinkError::NotFound => ENOENT,
+            ReadlinkError::NotDirectory => ENOTDIR,
+            ReadlinkError::TooManySymlinks => ELOOP,
+            ReadlinkError::NotSymlink => EINVAL,
+            ReadlinkError::ReadFailed => EIO,
         })
     }
 
-    fn open(&self, info: &Self::FileInfo) -> Result<Self::Fd, ()> {
-        let ofd = OpenFileDescription::from(info.node.clone());
+    fn open(&self, info: &Self::FileInfo, oflag: i32) -> Result<Self::Fd, ()> {
+        let ofd = OpenFileDescription::new(info.node.clone(), oflag);
         let num = self
             .process
             .file_descriptors()
@@ -92,31 +133,92 @@ impl FileAccess for KernelAccess<'_> {
         Ok(num)
     }
 
-    fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, ()> {
-        let fds = self.process.file_descriptors();
-        let guard = fds.read();
+    fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, Errno> {
+        // don't hold the descriptor table while waiting for input
+        let ofd = self
+            .process
+            .file_descriptors()
+            .read()
+            .get(&fd)
+            .ok_or(EBADF)?
+            .file_description()
+            .clone();
+        loop {
+            // snapshot before trying, so that input arriving in between
+            // ends the wait right away
+            let generation = INPUT_WAITERS.generation();
+            let offset = ofd.position().load(Relaxed);
+            match ofd.read(&mut *buf, offset.into_usize()) {
+                Ok(read) => {
+                    // The load and the store are separate, which is sound only while a
+                    // single task reaches a file description. Nothing forks and no
+                    // thread-spawn syscall is dispatched.
+                    ofd.position().store(offset + read.into_u64(), Relaxed);
+                    return Ok(read);
+                }
+                Err(ReadError::WouldBlock) if ofd.is_nonblocking() => return Err(EAGAIN),
+                Err(ReadError::WouldBlock) => {}
+                Err(ReadError::Interrupted) => return Err(EINTR),
+                Err(ReadError::Io) => return Err(EIO),
+                Err(_) => return Err(EINVAL),
+            }
 
-        let desc = guard.get(&fd).ok_or(())?;
-        let ofd = desc.file_description();
-        let offset = ofd.position().load(Relaxed);
-        let read = ofd.read(buf, offset.into_usize()).map_err(|_| ())?;
-        // The load and the store are separate, which is sound only while a
-        // single task reaches a file description. Nothing forks and no
-        // thread-spawn syscall is dispatched.
-        ofd.position().store(offset + read.into_u64(), Relaxed);
-        Ok(read)
+            let outcome = self.process.park_current_task_on(
+                None,
+                |waker| INPUT_WAITERS.register(waker),
+                || {
+                    INPUT_WAITERS.generation() != generation
+                        || self.process.signals_read().has_interrupting_deliverable()
+                },
+            );
+            if matches!(outcome, ParkOutcome::Interrupted)
+                || self.process.signals_read().has_interrupting_deliverable()
+            {
+                return Err(EINTR);
+            }
+        }
     }
 
-    fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, ()> {
-        let fds = self.process.file_descriptors();
-        let guard = fds.read();
+    fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, Errno> {
+        // don't hold the descriptor table while waiting for room
+        let ofd = self
+            .process
+            .file_descriptors()
+            .read()
+            .get(&fd)
+            .ok_or(EINVAL)?
+            .file_description()
+            .clone();
+        loop {
+            // snapshot before trying, like a read does
+            let generation = INPUT_WAITERS.generation();
+            let offset = ofd.position().load(Relaxed);
+            match ofd.write(buf, offset.into_usize()) {
+                Ok(written) => {
+                    ofd.position().store(offset + written.into_u64(), Relaxed);
+                    return Ok(written);
+                }
+                Err(WriteError::WouldBlock) if ofd.is_nonblocking() => return Err(EAGAIN),
+                Err(WriteError::WouldBlock) => {}
+                Err(WriteError::Interrupted) => return Err(EINTR),
+                Err(WriteError::Io) => return Err(EIO),
+                Err(_) => return Err(EINVAL),
+            }
 
-        let desc = guard.get(&fd).ok_or(())?;
-        let ofd = desc.file_description();
-        let offset = ofd.position().load(Relaxed);
-        let written = ofd.write(buf, offset.into_usize()).map_err(|_| ())?;
-        ofd.position().store(offset + written.into_u64(), Relaxed);
-        Ok(written)
+            let outcome = self.process.park_current_task_on(
+                None,
+                |waker| INPUT_WAITERS.register(waker),
+                || {
+                    INPUT_WAITERS.generation() != generation
+                        || self.process.signals_read().has_interrupting_deliverable()
+                },
+            );
+            if matches!(outcome, ParkOutcome::Interrupted)
+                || self.process.signals_read().has_interrupting_deliverable()
+            {
+                return Err(EINTR);
+            }
+        }
     }
 
     fn close(&self, fd: Self::Fd) -> Result<(), ()> {
@@ -134,9 +236,19 @@ impl FileAccess for KernelAccess<'_> {
             .map_err(|e| match e {
                 IoctlError::NotSupported => ENOTTY,
                 IoctlError::InvalidArgument | IoctlError::FsError(_) => EINVAL,
+                IoctlError::NotPermitted => EPERM,
+                IoctlError::Interrupted => EINTR,
             })
     }
 
+    fn poll(&self, fd: Self::Fd, events: PollEvents) -> Result<PollEvents, Errno> {
+        let fds = self.process.file_descriptors();
+        let guard = fds.read();
+
+        let desc = guard.get(&fd).ok_or(EBADF)?;
+        desc.file_description().poll(events).map_err(|_| EIO)
+    }
+
     fn fsync(&self, fd: Self::Fd) -> Result<(), Errno> {
         let fds = self.process.file_descriptors();
         let guard = fds.read();
@@ -156,6 +268,10 @@ impl FileAccess for KernelAccess<'_> {
         desc.file_description().stat(&mut stat).map_err(|_| EIO)?;
         Ok(Stat {
             size: stat.size.into_u64(),
+            ino: stat.ino,
+            mode: stat.mode,
+            nlink: stat.nlink,
+            blocks: stat.blocks,
         })
     }
 
@@ -177,6 +293,46 @@ impl FileAccess for KernelAccess<'_> {
     }
 }
 
+impl MountAccess for KernelAccess<'_> {
+    fn mount(
+        &self,
+        fs_type: &str,
+        source: Option<&AbsolutePath>,
+        target: &AbsolutePath,
+        flags: MountFlags,
+    ) -> Result<(), Errno> {
+        file::mount(fs_type, source, target, flags).map_err(|e| match e {
+            MountFsError::UnknownFileSystem => ENODEV,
+            MountFsError::NoSuchDevice => ENOENT,
+            MountFsError::CreateFileSystem(_) => EINVAL,
+            MountFsError::Mount(e) => match e {
+                MountError::AlreadyMounted => EBUSY,
+                MountError::NotFound => ENOENT,
+                MountError::NotDirectory => ENOTDIR,
+                MountError::NotEmpty => ENOTEMPTY,
+                MountError::TooManySymlinks => ELOOP,
+                MountError::Io => EIO,
+            },
+        })
+    }
+
+    fn unmount(&self, target: &AbsolutePath) -> Result<(), Errno> {
+        vfs().write().unmount(target).map_err(|e| match e {
+            UnmountError::NotMounted => EINVAL,
+            UnmountError::Busy => EBUSY,
+        })
+    }
+
+    fn mount_table(&self) -> String {
+        vfs()
+            .read()
+            .mounts()
+            .iter()
+            .map(|mount| format!("{mount}\n"))
+            .collect()
+    }
+}
+
 impl kernel_syscall::access::MemoryRegionAccess for KernelAccess<'_> {
     type Region = KernelMemoryRegionHandle;
 
@@ -217,8 +373,8 @@ impl kernel_syscall::access::MemoryRegionAccess for KernelAccess<'_> {
     ) -> Result<kernel_syscall::UserspacePtr<u8>, Errno> {
         let fd = FdNum::from(fd);
 
-        // Clone the node so the region keeps the device file open for its
-        // whole lifetime. OpenFileDescription derefs to VfsNode.
+        // Clone the node so the region keeps the file open for its whole
+        // lifetime. OpenFileDescription derefs to VfsNode.
         let node = {
             let fds = self.process.file_descriptors();
             let guard = fds.read();
@@ -233,23 +389,27 @@ impl kernel_syscall::access::MemoryRegionAccess for KernelAccess<'_> {
 
         let page_size = Size4KiB::SIZE as usize;
         let page_aligned = len.next_multiple_of(page_size);
-        // the device region must be large enough to satisfy the request
-        if page_aligned > region.len.next_multiple_of(page_size) {
-            return Err(EINVAL);
-        }
-
-        // The mmap pointer is a kernel HHDM virtual address. Translate it to
-        // the physical base of the contiguous device frames.
-        let phys = AddressSpace::kernel()
-            .translate(VirtAddr::from_ptr(region.ptr.as_ptr()))
-            .ok_or(EINVAL)?;
-        if !phys.is_aligned(Size4KiB::SIZE) {
+        let page_count = page_aligned / page_size;
+        // the region must be large enough to satisfy the request
+        if page_count > region.pages().len() {
             return Err(EINVAL);
         }
 
-        let page_count = page_aligned / page_size;
-        let start = PhysFrame::<Size4KiB>::containing_address(phys);
-        let frames = (0..page_count as u64).map(move |i| start + i);
+        // The mmap pages are kernel HHDM virtual addresses. Translate each
+        // of them to the physical frame behind it, they don't have to be
+        // contiguous.
+        let frames = region.pages()[..page_count]
+            .iter()
+            .map(|page| {
+                let phys = AddressSpace::kernel()
+                    .translate(VirtAddr::from_ptr(page.as_ptr()))
+                    .ok_or(EINVAL)?;
+                if !phys.is_aligned(Size4KiB::SIZE) {
+                    return Err(EINVAL);
+                }
+                Ok(PhysFrame::<Size4KiB>::containing_address(phys))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         let segment = self.process.vmm().reserve(page_count).ok_or(ENOMEM)?;
         let addr = segment.start;
@@ -258,7 +418,7 @@ impl kernel_syscall::access::MemoryRegionAccess for KernelAccess<'_> {
             .address_space()
             .map_range::<Size4KiB>(
                 &*segment,
-                frames,
+                frames.into_iter(),
                 PageTableFlags::PRESENT
                     | PageTableFlags::WRITABLE
                     | PageTableFlags::USER_ACCESSIBLE
@@ -268,8 +428,8 @@ impl kernel_syscall::access::MemoryRegionAccess for KernelAccess<'_> {
 
         let user_ptr = addr.as_ptr::<u8>().try_into().map_err(|_| EINVAL)?;
 
-        // The region owns the virtual reservation and the open device file,
-        // but not the physical frames, so process exit never frees them.
+        // The region owns the virtual reservation and the open file, but not
+        // the physical frames, so process exit never frees them.
         let inner = MemoryRegion::Shared(SharedMemoryRegion::new(segment, page_aligned, node));
         self.add_memory_region(KernelMemoryRegionHandle {
             addr: user_ptr,
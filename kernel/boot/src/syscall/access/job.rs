@@ -0,0 +1,19 @@
+use kernel_abi::ProcessId;
+use kernel_syscall::access::JobControlAccess;
+
+use crate::mcore::mtask::process::tree::process_tree;
+use crate::syscall::access::KernelAccess;
+
+impl JobControlAccess for KernelAccess<'_> {
+    fn set_process_group(&self, pid: ProcessId, pgid: ProcessId) {
+        // sys_setpgid looked the process up already, so it can only be gone
+        // if it died in the meantime
+        if let Some(process) = process_tree().read().processes.get(&pid) {
+            process.set_process_group_id(pgid);
+        }
+    }
+
+    fn create_session(&self) {
+        self.process.create_session();
+    }
+}
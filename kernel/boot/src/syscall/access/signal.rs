@@ -5,11 +5,9 @@ use kernel_abi::{Errno, ProcessId, SigInfo};
 use kernel_syscall::access::{
     Capability, Identity, PermissionAccess, ProcessAccess, ProcessesAccess, SignalAccess,
 };
-use kernel_syscall::signal::Disposition;
-use tracing::info;
 
+use crate::mcore::mtask::process::Process;
 use crate::mcore::mtask::process::tree::process_tree;
-use crate::mcore::mtask::process::{ExitOutcome, Process};
 use crate::syscall::access::KernelAccess;
 
 /// Local wrapper so the foreign `ProcessAccess` trait can be implemented
@@ -21,9 +19,16 @@ impl ProcessAccess for KernelProcess {
         self.0.pid()
     }
 
+    fn parent_process_id(&self) -> ProcessId {
+        self.0.ppid()
+    }
+
     fn process_group_id(&self) -> ProcessId {
-        // Every process is its own group until setpgid exists.
-        self.0.pid()
+        self.0.process_group_id()
+    }
+
+    fn session_id(&self) -> ProcessId {
+        self.0.session_id()
     }
 }
 
@@ -38,12 +43,11 @@ impl ProcessesAccess for KernelAccess<'_> {
 
 impl PermissionAccess for KernelAccess<'_> {
     fn current_identity(&self) -> Identity {
-        // No uid or pgid exists on Process yet. Single user, every process
-        // is its own group.
+        // No uid exists on Process yet, it's a single user kernel.
         Identity {
             process_id: self.process.pid(),
             user_id: 0,
-            process_group_id: self.process.pid(),
+            process_group_id: self.process.process_group_id(),
         }
     }
 
@@ -60,31 +64,6 @@ impl SignalAccess for KernelAccess<'_> {
         let Some(process) = process_tree().read().processes.get(&pid).cloned() else {
             return;
         };
-        let mut guard = process.signals_write();
-        let effect = guard.deliver(info.signo);
-        // Log stop and terminate outcomes at generation time. The victim may
-        // consume the signal at a timer tick, and the timer handler must not
-        // touch the serial lock, so this is the only safe place to record it.
-        // A blocked default-terminate signal logs early while it stays
-        // pending, which is acceptable for a single-user kernel.
-        match guard.disposition(info.signo) {
-            Disposition::DefaultStop => {
-                info!("stopping process {pid} on signal {}", info.signo.name());
-            }
-            Disposition::DefaultTerminate => {
-                info!(
-                    "terminating process on signal {} (pid {pid})",
-                    info.signo.name()
-                );
-                process.set_exit_outcome(ExitOutcome::Signaled(info.signo));
-            }
-            Disposition::Ignore | Disposition::Handler(_) => {}
-        }
-        if effect.resume_tasks {
-            info!("continuing process {pid}");
-            guard.resume_stopped_tasks();
-        }
-        // Spurious wakes are by design, the woken task re-checks its condition.
-        process.wake_interruptible();
+        process.deliver_signal(info.signo);
     }
 }
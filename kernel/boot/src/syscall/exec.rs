@@ -3,8 +3,8 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use kernel_abi::{
-    ARG_MAX, E2BIG, EFAULT, EINVAL, EIO, ENAMETOOLONG, ENOENT, ENOEXEC, ENOMEM, Errno, PATH_MAX,
-    Signal, StrSlice,
+    ARG_MAX, E2BIG, EACCES, EFAULT, EINVAL, EIO, ENAMETOOLONG, ENOENT, ENOEXEC, ENOMEM, Errno,
+    PATH_MAX, Signal, StrSlice,
 };
 use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, Path};
 use x86_64::VirtAddr;
@@ -69,6 +69,7 @@ pub fn dispatch_sys_execve(
     };
 
     process.set_executable_path(path);
+    process.set_cmdline(&args.argv());
     sole.finish_reap();
     process.signals_write().exec_reset();
 
@@ -188,6 +189,7 @@ impl ExecArgs {
 
 fn exec_errno(e: LoadExecutableError) -> Errno {
     match e {
+        LoadExecutableError::NoExec => EACCES,
         LoadExecutableError::Stat | LoadExecutableError::Read => EIO,
         LoadExecutableError::AllocationFailed => ENOMEM,
         LoadExecutableError::Truncated
@@ -3,14 +3,19 @@ use core::slice::{from_raw_parts, from_raw_parts_mut};
 
 use access::KernelAccess;
 use kernel_abi::{
-    EFAULT, EINTR, EINVAL, EIO, ENOENT, ENOMEM, ERANGE, ESRCH, Errno, IoctlRequest, ProcessId,
-    SigAction, SigMaskHow, SigSet, Signal, Stat, Timespec, Whence, syscall_name,
+    EFAULT, EINTR, EINVAL, EIO, ENOENT, ENOMEM, ERANGE, ESRCH, Errno, IoctlRequest, MountArgs,
+    PollFd, ProcessId, SigAction, SigMaskHow, SigSet, Signal, Stat, Timespec, Whence, syscall_name,
 };
 use kernel_syscall::access::{FileAccess, ProcessesAccess};
 use kernel_syscall::fcntl::sys_open;
+use kernel_syscall::job::{sys_getpgid, sys_getppid, sys_getsid, sys_setpgid, sys_setsid};
 use kernel_syscall::mman::sys_mmap;
+use kernel_syscall::mount::{sys_mount, sys_mount_table, sys_umount};
+use kernel_syscall::poll::poll_fds;
 use kernel_syscall::signal::{SignalTarget, sys_kill};
-use kernel_syscall::unistd::{sys_fsync, sys_getcwd, sys_ioctl, sys_lseek, sys_read, sys_write};
+use kernel_syscall::unistd::{
+    sys_fsync, sys_getcwd, sys_ioctl, sys_lseek, sys_read, sys_readlink, sys_write,
+};
 use kernel_syscall::{UserspaceMutPtr, UserspacePtr};
 use tracing::{debug, error};
 use x86_64::VirtAddr;
@@ -18,6 +23,7 @@ use x86_64::instructions::hlt;
 use x86_64::structures::idt::InterruptStackFrame;
 
 use crate::arch::idt::SyscallRegisters;
+use crate::file::INPUT_WAITERS;
 use crate::hpet::hpet;
 use crate::mcore::context::ExecutionContext;
 use crate::mcore::mtask::process::mem::PageInError;
@@ -48,6 +54,11 @@ pub(crate) fn dispatch_syscall(
         kernel_abi::SYS_WRITE => dispatch_sys_write(arg1, arg2, arg3),
         kernel_abi::SYS_EXIT => dispatch_sys_exit(arg1),
         kernel_abi::SYS_GETPID => dispatch_sys_getpid(),
+        kernel_abi::SYS_GETPPID => sys_getppid(&KernelAccess::new()),
+        kernel_abi::SYS_GETPGID => dispatch_sys_getpgid(arg1),
+        kernel_abi::SYS_SETPGID => dispatch_sys_setpgid(arg1, arg2),
+        kernel_abi::SYS_GETSID => dispatch_sys_getsid(arg1),
+        kernel_abi::SYS_SETSID => sys_setsid(&KernelAccess::new()),
         kernel_abi::SYS_KILL => dispatch_sys_kill(arg1, arg2),
         kernel_abi::SYS_SIGACTION => dispatch_sys_sigaction(arg1, arg2, arg3),
         kernel_abi::SYS_SIGPROCMASK => dispatch_sys_sigprocmask(arg1, arg2, arg3),
@@ -59,9 +70,14 @@ pub(crate) fn dispatch_syscall(
         kernel_abi::SYS_CLOCK_GETTIME => dispatch_sys_clock_gettime(arg1, arg2),
         kernel_abi::SYS_EXE_PATH => dispatch_sys_exe_path(arg1, arg2),
         kernel_abi::SYS_NANOSLEEP => dispatch_sys_nanosleep(arg1, arg2),
+        kernel_abi::SYS_POLL => dispatch_sys_poll(arg1, arg2, arg3),
         kernel_abi::SYS_EXECVE => {
             exec::dispatch_sys_execve(arg1, arg2, arg3, arg4, arg5, arg6, frame, regs)
         }
+        kernel_abi::SYS_READLINK => dispatch_sys_readlink(arg1, arg2, arg3, arg4),
+        kernel_abi::SYS_MOUNT => dispatch_sys_mount(arg1),
+        kernel_abi::SYS_UMOUNT => dispatch_sys_umount(arg1, arg2),
+        kernel_abi::SYS_MOUNT_TABLE => dispatch_sys_mount_table(arg1, arg2),
         _ => {
             error!("unimplemented syscall: {} ({n})", syscall_name(n));
             loop {
@@ -91,6 +107,33 @@ fn dispatch_sys_getpid() -> Result<usize, Errno> {
     Ok(ExecutionContext::load().pid().as_u64() as usize)
 }
 
+/// Rejects negative ids, which never name a process.
+fn process_id_arg(pid: usize) -> Result<ProcessId, Errno> {
+    if (pid as isize) < 0 {
+        return Err(ESRCH);
+    }
+    Ok(ProcessId::from(pid as u64))
+}
+
+fn dispatch_sys_getpgid(pid: usize) -> Result<usize, Errno> {
+    sys_getpgid(&KernelAccess::new(), process_id_arg(pid)?)
+}
+
+fn dispatch_sys_setpgid(pid: usize, pgid: usize) -> Result<usize, Errno> {
+    if (pgid as isize) < 0 {
+        return Err(EINVAL);
+    }
+    sys_setpgid(
+        &KernelAccess::new(),
+        process_id_arg(pid)?,
+        ProcessId::from(pgid as u64),
+    )
+}
+
+fn dispatch_sys_getsid(pid: usize) -> Result<usize, Errno> {
+    sys_getsid(&KernelAccess::new(), process_id_arg(pid)?)
+}
+
 fn dispatch_sys_kill(pid: usize, signo: usize) -> Result<usize, Errno> {
     let cx = KernelAccess::new();
 
@@ -306,6 +349,49 @@ fn dispatch_sys_open(
     sys_open(&cx, path, path_len, oflag as i32, mode as i32)
 }
 
+fn dispatch_sys_readlink(
+    path: usize,
+    path_len: usize,
+    buf: usize,
+    bufsize: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    make_user_range_resident(path, path_len, UserAccess::Read)?;
+    let path = unsafe { UserspacePtr::try_from_usize(path)? };
+    let slice = unsafe { slice_from_ptr_and_len_mut(buf, bufsize) }?;
+    make_user_range_resident(buf, bufsize, UserAccess::Write)?;
+    sys_readlink(&cx, path, path_len, slice)
+}
+
+fn dispatch_sys_mount(args: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let args = read_user::<MountArgs>(args)?;
+    for slice in [args.fs_type, args.source, args.target] {
+        if !slice.is_empty() {
+            make_user_range_resident(slice.ptr(), slice.len(), UserAccess::Read)?;
+        }
+    }
+    sys_mount(&cx, &args)
+}
+
+fn dispatch_sys_umount(target: usize, target_len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    make_user_range_resident(target, target_len, UserAccess::Read)?;
+    let target = unsafe { UserspacePtr::try_from_usize(target)? };
+    sys_umount(&cx, target, target_len)
+}
+
+fn dispatch_sys_mount_table(buf: usize, bufsize: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let slice = unsafe { slice_from_ptr_and_len_mut(buf, bufsize) }?;
+    make_user_range_resident(buf, bufsize, UserAccess::Write)?;
+    sys_mount_table(&cx, slice)
+}
+
 fn dispatch_sys_read(fd: usize, buf: usize, nbyte: usize) -> Result<usize, Errno> {
     let cx = KernelAccess::new();
 
@@ -446,6 +532,60 @@ fn dispatch_sys_nanosleep(req: usize, rem: usize) -> Result<usize, Errno> {
     }
 }
 
+/// POSIX poll. A negative timeout waits until an entry is ready, zero
+/// returns right away.
+///
+/// Only input wakes the task. That is enough, since no file makes writes
+/// wait, so `POLLOUT` is always reported on the first pass.
+fn dispatch_sys_poll(fds: usize, nfds: usize, timeout: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    // the timeout is a C int in milliseconds
+    let timeout = timeout as i32;
+    let fds: &mut [PollFd] = if nfds == 0 {
+        &mut []
+    } else {
+        if !fds.is_multiple_of(align_of::<PollFd>()) {
+            return Err(EFAULT);
+        }
+        let len = nfds.checked_mul(size_of::<PollFd>()).ok_or(EINVAL)?;
+        make_user_range_resident(fds, len, UserAccess::Write)?;
+        unsafe { slice_from_ptr_and_len_mut(fds, nfds) }?
+    };
+
+    let deadline = u64::try_from(timeout)
+        .ok()
+        .map(|ms| hpet().read().elapsed_ns().saturating_add(ms * 1_000_000));
+    let process = ExecutionContext::load().current_process().clone();
+    loop {
+        // snapshot before polling, so that input arriving in between ends
+        // the wait right away
+        let generation = INPUT_WAITERS.generation();
+        let ready = poll_fds(&cx, fds);
+        if ready > 0 {
+            return Ok(ready);
+        }
+        if deadline.is_some_and(|deadline| hpet().read().elapsed_ns() >= deadline) {
+            return Ok(0);
+        }
+
+        let outcome = process.park_current_task_on(
+            deadline,
+            |waker| INPUT_WAITERS.register(waker),
+            || {
+                INPUT_WAITERS.generation() != generation
+                    || deadline.is_some_and(|deadline| hpet().read().elapsed_ns() >= deadline)
+                    || process.signals_read().has_interrupting_deliverable()
+            },
+        );
+        if matches!(outcome, ParkOutcome::Interrupted)
+            || process.signals_read().has_interrupting_deliverable()
+        {
+            return Err(EINTR);
+        }
+    }
+}
+
 fn dispatch_sys_exe_path(buf: usize, len: usize) -> Result<usize, Errno> {
     let slice = unsafe { slice_from_ptr_and_len_mut(buf, len) }?;
     make_user_range_resident(buf, len, UserAccess::Write)?;
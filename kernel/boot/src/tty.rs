@@ -0,0 +1,504 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::ptr;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use conquer_once::spin::Lazy;
+use kernel_abi::{IoctlRequest, LocalFlags, PollEvents, ProcessId, Signal, Termios, WinSize};
+use kernel_devfs::DevFile;
+use kernel_syscall::signal::Disposition;
+use kernel_tty::LineDiscipline;
+use kernel_vfs::{IoctlError, ReadError, Stat, StatError, WriteError};
+use spin::Mutex;
+use tracing::{Level, debug, info, instrument};
+
+pub mod pty;
+
+use crate::file::INPUT_WAITERS;
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::process::tree::process_tree;
+use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
+use crate::mcore::mtask::task::Task;
+use crate::{U64Ext, console, serial};
+
+static SERIAL_TTY: Lazy<Arc<Tty>> =
+    Lazy::new(|| Arc::new(Tty::new(String::from("ttyS0"), serial::write_bytes)));
+
+static CONSOLE_TTY: Lazy<Option<Arc<Tty>>> = Lazy::new(|| {
+    let size = console::size()?;
+    let tty = Tty::new(String::from("tty0"), console::write_bytes);
+    tty.set_window_size(size);
+    Some(Arc::new(tty))
+});
+
+/// The terminal on COM1, which is the controlling terminal of every process
+/// that the kernel starts.
+#[must_use]
+pub fn serial() -> &'static Arc<Tty> {
+    &SERIAL_TTY
+}
+
+/// The terminal on the framebuffer console, if there is one.
+#[must_use]
+pub fn framebuffer() -> Option<&'static Arc<Tty>> {
+    Option::as_ref(&CONSOLE_TTY)
+}
+
+/// The terminal that the keyboard types into. Without a screen to show it
+/// on, that's the serial terminal.
+#[must_use]
+pub fn console() -> &'static Arc<Tty> {
+    framebuffer().unwrap_or_else(serial)
+}
+
+/// Starts the kernel task that feeds bytes received on the serial line into
+/// the serial terminal.
+#[instrument(name = "init tty", level = Level::TRACE)]
+pub fn init() {
+    let task = Task::create_new(Process::root(), serial_input, ptr::null_mut())
+        .expect("should be able to create the serial input task");
+    info!(id = %task.id(), "serial input task created");
+    GlobalTaskQueue::enqueue(Box::pin(task));
+}
+
+extern "C" fn serial_input(_: *mut c_void) {
+    let tty = serial();
+    let mut buf = [0_u8; 64];
+    loop {
+        let generation = serial::RECEIVE_WAITERS.generation();
+        let mut received = false;
+        loop {
+            let n = serial::read_received(&mut buf);
+            if n == 0 {
+                break;
+            }
+            tty.receive(&buf[..n]);
+            received = true;
+        }
+        if received {
+            INPUT_WAITERS.wake_all();
+        }
+        // the root process is never reaped, so the outcome is always ready
+        let _ = Process::root().park_current_task_on(
+            None,
+            |waker| serial::RECEIVE_WAITERS.register(waker),
+            || serial::RECEIVE_WAITERS.generation() != generation,
+        );
+    }
+}
+
+/// Sends the output of a terminal to its device.
+type Output = Box<dyn Fn(&[u8]) + Send + Sync>;
+
+/// Tells how many bytes of output the device can take right now.
+type OutputRoom = Box<dyn Fn() -> usize + Send + Sync>;
+
+/// A terminal, which runs the bytes between a character device and the
+/// processes that use it through a [`LineDiscipline`].
+pub struct Tty {
+    name: String,
+    discipline: Mutex<LineDiscipline>,
+    /// Sends bytes to the device. Must not block for long, since echo is
+    /// written while the discipline is locked.
+    output: Output,
+    /// The room of a device that buffers output, which limits writes. Echo
+    /// isn't limited, since it can't wait.
+    output_room: Option<OutputRoom>,
+    /// The process group that receives the signal characters.
+    foreground: Mutex<Option<ProcessId>>,
+    window_size: Mutex<WinSize>,
+    hung_up: AtomicBool,
+}
+
+impl Tty {
+    pub fn new(name: String, output: impl Fn(&[u8]) + Send + Sync + 'static) -> Self {
+        Self {
+            name,
+            discipline: Mutex::new(LineDiscipline::default()),
+            output: Box::new(output),
+            output_room: None,
+            foreground: Mutex::new(None),
+            window_size: Mutex::new(WinSize::default()),
+            hung_up: AtomicBool::new(false),
+        }
+    }
+
+    /// Makes writes stop at the room that `room` reports, for a device that
+    /// buffers output until someone reads it.
+    #[must_use]
+    pub fn with_output_room(mut self, room: impl Fn() -> usize + Send + Sync + 'static) -> Self {
+        self.output_room = Some(Box::new(room));
+        self
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Runs bytes that arrived from the device through the line discipline
+    /// and sends the resulting signals to the foreground process group. Task
+    /// context only, since signal delivery takes process locks.
+    pub fn receive(&self, bytes: &[u8]) {
+        for &byte in bytes {
+            let signal = self.discipline.lock().receive(byte, &self.output);
+            if let Some(signal) = signal {
+                self.signal_foreground(signal);
+            }
+        }
+    }
+
+    /// Returns [`ReadError::WouldBlock`] while there is no input to read.
+    /// Waiting for input is up to the caller. Once the terminal is hung up,
+    /// a read without input returns 0 like an end of file.
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, ReadError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        match self.discipline.lock().read(buf) {
+            Some(read) => Ok(read),
+            None if self.is_hung_up() => Ok(0),
+            None => Err(ReadError::WouldBlock),
+        }
+    }
+
+    /// Whether a read would return without blocking, which it also does
+    /// once the terminal is hung up.
+    #[must_use]
+    pub fn is_readable(&self) -> bool {
+        self.is_hung_up() || self.discipline.lock().is_readable()
+    }
+
+    /// Processes `buf` for output and returns how much of it was written,
+    /// which is less than all of it once the device has no room left.
+    pub fn write(&self, buf: &[u8]) -> usize {
+        let discipline = self.discipline.lock();
+        let len = self
+            .output_room
+            .as_ref()
+            .map_or(buf.len(), |room| discipline.output_fits(buf, room()));
+        discipline.write(&buf[..len], &self.output);
+        len
+    }
+
+    /// Whether a write would write something right now.
+    #[must_use]
+    pub fn is_writable(&self) -> bool {
+        self.output_room.as_ref().is_none_or(|room| room() > 0)
+    }
+
+    #[must_use]
+    pub fn termios(&self) -> Termios {
+        *self.discipline.lock().termios()
+    }
+
+    /// Takes effect immediately, for input that is already buffered, too.
+    pub fn set_termios(&self, termios: Termios) {
+        self.discipline.lock().set_termios(termios);
+        // switching to raw mode can make a partial line readable
+        INPUT_WAITERS.wake_all();
+    }
+
+    #[must_use]
+    pub fn foreground_process_group(&self) -> Option<ProcessId> {
+        *self.foreground.lock()
+    }
+
+    pub fn set_foreground_process_group(&self, pgid: ProcessId) {
+        *self.foreground.lock() = Some(pgid);
+    }
+
+    #[must_use]
+    pub fn window_size(&self) -> WinSize {
+        *self.window_size.lock()
+    }
+
+    /// Sends `SIGWINCH` to the foreground process group if the size
+    /// changes.
+    pub fn set_window_size(&self, size: WinSize) {
+        let old = core::mem::replace(&mut *self.window_size.lock(), size);
+        if old != size {
+            self.signal_foreground(Signal::WindowChanged);
+        }
+    }
+
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.hung_up.load(Relaxed)
+    }
+
+    /// Disconnects the terminal from its device, which sends `SIGHUP` to
+    /// the foreground process group. Reads return end of file from then
+    /// on, once the buffered input is consumed, and writes fail.
+    pub fn hang_up(&self) {
+        self.hung_up.store(true, Relaxed);
+        self.signal_foreground(Signal::Hangup);
+        INPUT_WAITERS.wake_all();
+    }
+
+    /// Makes this terminal the controlling terminal of `process` if that
+    /// leads a session without one and the terminal is free, which it is
+    /// while it has no foreground process group. The process group of
+    /// `process` becomes the foreground process group.
+    pub fn acquire_for(self: &Arc<Self>, process: &Process) {
+        if process.session_id() != process.pid() || process.controlling_terminal().is_some() {
+            return;
+        }
+        let mut foreground = self.foreground.lock();
+        if foreground.is_none() {
+            debug!(tty = %self.name, pid = %process.pid(), "acquired as controlling terminal");
+            process.set_controlling_terminal(self.clone());
+            *foreground = Some(process.process_group_id());
+        }
+    }
+
+    /// Makes `pgid` the foreground process group unless a live one exists
+    /// already.
+    pub fn claim_foreground(&self, pgid: ProcessId) {
+        let mut foreground = self.foreground.lock();
+        let taken = foreground.is_some_and(|current| {
+            process_tree()
+                .read()
+                .all()
+                .any(|p| p.process_group_id() == current && p.exit_outcome().is_none())
+        });
+        if !taken {
+            debug!(tty = %self.name, %pgid, "claiming foreground");
+            *foreground = Some(pgid);
+        }
+    }
+
+    fn is_controlling_terminal_of(&self, process: &Process) -> bool {
+        process
+            .controlling_terminal()
+            .is_some_and(|tty| ptr::eq(Arc::as_ptr(&tty), self))
+    }
+
+    /// Decides whether `process` may access the terminal. A process in a
+    /// background process group of the terminal's session gets `signal` sent
+    /// to its group instead, unless it blocks or ignores the signal.
+    fn job_access(&self, process: &Process, signal: Signal) -> JobAccess {
+        let pgid = process.process_group_id();
+        if !self.is_controlling_terminal_of(process)
+            || self.foreground_process_group().is_none_or(|fg| fg == pgid)
+        {
+            return JobAccess::Granted;
+        }
+        {
+            let signals = process.signals_read();
+            if signals.blocked() & signal.bit() != 0
+                || matches!(signals.disposition(signal), Disposition::Ignore)
+            {
+                return JobAccess::Refused;
+            }
+        }
+        signal_process_group(pgid, signal);
+        JobAccess::Signaled
+    }
+
+    /// Like [`Tty::job_access`] for the operations that are only signaled,
+    /// so a refused signal grants access.
+    fn job_output_access(&self, process: &Process) -> bool {
+        !matches!(
+            self.job_access(process, Signal::TerminalOutput),
+            JobAccess::Signaled
+        )
+    }
+
+    fn signal_foreground(&self, signal: Signal) {
+        if let Some(pgid) = self.foreground_process_group() {
+            signal_process_group(pgid, signal);
+        }
+    }
+}
+
+enum JobAccess {
+    /// The process is in the foreground, or the terminal isn't its
+    /// controlling terminal.
+    Granted,
+    /// The process is in the background and its group was signaled.
+    Signaled,
+    /// The process is in the background, but blocks or ignores the signal.
+    Refused,
+}
+
+fn signal_process_group(pgid: ProcessId, signal: Signal) {
+    let members = process_tree()
+        .read()
+        .all()
+        .filter(|p| p.process_group_id() == pgid)
+        .cloned()
+        .collect::<Vec<_>>();
+    for process in members {
+        process.deliver_signal(signal);
+    }
+}
+
+/// An open terminal in devfs.
+pub struct TtyFile {
+    tty: Arc<Tty>,
+}
+
+impl TtyFile {
+    pub fn new(tty: Arc<Tty>) -> Self {
+        Self { tty }
+    }
+
+    /// Like [`TtyFile::new`], but the terminal becomes the controlling
+    /// terminal of the opening process if that can acquire it, see
+    /// [`Tty::acquire_for`].
+    pub fn open(tty: Arc<Tty>) -> Self {
+        tty.acquire_for(ExecutionContext::load().current_process());
+        Self::new(tty)
+    }
+}
+
+impl DevFile for TtyFile {
+    /// A background process group that reads gets `SIGTTIN` and the read
+    /// fails with [`ReadError::Interrupted`], or with [`ReadError::Io`] if
+    /// the signal is blocked or ignored.
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        let process = ExecutionContext::load().current_process().clone();
+        match self.tty.job_access(&process, Signal::TerminalInput) {
+            JobAccess::Granted => self.tty.read(buf),
+            JobAccess::Signaled => Err(ReadError::Interrupted),
+            JobAccess::Refused => Err(ReadError::Io),
+        }
+    }
+
+    /// With [`LocalFlags::TOSTOP`], a background process group that writes
+    /// gets `SIGTTOU` and the write fails with [`WriteError::Interrupted`].
+    /// Returns [`WriteError::WouldBlock`] while the device has no room for
+    /// output. Waiting for room is up to the caller.
+    fn write(&mut self, buf: &[u8], _: usize) -> Result<usize, WriteError> {
+        if self.tty.is_hung_up() {
+            return Err(WriteError::Io);
+        }
+        let process = ExecutionContext::load().current_process().clone();
+        if self.tty.termios().lflag.contains(LocalFlags::TOSTOP)
+            && !self.tty.job_output_access(&process)
+        {
+            return Err(WriteError::Interrupted);
+        }
+        match self.tty.write(buf) {
+            0 if !buf.is_empty() => Err(WriteError::WouldBlock),
+            written => Ok(written),
+        }
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.size = 0;
+        Ok(())
+    }
+
+    fn ioctl(&mut self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
+        let process = ExecutionContext::load().current_process().clone();
+        match request {
+            IoctlRequest::TcGetAttr | IoctlRequest::TcGetWinSize | IoctlRequest::TcSetWinSize => {
+                terminal_ioctl(&self.tty, request, arg)
+            }
+            IoctlRequest::TcSetAttr => {
+                if !self.tty.job_output_access(&process) {
+                    return Err(IoctlError::Interrupted);
+                }
+                terminal_ioctl(&self.tty, request, arg)
+            }
+            IoctlRequest::TcGetPgrp => {
+                if !self.tty.is_controlling_terminal_of(&process) {
+                    return Err(IoctlError::NotSupported);
+                }
+                terminal_ioctl(&self.tty, request, arg)
+            }
+            IoctlRequest::TcSetPgrp => {
+                let arg = <[u8; 8]>::try_from(&*arg).map_err(|_| IoctlError::InvalidArgument)?;
+                let pgid = ProcessId::from(u64::from_ne_bytes(arg));
+                if !self.tty.is_controlling_terminal_of(&process) {
+                    return Err(IoctlError::NotSupported);
+                }
+                if !self.tty.job_output_access(&process) {
+                    return Err(IoctlError::Interrupted);
+                }
+                let session = process.session_id();
+                let in_session = process_tree()
+                    .read()
+                    .all()
+                    .any(|p| p.process_group_id() == pgid && p.session_id() == session);
+                if !in_session {
+                    return Err(IoctlError::NotPermitted);
+                }
+                self.tty.set_foreground_process_group(pgid);
+                Ok(0)
+            }
+            IoctlRequest::FbGetScreenInfo
+            | IoctlRequest::LoopAttach
+            | IoctlRequest::LoopDetach
+            | IoctlRequest::PtyGetNumber
+            | IoctlRequest::InputGetName
+            | IoctlRequest::InputGetCapabilities
+            | IoctlRequest::InputGetAbsInfo => Err(IoctlError::NotSupported),
+        }
+    }
+
+    fn poll(&mut self, events: PollEvents) -> PollEvents {
+        let mut ready = PollEvents::empty();
+        if self.tty.is_readable() {
+            ready |= PollEvents::POLLIN;
+        }
+        if self.tty.is_hung_up() {
+            ready |= PollEvents::POLLHUP;
+        } else if self.tty.is_writable() {
+            ready |= PollEvents::POLLOUT;
+        }
+        ready & (events | PollEvents::POLLHUP)
+    }
+}
+
+/// The terminal ioctls that are the same for every file of a terminal,
+/// without the checks for job control.
+fn terminal_ioctl(tty: &Tty, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
+    match request {
+        IoctlRequest::TcGetAttr => {
+            *termios_arg(arg)? = tty.termios().to_bytes();
+            Ok(0)
+        }
+        IoctlRequest::TcSetAttr => {
+            tty.set_termios(Termios::from_bytes(termios_arg(arg)?));
+            Ok(0)
+        }
+        IoctlRequest::TcGetPgrp => {
+            // like Linux, 0 while there is no foreground process group
+            Ok(tty
+                .foreground_process_group()
+                .map_or(0, |pgid| pgid.as_u64().into_usize()))
+        }
+        IoctlRequest::TcGetWinSize => {
+            *winsize_arg(arg)? = tty.window_size().to_bytes();
+            Ok(0)
+        }
+        IoctlRequest::TcSetWinSize => {
+            tty.set_window_size(WinSize::from_bytes(winsize_arg(arg)?));
+            Ok(0)
+        }
+        IoctlRequest::TcSetPgrp
+        | IoctlRequest::FbGetScreenInfo
+        | IoctlRequest::LoopAttach
+        | IoctlRequest::LoopDetach
+        | IoctlRequest::PtyGetNumber
+        | IoctlRequest::InputGetName
+        | IoctlRequest::InputGetCapabilities
+        | IoctlRequest::InputGetAbsInfo => Err(IoctlError::NotSupported),
+    }
+}
+
+fn termios_arg(arg: &mut [u8]) -> Result<&mut [u8; Termios::SIZE], IoctlError> {
+    arg.try_into().map_err(|_| IoctlError::InvalidArgument)
+}
+
+fn winsize_arg(arg: &mut [u8]) -> Result<&mut [u8; WinSize::SIZE], IoctlError> {
+    arg.try_into().map_err(|_| IoctlError::InvalidArgument)
+}
@@ -0,0 +1,163 @@
+//! Pseudo-terminals, pairs of a master and a slave terminal.
+//!
+//! Opening `/dev/ptmx` allocates a master, whose slave shows up as
+//! `/dev/pts/N` and behaves like any other terminal. What the master writes
+//! is input for the slave, and what the slave outputs, echo included, is
+//! what the master reads. Closing the master hangs up the slave and removes
+//! `/dev/pts/N`.
+
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::format;
+use alloc::sync::Arc;
+
+use kernel_abi::{IoctlRequest, PollEvents};
+use kernel_devfs::DevFile;
+use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+use kernel_vfs::{IoctlError, OpenError, ReadError, Stat, StatError, WriteError};
+use spin::Mutex;
+use tracing::{debug, info};
+
+use super::{Tty, TtyFile, terminal_ioctl};
+use crate::file::INPUT_WAITERS;
+use crate::file::devfs::devfs;
+
+/// The `N`s of the `/dev/pts/N` that exist.
+static PTY_NUMBERS: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// How much output of the slave the master buffers. Once it's full, slave
+/// writes wait for the master to read, and only echo is dropped.
+const MASTER_BUFFER_SIZE: usize = 4096;
+
+pub fn init() {
+    let mut devfs = devfs().write();
+    devfs
+        .register_directory(AbsolutePath::try_new("/pts").unwrap())
+        .expect("should be able to register /dev/pts");
+    devfs
+        .register_file(AbsolutePath::try_new("/ptmx").unwrap(), PtyMaster::open)
+        .expect("should be able to register /dev/ptmx");
+}
+
+/// The master side of a pseudo-terminal, which owns the pair.
+struct PtyMaster {
+    number: u32,
+    slave_path: AbsoluteOwnedPath,
+    tty: Arc<Tty>,
+    output: Arc<Mutex<VecDeque<u8>>>,
+}
+
+impl PtyMaster {
+    fn open() -> Result<Self, OpenError> {
+        let number = {
+            let mut numbers = PTY_NUMBERS.lock();
+            let number = (0..)
+                .find(|n| !numbers.contains(n))
+                .expect("should have a free pty number");
+            numbers.insert(number);
+            number
+        };
+
+        let output = Arc::new(Mutex::new(VecDeque::new()));
+        let tty = Tty::new(format!("pts/{number}"), {
+            let output = output.clone();
+            move |bytes: &[u8]| {
+                let mut output = output.lock();
+                let room = MASTER_BUFFER_SIZE.saturating_sub(output.len());
+                output.extend(bytes.iter().take(room));
+                INPUT_WAITERS.wake_all();
+            }
+        })
+        .with_output_room({
+            let output = output.clone();
+            move || MASTER_BUFFER_SIZE.saturating_sub(output.lock().len())
+        });
+        let tty = Arc::new(tty);
+
+        let slave_path = AbsoluteOwnedPath::try_from(format!("/pts/{number}").as_str())
+            .expect("should be a valid path");
+        let registered = devfs().write().register_file(slave_path.as_ref(), {
+            let tty = tty.clone();
+            move || Ok(TtyFile::open(tty.clone()))
+        });
+        if registered.is_err() {
+            PTY_NUMBERS.lock().remove(&number);
+            return Err(OpenError::NotFound);
+        }
+
+        info!("allocated /dev/pts/{number}");
+        Ok(Self {
+            number,
+            slave_path,
+            tty,
+            output,
+        })
+    }
+}
+
+impl Drop for PtyMaster {
+    fn drop(&mut self) {
+        let _ = devfs().write().unregister_file(self.slave_path.as_ref());
+        PTY_NUMBERS.lock().remove(&self.number);
+        self.tty.hang_up();
+        debug!("released /dev/pts/{}", self.number);
+    }
+}
+
+impl DevFile for PtyMaster {
+    /// Returns [`ReadError::WouldBlock`] while the slave has no output.
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        let mut output = self.output.lock();
+        if output.is_empty() && !buf.is_empty() {
+            return Err(ReadError::WouldBlock);
+        }
+        let read = buf.len().min(output.len());
+        for (dst, src) in buf.iter_mut().zip(output.drain(..read)) {
+            *dst = src;
+        }
+        drop(output);
+        // a slave write may be waiting for room
+        INPUT_WAITERS.wake_all();
+        Ok(read)
+    }
+
+    /// Passes the bytes to the slave as if they were typed.
+    fn write(&mut self, buf: &[u8], _: usize) -> Result<usize, WriteError> {
+        self.tty.receive(buf);
+        INPUT_WAITERS.wake_all();
+        Ok(buf.len())
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.size = 0;
+        Ok(())
+    }
+
+    /// The terminal ioctls on the master act on the slave, without job
+    /// control, since the master is nobody's controlling terminal.
+    fn ioctl(&mut self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
+        match request {
+            IoctlRequest::PtyGetNumber => Ok(self.number as usize),
+            IoctlRequest::TcGetAttr
+            | IoctlRequest::TcSetAttr
+            | IoctlRequest::TcGetPgrp
+            | IoctlRequest::TcGetWinSize
+            | IoctlRequest::TcSetWinSize => terminal_ioctl(&self.tty, request, arg),
+            IoctlRequest::TcSetPgrp
+            | IoctlRequest::FbGetScreenInfo
+            | IoctlRequest::LoopAttach
+            | IoctlRequest::LoopDetach
+            | IoctlRequest::InputGetName
+            | IoctlRequest::InputGetCapabilities
+            | IoctlRequest::InputGetAbsInfo => Err(IoctlError::NotSupported),
+        }
+    }
+
+    /// Writes never block, so the master is always writable.
+    fn poll(&mut self, events: PollEvents) -> PollEvents {
+        let mut ready = PollEvents::POLLOUT;
+        if !self.output.lock().is_empty() {
+            ready |= PollEvents::POLLIN;
+        }
+        ready & events
+    }
+}
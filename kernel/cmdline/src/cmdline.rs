@@ -0,0 +1,132 @@
+/// The kernel cmdline, like `root=/dev/blk1 ro init=/bin/init -- -v`.
+///
+/// The words up to `--` are for the kernel, either `key=value` or flags
+/// like `ro`, and the words after it are the arguments for init.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Cmdline<'a> {
+    cmdline: &'a str,
+}
+
+impl<'a> From<&'a str> for Cmdline<'a> {
+    fn from(v: &'a str) -> Self {
+        Self { cmdline: v }
+    }
+}
+
+impl Cmdline<'_> {
+    /// The arguments for the kernel, which are everything up to `--`.
+    fn words(&self) -> impl Iterator<Item = &str> {
+        self.cmdline
+            .split_ascii_whitespace()
+            .take_while(|word| *word != "--")
+    }
+
+    /// Returns the value of the last `key=value`, so that a key that was
+    /// appended to the cmdline overrides an earlier one, like on Linux.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.words()
+            .filter_map(|word| word.split_once('='))
+            .filter(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+            .last()
+    }
+
+    /// Returns whether the root file system should be mounted read-only,
+    /// which is the case if `ro` comes after the last `rw`.
+    #[must_use]
+    pub fn read_only(&self) -> bool {
+        self.words()
+            .filter(|word| matches!(*word, "ro" | "rw"))
+            .last()
+            == Some("ro")
+    }
+
+    /// Returns the arguments for init, which are everything after `--`.
+    pub fn init_args(&self) -> impl Iterator<Item = &str> {
+        self.cmdline
+            .split_ascii_whitespace()
+            .skip_while(|word| *word != "--")
+            .skip(1)
+    }
+}
+
+macro_rules! arg {
+    ($($name:ident : $key:literal),*,) => {
+        impl Cmdline<'_> {
+            $(
+                #[must_use]
+                pub fn $name(&self) -> Option<&str> {
+                    self.get($key)
+                }
+            )*
+        }
+    };
+}
+
+arg! {
+    rust_log: "RUST_LOG",
+    init: "init",
+    root: "root",
+    rootfstype: "rootfstype",
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        for (cmdline, root) in [
+            ("", None),
+            ("root=/dev/blk1", Some("/dev/blk1")),
+            (
+                "ro root=UUID=774b94ce init=/bin/init",
+                Some("UUID=774b94ce"),
+            ),
+            ("root=/dev/blk1 root=/dev/blk2", Some("/dev/blk2")),
+            ("root= rw", Some("")),
+            ("root", None),
+            ("rootfstype=ext2", None),
+            ("-- root=/dev/blk1", None),
+            ("root=/dev/blk1 -- root=/dev/blk2", Some("/dev/blk1")),
+        ] {
+            assert_eq!(root, Cmdline::from(cmdline).root(), "{cmdline}");
+        }
+    }
+
+    #[test]
+    fn test_read_only() {
+        for (cmdline, read_only) in [
+            ("", false),
+            ("ro", true),
+            ("rw", false),
+            ("ro rw", false),
+            ("rw ro", true),
+            ("ro root=/dev/blk0 rw init=/bin/init ro", true),
+            ("ro=1 rw=0", false),
+            ("rw -- ro", false),
+        ] {
+            assert_eq!(read_only, Cmdline::from(cmdline).read_only(), "{cmdline}");
+        }
+    }
+
+    #[test]
+    fn test_init_args() {
+        for (cmdline, init, args) in [
+            ("init=/bin/sh", Some("/bin/sh"), &[][..]),
+            ("init=/bin/sh --", Some("/bin/sh"), &[]),
+            (
+                "init=/bin/sh -- -v  /etc/rc",
+                Some("/bin/sh"),
+                &["-v", "/etc/rc"],
+            ),
+            ("-- init=/bin/sh -- b", None, &["init=/bin/sh", "--", "b"]),
+        ] {
+            let cmdline = Cmdline::from(cmdline);
+            assert_eq!(init, cmdline.init());
+            assert_eq!(args, cmdline.init_args().collect::<Vec<_>>().as_slice());
+        }
+    }
+}
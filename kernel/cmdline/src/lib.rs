@@ -0,0 +1,16 @@
+//! The kernel cmdline that the bootloader passes, and what its arguments
+//! select.
+//!
+//! A [`Cmdline`] splits the arguments for the kernel from the ones for init
+//! at `--`, and [`RootSource`] parses `root=`. Reading the cmdline from the
+//! bootloader and acting on it is up to the kernel.
+
+#![no_std]
+
+extern crate alloc;
+
+mod cmdline;
+mod root;
+
+pub use cmdline::*;
+pub use root::*;
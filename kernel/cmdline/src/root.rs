@@ -0,0 +1,112 @@
+use alloc::format;
+use alloc::string::String;
+
+use kernel_device::block::Guid;
+use kernel_ext2::Ext2FsId;
+use kernel_vfs::path::AbsoluteOwnedPath;
+use thiserror::Error;
+
+/// Where the root file system comes from, as given by `root=` on the
+/// kernel cmdline.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RootSource {
+    /// `root=initramfs`, the initramfs that the bootloader passed.
+    Initramfs,
+    /// `root=/dev/blk1` or `root=/dev/blk0p2`, or just the id of a whole
+    /// device, like `root=1`.
+    Device(AbsoluteOwnedPath),
+    /// `root=UUID=774b94ce-3d05-4b71-98fc-fcf637fd2b48`, the block device
+    /// that holds the ext2 file system with that UUID.
+    Uuid(Ext2FsId),
+    /// `root=PARTUUID=0fc63daf-8483-4772-8e79-3d69d8477de4`, the GPT
+    /// partition with that unique GUID.
+    PartUuid(Guid),
+    /// `root=PARTLABEL=root`, the GPT partition with that name.
+    PartLabel(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[error("invalid root={0}")]
+pub struct InvalidRootError(pub String);
+
+impl TryFrom<&str> for RootSource {
+    type Error = InvalidRootError;
+
+    fn try_from(root: &str) -> Result<Self, Self::Error> {
+        let invalid = || InvalidRootError(root.into());
+        if root == "initramfs" {
+            Ok(Self::Initramfs)
+        } else if let Some(uuid) = root.strip_prefix("UUID=") {
+            uuid.parse().map(Self::Uuid).map_err(|_| invalid())
+        } else if let Some(guid) = root.strip_prefix("PARTUUID=") {
+            guid.parse().map(Self::PartUuid).map_err(|_| invalid())
+        } else if let Some(label) = root.strip_prefix("PARTLABEL=") {
+            Ok(Self::PartLabel(label.into()))
+        } else if let Ok(id) = root.parse::<u64>() {
+            Ok(Self::device(id))
+        } else {
+            AbsoluteOwnedPath::try_from(root)
+                .map(Self::Device)
+                .map_err(|_| invalid())
+        }
+    }
+}
+
+impl RootSource {
+    /// The whole block device with the id `id`, `/dev/blkN`.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)] // the path is always valid
+    pub fn device(id: u64) -> Self {
+        Self::Device(AbsoluteOwnedPath::try_from(format!("/dev/blk{id}").as_str()).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(path: &str) -> RootSource {
+        RootSource::Device(AbsoluteOwnedPath::try_from(path).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        let uuid = RootSource::Uuid("774b94ce-3d05-4b71-98fc-fcf637fd2b48".parse().unwrap());
+        let guid = "0fc63daf-8483-4772-8e79-3d69d8477de4".parse().unwrap();
+        for (root, source) in [
+            ("initramfs", RootSource::Initramfs),
+            ("/dev/blk1", device("/dev/blk1")),
+            ("/dev/blk0p2", device("/dev/blk0p2")),
+            ("3", device("/dev/blk3")),
+            ("UUID=774b94ce-3d05-4b71-98fc-fcf637fd2b48", uuid.clone()),
+            ("UUID=774B94CE3D054B7198FCFCF637FD2B48", uuid),
+            (
+                "PARTUUID=0fc63daf-8483-4772-8e79-3d69d8477de4",
+                RootSource::PartUuid(guid),
+            ),
+            ("PARTLABEL=root", RootSource::PartLabel("root".into())),
+            ("PARTLABEL=", RootSource::PartLabel(String::new())),
+        ] {
+            assert_eq!(Ok(source), RootSource::try_from(root), "{root}");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for root in [
+            "",
+            "blk1",
+            "-1",
+            "UUID=",
+            "UUID=774b94ce",
+            "PARTUUID=root",
+            "uuid=774b94ce-3d05-4b71-98fc-fcf637fd2b48",
+        ] {
+            assert_eq!(
+                Err(InvalidRootError(root.into())),
+                RootSource::try_from(root),
+                "{root}"
+            );
+        }
+    }
+}
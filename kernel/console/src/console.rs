@@ -0,0 +1,663 @@
+use crate::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::framebuffer::{Framebuffer, Rgb};
+use crate::parser::{Action, Csi, Parser};
+
+/// The width of a character cell in pixels.
+pub const CELL_WIDTH: usize = GLYPH_WIDTH;
+/// The height of a character cell in pixels. Every row of a glyph is drawn
+/// twice, which gives the proportions of a VGA text mode.
+pub const CELL_HEIGHT: usize = GLYPH_HEIGHT * 2;
+
+const TAB_WIDTH: usize = 8;
+
+/// The colors of the Linux console, the eight normal ones first and then
+/// their bright variants.
+const PALETTE: [Rgb; 16] = [
+    Rgb::new(0x00, 0x00, 0x00),
+    Rgb::new(0xaa, 0x00, 0x00),
+    Rgb::new(0x00, 0xaa, 0x00),
+    Rgb::new(0xaa, 0x55, 0x00),
+    Rgb::new(0x00, 0x00, 0xaa),
+    Rgb::new(0xaa, 0x00, 0xaa),
+    Rgb::new(0x00, 0xaa, 0xaa),
+    Rgb::new(0xaa, 0xaa, 0xaa),
+    Rgb::new(0x55, 0x55, 0x55),
+    Rgb::new(0xff, 0x55, 0x55),
+    Rgb::new(0x55, 0xff, 0x55),
+    Rgb::new(0xff, 0xff, 0x55),
+    Rgb::new(0x55, 0x55, 0xff),
+    Rgb::new(0xff, 0x55, 0xff),
+    Rgb::new(0x55, 0xff, 0xff),
+    Rgb::new(0xff, 0xff, 0xff),
+];
+const DEFAULT_FOREGROUND: u8 = 7;
+const DEFAULT_BACKGROUND: u8 = 0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Color {
+    /// One of the 256 colors of xterm.
+    Indexed(u8),
+    Rgb(Rgb),
+}
+
+impl Color {
+    fn rgb(self) -> Rgb {
+        match self {
+            Self::Indexed(index) => indexed(index),
+            Self::Rgb(rgb) => rgb,
+        }
+    }
+}
+
+/// The 256 colors of xterm: the palette, a 6x6x6 color cube and a ramp of
+/// grays.
+fn indexed(index: u8) -> Rgb {
+    const LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+    match index {
+        0..16 => PALETTE[usize::from(index)],
+        16..232 => {
+            let cube = usize::from(index - 16);
+            Rgb::new(LEVELS[cube / 36], LEVELS[cube / 6 % 6], LEVELS[cube % 6])
+        }
+        232.. => {
+            let gray = 8 + (index - 232) * 10;
+            Rgb::new(gray, gray, gray)
+        }
+    }
+}
+
+/// How characters are drawn, as set with the SGR control sequence.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Attributes {
+    foreground: Color,
+    background: Color,
+    bold: bool,
+    dim: bool,
+    reverse: bool,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            foreground: Color::Indexed(DEFAULT_FOREGROUND),
+            background: Color::Indexed(DEFAULT_BACKGROUND),
+            bold: false,
+            dim: false,
+            reverse: false,
+        }
+    }
+}
+
+impl Attributes {
+    /// The foreground and background color of a character.
+    fn colors(self) -> (Rgb, Rgb) {
+        let mut foreground = match self.foreground {
+            // bold brightens the normal colors, like on the Linux console
+            Color::Indexed(index @ 0..8) if self.bold => PALETTE[usize::from(index) + 8],
+            color => color.rgb(),
+        };
+        if self.dim {
+            foreground = Rgb::new(foreground.r / 2, foreground.g / 2, foreground.b / 2);
+        }
+        let background = self.background.rgb();
+        if self.reverse {
+            (background, foreground)
+        } else {
+            (foreground, background)
+        }
+    }
+
+    /// Applies the parameters of a SGR control sequence. Unknown ones are
+    /// skipped.
+    fn apply(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Self::default();
+            return;
+        }
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                7 => self.reverse = true,
+                22 => (self.bold, self.dim) = (false, false),
+                27 => self.reverse = false,
+                30..=37 => self.foreground = Color::Indexed(color_index(param - 30)),
+                38 => {
+                    if let Some(color) = extended_color(&mut params) {
+                        self.foreground = color;
+                    }
+                }
+                39 => self.foreground = Color::Indexed(DEFAULT_FOREGROUND),
+                40..=47 => self.background = Color::Indexed(color_index(param - 40)),
+                48 => {
+                    if let Some(color) = extended_color(&mut params) {
+                        self.background = color;
+                    }
+                }
+                49 => self.background = Color::Indexed(DEFAULT_BACKGROUND),
+                90..=97 => self.foreground = Color::Indexed(color_index(param - 90 + 8)),
+                100..=107 => self.background = Color::Indexed(color_index(param - 100 + 8)),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn color_index(param: u16) -> u8 {
+    u8::try_from(param).unwrap_or(u8::MAX)
+}
+
+/// The color after a 38 or 48 parameter: `5;index` or `2;r;g;b`.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match params.next()? {
+        5 => params
+            .next()
+            .map(|index| Color::Indexed(color_index(index))),
+        2 => {
+            let mut channel = || params.next().map(color_index);
+            Some(Color::Rgb(Rgb::new(channel()?, channel()?, channel()?)))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct SavedCursor {
+    column: usize,
+    row: usize,
+    attributes: Attributes,
+}
+
+/// A text terminal that draws on a framebuffer. It keeps no text of its
+/// own: scrolling moves the pixels, and the cursor is shown by inverting
+/// the cell under it.
+#[derive(Debug)]
+pub struct Console<'a> {
+    fb: Framebuffer<'a>,
+    parser: Parser,
+    columns: usize,
+    rows: usize,
+    /// Equal to `columns` after a character was written into the last
+    /// column, so that the line only wraps when the next one arrives.
+    column: usize,
+    row: usize,
+    saved: SavedCursor,
+    attributes: Attributes,
+    cursor_visible: bool,
+}
+
+impl<'a> Console<'a> {
+    /// Clears `fb` and puts the cursor in the top left corner. Returns
+    /// `None` if not a single cell fits on it.
+    #[must_use]
+    pub fn new(fb: Framebuffer<'a>) -> Option<Self> {
+        let columns = fb.width() / CELL_WIDTH;
+        let rows = fb.height() / CELL_HEIGHT;
+        if columns == 0 || rows == 0 {
+            return None;
+        }
+        let mut console = Self {
+            fb,
+            parser: Parser::new(),
+            columns,
+            rows,
+            column: 0,
+            row: 0,
+            saved: SavedCursor::default(),
+            attributes: Attributes::default(),
+            cursor_visible: true,
+        };
+        let (width, height) = (console.fb.width(), console.fb.height());
+        console
+            .fb
+            .fill(0, 0, width, height, console.attributes.colors().1);
+        console.toggle_cursor();
+        Some(console)
+    }
+
+    #[must_use]
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Writes characters and escape sequences. A line feed only moves down,
+    /// translating it into a new line is up to the terminal above.
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.toggle_cursor();
+        let mut parser = core::mem::take(&mut self.parser);
+        for &byte in bytes {
+            parser.advance(byte, |action| self.perform(action));
+        }
+        self.parser = parser;
+        self.toggle_cursor();
+    }
+
+    /// Shows or hides the cursor, since inverting the cell twice restores
+    /// it.
+    fn toggle_cursor(&mut self) {
+        if self.cursor_visible {
+            let (x, y) = Self::cell_position(self.cursor_column(), self.row);
+            self.fb.invert(x, y, CELL_WIDTH, CELL_HEIGHT);
+        }
+    }
+
+    fn cell_position(column: usize, row: usize) -> (usize, usize) {
+        (column * CELL_WIDTH, row * CELL_HEIGHT)
+    }
+
+    /// The column of the cursor, which stays in the last one while a wrap
+    /// is pending.
+    fn cursor_column(&self) -> usize {
+        self.column.min(self.columns - 1)
+    }
+
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Print(c) => self.print(c),
+            Action::Execute(byte) => self.execute(byte),
+            Action::Escape(byte) => self.escape(byte),
+            Action::Csi(csi) if csi.private => self.private_csi(&csi),
+            Action::Csi(csi) => self.csi(&csi),
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.column == self.columns {
+            self.column = 0;
+            self.line_feed();
+        }
+        let (foreground, background) = self.attributes.colors();
+        let (foreground, background) = (
+            self.fb.format().pixel(foreground),
+            self.fb.format().pixel(background),
+        );
+        let (x, y) = Self::cell_position(self.column, self.row);
+        for (glyph_y, &bits) in font::glyph(c).iter().enumerate() {
+            let pixels = (0..GLYPH_WIDTH).map(|glyph_x| {
+                if bits & (0x80 >> glyph_x) == 0 {
+                    background
+                } else {
+                    foreground
+                }
+            });
+            self.fb.write_row(x, y + glyph_y * 2, pixels.clone());
+            self.fb.write_row(x, y + glyph_y * 2 + 1, pixels);
+        }
+        self.column += 1;
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            // backspace
+            0x08 => self.column = self.cursor_column().saturating_sub(1),
+            b'\t' => {
+                self.column = ((self.column / TAB_WIDTH + 1) * TAB_WIDTH).min(self.columns - 1);
+            }
+            // line feed, vertical tab and form feed
+            b'\n' | 0x0b | 0x0c => {
+                self.column = self.cursor_column();
+                self.line_feed();
+            }
+            b'\r' => self.column = 0,
+            _ => {}
+        }
+    }
+
+    fn escape(&mut self, byte: u8) {
+        match byte {
+            b'7' => self.save_cursor(),
+            b'8' => self.restore_cursor(),
+            b'D' => self.line_feed(),
+            b'E' => {
+                self.column = 0;
+                self.line_feed();
+            }
+            b'M' => self.reverse_line_feed(),
+            b'c' => {
+                self.attributes = Attributes::default();
+                self.cursor_visible = true;
+                self.erase_rows(0, self.rows);
+                (self.column, self.row) = (0, 0);
+            }
+            _ => {}
+        }
+    }
+
+    fn csi(&mut self, csi: &Csi) {
+        let n = usize::from(csi.param(0, 1));
+        let last_column = self.columns - 1;
+        let last_row = self.rows - 1;
+        match csi.final_byte {
+            b'A' => (self.column, self.row) = (self.cursor_column(), self.row.saturating_sub(n)),
+            b'B' => (self.column, self.row) = (self.cursor_column(), (self.row + n).min(last_row)),
+            b'C' => self.column = (self.cursor_column() + n).min(last_column),
+            b'D' => self.column = self.cursor_column().saturating_sub(n),
+            b'E' => (self.column, self.row) = (0, (self.row + n).min(last_row)),
+            b'F' => (self.column, self.row) = (0, self.row.saturating_sub(n)),
+            b'G' | b'`' => self.column = (n - 1).min(last_column),
+            b'd' => self.row = (n - 1).min(last_row),
+            b'H' | b'f' => {
+                self.row = (n - 1).min(last_row);
+                self.column = usize::from(csi.param(1, 1) - 1).min(last_column);
+            }
+            b'J' => match csi.param(0, 0) {
+                0 => {
+                    self.erase_in_row(self.cursor_column(), self.columns);
+                    self.erase_rows(self.row + 1, self.rows);
+                }
+                1 => {
+                    self.erase_rows(0, self.row);
+                    self.erase_in_row(0, self.cursor_column() + 1);
+                }
+                2 | 3 => self.erase_rows(0, self.rows),
+                _ => {}
+            },
+            b'K' => match csi.param(0, 0) {
+                0 => self.erase_in_row(self.cursor_column(), self.columns),
+                1 => self.erase_in_row(0, self.cursor_column() + 1),
+                2 => self.erase_in_row(0, self.columns),
+                _ => {}
+            },
+            b'X' => {
+                let column = self.cursor_column();
+                self.erase_in_row(column, column + n);
+            }
+            b'S' => self.scroll_up(n),
+            b'T' => self.scroll_down(n),
+            b'm' => self.attributes.apply(csi.params()),
+            b's' => self.save_cursor(),
+            b'u' => self.restore_cursor(),
+            _ => {}
+        }
+    }
+
+    fn private_csi(&mut self, csi: &Csi) {
+        // the only private mode is the visibility of the cursor
+        if csi.params().contains(&25) {
+            match csi.final_byte {
+                b'h' => self.cursor_visible = true,
+                b'l' => self.cursor_visible = false,
+                _ => {}
+            }
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved = SavedCursor {
+            column: self.column,
+            row: self.row,
+            attributes: self.attributes,
+        };
+    }
+
+    fn restore_cursor(&mut self) {
+        self.column = self.saved.column;
+        self.row = self.saved.row;
+        self.attributes = self.saved.attributes;
+    }
+
+    fn line_feed(&mut self) {
+        if self.row + 1 == self.rows {
+            self.scroll_up(1);
+        } else {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_line_feed(&mut self) {
+        if self.row == 0 {
+            self.scroll_down(1);
+        } else {
+            self.row -= 1;
+        }
+    }
+
+    /// Moves the screen up by `n` rows, which makes room at the bottom.
+    fn scroll_up(&mut self, n: usize) {
+        let n = n.min(self.rows);
+        self.fb
+            .copy_rows(n * CELL_HEIGHT, 0, (self.rows - n) * CELL_HEIGHT);
+        self.erase_rows(self.rows - n, self.rows);
+    }
+
+    /// Moves the screen down by `n` rows, which makes room at the top.
+    fn scroll_down(&mut self, n: usize) {
+        let n = n.min(self.rows);
+        self.fb
+            .copy_rows(0, n * CELL_HEIGHT, (self.rows - n) * CELL_HEIGHT);
+        self.erase_rows(0, n);
+    }
+
+    /// Erases the rows from `start` up to `end`, with the background color
+    /// of the current attributes like xterm.
+    fn erase_rows(&mut self, start: usize, end: usize) {
+        let (x, y) = Self::cell_position(0, start);
+        let width = self.columns * CELL_WIDTH;
+        let background = self.attributes.background.rgb();
+        self.fb
+            .fill(x, y, width, (end - start) * CELL_HEIGHT, background);
+    }
+
+    /// Erases the cells of the cursor row from column `start` up to `end`.
+    fn erase_in_row(&mut self, start: usize, end: usize) {
+        let end = end.min(self.columns);
+        let (x, y) = Self::cell_position(start, self.row);
+        let background = self.attributes.background.rgb();
+        self.fb
+            .fill(x, y, (end - start) * CELL_WIDTH, CELL_HEIGHT, background);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::PixelFormat;
+
+    const COLUMNS: usize = 4;
+    const ROWS: usize = 3;
+    const PITCH: usize = COLUMNS * CELL_WIDTH * 4;
+
+    fn buffer() -> Vec<u8> {
+        vec![0xee; PITCH * ROWS * CELL_HEIGHT]
+    }
+
+    /// A console with the cursor hidden, so that the cells can be read back.
+    fn console(buf: &mut [u8]) -> Console<'_> {
+        let fb = Framebuffer::new(
+            buf,
+            COLUMNS * CELL_WIDTH,
+            ROWS * CELL_HEIGHT,
+            PITCH,
+            PixelFormat::BGRX,
+        )
+        .unwrap();
+        let mut console = Console::new(fb).unwrap();
+        console.write(b"\x1b[?25l");
+        console
+    }
+
+    /// The character in a cell and its foreground color, by comparing the
+    /// cell with every glyph. The glyphs in these tests cover less than
+    /// half of their cell, so the most common color is the background.
+    fn cell(console: &Console, column: usize, row: usize) -> (char, u32) {
+        let (x, y) = Console::cell_position(column, row);
+        let pixels = (0..CELL_HEIGHT)
+            .flat_map(|dy| (0..CELL_WIDTH).map(move |dx| (dx, dy)))
+            .map(|(dx, dy)| console.fb.pixel(x + dx, y + dy))
+            .collect::<Vec<_>>();
+        let background = pixels
+            .iter()
+            .copied()
+            .max_by_key(|&pixel| pixels.iter().filter(|&&other| other == pixel).count())
+            .unwrap();
+        let foreground = pixels
+            .iter()
+            .copied()
+            .find(|&pixel| pixel != background)
+            .unwrap_or(background);
+        let c = (' '..='~')
+            .find(|&c| {
+                let glyph = font::glyph(c);
+                pixels.iter().enumerate().all(|(i, &pixel)| {
+                    let (dx, dy) = (i % CELL_WIDTH, i / CELL_WIDTH);
+                    let set = glyph[dy / 2] & (0x80 >> dx) != 0;
+                    pixel == if set { foreground } else { background }
+                })
+            })
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        (c, foreground)
+    }
+
+    fn text(console: &Console) -> Vec<String> {
+        (0..ROWS)
+            .map(|row| {
+                (0..COLUMNS)
+                    .map(|column| cell(console, column, row).0)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn pixel(color: Rgb) -> u32 {
+        PixelFormat::BGRX.pixel(color)
+    }
+
+    #[test]
+    fn test_new_clears() {
+        let mut buf = buffer();
+        let console = console(&mut buf);
+        assert_eq!((console.columns(), console.rows()), (COLUMNS, ROWS));
+        assert_eq!(text(&console), ["    "; ROWS]);
+        assert_eq!(console.fb.pixel(0, 0), 0);
+
+        let mut small = [0; 4];
+        let fb = Framebuffer::new(&mut small, 1, 1, 4, PixelFormat::BGRX).unwrap();
+        assert!(Console::new(fb).is_none());
+    }
+
+    #[test]
+    fn test_wrap_and_scroll() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"abcd");
+        assert_eq!(
+            (console.column, console.row),
+            (COLUMNS, 0),
+            "the wrap waits for the next character"
+        );
+        console.write(b"efgh\r\nij\r\nkl");
+        assert_eq!(text(&console), ["efgh", "ij  ", "kl  "]);
+        assert_eq!((console.column, console.row), (2, 2));
+    }
+
+    #[test]
+    fn test_controls() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"ab\x08c\tx\ny\rz");
+        assert_eq!(text(&console), ["ac x", "z  y", "    "]);
+    }
+
+    #[test]
+    fn test_cursor_movement_and_erase() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"abcd\r\nefgh\r\nijkl");
+        console.write(b"\x1b[2;3H\x1b[K");
+        assert_eq!(text(&console), ["abcd", "ef  ", "ijkl"]);
+        console.write(b"\x1b[A\x1b[1J");
+        assert_eq!(text(&console), ["   d", "ef  ", "ijkl"]);
+        console.write(b"\x1b[9;9HX\x1b[H\x1b[2B\x1b[CY");
+        assert_eq!(text(&console), ["   d", "ef  ", "iYkX"]);
+        console.write(b"\x1b[2J");
+        assert_eq!(text(&console), ["    "; ROWS]);
+    }
+
+    #[test]
+    fn test_save_restore_and_reverse_index() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"a\x1b7\x1b[3;1Hb\x1b8c\x1b[H\x1bMd");
+        assert_eq!(text(&console), ["d   ", "ac  ", "    "]);
+        console.write(b"\x1b[S");
+        assert_eq!(text(&console), ["ac  ", "    ", "    "]);
+    }
+
+    #[test]
+    fn test_colors() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"a\x1b[31mb\x1b[1mc\x1b[0;38;5;196md");
+        console.write(b"\x1b[38;2;1;2;3me\x1b[94mf\x1b[2;39mg\x1b[m");
+        let colors = [
+            PALETTE[7],
+            PALETTE[1],
+            PALETTE[9],
+            Rgb::new(0xff, 0, 0),
+            Rgb::new(1, 2, 3),
+            PALETTE[12],
+            Rgb::new(0x55, 0x55, 0x55),
+        ];
+        for (i, color) in colors.into_iter().enumerate() {
+            assert_eq!(
+                cell(&console, i % COLUMNS, i / COLUMNS).1,
+                pixel(color),
+                "cell {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_background_and_reverse() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"\x1b[44m \x1b[0;7m ");
+        let (x, y) = Console::cell_position(0, 0);
+        assert_eq!(console.fb.pixel(x, y), pixel(PALETTE[4]));
+        let (x, y) = Console::cell_position(1, 0);
+        assert_eq!(console.fb.pixel(x, y), pixel(PALETTE[7]));
+        console.write(b"\x1b[44m\x1b[2K");
+        let (x, y) = Console::cell_position(1, 1);
+        assert_eq!(
+            console.fb.pixel(x, y),
+            0,
+            "erasing doesn't reach other rows"
+        );
+        let (x, y) = Console::cell_position(3, 0);
+        assert_eq!(
+            console.fb.pixel(x, y),
+            pixel(PALETTE[4]),
+            "erasing uses the background"
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut buf = buffer();
+        let mut console = console(&mut buf);
+        console.write(b"\x1b[?25h");
+        assert_eq!(
+            console.fb.pixel(0, 0),
+            0x00ff_ffff,
+            "the cursor is inverted"
+        );
+        console.write(b"a");
+        assert_eq!(console.fb.pixel(0, 0), 0, "it moves on");
+        assert_eq!(cell(&console, 0, 0).0, 'a');
+        let (x, y) = Console::cell_position(1, 0);
+        assert_eq!(console.fb.pixel(x, y), 0x00ff_ffff);
+        console.write(b"\x1b[?25l");
+        assert_eq!(console.fb.pixel(x, y), 0);
+    }
+}
@@ -0,0 +1,206 @@
+/// Bytes per pixel. The console only draws on 32 bit framebuffers.
+pub const BYTES_PER_PIXEL: usize = 4;
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    #[must_use]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+/// Where the 8 bit color channels are in a 32 bit pixel, as the shift of
+/// each.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PixelFormat {
+    pub red_shift: u8,
+    pub green_shift: u8,
+    pub blue_shift: u8,
+}
+
+impl PixelFormat {
+    /// Blue in the lowest byte, which is what most firmware sets up.
+    pub const BGRX: Self = Self {
+        red_shift: 16,
+        green_shift: 8,
+        blue_shift: 0,
+    };
+
+    #[must_use]
+    pub const fn pixel(self, color: Rgb) -> u32 {
+        ((color.r as u32) << self.red_shift)
+            | ((color.g as u32) << self.green_shift)
+            | ((color.b as u32) << self.blue_shift)
+    }
+}
+
+/// Pixels in memory, row by row.
+#[derive(Debug)]
+pub struct Framebuffer<'a> {
+    buf: &'a mut [u8],
+    width: usize,
+    height: usize,
+    /// Bytes from the start of a row to the start of the next one.
+    pitch: usize,
+    format: PixelFormat,
+}
+
+impl<'a> Framebuffer<'a> {
+    /// Returns `None` if `buf` can't hold `height` rows of `pitch` bytes, or
+    /// a row can't hold `width` pixels.
+    #[must_use]
+    pub fn new(
+        buf: &'a mut [u8],
+        width: usize,
+        height: usize,
+        pitch: usize,
+        format: PixelFormat,
+    ) -> Option<Self> {
+        let fits = width
+            .checked_mul(BYTES_PER_PIXEL)
+            .is_some_and(|row| row <= pitch)
+            && pitch
+                .checked_mul(height)
+                .is_some_and(|len| len <= buf.len());
+        fits.then_some(Self {
+            buf,
+            width,
+            height,
+            pitch,
+            format,
+        })
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    fn offset(&self, x: usize, y: usize) -> usize {
+        y * self.pitch + x * BYTES_PER_PIXEL
+    }
+
+    /// The pixel at `x`, `y`, which must be on the screen.
+    #[must_use]
+    pub fn pixel(&self, x: usize, y: usize) -> u32 {
+        let offset = self.offset(x, y);
+        let bytes = self.buf[offset..offset + BYTES_PER_PIXEL]
+            .try_into()
+            .expect("a pixel is 4 bytes");
+        u32::from_ne_bytes(bytes)
+    }
+
+    /// Sets the pixels of one row, starting at `x`, `y`, which must all be
+    /// on the screen.
+    pub fn write_row(&mut self, x: usize, y: usize, pixels: impl IntoIterator<Item = u32>) {
+        let offset = self.offset(x, y);
+        let row = &mut self.buf[offset..offset + (self.width - x) * BYTES_PER_PIXEL];
+        for (dst, pixel) in row
+            .as_chunks_mut::<BYTES_PER_PIXEL>()
+            .0
+            .iter_mut()
+            .zip(pixels)
+        {
+            *dst = pixel.to_ne_bytes();
+        }
+    }
+
+    /// Fills a rectangle, which is clipped to the screen.
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        let pixel = self.format.pixel(color);
+        let width = width.min(self.width.saturating_sub(x));
+        for y in y..(y + height).min(self.height) {
+            self.write_row(x, y, core::iter::repeat_n(pixel, width));
+        }
+    }
+
+    /// Inverts the colors of a rectangle, which must be on the screen.
+    pub fn invert(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        let mask = self.format.pixel(Rgb::new(0xff, 0xff, 0xff));
+        for y in y..y + height {
+            let offset = self.offset(x, y);
+            let row = &mut self.buf[offset..offset + width * BYTES_PER_PIXEL];
+            for pixel in row.as_chunks_mut::<BYTES_PER_PIXEL>().0 {
+                *pixel = (u32::from_ne_bytes(*pixel) ^ mask).to_ne_bytes();
+            }
+        }
+    }
+
+    /// Copies `count` whole rows from `from` to `to`, which may overlap.
+    pub fn copy_rows(&mut self, from: usize, to: usize, count: usize) {
+        let len = count * self.pitch;
+        self.buf
+            .copy_within(from * self.pitch..from * self.pitch + len, to * self.pitch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_new_checks_size() {
+        let mut buf = vec![0; 4 * 10 * 4];
+        assert!(Framebuffer::new(&mut buf, 4, 10, 16, PixelFormat::BGRX).is_some());
+        assert!(Framebuffer::new(&mut buf, 5, 10, 16, PixelFormat::BGRX).is_none());
+        assert!(Framebuffer::new(&mut buf, 4, 11, 16, PixelFormat::BGRX).is_none());
+    }
+
+    #[test]
+    fn test_pixel_format() {
+        let color = Rgb::new(0x11, 0x22, 0x33);
+        assert_eq!(PixelFormat::BGRX.pixel(color), 0x0011_2233);
+        let rgbx = PixelFormat {
+            red_shift: 0,
+            green_shift: 8,
+            blue_shift: 16,
+        };
+        assert_eq!(rgbx.pixel(color), 0x0033_2211);
+    }
+
+    #[test]
+    fn test_fill_clips_and_invert() {
+        // a pitch with padding after each row
+        let mut buf = vec![0; 5 * 4 * 3];
+        let mut fb = Framebuffer::new(&mut buf, 4, 3, 20, PixelFormat::BGRX).unwrap();
+        fb.fill(2, 1, 10, 10, Rgb::new(0, 0, 0xff));
+        assert_eq!(fb.pixel(1, 1), 0);
+        assert_eq!(fb.pixel(3, 2), 0xff);
+        fb.invert(0, 2, 4, 1);
+        assert_eq!(fb.pixel(0, 2), 0x00ff_ffff);
+        assert_eq!(fb.pixel(3, 2), 0x00ff_ff00);
+        // the padding stays untouched
+        assert!(buf[16..20].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_copy_rows() {
+        let mut buf = vec![0; 4 * 3];
+        let mut fb = Framebuffer::new(&mut buf, 1, 3, 4, PixelFormat::BGRX).unwrap();
+        for y in 0..3 {
+            fb.write_row(0, y, [y as u32 + 1]);
+        }
+        fb.copy_rows(1, 0, 2);
+        assert_eq!([fb.pixel(0, 0), fb.pixel(0, 1), fb.pixel(0, 2)], [2, 3, 3]);
+        fb.copy_rows(0, 1, 2);
+        assert_eq!([fb.pixel(0, 0), fb.pixel(0, 1), fb.pixel(0, 2)], [2, 2, 3]);
+    }
+}
@@ -0,0 +1,21 @@
+//! A text console on a framebuffer.
+//!
+//! A [`Console`] draws characters with the built-in [`font`] and follows
+//! the escape sequences of a VT100 style terminal, which a [`Parser`]
+//! splits the bytes that it is given into: cursor movement, erasing,
+//! scrolling and the colors of xterm. It needs no heap, so that the kernel
+//! can show its log before it has one.
+
+#![no_std]
+
+#[cfg(test)]
+extern crate alloc;
+
+mod console;
+pub mod font;
+mod framebuffer;
+mod parser;
+
+pub use console::*;
+pub use framebuffer::*;
+pub use parser::*;
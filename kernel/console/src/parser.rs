@@ -0,0 +1,330 @@
+//! The escape sequences of a VT100 style terminal.
+
+/// Parameters of a control sequence beyond this are dropped.
+const MAX_PARAMS: usize = 16;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+const DEL: u8 = 0x7f;
+
+/// What a byte stream asks the terminal to do.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    /// Show a character at the cursor. Invalid UTF-8 becomes
+    /// [`char::REPLACEMENT_CHARACTER`].
+    Print(char),
+    /// A C0 control character, like a line feed.
+    Execute(u8),
+    /// An escape sequence, `ESC` followed by its final byte.
+    Escape(u8),
+    /// A control sequence, `ESC [` followed by parameters and a final byte.
+    Csi(Csi),
+}
+
+/// The parameters and final byte of a control sequence.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Csi {
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    /// Whether the parameters started with `?`, which marks the private
+    /// modes of DEC terminals.
+    pub private: bool,
+    pub final_byte: u8,
+}
+
+impl Csi {
+    const fn new() -> Self {
+        Self {
+            params: [0; MAX_PARAMS],
+            len: 0,
+            private: false,
+            final_byte: 0,
+        }
+    }
+
+    /// The parameters as they were given. An empty one is 0.
+    #[must_use]
+    pub fn params(&self) -> &[u16] {
+        &self.params[..self.len]
+    }
+
+    /// The parameter at `index`, or `default` if it is missing or 0.
+    #[must_use]
+    pub fn param(&self, index: usize, default: u16) -> u16 {
+        match self.params().get(index) {
+            Some(&param) if param != 0 => param,
+            _ => default,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    /// An escape sequence with intermediate bytes, which is ignored.
+    EscapeIntermediate,
+    Csi,
+    /// A control sequence that the terminal can't make sense of, ignored
+    /// until its final byte.
+    CsiIgnore,
+    /// An operating system command, like setting the window title, which
+    /// is ignored until `BEL` or `ESC \`.
+    Osc,
+}
+
+/// Splits a byte stream into characters and control functions.
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+    csi: Csi,
+    /// Whether a digit was seen since the last parameter separator.
+    param_started: bool,
+    utf8: [u8; 4],
+    utf8_len: usize,
+    utf8_needed: usize,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            csi: Csi::new(),
+            param_started: false,
+            utf8: [0; 4],
+            utf8_len: 0,
+            utf8_needed: 0,
+        }
+    }
+
+    /// Feeds one byte and passes the action that it completes, if any, to
+    /// `perform`.
+    pub fn advance(&mut self, byte: u8, mut perform: impl FnMut(Action)) {
+        // like on a VT100, control characters act even in the middle of a
+        // sequence, and ESC starts a new one
+        if self.state != State::Ground && byte == ESC {
+            self.state = if self.state == State::Osc {
+                // the start of the string terminator
+                State::EscapeIntermediate
+            } else {
+                State::Escape
+            };
+            return;
+        }
+        match self.state {
+            State::Ground => self.ground(byte, perform),
+            State::Escape => match byte {
+                b'[' => {
+                    self.csi = Csi::new();
+                    self.param_started = false;
+                    self.state = State::Csi;
+                }
+                b']' => self.state = State::Osc,
+                0x20..=0x2f => self.state = State::EscapeIntermediate,
+                0x30..=0x7e => {
+                    self.state = State::Ground;
+                    perform(Action::Escape(byte));
+                }
+                _ => self.control(byte, perform),
+            },
+            State::EscapeIntermediate => match byte {
+                0x30..=0x7e => self.state = State::Ground,
+                _ => self.control(byte, perform),
+            },
+            State::Csi => self.csi(byte, perform),
+            State::CsiIgnore => match byte {
+                0x40..=0x7e => self.state = State::Ground,
+                _ => self.control(byte, perform),
+            },
+            State::Osc => {
+                if byte == BEL {
+                    self.state = State::Ground;
+                }
+            }
+        }
+    }
+
+    fn ground(&mut self, byte: u8, mut perform: impl FnMut(Action)) {
+        if self.utf8_needed > 0 {
+            if byte & 0xc0 == 0x80 {
+                self.utf8[self.utf8_len] = byte;
+                self.utf8_len += 1;
+                if self.utf8_len == self.utf8_needed {
+                    self.utf8_needed = 0;
+                    let c = core::str::from_utf8(&self.utf8[..self.utf8_len])
+                        .ok()
+                        .and_then(|s| s.chars().next())
+                        .unwrap_or(char::REPLACEMENT_CHARACTER);
+                    perform(Action::Print(c));
+                }
+                return;
+            }
+            // the sequence broke off
+            self.utf8_needed = 0;
+            perform(Action::Print(char::REPLACEMENT_CHARACTER));
+        }
+        match byte {
+            ESC => self.state = State::Escape,
+            0x00..=0x1f => perform(Action::Execute(byte)),
+            0x20..=0x7e => perform(Action::Print(char::from(byte))),
+            DEL => {}
+            0xc2..=0xf4 => {
+                self.utf8[0] = byte;
+                self.utf8_len = 1;
+                self.utf8_needed = match byte {
+                    0xc2..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+            }
+            _ => perform(Action::Print(char::REPLACEMENT_CHARACTER)),
+        }
+    }
+
+    fn csi(&mut self, byte: u8, mut perform: impl FnMut(Action)) {
+        match byte {
+            b'0'..=b'9' => {
+                if !self.param_started {
+                    if self.csi.len == MAX_PARAMS {
+                        return;
+                    }
+                    self.csi.len += 1;
+                    self.param_started = true;
+                }
+                let param = &mut self.csi.params[self.csi.len - 1];
+                *param = param
+                    .saturating_mul(10)
+                    .saturating_add(u16::from(byte - b'0'));
+            }
+            b';' | b':' => {
+                if !self.param_started && self.csi.len < MAX_PARAMS {
+                    // an empty parameter
+                    self.csi.len += 1;
+                }
+                self.param_started = false;
+            }
+            b'?' if self.csi.len == 0 && !self.param_started && !self.csi.private => {
+                self.csi.private = true;
+            }
+            0x40..=0x7e => {
+                self.csi.final_byte = byte;
+                self.state = State::Ground;
+                perform(Action::Csi(self.csi));
+            }
+            // other private markers and intermediate bytes
+            0x20..=0x3f => self.state = State::CsiIgnore,
+            _ => self.control(byte, perform),
+        }
+    }
+
+    /// A byte that interrupts a sequence. Controls act and the rest is
+    /// dropped.
+    fn control(&mut self, byte: u8, mut perform: impl FnMut(Action)) {
+        match byte {
+            // cancel the sequence
+            0x18 | 0x1a => self.state = State::Ground,
+            0x00..=0x1f => perform(Action::Execute(byte)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Vec<Action> {
+        let mut parser = Parser::new();
+        let mut actions = Vec::new();
+        for &byte in bytes {
+            parser.advance(byte, |action| actions.push(action));
+        }
+        actions
+    }
+
+    fn csi(bytes: &[u8]) -> Csi {
+        match parse(bytes)[..] {
+            [Action::Csi(csi)] => csi,
+            ref actions => panic!("expected one control sequence, got {actions:?}"),
+        }
+    }
+
+    #[test]
+    fn test_print_and_execute() {
+        assert_eq!(
+            parse(b"a\r\n\x7f"),
+            [
+                Action::Print('a'),
+                Action::Execute(b'\r'),
+                Action::Execute(b'\n'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf8() {
+        assert_eq!(
+            parse("é€😀".as_bytes()),
+            [Action::Print('é'), Action::Print('€'), Action::Print('😀'),]
+        );
+        assert_eq!(
+            parse(b"\xc3a\xff"),
+            [
+                Action::Print(char::REPLACEMENT_CHARACTER),
+                Action::Print('a'),
+                Action::Print(char::REPLACEMENT_CHARACTER),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csi_params() {
+        let sequence = csi(b"\x1b[1;31m");
+        assert_eq!(
+            (sequence.params(), sequence.final_byte),
+            (&[1, 31][..], b'm')
+        );
+        assert!(!sequence.private);
+
+        let sequence = csi(b"\x1b[;5H");
+        assert_eq!(sequence.params(), [0, 5]);
+        assert_eq!((sequence.param(0, 1), sequence.param(1, 1)), (1, 5));
+        assert_eq!(sequence.param(2, 7), 7);
+
+        let sequence = csi(b"\x1b[?25l");
+        assert!(sequence.private);
+        assert_eq!(sequence.params(), [25]);
+
+        assert_eq!(csi(b"\x1b[99999A").params(), [u16::MAX]);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(parse(b"\x1b7x"), [Action::Escape(b'7'), Action::Print('x')]);
+        // a character set designation is ignored
+        assert_eq!(parse(b"\x1b(Bx"), [Action::Print('x')]);
+    }
+
+    #[test]
+    fn test_ignored_sequences() {
+        // a window title, terminated both ways
+        assert_eq!(parse(b"\x1b]0;title\x07a"), [Action::Print('a')]);
+        assert_eq!(parse(b"\x1b]0;title\x1b\\a"), [Action::Print('a')]);
+        // a private marker that isn't understood
+        assert_eq!(parse(b"\x1b[>1ca"), [Action::Print('a')]);
+        // cancelled, with the control inside still acting
+        assert_eq!(
+            parse(b"\x1b[1\n\x18a"),
+            [Action::Execute(b'\n'), Action::Print('a')]
+        );
+    }
+}
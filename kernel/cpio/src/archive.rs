@@ -0,0 +1,294 @@
+use core::str;
+
+use kernel_abi::{S_IFDIR, S_IFLNK, S_IFMT, S_IFREG};
+use thiserror::Error;
+
+const MAGIC: &[u8] = b"070701";
+/// The magic of archives with checksums (`-H crc`), which are laid out like
+/// `newc` archives otherwise.
+const MAGIC_CRC: &[u8] = b"070702";
+const HEADER_LEN: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum CpioError {
+    #[error("invalid header magic")]
+    InvalidMagic,
+    #[error("invalid header field")]
+    InvalidHeader,
+    #[error("invalid file name")]
+    InvalidName,
+    #[error("the archive ends in the middle of an entry")]
+    Truncated,
+}
+
+/// A cpio archive in the `newc` format.
+///
+/// ```
+/// # use kernel_cpio::CpioArchive;
+/// assert!(CpioArchive::is_cpio(b"070701"));
+/// assert!(!CpioArchive::is_cpio(b"\x7fELF"));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct CpioArchive<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> CpioArchive<'a> {
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Returns whether `data` starts like a `newc` archive.
+    #[must_use]
+    pub fn is_cpio(data: &[u8]) -> bool {
+        data.starts_with(MAGIC) || data.starts_with(MAGIC_CRC)
+    }
+
+    /// Returns the entries of the archive up to the trailer. Iteration stops
+    /// after the first error.
+    #[must_use]
+    pub fn entries(&self) -> Entries<'a> {
+        Entries {
+            data: self.data,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    /// Device nodes, fifos and sockets.
+    Other,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Entry<'a> {
+    name: &'a str,
+    ino: u32,
+    mode: u32,
+    nlink: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Entry<'a> {
+    /// The path of the entry as stored in the archive, usually relative and
+    /// sometimes starting with `./`.
+    #[must_use]
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    #[must_use]
+    pub fn ino(&self) -> u32 {
+        self.ino
+    }
+
+    /// The file type (`S_IF*` from `kernel_abi`) and permission bits.
+    #[must_use]
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    #[must_use]
+    pub fn nlink(&self) -> u32 {
+        self.nlink
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> EntryKind {
+        match self.mode & S_IFMT {
+            S_IFREG => EntryKind::File,
+            S_IFDIR => EntryKind::Directory,
+            S_IFLNK => EntryKind::Symlink,
+            _ => EntryKind::Other,
+        }
+    }
+
+    /// The contents of a file, or the target of a symlink.
+    #[must_use]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+pub struct Entries<'a> {
+    data: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> Entries<'a> {
+    fn next_entry(&mut self) -> Result<Option<Entry<'a>>, CpioError> {
+        let header = self.take(HEADER_LEN)?;
+        if !CpioArchive::is_cpio(header) {
+            return Err(CpioError::InvalidMagic);
+        }
+        let field = |index: usize| {
+            let start = MAGIC.len() + index * 8;
+            let digits =
+                str::from_utf8(&header[start..start + 8]).map_err(|_| CpioError::InvalidHeader)?;
+            u32::from_str_radix(digits, 16).map_err(|_| CpioError::InvalidHeader)
+        };
+        let ino = field(0)?;
+        let mode = field(1)?;
+        let nlink = field(4)?;
+        let file_size = field(6)? as usize;
+        let name_size = field(11)? as usize;
+
+        let name = self.take(name_size)?;
+        let name = name
+            .strip_suffix(&[0])
+            .and_then(|name| str::from_utf8(name).ok())
+            .ok_or(CpioError::InvalidName)?;
+        self.align();
+        if name == TRAILER {
+            return Ok(None);
+        }
+
+        let data = self.take(file_size)?;
+        self.align();
+
+        Ok(Some(Entry {
+            name,
+            ino,
+            mode,
+            nlink,
+            data,
+        }))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CpioError> {
+        let end = self.offset.checked_add(len).ok_or(CpioError::Truncated)?;
+        let bytes = self
+            .data
+            .get(self.offset..end)
+            .ok_or(CpioError::Truncated)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Skips the padding that aligns headers and file data to 4 bytes.
+    fn align(&mut self) {
+        self.offset = self.offset.next_multiple_of(4);
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>, CpioError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_entry().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn push_entry(archive: &mut Vec<u8>, name: &str, mode: u32, data: &[u8]) {
+        let header = format!(
+            "070701{:08x}{mode:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+            archive.len(),
+            0,
+            0,
+            1,
+            0,
+            data.len(),
+            0,
+            0,
+            0,
+            0,
+            name.len() + 1,
+            0,
+        );
+        archive.extend_from_slice(header.as_bytes());
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize(archive.len().next_multiple_of(4), 0);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().next_multiple_of(4), 0);
+    }
+
+    fn archive() -> Vec<u8> {
+        let mut archive = Vec::new();
+        push_entry(&mut archive, ".", S_IFDIR | 0o755, &[]);
+        push_entry(&mut archive, "bin", S_IFDIR | 0o755, &[]);
+        push_entry(
+            &mut archive,
+            "bin/init",
+            S_IFREG | 0o755,
+            b"\x7fELF and more",
+        );
+        push_entry(&mut archive, "sbin", S_IFLNK | 0o777, b"bin");
+        push_entry(&mut archive, TRAILER, 0, &[]);
+        archive
+    }
+
+    #[test]
+    fn test_entries() {
+        let archive = archive();
+        assert!(CpioArchive::is_cpio(&archive));
+        let entries = CpioArchive::new(&archive)
+            .entries()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let names = entries.iter().map(Entry::name).collect::<Vec<_>>();
+        assert_eq!([".", "bin", "bin/init", "sbin"], names.as_slice());
+
+        let init = &entries[2];
+        assert_eq!(EntryKind::File, init.kind());
+        assert_eq!(0o755, init.mode() & !S_IFMT);
+        assert_eq!(b"\x7fELF and more", init.data());
+
+        assert_eq!(EntryKind::Directory, entries[1].kind());
+        assert_eq!(EntryKind::Symlink, entries[3].kind());
+        assert_eq!(b"bin", entries[3].data());
+    }
+
+    #[test]
+    fn test_stops_at_trailer() {
+        let mut archive = archive();
+        // anything after the trailer, like the padding to a full block that
+        // cpio writes, is ignored
+        archive.extend_from_slice(&[0; 512]);
+        assert_eq!(4, CpioArchive::new(&archive).entries().count());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let archive = archive();
+        let truncated = &archive[..archive.len() - 150];
+        let result = CpioArchive::new(truncated)
+            .entries()
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(Err(CpioError::Truncated), result);
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut archive = archive();
+        archive[0] = b'1';
+        let mut entries = CpioArchive::new(&archive).entries();
+        assert_eq!(Some(Err(CpioError::InvalidMagic)), entries.next());
+        assert_eq!(None, entries.next());
+    }
+}
@@ -0,0 +1,7 @@
+//! A reader for cpio archives in the "new ASCII" (`newc`) format, which is
+//! what `cpio -H newc` and most initramfs tooling produce.
+#![no_std]
+
+pub use archive::*;
+
+mod archive;
@@ -1,4 +1,4 @@
-use kernel_abi::IoctlRequest;
+use kernel_abi::{IoctlRequest, PollEvents};
 use kernel_vfs::{
     FsyncError, IoctlError, MmapError, MmapRegion, ReadError, Stat, StatError, WriteError,
 };
@@ -7,8 +7,6 @@ mod block;
 pub use block::*;
 mod null;
 pub use null::*;
-mod serial;
-pub use serial::*;
 mod zero;
 pub use zero::*;
 
@@ -45,4 +43,11 @@ pub trait DevFile: Send + Sync {
     fn ioctl(&mut self, _request: IoctlRequest, _arg: &mut [u8]) -> Result<usize, IoctlError> {
         Err(IoctlError::NotSupported)
     }
+
+    /// Returns which of `events` the device is ready for, plus
+    /// [`PollEvents::POLLERR`] and [`PollEvents::POLLHUP`] if they apply.
+    /// Default impl reports the device as always readable and writable.
+    fn poll(&mut self, events: PollEvents) -> PollEvents {
+        events & (PollEvents::POLLIN | PollEvents::POLLOUT)
+    }
 }
@@ -1,19 +1,22 @@
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
-use kernel_abi::IoctlRequest;
-use kernel_vfs::fs::{FileSystem, FsHandle};
+use kernel_abi::{IoctlRequest, PollEvents};
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
 use kernel_vfs::path::{AbsolutePath, ROOT};
 use kernel_vfs::{
     CloseError, FsError, FsyncError, IoctlError, MmapError, MmapRegion, OpenError, ReadError, Stat,
     StatError, WriteError,
 };
+use spin::Mutex;
 use thiserror::Error;
 
-use crate::node::{DevDirectoryNode, DevFileNode, DevNode, DevNodeKind};
+use crate::node::{DevDirectoryNode, DevFileNode, DevNode, DevNodeKind, DevOpenFn};
 use crate::{DevFile, Null, Zero};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -32,6 +35,20 @@ pub enum ResolveError {
     ParentNotDirectory,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum UnregisterError {
+    #[error("resolve: {0}")]
+    ResolveError(#[from] ResolveError),
+    #[error("there is no file at the specified path")]
+    NotFound,
+    #[error("the path is a directory")]
+    IsDirectory,
+}
+
+/// An open device file. Each one has its own lock, so that a slow or
+/// blocking file doesn't hold up the rest of the file system.
+pub type OpenDevFile = Arc<Mutex<Box<dyn DevFile>>>;
+
 impl From<ResolveError> for OpenError {
     fn from(_: ResolveError) -> Self {
         OpenError::NotFound
@@ -40,7 +57,8 @@ impl From<ResolveError> for OpenError {
 
 pub struct DevFs {
     root: DevNode,
-    open_files: BTreeMap<FsHandle, Box<dyn DevFile>>,
+    next_node_id: u64,
+    open_files: BTreeMap<FsHandle, OpenDevFile>,
 }
 
 impl Default for DevFs {
@@ -53,9 +71,11 @@ impl DevFs {
     pub fn new() -> Self {
         let mut v = Self {
             root: DevNode::new(
+                FsNodeId::from(0),
                 String::from("/"),
                 DevNodeKind::Directory(DevDirectoryNode::new()),
             ),
+            next_node_id: 1,
             open_files: BTreeMap::new(),
         };
 
@@ -81,6 +101,7 @@ impl DevFs {
         let parent = path.parent().unwrap_or(ROOT);
         let filename = path.file_name().ok_or(ResolveError::ParentNotFound)?;
 
+        let id = FsNodeId::from(self.next_node_id);
         let parent_node = self.resolve_node_mut(parent)?;
         let parent_dir = parent_node
             .directory_mut()
@@ -92,29 +113,110 @@ impl DevFs {
         }
 
         let file_node = DevNode::new(
+            id,
             filename.to_string(),
             DevNodeKind::File(DevFileNode::new(Box::new(move || {
                 open_fn().map(|file| Box::new(file) as Box<dyn DevFile>)
             }))),
         );
         parent_dir.children_mut().push(file_node);
+        self.next_node_id += 1;
         Ok(())
     }
 
-    fn resolve_node(&self, path: &AbsolutePath) -> Result<&DevNode, ResolveError> {
-        let mut current_node = &self.root;
-        for component in path.filenames() {
-            let dir_node = current_node
-                .directory()
-                .ok_or(ResolveError::ParentNotDirectory)?;
-            if let Some(child) = dir_node.lookup_child(component) {
-                current_node = child;
-            } else {
-                return Err(ResolveError::ParentNotFound);
-            }
+    /// Creates an empty directory at `path`, which files can be registered
+    /// in.
+    ///
+    /// # Errors
+    /// Returns an error if the parent of `path` doesn't exist or if `path`
+    /// exists already.
+    pub fn register_directory(&mut self, path: &AbsolutePath) -> Result<(), RegisterError> {
+        let parent = path.parent().unwrap_or(ROOT);
+        let name = path.file_name().ok_or(ResolveError::ParentNotFound)?;
+
+        let id = FsNodeId::from(self.next_node_id);
+        let parent_dir = self
+            .resolve_node_mut(parent)?
+            .directory_mut()
+            .ok_or(ResolveError::ParentNotDirectory)?;
+        if parent_dir.lookup_child(name).is_some() {
+            return Err(RegisterError::AlreadyExists);
         }
 
-        Ok(current_node)
+        parent_dir.children_mut().push(DevNode::new(
+            id,
+            name.to_string(),
+            DevNodeKind::Directory(DevDirectoryNode::new()),
+        ));
+        self.next_node_id += 1;
+        Ok(())
+    }
+
+    /// Removes the file at `path`, so that it can't be opened anymore.
+    /// Files that are already open stay usable until they are closed.
+    ///
+    /// # Errors
+    /// Returns an error if there is no file at `path`.
+    pub fn unregister_file(&mut self, path: &AbsolutePath) -> Result<(), UnregisterError> {
+        let parent = path.parent().unwrap_or(ROOT);
+        let filename = path.file_name().ok_or(UnregisterError::IsDirectory)?;
+
+        let parent_dir = self
+            .resolve_node_mut(parent)?
+            .directory_mut()
+            .ok_or(ResolveError::ParentNotDirectory)?;
+        let children = parent_dir.children_mut();
+        let index = children
+            .iter()
+            .position(|child| child.name() == filename)
+            .ok_or(UnregisterError::NotFound)?;
+        if children[index].file().is_none() {
+            return Err(UnregisterError::IsDirectory);
+        }
+        children.remove(index);
+        Ok(())
+    }
+
+    /// Returns the function that opens the file `node`, which can be called
+    /// without holding the file system.
+    ///
+    /// # Errors
+    /// Returns an error if `node` doesn't exist or is a directory.
+    pub fn open_fn(&self, node: FsNodeId) -> Result<DevOpenFn, OpenError> {
+        // opening directories is not yet supported
+        let file_node = self.node(node)?.file().ok_or(OpenError::IsDirectory)?;
+        Ok(file_node.open_fn().clone())
+    }
+
+    /// Keeps `file` open under a new handle.
+    pub fn insert_open_file(&mut self, file: Box<dyn DevFile>) -> FsHandle {
+        let handle = Self::new_fs_handle();
+        self.open_files.insert(handle, Arc::new(Mutex::new(file)));
+        handle
+    }
+
+    /// Forgets the file that is open under `handle` and returns it, so that
+    /// the caller decides where it's dropped.
+    ///
+    /// # Errors
+    /// Returns [`CloseError::NotOpen`] if `handle` isn't open.
+    pub fn take_open_file(&mut self, handle: FsHandle) -> Result<OpenDevFile, CloseError> {
+        self.open_files.remove(&handle).ok_or(CloseError::NotOpen)
+    }
+
+    /// Returns the file that is open under `handle`.
+    ///
+    /// # Errors
+    /// Returns [`FsError::InvalidHandle`] if `handle` isn't open.
+    pub fn open_file(&self, handle: FsHandle) -> Result<OpenDevFile, FsError> {
+        self.open_files
+            .get(&handle)
+            .cloned()
+            .ok_or(FsError::InvalidHandle)
+    }
+
+    fn node(&self, id: FsNodeId) -> Result<&DevNode, OpenError> {
+        self.root.find(id).ok_or(OpenError::NotFound)
     }
 
     fn resolve_node_mut(&mut self, path: &AbsolutePath) -> Result<&mut DevNode, ResolveError> {
@@ -137,28 +239,39 @@ impl DevFs {
         static FS_COUNTER: AtomicU64 = AtomicU64::new(0);
         FsHandle::from(FS_COUNTER.fetch_add(1, Relaxed))
     }
-
-    fn resolve_handle(&mut self, handle: FsHandle) -> Result<&mut Box<dyn DevFile>, FsError> {
-        self.open_files
-            .get_mut(&handle)
-            .ok_or(FsError::InvalidHandle)
-    }
 }
 
 impl FileSystem for DevFs {
-    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
-        let node = self.resolve_node(path)?;
-        let file_node = node
-            .file()
-            .expect("should be regular file, opening directories is not yet supported");
-        let file = file_node.open_fn()()?;
-        let handle = Self::new_fs_handle();
-        self.open_files.insert(handle, file);
-        Ok(handle)
+    fn root(&self) -> FsNodeId {
+        self.root.id()
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let child = self
+            .node(dir)?
+            .directory()
+            .ok_or(OpenError::NotDirectory)?
+            .lookup_child(name)
+            .ok_or(OpenError::NotFound)?;
+        Ok((child.id(), child.fs_node_kind()))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        let dir = self.node(dir)?.directory().ok_or(OpenError::NotDirectory)?;
+        Ok(dir
+            .children()
+            .iter()
+            .map(|child| child.name().to_string())
+            .collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        let file = self.open_fn(node)?()?;
+        Ok(self.insert_open_file(file))
     }
 
     fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
-        self.open_files.remove(&handle).ok_or(CloseError::NotOpen)?;
+        self.take_open_file(handle)?;
         Ok(())
     }
 
@@ -168,20 +281,21 @@ impl FileSystem for DevFs {
         buf: &mut [u8],
         offset: usize,
     ) -> Result<usize, ReadError> {
-        self.resolve_handle(handle)?.read(buf, offset)
+        self.open_file(handle)?.lock().read(buf, offset)
     }
 
     fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
-        self.resolve_handle(handle)?.write(buf, offset)
+        self.open_file(handle)?.lock().write(buf, offset)
     }
 
     fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
-        self.resolve_handle(handle)?.stat(stat)
+        self.open_file(handle)?.lock().stat(stat)
     }
 
     fn mmap(&mut self, handle: FsHandle) -> Result<MmapRegion, MmapError> {
-        self.resolve_handle(handle)
+        self.open_file(handle)
             .map_err(MmapError::FsError)?
+            .lock()
             .mmap()
     }
 
@@ -191,14 +305,20 @@ impl FileSystem for DevFs {
         request: IoctlRequest,
         arg: &mut [u8],
     ) -> Result<usize, IoctlError> {
-        self.resolve_handle(handle)
+        self.open_file(handle)
             .map_err(IoctlError::FsError)?
+            .lock()
             .ioctl(request, arg)
     }
 
+    fn poll(&mut self, handle: FsHandle, events: PollEvents) -> Result<PollEvents, FsError> {
+        Ok(self.open_file(handle)?.lock().poll(events))
+    }
+
     fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
-        self.resolve_handle(handle)
+        self.open_file(handle)
             .map_err(FsyncError::FsError)?
+            .lock()
             .fsync()
     }
 }
@@ -213,14 +333,45 @@ mod tests {
 
     use super::*;
 
+    fn open(devfs: &mut DevFs, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
+        let node = path.filenames().try_fold(devfs.root(), |dir, name| {
+            devfs.lookup(dir, name).map(|(id, _)| id)
+        })?;
+        devfs.open(node)
+    }
+
     #[test]
     fn test_open_not_found() {
         let mut devfs = DevFs::new();
         let path = AbsolutePath::try_new("/nonexistent").unwrap();
-        let result = devfs.open(path);
+        let result = open(&mut devfs, path);
         assert_eq!(result, Err(OpenError::NotFound));
     }
 
+    #[test]
+    fn test_lookup() {
+        let mut devfs = DevFs::new();
+        devfs
+            .register_file(AbsolutePath::try_new("/testfile").unwrap(), || {
+                Ok(TestDevFile::new())
+            })
+            .expect("should be able to register file");
+
+        let (id, kind) = devfs.lookup(devfs.root(), "testfile").unwrap();
+        assert_eq!(kind, FsNodeKind::File);
+        assert_ne!(id, devfs.root());
+        assert_eq!(
+            devfs.lookup(id, "foo"),
+            Err(OpenError::NotDirectory),
+            "files have no children"
+        );
+        assert_eq!(devfs.open(devfs.root()), Err(OpenError::IsDirectory));
+        assert_eq!(
+            devfs.read_dir(devfs.root()).unwrap(),
+            ["null", "zero", "testfile"]
+        );
+    }
+
     #[derive(Debug, Eq, PartialEq)]
     struct TestDevFile {
         id: usize,
@@ -277,6 +428,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_register_directory() {
+        let dir = AbsolutePath::try_new("/pts").unwrap();
+        let path = AbsolutePath::try_new("/pts/0").unwrap();
+
+        let mut devfs = DevFs::new();
+        assert_eq!(
+            devfs.register_file(path, || Ok(TestDevFile::new())),
+            Err(RegisterError::ResolveError(ResolveError::ParentNotFound))
+        );
+        devfs
+            .register_directory(dir)
+            .expect("should be able to register a directory");
+        assert_eq!(
+            devfs.register_directory(dir),
+            Err(RegisterError::AlreadyExists)
+        );
+        devfs
+            .register_file(path, || Ok(TestDevFile::new()))
+            .expect("should be able to register a file in the directory");
+
+        let (id, kind) = devfs.lookup(devfs.root(), "pts").unwrap();
+        assert_eq!(kind, FsNodeKind::Directory);
+        assert_eq!(devfs.read_dir(id).unwrap(), ["0"]);
+        open(&mut devfs, path).expect("should be able to open the file in the directory");
+        assert_eq!(
+            devfs.unregister_file(dir),
+            Err(UnregisterError::IsDirectory)
+        );
+    }
+
+    #[test]
+    fn test_unregister() {
+        let path = AbsolutePath::try_new("/testfile").unwrap();
+
+        let mut devfs = DevFs::new();
+        devfs
+            .register_file(path, || Ok(TestDevFile::new()))
+            .expect("should be able to register file");
+        let file = open(&mut devfs, path).expect("should be able to open registered file");
+
+        devfs
+            .unregister_file(path)
+            .expect("should be able to unregister file");
+        assert_eq!(open(&mut devfs, path), Err(OpenError::NotFound));
+        assert_eq!(devfs.unregister_file(path), Err(UnregisterError::NotFound));
+        assert_eq!(devfs.write(file, b"still open", 0), Ok(10));
+
+        devfs
+            .register_file(path, || Ok(TestDevFile::new()))
+            .expect("should be able to register the file again");
+    }
+
     #[test]
     fn test_register_open_close() {
         let path = AbsolutePath::try_new("/testfile").unwrap();
@@ -286,9 +490,7 @@ mod tests {
             .register_file(path, || Ok(TestDevFile::new()))
             .expect("should be able to register file");
 
-        let file = devfs
-            .open(path)
-            .expect("should be able to open registered file");
+        let file = open(&mut devfs, path).expect("should be able to open registered file");
 
         devfs.close(file).expect("should be able to close file");
     }
@@ -310,12 +512,8 @@ mod tests {
             })
             .expect("should be able to register file");
 
-        let file1 = devfs
-            .open(path)
-            .expect("should be able to open registered file");
-        let file2 = devfs
-            .open(path)
-            .expect("should be able to open registered file");
+        let file1 = open(&mut devfs, path).expect("should be able to open registered file");
+        let file2 = open(&mut devfs, path).expect("should be able to open registered file");
 
         assert_ne!(
             file1, file2,
@@ -333,9 +531,7 @@ mod tests {
             .register_file(path, || Ok(TestDevFile::new()))
             .expect("should be able to register file");
 
-        let file = devfs
-            .open(path)
-            .expect("should be able to open registered file");
+        let file = open(&mut devfs, path).expect("should be able to open registered file");
 
         let write_buf = b"hello";
         let bytes_written = devfs
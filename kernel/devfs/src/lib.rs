@@ -3,7 +3,9 @@ extern crate alloc;
 
 mod file;
 
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ops::Deref;
 
 pub use file::*;
@@ -12,11 +14,10 @@ mod fs;
 mod node;
 
 pub use fs::*;
-use kernel_abi::IoctlRequest;
-use kernel_vfs::fs::{FileSystem, FsHandle};
-use kernel_vfs::path::AbsolutePath;
+use kernel_abi::{IoctlRequest, PollEvents};
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
 use kernel_vfs::{
-    CloseError, FsyncError, IoctlError, MmapError, MmapRegion, OpenError, ReadError, Stat,
+    CloseError, FsError, FsyncError, IoctlError, MmapError, MmapRegion, OpenError, ReadError, Stat,
     StatError, WriteError,
 };
 
@@ -48,12 +49,33 @@ impl Deref for ArcLockedDevFs {
 }
 
 impl FileSystem for ArcLockedDevFs {
-    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
-        self.inner.write().open(path)
+    fn root(&self) -> FsNodeId {
+        self.inner.read().root()
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        self.inner.write().lookup(dir, name)
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        self.inner.write().read_dir(dir)
+    }
+
+    // The file operations release the lock of the file system before they
+    // call into the file, so that files can register or unregister other
+    // files, like `/dev/loop-control` and `/dev/ptmx` do. That includes
+    // opening a file and dropping it on close.
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        let open_fn = self.inner.read().open_fn(node)?;
+        let file = open_fn()?;
+        Ok(self.inner.write().insert_open_file(file))
     }
 
     fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
-        self.inner.write().close(handle)
+        let file = self.inner.write().take_open_file(handle)?;
+        drop(file);
+        Ok(())
     }
 
     fn read(
@@ -62,19 +84,27 @@ impl FileSystem for ArcLockedDevFs {
         buf: &mut [u8],
         offset: usize,
     ) -> Result<usize, ReadError> {
-        self.inner.write().read(handle, buf, offset)
+        let file = self.inner.read().open_file(handle)?;
+        file.lock().read(buf, offset)
     }
 
     fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
-        self.inner.write().write(handle, buf, offset)
+        let file = self.inner.read().open_file(handle)?;
+        file.lock().write(buf, offset)
     }
 
     fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
-        self.inner.write().stat(handle, stat)
+        let file = self.inner.read().open_file(handle)?;
+        file.lock().stat(stat)
     }
 
     fn mmap(&mut self, handle: FsHandle) -> Result<MmapRegion, MmapError> {
-        self.inner.write().mmap(handle)
+        let file = self
+            .inner
+            .read()
+            .open_file(handle)
+            .map_err(MmapError::FsError)?;
+        file.lock().mmap()
     }
 
     fn ioctl(
@@ -83,10 +113,21 @@ impl FileSystem for ArcLockedDevFs {
         request: IoctlRequest,
         arg: &mut [u8],
     ) -> Result<usize, IoctlError> {
-        self.inner.write().ioctl(handle, request, arg)
+        let file = self.inner.read().open_file(handle)?;
+        file.lock().ioctl(request, arg)
+    }
+
+    fn poll(&mut self, handle: FsHandle, events: PollEvents) -> Result<PollEvents, FsError> {
+        let file = self.inner.read().open_file(handle)?;
+        Ok(file.lock().poll(events))
     }
 
     fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
-        self.inner.write().fsync(handle)
+        let file = self
+            .inner
+            .read()
+            .open_file(handle)
+            .map_err(FsyncError::FsError)?;
+        file.lock().fsync()
     }
 }
@@ -1,25 +1,42 @@
 use alloc::boxed::Box;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
 
 use kernel_vfs::OpenError;
+use kernel_vfs::fs::{FsNodeId, FsNodeKind};
 
 use crate::DevFile;
 
 pub struct DevNode {
+    id: FsNodeId,
     name: String,
     kind: DevNodeKind,
 }
 
 impl DevNode {
-    pub fn new(name: String, kind: DevNodeKind) -> Self {
-        Self { name, kind }
+    pub fn new(id: FsNodeId, name: String, kind: DevNodeKind) -> Self {
+        Self { id, name, kind }
+    }
+
+    pub fn id(&self) -> FsNodeId {
+        self.id
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn find(&self, id: FsNodeId) -> Option<&DevNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.directory()?
+            .children
+            .iter()
+            .find_map(|child| child.find(id))
+    }
 }
 
 impl Deref for DevNode {
@@ -42,6 +59,13 @@ pub enum DevNodeKind {
 }
 
 impl DevNodeKind {
+    pub fn fs_node_kind(&self) -> FsNodeKind {
+        match self {
+            DevNodeKind::Directory(_) => FsNodeKind::Directory,
+            DevNodeKind::File(_) => FsNodeKind::File,
+        }
+    }
+
     pub fn directory(&self) -> Option<&DevDirectoryNode> {
         if let DevNodeKind::Directory(dir) = self {
             Some(dir)
@@ -92,13 +116,20 @@ impl DevDirectoryNode {
         self.children.iter_mut().find(|node| node.name() == name)
     }
 
+    pub fn children(&self) -> &[DevNode] {
+        &self.children
+    }
+
     pub fn children_mut(&mut self) -> &mut Vec<DevNode> {
         &mut self.children
     }
 }
 
+/// Opens a fresh instance of a device file.
+pub type DevOpenFn = Arc<dyn Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync>;
+
 pub struct DevFileNode {
-    open_fn: Box<dyn Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync>,
+    open_fn: DevOpenFn,
 }
 
 impl DevFileNode {
@@ -107,11 +138,11 @@ impl DevFileNode {
         F: Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync + 'static,
     {
         Self {
-            open_fn: Box::new(open_fn),
+            open_fn: Arc::new(open_fn),
         }
     }
 
-    pub fn open_fn(&self) -> &(dyn Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync) {
+    pub fn open_fn(&self) -> &DevOpenFn {
         &self.open_fn
     }
 }
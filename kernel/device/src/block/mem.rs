@@ -33,7 +33,7 @@ where
     }
 
     fn sector_count(&self) -> usize {
-        self.data.as_ref().len()
+        self.data.as_ref().len() / self.sector_size
     }
 
     fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
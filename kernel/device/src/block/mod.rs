@@ -2,9 +2,11 @@ use alloc::sync::Arc;
 use alloc::vec;
 
 pub use mem::*;
+pub use partition::*;
 use spin::RwLock;
 
 mod mem;
+mod partition;
 
 pub trait BlockDevice {
     type Error;
@@ -0,0 +1,666 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use thiserror::Error;
+
+use crate::block::BlockDevice;
+
+/// The sector size that MBR addresses are in, regardless of the device.
+const MBR_SECTOR_SIZE: usize = 512;
+const MBR_ENTRIES_OFFSET: usize = 446;
+const MBR_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const MBR_TYPE_EMPTY: u8 = 0x00;
+const MBR_TYPE_EXTENDED: [u8; 3] = [0x05, 0x0F, 0x85];
+const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xEE;
+
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+const GPT_HEADER_MIN_SIZE: usize = 92;
+const GPT_ENTRY_MIN_SIZE: usize = 128;
+/// More entries, and larger ones, than any real table has, so that a corrupt
+/// header can't make us allocate arbitrary amounts of memory.
+const GPT_MAX_ENTRIES: usize = 1024;
+const GPT_ENTRY_MAX_SIZE: usize = 4096;
+
+/// A GUID as used in GPT partition tables, which is formatted like
+/// `c12a7328-f81f-11d2-ba4b-00a0c93ec93b`.
+///
+/// The first three groups are stored little endian on disk.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Guid([u8; 16]);
+
+impl Guid {
+    pub const NIL: Guid = Guid([0; 16]);
+
+    /// Creates a GUID from its on-disk representation.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+/// The order in which the bytes of a GUID appear in its string form.
+const GUID_STRING_ORDER: [usize; 16] = [3, 2, 1, 0, 5, 4, 7, 6, 8, 9, 10, 11, 12, 13, 14, 15];
+
+impl Display for Guid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, index) in GUID_STRING_ORDER.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{:02x}", self.0[*index])?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+#[error("invalid GUID")]
+pub struct ParseGuidError;
+
+impl FromStr for Guid {
+    type Err = ParseGuidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut digits = s.chars().filter(|c| *c != '-');
+        let mut guid = [0_u8; 16];
+        for index in GUID_STRING_ORDER {
+            let mut digit = || {
+                digits
+                    .next()
+                    .and_then(|c| c.to_digit(16))
+                    .ok_or(ParseGuidError)
+            };
+            guid[index] = u8::try_from(digit()? << 4 | digit()?).unwrap();
+        }
+        if digits.next().is_some() {
+            return Err(ParseGuidError);
+        }
+        Ok(Self(guid))
+    }
+}
+
+/// A partition in the partition table of a block device.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Partition {
+    /// The number of the partition, starting at 1. This is the index of the
+    /// entry in the table, so numbers can have gaps.
+    pub number: usize,
+    /// The first sector of the partition, in sectors of the device.
+    pub start: usize,
+    /// The length of the partition, in sectors of the device.
+    pub sector_count: usize,
+    pub kind: PartitionKind,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PartitionKind {
+    Mbr {
+        /// The partition type byte, like `0x83` for Linux.
+        typ: u8,
+    },
+    Gpt {
+        type_guid: Guid,
+        /// The unique GUID of this partition, which Linux calls `PARTUUID`.
+        guid: Guid,
+        /// The partition name, which Linux calls `PARTLABEL`.
+        label: String,
+    },
+}
+
+impl Partition {
+    #[must_use]
+    pub fn guid(&self) -> Option<Guid> {
+        match self.kind {
+            PartitionKind::Gpt { guid, .. } => Some(guid),
+            PartitionKind::Mbr { .. } => None,
+        }
+    }
+
+    #[must_use]
+    pub fn label(&self) -> Option<&str> {
+        match &self.kind {
+            PartitionKind::Gpt { label, .. } => Some(label),
+            PartitionKind::Mbr { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReadPartitionsError<E> {
+    #[error("can't read the partition table from the device")]
+    Device(E),
+    #[error("invalid GPT header")]
+    InvalidGptHeader,
+    #[error("the GPT partition entries don't match their checksum")]
+    InvalidGptEntries,
+    #[error("partition {0} exceeds the device")]
+    OutOfBounds(usize),
+}
+
+/// Reads the partitions of `device`, from a GPT if the MBR is a protective
+/// one, and from the MBR otherwise. A device without an MBR signature has
+/// no partitions.
+///
+/// Extended MBR partitions are skipped, along with the logical partitions
+/// in them.
+///
+/// # Errors
+/// Returns an error if the device can't be read, or if the partition table
+/// is corrupt.
+pub fn read_partitions<T>(device: &T) -> Result<Vec<Partition>, ReadPartitionsError<T::Error>>
+where
+    T: BlockDevice + ?Sized,
+{
+    let mut mbr = [0_u8; MBR_SECTOR_SIZE];
+    device
+        .read_at(0, &mut mbr)
+        .map_err(ReadPartitionsError::Device)?;
+    if mbr[510..] != MBR_SIGNATURE {
+        return Ok(Vec::new());
+    }
+
+    let entries = mbr[MBR_ENTRIES_OFFSET..510].as_chunks::<16>().0.iter();
+    let partitions = if entries
+        .clone()
+        .any(|entry| entry[4] == MBR_TYPE_GPT_PROTECTIVE)
+    {
+        read_gpt(device)?
+    } else {
+        read_mbr(device, entries)
+    };
+
+    let device_sectors = device.sector_count();
+    for partition in &partitions {
+        let end = partition.start.checked_add(partition.sector_count);
+        if end.is_none_or(|end| end > device_sectors) {
+            return Err(ReadPartitionsError::OutOfBounds(partition.number));
+        }
+    }
+    Ok(partitions)
+}
+
+fn read_mbr<'a, T>(device: &T, entries: impl Iterator<Item = &'a [u8; 16]>) -> Vec<Partition>
+where
+    T: BlockDevice + ?Sized,
+{
+    // MBR addresses are in 512 byte sectors, which we convert to the
+    // sectors of the device
+    let scale = |sectors: u32| sectors as usize * MBR_SECTOR_SIZE / device.sector_size();
+    entries
+        .enumerate()
+        .filter(|(_, entry)| entry[4] != MBR_TYPE_EMPTY && !MBR_TYPE_EXTENDED.contains(&entry[4]))
+        .map(|(index, entry)| Partition {
+            number: index + 1,
+            start: scale(u32_at(entry, 8)),
+            sector_count: scale(u32_at(entry, 12)),
+            kind: PartitionKind::Mbr { typ: entry[4] },
+        })
+        .filter(|partition| partition.sector_count > 0)
+        .collect()
+}
+
+fn read_gpt<T>(device: &T) -> Result<Vec<Partition>, ReadPartitionsError<T::Error>>
+where
+    T: BlockDevice + ?Sized,
+{
+    let sector_size = device.sector_size();
+    let mut header = vec![0_u8; sector_size];
+    device
+        .read_at(sector_size, &mut header)
+        .map_err(ReadPartitionsError::Device)?;
+
+    let header_size = u32_at(&header, 12) as usize;
+    if &header[..8] != GPT_SIGNATURE || !(GPT_HEADER_MIN_SIZE..=sector_size).contains(&header_size)
+    {
+        return Err(ReadPartitionsError::InvalidGptHeader);
+    }
+    let header_crc = u32_at(&header, 16);
+    header[16..20].fill(0);
+    if crc32(&header[..header_size]) != header_crc {
+        return Err(ReadPartitionsError::InvalidGptHeader);
+    }
+
+    let entries_lba = u64_at(&header, 72) as usize;
+    let entry_count = u32_at(&header, 80) as usize;
+    let entry_size = u32_at(&header, 84) as usize;
+    if entry_count > GPT_MAX_ENTRIES
+        || !(GPT_ENTRY_MIN_SIZE..=GPT_ENTRY_MAX_SIZE).contains(&entry_size)
+        || !entry_size.is_multiple_of(8)
+    {
+        return Err(ReadPartitionsError::InvalidGptHeader);
+    }
+    // the entry array has to lie on the device
+    let entries_len = entry_count * entry_size;
+    let entries_offset = entries_lba
+        .checked_mul(sector_size)
+        .filter(|offset| {
+            offset
+                .checked_add(entries_len)
+                .is_some_and(|end| end <= device.sector_count() * sector_size)
+        })
+        .ok_or(ReadPartitionsError::InvalidGptHeader)?;
+
+    let mut entries = vec![0_u8; entries_len];
+    device
+        .read_at(entries_offset, &mut entries)
+        .map_err(ReadPartitionsError::Device)?;
+    if crc32(&entries) != u32_at(&header, 88) {
+        return Err(ReadPartitionsError::InvalidGptEntries);
+    }
+
+    let mut partitions = Vec::new();
+    for (index, entry) in entries.chunks_exact(entry_size).enumerate() {
+        let type_guid = guid_at(entry, 0);
+        if type_guid == Guid::NIL {
+            continue;
+        }
+        let first = u64_at(entry, 32) as usize;
+        let last = u64_at(entry, 40) as usize;
+        if last < first {
+            return Err(ReadPartitionsError::InvalidGptEntries);
+        }
+        let name = entry[56..128]
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|c| u16::from_le_bytes(*c))
+            .take_while(|c| *c != 0);
+        let label = char::decode_utf16(name)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        partitions.push(Partition {
+            number: index + 1,
+            start: first,
+            sector_count: last - first + 1,
+            kind: PartitionKind::Gpt {
+                type_guid,
+                guid: guid_at(entry, 16),
+                label,
+            },
+        });
+    }
+    Ok(partitions)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn guid_at(data: &[u8], offset: usize) -> Guid {
+    Guid(data[offset..offset + 16].try_into().unwrap())
+}
+
+/// The CRC32 that GPT uses, the same as the one of zlib and Ethernet.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+#[error("access beyond the end of the partition")]
+pub struct OutOfBoundsError;
+
+/// A partition of a block device, which is itself a block device. Sector 0
+/// is the first sector of the partition, and accesses past its end fail
+/// with [`OutOfBoundsError`] instead of reaching the rest of the device.
+pub struct PartitionDevice<T> {
+    inner: T,
+    start: usize,
+    sector_count: usize,
+}
+
+impl<T> PartitionDevice<T>
+where
+    T: BlockDevice,
+{
+    pub fn new(inner: T, partition: &Partition) -> Self {
+        Self {
+            inner,
+            start: partition.start,
+            sector_count: partition.sector_count,
+        }
+    }
+
+    fn check_sector(&self, sector_index: usize) -> Result<(), OutOfBoundsError> {
+        if sector_index < self.sector_count {
+            Ok(())
+        } else {
+            Err(OutOfBoundsError)
+        }
+    }
+
+    /// Returns the offset of `offset` on the underlying device, if `len`
+    /// bytes from there are within the partition.
+    fn translate(&self, offset: usize, len: usize) -> Result<usize, OutOfBoundsError> {
+        let size = self.sector_count * self.inner.sector_size();
+        match offset.checked_add(len) {
+            Some(end) if end <= size => Ok(self.start * self.inner.sector_size() + offset),
+            _ => Err(OutOfBoundsError),
+        }
+    }
+}
+
+impl<T> BlockDevice for PartitionDevice<T>
+where
+    T: BlockDevice,
+    T::Error: From<OutOfBoundsError>,
+{
+    type Error = T::Error;
+
+    fn sector_size(&self) -> usize {
+        self.inner.sector_size()
+    }
+
+    fn sector_count(&self) -> usize {
+        self.sector_count
+    }
+
+    fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.check_sector(sector_index)?;
+        self.inner.read_sector(self.start + sector_index, buf)
+    }
+
+    fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.check_sector(sector_index)?;
+        self.inner.write_sector(self.start + sector_index, buf)
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let offset = self.translate(offset, buf.len())?;
+        self.inner.read_at(offset, buf)
+    }
+
+    fn write_at(&mut self, offset: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        let offset = self.translate(offset, buf.len())?;
+        self.inner.write_at(offset, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+    use crate::block::MemoryBlockDevice;
+
+    const SECTORS: usize = 128;
+
+    /// A memory device with an error type that partitions can report
+    /// [`OutOfBoundsError`] through.
+    struct TestDevice(MemoryBlockDevice<Vec<u8>>);
+
+    #[derive(Debug, Eq, PartialEq)]
+    enum TestError {
+        Device,
+        OutOfBounds,
+    }
+
+    impl From<OutOfBoundsError> for TestError {
+        fn from(_: OutOfBoundsError) -> Self {
+            Self::OutOfBounds
+        }
+    }
+
+    impl BlockDevice for TestDevice {
+        type Error = TestError;
+
+        fn sector_size(&self) -> usize {
+            self.0.sector_size()
+        }
+
+        fn sector_count(&self) -> usize {
+            self.0.sector_count()
+        }
+
+        fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.0
+                .read_sector(sector_index, buf)
+                .map_err(|()| TestError::Device)
+        }
+
+        fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.0
+                .write_sector(sector_index, buf)
+                .map_err(|()| TestError::Device)
+        }
+    }
+
+    fn device(data: Vec<u8>) -> TestDevice {
+        TestDevice(MemoryBlockDevice::try_new(512, data).unwrap())
+    }
+
+    fn mbr_entry(disk: &mut [u8], index: usize, typ: u8, start: u32, len: u32) {
+        let entry = &mut disk[MBR_ENTRIES_OFFSET + index * 16..][..16];
+        entry[4] = typ;
+        entry[8..12].copy_from_slice(&start.to_le_bytes());
+        entry[12..16].copy_from_slice(&len.to_le_bytes());
+    }
+
+    fn mbr_disk() -> Vec<u8> {
+        let mut disk = vec![0_u8; SECTORS * 512];
+        disk[510..512].copy_from_slice(&MBR_SIGNATURE);
+        disk
+    }
+
+    const LINUX_DATA: Guid = Guid::from_bytes([
+        0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47, 0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d,
+        0xe4,
+    ]);
+
+    fn gpt_disk(partitions: &[(u64, u64, &str)]) -> Vec<u8> {
+        let mut disk = mbr_disk();
+        mbr_entry(&mut disk, 0, MBR_TYPE_GPT_PROTECTIVE, 1, SECTORS as u32 - 1);
+
+        let entries_offset = 2 * 512;
+        for (index, (first, last, name)) in partitions.iter().enumerate() {
+            let entry = &mut disk[entries_offset + index * 128..][..128];
+            entry[0..16].copy_from_slice(LINUX_DATA.as_bytes());
+            entry[16..32].fill(index as u8 + 1);
+            entry[32..40].copy_from_slice(&first.to_le_bytes());
+            entry[40..48].copy_from_slice(&last.to_le_bytes());
+            for (i, c) in name.encode_utf16().enumerate() {
+                entry[56 + i * 2..58 + i * 2].copy_from_slice(&c.to_le_bytes());
+            }
+        }
+        let entries_crc = crc32(&disk[entries_offset..entries_offset + 4 * 128]);
+
+        let header = &mut disk[512..1024];
+        header[0..8].copy_from_slice(GPT_SIGNATURE);
+        header[12..16].copy_from_slice(&92_u32.to_le_bytes());
+        header[72..80].copy_from_slice(&2_u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4_u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128_u32.to_le_bytes());
+        header[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+        let header_crc = crc32(&header[..92]);
+        header[16..20].copy_from_slice(&header_crc.to_le_bytes());
+        disk
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn test_guid_display_and_parse() {
+        let s = "0fc63daf-8483-4772-8e79-3d69d8477de4";
+        assert_eq!(s, format!("{LINUX_DATA}"));
+        assert_eq!(Ok(LINUX_DATA), s.parse());
+        assert_eq!(Ok(LINUX_DATA), "0FC63DAF848347728E793D69D8477DE4".parse());
+        assert_eq!(Err(ParseGuidError), "0fc63daf-8483".parse::<Guid>());
+    }
+
+    #[test]
+    fn test_no_partition_table() {
+        let device = device(vec![0_u8; SECTORS * 512]);
+        assert_eq!(Ok(Vec::new()), read_partitions(&device));
+    }
+
+    #[test]
+    fn test_mbr() {
+        let mut disk = mbr_disk();
+        mbr_entry(&mut disk, 0, 0x83, 2, 10);
+        mbr_entry(&mut disk, 1, 0x05, 12, 10);
+        mbr_entry(&mut disk, 3, 0x0c, 64, 64);
+        let partitions = read_partitions(&device(disk)).unwrap();
+
+        assert_eq!(
+            vec![
+                Partition {
+                    number: 1,
+                    start: 2,
+                    sector_count: 10,
+                    kind: PartitionKind::Mbr { typ: 0x83 },
+                },
+                Partition {
+                    number: 4,
+                    start: 64,
+                    sector_count: 64,
+                    kind: PartitionKind::Mbr { typ: 0x0c },
+                },
+            ],
+            partitions
+        );
+    }
+
+    #[test]
+    fn test_mbr_out_of_bounds() {
+        let mut disk = mbr_disk();
+        mbr_entry(&mut disk, 0, 0x83, 64, 65);
+        assert_eq!(
+            Err(ReadPartitionsError::OutOfBounds(1)),
+            read_partitions(&device(disk))
+        );
+    }
+
+    #[test]
+    fn test_gpt() {
+        let disk = gpt_disk(&[(34, 63, "boot"), (64, 127, "root")]);
+        let partitions = read_partitions(&device(disk)).unwrap();
+
+        assert_eq!(2, partitions.len());
+        assert_eq!(1, partitions[0].number);
+        assert_eq!(34, partitions[0].start);
+        assert_eq!(30, partitions[0].sector_count);
+        assert_eq!(Some("boot"), partitions[0].label());
+        assert_eq!(Some(Guid::from_bytes([1; 16])), partitions[0].guid());
+
+        assert_eq!(2, partitions[1].number);
+        assert_eq!(64, partitions[1].start);
+        assert_eq!(64, partitions[1].sector_count);
+        assert_eq!(Some("root"), partitions[1].label());
+        let PartitionKind::Gpt { type_guid, .. } = partitions[1].kind else {
+            panic!("should be a GPT partition");
+        };
+        assert_eq!(LINUX_DATA, type_guid);
+    }
+
+    #[test]
+    fn test_gpt_checksums() {
+        let mut disk = gpt_disk(&[(34, 63, "boot")]);
+        disk[2 * 512 + 56] = b'B';
+        assert_eq!(
+            Err(ReadPartitionsError::InvalidGptEntries),
+            read_partitions(&device(disk.clone()))
+        );
+
+        disk[512 + 40] = 1;
+        assert_eq!(
+            Err(ReadPartitionsError::InvalidGptHeader),
+            read_partitions(&device(disk))
+        );
+    }
+
+    /// Rewrites a header field of a [`gpt_disk`] and fixes up the checksum.
+    fn patch_gpt_header(disk: &mut [u8], offset: usize, value: &[u8]) {
+        let header = &mut disk[512..1024];
+        header[offset..offset + value.len()].copy_from_slice(value);
+        header[16..20].fill(0);
+        let header_crc = crc32(&header[..92]);
+        header[16..20].copy_from_slice(&header_crc.to_le_bytes());
+    }
+
+    #[test]
+    fn test_gpt_entries_out_of_bounds() {
+        let mut disk = gpt_disk(&[(34, 63, "boot")]);
+        patch_gpt_header(&mut disk, 84, &0xFFFF_FFF8_u32.to_le_bytes());
+        assert_eq!(
+            Err(ReadPartitionsError::InvalidGptHeader),
+            read_partitions(&device(disk))
+        );
+
+        let mut disk = gpt_disk(&[(34, 63, "boot")]);
+        patch_gpt_header(&mut disk, 72, &(u64::MAX / 2).to_le_bytes());
+        assert_eq!(
+            Err(ReadPartitionsError::InvalidGptHeader),
+            read_partitions(&device(disk))
+        );
+
+        let mut disk = gpt_disk(&[(34, 63, "boot")]);
+        patch_gpt_header(&mut disk, 72, &(SECTORS as u64).to_le_bytes());
+        assert_eq!(
+            Err(ReadPartitionsError::InvalidGptHeader),
+            read_partitions(&device(disk))
+        );
+    }
+
+    #[test]
+    fn test_partition_device() {
+        let mut disk = mbr_disk();
+        mbr_entry(&mut disk, 0, 0x83, 4, 2);
+        for (i, sector) in disk.as_chunks_mut::<512>().0.iter_mut().enumerate().skip(1) {
+            sector[1..].fill(i as u8);
+        }
+        let device = device(disk);
+        let partitions = read_partitions(&device).unwrap();
+        let mut partition = PartitionDevice::new(device, &partitions[0]);
+
+        assert_eq!(2, partition.sector_count());
+        let mut buf = [0_u8; 512];
+        partition.read_sector(1, &mut buf).unwrap();
+        assert_eq!([5; 511], buf[1..]);
+        assert_eq!(
+            Err(TestError::OutOfBounds),
+            partition.read_sector(2, &mut buf)
+        );
+
+        let mut buf = [0_u8; 4];
+        partition.read_at(510, &mut buf).unwrap();
+        assert_eq!([4, 4, 0, 5], buf);
+        assert_eq!(
+            Err(TestError::OutOfBounds),
+            partition.read_at(1022, &mut buf)
+        );
+
+        partition.write_at(1020, &[9; 4]).unwrap();
+        assert_eq!(
+            Err(TestError::OutOfBounds),
+            partition.write_at(1021, &[9; 4])
+        );
+        let mut buf = [0_u8; 8];
+        partition.inner.read_at(6 * 512 - 6, &mut buf).unwrap();
+        assert_eq!([5, 5, 9, 9, 9, 9, 0, 6], buf);
+    }
+}
@@ -15,6 +15,24 @@ bytefield! {
 }
 
 impl BlockGroupDescriptor {
+    pub(crate) fn new(
+        block_usage_bitmap_block: u32,
+        inode_usage_bitmap_block: u32,
+        inode_table_starting_block: u32,
+        num_unallocated_blocks: u16,
+        num_unallocated_inodes: u16,
+        num_directories: u16,
+    ) -> Self {
+        Self {
+            block_usage_bitmap_block,
+            inode_usage_bitmap_block,
+            inode_table_starting_block,
+            num_unallocated_blocks,
+            num_unallocated_inodes,
+            num_directories,
+        }
+    }
+
     pub fn block_usage_bitmap_block(&self) -> u32 {
         self.block_usage_bitmap_block
     }
@@ -39,9 +57,17 @@ impl BlockGroupDescriptor {
         self.num_unallocated_inodes
     }
 
+    pub fn num_unallocated_inodes_mut(&mut self) -> &mut u16 {
+        &mut self.num_unallocated_inodes
+    }
+
     pub fn num_directories(&self) -> u16 {
         self.num_directories
     }
+
+    pub fn num_directories_mut(&mut self) -> &mut u16 {
+        &mut self.num_directories
+    }
 }
 
 pub type Inner = Vec<BlockGroupDescriptor>;
@@ -1,6 +1,9 @@
+use alloc::vec;
+
 use kernel_device::block::BlockDevice;
 
-use crate::{Directory, Error, Ext2Fs, Inode, InodeAddress, RegularFile, Type};
+use crate::superblock::RequiredFeatures;
+use crate::{DirEntry, DirType, Directory, Error, Ext2Fs, Inode, InodeAddress, RegularFile, Type};
 
 impl<T> Ext2Fs<T>
 where
@@ -12,8 +15,17 @@ where
         name: &str,
         typ: Type,
     ) -> Result<(InodeAddress, Inode), Error> {
+        // check before allocating, so that a collision doesn't leak the inode
+        if self
+            .find_entry(parent, |e| e.name() == Some(name))?
+            .is_some()
+        {
+            return Err(Error::EntryExists);
+        }
+
         let inode_address = self.allocate_inode()?.ok_or(Error::NoSpace)?;
-        let inode = Inode::new(typ);
+        let mut inode = Inode::new(typ);
+        *inode.num_hard_links_mut() = 1;
 
         self.write_inode(inode_address, &inode)?;
 
@@ -30,4 +42,53 @@ where
         self.create_inode(parent, name, Type::RegularFile)
             .map(|v| v.try_into().unwrap()) // if we don't get an inode with type RegularFile, something is really broken
     }
+
+    /// Creates an empty directory called `name` in `parent`, with entries
+    /// for `.` and `..`.
+    pub fn create_directory(
+        &mut self,
+        parent: &mut Directory,
+        name: &str,
+    ) -> Result<Directory, Error> {
+        let mut dir: Directory = self
+            .create_inode(parent, name, Type::Directory)?
+            .try_into()
+            .unwrap(); // if we don't get an inode with type Directory, something is really broken
+
+        let block_size = self.superblock.block_size() as usize;
+        let dir_entries_have_type = self
+            .superblock
+            .required_features()
+            .contains(RequiredFeatures::DIRECTORY_ENTRIES_HAVE_TYPE);
+        let block = self.allocate_block_for_inode(dir.inode_mut(), 0)?;
+        let dot = DirEntry::new(dir.inode_address(), ".", DirType::Directory, 12);
+        let dot_dot = DirEntry::new(
+            parent.inode_address(),
+            "..",
+            DirType::Directory,
+            block_size as u16 - 12,
+        );
+        let mut data = vec![0_u8; block_size];
+        for (offset, entry) in [(0, dot), (12, dot_dot)] {
+            let serialized = entry.serialize(dir_entries_have_type);
+            data[offset..offset + serialized.len()].copy_from_slice(&serialized);
+        }
+        self.write_block(block, &data)?;
+
+        let inode = dir.inode_mut();
+        inode.set_file_size_lower(block_size as u32);
+        // the entry in the parent and `.`
+        *inode.num_hard_links_mut() = 2;
+        self.write_inode(dir.inode_address(), &dir)?;
+
+        // `..` links back to the parent
+        *parent.inode_mut().num_hard_links_mut() += 1;
+        self.write_inode(parent.inode_address(), parent)?;
+
+        let group_index = (dir.inode_address().get() - 1) / self.superblock.inodes_per_group();
+        *self.bgdt[group_index as usize].num_directories_mut() += 1;
+        self.write_group_descriptor(group_index as usize)?;
+
+        Ok(dir)
+    }
 }
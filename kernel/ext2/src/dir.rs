@@ -8,7 +8,7 @@ use kernel_device::block::BlockDevice;
 use crate::error::Error;
 use crate::superblock::RequiredFeatures;
 use crate::{
-    Directory, Ext2Fs, Inode, InodeAddress, Type, bytefield, bytefield_field_read,
+    BlockAddress, Directory, Ext2Fs, Inode, InodeAddress, Type, bytefield, bytefield_field_read,
     bytefield_field_write, check_is_implemented,
 };
 
@@ -29,22 +29,26 @@ where
             .required_features()
             .contains(RequiredFeatures::DIRECTORY_ENTRIES_HAVE_TYPE);
 
-        for addr in dir.direct_ptrs().flatten() {
+        for addr in self.dir_blocks(dir)? {
             let mut data = vec![0_u8; block_size];
             self.read_block(addr, &mut data)
                 .map_err(|_| Error::DeviceRead)?;
 
             let mut offset = 0;
             while offset < block_size - 8 {
-                let dir_entry = DirEntry::from(dir_entries_have_type, &data[offset..]);
+                let total_size = DirEntry::total_size_at(&data[offset..]);
+                if total_size == 0 {
+                    return Err(Error::InvalidDirEntry);
+                }
+                // entries of removed files are kept with inode 0 to fill the space
+                if let Some(dir_entry) = DirEntry::from(dir_entries_have_type, &data[offset..]) {
+                    entries.push(dir_entry);
+                }
                 // we don't need to align the offset, as there must be no space between entries
-                offset += dir_entry.total_size as usize;
-                entries.push(dir_entry);
+                offset += total_size as usize;
             }
         }
 
-        // TODO: handle indirect ptrsa
-
         Ok(entries)
     }
 
@@ -72,6 +76,18 @@ where
         self.read_inode(entry.inode)
     }
 
+    /// The data blocks of the directory, in order.
+    fn dir_blocks(&self, dir: &Inode) -> Result<Vec<BlockAddress>, Error> {
+        let num_blocks = dir.len().div_ceil(self.superblock.block_size() as usize);
+        let mut blocks = Vec::with_capacity(num_blocks);
+        for index in 0..num_blocks as u32 {
+            if let Some(block) = self.resolve_block_index(dir, index)? {
+                blocks.push(block);
+            }
+        }
+        Ok(blocks)
+    }
+
     pub fn add_entry_to_dir(
         &mut self,
         dir: &mut Directory,
@@ -90,13 +106,22 @@ where
             .required_features()
             .contains(RequiredFeatures::DIRECTORY_ENTRIES_HAVE_TYPE);
 
-        let inode = &mut dir.inode_mut();
-
         // compute the size of the directory entry that we need
         let required_size = DirEntry::size(name.len() as u16);
+        let new_entry = |total_size| DirEntry {
+            inode: inode_address,
+            total_size,
+            name_length: name.len() as u16,
+            type_indicator: if dir_entries_have_type {
+                Some(typ)
+            } else {
+                None
+            },
+            name_bytes: name.as_bytes().to_vec(),
+        };
 
         // find a free slot and insert the entry
-        for block in inode.direct_ptrs().flatten() {
+        for block in self.dir_blocks(dir)? {
             let mut block_data = vec![0_u8; block_size];
             self.read_block(block, &mut block_data)?;
 
@@ -107,7 +132,24 @@ where
             while offset < block_size - 8 {
                 debug_assert_eq!(offset % 4, 0, "offset is not aligned");
 
-                let mut entry = DirEntry::from(dir_entries_have_type, &block_data[offset..]);
+                let total_size = DirEntry::total_size_at(&block_data[offset..]);
+                if total_size == 0 {
+                    return Err(Error::InvalidDirEntry);
+                }
+                let Some(mut entry) = DirEntry::from(dir_entries_have_type, &block_data[offset..])
+                else {
+                    // an unused entry can be taken over as a whole
+                    if total_size >= required_size {
+                        let new_entry_serialized =
+                            new_entry(total_size).serialize(dir_entries_have_type);
+                        block_data[offset..offset + new_entry_serialized.len()]
+                            .copy_from_slice(&new_entry_serialized);
+                        self.write_block(block, &block_data)?;
+                        return Ok(());
+                    }
+                    offset += total_size as usize;
+                    continue;
+                };
                 let entry_size = DirEntry::size(entry.name_length);
                 if entry.total_size >= required_size + entry_size {
                     // we found a slot that is big enough
@@ -124,20 +166,9 @@ where
                     let new_entry_offset = offset + entry_size as usize;
                     debug_assert_eq!(new_entry_offset % 4, 0, "new entry offset is not aligned");
 
-                    let new_entry = DirEntry {
-                        inode: inode_address,
-                        total_size: new_entry_total_size,
-                        name_length: name.len() as u16,
-                        type_indicator: if dir_entries_have_type {
-                            Some(typ)
-                        } else {
-                            None
-                        },
-                        name_bytes: name.as_bytes().to_vec(),
-                    };
-
                     // merge the new entry into the block data
-                    let new_entry_serialized = new_entry.serialize(dir_entries_have_type);
+                    let new_entry_serialized =
+                        new_entry(new_entry_total_size).serialize(dir_entries_have_type);
                     block_data[new_entry_offset..new_entry_offset + new_entry_serialized.len()]
                         .copy_from_slice(&new_entry_serialized);
 
@@ -151,7 +182,18 @@ where
             }
         }
 
-        todo!("add_entry_to_dir with indirect pointers")
+        // all blocks are full, so the directory grows by a block that holds
+        // only the new entry
+        let block_index = (dir.len() / block_size) as u32;
+        let block = self.allocate_block_for_inode(dir.inode_mut(), block_index)?;
+        let mut block_data = vec![0_u8; block_size];
+        let new_entry_serialized = new_entry(block_size as u16).serialize(dir_entries_have_type);
+        block_data[..new_entry_serialized.len()].copy_from_slice(&new_entry_serialized);
+        self.write_block(block, &block_data)?;
+
+        let new_size = dir.len() + block_size;
+        dir.inode_mut().set_file_size_lower(new_size as u32);
+        self.write_inode(dir.inode_address(), dir)
     }
 }
 
@@ -164,7 +206,17 @@ pub struct DirEntry {
 }
 
 impl DirEntry {
-    const fn size(name_length: u16) -> u16 {
+    pub(crate) fn new(inode: InodeAddress, name: &str, typ: DirType, total_size: u16) -> Self {
+        Self {
+            inode,
+            total_size,
+            name_length: name.len() as u16,
+            type_indicator: Some(typ),
+            name_bytes: name.as_bytes().to_vec(),
+        }
+    }
+
+    pub(crate) const fn size(name_length: u16) -> u16 {
         let unaligned_size = 4 + // inode
             2 + // total_size
             2 + // name_length and type_indicator
@@ -173,7 +225,15 @@ impl DirEntry {
         (unaligned_size + 3) & !3
     }
 
-    fn from(dir_entries_have_type: bool, value: &[u8]) -> Self {
+    /// The size of the entry at the start of `value`, including the space
+    /// up to the next entry.
+    fn total_size_at(value: &[u8]) -> u16 {
+        u16::from_le_bytes([value[4], value[5]])
+    }
+
+    /// Parses the entry at the start of `value`, or returns `None` if the
+    /// entry is unused.
+    fn from(dir_entries_have_type: bool, value: &[u8]) -> Option<Self> {
         let required_size = Self::size(0);
         debug_assert!(
             value.len() >= required_size as usize,
@@ -182,6 +242,7 @@ impl DirEntry {
         );
 
         let arr = DirEntryNoName::try_from(&value[0..8].try_into().unwrap()).unwrap();
+        let inode = InodeAddress::new(arr.inode)?;
         let name_length = if dir_entries_have_type {
             arr.name_length_lsb as u16
         } else {
@@ -204,13 +265,13 @@ impl DirEntry {
         );
 
         let name_bytes = value[8..8 + name_length as usize].to_vec();
-        Self {
-            inode: InodeAddress::new(arr.inode).unwrap(),
+        Some(Self {
+            inode,
             total_size: arr.total_size,
             name_length,
             type_indicator,
             name_bytes,
-        }
+        })
     }
 
     pub fn serialize(self, dir_entries_have_type: bool) -> Vec<u8> {
@@ -16,6 +16,12 @@ pub enum Error {
     NoSpace,
     NotSupported,
     EntryExists,
+    InvalidSymLink,
+    InvalidDirEntry,
+    InvalidBlockSize(u32),
+    InvalidInodeRatio(u32),
+    LabelTooLong,
+    DeviceTooSmall,
 }
 
 impl Display for Error {
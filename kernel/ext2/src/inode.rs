@@ -164,6 +164,11 @@ impl Inode {
         Permissions::from_bits_truncate(self.type_and_perm)
     }
 
+    /// Replaces the permission bits, keeping the type.
+    pub fn set_perm(&mut self, perm: Permissions) {
+        self.type_and_perm = self.typ().bits() | perm.bits();
+    }
+
     pub fn flags(&self) -> Flags {
         Flags::from_bits_truncate(self.flags)
     }
@@ -238,6 +243,53 @@ impl Inode {
         BlockAddress::new(self.triply_indirect_block_ptr)
     }
 
+    pub fn set_single_indirect_ptr(&mut self, ptr: Option<BlockAddress>) {
+        self.singly_indirect_block_ptr = ptr.map_or(0, |v| v.into_u32());
+    }
+
+    pub fn set_double_indirect_ptr(&mut self, ptr: Option<BlockAddress>) {
+        self.doubly_indirect_block_ptr = ptr.map_or(0, |v| v.into_u32());
+    }
+
+    pub fn set_triple_indirect_ptr(&mut self, ptr: Option<BlockAddress>) {
+        self.triply_indirect_block_ptr = ptr.map_or(0, |v| v.into_u32());
+    }
+
+    /// Returns the 60 bytes occupied by the block pointers. Fast symlinks
+    /// store their target here instead of in a data block.
+    pub fn inline_data(&self) -> [u8; 60] {
+        let mut data = [0_u8; 60];
+        let ptrs = self.direct_block_ptr.iter().chain([
+            &self.singly_indirect_block_ptr,
+            &self.doubly_indirect_block_ptr,
+            &self.triply_indirect_block_ptr,
+        ]);
+        for (chunk, ptr) in data.as_chunks_mut::<4>().0.iter_mut().zip(ptrs) {
+            *chunk = ptr.to_le_bytes();
+        }
+        data
+    }
+
+    /// Overwrites the block pointers with `data`, padded with zeros.
+    ///
+    /// # Panics
+    /// Panics if `data` is longer than 60 bytes.
+    pub fn set_inline_data(&mut self, data: &[u8]) {
+        let mut padded = [0_u8; 60];
+        padded[..data.len()].copy_from_slice(data);
+        let mut words = padded
+            .as_chunks::<4>()
+            .0
+            .iter()
+            .map(|&chunk| u32::from_le_bytes(chunk));
+        for ptr in &mut self.direct_block_ptr {
+            *ptr = words.next().unwrap();
+        }
+        self.singly_indirect_block_ptr = words.next().unwrap();
+        self.doubly_indirect_block_ptr = words.next().unwrap();
+        self.triply_indirect_block_ptr = words.next().unwrap();
+    }
+
     pub fn len(&self) -> usize {
         if self.typ() == Type::Directory {
             self.byte_size_lower as usize
@@ -9,6 +9,7 @@ pub use dir::*;
 pub use error::*;
 pub use inode::*;
 use kernel_device::block::BlockDevice;
+pub use mkfs::{FormatOptions, format};
 use spin::Mutex;
 pub use superblock::*;
 
@@ -22,11 +23,13 @@ mod create;
 mod dir;
 mod error;
 mod inode;
+mod mkfs;
 mod read;
 mod superblock;
+mod symlink;
 mod write;
 
-const ROOT_DIR_INODE_ADDRESS: InodeAddress = InodeAddress::new(2).unwrap();
+pub const ROOT_DIR_INODE_ADDRESS: InodeAddress = InodeAddress::new(2).unwrap();
 
 /// An ext2 filesystem over a block device.
 pub struct Ext2Fs<T> {
@@ -44,15 +47,12 @@ where
     T: BlockDevice,
 {
     pub fn try_new(block_device: T) -> Result<Self, Error> {
-        let mut superblock_data = [0_u8; 1024];
-        block_device
-            .read_at(SUPERBLOCK_OFFSET, &mut superblock_data)
-            .map_err(|_| Error::UnableToReadSuperblock)?;
-
-        let superblock = Superblock::try_from(SuperblockArray::from(superblock_data)).unwrap();
-        let number_of_block_groups = superblock
-            .num_blocks()
-            .div_ceil(superblock.blocks_per_group());
+        let superblock = Self::read_superblock(&block_device)?;
+        // the first group starts at the block of the superblock, the blocks
+        // before it aren't part of any group
+        let number_of_block_groups = (superblock.num_blocks()
+            - superblock.superblock_block_number())
+        .div_ceil(superblock.blocks_per_group());
 
         let bgdt_offset = if superblock.block_size() == 1024 {
             2048
@@ -60,7 +60,13 @@ where
             superblock.block_size()
         } as usize;
 
-        let mut bgdt_data = vec![0_u8; superblock.block_size() as usize];
+        // a table that the device can't hold comes from a broken superblock,
+        // and allocating it could exhaust the heap
+        let bgdt_len = number_of_block_groups as usize * BGD_SIZE;
+        if bgdt_len > block_device.sector_count() * block_device.sector_size() {
+            return Err(Error::InvalidSuperblock);
+        }
+        let mut bgdt_data = vec![0_u8; bgdt_len];
         block_device
             .read_at(bgdt_offset, &mut bgdt_data)
             .map_err(|_| Error::UnableToReadBlockGroupDescriptorTable)?;
@@ -81,6 +87,26 @@ where
         })
     }
 
+    /// Reads the superblock of the file system on `block_device` without
+    /// mounting it, for example to find a file system by its
+    /// [`Superblock::fsid`].
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidSuperblock`] if the device doesn't contain an
+    /// ext2 file system, or its superblock is corrupted.
+    pub fn read_superblock(block_device: &T) -> Result<Superblock, Error> {
+        let mut superblock_data = [0_u8; 1024];
+        block_device
+            .read_at(SUPERBLOCK_OFFSET, &mut superblock_data)
+            .map_err(|_| Error::UnableToReadSuperblock)?;
+
+        let superblock = Superblock::try_from(SuperblockArray::from(superblock_data)).unwrap();
+        if superblock.magic_number() != EXT2_MAGIC || !superblock.has_valid_layout() {
+            return Err(Error::InvalidSuperblock);
+        }
+        Ok(superblock)
+    }
+
     fn bgdt_offset(&self) -> usize {
         let block_size = self.superblock.block_size() as usize;
         if block_size == 1024 { 2048 } else { block_size }
@@ -154,99 +180,117 @@ where
         (addr.get() * self.superblock.block_size()) as usize
     }
 
+    /// Marks the first free block as used and returns it.
     pub fn allocate_block(&mut self) -> Result<Option<BlockAddress>, Error> {
+        let first_data_block = self.superblock.superblock_block_number();
         let blocks_per_group = self.superblock.blocks_per_group();
-        self.allocate_resource(blocks_per_group, Self::try_reserve_block_in_group)
-            .map(|block| block.and_then(BlockAddress::new))
+        Ok(self
+            .allocate_resource(Resource::Block)?
+            .and_then(|(group, index)| {
+                BlockAddress::new(first_data_block + group * blocks_per_group + index)
+            }))
     }
 
+    /// Marks the first free inode as used and returns it.
     pub fn allocate_inode(&mut self) -> Result<Option<InodeAddress>, Error> {
         let inodes_per_group = self.superblock.inodes_per_group();
-        self.allocate_resource(inodes_per_group, Self::try_reserve_inode_in_group)
-            .map(|inode| inode.and_then(InodeAddress::new))
+        Ok(self
+            .allocate_resource(Resource::Inode)?
+            .and_then(|(group, index)| InodeAddress::new(group * inodes_per_group + index + 1)))
     }
 
-    fn allocate_resource<F>(
-        &mut self,
-        resource_per_group: u32,
-        try_reserve_in_group: F,
-    ) -> Result<Option<u32>, Error>
-    where
-        F: Fn(&mut Self, usize) -> Result<Option<usize>, Error>,
-    {
-        let block_size = self.superblock.block_size();
-        let num_groups = self.bgdt.len();
-        let bgdt_offset = self.bgdt_offset();
-
-        for group_index in 0..num_groups {
-            let first_free_resource_index = try_reserve_in_group(self, group_index)?;
-            if first_free_resource_index.is_none() {
+    /// Reserves the first free resource and returns its group and its index
+    /// in the group.
+    fn allocate_resource(&mut self, resource: Resource) -> Result<Option<(u32, u32)>, Error> {
+        for group_index in 0..self.bgdt.len() {
+            let descriptor = &self.bgdt[group_index];
+            let (free, bitmap_block, count) = match resource {
+                Resource::Block => {
+                    // the last group may be shorter than the others
+                    let group_start = self.superblock.superblock_block_number()
+                        + group_index as u32 * self.superblock.blocks_per_group();
+                    let count = (self.superblock.num_blocks() - group_start)
+                        .min(self.superblock.blocks_per_group());
+                    (
+                        descriptor.num_unallocated_blocks(),
+                        descriptor.block_usage_bitmap_block(),
+                        count,
+                    )
+                }
+                Resource::Inode => (
+                    descriptor.num_unallocated_inodes(),
+                    descriptor.inode_usage_bitmap_block(),
+                    self.superblock.inodes_per_group(),
+                ),
+            };
+            if free == 0 {
                 continue;
             }
-            let first_free_resource_index = first_free_resource_index.unwrap();
+            let bitmap_block = BlockAddress::new(bitmap_block)
+                .expect("bgdt does not have valid block address for bitmap block");
+            let Some(index) = self.try_reserve_in_bitmap(bitmap_block, count)? else {
+                continue;
+            };
 
             let descriptor = &mut self.bgdt[group_index];
-            *descriptor.num_unallocated_blocks_mut() -= 1;
-
-            // read the block group descriptor table
-            let mut bgdt_data = vec![0_u8; block_size as usize];
-            self.block_device
-                .read_at(bgdt_offset, &mut bgdt_data)
-                .map_err(|_| Error::UnableToReadBlockGroupDescriptorTable)?;
-            // merge the changed descriptor back into the table
-            let bgd_offset = group_index * BGD_SIZE;
-            let bgd_end = bgd_offset + BGD_SIZE;
-            let bgd_data = Into::<[u8; BGD_SIZE]>::into(&*descriptor);
-            bgdt_data[bgd_offset..bgd_end].copy_from_slice(&bgd_data);
-            // write the block group descriptor table back
-            self.block_device
-                .write_at(bgdt_offset, &bgdt_data)
-                .map_err(|_| Error::UnableToWriteBlockGroupDescriptorTable)?;
-
-            *self.superblock.num_unallocated_blocks_mut() -= 1;
-            let superblock_data = Into::<SuperblockArray>::into(&self.superblock);
-            self.block_device
-                .write_at(SUPERBLOCK_OFFSET, superblock_data.as_slice())
-                .map_err(|_| Error::UnableToWriteSuperblock)?;
-
-            let global_resource_num =
-                group_index as u32 * resource_per_group + first_free_resource_index as u32;
-            return Ok(Some(global_resource_num));
+            match resource {
+                Resource::Block => {
+                    *descriptor.num_unallocated_blocks_mut() -= 1;
+                    *self.superblock.num_unallocated_blocks_mut() -= 1;
+                }
+                Resource::Inode => {
+                    *descriptor.num_unallocated_inodes_mut() -= 1;
+                    *self.superblock.num_unallocated_inodes_mut() -= 1;
+                }
+            }
+            self.write_group_descriptor(group_index)?;
+            self.write_superblock()?;
+
+            return Ok(Some((group_index as u32, index)));
         }
 
         Ok(None)
     }
 
-    fn try_reserve_block_in_group(&mut self, group_index: usize) -> Result<Option<usize>, Error> {
-        let bitmap_block = self.bgdt[group_index].block_usage_bitmap_block();
-        let bitmap_block_address = BlockAddress::new(bitmap_block)
-            .expect("bgdt does not have valid block address for bitmap block");
-        self.try_reserve_in_group_with_bitmap(bitmap_block_address)
+    /// Sets the first clear bit among the first `count` bits of the bitmap
+    /// and returns its index.
+    fn try_reserve_in_bitmap(
+        &mut self,
+        bitmap_block: BlockAddress,
+        count: u32,
+    ) -> Result<Option<u32>, Error> {
+        let mut bitmap = vec![0_u8; self.superblock.block_size() as usize];
+        self.read_block(bitmap_block, &mut bitmap)?;
+
+        let Some(index) = (0..count).find(|i| bitmap[*i as usize / 8] & (1 << (i % 8)) == 0) else {
+            return Ok(None);
+        };
+        bitmap[index as usize / 8] |= 1 << (index % 8);
+        self.write_block(bitmap_block, &bitmap)?;
+        Ok(Some(index))
     }
 
-    fn try_reserve_inode_in_group(&mut self, group_index: usize) -> Result<Option<usize>, Error> {
-        let bitmap_block = self.bgdt[group_index].inode_usage_bitmap_block();
-        let bitmap_block_address = BlockAddress::new(bitmap_block)
-            .expect("bgdt does not have valid block address for bitmap block");
-        self.try_reserve_in_group_with_bitmap(bitmap_block_address)
+    /// Writes the descriptor of the given group back into the table, which
+    /// may span more than one block.
+    pub(crate) fn write_group_descriptor(&mut self, group_index: usize) -> Result<(), Error> {
+        let bgd_data = Into::<[u8; BGD_SIZE]>::into(&self.bgdt[group_index]);
+        self.block_device
+            .write_at(self.bgdt_offset() + group_index * BGD_SIZE, &bgd_data)
+            .map_err(|_| Error::UnableToWriteBlockGroupDescriptorTable)
+            .map(|_| ())
     }
 
-    fn try_reserve_in_group_with_bitmap(
-        &mut self,
-        bitmap_block: BlockAddress,
-    ) -> Result<Option<usize>, Error> {
-        let mut inode_bitmap = vec![0_u8; self.superblock.block_size() as usize];
-        self.read_block(bitmap_block, &mut inode_bitmap)?;
-
-        for (i, byte) in inode_bitmap.iter_mut().enumerate() {
-            for bit_index in 0..8 {
-                if *byte & (1_u8 << bit_index) == 0 {
-                    *byte |= 1 << bit_index;
-                    return Ok(Some(i * 8 + bit_index));
-                }
-            }
-        }
-        self.write_block(bitmap_block, &inode_bitmap)?;
-        Ok(None)
+    fn write_superblock(&mut self) -> Result<(), Error> {
+        let superblock_data = Into::<SuperblockArray>::into(&self.superblock);
+        self.block_device
+            .write_at(SUPERBLOCK_OFFSET, superblock_data.as_slice())
+            .map_err(|_| Error::UnableToWriteSuperblock)
+            .map(|_| ())
     }
 }
+
+#[derive(Copy, Clone)]
+enum Resource {
+    Block,
+    Inode,
+}
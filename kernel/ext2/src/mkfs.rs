@@ -0,0 +1,475 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use kernel_device::block::BlockDevice;
+
+use crate::block_group::BlockGroupDescriptor;
+use crate::dir::{DirEntry, DirType};
+use crate::{
+    BGD_SIZE, BlockAddress, Error, Ext2FsId, Inode, InodeAddress, InodeRawArray, Permissions,
+    ROOT_DIR_INODE_ADDRESS, SUPERBLOCK_OFFSET, Superblock, SuperblockArray, Type,
+};
+
+/// The size of the inodes that [`format`] creates. This is the size of
+/// the fields that [`Inode`] knows about.
+pub(crate) const INODE_SIZE: u16 = 128;
+
+/// The first inode that isn't reserved, which is `lost+found`.
+pub(crate) const FIRST_NON_RESERVED_INODE: u32 = 11;
+
+const LOST_AND_FOUND_INODE_ADDRESS: InodeAddress =
+    InodeAddress::new(FIRST_NON_RESERVED_INODE).unwrap();
+
+/// Devices smaller than this get 1KiB blocks and an inode per 4KiB by
+/// default, larger ones 4KiB blocks and an inode per 16KiB, which is what
+/// `mke2fs` does.
+const SMALL_DEVICE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Each group has at least this many inodes, so that the reserved inodes
+/// and `lost+found` are all in the first group.
+const MIN_INODES_PER_GROUP: u32 = 16;
+
+/// A last group with fewer data blocks than this isn't worth its metadata
+/// and is left out of the file system.
+const MIN_DATA_BLOCKS_IN_LAST_GROUP: u32 = 50;
+
+/// The root directory and `lost+found` have one data block each.
+const DIRECTORY_BLOCKS: u32 = 2;
+
+/// The parameters of a new file system, see [`format`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FormatOptions {
+    /// The size of a block in bytes, which is 1024, 2048 or 4096. `None`
+    /// picks one depending on the size of the device.
+    pub block_size: Option<u32>,
+    /// The number of bytes of the device per inode. Every file needs an
+    /// inode, so this limits how many files fit on the file system. `None`
+    /// picks one depending on the size of the device.
+    pub bytes_per_inode: Option<u32>,
+    /// The volume name, at most 16 bytes.
+    pub label: String,
+    pub fsid: Ext2FsId,
+    /// The percentage of blocks that only the super user can allocate.
+    pub reserved_percent: u8,
+    /// The creation time of the file system and its directories, in
+    /// seconds since the epoch.
+    pub timestamp: u32,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            block_size: None,
+            bytes_per_inode: None,
+            label: String::new(),
+            fsid: Ext2FsId::from([0; 16]),
+            reserved_percent: 5,
+            timestamp: 0,
+        }
+    }
+}
+
+/// Creates an empty ext2 file system on `device` that contains only the
+/// root directory and `lost+found`, and returns its superblock.
+///
+/// The file system spans the whole device. Like `mke2fs`, only groups 0, 1
+/// and the powers of 3, 5 and 7 have a backup of the superblock and the
+/// block group descriptors.
+///
+/// # Errors
+/// Returns an error if the options are invalid, if the device is too
+/// small for a file system, or if writing to it fails.
+pub fn format<T>(device: &mut T, options: &FormatOptions) -> Result<Superblock, Error>
+where
+    T: BlockDevice,
+{
+    if options.label.len() > 16 {
+        return Err(Error::LabelTooLong);
+    }
+    let device_size = device.sector_count() as u64 * device.sector_size() as u64;
+    let layout = Layout::new(options, device_size)?;
+    let block_size = layout.block_size as usize;
+
+    let mut descriptors = Vec::with_capacity(layout.num_groups as usize);
+    let mut free_blocks = 0;
+    let mut free_inodes = 0;
+    for group in 0..layout.num_groups {
+        let (used_blocks, used_inodes, directories) = if group == 0 {
+            (
+                layout.overhead(group) + DIRECTORY_BLOCKS,
+                FIRST_NON_RESERVED_INODE,
+                2,
+            )
+        } else {
+            (layout.overhead(group), 0, 0)
+        };
+
+        // Both bitmaps are one block long, so the bits past the end of the
+        // group are set to keep them from being allocated.
+        let mut block_bitmap = vec![0_u8; block_size];
+        set_bits(&mut block_bitmap, 0..used_blocks);
+        set_bits(
+            &mut block_bitmap,
+            layout.group_blocks(group)..layout.blocks_per_group,
+        );
+        let mut inode_bitmap = vec![0_u8; block_size];
+        set_bits(&mut inode_bitmap, 0..used_inodes);
+        set_bits(
+            &mut inode_bitmap,
+            layout.inodes_per_group..layout.block_size * 8,
+        );
+
+        write_block(device, &layout, layout.block_bitmap(group), &block_bitmap)?;
+        write_block(device, &layout, layout.inode_bitmap(group), &inode_bitmap)?;
+        let zeroes = vec![0_u8; block_size];
+        for block in 0..layout.inode_table_blocks {
+            write_block(device, &layout, layout.inode_table(group) + block, &zeroes)?;
+        }
+
+        let group_free_blocks = layout.group_blocks(group) - used_blocks;
+        let group_free_inodes = layout.inodes_per_group - used_inodes;
+        free_blocks += group_free_blocks;
+        free_inodes += group_free_inodes;
+        descriptors.push(BlockGroupDescriptor::new(
+            layout.block_bitmap(group),
+            layout.inode_bitmap(group),
+            layout.inode_table(group),
+            group_free_blocks as u16,
+            group_free_inodes as u16,
+            directories,
+        ));
+    }
+
+    let root_block = layout.data_start(0);
+    let lost_and_found_block = root_block + 1;
+    write_block(
+        device,
+        &layout,
+        root_block,
+        &dir_block(
+            block_size,
+            &[
+                (ROOT_DIR_INODE_ADDRESS, "."),
+                (ROOT_DIR_INODE_ADDRESS, ".."),
+                (LOST_AND_FOUND_INODE_ADDRESS, "lost+found"),
+            ],
+        ),
+    )?;
+    write_block(
+        device,
+        &layout,
+        lost_and_found_block,
+        &dir_block(
+            block_size,
+            &[
+                (LOST_AND_FOUND_INODE_ADDRESS, "."),
+                (ROOT_DIR_INODE_ADDRESS, ".."),
+            ],
+        ),
+    )?;
+
+    // the root directory is linked from itself, its parent and lost+found
+    let root = dir_inode(
+        &layout,
+        options,
+        root_block,
+        3,
+        Permissions::from_bits_truncate(0o755),
+    );
+    let lost_and_found = dir_inode(
+        &layout,
+        options,
+        lost_and_found_block,
+        2,
+        Permissions::from_bits_truncate(0o700),
+    );
+    for (address, inode) in [
+        (ROOT_DIR_INODE_ADDRESS, root),
+        (LOST_AND_FOUND_INODE_ADDRESS, lost_and_found),
+    ] {
+        let offset = layout.offset(layout.inode_table(0))
+            + (address.get() - 1) as usize * INODE_SIZE as usize;
+        device
+            .write_at(offset, InodeRawArray::from(&inode).as_slice())
+            .map_err(|_| Error::DeviceWrite)?;
+    }
+
+    let mut descriptor_table = vec![0_u8; layout.descriptor_blocks as usize * block_size];
+    for (descriptor, data) in descriptors
+        .iter()
+        .zip(descriptor_table.as_chunks_mut::<BGD_SIZE>().0)
+    {
+        *data = <[u8; BGD_SIZE]>::from(descriptor);
+    }
+
+    let mut superblock = Superblock::new(&layout, options, free_blocks, free_inodes);
+    for group in (0..layout.num_groups).filter(|&group| layout.has_superblock(group)) {
+        *superblock.this_superblock_block_group_mut() = group as u16;
+        let offset = if group == 0 {
+            SUPERBLOCK_OFFSET
+        } else {
+            layout.offset(layout.group_start(group))
+        };
+        device
+            .write_at(offset, SuperblockArray::from(&superblock).as_slice())
+            .map_err(|_| Error::UnableToWriteSuperblock)?;
+        device
+            .write_at(
+                layout.offset(layout.group_start(group) + 1),
+                &descriptor_table,
+            )
+            .map_err(|_| Error::UnableToWriteBlockGroupDescriptorTable)?;
+    }
+    *superblock.this_superblock_block_group_mut() = 0;
+
+    Ok(superblock)
+}
+
+/// Where the parts of a new file system go.
+///
+/// Every group starts with a backup of the superblock and the descriptor
+/// table if it has one, followed by its block bitmap, inode bitmap and
+/// inode table. The rest of the group holds data.
+pub(crate) struct Layout {
+    pub(crate) block_size: u32,
+    pub(crate) num_blocks: u32,
+    pub(crate) first_data_block: u32,
+    pub(crate) blocks_per_group: u32,
+    pub(crate) inodes_per_group: u32,
+    pub(crate) num_groups: u32,
+    descriptor_blocks: u32,
+    inode_table_blocks: u32,
+}
+
+impl Layout {
+    fn new(options: &FormatOptions, device_size: u64) -> Result<Self, Error> {
+        let small = device_size < SMALL_DEVICE_SIZE;
+        let block_size = options
+            .block_size
+            .unwrap_or(if small { 1024 } else { 4096 });
+        if !matches!(block_size, 1024 | 2048 | 4096) {
+            return Err(Error::InvalidBlockSize(block_size));
+        }
+        let bytes_per_inode = options
+            .bytes_per_inode
+            .unwrap_or(if small { 4096 } else { 16384 });
+        if bytes_per_inode < 1024 {
+            return Err(Error::InvalidInodeRatio(bytes_per_inode));
+        }
+
+        let inodes_per_block = block_size / u32::from(INODE_SIZE);
+        let mut layout = Self {
+            block_size,
+            num_blocks: u32::try_from(device_size / u64::from(block_size)).unwrap_or(u32::MAX),
+            first_data_block: u32::from(block_size == 1024),
+            blocks_per_group: block_size * 8,
+            inodes_per_group: 0,
+            num_groups: 0,
+            descriptor_blocks: 0,
+            inode_table_blocks: 0,
+        };
+        loop {
+            layout.num_groups = layout
+                .num_blocks
+                .checked_sub(layout.first_data_block)
+                .filter(|&blocks| blocks > 0)
+                .ok_or(Error::DeviceTooSmall)?
+                .div_ceil(layout.blocks_per_group);
+
+            let num_inodes =
+                u64::from(layout.num_blocks) * u64::from(block_size) / u64::from(bytes_per_inode);
+            let max_inodes_per_group = (block_size * 8)
+                .min(u32::MAX / layout.num_groups)
+                .min(layout.blocks_per_group / 2 * inodes_per_block);
+            layout.inodes_per_group =
+                u32::try_from(num_inodes.div_ceil(u64::from(layout.num_groups)))
+                    .unwrap_or(u32::MAX)
+                    .max(MIN_INODES_PER_GROUP)
+                    .next_multiple_of(inodes_per_block)
+                    .min(max_inodes_per_group / inodes_per_block * inodes_per_block);
+            layout.inode_table_blocks = layout.inodes_per_group / inodes_per_block;
+            layout.descriptor_blocks = (layout.num_groups * BGD_SIZE as u32).div_ceil(block_size);
+
+            let last = layout.num_groups - 1;
+            if last == 0 {
+                if layout.group_blocks(0) < layout.overhead(0) + DIRECTORY_BLOCKS {
+                    return Err(Error::DeviceTooSmall);
+                }
+                return Ok(layout);
+            }
+            if layout.group_blocks(last) >= layout.overhead(last) + MIN_DATA_BLOCKS_IN_LAST_GROUP {
+                return Ok(layout);
+            }
+            layout.num_blocks = layout.group_start(last);
+        }
+    }
+
+    fn offset(&self, block: u32) -> usize {
+        block as usize * self.block_size as usize
+    }
+
+    fn group_start(&self, group: u32) -> u32 {
+        self.first_data_block + group * self.blocks_per_group
+    }
+
+    /// The number of blocks in `group`, which is less than
+    /// [`Self::blocks_per_group`] for the last group.
+    fn group_blocks(&self, group: u32) -> u32 {
+        (self.num_blocks - self.group_start(group)).min(self.blocks_per_group)
+    }
+
+    fn has_superblock(&self, group: u32) -> bool {
+        let is_power_of = |base: u32| {
+            let mut n = group;
+            while n > 1 && n.is_multiple_of(base) {
+                n /= base;
+            }
+            n == 1
+        };
+        group == 0 || [3, 5, 7].into_iter().any(is_power_of)
+    }
+
+    /// The number of blocks at the start of `group` that hold metadata.
+    fn overhead(&self, group: u32) -> u32 {
+        self.data_start(group) - self.group_start(group)
+    }
+
+    fn block_bitmap(&self, group: u32) -> u32 {
+        let backup = if self.has_superblock(group) {
+            1 + self.descriptor_blocks
+        } else {
+            0
+        };
+        self.group_start(group) + backup
+    }
+
+    fn inode_bitmap(&self, group: u32) -> u32 {
+        self.block_bitmap(group) + 1
+    }
+
+    fn inode_table(&self, group: u32) -> u32 {
+        self.inode_bitmap(group) + 1
+    }
+
+    fn data_start(&self, group: u32) -> u32 {
+        self.inode_table(group) + self.inode_table_blocks
+    }
+}
+
+fn write_block<T>(device: &mut T, layout: &Layout, block: u32, data: &[u8]) -> Result<(), Error>
+where
+    T: BlockDevice,
+{
+    device
+        .write_at(layout.offset(block), data)
+        .map(|_| ())
+        .map_err(|_| Error::DeviceWrite)
+}
+
+fn set_bits(bitmap: &mut [u8], bits: Range<u32>) {
+    for bit in bits {
+        bitmap[bit as usize / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// A directory block with the given entries, where the last entry spans
+/// the rest of the block.
+fn dir_block(block_size: usize, entries: &[(InodeAddress, &str)]) -> Vec<u8> {
+    let mut data = vec![0_u8; block_size];
+    let mut offset = 0;
+    for (i, (inode, name)) in entries.iter().enumerate() {
+        let size = if i == entries.len() - 1 {
+            (block_size - offset) as u16
+        } else {
+            DirEntry::size(name.len() as u16)
+        };
+        let entry = DirEntry::new(*inode, name, DirType::Directory, size).serialize(true);
+        data[offset..offset + entry.len()].copy_from_slice(&entry);
+        offset += size as usize;
+    }
+    data
+}
+
+fn dir_inode(
+    layout: &Layout,
+    options: &FormatOptions,
+    block: u32,
+    links: u16,
+    perm: Permissions,
+) -> Inode {
+    let mut inode = Inode::new(Type::Directory);
+    inode.set_perm(perm);
+    inode.set_file_size_lower(layout.block_size);
+    inode.set_direct_ptr(0, BlockAddress::new(block));
+    *inode.num_disk_sectors_mut() = layout.block_size / 512;
+    *inode.num_hard_links_mut() = links;
+    *inode.creation_time_mut() = options.timestamp;
+    *inode.last_access_time_mut() = options.timestamp;
+    *inode.last_modification_time_mut() = options.timestamp;
+    inode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout_for(block_size: u32, device_size: u64) -> Result<Layout, Error> {
+        let options = FormatOptions {
+            block_size: Some(block_size),
+            ..FormatOptions::default()
+        };
+        Layout::new(&options, device_size)
+    }
+
+    #[test]
+    fn test_sparse_superblocks() {
+        let layout = layout_for(1024, 1 << 30).unwrap();
+        let groups = (0..layout.num_groups)
+            .filter(|&group| layout.has_superblock(group))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 3, 5, 7, 9, 25, 27, 49, 81, 125], groups);
+    }
+
+    #[test]
+    fn test_small_last_group_is_dropped() {
+        let layout = layout_for(1024, (8192 + 1 + 20) * 1024).unwrap();
+        assert_eq!(1, layout.num_groups);
+        assert_eq!(8193, layout.num_blocks);
+
+        let layout = layout_for(1024, (8192 + 1 + 2000) * 1024).unwrap();
+        assert_eq!(2, layout.num_groups);
+        assert_eq!(8192 + 1 + 2000, layout.num_blocks);
+    }
+
+    #[test]
+    fn test_groups_fit_their_metadata() {
+        for block_size in [1024, 2048, 4096] {
+            for device_size in [64 << 10, 1 << 20, 3 << 20, 100 << 20, 1 << 30] {
+                let layout = layout_for(block_size, device_size).unwrap();
+                for group in 0..layout.num_groups {
+                    assert!(
+                        layout.overhead(group) < layout.group_blocks(group),
+                        "{block_size} {device_size} {group}"
+                    );
+                }
+                assert!(layout.inodes_per_group <= block_size * 8);
+                assert!(layout.inodes_per_group >= MIN_INODES_PER_GROUP);
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_small() {
+        assert!(matches!(layout_for(1024, 0), Err(Error::DeviceTooSmall)));
+        assert!(matches!(
+            layout_for(1024, 8 * 1024),
+            Err(Error::DeviceTooSmall)
+        ));
+        assert!(matches!(
+            layout_for(4096, 16 * 1024),
+            Err(Error::DeviceTooSmall)
+        ));
+    }
+}
@@ -1,7 +1,10 @@
+use core::fmt::{Display, Formatter};
 use core::ops::{Deref, DerefMut, Shl};
+use core::str::FromStr;
 
 use bitflags::bitflags;
 
+use crate::mkfs::{FIRST_NON_RESERVED_INODE, FormatOptions, INODE_SIZE, Layout};
 use crate::{bytefield, bytefield_field_read, bytefield_field_write, check_is_implemented};
 
 pub struct SuperblockArray([u8; 1024]);
@@ -85,6 +88,62 @@ bytefield! {
 }
 
 impl Superblock {
+    /// The superblock of a new file system with the given layout, see
+    /// [`crate::format`].
+    pub(crate) fn new(
+        layout: &Layout,
+        options: &FormatOptions,
+        num_unallocated_blocks: u32,
+        num_unallocated_inodes: u32,
+    ) -> Self {
+        let log2_block_size = layout.block_size.trailing_zeros() - 10;
+        let mut volume_name = [0_u8; 16];
+        volume_name[..options.label.len()].copy_from_slice(options.label.as_bytes());
+        Self {
+            num_inodes: layout.inodes_per_group * layout.num_groups,
+            num_blocks: layout.num_blocks,
+            num_superuser_reserved_blocks: (u64::from(layout.num_blocks)
+                * u64::from(options.reserved_percent)
+                / 100) as u32,
+            num_unallocated_blocks,
+            num_unallocated_inodes,
+            superblock_block_number: layout.first_data_block,
+            log2_block_size,
+            log2_fragment_size: log2_block_size,
+            blocks_per_group: layout.blocks_per_group,
+            fragments_per_group: layout.blocks_per_group,
+            inodes_per_group: layout.inodes_per_group,
+            last_mount_time: 0,
+            last_written_time: options.timestamp,
+            mounts_since_fsck: 0,
+            mounts_allowed_before_fsck: u16::MAX,
+            magic_number: EXT2_MAGIC,
+            state: State::CLEAN.bits(),
+            error_policy: ErrorPolicy::IGNORE.bits(),
+            version_minor: 0,
+            last_fsck: options.timestamp,
+            fsck_force_interval: 0,
+            os_id: 0,
+            version_major: 1,
+            uid_for_reserved_blocks: 0,
+            gid_for_reserved_blocks: 0,
+            first_non_reserved_inode: FIRST_NON_RESERVED_INODE,
+            inode_size: INODE_SIZE,
+            this_superblock_block_group: 0,
+            optional_features: 0,
+            required_features: RequiredFeatures::DIRECTORY_ENTRIES_HAVE_TYPE.bits(),
+            write_required_features: (ReadOnlyFeatures::SPARSE_SUPERBLOCK_AND_GDTS
+                | ReadOnlyFeatures::USE_64BIT_FILE_SIZE)
+                .bits(),
+            fsid: *options.fsid.as_bytes(),
+            volume_name,
+            last_mount_path: [0; 16],
+            compression: 0,
+            num_preallocate_blocks_file: 0,
+            num_preallocate_blocks_directory: 0,
+        }
+    }
+
     pub fn num_inodes(&self) -> u32 {
         self.num_inodes
     }
@@ -121,6 +180,17 @@ impl Superblock {
         u32::shl(1024, self.log2_block_size)
     }
 
+    /// Whether the fields that the layout of the file system is computed
+    /// from make sense. The arithmetic on those of a crafted image would
+    /// otherwise divide by zero or overflow.
+    #[must_use]
+    pub fn has_valid_layout(&self) -> bool {
+        self.log2_block_size <= MAX_LOG2_BLOCK_SIZE
+            && self.blocks_per_group != 0
+            && self.inodes_per_group != 0
+            && self.superblock_block_number < self.num_blocks
+    }
+
     pub fn fragment_size(&self) -> u32 {
         u32::shl(1024, self.log2_fragment_size)
     }
@@ -205,6 +275,10 @@ impl Superblock {
         self.this_superblock_block_group
     }
 
+    pub(crate) fn this_superblock_block_group_mut(&mut self) -> &mut u16 {
+        &mut self.this_superblock_block_group
+    }
+
     pub fn optional_features(&self) -> OptionalFeatures {
         OptionalFeatures::from_bits_truncate(self.optional_features)
     }
@@ -221,8 +295,15 @@ impl Superblock {
         Ext2FsId(self.fsid)
     }
 
+    /// The label of the file system, without the zeros that pad it to 16
+    /// bytes.
     pub fn volume_name(&self) -> &str {
-        core::str::from_utf8(&self.volume_name).unwrap()
+        let len = self
+            .volume_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.volume_name.len());
+        core::str::from_utf8(&self.volume_name[..len]).unwrap()
     }
 
     pub fn last_mount_path(&self) -> &str {
@@ -242,9 +323,69 @@ impl Superblock {
     }
 }
 
+/// Blocks are at most 64 KiB large, like on Linux.
+const MAX_LOG2_BLOCK_SIZE: u32 = 6;
+
+/// The magic number in [`Superblock::magic_number`] of every ext2 file
+/// system.
+pub const EXT2_MAGIC: u16 = 0xEF53;
+
+/// The UUID of an ext2 file system, which is formatted like
+/// `774b94ce-3d05-4b71-98fc-fcf637fd2b48`, the way `blkid` prints it.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct Ext2FsId([u8; 16]);
 
+impl Ext2FsId {
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl From<[u8; 16]> for Ext2FsId {
+    fn from(value: [u8; 16]) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for Ext2FsId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ParseFsIdError;
+
+impl FromStr for Ext2FsId {
+    type Err = ParseFsIdError;
+
+    /// Parses a UUID with or without dashes, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut digits = s.chars().filter(|c| *c != '-');
+        let mut id = [0_u8; 16];
+        for byte in &mut id {
+            let mut digit = || {
+                digits
+                    .next()
+                    .and_then(|c| c.to_digit(16))
+                    .ok_or(ParseFsIdError)
+            };
+            *byte = u8::try_from(digit()? << 4 | digit()?).unwrap();
+        }
+        if digits.next().is_some() {
+            return Err(ParseFsIdError);
+        }
+        Ok(Self(id))
+    }
+}
+
 bitflags! {
     #[derive(Debug, Copy, Clone, Eq, PartialEq)]
     pub struct OptionalFeatures: u32 {
@@ -427,4 +568,52 @@ mod tests {
         let reversed = Into::<SuperblockArray>::into(sb);
         assert_eq!(data[..206], reversed[..206]); // only check the actual superblock data
     }
+
+    #[test]
+    fn test_has_valid_layout() {
+        let valid = || {
+            let mut sb = Superblock::try_from(SuperblockArray::from([0_u8; 1024])).unwrap();
+            sb.num_blocks = 1024;
+            sb.superblock_block_number = 1;
+            sb.blocks_per_group = 8192;
+            sb.inodes_per_group = 128;
+            sb
+        };
+        assert!(valid().has_valid_layout());
+
+        let corruptions: [fn(&mut Superblock); 4] = [
+            |sb| sb.blocks_per_group = 0,
+            |sb| sb.inodes_per_group = 0,
+            |sb| sb.superblock_block_number = sb.num_blocks,
+            |sb| sb.log2_block_size = 22,
+        ];
+        for (i, corrupt) in corruptions.into_iter().enumerate() {
+            let mut sb = valid();
+            corrupt(&mut sb);
+            assert!(!sb.has_valid_layout(), "corruption {i}");
+        }
+    }
+
+    #[test]
+    fn test_fsid_display_and_parse() {
+        let id = Ext2FsId::from([
+            119, 75, 148, 206, 61, 5, 75, 113, 152, 252, 252, 246, 55, 253, 43, 72,
+        ]);
+        let s = "774b94ce-3d05-4b71-98fc-fcf637fd2b48";
+        assert_eq!(s, alloc::format!("{id}"));
+        assert_eq!(Ok(id), s.parse());
+        assert_eq!(Ok(id), "774B94CE3D054B7198FCFCF637FD2B48".parse());
+        assert_eq!(
+            Err(ParseFsIdError),
+            "774b94ce-3d05-4b71-98fc-fcf637fd2b".parse::<Ext2FsId>()
+        );
+        assert_eq!(
+            Err(ParseFsIdError),
+            "774b94ce-3d05-4b71-98fc-fcf637fd2b4800".parse::<Ext2FsId>()
+        );
+        assert_eq!(
+            Err(ParseFsIdError),
+            "774b94ce-3d05-4b71-98fc-fcf637fd2bxx".parse::<Ext2FsId>()
+        );
+    }
 }
@@ -0,0 +1,77 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use kernel_device::block::BlockDevice;
+
+use crate::{Directory, Error, Ext2Fs, SymLink, Type};
+
+/// Symlink targets shorter than this are stored inline in the block
+/// pointers of the inode instead of in a data block.
+const FAST_SYMLINK_MAX_LEN: usize = 60;
+
+impl<T> Ext2Fs<T>
+where
+    T: BlockDevice,
+{
+    /// Reads the target of the given symlink.
+    ///
+    /// Fast symlinks (no data blocks) are read from the inode itself,
+    /// slow symlinks from their first data block.
+    pub fn read_symlink(&self, link: &SymLink) -> Result<Vec<u8>, Error> {
+        let len = link.len();
+        if link.num_disk_sectors() == 0 {
+            if len > FAST_SYMLINK_MAX_LEN {
+                return Err(Error::InvalidSymLink);
+            }
+            return Ok(link.inline_data()[..len].to_vec());
+        }
+
+        let block_size = self.superblock.block_size() as usize;
+        if len > block_size {
+            return Err(Error::InvalidSymLink);
+        }
+        let mut data = vec![0_u8; block_size];
+        self.read_blocks_from_inode(link, 0, 0, &mut data)?;
+        data.truncate(len);
+        Ok(data)
+    }
+
+    /// Creates a symlink called `name` in `parent` that points to `target`.
+    ///
+    /// Short targets are stored inline, longer ones in a single data block.
+    pub fn create_symlink(
+        &mut self,
+        parent: &mut Directory,
+        name: &str,
+        target: &str,
+    ) -> Result<SymLink, Error> {
+        let block_size = self.superblock.block_size() as usize;
+        if target.is_empty() || target.len() >= block_size {
+            return Err(Error::InvalidSymLink);
+        }
+
+        let mut link: SymLink = self
+            .create_inode(parent, name, Type::SymLink)?
+            .try_into()
+            .unwrap(); // if we don't get an inode with type SymLink, something is really broken
+
+        let inode = link.inode_mut();
+        inode.set_file_size_lower(target.len() as u32);
+        *inode.num_hard_links_mut() = 1;
+        if target.len() < FAST_SYMLINK_MAX_LEN {
+            inode.set_inline_data(target.as_bytes());
+        } else {
+            let block = self.allocate_block()?.ok_or(Error::NoSpace)?;
+            let mut data = vec![0_u8; block_size];
+            data[..target.len()].copy_from_slice(target.as_bytes());
+            self.write_block(block, &data)?;
+
+            let inode = link.inode_mut();
+            inode.set_direct_ptr(0, Some(block));
+            *inode.num_disk_sectors_mut() = (block_size / 512) as u32;
+        }
+        self.write_inode(link.inode_address(), &link)?;
+
+        Ok(link)
+    }
+}
@@ -2,7 +2,9 @@ use alloc::vec;
 
 use kernel_device::block::BlockDevice;
 
-use crate::{Error, Ext2Fs, RegularFile};
+use crate::{BlockAddress, Error, Ext2Fs, Inode, RegularFile};
+
+const SZ: usize = size_of::<BlockAddress>();
 
 impl<T> Ext2Fs<T>
 where
@@ -14,6 +16,10 @@ where
         offset: usize,
         buf: &[u8],
     ) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
         let block_size = self.superblock.block_size();
         let offset = offset as u32;
 
@@ -33,35 +39,16 @@ where
             data
         };
 
-        let mut num_new_allocated_blocks = 0;
+        let mut inode_changed = false;
         let mut chunks = data.chunks_exact(block_size as usize);
-        for ((_, block), chunk) in (start_block..=end_block).enumerate().zip(&mut chunks) {
+        for (block, chunk) in (start_block..=end_block).zip(&mut chunks) {
             let block_address =
                 if let Some(block_address) = self.resolve_block_index(file, block)? {
                     block_address
                 } else {
                     // TODO: we don't need to allocate if the full content of this block would be zero if the fs allows sparse files
-                    let free_block_address = self.allocate_block()?;
-                    if free_block_address.is_none() {
-                        return Err(Error::NoSpace);
-                    }
-                    let free_block_address = free_block_address.unwrap();
-                    num_new_allocated_blocks += 1;
-
-                    let inode = file.inode_mut();
-                    let free_slot = inode
-                        .direct_ptrs()
-                        .enumerate()
-                        .find(|(_, ptr)| ptr.is_none())
-                        .map(|(i, _)| i);
-                    if let Some(free_slot) = free_slot {
-                        inode.set_direct_ptr(free_slot, Some(free_block_address));
-                        self.write_inode(file.inode_address(), file)?;
-                    } else {
-                        todo!("allocate indirect block");
-                    }
-
-                    free_block_address
+                    inode_changed = true;
+                    self.allocate_block_for_inode(file.inode_mut(), block)?
                 };
 
             self.write_block(block_address, chunk)?;
@@ -80,12 +67,115 @@ where
             let inode = file.inode_mut();
             inode.set_file_size_lower(new_size_lower);
             inode.set_file_size_upper(new_size_upper);
+            inode_changed = true;
+        }
 
-            *inode.num_disk_sectors_mut() += num_new_allocated_blocks;
-
+        if inode_changed {
             self.write_inode(file.inode_address(), file)?;
         }
 
         Ok(buf.len())
     }
+
+    /// Allocates a data block for the block with the given index in the
+    /// inode, along with any indirect blocks that are missing on the way to
+    /// it, and returns the data block.
+    ///
+    /// The inode is updated, but not written back to the device.
+    pub(crate) fn allocate_block_for_inode(
+        &mut self,
+        inode: &mut Inode,
+        block_index: u32,
+    ) -> Result<BlockAddress, Error> {
+        let (direct_limit, indirect_limit, double_indirect_limit) = self.indirect_pointer_limits();
+        let pointers_per_block = self.superblock.block_size() / SZ as u32;
+
+        if block_index < direct_limit {
+            let block = self.allocate_block_of_inode(inode)?;
+            inode.set_direct_ptr(block_index as usize, Some(block));
+            return Ok(block);
+        }
+
+        // the pointer in the inode and the index into each level of
+        // indirect blocks below it
+        let (top, path) = if block_index < indirect_limit {
+            (
+                inode.single_indirect_ptr(),
+                vec![block_index - direct_limit],
+            )
+        } else if block_index < double_indirect_limit {
+            let index = block_index - indirect_limit;
+            (
+                inode.double_indirect_ptr(),
+                vec![index / pointers_per_block, index % pointers_per_block],
+            )
+        } else {
+            let index = block_index - double_indirect_limit;
+            if u64::from(index) >= u64::from(pointers_per_block).pow(3) {
+                return Err(Error::InvalidBlockIndex(block_index));
+            }
+            (
+                inode.triple_indirect_ptr(),
+                vec![
+                    index / pointers_per_block / pointers_per_block,
+                    index / pointers_per_block % pointers_per_block,
+                    index % pointers_per_block,
+                ],
+            )
+        };
+
+        let mut table_block = match top {
+            Some(block) => block,
+            None => {
+                let block = self.allocate_indirect_block(inode)?;
+                if block_index < indirect_limit {
+                    inode.set_single_indirect_ptr(Some(block));
+                } else if block_index < double_indirect_limit {
+                    inode.set_double_indirect_ptr(Some(block));
+                } else {
+                    inode.set_triple_indirect_ptr(Some(block));
+                }
+                block
+            }
+        };
+
+        let mut table = vec![0_u8; self.superblock.block_size() as usize];
+        for (level, index) in path.iter().enumerate() {
+            let is_last = level == path.len() - 1;
+            self.read_block(table_block, &mut table)?;
+            let entry = &mut table.as_chunks_mut::<SZ>().0[*index as usize];
+            if let Some(next) = BlockAddress::new(u32::from_le_bytes(*entry)) {
+                debug_assert!(!is_last, "block {block_index} is already allocated");
+                table_block = next;
+                continue;
+            }
+
+            let next = if is_last {
+                self.allocate_block_of_inode(inode)?
+            } else {
+                self.allocate_indirect_block(inode)?
+            };
+            *entry = next.into_u32().to_le_bytes();
+            self.write_block(table_block, &table)?;
+            table_block = next;
+        }
+
+        Ok(table_block)
+    }
+
+    /// Allocates a zeroed block to hold pointers of the inode.
+    fn allocate_indirect_block(&mut self, inode: &mut Inode) -> Result<BlockAddress, Error> {
+        let block = self.allocate_block_of_inode(inode)?;
+        self.write_block(block, &vec![0_u8; self.superblock.block_size() as usize])?;
+        Ok(block)
+    }
+
+    /// Allocates a block and counts it towards the sectors of the inode.
+    fn allocate_block_of_inode(&mut self, inode: &mut Inode) -> Result<BlockAddress, Error> {
+        let block = self.allocate_block()?.ok_or(Error::NoSpace)?;
+        // the count is in units of 512 bytes, whatever the sector size of
+        // the device
+        *inode.num_disk_sectors_mut() += self.superblock.block_size() / 512;
+        Ok(block)
+    }
 }
@@ -0,0 +1,183 @@
+use kernel_device::block::MemoryBlockDevice;
+use kernel_ext2::{
+    Error, Ext2Fs, Ext2FsId, FormatOptions, InodeAddress, ROOT_DIR_INODE_ADDRESS, Type, format,
+};
+
+fn formatted(
+    size: usize,
+    sector_size: usize,
+    options: &FormatOptions,
+) -> Ext2Fs<MemoryBlockDevice<Vec<u8>>> {
+    let mut device = MemoryBlockDevice::try_new(sector_size, vec![0xAA_u8; size]).unwrap();
+    format(&mut device, options).unwrap();
+    Ext2Fs::try_new(device).unwrap()
+}
+
+#[test]
+fn test_format_and_mount() {
+    for (sector_size, block_size) in [1, 512, 4096]
+        .into_iter()
+        .flat_map(|sector_size| [1024, 2048, 4096].map(|block_size| (sector_size, block_size)))
+    {
+        let options = FormatOptions {
+            block_size: Some(block_size),
+            ..FormatOptions::default()
+        };
+        let fs = formatted(4 << 20, sector_size, &options);
+
+        let superblock = fs.superblock();
+        assert_eq!(block_size, superblock.block_size());
+        assert_eq!((4 << 20) / block_size, superblock.num_blocks());
+        assert_eq!(11, superblock.first_non_reserved_inode());
+
+        let root = fs.read_root_inode().unwrap();
+        let entries = fs.list_dir(&root).unwrap();
+        let names = entries
+            .iter()
+            .map(|e| e.name().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![".", "..", "lost+found"], names);
+        assert_eq!(ROOT_DIR_INODE_ADDRESS, entries[1].inode());
+
+        let (address, lost_and_found) = fs
+            .resolve_dir_entry(entries.into_iter().nth(2).unwrap())
+            .unwrap();
+        assert_eq!(InodeAddress::new(11).unwrap(), address);
+        assert_eq!(Type::Directory, lost_and_found.typ());
+        assert_eq!(2, lost_and_found.num_hard_links());
+        let entries = fs.list_dir(&lost_and_found).unwrap();
+        assert_eq!(ROOT_DIR_INODE_ADDRESS, entries[1].inode());
+    }
+}
+
+#[test]
+fn test_counts() {
+    let fs = formatted(
+        64 << 20,
+        512,
+        &FormatOptions {
+            block_size: Some(1024),
+            bytes_per_inode: Some(8192),
+            reserved_percent: 10,
+            ..FormatOptions::default()
+        },
+    );
+    let superblock = fs.superblock();
+    assert_eq!(65536, superblock.num_blocks());
+    assert_eq!(8192, superblock.blocks_per_group());
+    assert_eq!(8 * 1024, superblock.num_inodes());
+    assert_eq!(6553, superblock.num_superuser_reserved_blocks());
+    assert_eq!(8 * 1024 - 11, superblock.num_unallocated_inodes());
+
+    let metadata = superblock.num_blocks() - superblock.num_unallocated_blocks();
+    // boot block, superblock backups with a descriptor table in 5 of the 8
+    // groups, two bitmaps and 128 inode table blocks per group and one block
+    // for each of the directories
+    assert_eq!(1 + 5 * 2 + 8 * (2 + 128) + 2, metadata);
+}
+
+#[test]
+fn test_label_and_fsid() {
+    let fsid = "774b94ce-3d05-4b71-98fc-fcf637fd2b48"
+        .parse::<Ext2FsId>()
+        .unwrap();
+    let fs = formatted(
+        1 << 20,
+        512,
+        &FormatOptions {
+            label: "muffin".to_string(),
+            fsid,
+            timestamp: 1_700_000_000,
+            ..FormatOptions::default()
+        },
+    );
+    assert_eq!("muffin", fs.superblock().volume_name());
+    assert_eq!(fsid, fs.superblock().fsid());
+    assert_eq!(1_700_000_000, fs.superblock().last_written_time());
+    assert_eq!(1_700_000_000, fs.read_root_inode().unwrap().creation_time());
+}
+
+#[test]
+fn test_backup_superblocks() {
+    let mut device = MemoryBlockDevice::try_new(512, vec![0_u8; 32 << 20]).unwrap();
+    let options = FormatOptions {
+        block_size: Some(1024),
+        ..FormatOptions::default()
+    };
+    let superblock = format(&mut device, &options).unwrap();
+    assert_eq!(4, superblock.num_blocks().div_ceil(8192));
+
+    let data = device.data();
+    for (group, has_backup) in [(1, true), (2, false), (3, true)] {
+        let offset = (1 + group * 8192) * 1024;
+        let backup = &data[offset..offset + 1024];
+        assert_eq!(has_backup, backup[56..58] == [0x53, 0xEF], "group {group}");
+        if has_backup {
+            // everything but the number of the group is the same
+            assert_eq!(data[1024..1024 + 90], backup[..90]);
+            assert_eq!([group as u8, 0], backup[90..92]);
+        }
+    }
+}
+
+#[test]
+fn test_invalid_options() {
+    let mut device = MemoryBlockDevice::try_new(512, vec![0_u8; 1 << 20]).unwrap();
+    for (options, error) in [
+        (
+            FormatOptions {
+                block_size: Some(512),
+                ..FormatOptions::default()
+            },
+            Error::InvalidBlockSize(512),
+        ),
+        (
+            FormatOptions {
+                bytes_per_inode: Some(512),
+                ..FormatOptions::default()
+            },
+            Error::InvalidInodeRatio(512),
+        ),
+        (
+            FormatOptions {
+                label: "a label that is too long".to_string(),
+                ..FormatOptions::default()
+            },
+            Error::LabelTooLong,
+        ),
+    ] {
+        assert_eq!(Err(error), format(&mut device, &options).map(|_| ()));
+    }
+
+    let mut device = MemoryBlockDevice::try_new(512, vec![0_u8; 4096]).unwrap();
+    assert_eq!(
+        Err(Error::DeviceTooSmall),
+        format(&mut device, &FormatOptions::default()).map(|_| ())
+    );
+}
+
+#[test]
+fn test_corrupted_superblock() {
+    let mut device = MemoryBlockDevice::try_new(512, vec![0_u8; 1 << 20]).unwrap();
+    format(&mut device, &FormatOptions::default()).unwrap();
+    let image = device.data().clone();
+
+    // the offsets of fields in the superblock, which starts at byte 1024
+    for (field, offset, value) in [
+        ("blocks per group", 1024 + 32, 0),
+        ("inodes per group", 1024 + 40, 0),
+        ("superblock block number", 1024 + 20, u32::MAX),
+        ("log2 of the block size", 1024 + 24, 22),
+        // more block groups than the device has room for descriptors
+        ("number of blocks", 1024 + 4, u32::MAX),
+    ] {
+        let mut image = image.clone();
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        let device = MemoryBlockDevice::try_new(512, image).unwrap();
+        assert_eq!(
+            Some(Error::InvalidSuperblock),
+            Ext2Fs::try_new(device).err(),
+            "{field}"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use kernel_device::block::MemoryBlockDevice;
+use kernel_ext2::{Error, Ext2Fs, SymLink, Type};
+
+mod common;
+
+generate_tests!(
+    test_fast_symlink:
+    512 - test_fast_symlink_standard,
+    1 - test_fast_symlink_tiny,
+    32 - test_fast_symlink_small,
+    32768 - test_fast_symlink_large,
+    1048576 - test_fast_symlink_huge,
+);
+
+fn test_fast_symlink(sector_size: usize) {
+    let mut fs = cow_fs!("kernel/ext2/tests/filesystems/empty.img", sector_size);
+
+    let mut root = fs.read_root_inode().unwrap();
+    let target = "/usr/bin/file.txt";
+    let link = fs.create_symlink(&mut root, "link", target).unwrap();
+    assert_eq!(link.num_disk_sectors(), 0);
+    assert_eq!(fs.read_symlink(&link).unwrap(), target.as_bytes());
+
+    let (addr, inode) = fs
+        .find_and_resolve_entry(&root, |e| e.name() == Some("link"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(inode.typ(), Type::SymLink);
+    let link = SymLink::try_from((addr, inode)).unwrap();
+    assert_eq!(fs.read_symlink(&link).unwrap(), target.as_bytes());
+}
+
+generate_tests!(
+    test_slow_symlink:
+    512 - test_slow_symlink_standard,
+    1 - test_slow_symlink_tiny,
+    32 - test_slow_symlink_small,
+    32768 - test_slow_symlink_large,
+    1048576 - test_slow_symlink_huge,
+);
+
+fn test_slow_symlink(sector_size: usize) {
+    let mut fs = cow_fs!("kernel/ext2/tests/filesystems/empty.img", sector_size);
+
+    let mut root = fs.read_root_inode().unwrap();
+    let target = "../".repeat(100) + "some/deeply/nested/target";
+    let link = fs.create_symlink(&mut root, "link", &target).unwrap();
+    assert_ne!(link.num_disk_sectors(), 0);
+
+    let (addr, inode) = fs
+        .find_and_resolve_entry(&root, |e| e.name() == Some("link"))
+        .unwrap()
+        .unwrap();
+    let link = SymLink::try_from((addr, inode)).unwrap();
+    assert_eq!(fs.read_symlink(&link).unwrap(), target.as_bytes());
+}
+
+#[test]
+fn test_symlink_invalid_target() {
+    let mut fs = cow_fs!("kernel/ext2/tests/filesystems/empty.img", 512);
+
+    let mut root = fs.read_root_inode().unwrap();
+    assert_eq!(
+        fs.create_symlink(&mut root, "empty", "").unwrap_err(),
+        Error::InvalidSymLink
+    );
+    assert_eq!(
+        fs.create_symlink(&mut root, "long", &"a".repeat(4096))
+            .unwrap_err(),
+        Error::InvalidSymLink
+    );
+
+    fs.create_symlink(&mut root, "link", "target").unwrap();
+    let mut root = fs.read_root_inode().unwrap();
+    assert_eq!(
+        fs.create_symlink(&mut root, "link", "target").unwrap_err(),
+        Error::EntryExists
+    );
+}
@@ -1,5 +1,7 @@
 use kernel_device::block::MemoryBlockDevice;
-use kernel_ext2::{Error, Ext2Fs};
+use kernel_ext2::{
+    Directory, Error, Ext2Fs, FormatOptions, ROOT_DIR_INODE_ADDRESS, RegularFile, format,
+};
 
 mod common;
 
@@ -92,3 +94,82 @@ fn test_create_file_collision(sector_size: usize) {
     let result = fs.create_regular_file(&mut root, file_name);
     assert_eq!(result.unwrap_err(), Error::EntryExists);
 }
+
+fn formatted_fs(size: usize) -> Ext2Fs<MemoryBlockDevice<Vec<u8>>> {
+    let mut device = MemoryBlockDevice::try_new(512, vec![0_u8; size]).unwrap();
+    let options = FormatOptions {
+        block_size: Some(1024),
+        ..FormatOptions::default()
+    };
+    format(&mut device, &options).unwrap();
+    Ext2Fs::try_new(device).unwrap()
+}
+
+#[test]
+fn test_write_through_indirect_blocks() {
+    let mut fs = formatted_fs(8 << 20);
+    let free_blocks = fs.superblock().num_unallocated_blocks();
+
+    let mut root = fs.read_root_inode().unwrap();
+    let mut file = fs.create_regular_file(&mut root, "big").unwrap();
+    // past the direct and single indirect blocks into the double indirect ones
+    let num_blocks = 12 + 256 + 10;
+    let data = (0..num_blocks * 1024)
+        .map(|i| (i / 1024 + i) as u8)
+        .collect::<Vec<_>>();
+    assert_eq!(data.len(), fs.write_to_file(&mut file, 0, &data).unwrap());
+
+    // the data blocks, the single indirect block and the double indirect
+    // block with one table below it
+    let allocated = num_blocks as u32 + 3;
+    assert_eq!(allocated * 2, file.num_disk_sectors());
+    assert_eq!(
+        free_blocks - allocated,
+        fs.superblock().num_unallocated_blocks()
+    );
+
+    let (addr, inode) = fs
+        .find_and_resolve_entry(&root, |e| e.name() == Some("big"))
+        .unwrap()
+        .unwrap();
+    let file = RegularFile::try_from((addr, inode)).unwrap();
+    assert_eq!(data.len(), file.len());
+    let mut read = vec![0_u8; data.len()];
+    assert_eq!(data.len(), fs.read_from_file(&file, 0, &mut read).unwrap());
+    assert_eq!(data, read);
+}
+
+#[test]
+fn test_create_directories() {
+    let mut fs = formatted_fs(4 << 20);
+    let free_inodes = fs.superblock().num_unallocated_inodes();
+
+    let mut root = fs.read_root_inode().unwrap();
+    let mut dir = fs.create_directory(&mut root, "dir").unwrap();
+    assert_eq!(11 + 1, dir.inode_address().get());
+    assert_eq!(2, dir.num_hard_links());
+    assert_eq!(4, fs.read_root_inode().unwrap().num_hard_links());
+    assert_eq!(
+        Error::EntryExists,
+        fs.create_directory(&mut root, "dir").unwrap_err()
+    );
+
+    // enough entries to need more than one block
+    for i in 0..100 {
+        fs.create_regular_file(&mut dir, &format!("file_with_a_long_name_{i}"))
+            .unwrap();
+    }
+    assert!(dir.len() > 1024);
+    assert_eq!(free_inodes - 101, fs.superblock().num_unallocated_inodes());
+
+    let (addr, inode) = fs
+        .find_and_resolve_entry(&root, |e| e.name() == Some("dir"))
+        .unwrap()
+        .unwrap();
+    let dir = Directory::try_from((addr, inode)).unwrap();
+    let entries = fs.list_dir(&dir).unwrap();
+    assert_eq!(102, entries.len());
+    assert_eq!(Some("."), entries[0].name());
+    assert_eq!(ROOT_DIR_INODE_ADDRESS, entries[1].inode());
+    assert_eq!(Some("file_with_a_long_name_99"), entries[101].name());
+}
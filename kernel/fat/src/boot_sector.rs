@@ -0,0 +1,243 @@
+use crate::FatError;
+
+/// The size of a directory entry, both for short names and for the
+/// entries that hold a long name.
+pub(crate) const DIR_ENTRY_SIZE: usize = 32;
+
+/// Clusters 0 and 1 are reserved, the first data cluster is number 2.
+pub(crate) const FIRST_CLUSTER: u32 = 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+impl FatType {
+    /// The number of bits of a FAT entry. FAT32 only uses the lower 28.
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Fat12 => 12,
+            Self::Fat16 => 16,
+            Self::Fat32 => 32,
+        }
+    }
+
+    /// Determines the FAT type from the number of data clusters, which is
+    /// the only thing that does, no matter what the label in the boot
+    /// sector says.
+    fn from_cluster_count(count: u32) -> Self {
+        if count < 4085 {
+            Self::Fat12
+        } else if count < 65525 {
+            Self::Fat16
+        } else {
+            Self::Fat32
+        }
+    }
+}
+
+/// The BIOS parameter block in the first sector of the file system, and the
+/// layout of the file system that follows from it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BootSector {
+    fat_type: FatType,
+    bytes_per_sector: usize,
+    sectors_per_cluster: usize,
+    reserved_sectors: usize,
+    fat_count: usize,
+    root_entry_count: usize,
+    total_sectors: usize,
+    sectors_per_fat: usize,
+    root_cluster: u32,
+    fs_info_sector: Option<usize>,
+    volume_id: u32,
+    volume_label: [u8; 11],
+}
+
+impl TryFrom<&[u8; 512]> for BootSector {
+    type Error = FatError;
+
+    fn try_from(sector: &[u8; 512]) -> Result<Self, Self::Error> {
+        let u16_at =
+            |offset: usize| usize::from(u16::from_le_bytes([sector[offset], sector[offset + 1]]));
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(sector[offset..offset + 4].try_into().unwrap());
+
+        if sector[510..] != [0x55, 0xAA] {
+            return Err(FatError::InvalidBootSector);
+        }
+        let bytes_per_sector = u16_at(11);
+        let sectors_per_cluster = usize::from(sector[13]);
+        let reserved_sectors = u16_at(14);
+        let fat_count = usize::from(sector[16]);
+        let root_entry_count = u16_at(17);
+        let total_sectors = match u16_at(19) {
+            0 => u32_at(32) as usize,
+            count => count,
+        };
+        let sectors_per_fat = match u16_at(22) {
+            0 => u32_at(36) as usize,
+            count => count,
+        };
+        if !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+            || !sectors_per_cluster.is_power_of_two()
+            || reserved_sectors == 0
+            || fat_count == 0
+            || sectors_per_fat == 0
+        {
+            return Err(FatError::InvalidBootSector);
+        }
+
+        let root_dir_sectors = (root_entry_count * DIR_ENTRY_SIZE).div_ceil(bytes_per_sector);
+        let data_start = reserved_sectors + fat_count * sectors_per_fat + root_dir_sectors;
+        let cluster_count = total_sectors
+            .checked_sub(data_start)
+            .ok_or(FatError::InvalidBootSector)?
+            / sectors_per_cluster;
+        let fat_type =
+            FatType::from_cluster_count(u32::try_from(cluster_count).unwrap_or(u32::MAX));
+        // the FAT must have an entry for every cluster
+        if sectors_per_fat * bytes_per_sector * 8
+            < (cluster_count + FIRST_CLUSTER as usize) * fat_type.bits() as usize
+        {
+            return Err(FatError::InvalidBootSector);
+        }
+
+        let (root_cluster, fs_info_sector, label_offset) = if fat_type == FatType::Fat32 {
+            if root_entry_count != 0 {
+                return Err(FatError::InvalidBootSector);
+            }
+            let fs_info_sector = match u16_at(48) {
+                0 | 0xFFFF => None,
+                sector => Some(sector),
+            };
+            (u32_at(44), fs_info_sector, 67)
+        } else {
+            if root_entry_count == 0 {
+                return Err(FatError::InvalidBootSector);
+            }
+            (0, None, 39)
+        };
+
+        Ok(Self {
+            fat_type,
+            bytes_per_sector,
+            sectors_per_cluster,
+            reserved_sectors,
+            fat_count,
+            root_entry_count,
+            total_sectors,
+            sectors_per_fat,
+            root_cluster,
+            fs_info_sector,
+            volume_id: u32_at(label_offset),
+            volume_label: sector[label_offset + 4..label_offset + 15]
+                .try_into()
+                .unwrap(),
+        })
+    }
+}
+
+impl BootSector {
+    #[must_use]
+    pub fn fat_type(&self) -> FatType {
+        self.fat_type
+    }
+
+    #[must_use]
+    pub fn bytes_per_sector(&self) -> usize {
+        self.bytes_per_sector
+    }
+
+    /// The size of a cluster in bytes.
+    #[must_use]
+    pub fn cluster_size(&self) -> usize {
+        self.bytes_per_sector * self.sectors_per_cluster
+    }
+
+    /// The number of data clusters. Valid cluster numbers are
+    /// `2..cluster_count() + 2`.
+    #[must_use]
+    pub fn cluster_count(&self) -> u32 {
+        ((self.total_sectors - self.data_start_sector()) / self.sectors_per_cluster) as u32
+    }
+
+    /// The size of the whole file system in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.total_sectors * self.bytes_per_sector
+    }
+
+    /// The serial number that `mkfs.vfat` generates, usually shown as
+    /// `ABCD-1234`.
+    #[must_use]
+    pub fn volume_id(&self) -> u32 {
+        self.volume_id
+    }
+
+    /// The label in the boot sector, without the padding. The label in the
+    /// root directory, which some tools write instead, is ignored.
+    #[must_use]
+    pub fn volume_label(&self) -> &[u8] {
+        let len = self
+            .volume_label
+            .iter()
+            .rposition(|&b| b != b' ')
+            .map_or(0, |last| last + 1);
+        &self.volume_label[..len]
+    }
+
+    /// The first cluster of the root directory on FAT32. FAT12 and FAT16
+    /// have a fixed root directory region instead.
+    pub(crate) fn root_cluster(&self) -> Option<u32> {
+        (self.fat_type == FatType::Fat32).then_some(self.root_cluster)
+    }
+
+    pub(crate) fn fat_count(&self) -> usize {
+        self.fat_count
+    }
+
+    /// The byte offset of the first copy of the FAT.
+    pub(crate) fn fat_offset(&self) -> usize {
+        self.reserved_sectors * self.bytes_per_sector
+    }
+
+    /// The size of one copy of the FAT in bytes.
+    pub(crate) fn fat_size(&self) -> usize {
+        self.sectors_per_fat * self.bytes_per_sector
+    }
+
+    /// The byte offset and size of the fixed root directory of FAT12 and
+    /// FAT16.
+    pub(crate) fn root_dir_region(&self) -> (usize, usize) {
+        (
+            self.fat_offset() + self.fat_count * self.fat_size(),
+            self.root_entry_count * DIR_ENTRY_SIZE,
+        )
+    }
+
+    pub(crate) fn fs_info_offset(&self) -> Option<usize> {
+        self.fs_info_sector
+            .map(|sector| sector * self.bytes_per_sector)
+    }
+
+    fn data_start_sector(&self) -> usize {
+        self.reserved_sectors
+            + self.fat_count * self.sectors_per_fat
+            + (self.root_entry_count * DIR_ENTRY_SIZE).div_ceil(self.bytes_per_sector)
+    }
+
+    /// The byte offset of the data cluster `cluster`.
+    pub(crate) fn cluster_offset(&self, cluster: u32) -> usize {
+        (self.data_start_sector() + (cluster - FIRST_CLUSTER) as usize * self.sectors_per_cluster)
+            * self.bytes_per_sector
+    }
+
+    /// Returns whether `cluster` is a data cluster of this file system.
+    pub(crate) fn is_valid_cluster(&self, cluster: u32) -> bool {
+        (FIRST_CLUSTER..self.cluster_count() + FIRST_CLUSTER).contains(&cluster)
+    }
+}
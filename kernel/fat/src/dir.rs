@@ -0,0 +1,300 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::boot_sector::DIR_ENTRY_SIZE;
+use crate::name::ShortName;
+
+pub(crate) const ATTR_READ_ONLY: u8 = 0x01;
+pub(crate) const ATTR_VOLUME_ID: u8 = 0x08;
+pub(crate) const ATTR_DIRECTORY: u8 = 0x10;
+pub(crate) const ATTR_ARCHIVE: u8 = 0x20;
+/// The attributes of the entries that hold a long name, which old systems
+/// skip as a read-only, hidden, system volume label.
+const ATTR_LONG_NAME: u8 = 0x0F;
+
+/// The first byte of a free entry that follows used ones.
+pub(crate) const DELETED: u8 = 0xE5;
+/// The first byte of the entry after the last used one.
+const END: u8 = 0x00;
+
+/// The flag in the ordinal of the long name entry that comes first on disk
+/// and holds the end of the name.
+const LAST_LONG_ENTRY: u8 = 0x40;
+/// The number of UTF-16 code units in one long name entry.
+const LONG_ENTRY_CHARS: usize = 13;
+/// Where the code units of a long name entry are, split into three runs.
+const LONG_ENTRY_CHAR_OFFSETS: [usize; LONG_ENTRY_CHARS] =
+    [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+/// 1980-01-01, the earliest date that FAT can store, for entries that we
+/// create without a clock.
+const DEFAULT_DATE: u16 = (1 << 5) | 1;
+
+/// A used entry of a directory, together with its long name, if it has
+/// one.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct DirEntry {
+    pub(crate) name: String,
+    pub(crate) short_name: ShortName,
+    pub(crate) attributes: u8,
+    pub(crate) first_cluster: u32,
+    pub(crate) size: u32,
+    /// The index of the first entry of the long name, or of the short entry
+    /// if there is no long name.
+    pub(crate) first_slot: usize,
+    /// The index of the short entry.
+    pub(crate) slot: usize,
+}
+
+impl DirEntry {
+    pub(crate) fn is_directory(&self) -> bool {
+        self.attributes & ATTR_DIRECTORY != 0
+    }
+
+    /// Returns whether this is the `.` or `..` entry of a subdirectory.
+    pub(crate) fn is_dot(&self) -> bool {
+        self.short_name == ShortName::DOT || self.short_name == ShortName::DOT_DOT
+    }
+}
+
+/// The long name that the entries before a short entry spell out.
+struct LongName {
+    checksum: u8,
+    /// The ordinal of the next entry, counting down to 1.
+    next: u8,
+    chars: Vec<u16>,
+    first_slot: usize,
+}
+
+impl LongName {
+    fn start(entry: &[u8; DIR_ENTRY_SIZE], slot: usize) -> Option<Self> {
+        let count = entry[0] & !LAST_LONG_ENTRY;
+        if entry[0] & LAST_LONG_ENTRY == 0 || !(1..=20).contains(&count) {
+            return None;
+        }
+        let mut name = Self {
+            checksum: entry[13],
+            next: count,
+            chars: vec![0xFFFF; usize::from(count) * LONG_ENTRY_CHARS],
+            first_slot: slot,
+        };
+        name.push(entry).then_some(name)
+    }
+
+    /// Adds the next entry of the name, returning whether it belongs to it.
+    fn push(&mut self, entry: &[u8; DIR_ENTRY_SIZE]) -> bool {
+        let ordinal = entry[0] & !LAST_LONG_ENTRY;
+        if ordinal != self.next || ordinal == 0 || entry[13] != self.checksum {
+            return false;
+        }
+        let start = usize::from(ordinal - 1) * LONG_ENTRY_CHARS;
+        for (index, offset) in LONG_ENTRY_CHAR_OFFSETS.into_iter().enumerate() {
+            self.chars[start + index] = u16::from_le_bytes([entry[offset], entry[offset + 1]]);
+        }
+        self.next -= 1;
+        true
+    }
+
+    /// Returns the name if all of its entries were there and they belong to
+    /// the short entry with `short_name`.
+    fn finish(self, short_name: &ShortName) -> Option<String> {
+        if self.next != 0 || self.checksum != short_name.checksum() {
+            return None;
+        }
+        let len = self
+            .chars
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(self.chars.len());
+        let name = char::decode_utf16(self.chars[..len].iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect::<String>();
+        (!name.is_empty()).then_some(name)
+    }
+}
+
+/// Parses the used entries of a directory. The volume label in the root
+/// directory is skipped, `.` and `..` are not.
+pub(crate) fn parse_entries(data: &[u8]) -> Vec<DirEntry> {
+    let mut entries = Vec::new();
+    let mut long_name: Option<LongName> = None;
+    for (slot, entry) in data.as_chunks::<DIR_ENTRY_SIZE>().0.iter().enumerate() {
+        match entry[0] {
+            END => break,
+            DELETED => {
+                long_name = None;
+                continue;
+            }
+            _ => {}
+        }
+
+        let attributes = entry[11];
+        if attributes & 0x3F == ATTR_LONG_NAME {
+            let continued = long_name.as_mut().is_some_and(|name| name.push(entry));
+            if !continued {
+                long_name = LongName::start(entry, slot);
+            }
+            continue;
+        }
+        let long_name = long_name.take();
+        if attributes & ATTR_VOLUME_ID != 0 {
+            continue;
+        }
+
+        let short_name = ShortName(entry[..11].try_into().unwrap());
+        let (name, first_slot) =
+            match long_name.map(|name| (name.first_slot, name.finish(&short_name))) {
+                Some((first_slot, Some(name))) => (name, first_slot),
+                _ => (short_name.decode(entry[12]), slot),
+            };
+        let cluster_high = u16::from_le_bytes([entry[20], entry[21]]);
+        let cluster_low = u16::from_le_bytes([entry[26], entry[27]]);
+        entries.push(DirEntry {
+            name,
+            short_name,
+            attributes,
+            first_cluster: (u32::from(cluster_high) << 16) | u32::from(cluster_low),
+            size: u32::from_le_bytes(entry[28..32].try_into().unwrap()),
+            first_slot,
+            slot,
+        });
+    }
+    entries
+}
+
+/// Returns the slots of a directory that are free, which are the deleted
+/// ones and all from the end marker on.
+pub(crate) fn free_slots(data: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let entries = data.as_chunks::<DIR_ENTRY_SIZE>().0;
+    let end = entries
+        .iter()
+        .position(|entry| entry[0] == END)
+        .unwrap_or(entries.len());
+    entries
+        .iter()
+        .enumerate()
+        .filter(move |(slot, entry)| *slot >= end || entry[0] == DELETED)
+        .map(|(slot, _)| slot)
+}
+
+/// Builds a short entry.
+pub(crate) fn short_entry(
+    short_name: &ShortName,
+    case: u8,
+    attributes: u8,
+    first_cluster: u32,
+) -> [u8; DIR_ENTRY_SIZE] {
+    let mut entry = [0; DIR_ENTRY_SIZE];
+    entry[..11].copy_from_slice(&short_name.0);
+    entry[11] = attributes;
+    entry[12] = case;
+    // creation, last access and modification date
+    for offset in [16, 18, 24] {
+        entry[offset..offset + 2].copy_from_slice(&DEFAULT_DATE.to_le_bytes());
+    }
+    set_first_cluster(&mut entry, first_cluster);
+    entry
+}
+
+pub(crate) fn set_first_cluster(entry: &mut [u8; DIR_ENTRY_SIZE], cluster: u32) {
+    entry[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    entry[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+}
+
+pub(crate) fn set_size(entry: &mut [u8; DIR_ENTRY_SIZE], size: u32) {
+    entry[28..32].copy_from_slice(&size.to_le_bytes());
+}
+
+/// Builds the entries that hold `name` as a long name, in the order in
+/// which they go on disk, right before the short entry.
+pub(crate) fn long_entries(name: &str, checksum: u8) -> Vec<[u8; DIR_ENTRY_SIZE]> {
+    let chars = name.encode_utf16().collect::<Vec<_>>();
+    let count = chars.len().div_ceil(LONG_ENTRY_CHARS);
+    (1..=count)
+        .rev()
+        .map(|ordinal| {
+            let mut entry = [0; DIR_ENTRY_SIZE];
+            entry[0] = ordinal as u8;
+            if ordinal == count {
+                entry[0] |= LAST_LONG_ENTRY;
+            }
+            entry[11] = ATTR_LONG_NAME;
+            entry[13] = checksum;
+            let start = (ordinal - 1) * LONG_ENTRY_CHARS;
+            for (index, offset) in LONG_ENTRY_CHAR_OFFSETS.into_iter().enumerate() {
+                // the name ends with a 0 if it doesn't fill the last entry,
+                // and the rest is padded with 0xFFFF
+                let c = match (start + index).cmp(&chars.len()) {
+                    core::cmp::Ordering::Less => chars[start + index],
+                    core::cmp::Ordering::Equal => 0,
+                    core::cmp::Ordering::Greater => 0xFFFF,
+                };
+                entry[offset..offset + 2].copy_from_slice(&c.to_le_bytes());
+            }
+            entry
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory(name: &str, short_name: ShortName, case: u8) -> Vec<u8> {
+        let mut data = Vec::new();
+        for entry in long_entries(name, short_name.checksum()) {
+            data.extend_from_slice(&entry);
+        }
+        data.extend_from_slice(&short_entry(&short_name, case, ATTR_ARCHIVE, 0x0012_0034));
+        data
+    }
+
+    #[test]
+    fn test_long_name_roundtrip() {
+        let name = "A name that is longer than thirteen characters.txt";
+        let short_name = ShortName(*b"ANAMET~1TXT");
+        let mut data = directory(name, short_name, 0);
+        data.resize(data.len() + DIR_ENTRY_SIZE, 0);
+
+        let entries = parse_entries(&data);
+        assert_eq!(1, entries.len());
+        let entry = &entries[0];
+        assert_eq!(name, entry.name);
+        assert_eq!(short_name, entry.short_name);
+        assert_eq!(0x0012_0034, entry.first_cluster);
+        assert_eq!(0, entry.first_slot);
+        assert_eq!(4, entry.slot);
+        assert_eq!(vec![5], free_slots(&data).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_mismatched_checksum() {
+        let mut data = directory("long name.txt", ShortName(*b"LONGNA~1TXT"), 0);
+        // an old system renamed the file without knowing about long names
+        data[DIR_ENTRY_SIZE..DIR_ENTRY_SIZE + 11].copy_from_slice(b"RENAMED TXT");
+
+        let entries = parse_entries(&data);
+        assert_eq!(1, entries.len());
+        assert_eq!("RENAMED.TXT", entries[0].name);
+        assert_eq!(1, entries[0].first_slot);
+    }
+
+    #[test]
+    fn test_deleted_and_end() {
+        let mut data = directory("hello.txt", ShortName(*b"HELLO   TXT"), 0x18);
+        data.extend(directory("world.txt", ShortName(*b"WORLD   TXT"), 0x18));
+        data.extend(directory("after the end", ShortName(*b"AFTERT~1   "), 0));
+        data[0] = DELETED;
+        data[2 * DIR_ENTRY_SIZE] = DELETED;
+        data[3 * DIR_ENTRY_SIZE] = DELETED;
+        data[4 * DIR_ENTRY_SIZE] = END;
+
+        let entries = parse_entries(&data);
+        assert_eq!(1, entries.len());
+        // the short entry is left after its long name was deleted
+        assert_eq!("hello.txt", entries[0].name);
+        assert_eq!(1, entries[0].first_slot);
+        assert_eq!(vec![0, 2, 3, 4, 5], free_slots(&data).collect::<Vec<_>>());
+    }
+}
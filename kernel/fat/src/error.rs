@@ -0,0 +1,66 @@
+use kernel_vfs::{CreateError, OpenError, ReadError, UnlinkError, WriteError};
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum FatError {
+    #[error("the device does not contain a FAT file system")]
+    InvalidBootSector,
+    #[error("the device could not be read")]
+    DeviceRead,
+    #[error("the device could not be written")]
+    DeviceWrite,
+    #[error("cluster {0} is not part of the file system")]
+    InvalidCluster(u32),
+    #[error("no space left on the file system")]
+    NoSpace,
+    #[error("not found")]
+    NotFound,
+    #[error("not a directory")]
+    NotDirectory,
+}
+
+// The vfs errors have no variant for a failing device, so lookups report
+// the entry as missing, like the ext2 driver does.
+
+impl From<FatError> for OpenError {
+    fn from(value: FatError) -> Self {
+        match value {
+            FatError::NotDirectory => Self::NotDirectory,
+            _ => Self::NotFound,
+        }
+    }
+}
+
+impl From<FatError> for CreateError {
+    fn from(value: FatError) -> Self {
+        match value {
+            FatError::NotDirectory => Self::NotDirectory,
+            FatError::NoSpace | FatError::DeviceWrite => Self::NoSpace,
+            _ => Self::NotFound,
+        }
+    }
+}
+
+impl From<FatError> for UnlinkError {
+    fn from(value: FatError) -> Self {
+        match value {
+            FatError::NotDirectory => Self::NotDirectory,
+            _ => Self::NotFound,
+        }
+    }
+}
+
+impl From<FatError> for ReadError {
+    fn from(_: FatError) -> Self {
+        Self::ReadFailed
+    }
+}
+
+impl From<FatError> for WriteError {
+    fn from(value: FatError) -> Self {
+        match value {
+            FatError::NoSpace => Self::NoSpace,
+            _ => Self::WriteFailed,
+        }
+    }
+}
@@ -0,0 +1,650 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+use kernel_abi::{S_IFDIR, S_IFREG};
+use kernel_device::block::BlockDevice;
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use kernel_vfs::{
+    CloseError, CreateError, FsError, FsyncError, OpenError, ReadError, Stat, StatError,
+    UnlinkError, WriteError,
+};
+
+use crate::boot_sector::DIR_ENTRY_SIZE;
+use crate::dir::{
+    ATTR_ARCHIVE, ATTR_DIRECTORY, ATTR_READ_ONLY, DELETED, DirEntry, free_slots, long_entries,
+    parse_entries, set_first_cluster, set_size, short_entry,
+};
+use crate::name::{ShortName, is_valid_long_name, names_match};
+use crate::{BootSector, FatError};
+
+/// The root directory has no directory entry, so its id can't be the
+/// offset of one.
+const ROOT_NODE_ID: u64 = 0;
+
+/// A FAT12, FAT16 or FAT32 file system over a block device.
+///
+/// Nodes are identified by the byte offset of their short directory entry
+/// on the device, which never moves while the node exists.
+pub struct FatFs<T> {
+    pub(crate) device: T,
+    pub(crate) boot_sector: BootSector,
+    /// The number of free clusters, if the FSInfo sector of a FAT32 file
+    /// system knew it.
+    pub(crate) free_count: Option<u32>,
+    /// Where the search for a free cluster starts.
+    pub(crate) next_free: u32,
+    pub(crate) fs_info_valid: bool,
+    nodes: BTreeMap<FsNodeId, FatNode>,
+    handles: BTreeMap<FsHandle, FsNodeId>,
+    next_handle: u64,
+}
+
+/// A node that is open through at least one handle.
+struct FatNode {
+    /// Whether the node still has a directory entry. Unlinked nodes keep
+    /// their clusters until they are closed.
+    linked: bool,
+    directory: bool,
+    read_only: bool,
+    clusters: Vec<u32>,
+    size: u32,
+    open: usize,
+}
+
+/// The contents of a directory, read in one go.
+struct Directory {
+    /// The clusters of the directory, or `None` for the fixed root
+    /// directory of FAT12 and FAT16.
+    clusters: Option<Vec<u32>>,
+    data: Vec<u8>,
+}
+
+impl Directory {
+    fn entries(&self) -> Vec<DirEntry> {
+        parse_entries(&self.data)
+            .into_iter()
+            .filter(|entry| !entry.is_dot())
+            .collect()
+    }
+
+    fn find(&self, name: &str) -> Option<DirEntry> {
+        self.entries().into_iter().find(|entry| {
+            names_match(&entry.name, name) || names_match(&entry.short_name.decode(0), name)
+        })
+    }
+}
+
+/// The cluster at `index` in a chain. A chain that ends early is reported
+/// with the last cluster that it has.
+fn chain_cluster(clusters: &[u32], index: usize) -> Result<u32, FatError> {
+    clusters.get(index).copied().ok_or(FatError::InvalidCluster(
+        clusters.last().copied().unwrap_or(0),
+    ))
+}
+
+impl<T> FatFs<T>
+where
+    T: BlockDevice,
+{
+    /// # Errors
+    /// Returns [`FatError::InvalidBootSector`] if the device doesn't
+    /// contain a FAT file system, or an error if it can't be read.
+    pub fn try_new(device: T) -> Result<Self, FatError> {
+        let mut sector = [0; 512];
+        device
+            .read_at(0, &mut sector)
+            .map_err(|_| FatError::DeviceRead)?;
+        let boot_sector = BootSector::try_from(&sector)?;
+        if boot_sector.size() > device.sector_count() * device.sector_size() {
+            return Err(FatError::InvalidBootSector);
+        }
+
+        let mut fs = Self {
+            device,
+            boot_sector,
+            free_count: None,
+            next_free: 0,
+            fs_info_valid: false,
+            nodes: BTreeMap::new(),
+            handles: BTreeMap::new(),
+            next_handle: 0,
+        };
+        fs.read_fs_info()?;
+        Ok(fs)
+    }
+
+    #[must_use]
+    pub fn boot_sector(&self) -> &BootSector {
+        &self.boot_sector
+    }
+
+    #[must_use]
+    pub fn device(&self) -> &T {
+        &self.device
+    }
+
+    fn handle_node(&self, handle: FsHandle) -> Result<FsNodeId, FsError> {
+        self.handles
+            .get(&handle)
+            .copied()
+            .ok_or(FsError::InvalidHandle)
+    }
+
+    /// Reads the short entry of `id`, which must still be in use.
+    fn short_entry(&self, id: FsNodeId) -> Result<[u8; DIR_ENTRY_SIZE], FatError> {
+        let mut entry = [0; DIR_ENTRY_SIZE];
+        self.device
+            .read_at(id.get() as usize, &mut entry)
+            .map_err(|_| FatError::DeviceRead)?;
+        if matches!(entry[0], 0 | DELETED) {
+            return Err(FatError::NotFound);
+        }
+        Ok(entry)
+    }
+
+    /// Reads the directory `id`.
+    fn directory(&self, id: FsNodeId) -> Result<Directory, FatError> {
+        let clusters = if id.get() == ROOT_NODE_ID {
+            match self.boot_sector.root_cluster() {
+                Some(cluster) => Some(self.cluster_chain(cluster)?),
+                None => None,
+            }
+        } else if let Some(node) = self.nodes.get(&id) {
+            if !node.directory {
+                return Err(FatError::NotDirectory);
+            }
+            Some(node.clusters.clone())
+        } else {
+            let entry = parse_entries(&self.short_entry(id)?)
+                .pop()
+                .ok_or(FatError::NotFound)?;
+            if !entry.is_directory() {
+                return Err(FatError::NotDirectory);
+            }
+            Some(self.cluster_chain(entry.first_cluster)?)
+        };
+
+        let data = match &clusters {
+            Some(clusters) => {
+                let mut data = vec![0; clusters.len() * self.boot_sector.cluster_size()];
+                self.read_clusters(clusters, 0, &mut data)?;
+                data
+            }
+            None => {
+                let (offset, len) = self.boot_sector.root_dir_region();
+                let mut data = vec![0; len];
+                self.device
+                    .read_at(offset, &mut data)
+                    .map_err(|_| FatError::DeviceRead)?;
+                data
+            }
+        };
+        Ok(Directory { clusters, data })
+    }
+
+    /// Returns the byte offset of entry `slot` of `dir` on the device.
+    fn slot_offset(&self, dir: &Directory, slot: usize) -> usize {
+        let offset = slot * DIR_ENTRY_SIZE;
+        match &dir.clusters {
+            Some(clusters) => {
+                let cluster_size = self.boot_sector.cluster_size();
+                self.boot_sector
+                    .cluster_offset(clusters[offset / cluster_size])
+                    + offset % cluster_size
+            }
+            None => self.boot_sector.root_dir_region().0 + offset,
+        }
+    }
+
+    fn node_id(&self, dir: &Directory, entry: &DirEntry) -> FsNodeId {
+        FsNodeId::from(self.slot_offset(dir, entry.slot) as u64)
+    }
+
+    /// Reads from the data of a chain of clusters, starting at `offset`.
+    fn read_clusters(
+        &self,
+        clusters: &[u32],
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<(), FatError> {
+        let cluster_size = self.boot_sector.cluster_size();
+        let mut done = 0;
+        while done < buf.len() {
+            let position = offset + done;
+            let within = position % cluster_size;
+            let len = (cluster_size - within).min(buf.len() - done);
+            let cluster = chain_cluster(clusters, position / cluster_size)?;
+            self.device
+                .read_at(
+                    self.boot_sector.cluster_offset(cluster) + within,
+                    &mut buf[done..done + len],
+                )
+                .map_err(|_| FatError::DeviceRead)?;
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Writes to the data of a chain of clusters, starting at `offset`.
+    fn write_clusters(
+        &mut self,
+        clusters: &[u32],
+        offset: usize,
+        buf: &[u8],
+    ) -> Result<(), FatError> {
+        let cluster_size = self.boot_sector.cluster_size();
+        let mut done = 0;
+        while done < buf.len() {
+            let position = offset + done;
+            let within = position % cluster_size;
+            let len = (cluster_size - within).min(buf.len() - done);
+            let cluster = chain_cluster(clusters, position / cluster_size)?;
+            self.device
+                .write_at(
+                    self.boot_sector.cluster_offset(cluster) + within,
+                    &buf[done..done + len],
+                )
+                .map_err(|_| FatError::DeviceWrite)?;
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Finds `count` consecutive free entries in `dir`, growing it by a
+    /// cluster if there are none. Entries of unlinked nodes that are still
+    /// open are not reused, because the offset of an entry is the id of its
+    /// node.
+    fn free_slots(
+        &mut self,
+        dir_id: FsNodeId,
+        dir: &mut Directory,
+        count: usize,
+    ) -> Result<usize, FatError> {
+        loop {
+            let mut run_start = 0;
+            let mut run_len = 0;
+            for slot in free_slots(&dir.data) {
+                let id = FsNodeId::from(self.slot_offset(dir, slot) as u64);
+                if self.nodes.contains_key(&id) {
+                    run_len = 0;
+                    continue;
+                }
+                if run_len == 0 || slot != run_start + run_len {
+                    run_start = slot;
+                    run_len = 0;
+                }
+                run_len += 1;
+                if run_len == count {
+                    return Ok(run_start);
+                }
+            }
+
+            // the root directory of FAT12 and FAT16 has a fixed size
+            let clusters = dir.clusters.as_mut().ok_or(FatError::NoSpace)?;
+            self.allocate_clusters(clusters, 1, true)?;
+            if let Some(node) = self.nodes.get_mut(&dir_id) {
+                node.clusters.clone_from(clusters);
+            }
+            dir.data
+                .resize(dir.data.len() + self.boot_sector.cluster_size(), 0);
+        }
+    }
+
+    /// Writes `entries` into consecutive free entries of `dir` and returns
+    /// the id of the node, whose short entry is the last one.
+    fn insert_entries(
+        &mut self,
+        dir_id: FsNodeId,
+        dir: &mut Directory,
+        entries: &[[u8; DIR_ENTRY_SIZE]],
+    ) -> Result<FsNodeId, FatError> {
+        let start = self.free_slots(dir_id, dir, entries.len())?;
+        let mut offset = 0;
+        for (index, entry) in entries.iter().enumerate() {
+            offset = self.slot_offset(dir, start + index);
+            self.device
+                .write_at(offset, entry)
+                .map_err(|_| FatError::DeviceWrite)?;
+        }
+        Ok(FsNodeId::from(offset as u64))
+    }
+
+    /// Marks the entries of `entry`, including its long name, as deleted,
+    /// and frees its clusters unless its node is still open.
+    fn remove_entry(&mut self, dir: &Directory, entry: &DirEntry) -> Result<(), FatError> {
+        for slot in entry.first_slot..=entry.slot {
+            self.device
+                .write_at(self.slot_offset(dir, slot), &[DELETED])
+                .map_err(|_| FatError::DeviceWrite)?;
+        }
+        let id = self.node_id(dir, entry);
+        match self.nodes.get_mut(&id) {
+            Some(node) => node.linked = false,
+            None => {
+                let chain = self.cluster_chain(entry.first_cluster)?;
+                self.free_clusters(&chain)?;
+            }
+        }
+        self.write_fs_info()
+    }
+
+    /// Writes the first cluster and size of an open node back into its
+    /// short entry.
+    fn update_entry(&mut self, id: FsNodeId) -> Result<(), FatError> {
+        let node = &self.nodes[&id];
+        if !node.linked || id.get() == ROOT_NODE_ID {
+            return Ok(());
+        }
+        let first_cluster = node.clusters.first().copied().unwrap_or(0);
+        let size = node.size;
+        let mut entry = self.short_entry(id)?;
+        set_first_cluster(&mut entry, first_cluster);
+        set_size(&mut entry, size);
+        self.device
+            .write_at(id.get() as usize, &entry)
+            .map_err(|_| FatError::DeviceWrite)?;
+        Ok(())
+    }
+
+    fn create_entry(
+        &mut self,
+        dir_id: FsNodeId,
+        name: &str,
+        kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        if !is_valid_long_name(name) {
+            return Err(CreateError::NotSupported);
+        }
+        let mut dir = self.directory(dir_id)?;
+        let existing = parse_entries(&dir.data);
+        if dir.find(name).is_some() {
+            return Err(CreateError::AlreadyExists);
+        }
+
+        let is_taken =
+            |short_name: &ShortName| existing.iter().any(|entry| entry.short_name == *short_name);
+        let (short_name, case, long_name) = match ShortName::exact(name) {
+            Some((short_name, case)) if !is_taken(&short_name) => (short_name, case, false),
+            _ => (
+                ShortName::generate(name, is_taken).ok_or(CreateError::AlreadyExists)?,
+                0,
+                true,
+            ),
+        };
+
+        let mut clusters = Vec::new();
+        let attributes = match kind {
+            FsNodeKind::File => ATTR_ARCHIVE,
+            FsNodeKind::Directory => {
+                self.allocate_clusters(&mut clusters, 1, true)?;
+                // `..` points at cluster 0 if the parent is the root, even
+                // on FAT32
+                let parent = match &dir.clusters {
+                    Some(parent) if dir_id.get() != ROOT_NODE_ID => parent[0],
+                    _ => 0,
+                };
+                let mut dots = [0; 2 * DIR_ENTRY_SIZE];
+                dots[..DIR_ENTRY_SIZE].copy_from_slice(&short_entry(
+                    &ShortName::DOT,
+                    0,
+                    ATTR_DIRECTORY,
+                    clusters[0],
+                ));
+                dots[DIR_ENTRY_SIZE..].copy_from_slice(&short_entry(
+                    &ShortName::DOT_DOT,
+                    0,
+                    ATTR_DIRECTORY,
+                    parent,
+                ));
+                self.write_clusters(&clusters, 0, &dots)?;
+                ATTR_DIRECTORY
+            }
+            FsNodeKind::Symlink => return Err(CreateError::NotSupported),
+        };
+
+        let mut entries = if long_name {
+            long_entries(name, short_name.checksum())
+        } else {
+            Vec::new()
+        };
+        entries.push(short_entry(
+            &short_name,
+            case,
+            attributes,
+            clusters.first().copied().unwrap_or(0),
+        ));
+        let id = self
+            .insert_entries(dir_id, &mut dir, &entries)
+            .or_else(|e| {
+                self.free_clusters(&clusters)?;
+                Err(e)
+            })?;
+        self.write_fs_info()?;
+        Ok(id)
+    }
+
+    fn write_node(&mut self, id: FsNodeId, buf: &[u8], offset: usize) -> Result<(), FatError> {
+        let cluster_size = self.boot_sector.cluster_size();
+        let end = offset
+            .checked_add(buf.len())
+            .filter(|&end| u32::try_from(end).is_ok())
+            .ok_or(FatError::NoSpace)?;
+        let node = self.nodes.get_mut(&id).ok_or(FatError::NotFound)?;
+        let size = node.size as usize;
+        let mut clusters = mem::take(&mut node.clusters);
+
+        let missing = end.div_ceil(cluster_size).saturating_sub(clusters.len());
+        let allocated = self.allocate_clusters(&mut clusters, missing, false);
+        let written = allocated.and_then(|()| {
+            // whatever was on the device where the file now has a hole
+            // must read as zeroes
+            let zeroes = vec![0; cluster_size];
+            let mut hole = size;
+            while hole < offset {
+                let len = (offset - hole).min(cluster_size);
+                self.write_clusters(&clusters, hole, &zeroes[..len])?;
+                hole += len;
+            }
+            self.write_clusters(&clusters, offset, buf)
+        });
+
+        let node = self.nodes.get_mut(&id).ok_or(FatError::NotFound)?;
+        node.clusters = clusters;
+        written?;
+        node.size = node.size.max(end as u32);
+        self.update_entry(id)?;
+        self.write_fs_info()
+    }
+}
+
+impl<T> FileSystem for FatFs<T>
+where
+    T: BlockDevice + Send + Sync,
+{
+    fn root(&self) -> FsNodeId {
+        FsNodeId::from(ROOT_NODE_ID)
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let dir = self.directory(dir)?;
+        let entry = dir.find(name).ok_or(OpenError::NotFound)?;
+        let kind = if entry.is_directory() {
+            FsNodeKind::Directory
+        } else {
+            FsNodeKind::File
+        };
+        Ok((self.node_id(&dir, &entry), kind))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        Ok(self
+            .directory(dir)?
+            .entries()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        if let Some(open) = self.nodes.get_mut(&node) {
+            open.open += 1;
+        } else {
+            let open = if node.get() == ROOT_NODE_ID {
+                let clusters = match self.boot_sector.root_cluster() {
+                    Some(cluster) => self.cluster_chain(cluster)?,
+                    None => Vec::new(),
+                };
+                FatNode {
+                    linked: true,
+                    directory: true,
+                    read_only: false,
+                    clusters,
+                    size: 0,
+                    open: 1,
+                }
+            } else {
+                let entry = parse_entries(&self.short_entry(node)?)
+                    .pop()
+                    .ok_or(OpenError::NotFound)?;
+                let clusters = self.cluster_chain(entry.first_cluster)?;
+                let size = if entry.is_directory() { 0 } else { entry.size };
+                // a file can't hold more than its chain, or reading the rest
+                // would run off the end of it
+                if size as usize > clusters.len() * self.boot_sector.cluster_size() {
+                    return Err(FatError::InvalidCluster(entry.first_cluster).into());
+                }
+                FatNode {
+                    linked: true,
+                    directory: entry.is_directory(),
+                    read_only: entry.attributes & ATTR_READ_ONLY != 0,
+                    clusters,
+                    size,
+                    open: 1,
+                }
+            };
+            self.nodes.insert(node, open);
+        }
+
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.handles.insert(handle, node);
+        Ok(handle)
+    }
+
+    fn create(
+        &mut self,
+        dir: FsNodeId,
+        name: &str,
+        kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        self.create_entry(dir, name, kind)
+    }
+
+    fn unlink(&mut self, dir: FsNodeId, name: &str) -> Result<(), UnlinkError> {
+        let dir = self.directory(dir)?;
+        let entry = dir.find(name).ok_or(UnlinkError::NotFound)?;
+        if entry.is_directory() {
+            return Err(UnlinkError::IsDirectory);
+        }
+        Ok(self.remove_entry(&dir, &entry)?)
+    }
+
+    fn rmdir(&mut self, dir: FsNodeId, name: &str) -> Result<(), UnlinkError> {
+        let dir = self.directory(dir)?;
+        let entry = dir.find(name).ok_or(UnlinkError::NotFound)?;
+        if !entry.is_directory() {
+            return Err(UnlinkError::NotDirectory);
+        }
+        if !self
+            .directory(self.node_id(&dir, &entry))?
+            .entries()
+            .is_empty()
+        {
+            return Err(UnlinkError::NotEmpty);
+        }
+        Ok(self.remove_entry(&dir, &entry)?)
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        let id = self.handles.remove(&handle).ok_or(CloseError::NotOpen)?;
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return Ok(());
+        };
+        node.open -= 1;
+        if node.open > 0 {
+            return Ok(());
+        }
+        let node = self.nodes.remove(&id).unwrap();
+        if !node.linked {
+            // there's no way to report this, the clusters are lost until
+            // the file system is checked
+            let _ = self
+                .free_clusters(&node.clusters)
+                .and_then(|()| self.write_fs_info());
+        }
+        Ok(())
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let id = self.handle_node(handle)?;
+        let node = &self.nodes[&id];
+        if node.directory {
+            return Err(ReadError::NotReadable);
+        }
+        let size = node.size as usize;
+        if offset >= size {
+            return Err(ReadError::EndOfFile);
+        }
+        let len = buf.len().min(size - offset);
+        self.read_clusters(&node.clusters, offset, &mut buf[..len])?;
+        Ok(len)
+    }
+
+    fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
+        let id = self.handle_node(handle)?;
+        let node = &self.nodes[&id];
+        if node.directory || node.read_only {
+            return Err(WriteError::NotWritable);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.write_node(id, buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let id = self.handle_node(handle)?;
+        let node = &self.nodes[&id];
+        let allocated = node.clusters.len() * self.boot_sector.cluster_size();
+        let (size, mode) = if node.directory {
+            (allocated, S_IFDIR | 0o755)
+        } else if node.read_only {
+            (node.size as usize, S_IFREG | 0o444)
+        } else {
+            (node.size as usize, S_IFREG | 0o644)
+        };
+        *stat = Stat {
+            size,
+            ino: id.get(),
+            mode,
+            nlink: 1,
+            blocks: (allocated / 512) as u64,
+        };
+        Ok(())
+    }
+
+    fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
+        // writes go straight to the device
+        self.handle_node(handle)?;
+        Ok(())
+    }
+}
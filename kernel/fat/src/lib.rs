@@ -0,0 +1,15 @@
+//! A FAT12/16/32 file system with long file names, as written by
+//! `mkfs.vfat` and found on EFI system partitions.
+#![no_std]
+extern crate alloc;
+
+mod boot_sector;
+mod dir;
+mod error;
+mod fs;
+mod name;
+mod table;
+
+pub use boot_sector::*;
+pub use error::*;
+pub use fs::*;
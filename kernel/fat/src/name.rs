@@ -0,0 +1,241 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The characters that long names may contain besides letters, digits and
+/// anything outside of ASCII.
+const LONG_NAME_SPECIAL: &str = " !#$%&'()-@^_`{}~+,;=[].";
+/// The longest long name, in UTF-16 code units.
+const MAX_LONG_NAME_LEN: usize = 255;
+
+/// The letter case flags in the reserved byte of a short entry, which
+/// Windows and Linux use to store names like `readme.txt` without a long
+/// name.
+pub(crate) const LOWERCASE_BASE: u8 = 0x08;
+pub(crate) const LOWERCASE_EXTENSION: u8 = 0x10;
+
+/// A name in the 8.3 format as stored in a short entry: an upper case base
+/// name and extension, both padded with spaces.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct ShortName(pub(crate) [u8; 11]);
+
+impl ShortName {
+    pub(crate) const DOT: Self = Self(*b".          ");
+    pub(crate) const DOT_DOT: Self = Self(*b"..         ");
+
+    /// Returns the short name of `name` with the case flags that make it
+    /// read back as `name`, if `name` is a valid 8.3 name that doesn't
+    /// need a long name.
+    pub(crate) fn exact(name: &str) -> Option<(Self, u8)> {
+        let (base, extension) = match name.rsplit_once('.') {
+            Some((base, extension)) => (base, extension),
+            None => (name, ""),
+        };
+        if base.is_empty()
+            || base.len() > 8
+            || extension.len() > 3
+            || !base.bytes().chain(extension.bytes()).all(is_short_char)
+        {
+            return None;
+        }
+        let base_case = case_flag(base, LOWERCASE_BASE)?;
+        let extension_case = case_flag(extension, LOWERCASE_EXTENSION)?;
+
+        let mut short = [b' '; 11];
+        short[..base.len()].copy_from_slice(base.as_bytes());
+        short[8..8 + extension.len()].copy_from_slice(extension.as_bytes());
+        short.make_ascii_uppercase();
+        Some((Self(short), base_case | extension_case))
+    }
+
+    /// Generates a short name for `name` with a numeric tail like `~1`,
+    /// using the lowest number that `is_taken` allows.
+    pub(crate) fn generate(name: &str, is_taken: impl Fn(&Self) -> bool) -> Option<Self> {
+        let name = name.trim_start_matches(['.', ' ']);
+        let (base, extension) = match name.rsplit_once('.') {
+            Some((base, extension)) if !base.is_empty() => (base, extension),
+            _ => (name, ""),
+        };
+        let basis = |part: &str, len: usize| {
+            part.chars()
+                .filter(|&c| c != ' ' && c != '.')
+                .map(|c| {
+                    let c = c.to_ascii_uppercase();
+                    if c.is_ascii() && is_short_char(c as u8) {
+                        c as u8
+                    } else {
+                        b'_'
+                    }
+                })
+                .take(len)
+                .collect::<Vec<_>>()
+        };
+        let base = basis(base, 8);
+        let extension = basis(extension, 3);
+
+        (1..1_000_000u32).find_map(|number| {
+            let mut tail = [0; 8];
+            let tail = {
+                let mut n = number;
+                let mut len = 0;
+                while n > 0 {
+                    tail[7 - len] = b'0' + (n % 10) as u8;
+                    n /= 10;
+                    len += 1;
+                }
+                tail[7 - len] = b'~';
+                &tail[7 - len..]
+            };
+            let base_len = base.len().min(8 - tail.len());
+            let mut short = [b' '; 11];
+            short[..base_len].copy_from_slice(&base[..base_len]);
+            short[base_len..base_len + tail.len()].copy_from_slice(tail);
+            short[8..8 + extension.len()].copy_from_slice(&extension);
+            let short = Self(short);
+            (!is_taken(&short)).then_some(short)
+        })
+    }
+
+    /// Decodes the name for display, applying the case flags.
+    pub(crate) fn decode(&self, case: u8) -> String {
+        let mut bytes = self.0;
+        // 0xE5 marks deleted entries, so names that start with it store 0x05
+        if bytes[0] == 0x05 {
+            bytes[0] = 0xE5;
+        }
+        if case & LOWERCASE_BASE != 0 {
+            bytes[..8].make_ascii_lowercase();
+        }
+        if case & LOWERCASE_EXTENSION != 0 {
+            bytes[8..].make_ascii_lowercase();
+        }
+        let trim = |part: &[u8]| {
+            let len = part
+                .iter()
+                .rposition(|&b| b != b' ')
+                .map_or(0, |last| last + 1);
+            part[..len]
+                .iter()
+                .map(|&b| char::from(b))
+                .collect::<String>()
+        };
+        let base = trim(&bytes[..8]);
+        let extension = trim(&bytes[8..]);
+        if extension.is_empty() {
+            base
+        } else {
+            base + "." + &extension
+        }
+    }
+
+    /// The checksum that ties the entries of a long name to their short
+    /// entry.
+    pub(crate) fn checksum(&self) -> u8 {
+        self.0
+            .iter()
+            .fold(0u8, |sum, &b| sum.rotate_right(1).wrapping_add(b))
+    }
+}
+
+/// Returns whether `byte` may appear in a short name, ignoring case.
+fn is_short_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'()-@^_`{}~".contains(&byte)
+}
+
+/// Returns the case flag for a part of an 8.3 name, or `None` if the part
+/// mixes upper and lower case and therefore needs a long name.
+fn case_flag(part: &str, lowercase: u8) -> Option<u8> {
+    let has_lower = part.bytes().any(|b| b.is_ascii_lowercase());
+    let has_upper = part.bytes().any(|b| b.is_ascii_uppercase());
+    match (has_lower, has_upper) {
+        (true, true) => None,
+        (true, false) => Some(lowercase),
+        _ => Some(0),
+    }
+}
+
+/// Returns whether `name` can be stored as a long name.
+pub(crate) fn is_valid_long_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name.encode_utf16().count() <= MAX_LONG_NAME_LEN
+        && !name.ends_with(['.', ' '])
+        && name
+            .chars()
+            .all(|c| !c.is_ascii() || c.is_ascii_alphanumeric() || LONG_NAME_SPECIAL.contains(c))
+}
+
+/// Compares two names the way FAT does, ignoring the case of ASCII letters.
+pub(crate) fn names_match(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact() {
+        assert_eq!(
+            Some((ShortName(*b"README  TXT"), 0)),
+            ShortName::exact("README.TXT")
+        );
+        assert_eq!(
+            Some((
+                ShortName(*b"README  TXT"),
+                LOWERCASE_BASE | LOWERCASE_EXTENSION
+            )),
+            ShortName::exact("readme.txt")
+        );
+        assert_eq!(
+            Some((ShortName(*b"KERNEL     "), LOWERCASE_BASE)),
+            ShortName::exact("kernel")
+        );
+        assert_eq!(None, ShortName::exact("ReadMe.txt"));
+        assert_eq!(None, ShortName::exact("long_name.txt"));
+        assert_eq!(None, ShortName::exact("a.tar.gz"));
+        assert_eq!(None, ShortName::exact(".hidden"));
+    }
+
+    #[test]
+    fn test_generate() {
+        let short = ShortName::generate("A long file name.txt", |_| false).unwrap();
+        assert_eq!(*b"ALONGF~1TXT", short.0);
+
+        let taken = [ShortName(*b"ALONGF~1TXT"), ShortName(*b"ALONGF~2TXT")];
+        let short = ShortName::generate("A long file name.txt", |s| taken.contains(s)).unwrap();
+        assert_eq!(*b"ALONGF~3TXT", short.0);
+
+        let short = ShortName::generate(".bashrc", |_| false).unwrap();
+        assert_eq!(*b"BASHRC~1   ", short.0);
+
+        let short = ShortName::generate("a+b.tar.gz", |_| false).unwrap();
+        assert_eq!(*b"A_BTAR~1GZ ", short.0);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!("README.TXT", ShortName(*b"README  TXT").decode(0));
+        assert_eq!(
+            "readme.TXT",
+            ShortName(*b"README  TXT").decode(LOWERCASE_BASE)
+        );
+        assert_eq!("EFI", ShortName(*b"EFI        ").decode(0));
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(0x02, ShortName(*b"ALONGF~1TXT").checksum());
+        assert_eq!(0x73, ShortName(*b"README  TXT").checksum());
+    }
+
+    #[test]
+    fn test_valid_long_name() {
+        assert!(is_valid_long_name("A long file name.txt"));
+        assert!(is_valid_long_name("ünïcödé"));
+        assert!(!is_valid_long_name("a/b"));
+        assert!(!is_valid_long_name("what?"));
+        assert!(!is_valid_long_name("trailing."));
+        assert!(!is_valid_long_name(""));
+    }
+}
@@ -0,0 +1,307 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use kernel_device::block::BlockDevice;
+
+use crate::boot_sector::FIRST_CLUSTER;
+use crate::{FatError, FatFs, FatType};
+
+const FS_INFO_LEAD_SIGNATURE: u32 = 0x4161_5252;
+const FS_INFO_STRUCT_SIGNATURE: u32 = 0x6141_7272;
+/// The offset of the free cluster count in the FSInfo sector, which is
+/// followed by the hint for the next free cluster.
+const FS_INFO_FREE_COUNT: usize = 488;
+const FS_INFO_UNKNOWN: u32 = 0xFFFF_FFFF;
+
+/// The value of a FAT entry, which tells what follows the cluster of the
+/// same number.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum FatEntry {
+    Free,
+    Next(u32),
+    Bad,
+    End,
+}
+
+impl FatEntry {
+    fn decode(fat_type: FatType, raw: u32) -> Self {
+        let (bad, end) = match fat_type {
+            FatType::Fat12 => (0xFF7, 0xFF8),
+            FatType::Fat16 => (0xFFF7, 0xFFF8),
+            FatType::Fat32 => (0x0FFF_FFF7, 0x0FFF_FFF8),
+        };
+        match raw {
+            0 => Self::Free,
+            raw if raw == bad => Self::Bad,
+            raw if raw >= end => Self::End,
+            raw => Self::Next(raw),
+        }
+    }
+
+    fn encode(self, fat_type: FatType) -> u32 {
+        match (self, fat_type) {
+            (Self::Free, _) => 0,
+            (Self::Next(next), _) => next,
+            (Self::Bad, FatType::Fat12) => 0xFF7,
+            (Self::Bad, FatType::Fat16) => 0xFFF7,
+            (Self::Bad, FatType::Fat32) => 0x0FFF_FFF7,
+            (Self::End, FatType::Fat12) => 0xFFF,
+            (Self::End, FatType::Fat16) => 0xFFFF,
+            (Self::End, FatType::Fat32) => 0x0FFF_FFFF,
+        }
+    }
+}
+
+/// Where the entry of a cluster lives within a copy of the FAT. A FAT12
+/// entry takes up the lower or upper 12 bits of the two bytes.
+fn entry_position(fat_type: FatType, cluster: u32) -> (usize, usize) {
+    let cluster = cluster as usize;
+    match fat_type {
+        FatType::Fat12 => (cluster + cluster / 2, 2),
+        FatType::Fat16 => (cluster * 2, 2),
+        FatType::Fat32 => (cluster * 4, 4),
+    }
+}
+
+/// Reads the raw entry of `cluster` out of `bytes`, which start at the
+/// entry's position.
+fn read_raw(fat_type: FatType, cluster: u32, bytes: &[u8]) -> u32 {
+    match fat_type {
+        FatType::Fat12 => {
+            let value = u16::from_le_bytes([bytes[0], bytes[1]]);
+            u32::from(if cluster.is_multiple_of(2) {
+                value & 0xFFF
+            } else {
+                value >> 4
+            })
+        }
+        FatType::Fat16 => u32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+        FatType::Fat32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) & 0x0FFF_FFFF,
+    }
+}
+
+/// Writes the raw entry of `cluster` into `bytes`, keeping the bits that
+/// belong to the neighbouring FAT12 entry and the upper four reserved bits
+/// of a FAT32 entry.
+fn write_raw(fat_type: FatType, cluster: u32, bytes: &mut [u8], raw: u32) {
+    match fat_type {
+        FatType::Fat12 => {
+            let old = u16::from_le_bytes([bytes[0], bytes[1]]);
+            let raw = raw as u16 & 0xFFF;
+            let value = if cluster.is_multiple_of(2) {
+                (old & 0xF000) | raw
+            } else {
+                (old & 0x000F) | (raw << 4)
+            };
+            bytes[..2].copy_from_slice(&value.to_le_bytes());
+        }
+        FatType::Fat16 => bytes[..2].copy_from_slice(&(raw as u16).to_le_bytes()),
+        FatType::Fat32 => {
+            let old = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+            let value = (old & 0xF000_0000) | (raw & 0x0FFF_FFFF);
+            bytes[..4].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+impl<T> FatFs<T>
+where
+    T: BlockDevice,
+{
+    pub(crate) fn fat_entry(&self, cluster: u32) -> Result<FatEntry, FatError> {
+        if !self.boot_sector.is_valid_cluster(cluster) {
+            return Err(FatError::InvalidCluster(cluster));
+        }
+        let fat_type = self.boot_sector.fat_type();
+        let (offset, len) = entry_position(fat_type, cluster);
+        let mut bytes = [0; 4];
+        self.device
+            .read_at(self.boot_sector.fat_offset() + offset, &mut bytes[..len])
+            .map_err(|_| FatError::DeviceRead)?;
+        Ok(FatEntry::decode(
+            fat_type,
+            read_raw(fat_type, cluster, &bytes),
+        ))
+    }
+
+    /// Sets the entry of `cluster` in every copy of the FAT.
+    pub(crate) fn set_fat_entry(&mut self, cluster: u32, entry: FatEntry) -> Result<(), FatError> {
+        if !self.boot_sector.is_valid_cluster(cluster) {
+            return Err(FatError::InvalidCluster(cluster));
+        }
+        let fat_type = self.boot_sector.fat_type();
+        let (offset, len) = entry_position(fat_type, cluster);
+        for copy in 0..self.boot_sector.fat_count() {
+            let offset =
+                self.boot_sector.fat_offset() + copy * self.boot_sector.fat_size() + offset;
+            let mut bytes = [0; 4];
+            self.device
+                .read_at(offset, &mut bytes[..len])
+                .map_err(|_| FatError::DeviceRead)?;
+            write_raw(fat_type, cluster, &mut bytes, entry.encode(fat_type));
+            self.device
+                .write_at(offset, &bytes[..len])
+                .map_err(|_| FatError::DeviceWrite)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the clusters of the chain that starts at `first`, in order.
+    /// A `first` of 0 is the empty chain of an empty file.
+    pub(crate) fn cluster_chain(&self, first: u32) -> Result<Vec<u32>, FatError> {
+        let mut chain = Vec::new();
+        if first == 0 {
+            return Ok(chain);
+        }
+        let mut cluster = first;
+        loop {
+            chain.push(cluster);
+            // a chain can't be longer than the file system, so anything
+            // longer is a loop
+            if chain.len() > self.boot_sector.cluster_count() as usize {
+                return Err(FatError::InvalidCluster(cluster));
+            }
+            match self.fat_entry(cluster)? {
+                FatEntry::Next(next) => cluster = next,
+                FatEntry::End => return Ok(chain),
+                FatEntry::Free | FatEntry::Bad => return Err(FatError::InvalidCluster(cluster)),
+            }
+        }
+    }
+
+    /// Allocates `count` clusters and links them into a chain, which is
+    /// appended to `chain` on disk and in memory. The new clusters are
+    /// zeroed if `zero` is set.
+    pub(crate) fn allocate_clusters(
+        &mut self,
+        chain: &mut Vec<u32>,
+        count: usize,
+        zero: bool,
+    ) -> Result<(), FatError> {
+        let new = self.find_free_clusters(count)?;
+        for (index, &cluster) in new.iter().enumerate() {
+            let entry = new
+                .get(index + 1)
+                .map_or(FatEntry::End, |&next| FatEntry::Next(next));
+            self.set_fat_entry(cluster, entry)?;
+        }
+        if let (Some(&last), Some(&first)) = (chain.last(), new.first()) {
+            self.set_fat_entry(last, FatEntry::Next(first))?;
+        }
+        if zero {
+            let zeroes = vec![0; self.boot_sector.cluster_size()];
+            for &cluster in &new {
+                self.device
+                    .write_at(self.boot_sector.cluster_offset(cluster), &zeroes)
+                    .map_err(|_| FatError::DeviceWrite)?;
+            }
+        }
+
+        if let Some(&last) = new.last() {
+            self.next_free = last + 1;
+        }
+        self.free_count = self.free_count.map(|free| free - new.len() as u32);
+        chain.extend(new);
+        Ok(())
+    }
+
+    /// Marks all clusters of `chain` as free.
+    pub(crate) fn free_clusters(&mut self, chain: &[u32]) -> Result<(), FatError> {
+        for &cluster in chain {
+            self.set_fat_entry(cluster, FatEntry::Free)?;
+        }
+        self.free_count = self.free_count.map(|free| free + chain.len() as u32);
+        Ok(())
+    }
+
+    /// Finds `count` free clusters, starting at the cluster after the one
+    /// that was allocated last, so that files written in one go end up
+    /// contiguous.
+    fn find_free_clusters(&self, count: usize) -> Result<Vec<u32>, FatError> {
+        let mut found = Vec::with_capacity(count);
+        if count == 0 {
+            return Ok(found);
+        }
+        let fat_type = self.boot_sector.fat_type();
+        let end = self.boot_sector.cluster_count() + FIRST_CLUSTER;
+        let start = if self.boot_sector.is_valid_cluster(self.next_free) {
+            self.next_free
+        } else {
+            FIRST_CLUSTER
+        };
+
+        // Reading the FAT in chunks of three sectors keeps FAT12 entries,
+        // which are one and a half bytes, from straddling two chunks.
+        let chunk_size = 3 * self.boot_sector.bytes_per_sector();
+        let entries_per_chunk = (chunk_size * 8 / fat_type.bits() as usize) as u32;
+        let mut chunk = vec![0; chunk_size + 1];
+        let mut chunk_index = None;
+        for cluster in (start..end).chain(FIRST_CLUSTER..start) {
+            let index = cluster / entries_per_chunk;
+            if chunk_index != Some(index) {
+                let offset = self.boot_sector.fat_offset() + index as usize * chunk_size;
+                let len = chunk_size
+                    .min(self.boot_sector.fat_offset() + self.boot_sector.fat_size() - offset);
+                chunk.fill(0);
+                self.device
+                    .read_at(offset, &mut chunk[..len])
+                    .map_err(|_| FatError::DeviceRead)?;
+                chunk_index = Some(index);
+            }
+            let (position, _) = entry_position(fat_type, cluster - index * entries_per_chunk);
+            let raw = read_raw(fat_type, cluster, &chunk[position..]);
+            if FatEntry::decode(fat_type, raw) == FatEntry::Free {
+                found.push(cluster);
+                if found.len() == count {
+                    return Ok(found);
+                }
+            }
+        }
+        Err(FatError::NoSpace)
+    }
+
+    /// Reads the free cluster count and the next free cluster hint from the
+    /// FSInfo sector of a FAT32 file system.
+    pub(crate) fn read_fs_info(&mut self) -> Result<(), FatError> {
+        let Some(offset) = self.boot_sector.fs_info_offset() else {
+            return Ok(());
+        };
+        let mut sector = [0; 512];
+        self.device
+            .read_at(offset, &mut sector)
+            .map_err(|_| FatError::DeviceRead)?;
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(sector[offset..offset + 4].try_into().unwrap());
+        if u32_at(0) != FS_INFO_LEAD_SIGNATURE || u32_at(484) != FS_INFO_STRUCT_SIGNATURE {
+            return Ok(());
+        }
+        let free_count = u32_at(FS_INFO_FREE_COUNT);
+        if free_count <= self.boot_sector.cluster_count() {
+            self.free_count = Some(free_count);
+        }
+        let next_free = u32_at(FS_INFO_FREE_COUNT + 4);
+        if next_free != FS_INFO_UNKNOWN {
+            self.next_free = next_free;
+        }
+        self.fs_info_valid = true;
+        Ok(())
+    }
+
+    /// Writes the free cluster count and the next free cluster hint back to
+    /// the FSInfo sector, if the file system has a valid one.
+    pub(crate) fn write_fs_info(&mut self) -> Result<(), FatError> {
+        let Some(offset) = self.boot_sector.fs_info_offset() else {
+            return Ok(());
+        };
+        if !self.fs_info_valid {
+            return Ok(());
+        }
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&self.free_count.unwrap_or(FS_INFO_UNKNOWN).to_le_bytes());
+        bytes[4..].copy_from_slice(&self.next_free.to_le_bytes());
+        self.device
+            .write_at(offset + FS_INFO_FREE_COUNT, &bytes)
+            .map_err(|_| FatError::DeviceWrite)?;
+        Ok(())
+    }
+}
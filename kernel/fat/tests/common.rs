@@ -0,0 +1,93 @@
+#![allow(dead_code)] // not every test binary uses every helper
+
+use std::fs;
+use std::path::Path;
+
+use kernel_device::block::MemoryBlockDevice;
+use kernel_fat::FatFs;
+use kernel_vfs::ReadError;
+use kernel_vfs::fs::{FileSystem, FsNodeId, FsNodeKind};
+
+pub type TestFs = FatFs<MemoryBlockDevice<Vec<u8>>>;
+
+/// The images that `BUILD.bazel` builds with mkfs.vfat and mcopy, which all
+/// hold the same tree.
+pub const FAT12: &str = "kernel/fat/tests/filesystems/fat12.img";
+pub const FAT16: &str = "kernel/fat/tests/filesystems/fat16.img";
+pub const FAT32: &str = "kernel/fat/tests/filesystems/fat32.img";
+
+#[macro_export]
+macro_rules! generate_tests {
+    ($test_fn:ident : $($image:expr => $name:ident),*,) => {
+        const _: &dyn Fn(&str) = &$test_fn;
+        $(
+            #[test]
+            fn $name() {
+                $test_fn($image);
+            }
+        )*
+    };
+}
+
+pub fn load_copy_of_image(test_image: impl AsRef<Path>) -> Vec<u8> {
+    fs::read(test_image).unwrap()
+}
+
+pub fn open_fs(test_image: &str) -> TestFs {
+    mount(load_copy_of_image(test_image))
+}
+
+pub fn mount(image: Vec<u8>) -> TestFs {
+    let device = MemoryBlockDevice::try_new(512, image).unwrap();
+    FatFs::try_new(device).unwrap()
+}
+
+/// Mounts the image of `fs` again, to check that changes made it to the
+/// device.
+pub fn remount(fs: &TestFs) -> TestFs {
+    mount(fs.device().data().clone())
+}
+
+/// Walks `path`, relative to the root and separated by `/`.
+pub fn lookup(fs: &mut TestFs, path: &str) -> (FsNodeId, FsNodeKind) {
+    path.split('/')
+        .fold((fs.root(), FsNodeKind::Directory), |(dir, _), name| {
+            fs.lookup(dir, name)
+                .unwrap_or_else(|e| panic!("{name} in {path}: {e}"))
+        })
+}
+
+pub fn read_file(fs: &mut TestFs, path: &str) -> Vec<u8> {
+    let (node, _) = lookup(fs, path);
+    let handle = fs.open(node).unwrap();
+    let mut data = Vec::new();
+    let mut buf = [0; 1000];
+    loop {
+        match fs.read(handle, &mut buf, data.len()) {
+            Ok(read) => data.extend_from_slice(&buf[..read]),
+            Err(ReadError::EndOfFile) => break,
+            Err(e) => panic!("{path}: {e}"),
+        }
+    }
+    fs.close(handle).unwrap();
+    data
+}
+
+pub fn write_file(fs: &mut TestFs, dir: FsNodeId, name: &str, data: &[u8]) -> FsNodeId {
+    let node = fs.create(dir, name, FsNodeKind::File).unwrap();
+    let handle = fs.open(node).unwrap();
+    assert_eq!(Ok(data.len()), fs.write(handle, data, 0));
+    fs.close(handle).unwrap();
+    node
+}
+
+pub fn numbers() -> Vec<u8> {
+    (0..10000)
+        .flat_map(|i| format!("{i}\n").into_bytes())
+        .collect()
+}
+
+pub fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names
+}
@@ -0,0 +1,243 @@
+use kernel_fat::FatType;
+use kernel_vfs::fs::{FileSystem, FsNodeKind};
+use kernel_vfs::{OpenError, ReadError, Stat};
+
+use crate::common::{FAT12, FAT16, FAT32, lookup, mount, numbers, open_fs, read_file, sorted};
+
+mod common;
+
+#[test]
+fn test_boot_sector() {
+    for (image, fat_type) in [
+        (FAT12, FatType::Fat12),
+        (FAT16, FatType::Fat16),
+        (FAT32, FatType::Fat32),
+    ] {
+        let fs = open_fs(image);
+        let boot_sector = fs.boot_sector();
+        assert_eq!(fat_type, boot_sector.fat_type(), "{image}");
+        assert_eq!(0x1234_5678, boot_sector.volume_id(), "{image}");
+        assert_eq!(b"MUFFIN", boot_sector.volume_label(), "{image}");
+    }
+}
+
+generate_tests!(
+    test_list_root:
+    FAT12 => test_list_root_fat12,
+    FAT16 => test_list_root_fat16,
+    FAT32 => test_list_root_fat32,
+);
+
+fn test_list_root(image: &str) {
+    let mut fs = open_fs(image);
+    let names = fs.read_dir(fs.root()).unwrap();
+    assert_eq!(
+        vec![
+            "A long file name.txt",
+            "README.TXT",
+            "docs",
+            "empty",
+            "hello.txt",
+            "many",
+            "numbers.txt"
+        ],
+        sorted(names)
+    );
+
+    let (empty, kind) = lookup(&mut fs, "empty");
+    assert_eq!(FsNodeKind::Directory, kind);
+    assert!(fs.read_dir(empty).unwrap().is_empty());
+}
+
+generate_tests!(
+    test_read_files:
+    FAT12 => test_read_files_fat12,
+    FAT16 => test_read_files_fat16,
+    FAT32 => test_read_files_fat32,
+);
+
+fn test_read_files(image: &str) {
+    let mut fs = open_fs(image);
+    assert_eq!(
+        b"Hello, World!\n",
+        read_file(&mut fs, "hello.txt").as_slice()
+    );
+    assert_eq!(
+        b"This file has a long name.\n",
+        read_file(&mut fs, "A long file name.txt").as_slice()
+    );
+    assert_eq!(
+        b"An 8.3 name in upper case.\n",
+        read_file(&mut fs, "README.TXT").as_slice()
+    );
+    assert_eq!(
+        b"deep\n",
+        read_file(&mut fs, "docs/nested/deep.txt").as_slice()
+    );
+    assert_eq!(numbers(), read_file(&mut fs, "numbers.txt"));
+}
+
+generate_tests!(
+    test_lookup_ignores_case:
+    FAT12 => test_lookup_ignores_case_fat12,
+    FAT16 => test_lookup_ignores_case_fat16,
+    FAT32 => test_lookup_ignores_case_fat32,
+);
+
+fn test_lookup_ignores_case(image: &str) {
+    let mut fs = open_fs(image);
+    let root = fs.root();
+    let hello = fs.lookup(root, "hello.txt").unwrap();
+    assert_eq!(Ok(hello), fs.lookup(root, "HELLO.TXT"));
+    let readme = fs.lookup(root, "README.TXT").unwrap();
+    assert_eq!(Ok(readme), fs.lookup(root, "readme.txt"));
+    assert_eq!(Err(OpenError::NotFound), fs.lookup(root, "hello"));
+}
+
+generate_tests!(
+    test_large_directory:
+    FAT12 => test_large_directory_fat12,
+    FAT16 => test_large_directory_fat16,
+    FAT32 => test_large_directory_fat32,
+);
+
+fn test_large_directory(image: &str) {
+    let mut fs = open_fs(image);
+    let (many, _) = lookup(&mut fs, "many");
+    let names = sorted(fs.read_dir(many).unwrap());
+    let expected = (0..100)
+        .map(|i| format!("file_{i:03}.txt"))
+        .collect::<Vec<_>>();
+    assert_eq!(expected, names);
+    for i in [0, 42, 99] {
+        assert_eq!(
+            format!("file_{i}\n").as_bytes(),
+            read_file(&mut fs, &format!("many/file_{i:03}.txt"))
+        );
+    }
+}
+
+generate_tests!(
+    test_read_at_offsets:
+    FAT12 => test_read_at_offsets_fat12,
+    FAT16 => test_read_at_offsets_fat16,
+    FAT32 => test_read_at_offsets_fat32,
+);
+
+fn test_read_at_offsets(image: &str) {
+    let mut fs = open_fs(image);
+    let numbers = numbers();
+    let (node, _) = lookup(&mut fs, "numbers.txt");
+    let handle = fs.open(node).unwrap();
+
+    // across cluster boundaries for every cluster size
+    let mut buf = [0; 4099];
+    for offset in [0, 511, 2047, 4000, 20000] {
+        assert_eq!(Ok(buf.len()), fs.read(handle, &mut buf, offset));
+        assert_eq!(&numbers[offset..offset + buf.len()], buf.as_slice());
+    }
+
+    let last = numbers.len() - 10;
+    assert_eq!(Ok(10), fs.read(handle, &mut buf, last));
+    assert_eq!(&numbers[last..], &buf[..10]);
+    assert_eq!(
+        Err(ReadError::EndOfFile),
+        fs.read(handle, &mut buf, numbers.len())
+    );
+    fs.close(handle).unwrap();
+}
+
+generate_tests!(
+    test_errors:
+    FAT12 => test_errors_fat12,
+    FAT16 => test_errors_fat16,
+    FAT32 => test_errors_fat32,
+);
+
+fn test_errors(image: &str) {
+    let mut fs = open_fs(image);
+    let (hello, _) = lookup(&mut fs, "hello.txt");
+    assert_eq!(Err(OpenError::NotDirectory), fs.lookup(hello, "x"));
+    assert_eq!(Err(OpenError::NotDirectory), fs.read_dir(hello));
+
+    let (docs, _) = lookup(&mut fs, "docs");
+    let handle = fs.open(docs).unwrap();
+    assert_eq!(
+        Err(ReadError::NotReadable),
+        fs.read(handle, &mut [0; 16], 0)
+    );
+    fs.close(handle).unwrap();
+}
+
+/// A FAT16 image with one file in the root directory, whose entry claims
+/// `size` bytes while its chain is the single cluster 2.
+fn one_cluster_file(size: u32) -> Vec<u8> {
+    const SECTORS: u16 = 5000;
+    let mut image = vec![0; usize::from(SECTORS) * 512];
+    // 512 byte sectors, one per cluster, one reserved sector, one FAT of 20
+    // sectors and 16 root directory entries
+    image[11..13].copy_from_slice(&512_u16.to_le_bytes());
+    image[13] = 1;
+    image[14..16].copy_from_slice(&1_u16.to_le_bytes());
+    image[16] = 1;
+    image[17..19].copy_from_slice(&16_u16.to_le_bytes());
+    image[19..21].copy_from_slice(&SECTORS.to_le_bytes());
+    image[21] = 0xf8;
+    image[22..24].copy_from_slice(&20_u16.to_le_bytes());
+    image[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+    let fat = 512;
+    image[fat..fat + 6].copy_from_slice(&[0xf8, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+    let root = 21 * 512;
+    image[root..root + 11].copy_from_slice(b"BIG     TXT");
+    image[root + 11] = 0x20;
+    image[root + 26..root + 28].copy_from_slice(&2_u16.to_le_bytes());
+    image[root + 28..root + 32].copy_from_slice(&size.to_le_bytes());
+    image
+}
+
+#[test]
+fn test_size_beyond_chain() {
+    let mut fs = mount(one_cluster_file(512));
+    let (node, _) = fs.lookup(fs.root(), "BIG.TXT").unwrap();
+    let handle = fs.open(node).unwrap();
+    assert_eq!(Ok(512), fs.read(handle, &mut [0; 4096], 0));
+    fs.close(handle).unwrap();
+
+    // a crafted entry that claims more than its chain holds
+    let mut fs = mount(one_cluster_file(100_000));
+    let (node, _) = fs.lookup(fs.root(), "BIG.TXT").unwrap();
+    assert_eq!(Err(OpenError::NotFound), fs.open(node));
+}
+
+generate_tests!(
+    test_stat:
+    FAT12 => test_stat_fat12,
+    FAT16 => test_stat_fat16,
+    FAT32 => test_stat_fat32,
+);
+
+fn test_stat(image: &str) {
+    let mut fs = open_fs(image);
+    let (node, _) = lookup(&mut fs, "numbers.txt");
+    let handle = fs.open(node).unwrap();
+    let mut stat = Stat::default();
+    fs.stat(handle, &mut stat).unwrap();
+    fs.close(handle).unwrap();
+
+    let cluster_size = fs.boot_sector().cluster_size();
+    assert_eq!(numbers().len(), stat.size);
+    assert_eq!(node.get(), stat.ino);
+    assert_eq!(0o100_644, stat.mode);
+    assert_eq!(
+        (stat.size.next_multiple_of(cluster_size) / 512) as u64,
+        stat.blocks
+    );
+
+    let (docs, _) = lookup(&mut fs, "docs");
+    let handle = fs.open(docs).unwrap();
+    fs.stat(handle, &mut stat).unwrap();
+    assert_eq!(0o040_755, stat.mode);
+    fs.close(handle).unwrap();
+}
@@ -0,0 +1,255 @@
+use kernel_vfs::fs::{FileSystem, FsNodeKind};
+use kernel_vfs::{CreateError, OpenError, ReadError, UnlinkError, WriteError};
+
+use crate::common::{
+    FAT12, FAT16, FAT32, lookup, numbers, open_fs, read_file, remount, sorted, write_file,
+};
+
+mod common;
+
+generate_tests!(
+    test_create_and_write:
+    FAT12 => test_create_and_write_fat12,
+    FAT16 => test_create_and_write_fat16,
+    FAT32 => test_create_and_write_fat32,
+);
+
+fn test_create_and_write(image: &str) {
+    let mut fs = open_fs(image);
+    let root = fs.root();
+    let data = numbers();
+    write_file(&mut fs, root, "new.txt", b"short and 8.3\n");
+    write_file(&mut fs, root, "A new file with a long name.bin", &data);
+    assert_eq!(
+        Err(CreateError::AlreadyExists),
+        fs.create(root, "NEW.TXT", FsNodeKind::File)
+    );
+
+    let mut fs = remount(&fs);
+    let names = fs.read_dir(fs.root()).unwrap();
+    assert!(names.iter().any(|name| name == "new.txt"));
+    assert!(
+        names
+            .iter()
+            .any(|name| name == "A new file with a long name.bin")
+    );
+    assert_eq!(b"short and 8.3\n", read_file(&mut fs, "new.txt").as_slice());
+    assert_eq!(data, read_file(&mut fs, "A new file with a long name.bin"));
+}
+
+generate_tests!(
+    test_overwrite_and_append:
+    FAT12 => test_overwrite_and_append_fat12,
+    FAT16 => test_overwrite_and_append_fat16,
+    FAT32 => test_overwrite_and_append_fat32,
+);
+
+fn test_overwrite_and_append(image: &str) {
+    let mut fs = open_fs(image);
+    let (hello, _) = lookup(&mut fs, "hello.txt");
+    let handle = fs.open(hello).unwrap();
+    assert_eq!(Ok(5), fs.write(handle, b"Howdy", 0));
+    assert_eq!(Ok(7), fs.write(handle, b" again\n", 14));
+    fs.close(handle).unwrap();
+
+    let mut fs = remount(&fs);
+    assert_eq!(
+        b"Howdy, World!\n again\n",
+        read_file(&mut fs, "hello.txt").as_slice()
+    );
+}
+
+generate_tests!(
+    test_write_leaves_zeroed_hole:
+    FAT12 => test_write_leaves_zeroed_hole_fat12,
+    FAT16 => test_write_leaves_zeroed_hole_fat16,
+    FAT32 => test_write_leaves_zeroed_hole_fat32,
+);
+
+fn test_write_leaves_zeroed_hole(image: &str) {
+    let mut fs = open_fs(image);
+    let root = fs.root();
+    // take clusters that numbers.txt used to have, which aren't zeroed
+    fs.unlink(root, "numbers.txt").unwrap();
+
+    let node = fs.create(root, "sparse", FsNodeKind::File).unwrap();
+    let handle = fs.open(node).unwrap();
+    assert_eq!(Ok(4), fs.write(handle, b"tail", 10000));
+    fs.close(handle).unwrap();
+
+    let data = read_file(&mut fs, "sparse");
+    assert_eq!(10004, data.len());
+    assert!(data[..10000].iter().all(|&b| b == 0));
+    assert_eq!(b"tail", &data[10000..]);
+}
+
+generate_tests!(
+    test_directories:
+    FAT12 => test_directories_fat12,
+    FAT16 => test_directories_fat16,
+    FAT32 => test_directories_fat32,
+);
+
+fn test_directories(image: &str) {
+    let mut fs = open_fs(image);
+    let root = fs.root();
+    let (docs, _) = lookup(&mut fs, "docs");
+    let dir = fs
+        .create(docs, "new directory", FsNodeKind::Directory)
+        .unwrap();
+    write_file(&mut fs, dir, "file.txt", b"in a new directory\n");
+
+    let mut fs = remount(&fs);
+    assert_eq!(
+        vec!["nested", "new directory"],
+        sorted(fs.read_dir(docs).unwrap())
+    );
+    assert_eq!(
+        b"in a new directory\n",
+        read_file(&mut fs, "docs/new directory/file.txt").as_slice()
+    );
+
+    assert_eq!(Err(UnlinkError::NotEmpty), fs.rmdir(docs, "new directory"));
+    assert_eq!(
+        Err(UnlinkError::IsDirectory),
+        fs.unlink(docs, "new directory")
+    );
+    assert_eq!(Err(UnlinkError::NotDirectory), fs.rmdir(root, "hello.txt"));
+    let (dir, _) = lookup(&mut fs, "docs/new directory");
+    fs.unlink(dir, "file.txt").unwrap();
+    fs.rmdir(docs, "new directory").unwrap();
+
+    let mut fs = remount(&fs);
+    assert_eq!(vec!["nested"], fs.read_dir(docs).unwrap());
+    assert_eq!(Err(OpenError::NotFound), fs.lookup(docs, "new directory"));
+}
+
+generate_tests!(
+    test_grow_directory:
+    FAT12 => test_grow_directory_fat12,
+    FAT16 => test_grow_directory_fat16,
+    FAT32 => test_grow_directory_fat32,
+);
+
+fn test_grow_directory(image: &str) {
+    let mut fs = open_fs(image);
+    let (many, _) = lookup(&mut fs, "many");
+    for i in 100..300 {
+        write_file(&mut fs, many, &format!("file_{i:03}.txt"), &[]);
+    }
+
+    let mut fs = remount(&fs);
+    assert_eq!(300, fs.read_dir(many).unwrap().len());
+    assert_eq!(
+        b"file_42\n",
+        read_file(&mut fs, "many/file_042.txt").as_slice()
+    );
+}
+
+#[test]
+fn test_fixed_root_directory_is_full() {
+    // the root directory of the FAT12 image has room for 224 entries
+    let mut fs = open_fs(FAT12);
+    let root = fs.root();
+    let result = (0..224)
+        .map(|i| fs.create(root, &format!("F{i}"), FsNodeKind::File))
+        .find(Result::is_err);
+    assert_eq!(Some(Err(CreateError::NoSpace)), result);
+}
+
+#[test]
+fn test_file_system_is_full() {
+    let mut fs = open_fs(FAT12);
+    let root = fs.root();
+    let node = fs.create(root, "big", FsNodeKind::File).unwrap();
+    let handle = fs.open(node).unwrap();
+    let data = vec![0xAA; 2 * 1024 * 1024];
+    assert_eq!(Err(WriteError::NoSpace), fs.write(handle, &data, 0));
+    fs.close(handle).unwrap();
+
+    // the failed write didn't leak any clusters
+    let handle = fs.open(node).unwrap();
+    assert_eq!(Ok(1024 * 1024), fs.write(handle, &data[..1024 * 1024], 0));
+    fs.close(handle).unwrap();
+}
+
+generate_tests!(
+    test_unlink_open_file:
+    FAT12 => test_unlink_open_file_fat12,
+    FAT16 => test_unlink_open_file_fat16,
+    FAT32 => test_unlink_open_file_fat32,
+);
+
+fn test_unlink_open_file(image: &str) {
+    let mut fs = open_fs(image);
+    let root = fs.root();
+    let (node, _) = lookup(&mut fs, "numbers.txt");
+    let handle = fs.open(node).unwrap();
+    fs.unlink(root, "numbers.txt").unwrap();
+    assert_eq!(Err(OpenError::NotFound), fs.lookup(root, "numbers.txt"));
+
+    // a new file doesn't take the entry, or the clusters, of the open one
+    let new = write_file(&mut fs, root, "numbers.txt", &[0xFF; 30000]);
+    assert_ne!(node, new);
+    let mut buf = [0; 16];
+    assert_eq!(Ok(16), fs.read(handle, &mut buf, 0));
+    assert_eq!(&numbers()[..16], &buf);
+    fs.close(handle).unwrap();
+    assert_eq!(
+        Err(ReadError::FsError(kernel_vfs::FsError::InvalidHandle)),
+        fs.read(handle, &mut buf, 0)
+    );
+
+    let mut fs = remount(&fs);
+    assert_eq!(vec![0xFF; 30000], read_file(&mut fs, "numbers.txt"));
+}
+
+generate_tests!(
+    test_short_name_collisions:
+    FAT12 => test_short_name_collisions_fat12,
+    FAT16 => test_short_name_collisions_fat16,
+    FAT32 => test_short_name_collisions_fat32,
+);
+
+fn test_short_name_collisions(image: &str) {
+    let mut fs = open_fs(image);
+    let root = fs.root();
+    // these all shorten to ALONGF~N.TXT, like the long name in the image
+    for i in 0..5 {
+        write_file(
+            &mut fs,
+            root,
+            &format!("A long file name {i}.txt"),
+            format!("{i}\n").as_bytes(),
+        );
+    }
+
+    let mut fs = remount(&fs);
+    assert_eq!(
+        b"This file has a long name.\n",
+        read_file(&mut fs, "A long file name.txt").as_slice()
+    );
+    for i in 0..5 {
+        assert_eq!(
+            format!("{i}\n").as_bytes(),
+            read_file(&mut fs, &format!("A long file name {i}.txt"))
+        );
+    }
+}
+
+#[test]
+fn test_invalid_names() {
+    let mut fs = open_fs(FAT32);
+    let root = fs.root();
+    for name in ["a:b", "what?", "trailing.", "<>"] {
+        assert_eq!(
+            Err(CreateError::NotSupported),
+            fs.create(root, name, FsNodeKind::File),
+            "{name}"
+        );
+    }
+    assert_eq!(
+        Err(CreateError::NotSupported),
+        fs.create(root, "link", FsNodeKind::Symlink)
+    );
+}
@@ -0,0 +1,239 @@
+//! Input devices, independent of the driver that their events come from.
+//!
+//! A driver describes its device with a [`DeviceInfo`] and reports what
+//! happens as [`InputEvent`]s, each batch ended by a [`SYN_REPORT`]. Every
+//! reader of a device has its own [`EventQueue`], which buffers events until
+//! they are read and tells the reader when it fell behind. Nothing here
+//! blocks. Waiting for events is up to the caller.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+
+use kernel_abi::{
+    AbsInfo, EventType, INPUT_NAME_SIZE, InputCapabilities, InputEvent, SYN_DROPPED, SYN_REPORT,
+};
+
+/// How many events a reader can fall behind before its queue overflows.
+pub const EVENT_QUEUE_SIZE: usize = 256;
+
+/// What an input device is and which events it reports.
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    name: String,
+    capabilities: InputCapabilities,
+    absolute: BTreeMap<u16, AbsInfo>,
+}
+
+impl DeviceInfo {
+    /// A device that reports only sync events so far. Add the others with
+    /// [`DeviceInfo::with_event`] and [`DeviceInfo::with_absolute_axis`].
+    #[must_use]
+    pub fn new(name: String) -> Self {
+        let mut capabilities = InputCapabilities::default();
+        capabilities.insert(EventType::Sync, SYN_REPORT);
+        Self {
+            name,
+            capabilities,
+            absolute: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_event(mut self, kind: EventType, code: u16) -> Self {
+        self.capabilities.insert(kind, code);
+        self
+    }
+
+    /// Adds an absolute axis with positions from `minimum` to `maximum`.
+    #[must_use]
+    pub fn with_absolute_axis(mut self, axis: u16, minimum: i32, maximum: i32) -> Self {
+        self.capabilities.insert(EventType::Absolute, axis);
+        self.absolute.insert(
+            axis,
+            AbsInfo {
+                axis: u32::from(axis),
+                value: minimum,
+                minimum,
+                maximum,
+            },
+        );
+        self
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name as `InputGetName` returns it, cut to fit and padded with
+    /// NUL bytes, and its length.
+    #[must_use]
+    pub fn name_bytes(&self) -> ([u8; INPUT_NAME_SIZE], usize) {
+        let mut bytes = [0; INPUT_NAME_SIZE];
+        // keep a NUL at the end for C readers
+        let len = self.name.len().min(INPUT_NAME_SIZE - 1);
+        bytes[..len].copy_from_slice(&self.name.as_bytes()[..len]);
+        (bytes, len)
+    }
+
+    #[must_use]
+    pub fn capabilities(&self) -> &InputCapabilities {
+        &self.capabilities
+    }
+
+    /// The range and last position of `axis`, or `None` if the device has
+    /// no such axis.
+    #[must_use]
+    pub fn abs_info(&self, axis: u32) -> Option<AbsInfo> {
+        self.absolute.get(&u16::try_from(axis).ok()?).copied()
+    }
+
+    /// Remembers the position that an absolute event reports.
+    pub fn update(&mut self, event: &InputEvent) {
+        if event.kind() == Some(EventType::Absolute)
+            && let Some(info) = self.absolute.get_mut(&event.code)
+        {
+            info.value = event.value;
+        }
+    }
+}
+
+/// The events of a device that one reader hasn't read yet.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    events: VecDeque<InputEvent>,
+}
+
+impl EventQueue {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `event`. If the queue is full, everything in it is dropped
+    /// for a [`SYN_DROPPED`] like on Linux, so that the reader learns that
+    /// it lost track of the device state.
+    pub fn push(&mut self, event: InputEvent) {
+        if self.events.len() == EVENT_QUEUE_SIZE {
+            self.events.clear();
+            self.events
+                .push_back(InputEvent::new(event.time, EventType::Sync, SYN_DROPPED, 0));
+        }
+        self.events.push_back(event);
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Moves as many whole events into `buf` as fit and returns how many
+    /// bytes that was.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let mut read = 0;
+        for chunk in buf.as_chunks_mut::<{ InputEvent::SIZE }>().0 {
+            let Some(event) = self.events.pop_front() else {
+                break;
+            };
+            *chunk = event.to_bytes();
+            read += InputEvent::SIZE;
+        }
+        read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use kernel_abi::{AbsoluteAxis, KEY_PRESSED, KeyCode};
+
+    use super::*;
+
+    fn key(time: u64) -> InputEvent {
+        InputEvent::new(time, EventType::Key, KeyCode::A.number(), KEY_PRESSED)
+    }
+
+    fn read_all(queue: &mut EventQueue) -> alloc::vec::Vec<InputEvent> {
+        let mut buf = vec![0; (queue.len() + 1) * InputEvent::SIZE];
+        let read = queue.read(&mut buf);
+        buf[..read]
+            .as_chunks::<{ InputEvent::SIZE }>()
+            .0
+            .iter()
+            .map(InputEvent::from_bytes)
+            .collect()
+    }
+
+    #[test]
+    fn test_read_whole_events() {
+        let mut queue = EventQueue::new();
+        queue.push(key(1));
+        queue.push(key(2));
+
+        let mut buf = [0; InputEvent::SIZE + 3];
+        assert_eq!(queue.read(&mut buf), InputEvent::SIZE);
+        assert_eq!(
+            InputEvent::from_bytes(buf[..InputEvent::SIZE].try_into().unwrap()),
+            key(1)
+        );
+        assert_eq!(queue.read(&mut [0; 3]), 0, "a partial event isn't read");
+        assert_eq!(read_all(&mut queue), [key(2)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_overflow_drops_for_syn_dropped() {
+        let mut queue = EventQueue::new();
+        for time in 0..EVENT_QUEUE_SIZE as u64 + 1 {
+            queue.push(key(time));
+        }
+        let events = read_all(&mut queue);
+        assert_eq!(
+            events,
+            [
+                InputEvent::new(EVENT_QUEUE_SIZE as u64, EventType::Sync, SYN_DROPPED, 0),
+                key(EVENT_QUEUE_SIZE as u64),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_device_info() {
+        let x = AbsoluteAxis::X as u16;
+        let mut info = DeviceInfo::new(String::from("tablet"))
+            .with_event(EventType::Key, KeyCode::BtnLeft.number())
+            .with_absolute_axis(x, 0, 32767);
+
+        let capabilities = info.capabilities();
+        assert!(capabilities.contains(EventType::Sync, SYN_REPORT));
+        assert!(capabilities.contains(EventType::Key, KeyCode::BtnLeft.number()));
+        assert!(capabilities.contains(EventType::Absolute, x));
+        assert!(info.abs_info(AbsoluteAxis::Y as u32).is_none());
+
+        info.update(&InputEvent::new(0, EventType::Absolute, x, 1000));
+        let abs = info.abs_info(u32::from(x)).unwrap();
+        assert_eq!((abs.value, abs.minimum, abs.maximum), (1000, 0, 32767));
+
+        let (name, len) = info.name_bytes();
+        assert_eq!(&name[..len], b"tablet");
+        assert!(name[len..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_long_name_keeps_nul() {
+        let info = DeviceInfo::new("x".repeat(2 * INPUT_NAME_SIZE));
+        let (name, len) = info.name_bytes();
+        assert_eq!(len, INPUT_NAME_SIZE - 1);
+        assert_eq!(name[INPUT_NAME_SIZE - 1], 0);
+    }
+}
@@ -0,0 +1,41 @@
+use kernel_vfs::{OpenError, ReadError, ReadlinkError};
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum IsoError {
+    #[error("the device does not contain an ISO9660 file system")]
+    InvalidVolumeDescriptor,
+    #[error("the device could not be read")]
+    DeviceRead,
+    #[error("invalid directory record at byte {0}")]
+    InvalidRecord(usize),
+    #[error("not a directory")]
+    NotDirectory,
+}
+
+// The vfs errors have no variant for a failing device, so lookups report
+// the entry as missing, like the ext2 driver does.
+
+impl From<IsoError> for OpenError {
+    fn from(value: IsoError) -> Self {
+        match value {
+            IsoError::NotDirectory => Self::NotDirectory,
+            _ => Self::NotFound,
+        }
+    }
+}
+
+impl From<IsoError> for ReadlinkError {
+    fn from(value: IsoError) -> Self {
+        match value {
+            IsoError::NotDirectory => Self::NotDirectory,
+            _ => Self::ReadFailed,
+        }
+    }
+}
+
+impl From<IsoError> for ReadError {
+    fn from(_: IsoError) -> Self {
+        Self::ReadFailed
+    }
+}
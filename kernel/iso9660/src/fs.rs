@@ -0,0 +1,353 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use kernel_abi::{S_IFDIR, S_IFLNK, S_IFREG};
+use kernel_device::block::BlockDevice;
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use kernel_vfs::path::{OwnedPath, Path};
+use kernel_vfs::{
+    CloseError, CreateError, FsError, FsyncError, OpenError, ReadError, ReadlinkError, Stat,
+    StatError, UnlinkError, WriteError,
+};
+
+use crate::record::{DirRecord, MAX_RECORD_SIZE, parse_records};
+use crate::susp::{RockRidge, sharing_protocol};
+use crate::volume::{
+    FIRST_VOLUME_DESCRIPTOR, SECTOR_SIZE, TYPE_PRIMARY, TYPE_TERMINATOR, descriptor_type,
+};
+use crate::{IsoError, PrimaryVolumeDescriptor};
+
+/// More volume descriptors than any real image has, so that a missing
+/// terminator doesn't make us read the whole device.
+const MAX_VOLUME_DESCRIPTORS: usize = 64;
+
+/// The number of continuation areas of a record that are followed, which
+/// keeps a loop of them from hanging the lookup.
+const MAX_CONTINUATIONS: usize = 16;
+
+/// A read-only ISO9660 file system over a block device, with the names,
+/// permissions and symlinks of Rock Ridge if the volume has them.
+///
+/// Directories are identified by the byte offset of their data, which
+/// starts with their `.` record, and all other nodes by the byte offset of
+/// their directory record. Both are unique and never change.
+///
+/// Files larger than 4 GiB, which span several extents, are not supported.
+pub struct Iso9660Fs<T> {
+    device: T,
+    volume: PrimaryVolumeDescriptor,
+    /// The number of bytes to skip at the start of the system use area of
+    /// every record, if the volume has Rock Ridge entries.
+    rock_ridge: Option<usize>,
+    handles: BTreeMap<FsHandle, IsoNode>,
+    next_handle: u64,
+}
+
+/// A node as its directory record and Rock Ridge entries describe it.
+#[derive(Debug, Clone)]
+struct IsoNode {
+    id: FsNodeId,
+    name: String,
+    kind: FsNodeKind,
+    data_offset: usize,
+    size: usize,
+    mode: u32,
+    nlink: u32,
+    symlink: Option<String>,
+    relocated: bool,
+}
+
+impl<T> Iso9660Fs<T>
+where
+    T: BlockDevice,
+{
+    /// # Errors
+    /// Returns an error if the device can't be read or doesn't contain an
+    /// ISO9660 file system.
+    pub fn try_new(device: T) -> Result<Self, IsoError> {
+        let volume = Self::read_volume_descriptor(&device)?;
+        let mut fs = Self {
+            device,
+            volume,
+            rock_ridge: None,
+            handles: BTreeMap::new(),
+            next_handle: 0,
+        };
+        let root = fs.record_at(fs.root_offset())?;
+        fs.rock_ridge = sharing_protocol(&root.system_use);
+        Ok(fs)
+    }
+
+    /// Reads the primary volume descriptor of the ISO9660 file system on
+    /// `device`, which tells whether there is one.
+    ///
+    /// # Errors
+    /// Returns an error if the device can't be read or doesn't contain an
+    /// ISO9660 file system.
+    pub fn read_volume_descriptor(device: &T) -> Result<PrimaryVolumeDescriptor, IsoError> {
+        let mut sector = [0; SECTOR_SIZE];
+        for index in FIRST_VOLUME_DESCRIPTOR..FIRST_VOLUME_DESCRIPTOR + MAX_VOLUME_DESCRIPTORS {
+            device
+                .read_at(index * SECTOR_SIZE, &mut sector)
+                .map_err(|_| IsoError::DeviceRead)?;
+            match descriptor_type(&sector) {
+                Some(TYPE_PRIMARY) => return PrimaryVolumeDescriptor::try_from(&sector),
+                Some(TYPE_TERMINATOR) | None => break,
+                Some(_) => {}
+            }
+        }
+        Err(IsoError::InvalidVolumeDescriptor)
+    }
+
+    #[must_use]
+    pub fn volume(&self) -> &PrimaryVolumeDescriptor {
+        &self.volume
+    }
+
+    #[must_use]
+    pub fn device(&self) -> &T {
+        &self.device
+    }
+
+    fn root_offset(&self) -> usize {
+        self.volume.root().data_offset(self.volume.block_size())
+    }
+
+    fn read(&self, offset: usize, len: usize) -> Result<Vec<u8>, IsoError> {
+        let mut data = vec![0; len];
+        self.device
+            .read_at(offset, &mut data)
+            .map_err(|_| IsoError::DeviceRead)?;
+        Ok(data)
+    }
+
+    /// Reads the directory record at byte `offset`, which can't extend past
+    /// the end of its block.
+    fn record_at(&self, offset: usize) -> Result<DirRecord, IsoError> {
+        let block_size = self.volume.block_size();
+        let len = MAX_RECORD_SIZE.min(block_size - offset % block_size);
+        DirRecord::parse(&self.read(offset, len)?, offset)
+    }
+
+    fn rock_ridge(&self, record: &DirRecord) -> Result<RockRidge, IsoError> {
+        let mut rock_ridge = RockRidge::default();
+        let Some(skip) = self.rock_ridge else {
+            return Ok(rock_ridge);
+        };
+        let mut continuation = rock_ridge.parse(record.system_use.get(skip..).unwrap_or(&[]));
+        for _ in 0..MAX_CONTINUATIONS {
+            let Some(area) = continuation else {
+                break;
+            };
+            let offset = area.block as usize * self.volume.block_size() + area.offset as usize;
+            let len = (area.len as usize).min(self.volume.block_size());
+            continuation = rock_ridge.parse(&self.read(offset, len)?);
+        }
+        Ok(rock_ridge)
+    }
+
+    fn node(&self, record: &DirRecord) -> Result<IsoNode, IsoError> {
+        let rock_ridge = self.rock_ridge(record)?;
+        let Some(block) = rock_ridge.child_link else {
+            return Ok(self.plain_node(record, rock_ridge));
+        };
+
+        // the directory that was moved away takes the place of this record,
+        // under the name of the record. Its `.` can't link on, which would
+        // let a crafted image recurse forever.
+        let dot = self.record_at(block as usize * self.volume.block_size())?;
+        let target = self.rock_ridge(&dot)?;
+        if !dot.is_dot() || target.child_link.is_some() {
+            return Err(IsoError::InvalidRecord(dot.offset));
+        }
+        Ok(IsoNode {
+            name: rock_ridge.name().unwrap_or_else(|| record.plain_name()),
+            ..self.plain_node(&dot, target)
+        })
+    }
+
+    /// The node of `record` itself, without following a child link.
+    fn plain_node(&self, record: &DirRecord, rock_ridge: RockRidge) -> IsoNode {
+        let block_size = self.volume.block_size();
+        let symlink = rock_ridge.symlink();
+        let (id, kind, default_mode) = if symlink.is_some() {
+            (record.offset, FsNodeKind::Symlink, S_IFLNK | 0o777)
+        } else if record.is_directory() {
+            (
+                record.data_offset(block_size),
+                FsNodeKind::Directory,
+                S_IFDIR | 0o555,
+            )
+        } else {
+            (record.offset, FsNodeKind::File, S_IFREG | 0o444)
+        };
+        IsoNode {
+            id: FsNodeId::from(id as u64),
+            name: rock_ridge.name().unwrap_or_else(|| record.plain_name()),
+            kind,
+            data_offset: record.data_offset(block_size),
+            size: record.size as usize,
+            mode: rock_ridge.mode.unwrap_or(default_mode),
+            nlink: rock_ridge.nlink.unwrap_or(1),
+            symlink,
+            relocated: rock_ridge.relocated,
+        }
+    }
+
+    /// Reads the node with the id `id`, which is either a directory that
+    /// starts with its `.` record, or the record of any other node.
+    fn node_by_id(&self, id: FsNodeId) -> Result<IsoNode, IsoError> {
+        self.node(&self.record_at(id.get() as usize)?)
+    }
+
+    /// Returns the entries of the directory `dir`, without `.` and `..`.
+    fn entries(&self, dir: FsNodeId) -> Result<Vec<IsoNode>, IsoError> {
+        let dot = self.record_at(dir.get() as usize)?;
+        if !dot.is_dot() {
+            return Err(IsoError::NotDirectory);
+        }
+        // the size comes from the image, a directory that doesn't fit on the
+        // device is corrupt and must not be allocated
+        let device_size = self.device.sector_count() * self.device.sector_size();
+        let size = dot.size as usize;
+        if dir.get() as usize + size > device_size {
+            return Err(IsoError::InvalidRecord(dot.offset));
+        }
+        let data = self.read(dir.get() as usize, size)?;
+        parse_records(&data, dir.get() as usize, self.volume.block_size())?
+            .iter()
+            .filter(|record| !record.is_dot() && !record.is_dot_dot() && !record.is_associated())
+            .map(|record| self.node(record))
+            // moved directories show up where their child link is
+            .filter(|node| !node.as_ref().is_ok_and(|node| node.relocated))
+            .collect()
+    }
+
+    fn handle_node(&self, handle: FsHandle) -> Result<&IsoNode, FsError> {
+        self.handles.get(&handle).ok_or(FsError::InvalidHandle)
+    }
+}
+
+impl<T> FileSystem for Iso9660Fs<T>
+where
+    T: BlockDevice + Send + Sync,
+{
+    fn root(&self) -> FsNodeId {
+        FsNodeId::from(self.root_offset() as u64)
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        self.entries(dir)?
+            .into_iter()
+            .find(|node| node.name == name)
+            .map(|node| (node.id, node.kind))
+            .ok_or(OpenError::NotFound)
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        Ok(self
+            .entries(dir)?
+            .into_iter()
+            .map(|node| node.name)
+            .collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        let node = self.node_by_id(node)?;
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.handles.insert(handle, node);
+        Ok(handle)
+    }
+
+    fn readlink(&mut self, node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        self.node_by_id(node)?
+            .symlink
+            .map(OwnedPath::new)
+            .ok_or(ReadlinkError::NotSymlink)
+    }
+
+    fn create(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::ReadOnly)
+    }
+
+    fn symlink(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _target: &Path,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::ReadOnly)
+    }
+
+    fn unlink(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::ReadOnly)
+    }
+
+    fn rmdir(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::ReadOnly)
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        self.handles
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or(CloseError::NotOpen)
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let node = self.handle_node(handle)?;
+        if node.kind != FsNodeKind::File {
+            return Err(ReadError::NotReadable);
+        }
+        if offset >= node.size {
+            return Err(ReadError::EndOfFile);
+        }
+        let len = buf.len().min(node.size - offset);
+        self.device
+            .read_at(node.data_offset + offset, &mut buf[..len])
+            .map_err(|_| ReadError::ReadFailed)?;
+        Ok(len)
+    }
+
+    fn write(
+        &mut self,
+        handle: FsHandle,
+        _buf: &[u8],
+        _offset: usize,
+    ) -> Result<usize, WriteError> {
+        self.handle_node(handle)?;
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let node = self.handle_node(handle)?;
+        let allocated = node.size.next_multiple_of(self.volume.block_size());
+        *stat = Stat {
+            size: node.size,
+            ino: node.id.get(),
+            mode: node.mode,
+            nlink: node.nlink,
+            blocks: (allocated / 512) as u64,
+        };
+        Ok(())
+    }
+
+    fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
+        // there's nothing to write back
+        self.handle_node(handle)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! A read-only ISO9660 file system with the Rock Ridge extensions, as
+//! written by `xorriso -as mkisofs -R` for the boot medium.
+#![no_std]
+extern crate alloc;
+
+mod error;
+mod fs;
+mod record;
+mod susp;
+mod volume;
+
+pub use error::*;
+pub use fs::*;
+pub use volume::*;
@@ -0,0 +1,191 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::IsoError;
+
+const FLAG_DIRECTORY: u8 = 0x02;
+/// Marks an associated file, the resource fork of a Mac file, which has the
+/// same name as the file it belongs to.
+const FLAG_ASSOCIATED: u8 = 0x04;
+
+/// The size of a directory record without its name and system use area.
+const RECORD_HEADER_SIZE: usize = 33;
+
+/// The largest possible directory record, since the length is a single byte.
+pub(crate) const MAX_RECORD_SIZE: usize = 255;
+
+/// A directory record, which describes one entry of a directory.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct DirRecord {
+    /// The byte offset of the record on the device.
+    pub offset: usize,
+    /// The first logical block of the data.
+    pub extent: u32,
+    /// The number of logical blocks of the extended attribute record in
+    /// front of the data.
+    pub ext_attr_len: u8,
+    pub size: u32,
+    pub flags: u8,
+    /// The raw ISO9660 name, like `README.TXT;1`, or `[0]` and `[1]` for
+    /// `.` and `..`.
+    pub name: Vec<u8>,
+    /// The system use area after the name, which holds the SUSP entries
+    /// of Rock Ridge.
+    pub system_use: Vec<u8>,
+}
+
+impl DirRecord {
+    /// Parses the record that `bytes` start with, which is at byte `offset`
+    /// on the device.
+    pub fn parse(bytes: &[u8], offset: usize) -> Result<Self, IsoError> {
+        let len = usize::from(*bytes.first().ok_or(IsoError::InvalidRecord(offset))?);
+        if len < RECORD_HEADER_SIZE + 1 || len > bytes.len() {
+            return Err(IsoError::InvalidRecord(offset));
+        }
+        let bytes = &bytes[..len];
+        let name_len = usize::from(bytes[32]);
+        let name_end = RECORD_HEADER_SIZE + name_len;
+        if name_len == 0 || name_end > len {
+            return Err(IsoError::InvalidRecord(offset));
+        }
+        // the name is padded to an even length
+        let system_use_start = (name_end + (name_len + 1) % 2).min(len);
+
+        Ok(Self {
+            offset,
+            extent: u32::from_le_bytes(bytes[2..6].try_into().unwrap()),
+            ext_attr_len: bytes[1],
+            size: u32::from_le_bytes(bytes[10..14].try_into().unwrap()),
+            flags: bytes[25],
+            name: bytes[RECORD_HEADER_SIZE..name_end].to_vec(),
+            system_use: bytes[system_use_start..].to_vec(),
+        })
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.flags & FLAG_DIRECTORY != 0
+    }
+
+    pub fn is_associated(&self) -> bool {
+        self.flags & FLAG_ASSOCIATED != 0
+    }
+
+    pub fn is_dot(&self) -> bool {
+        self.name == [0]
+    }
+
+    pub fn is_dot_dot(&self) -> bool {
+        self.name == [1]
+    }
+
+    /// The byte offset of the data on the device, after the extended
+    /// attribute record.
+    pub fn data_offset(&self, block_size: usize) -> usize {
+        (self.extent as usize + usize::from(self.ext_attr_len)) * block_size
+    }
+
+    /// The name without Rock Ridge, like Linux shows it: without the
+    /// version, without the dot of a name that has no extension, and in
+    /// lower case.
+    pub fn plain_name(&self) -> String {
+        let name = match self.name.iter().rposition(|&b| b == b';') {
+            Some(version) if !self.is_directory() => &self.name[..version],
+            _ => &self.name[..],
+        };
+        let name = name.strip_suffix(b".").unwrap_or(name);
+        String::from_utf8_lossy(name).to_ascii_lowercase()
+    }
+}
+
+/// Parses all records of a directory whose data, starting at byte `offset`
+/// on the device, is `data`. Records don't cross block boundaries, the rest
+/// of a block that has no room for the next record is zero.
+pub(crate) fn parse_records(
+    data: &[u8],
+    offset: usize,
+    block_size: usize,
+) -> Result<Vec<DirRecord>, IsoError> {
+    let mut records = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        if data[position] == 0 {
+            position = (position + 1).next_multiple_of(block_size);
+            continue;
+        }
+        let record = DirRecord::parse(&data[position..], offset + position)?;
+        position += usize::from(data[position]);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn record(name: &[u8], flags: u8, system_use: &[u8]) -> Vec<u8> {
+        let padding = (name.len() + 1) % 2;
+        let len = RECORD_HEADER_SIZE + name.len() + padding + system_use.len();
+        let mut bytes = vec![0; len];
+        bytes[0] = len as u8;
+        bytes[2..6].copy_from_slice(&20_u32.to_le_bytes());
+        bytes[10..14].copy_from_slice(&100_u32.to_le_bytes());
+        bytes[25] = flags;
+        bytes[32] = name.len() as u8;
+        bytes[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + name.len()].copy_from_slice(name);
+        bytes[len - system_use.len()..].copy_from_slice(system_use);
+        bytes
+    }
+
+    #[test]
+    fn test_parse() {
+        let bytes = record(b"README.TXT;1", 0, b"RR\x05\x01\x81");
+        let record = DirRecord::parse(&bytes, 4096).unwrap();
+        assert_eq!(4096, record.offset);
+        assert_eq!(20 * 2048, record.data_offset(2048));
+        assert_eq!(100, record.size);
+        assert_eq!(b"README.TXT;1", record.name.as_slice());
+        // the name has an even length, so there's a padding byte
+        assert_eq!(b"RR\x05\x01\x81", record.system_use.as_slice());
+        assert_eq!("readme.txt", record.plain_name());
+    }
+
+    #[test]
+    fn test_plain_name() {
+        for (name, flags, expected) in [
+            (&b"HELLO.;1"[..], 0, "hello"),
+            (b"HELLO", 0, "hello"),
+            (b"A.B.C;2", 0, "a.b.c"),
+            (b"DOCS", FLAG_DIRECTORY, "docs"),
+        ] {
+            let record = DirRecord::parse(&record(name, flags, &[]), 0).unwrap();
+            assert_eq!(expected, record.plain_name());
+        }
+    }
+
+    #[test]
+    fn test_records_skip_to_next_block() {
+        let mut data = vec![0; 4096];
+        let dot = record(&[0], FLAG_DIRECTORY, &[]);
+        let file = record(b"A.TXT;1", 0, &[]);
+        data[..dot.len()].copy_from_slice(&dot);
+        data[2048..2048 + file.len()].copy_from_slice(&file);
+
+        let records = parse_records(&data, 8192, 2048).unwrap();
+        assert_eq!(2, records.len());
+        assert!(records[0].is_dot());
+        assert_eq!(8192 + 2048, records[1].offset);
+    }
+
+    #[test]
+    fn test_invalid_record() {
+        let mut bytes = record(b"A.TXT;1", 0, &[]);
+        bytes[32] = 200;
+        assert_eq!(
+            Err(IsoError::InvalidRecord(10)),
+            DirRecord::parse(&bytes, 10)
+        );
+    }
+}
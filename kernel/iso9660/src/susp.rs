@@ -0,0 +1,255 @@
+//! The System Use Sharing Protocol entries in the system use area of a
+//! directory record, and the Rock Ridge entries among them.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const NM_CURRENT: u8 = 0x02;
+const NM_PARENT: u8 = 0x04;
+
+const SL_CONTINUE: u8 = 0x01;
+const SL_CURRENT: u8 = 0x02;
+const SL_PARENT: u8 = 0x04;
+const SL_ROOT: u8 = 0x08;
+
+/// Returns the number of bytes to skip at the start of the system use area
+/// of every record, if `system_use`, the area of the `.` record of the root
+/// directory, starts with an `SP` entry. Without one, the volume has no
+/// SUSP entries, and so no Rock Ridge.
+pub(crate) fn sharing_protocol(system_use: &[u8]) -> Option<usize> {
+    match system_use {
+        [b'S', b'P', 7, 1, 0xBE, 0xEF, skip, ..] => Some(usize::from(*skip)),
+        _ => None,
+    }
+}
+
+/// A continuation area, which holds the SUSP entries that don't fit into
+/// the record.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Continuation {
+    pub block: u32,
+    pub offset: u32,
+    pub len: u32,
+}
+
+/// What the Rock Ridge entries of a directory record say about its node.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub(crate) struct RockRidge {
+    name: Option<Vec<u8>>,
+    pub mode: Option<u32>,
+    pub nlink: Option<u32>,
+    symlink: Option<Vec<String>>,
+    /// Whether the last component of the symlink continues in the next
+    /// component record.
+    component_continues: bool,
+    /// The block of a directory that was moved away because it was nested
+    /// too deeply for ISO9660. The record is a file in its place.
+    pub child_link: Option<u32>,
+    /// Whether this is the record of such a moved directory, in the
+    /// directory that it was moved to.
+    pub relocated: bool,
+}
+
+impl RockRidge {
+    /// Adds the entries of a system use or continuation area, and returns
+    /// the next continuation area, if there is one.
+    pub fn parse(&mut self, area: &[u8]) -> Option<Continuation> {
+        let u32_at = |entry: &[u8], offset: usize| {
+            u32::from_le_bytes(entry[offset..offset + 4].try_into().unwrap())
+        };
+
+        let mut continuation = None;
+        let mut position = 0;
+        while let [a, b, len, _version, ..] = area[position..] {
+            let len = usize::from(len);
+            if len < 4 || position + len > area.len() {
+                break;
+            }
+            let entry = &area[position..position + len];
+            match [a, b] {
+                [b'C', b'E'] if len >= 28 => {
+                    continuation = Some(Continuation {
+                        block: u32_at(entry, 4),
+                        offset: u32_at(entry, 12),
+                        len: u32_at(entry, 20),
+                    });
+                }
+                [b'P', b'X'] if len >= 36 => {
+                    self.mode = Some(u32_at(entry, 4));
+                    self.nlink = Some(u32_at(entry, 12));
+                }
+                [b'N', b'M'] if len >= 5 => {
+                    if entry[4] & (NM_CURRENT | NM_PARENT) == 0 {
+                        self.name.get_or_insert_default().extend(&entry[5..]);
+                    }
+                }
+                [b'S', b'L'] if len >= 5 => self.parse_components(&entry[5..]),
+                [b'C', b'L'] if len >= 12 => self.child_link = Some(u32_at(entry, 4)),
+                [b'R', b'E'] => self.relocated = true,
+                [b'S', b'T'] => break,
+                _ => {}
+            }
+            position += len;
+        }
+        continuation
+    }
+
+    fn parse_components(&mut self, mut records: &[u8]) {
+        let components = self.symlink.get_or_insert_default();
+        while let [flags, len, ref rest @ ..] = *records {
+            let Some(content) = rest.get(..usize::from(len)) else {
+                break;
+            };
+            let component = if flags & SL_CURRENT != 0 {
+                String::from(".")
+            } else if flags & SL_PARENT != 0 {
+                String::from("..")
+            } else if flags & SL_ROOT != 0 {
+                String::new()
+            } else {
+                String::from_utf8_lossy(content).into_owned()
+            };
+            match components.last_mut() {
+                Some(last) if self.component_continues => last.push_str(&component),
+                _ => components.push(component),
+            }
+            self.component_continues = flags & SL_CONTINUE != 0;
+            records = &rest[usize::from(len)..];
+        }
+    }
+
+    /// The name from the `NM` entries.
+    pub fn name(&self) -> Option<String> {
+        self.name
+            .as_ref()
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+    }
+
+    /// The target from the `SL` entries, if the node is a symlink.
+    pub fn symlink(&self) -> Option<String> {
+        let components = self.symlink.as_ref()?;
+        Some(if components.as_slice() == [""] {
+            String::from("/")
+        } else {
+            components.join("/")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn entry(signature: &[u8; 2], data: &[u8]) -> Vec<u8> {
+        let mut entry = vec![signature[0], signature[1], 4 + data.len() as u8, 1];
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    fn both_endian(value: u32) -> Vec<u8> {
+        [value.to_le_bytes(), value.to_be_bytes()].concat()
+    }
+
+    #[test]
+    fn test_sharing_protocol() {
+        let sp = entry(b"SP", &[0xBE, 0xEF, 0]);
+        assert_eq!(Some(0), sharing_protocol(&sp));
+        assert_eq!(None, sharing_protocol(&entry(b"SP", &[0xBE, 0xEE, 0])));
+        assert_eq!(None, sharing_protocol(&[]));
+    }
+
+    #[test]
+    fn test_name_and_attributes() {
+        let area = [
+            entry(
+                b"PX",
+                &[
+                    both_endian(0o100_644),
+                    both_endian(1),
+                    both_endian(0),
+                    both_endian(0),
+                ]
+                .concat(),
+            ),
+            entry(b"NM", b"\x01A long"),
+            entry(b"NM", b"\x00 name.txt"),
+            entry(b"ST", &[]),
+            entry(b"NM", b"\x00ignored"),
+        ]
+        .concat();
+        let mut rock_ridge = RockRidge::default();
+        assert_eq!(None, rock_ridge.parse(&area));
+        assert_eq!(Some("A long name.txt".into()), rock_ridge.name());
+        assert_eq!(Some(0o100_644), rock_ridge.mode);
+        assert_eq!(Some(1), rock_ridge.nlink);
+        assert_eq!(None, rock_ridge.symlink());
+    }
+
+    #[test]
+    fn test_dot_names_are_ignored() {
+        let mut rock_ridge = RockRidge::default();
+        rock_ridge.parse(&[entry(b"NM", &[NM_CURRENT]), entry(b"NM", &[NM_PARENT])].concat());
+        assert_eq!(None, rock_ridge.name());
+    }
+
+    #[test]
+    fn test_continuation() {
+        let area = [
+            entry(b"NM", b"\x00short"),
+            entry(
+                b"CE",
+                &[both_endian(30), both_endian(100), both_endian(237)].concat(),
+            ),
+        ]
+        .concat();
+        let mut rock_ridge = RockRidge::default();
+        assert_eq!(
+            Some(Continuation {
+                block: 30,
+                offset: 100,
+                len: 237
+            }),
+            rock_ridge.parse(&area)
+        );
+    }
+
+    #[test]
+    fn test_symlinks() {
+        for (components, expected) in [
+            (&[&[SL_ROOT, 0][..], &[0, 3, b'e', b't', b'c']][..], "/etc"),
+            (&[&[SL_ROOT, 0][..]], "/"),
+            (&[&[SL_PARENT, 0][..], &[0, 1, b'a']], "../a"),
+            (&[&[SL_CURRENT, 0][..], &[0, 1, b'a']], "./a"),
+        ] {
+            let mut rock_ridge = RockRidge::default();
+            rock_ridge.parse(&entry(b"SL", &[&[0][..], &components.concat()].concat()));
+            assert_eq!(Some(expected.into()), rock_ridge.symlink());
+        }
+    }
+
+    #[test]
+    fn test_symlink_split_over_entries() {
+        // "dir/long" where "long" is split over two entries
+        let area = [
+            entry(b"SL", b"\x01\x00\x03dir\x01\x02lo"),
+            entry(b"SL", b"\x00\x00\x02ng"),
+        ]
+        .concat();
+        let mut rock_ridge = RockRidge::default();
+        rock_ridge.parse(&area);
+        assert_eq!(Some("dir/long".into()), rock_ridge.symlink());
+    }
+
+    #[test]
+    fn test_relocated_directories() {
+        let mut rock_ridge = RockRidge::default();
+        rock_ridge.parse(&entry(b"CL", &both_endian(42)));
+        assert_eq!(Some(42), rock_ridge.child_link);
+
+        let mut rock_ridge = RockRidge::default();
+        rock_ridge.parse(&entry(b"RE", &[]));
+        assert!(rock_ridge.relocated);
+    }
+}
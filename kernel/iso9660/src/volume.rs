@@ -0,0 +1,98 @@
+use crate::IsoError;
+use crate::record::DirRecord;
+
+/// The size of a sector on a CD, which is also the size of a volume
+/// descriptor.
+pub(crate) const SECTOR_SIZE: usize = 2048;
+
+/// The volume descriptors start after the system area of the first 16
+/// sectors, which ISO9660 leaves to the boot loader.
+pub(crate) const FIRST_VOLUME_DESCRIPTOR: usize = 16;
+
+pub(crate) const TYPE_PRIMARY: u8 = 1;
+pub(crate) const TYPE_TERMINATOR: u8 = 255;
+const STANDARD_IDENTIFIER: &[u8; 5] = b"CD001";
+
+/// The offset of the directory record of the root directory in the primary
+/// volume descriptor.
+const ROOT_RECORD_OFFSET: usize = 156;
+const ROOT_RECORD_SIZE: usize = 34;
+
+/// Returns the type of the volume descriptor in `sector`, or `None` if it
+/// isn't one.
+pub(crate) fn descriptor_type(sector: &[u8; SECTOR_SIZE]) -> Option<u8> {
+    (&sector[1..6] == STANDARD_IDENTIFIER && sector[6] == 1).then_some(sector[0])
+}
+
+/// The primary volume descriptor, which describes the ISO9660 volume and
+/// where its root directory is. Supplementary descriptors, like the one for
+/// Joliet names, are ignored in favour of Rock Ridge.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PrimaryVolumeDescriptor {
+    volume_id: [u8; 32],
+    block_size: usize,
+    block_count: u32,
+    root: DirRecord,
+}
+
+impl TryFrom<&[u8; SECTOR_SIZE]> for PrimaryVolumeDescriptor {
+    type Error = IsoError;
+
+    fn try_from(sector: &[u8; SECTOR_SIZE]) -> Result<Self, Self::Error> {
+        if descriptor_type(sector) != Some(TYPE_PRIMARY) {
+            return Err(IsoError::InvalidVolumeDescriptor);
+        }
+        // numbers are stored both little and big endian, the little endian
+        // half comes first
+        let block_count = u32::from_le_bytes(sector[80..84].try_into().unwrap());
+        let block_size = usize::from(u16::from_le_bytes([sector[128], sector[129]]));
+        if !matches!(block_size, 512 | 1024 | 2048) {
+            return Err(IsoError::InvalidVolumeDescriptor);
+        }
+        let root = DirRecord::parse(
+            &sector[ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + ROOT_RECORD_SIZE],
+            0,
+        )
+        .map_err(|_| IsoError::InvalidVolumeDescriptor)?;
+        if !root.is_directory() {
+            return Err(IsoError::InvalidVolumeDescriptor);
+        }
+
+        Ok(Self {
+            volume_id: sector[40..72].try_into().unwrap(),
+            block_size,
+            block_count,
+            root,
+        })
+    }
+}
+
+impl PrimaryVolumeDescriptor {
+    /// The volume label, as set with `xorriso -volid`, without the padding.
+    #[must_use]
+    pub fn volume_id(&self) -> &[u8] {
+        let len = self
+            .volume_id
+            .iter()
+            .rposition(|&b| b != b' ')
+            .map_or(0, |last| last + 1);
+        &self.volume_id[..len]
+    }
+
+    /// The size of a logical block in bytes, which extents are counted in.
+    /// It's 2048, the sector size of a CD, on every image in practice.
+    #[must_use]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// The size of the whole volume in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.block_count as usize * self.block_size
+    }
+
+    pub(crate) fn root(&self) -> &DirRecord {
+        &self.root
+    }
+}
@@ -0,0 +1,75 @@
+#![allow(dead_code)] // not every test binary uses every helper
+
+use std::fs;
+use std::path::Path;
+
+use kernel_device::block::MemoryBlockDevice;
+use kernel_iso9660::Iso9660Fs;
+use kernel_vfs::ReadError;
+use kernel_vfs::fs::{FileSystem, FsNodeId, FsNodeKind};
+
+pub type TestFs = Iso9660Fs<MemoryBlockDevice<Vec<u8>>>;
+
+/// The images that `BUILD.bazel` builds with xorriso. The plain one has no
+/// Rock Ridge, so it only holds the part of the tree whose names ISO9660
+/// can store.
+pub const ROCK_RIDGE: &str = "kernel/iso9660/tests/filesystems/rockridge.iso";
+pub const PLAIN: &str = "kernel/iso9660/tests/filesystems/plain.iso";
+
+#[macro_export]
+macro_rules! generate_tests {
+    ($test_fn:ident : $($image:expr => $name:ident),*,) => {
+        const _: &dyn Fn(&str) = &$test_fn;
+        $(
+            #[test]
+            fn $name() {
+                $test_fn($image);
+            }
+        )*
+    };
+}
+
+pub fn load_copy_of_image(test_image: impl AsRef<Path>) -> Vec<u8> {
+    fs::read(test_image).unwrap()
+}
+
+pub fn open_fs(test_image: &str) -> TestFs {
+    let device = MemoryBlockDevice::try_new(2048, load_copy_of_image(test_image)).unwrap();
+    Iso9660Fs::try_new(device).unwrap()
+}
+
+/// Walks `path`, relative to the root and separated by `/`.
+pub fn lookup(fs: &mut TestFs, path: &str) -> (FsNodeId, FsNodeKind) {
+    path.split('/')
+        .fold((fs.root(), FsNodeKind::Directory), |(dir, _), name| {
+            fs.lookup(dir, name)
+                .unwrap_or_else(|e| panic!("{name} in {path}: {e}"))
+        })
+}
+
+pub fn read_file(fs: &mut TestFs, path: &str) -> Vec<u8> {
+    let (node, _) = lookup(fs, path);
+    let handle = fs.open(node).unwrap();
+    let mut data = Vec::new();
+    let mut buf = [0; 1000];
+    loop {
+        match fs.read(handle, &mut buf, data.len()) {
+            Ok(read) => data.extend_from_slice(&buf[..read]),
+            Err(ReadError::EndOfFile) => break,
+            Err(e) => panic!("{path}: {e}"),
+        }
+    }
+    fs.close(handle).unwrap();
+    data
+}
+
+pub fn numbers() -> Vec<u8> {
+    (0..10000)
+        .flat_map(|i| format!("{i}\n").into_bytes())
+        .collect()
+}
+
+pub fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names
+}
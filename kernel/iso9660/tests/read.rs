@@ -0,0 +1,322 @@
+use kernel_device::block::MemoryBlockDevice;
+use kernel_iso9660::Iso9660Fs;
+use kernel_vfs::fs::{FileSystem, FsNodeKind};
+use kernel_vfs::path::OwnedPath;
+use kernel_vfs::{CreateError, OpenError, ReadError, ReadlinkError, Stat, UnlinkError, WriteError};
+
+use crate::common::{PLAIN, ROCK_RIDGE, lookup, numbers, open_fs, read_file, sorted};
+
+mod common;
+
+#[test]
+fn test_volume_descriptor() {
+    for image in [PLAIN, ROCK_RIDGE] {
+        let fs = open_fs(image);
+        assert_eq!(b"MUFFIN", fs.volume().volume_id(), "{image}");
+        assert_eq!(2048, fs.volume().block_size(), "{image}");
+        assert!(fs.volume().size() <= fs.device().data().len(), "{image}");
+    }
+}
+
+#[test]
+fn test_list_root() {
+    let mut fs = open_fs(ROCK_RIDGE);
+    let names = fs.read_dir(fs.root()).unwrap();
+    assert_eq!(
+        vec![
+            "A long file name.txt",
+            "docs",
+            "empty",
+            "hello.txt",
+            "link",
+            "long",
+            "many",
+            "numbers.txt"
+        ],
+        sorted(names)
+    );
+
+    let (empty, kind) = lookup(&mut fs, "empty");
+    assert_eq!(FsNodeKind::Directory, kind);
+    assert!(fs.read_dir(empty).unwrap().is_empty());
+}
+
+#[test]
+fn test_list_root_without_rock_ridge() {
+    // names lose their version and are shown in lower case, like Linux
+    // does
+    let mut fs = open_fs(PLAIN);
+    let names = fs.read_dir(fs.root()).unwrap();
+    assert_eq!(
+        vec!["docs", "hello.txt", "many", "numbers.txt"],
+        sorted(names)
+    );
+}
+
+generate_tests!(
+    test_read_files:
+    PLAIN => test_read_files_plain,
+    ROCK_RIDGE => test_read_files_rock_ridge,
+);
+
+fn test_read_files(image: &str) {
+    let mut fs = open_fs(image);
+    assert_eq!(
+        b"Hello, World!\n",
+        read_file(&mut fs, "hello.txt").as_slice()
+    );
+    assert_eq!(
+        b"deep\n",
+        read_file(&mut fs, "docs/nested/deep.txt").as_slice()
+    );
+    assert_eq!(numbers(), read_file(&mut fs, "numbers.txt"));
+}
+
+#[test]
+fn test_long_names() {
+    let mut fs = open_fs(ROCK_RIDGE);
+    assert_eq!(
+        b"This file has a long name.\n",
+        read_file(&mut fs, "A long file name.txt").as_slice()
+    );
+
+    let name = format!("{}.txt", "a".repeat(200));
+    let (long, _) = lookup(&mut fs, "long");
+    assert_eq!(vec![name.clone()], fs.read_dir(long).unwrap());
+    assert_eq!(
+        b"long name\n",
+        read_file(&mut fs, &format!("long/{name}")).as_slice()
+    );
+}
+
+generate_tests!(
+    test_large_directory:
+    PLAIN => test_large_directory_plain,
+    ROCK_RIDGE => test_large_directory_rock_ridge,
+);
+
+fn test_large_directory(image: &str) {
+    let mut fs = open_fs(image);
+    let (many, _) = lookup(&mut fs, "many");
+    let names = sorted(fs.read_dir(many).unwrap());
+    let expected = (0..100)
+        .map(|i| format!("file_{i:03}.txt"))
+        .collect::<Vec<_>>();
+    assert_eq!(expected, names);
+    for i in [0, 42, 99] {
+        assert_eq!(
+            format!("file_{i}\n").as_bytes(),
+            read_file(&mut fs, &format!("many/file_{i:03}.txt"))
+        );
+    }
+}
+
+generate_tests!(
+    test_read_at_offsets:
+    PLAIN => test_read_at_offsets_plain,
+    ROCK_RIDGE => test_read_at_offsets_rock_ridge,
+);
+
+fn test_read_at_offsets(image: &str) {
+    let mut fs = open_fs(image);
+    let numbers = numbers();
+    let (node, _) = lookup(&mut fs, "numbers.txt");
+    let handle = fs.open(node).unwrap();
+
+    let mut buf = [0; 4099];
+    for offset in [0, 511, 2047, 4000, 20000] {
+        assert_eq!(Ok(buf.len()), fs.read(handle, &mut buf, offset));
+        assert_eq!(&numbers[offset..offset + buf.len()], buf.as_slice());
+    }
+
+    let last = numbers.len() - 10;
+    assert_eq!(Ok(10), fs.read(handle, &mut buf, last));
+    assert_eq!(&numbers[last..], &buf[..10]);
+    assert_eq!(
+        Err(ReadError::EndOfFile),
+        fs.read(handle, &mut buf, numbers.len())
+    );
+    fs.close(handle).unwrap();
+}
+
+#[test]
+fn test_symlinks() {
+    let mut fs = open_fs(ROCK_RIDGE);
+    for (path, target) in [
+        ("link", "hello.txt"),
+        ("docs/absolute", "/boot/hello.txt"),
+        ("docs/relative", "../hello.txt"),
+    ] {
+        let (node, kind) = lookup(&mut fs, path);
+        assert_eq!(FsNodeKind::Symlink, kind, "{path}");
+        assert_eq!(Ok(OwnedPath::new(target)), fs.readlink(node), "{path}");
+    }
+
+    let (hello, _) = lookup(&mut fs, "hello.txt");
+    assert_eq!(Err(ReadlinkError::NotSymlink), fs.readlink(hello));
+}
+
+#[test]
+fn test_stat() {
+    for (image, file_mode, dir_mode) in [
+        (PLAIN, 0o100_444, 0o040_555),
+        (ROCK_RIDGE, 0o100_644, 0o040_755),
+    ] {
+        let mut fs = open_fs(image);
+        let (node, _) = lookup(&mut fs, "numbers.txt");
+        let handle = fs.open(node).unwrap();
+        let mut stat = Stat::default();
+        fs.stat(handle, &mut stat).unwrap();
+        fs.close(handle).unwrap();
+
+        assert_eq!(numbers().len(), stat.size, "{image}");
+        assert_eq!(node.get(), stat.ino, "{image}");
+        assert_eq!(file_mode, stat.mode, "{image}");
+        assert_eq!(
+            (stat.size.next_multiple_of(2048) / 512) as u64,
+            stat.blocks,
+            "{image}"
+        );
+
+        let (docs, _) = lookup(&mut fs, "docs");
+        let handle = fs.open(docs).unwrap();
+        fs.stat(handle, &mut stat).unwrap();
+        assert_eq!(dir_mode, stat.mode, "{image}");
+        fs.close(handle).unwrap();
+    }
+}
+
+#[test]
+fn test_directory_ids_are_stable() {
+    // a directory is found both through its parent and as the root of a
+    // walk, and both must agree on its id
+    let mut fs = open_fs(ROCK_RIDGE);
+    let (docs, _) = lookup(&mut fs, "docs");
+    let (nested, _) = lookup(&mut fs, "docs/nested");
+    assert_eq!(
+        Ok((nested, FsNodeKind::Directory)),
+        fs.lookup(docs, "nested")
+    );
+    assert_eq!(vec!["deep.txt"], fs.read_dir(nested).unwrap());
+}
+
+#[test]
+fn test_errors() {
+    let mut fs = open_fs(ROCK_RIDGE);
+    let root = fs.root();
+    let (hello, _) = lookup(&mut fs, "hello.txt");
+    assert_eq!(Err(OpenError::NotDirectory), fs.lookup(hello, "x"));
+    assert_eq!(Err(OpenError::NotDirectory), fs.read_dir(hello));
+    assert_eq!(Err(OpenError::NotFound), fs.lookup(root, "HELLO.TXT"));
+
+    let (docs, _) = lookup(&mut fs, "docs");
+    let handle = fs.open(docs).unwrap();
+    assert_eq!(
+        Err(ReadError::NotReadable),
+        fs.read(handle, &mut [0; 16], 0)
+    );
+    fs.close(handle).unwrap();
+}
+
+#[test]
+fn test_read_only() {
+    let mut fs = open_fs(ROCK_RIDGE);
+    let root = fs.root();
+    assert_eq!(
+        Err(CreateError::ReadOnly),
+        fs.create(root, "new", FsNodeKind::File)
+    );
+    assert_eq!(Err(UnlinkError::ReadOnly), fs.unlink(root, "hello.txt"));
+    assert_eq!(Err(UnlinkError::ReadOnly), fs.rmdir(root, "empty"));
+
+    let (hello, _) = lookup(&mut fs, "hello.txt");
+    let handle = fs.open(hello).unwrap();
+    assert_eq!(Err(WriteError::NotWritable), fs.write(handle, b"x", 0));
+    fs.close(handle).unwrap();
+}
+
+/// A directory record with the extent `extent`, like xorriso writes them.
+fn record(name: &[u8], extent: u32, size: u32, flags: u8, system_use: &[u8]) -> Vec<u8> {
+    let padding = (name.len() + 1) % 2;
+    let len = 33 + name.len() + padding + system_use.len();
+    let mut bytes = vec![0; len];
+    bytes[0] = len as u8;
+    bytes[2..6].copy_from_slice(&extent.to_le_bytes());
+    bytes[10..14].copy_from_slice(&size.to_le_bytes());
+    bytes[25] = flags;
+    bytes[32] = name.len() as u8;
+    bytes[33..33 + name.len()].copy_from_slice(name);
+    bytes[len - system_use.len()..].copy_from_slice(system_use);
+    bytes
+}
+
+/// A Rock Ridge `CL` entry that links to the directory at `block`.
+fn child_link(block: u32) -> Vec<u8> {
+    let mut entry = b"CL\x0c\x01".to_vec();
+    entry.extend(block.to_le_bytes());
+    entry.extend(block.to_be_bytes());
+    entry
+}
+
+/// A Rock Ridge image with the root directory in block 18, whose `.`
+/// claims `root_size` bytes, and a single entry `link` that is a child link
+/// to block 19, where the directory `target` starts.
+fn crafted_image(root_size: u32, target: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 2048;
+    let mut image = vec![0; 20 * BLOCK];
+
+    let pvd = &mut image[16 * BLOCK..17 * BLOCK];
+    pvd[..7].copy_from_slice(b"\x01CD001\x01");
+    pvd[80..84].copy_from_slice(&20_u32.to_le_bytes());
+    pvd[128..130].copy_from_slice(&(BLOCK as u16).to_le_bytes());
+    pvd[156..190].copy_from_slice(&record(&[0], 18, BLOCK as u32, 2, &[]));
+    image[17 * BLOCK..17 * BLOCK + 7].copy_from_slice(b"\xffCD001\x01");
+
+    let root = [
+        record(&[0], 18, root_size, 2, b"SP\x07\x01\xbe\xef\x00"),
+        record(&[1], 18, BLOCK as u32, 2, &[]),
+        record(b"LINK;1", 0, 0, 0, &child_link(19)),
+    ]
+    .concat();
+    image[18 * BLOCK..][..root.len()].copy_from_slice(&root);
+    image[19 * BLOCK..][..target.len()].copy_from_slice(target);
+    image
+}
+
+fn open_crafted(image: Vec<u8>) -> Iso9660Fs<MemoryBlockDevice<Vec<u8>>> {
+    Iso9660Fs::try_new(MemoryBlockDevice::try_new(2048, image).unwrap()).unwrap()
+}
+
+#[test]
+fn test_child_link() {
+    let target = [
+        record(&[0], 19, 2048, 2, &[]),
+        record(&[1], 18, 2048, 2, &[]),
+    ]
+    .concat();
+    let mut fs = open_crafted(crafted_image(2048, &target));
+    let root = fs.root();
+    let (link, kind) = fs.lookup(root, "link").unwrap();
+    assert_eq!(FsNodeKind::Directory, kind);
+    assert_eq!(Vec::<String>::new(), fs.read_dir(link).unwrap());
+}
+
+#[test]
+fn test_child_link_loop() {
+    // the `.` of the target links back to itself
+    let target = [
+        record(&[0], 19, 2048, 2, &child_link(19)),
+        record(&[1], 18, 2048, 2, &[]),
+    ]
+    .concat();
+    let mut fs = open_crafted(crafted_image(2048, &target));
+    let root = fs.root();
+    assert_eq!(Err(OpenError::NotFound), fs.lookup(root, "link"));
+}
+
+#[test]
+fn test_directory_larger_than_device() {
+    let mut fs = open_crafted(crafted_image(u32::MAX, &[]));
+    let root = fs.root();
+    assert_eq!(Err(OpenError::NotFound), fs.read_dir(root));
+}
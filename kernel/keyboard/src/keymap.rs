@@ -0,0 +1,369 @@
+use bitflags::bitflags;
+use kernel_abi::KeyCode;
+
+use crate::KeyEvent;
+
+bitflags! {
+    /// The modifier keys that are held down and the lock keys that are on.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Modifiers: u16 {
+        const LEFT_SHIFT = 1 << 0;
+        const RIGHT_SHIFT = 1 << 1;
+        const LEFT_CTRL = 1 << 2;
+        const RIGHT_CTRL = 1 << 3;
+        const LEFT_ALT = 1 << 4;
+        const RIGHT_ALT = 1 << 5;
+        const CAPS_LOCK = 1 << 6;
+        const NUM_LOCK = 1 << 7;
+    }
+}
+
+impl Modifiers {
+    const SHIFT: Self = Self::LEFT_SHIFT.union(Self::RIGHT_SHIFT);
+    const CTRL: Self = Self::LEFT_CTRL.union(Self::RIGHT_CTRL);
+    const ALT: Self = Self::LEFT_ALT.union(Self::RIGHT_ALT);
+
+    #[must_use]
+    pub fn shift(self) -> bool {
+        self.intersects(Self::SHIFT)
+    }
+
+    #[must_use]
+    pub fn ctrl(self) -> bool {
+        self.intersects(Self::CTRL)
+    }
+
+    #[must_use]
+    pub fn alt(self) -> bool {
+        self.intersects(Self::ALT)
+    }
+}
+
+const ESC: u8 = 0x1b;
+
+/// Translates key events into the bytes that a terminal expects, for a US
+/// keyboard layout. Cursor and function keys become the escape sequences of
+/// a VT220/xterm, Alt prefixes a character with ESC.
+#[derive(Debug)]
+pub struct Keymap {
+    modifiers: Modifiers,
+}
+
+impl Keymap {
+    /// Starts with num lock on, like most firmware leaves it.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            modifiers: Modifiers::NUM_LOCK,
+        }
+    }
+
+    #[must_use]
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Updates the modifiers with `event` and passes the bytes that it types
+    /// to `out`, if any. Releases and keys without a meaning on a terminal
+    /// type nothing.
+    pub fn translate(&mut self, event: KeyEvent, mut out: impl FnMut(&[u8])) {
+        let key = event.key;
+        if let Some(modifier) = modifier(key) {
+            self.modifiers.set(modifier, event.pressed);
+            return;
+        }
+        if !event.pressed {
+            return;
+        }
+        match key {
+            KeyCode::CapsLock => self.modifiers.toggle(Modifiers::CAPS_LOCK),
+            KeyCode::NumLock => self.modifiers.toggle(Modifiers::NUM_LOCK),
+            _ => {
+                if let Some(sequence) = self.sequence(key) {
+                    out(sequence);
+                } else if let Some(c) = self.character(key) {
+                    if self.modifiers.alt() {
+                        out(&[ESC, c]);
+                    } else {
+                        out(&[c]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn character(&self, key: KeyCode) -> Option<u8> {
+        let key = match key {
+            KeyCode::KpEnter => KeyCode::Enter,
+            KeyCode::KpSlash => KeyCode::Slash,
+            _ => key,
+        };
+        let (plain, shifted) = match key {
+            KeyCode::Kp0 => (b'0', b'0'),
+            KeyCode::Kp1 => (b'1', b'1'),
+            KeyCode::Kp2 => (b'2', b'2'),
+            KeyCode::Kp3 => (b'3', b'3'),
+            KeyCode::Kp4 => (b'4', b'4'),
+            KeyCode::Kp5 => (b'5', b'5'),
+            KeyCode::Kp6 => (b'6', b'6'),
+            KeyCode::Kp7 => (b'7', b'7'),
+            KeyCode::Kp8 => (b'8', b'8'),
+            KeyCode::Kp9 => (b'9', b'9'),
+            KeyCode::KpDot => (b'.', b'.'),
+            KeyCode::KpAsterisk => (b'*', b'*'),
+            KeyCode::KpMinus => (b'-', b'-'),
+            KeyCode::KpPlus => (b'+', b'+'),
+            _ => us_layout(key)?,
+        };
+        let mut c = if self.modifiers.shift() {
+            shifted
+        } else {
+            plain
+        };
+        if plain.is_ascii_lowercase() && self.modifiers.contains(Modifiers::CAPS_LOCK) {
+            c = if c.is_ascii_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            };
+        }
+        if self.modifiers.ctrl() {
+            c = match c {
+                b'a'..=b'z' | b'A'..=b'Z' => c & 0x1f,
+                b'[' | b'{' => 0x1b,
+                b'\\' | b'|' => 0x1c,
+                b']' | b'}' => 0x1d,
+                b' ' | b'@' | b'2' => 0,
+                b'?' | b'/' => 0x7f,
+                _ => c,
+            };
+        }
+        Some(c)
+    }
+
+    /// Keys that type escape sequences, and the keypad while num lock is off.
+    fn sequence(&self, key: KeyCode) -> Option<&'static [u8]> {
+        let key = if self.modifiers.contains(Modifiers::NUM_LOCK) {
+            key
+        } else {
+            match key {
+                KeyCode::Kp0 => KeyCode::Insert,
+                KeyCode::Kp1 => KeyCode::End,
+                KeyCode::Kp2 => KeyCode::Down,
+                KeyCode::Kp3 => KeyCode::PageDown,
+                KeyCode::Kp4 => KeyCode::Left,
+                KeyCode::Kp6 => KeyCode::Right,
+                KeyCode::Kp7 => KeyCode::Home,
+                KeyCode::Kp8 => KeyCode::Up,
+                KeyCode::Kp9 => KeyCode::PageUp,
+                KeyCode::KpDot => KeyCode::Delete,
+                KeyCode::Kp5 => return Some(b""),
+                _ => key,
+            }
+        };
+        Some(match key {
+            KeyCode::Up => b"\x1b[A",
+            KeyCode::Down => b"\x1b[B",
+            KeyCode::Right => b"\x1b[C",
+            KeyCode::Left => b"\x1b[D",
+            KeyCode::Home => b"\x1b[H",
+            KeyCode::End => b"\x1b[F",
+            KeyCode::Insert => b"\x1b[2~",
+            KeyCode::Delete => b"\x1b[3~",
+            KeyCode::PageUp => b"\x1b[5~",
+            KeyCode::PageDown => b"\x1b[6~",
+            KeyCode::F1 => b"\x1bOP",
+            KeyCode::F2 => b"\x1bOQ",
+            KeyCode::F3 => b"\x1bOR",
+            KeyCode::F4 => b"\x1bOS",
+            KeyCode::F5 => b"\x1b[15~",
+            KeyCode::F6 => b"\x1b[17~",
+            KeyCode::F7 => b"\x1b[18~",
+            KeyCode::F8 => b"\x1b[19~",
+            KeyCode::F9 => b"\x1b[20~",
+            KeyCode::F10 => b"\x1b[21~",
+            KeyCode::F11 => b"\x1b[23~",
+            KeyCode::F12 => b"\x1b[24~",
+            _ => return None,
+        })
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn modifier(key: KeyCode) -> Option<Modifiers> {
+    Some(match key {
+        KeyCode::LeftShift => Modifiers::LEFT_SHIFT,
+        KeyCode::RightShift => Modifiers::RIGHT_SHIFT,
+        KeyCode::LeftCtrl => Modifiers::LEFT_CTRL,
+        KeyCode::RightCtrl => Modifiers::RIGHT_CTRL,
+        KeyCode::LeftAlt => Modifiers::LEFT_ALT,
+        KeyCode::RightAlt => Modifiers::RIGHT_ALT,
+        _ => return None,
+    })
+}
+
+/// The character of a key on a US keyboard, without and with shift.
+fn us_layout(key: KeyCode) -> Option<(u8, u8)> {
+    Some(match key {
+        KeyCode::Esc => (ESC, ESC),
+        KeyCode::Num1 => (b'1', b'!'),
+        KeyCode::Num2 => (b'2', b'@'),
+        KeyCode::Num3 => (b'3', b'#'),
+        KeyCode::Num4 => (b'4', b'$'),
+        KeyCode::Num5 => (b'5', b'%'),
+        KeyCode::Num6 => (b'6', b'^'),
+        KeyCode::Num7 => (b'7', b'&'),
+        KeyCode::Num8 => (b'8', b'*'),
+        KeyCode::Num9 => (b'9', b'('),
+        KeyCode::Num0 => (b'0', b')'),
+        KeyCode::Minus => (b'-', b'_'),
+        KeyCode::Equal => (b'=', b'+'),
+        KeyCode::Backspace => (0x7f, 0x7f),
+        KeyCode::Tab => (b'\t', b'\t'),
+        KeyCode::Q => (b'q', b'Q'),
+        KeyCode::W => (b'w', b'W'),
+        KeyCode::E => (b'e', b'E'),
+        KeyCode::R => (b'r', b'R'),
+        KeyCode::T => (b't', b'T'),
+        KeyCode::Y => (b'y', b'Y'),
+        KeyCode::U => (b'u', b'U'),
+        KeyCode::I => (b'i', b'I'),
+        KeyCode::O => (b'o', b'O'),
+        KeyCode::P => (b'p', b'P'),
+        KeyCode::LeftBrace => (b'[', b'{'),
+        KeyCode::RightBrace => (b']', b'}'),
+        KeyCode::Enter => (b'\r', b'\r'),
+        KeyCode::A => (b'a', b'A'),
+        KeyCode::S => (b's', b'S'),
+        KeyCode::D => (b'd', b'D'),
+        KeyCode::F => (b'f', b'F'),
+        KeyCode::G => (b'g', b'G'),
+        KeyCode::H => (b'h', b'H'),
+        KeyCode::J => (b'j', b'J'),
+        KeyCode::K => (b'k', b'K'),
+        KeyCode::L => (b'l', b'L'),
+        KeyCode::Semicolon => (b';', b':'),
+        KeyCode::Apostrophe => (b'\'', b'"'),
+        KeyCode::Grave => (b'`', b'~'),
+        KeyCode::Backslash | KeyCode::Key102nd => (b'\\', b'|'),
+        KeyCode::Z => (b'z', b'Z'),
+        KeyCode::X => (b'x', b'X'),
+        KeyCode::C => (b'c', b'C'),
+        KeyCode::V => (b'v', b'V'),
+        KeyCode::B => (b'b', b'B'),
+        KeyCode::N => (b'n', b'N'),
+        KeyCode::M => (b'm', b'M'),
+        KeyCode::Comma => (b',', b'<'),
+        KeyCode::Dot => (b'.', b'>'),
+        KeyCode::Slash => (b'/', b'?'),
+        KeyCode::Space => (b' ', b' '),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// Feeds `events` in order and collects what they type.
+    fn typed(keymap: &mut Keymap, events: &[(KeyCode, bool)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for &(key, pressed) in events {
+            keymap.translate(KeyEvent::new(key, pressed), |b| {
+                bytes.extend_from_slice(b);
+            });
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_characters() {
+        let mut keymap = Keymap::new();
+        let bytes = typed(
+            &mut keymap,
+            &[
+                (KeyCode::H, true),
+                (KeyCode::H, false),
+                (KeyCode::LeftShift, true),
+                (KeyCode::I, true),
+                (KeyCode::Num1, true),
+                (KeyCode::LeftShift, false),
+                (KeyCode::Enter, true),
+            ],
+        );
+        assert_eq!(bytes, b"hI!\r");
+        assert_eq!(keymap.modifiers(), Modifiers::NUM_LOCK);
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut keymap = Keymap::new();
+        let bytes = typed(
+            &mut keymap,
+            &[
+                (KeyCode::CapsLock, true),
+                (KeyCode::CapsLock, false),
+                (KeyCode::A, true),
+                (KeyCode::Num1, true),
+                (KeyCode::RightShift, true),
+                (KeyCode::A, true),
+            ],
+        );
+        assert_eq!(bytes, b"A1a", "caps lock only affects letters");
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let mut keymap = Keymap::new();
+        let bytes = typed(
+            &mut keymap,
+            &[
+                (KeyCode::RightCtrl, true),
+                (KeyCode::C, true),
+                (KeyCode::Backslash, true),
+                (KeyCode::Space, true),
+                (KeyCode::RightCtrl, false),
+                (KeyCode::C, true),
+            ],
+        );
+        assert_eq!(bytes, b"\x03\x1c\x00c");
+    }
+
+    #[test]
+    fn test_alt_prefixes_escape() {
+        let mut keymap = Keymap::new();
+        let bytes = typed(&mut keymap, &[(KeyCode::LeftAlt, true), (KeyCode::X, true)]);
+        assert_eq!(bytes, b"\x1bx");
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let mut keymap = Keymap::new();
+        let bytes = typed(
+            &mut keymap,
+            &[
+                (KeyCode::Up, true),
+                (KeyCode::Delete, true),
+                (KeyCode::F1, true),
+                (KeyCode::F12, true),
+            ],
+        );
+        assert_eq!(bytes, b"\x1b[A\x1b[3~\x1bOP\x1b[24~");
+    }
+
+    #[test]
+    fn test_keypad_num_lock() {
+        let mut keymap = Keymap::new();
+        let keys = [(KeyCode::Kp8, true), (KeyCode::KpEnter, true)];
+        assert_eq!(typed(&mut keymap, &keys), b"8\r");
+        typed(&mut keymap, &[(KeyCode::NumLock, true)]);
+        assert_eq!(typed(&mut keymap, &keys), b"\x1b[A\r");
+    }
+}
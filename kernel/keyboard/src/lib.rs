@@ -0,0 +1,43 @@
+//! Keyboard input, independent of the device that it arrives from.
+//!
+//! A driver feeds the bytes that a PS/2 keyboard sends to a
+//! [`ScancodeDecoder`], which turns them into [`KeyEvent`]s. A [`Keymap`]
+//! tracks the modifiers across events and translates key presses into the
+//! bytes that a terminal expects, like a US keyboard layout does.
+
+#![no_std]
+
+#[cfg(test)]
+extern crate alloc;
+
+mod keymap;
+mod scancode;
+
+use kernel_abi::{KEY_PRESSED, KEY_RELEASED, KeyCode};
+pub use keymap::*;
+pub use scancode::*;
+
+/// A key that was pressed or released.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeyEvent {
+    pub key: KeyCode,
+    pub pressed: bool,
+}
+
+impl KeyEvent {
+    #[must_use]
+    pub const fn new(key: KeyCode, pressed: bool) -> Self {
+        Self { key, pressed }
+    }
+
+    /// [`KEY_PRESSED`] or [`KEY_RELEASED`], the value of the event on an
+    /// input device.
+    #[must_use]
+    pub const fn value(self) -> i32 {
+        if self.pressed {
+            KEY_PRESSED
+        } else {
+            KEY_RELEASED
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use kernel_abi::KeyCode;
+
+use crate::KeyEvent;
+
+/// The two scancode sets that a PS/2 keyboard speaks in practice. Set 2 is
+/// what keyboards send, set 1 is what an i8042 controller makes of it while
+/// it translates.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ScancodeSet {
+    One,
+    Two,
+}
+
+/// Prefix of the extended keys in both sets.
+const EXTENDED: u8 = 0xe0;
+/// Prefix of the pause key in both sets, which has no break code.
+const PAUSE: u8 = 0xe1;
+/// Prefix of a break code in set 2.
+const RELEASE: u8 = 0xf0;
+/// The bytes that follow [`PAUSE`] in set 1.
+const PAUSE_LEN: u8 = 5;
+
+/// Turns the bytes of a scancode set into key events.
+#[derive(Debug)]
+pub struct ScancodeDecoder {
+    set: ScancodeSet,
+    extended: bool,
+    release: bool,
+    pause_remaining: u8,
+}
+
+impl ScancodeDecoder {
+    #[must_use]
+    pub const fn new(set: ScancodeSet) -> Self {
+        Self {
+            set,
+            extended: false,
+            release: false,
+            pause_remaining: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn set(&self) -> ScancodeSet {
+        self.set
+    }
+
+    /// Feeds one byte from the keyboard and returns the event that it
+    /// completes, if any. Unknown keys and the fake shifts that some
+    /// keyboards wrap around extended keys are dropped.
+    pub fn feed(&mut self, byte: u8) -> Option<KeyEvent> {
+        let byte = match self.set {
+            ScancodeSet::One => byte,
+            ScancodeSet::Two => {
+                if byte == RELEASE {
+                    self.release = true;
+                    return None;
+                }
+                // Set 2 maps onto set 1 byte by byte, with the break prefix
+                // becoming the top bit, which is how an i8042 translates.
+                let release = core::mem::take(&mut self.release);
+                match byte {
+                    EXTENDED | PAUSE => byte,
+                    _ => set2_to_set1(byte)? | if release { 0x80 } else { 0 },
+                }
+            }
+        };
+        self.feed_set1(byte)
+    }
+
+    fn feed_set1(&mut self, byte: u8) -> Option<KeyEvent> {
+        if self.pause_remaining > 0 {
+            self.pause_remaining -= 1;
+            return (self.pause_remaining == 0).then_some(KeyEvent::new(KeyCode::Pause, true));
+        }
+        match byte {
+            EXTENDED => {
+                self.extended = true;
+                None
+            }
+            PAUSE => {
+                self.pause_remaining = PAUSE_LEN;
+                None
+            }
+            _ => {
+                let pressed = byte & 0x80 == 0;
+                let code = byte & 0x7f;
+                let key = if core::mem::take(&mut self.extended) {
+                    extended_key(code)?
+                } else if code <= KeyCode::F12 as u8 {
+                    // below F12, set 1 make codes are key codes
+                    KeyCode::from_number(u16::from(code))?
+                } else {
+                    return None;
+                };
+                Some(KeyEvent::new(key, pressed))
+            }
+        }
+    }
+}
+
+/// The keys behind an [`EXTENDED`] prefix in set 1.
+fn extended_key(code: u8) -> Option<KeyCode> {
+    Some(match code {
+        0x1c => KeyCode::KpEnter,
+        0x1d => KeyCode::RightCtrl,
+        0x35 => KeyCode::KpSlash,
+        0x37 => KeyCode::SysRq,
+        0x38 => KeyCode::RightAlt,
+        0x47 => KeyCode::Home,
+        0x48 => KeyCode::Up,
+        0x49 => KeyCode::PageUp,
+        0x4b => KeyCode::Left,
+        0x4d => KeyCode::Right,
+        0x4f => KeyCode::End,
+        0x50 => KeyCode::Down,
+        0x51 => KeyCode::PageDown,
+        0x52 => KeyCode::Insert,
+        0x53 => KeyCode::Delete,
+        0x5b => KeyCode::LeftMeta,
+        0x5c => KeyCode::RightMeta,
+        0x5d => KeyCode::Compose,
+        // 0x2a and 0x36 are the fake shifts
+        _ => return None,
+    })
+}
+
+/// Translates a set 2 make code into the set 1 make code of the same key.
+fn set2_to_set1(code: u8) -> Option<u8> {
+    Some(match code {
+        0x01 => 0x43, // F9
+        0x03 => 0x3f, // F5
+        0x04 => 0x3d, // F3
+        0x05 => 0x3b, // F1
+        0x06 => 0x3c, // F2
+        0x07 => 0x58, // F12
+        0x09 => 0x44, // F10
+        0x0a => 0x42, // F8
+        0x0b => 0x40, // F6
+        0x0c => 0x3e, // F4
+        0x0d => 0x0f, // Tab
+        0x0e => 0x29, // `
+        0x11 => 0x38, // left alt
+        0x12 => 0x2a, // left shift
+        0x14 => 0x1d, // left ctrl
+        0x15 => 0x10, // Q
+        0x16 => 0x02, // 1
+        0x1a => 0x2c, // Z
+        0x1b => 0x1f, // S
+        0x1c => 0x1e, // A
+        0x1d => 0x11, // W
+        0x1e => 0x03, // 2
+        0x1f => 0x5b, // left meta, extended only
+        0x21 => 0x2e, // C
+        0x22 => 0x2d, // X
+        0x23 => 0x20, // D
+        0x24 => 0x12, // E
+        0x25 => 0x05, // 4
+        0x26 => 0x04, // 3
+        0x27 => 0x5c, // right meta, extended only
+        0x29 => 0x39, // space
+        0x2a => 0x2f, // V
+        0x2b => 0x21, // F
+        0x2c => 0x14, // T
+        0x2d => 0x13, // R
+        0x2e => 0x06, // 5
+        0x2f => 0x5d, // compose, extended only
+        0x31 => 0x31, // N
+        0x32 => 0x30, // B
+        0x33 => 0x23, // H
+        0x34 => 0x22, // G
+        0x35 => 0x15, // Y
+        0x36 => 0x07, // 6
+        0x3a => 0x32, // M
+        0x3b => 0x24, // J
+        0x3c => 0x16, // U
+        0x3d => 0x08, // 7
+        0x3e => 0x09, // 8
+        0x41 => 0x33, // ,
+        0x42 => 0x25, // K
+        0x43 => 0x17, // I
+        0x44 => 0x18, // O
+        0x45 => 0x0b, // 0
+        0x46 => 0x0a, // 9
+        0x49 => 0x34, // .
+        0x4a => 0x35, // /
+        0x4b => 0x26, // L
+        0x4c => 0x27, // ;
+        0x4d => 0x19, // P
+        0x4e => 0x0c, // -
+        0x52 => 0x28, // '
+        0x54 => 0x1a, // [
+        0x55 => 0x0d, // =
+        0x58 => 0x3a, // caps lock
+        0x59 => 0x36, // right shift
+        0x5a => 0x1c, // enter
+        0x5b => 0x1b, // ]
+        0x5d => 0x2b, // backslash
+        0x61 => 0x56, // 102nd key
+        0x66 => 0x0e, // backspace
+        0x69 => 0x4f, // keypad 1
+        0x6b => 0x4b, // keypad 4
+        0x6c => 0x47, // keypad 7
+        0x70 => 0x52, // keypad 0
+        0x71 => 0x53, // keypad .
+        0x72 => 0x50, // keypad 2
+        0x73 => 0x4c, // keypad 5
+        0x74 => 0x4d, // keypad 6
+        0x75 => 0x48, // keypad 8
+        0x76 => 0x01, // escape
+        0x77 => 0x45, // num lock
+        0x78 => 0x57, // F11
+        0x79 => 0x4e, // keypad +
+        0x7a => 0x51, // keypad 3
+        0x7b => 0x4a, // keypad -
+        0x7c => 0x37, // keypad *
+        0x7d => 0x49, // keypad 9
+        0x7e => 0x46, // scroll lock
+        0x83 => 0x41, // F7
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn decode(set: ScancodeSet, bytes: &[u8]) -> Vec<KeyEvent> {
+        let mut decoder = ScancodeDecoder::new(set);
+        bytes.iter().filter_map(|&b| decoder.feed(b)).collect()
+    }
+
+    fn press(key: KeyCode) -> KeyEvent {
+        KeyEvent::new(key, true)
+    }
+
+    fn release(key: KeyCode) -> KeyEvent {
+        KeyEvent::new(key, false)
+    }
+
+    #[test]
+    fn test_set1() {
+        assert_eq!(
+            decode(ScancodeSet::One, &[0x1e, 0x9e, 0x2a, 0x10, 0x90, 0xaa]),
+            [
+                press(KeyCode::A),
+                release(KeyCode::A),
+                press(KeyCode::LeftShift),
+                press(KeyCode::Q),
+                release(KeyCode::Q),
+                release(KeyCode::LeftShift),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set1_extended() {
+        assert_eq!(
+            decode(ScancodeSet::One, &[0xe0, 0x48, 0xe0, 0xc8, 0xe0, 0x1d]),
+            [
+                press(KeyCode::Up),
+                release(KeyCode::Up),
+                press(KeyCode::RightCtrl)
+            ]
+        );
+        assert_eq!(
+            decode(
+                ScancodeSet::One,
+                &[0xe0, 0x2a, 0xe0, 0x53, 0xe0, 0xd3, 0xe0, 0xaa]
+            ),
+            [press(KeyCode::Delete), release(KeyCode::Delete)],
+            "fake shifts around extended keys are dropped"
+        );
+    }
+
+    #[test]
+    fn test_set2() {
+        assert_eq!(
+            decode(ScancodeSet::Two, &[0x1c, 0xf0, 0x1c, 0x83, 0xf0, 0x83]),
+            [
+                press(KeyCode::A),
+                release(KeyCode::A),
+                press(KeyCode::F7),
+                release(KeyCode::F7),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set2_extended() {
+        assert_eq!(
+            decode(
+                ScancodeSet::Two,
+                &[0xe0, 0x75, 0xe0, 0xf0, 0x75, 0xe0, 0x4a, 0xe0, 0x1f]
+            ),
+            [
+                press(KeyCode::Up),
+                release(KeyCode::Up),
+                press(KeyCode::KpSlash),
+                press(KeyCode::LeftMeta),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause() {
+        assert_eq!(
+            decode(
+                ScancodeSet::One,
+                &[0xe1, 0x1d, 0x45, 0xe1, 0x9d, 0xc5, 0x1e]
+            ),
+            [press(KeyCode::Pause), press(KeyCode::A)]
+        );
+        assert_eq!(
+            decode(
+                ScancodeSet::Two,
+                &[0xe1, 0x14, 0x77, 0xe1, 0xf0, 0x14, 0xf0, 0x77, 0x1c]
+            ),
+            [press(KeyCode::Pause), press(KeyCode::A)]
+        );
+    }
+
+    #[test]
+    fn test_unknown_codes_are_dropped() {
+        assert_eq!(
+            decode(ScancodeSet::Two, &[0x02, 0xf0, 0x02, 0x1c]),
+            [press(KeyCode::A)]
+        );
+        assert_eq!(
+            decode(ScancodeSet::One, &[0x54, 0x60, 0xe0, 0x10, 0x1e]),
+            [press(KeyCode::A)]
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! Mouse input, independent of the device that it arrives from.
+//!
+//! A driver feeds the bytes that a PS/2 mouse sends to a [`PacketDecoder`],
+//! which assembles them into [`Packet`]s. A [`Mouse`] remembers which
+//! buttons are held and turns packets into the events of an input device:
+//! relative motion, the wheel and button presses and releases.
+
+#![no_std]
+
+#[cfg(test)]
+extern crate alloc;
+
+use bitflags::bitflags;
+use kernel_abi::{EventType, KEY_PRESSED, KEY_RELEASED, KeyCode, RelativeAxis};
+
+/// Bits of the first byte of every packet.
+const BUTTONS: u8 = 0b111;
+const ALWAYS_SET: u8 = 1 << 3;
+const X_SIGN: u8 = 1 << 4;
+const Y_SIGN: u8 = 1 << 5;
+const X_OVERFLOW: u8 = 1 << 6;
+const Y_OVERFLOW: u8 = 1 << 7;
+
+/// The extra buttons in the fourth byte of [`Protocol::FiveButton`].
+const FOURTH_BUTTON: u8 = 1 << 4;
+const FIFTH_BUTTON: u8 = 1 << 5;
+
+/// The protocols of PS/2 mice. A mouse starts out with the standard one
+/// and switches when it receives a sequence of sample rates that it knows,
+/// which shows in the id that it answers with afterwards.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Protocol {
+    /// Three buttons in packets of three bytes.
+    Standard,
+    /// IntelliMouse, with a scroll wheel in a fourth byte.
+    Wheel,
+    /// IntelliMouse Explorer, with a fourth and fifth button next to the
+    /// scroll wheel.
+    FiveButton,
+}
+
+impl Protocol {
+    /// Switches a standard mouse to [`Protocol::Wheel`], if it has a wheel.
+    pub const WHEEL_SAMPLE_RATES: [u8; 3] = [200, 100, 80];
+    /// Switches a mouse with [`Protocol::Wheel`] to
+    /// [`Protocol::FiveButton`], if it has the buttons.
+    pub const FIVE_BUTTON_SAMPLE_RATES: [u8; 3] = [200, 200, 80];
+
+    /// The protocol of a mouse that answers with `id`.
+    #[must_use]
+    pub const fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Standard),
+            3 => Some(Self::Wheel),
+            4 => Some(Self::FiveButton),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn packet_size(self) -> usize {
+        match self {
+            Self::Standard => 3,
+            Self::Wheel | Self::FiveButton => 4,
+        }
+    }
+
+    /// The buttons that a mouse with this protocol reports.
+    #[must_use]
+    pub const fn buttons(self) -> &'static [KeyCode] {
+        match self {
+            Self::Standard | Self::Wheel => {
+                &[KeyCode::BtnLeft, KeyCode::BtnRight, KeyCode::BtnMiddle]
+            }
+            Self::FiveButton => &[
+                KeyCode::BtnLeft,
+                KeyCode::BtnRight,
+                KeyCode::BtnMiddle,
+                KeyCode::BtnSide,
+                KeyCode::BtnExtra,
+            ],
+        }
+    }
+
+    /// The axes that a mouse with this protocol reports motion on.
+    #[must_use]
+    pub const fn axes(self) -> &'static [RelativeAxis] {
+        match self {
+            Self::Standard => &[RelativeAxis::X, RelativeAxis::Y],
+            Self::Wheel | Self::FiveButton => {
+                &[RelativeAxis::X, RelativeAxis::Y, RelativeAxis::Wheel]
+            }
+        }
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub struct Buttons: u8 {
+        const LEFT = 1 << 0;
+        const RIGHT = 1 << 1;
+        const MIDDLE = 1 << 2;
+        const SIDE = 1 << 3;
+        const EXTRA = 1 << 4;
+    }
+}
+
+impl Buttons {
+    const CODES: [(Buttons, KeyCode); 5] = [
+        (Buttons::LEFT, KeyCode::BtnLeft),
+        (Buttons::RIGHT, KeyCode::BtnRight),
+        (Buttons::MIDDLE, KeyCode::BtnMiddle),
+        (Buttons::SIDE, KeyCode::BtnSide),
+        (Buttons::EXTRA, KeyCode::BtnExtra),
+    ];
+}
+
+/// What a mouse reports at once. Motion is in the directions of the screen,
+/// with `dy` growing downwards, and the wheel counts clicks away from the
+/// user, like the relative axes of an input device.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Packet {
+    /// The buttons that are held down.
+    pub buttons: Buttons,
+    pub dx: i16,
+    pub dy: i16,
+    pub wheel: i8,
+}
+
+/// Assembles the bytes that a PS/2 mouse sends into packets.
+#[derive(Debug)]
+pub struct PacketDecoder {
+    protocol: Protocol,
+    bytes: [u8; 4],
+    len: usize,
+}
+
+impl PacketDecoder {
+    #[must_use]
+    pub const fn new(protocol: Protocol) -> Self {
+        Self {
+            protocol,
+            bytes: [0; 4],
+            len: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// Feeds one byte from the mouse and returns the packet that it
+    /// completes, if any.
+    pub fn feed(&mut self, byte: u8) -> Option<Packet> {
+        // a byte that can't start a packet means that one was lost, and
+        // skipping until one that can finds the next packet again
+        if self.len == 0 && byte & ALWAYS_SET == 0 {
+            return None;
+        }
+        self.bytes[self.len] = byte;
+        self.len += 1;
+        if self.len < self.protocol.packet_size() {
+            return None;
+        }
+        self.len = 0;
+        Some(self.decode())
+    }
+
+    fn decode(&self) -> Packet {
+        let [flags, x, y, fourth] = self.bytes;
+        let mut buttons = Buttons::from_bits_truncate(flags & BUTTONS);
+        // an overflowed axis moved too far to tell where to
+        let dx = if flags & X_OVERFLOW == 0 {
+            nine_bits(x, flags & X_SIGN != 0)
+        } else {
+            0
+        };
+        let dy = if flags & Y_OVERFLOW == 0 {
+            nine_bits(y, flags & Y_SIGN != 0)
+        } else {
+            0
+        };
+        // The mouse counts the wheel towards the user, and the lowest four
+        // bits of the fourth byte are a signed number with the extra
+        // buttons.
+        let wheel = match self.protocol {
+            Protocol::Standard => 0,
+            Protocol::Wheel => fourth.cast_signed(),
+            Protocol::FiveButton => {
+                buttons.set(Buttons::SIDE, fourth & FOURTH_BUTTON != 0);
+                buttons.set(Buttons::EXTRA, fourth & FIFTH_BUTTON != 0);
+                (fourth << 4).cast_signed() >> 4
+            }
+        };
+        Packet {
+            buttons,
+            dx,
+            // the mouse counts upwards
+            dy: -dy,
+            wheel: wheel.saturating_neg(),
+        }
+    }
+}
+
+/// A 9 bit two's complement number from its lower eight bits and its sign.
+fn nine_bits(low: u8, negative: bool) -> i16 {
+    i16::from(low) - if negative { 0x100 } else { 0 }
+}
+
+/// A mouse as an input device, which reports the changes between packets.
+#[derive(Debug)]
+pub struct Mouse {
+    decoder: PacketDecoder,
+    buttons: Buttons,
+}
+
+impl Mouse {
+    #[must_use]
+    pub const fn new(protocol: Protocol) -> Self {
+        Self {
+            decoder: PacketDecoder::new(protocol),
+            buttons: Buttons::empty(),
+        }
+    }
+
+    #[must_use]
+    pub fn protocol(&self) -> Protocol {
+        self.decoder.protocol()
+    }
+
+    /// Feeds one byte from the mouse and passes the events of the packet
+    /// that it completes to `report`, as the type, code and value of each.
+    /// Returns whether there were any, so that the caller can end the batch.
+    pub fn feed(&mut self, byte: u8, mut report: impl FnMut(EventType, u16, i32)) -> bool {
+        let Some(packet) = self.decoder.feed(byte) else {
+            return false;
+        };
+        let mut reported = false;
+        let mut relative = |axis: RelativeAxis, value: i32| {
+            if value != 0 {
+                report(EventType::Relative, axis as u16, value);
+                reported = true;
+            }
+        };
+        relative(RelativeAxis::X, i32::from(packet.dx));
+        relative(RelativeAxis::Y, i32::from(packet.dy));
+        relative(RelativeAxis::Wheel, i32::from(packet.wheel));
+
+        let changed = self.buttons.symmetric_difference(packet.buttons);
+        for (button, key) in Buttons::CODES {
+            if changed.contains(button) {
+                let value = if packet.buttons.contains(button) {
+                    KEY_PRESSED
+                } else {
+                    KEY_RELEASED
+                };
+                report(EventType::Key, key.number(), value);
+                reported = true;
+            }
+        }
+        self.buttons = packet.buttons;
+        reported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn decode(protocol: Protocol, bytes: &[u8]) -> Vec<Packet> {
+        let mut decoder = PacketDecoder::new(protocol);
+        bytes
+            .iter()
+            .filter_map(|&byte| decoder.feed(byte))
+            .collect()
+    }
+
+    fn events(mouse: &mut Mouse, bytes: &[u8]) -> Vec<(EventType, u16, i32)> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            mouse.feed(byte, |kind, code, value| events.push((kind, code, value)));
+        }
+        events
+    }
+
+    #[test]
+    fn test_standard_packet() {
+        // left button, 5 to the right and 3 up
+        assert_eq!(
+            decode(Protocol::Standard, &[0x09, 5, 3]),
+            [Packet {
+                buttons: Buttons::LEFT,
+                dx: 5,
+                dy: -3,
+                wheel: 0,
+            }]
+        );
+        // 2 to the left and 256 down
+        assert_eq!(
+            decode(Protocol::Standard, &[0x38, 0xfe, 0x00]),
+            [Packet {
+                buttons: Buttons::empty(),
+                dx: -2,
+                dy: 256,
+                wheel: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_overflow_drops_motion() {
+        let packets = decode(Protocol::Standard, &[0x08 | X_OVERFLOW | 0x02, 0x10, 0x20]);
+        assert_eq!(
+            packets,
+            [Packet {
+                buttons: Buttons::RIGHT,
+                dx: 0,
+                dy: -0x20,
+                wheel: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resync_after_lost_byte() {
+        // the first byte of the first packet was lost
+        let packets = decode(Protocol::Standard, &[0x05, 0x07, 0x08, 1, 1]);
+        assert_eq!(packets.len(), 1);
+        assert_eq!((packets[0].dx, packets[0].dy), (1, -1));
+    }
+
+    #[test]
+    fn test_wheel() {
+        let packets = decode(Protocol::Wheel, &[0x08, 0, 0, 0xff, 0x08, 0, 0, 0x02]);
+        let wheel: Vec<_> = packets.iter().map(|p| p.wheel).collect();
+        assert_eq!(wheel, [1, -2], "the wheel counts away from the user");
+    }
+
+    #[test]
+    fn test_five_buttons() {
+        let packets = decode(Protocol::FiveButton, &[0x0c, 0, 0, FIFTH_BUTTON | 0x0f]);
+        assert_eq!(
+            packets,
+            [Packet {
+                buttons: Buttons::MIDDLE | Buttons::EXTRA,
+                dx: 0,
+                dy: 0,
+                wheel: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_mouse_reports_changes() {
+        let mut mouse = Mouse::new(Protocol::Wheel);
+        let left = KeyCode::BtnLeft.number();
+        assert_eq!(
+            events(&mut mouse, &[0x09, 4, 0, 0]),
+            [
+                (EventType::Relative, RelativeAxis::X as u16, 4),
+                (EventType::Key, left, KEY_PRESSED),
+            ]
+        );
+        assert!(
+            events(&mut mouse, &[0x09, 0, 0, 0]).is_empty(),
+            "a held button is reported once"
+        );
+        assert_eq!(
+            events(&mut mouse, &[0x08, 0, 0, 0xff]),
+            [
+                (EventType::Relative, RelativeAxis::Wheel as u16, 1),
+                (EventType::Key, left, KEY_RELEASED),
+            ]
+        );
+    }
+}
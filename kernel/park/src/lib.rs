@@ -383,6 +383,13 @@ impl<T: Send> Waker<T> {
         }
         .unpark()
     }
+
+    /// True once any clone has fired. A spent waker can be dropped by
+    /// whoever holds it, since waking it again does nothing.
+    #[must_use]
+    pub fn is_spent(&self) -> bool {
+        self.slot.load(Acquire).is_null()
+    }
 }
 
 impl<T> Clone for Waker<T> {
@@ -993,7 +1000,9 @@ mod tests {
 
         let waker = unpark_ticket.into_waker();
         let clone = waker.clone();
+        assert!(!clone.is_spent(), "waker spent before firing");
         assert_eq!(waker.wake(), Some(11), "first wake lost the value");
+        assert!(clone.is_spent(), "clone not spent after the original fired");
         assert_eq!(clone.wake(), None, "second clone fired again");
         assert_eq!(waker.wake(), None, "the original fired again");
     }
@@ -0,0 +1,118 @@
+use alloc::vec::Vec;
+
+/// A decoded base address register of a PCI function.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Bar {
+    /// The number of the register, or of the lower one for a 64-bit bar.
+    pub index: u8,
+    pub kind: BarKind,
+    pub address: u64,
+    pub prefetchable: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BarKind {
+    Io,
+    Memory32,
+    Memory64,
+}
+
+impl Bar {
+    /// Decodes the raw values of consecutive base address registers,
+    /// starting at register 0.
+    ///
+    /// Registers that are zero aren't implemented by the function and are
+    /// skipped, and a 64-bit bar consumes the register after it.
+    #[must_use]
+    pub fn decode(raw: &[u32]) -> Vec<Bar> {
+        let mut bars = Vec::new();
+        let mut index = 0;
+        while index < raw.len() {
+            let value = raw[index];
+            let bar_index = index as u8;
+            index += 1;
+            if value == 0 {
+                continue;
+            }
+
+            let bar = if value & 1 != 0 {
+                Bar {
+                    index: bar_index,
+                    kind: BarKind::Io,
+                    address: u64::from(value & !0b11),
+                    prefetchable: false,
+                }
+            } else {
+                let prefetchable = value & 0b1000 != 0;
+                let low = u64::from(value & !0b1111);
+                if (value >> 1) & 0b11 == 0b10 {
+                    let high = raw.get(index).copied().unwrap_or(0);
+                    index += 1;
+                    Bar {
+                        index: bar_index,
+                        kind: BarKind::Memory64,
+                        address: (u64::from(high) << 32) | low,
+                        prefetchable,
+                    }
+                } else {
+                    Bar {
+                        index: bar_index,
+                        kind: BarKind::Memory32,
+                        address: low,
+                        prefetchable,
+                    }
+                }
+            };
+            bars.push(bar);
+        }
+        bars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // an io bar, an unimplemented one, a 32-bit and a prefetchable
+        // 64-bit memory bar
+        let raw = [0xC041, 0, 0xFEBD_1000, 0xFE00_000C, 0x1, 0];
+        assert_eq!(
+            alloc::vec![
+                Bar {
+                    index: 0,
+                    kind: BarKind::Io,
+                    address: 0xC040,
+                    prefetchable: false,
+                },
+                Bar {
+                    index: 2,
+                    kind: BarKind::Memory32,
+                    address: 0xFEBD_1000,
+                    prefetchable: false,
+                },
+                Bar {
+                    index: 3,
+                    kind: BarKind::Memory64,
+                    address: 0x1_FE00_0000,
+                    prefetchable: true,
+                },
+            ],
+            Bar::decode(&raw)
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated_64_bit() {
+        assert_eq!(
+            alloc::vec![Bar {
+                index: 0,
+                kind: BarKind::Memory64,
+                address: 0x8000_0000,
+                prefetchable: false,
+            }],
+            Bar::decode(&[0x8000_0004])
+        );
+    }
+}
@@ -1,12 +1,20 @@
 #![no_std]
 extern crate alloc;
 
+use alloc::vec::Vec;
 use core::fmt::Display;
 
-use crate::config::{ConfigKey, ReadConfig};
+use crate::config::{ConfigKey, ReadConfig, WriteConfig};
 
+mod bar;
 pub mod config;
 
+pub use bar::*;
+
+/// The bit of the command register that keeps the function from asserting
+/// its interrupt pin.
+const COMMAND_INTERRUPT_DISABLE: u16 = 1 << 10;
+
 /// The description of a pci address consisting of bus, device and function.
 /// A pci address does not imply that a device is present at that address.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -45,6 +53,11 @@ impl PciAddress {
 
     getter!(vendor_id: u16 = ConfigKey::VENDOR_ID);
     getter!(device_id: u16 = ConfigKey::DEVICE_ID);
+    getter!(command: u16 = ConfigKey::COMMAND);
+    getter!(revision_id: u8 = ConfigKey::REVISION_ID);
+    getter!(prog_if: u8 = ConfigKey::PROG_IF);
+    getter!(subclass: u8 = ConfigKey::SUBCLASS);
+    getter!(class: u8 = ConfigKey::CLASS);
     getter!(header_type: u8 = ConfigKey::HEADER_TYPE);
     getter!(bar0: u32 = ConfigKey::BAR0);
     getter!(bar1: u32 = ConfigKey::BAR1);
@@ -52,9 +65,48 @@ impl PciAddress {
     getter!(bar3: u32 = ConfigKey::BAR3);
     getter!(bar4: u32 = ConfigKey::BAR4);
     getter!(bar5: u32 = ConfigKey::BAR5);
+    getter!(subsystem_vendor_id: u16 = ConfigKey::SUBSYSTEM_VENDOR_ID);
     getter!(subsystem_id: u16 = ConfigKey::SUBSYSTEM_ID);
+    getter!(interrupt_line: u8 = ConfigKey::INTERRUPT_LINE);
+    getter!(interrupt_pin: u8 = ConfigKey::INTERRUPT_PIN);
+
+    /// Allows or forbids the function to assert its legacy interrupt pin.
+    pub fn set_legacy_interrupts<C: ReadConfig<u16> + WriteConfig<u16> + ?Sized>(
+        &self,
+        config: &C,
+        enabled: bool,
+    ) {
+        let command = self.command(config);
+        let command = if enabled {
+            command & !COMMAND_INTERRUPT_DISABLE
+        } else {
+            command | COMMAND_INTERRUPT_DISABLE
+        };
+        config.write_config(*self, ConfigKey::COMMAND, command);
+    }
 
     pub fn is_multifunction<C: ReadConfig<u8>>(&self, config: &C) -> bool {
         self.header_type(config) & 0x80 != 0
     }
+
+    /// Reads and decodes the base address registers of the function.
+    ///
+    /// Bridges only have the first two registers, and other header types
+    /// have none.
+    pub fn bars<C: ReadConfig<u8> + ReadConfig<u32> + ?Sized>(&self, config: &C) -> Vec<Bar> {
+        let count = match self.header_type(config) & 0x7F {
+            0 => 6,
+            1 => 2,
+            _ => 0,
+        };
+        let raw = [
+            self.bar0(config),
+            self.bar1(config),
+            self.bar2(config),
+            self.bar3(config),
+            self.bar4(config),
+            self.bar5(config),
+        ];
+        Bar::decode(&raw[..count])
+    }
 }
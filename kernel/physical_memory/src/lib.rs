@@ -56,6 +56,26 @@ impl PhysicalMemoryManager {
         }
     }
 
+    /// Returns the number of usable frames, which are the allocated and the
+    /// free ones.
+    #[must_use]
+    pub fn usable_frames(&self) -> usize {
+        self.count_frames(FrameState::is_usable)
+    }
+
+    /// Returns the number of frames that are free to allocate.
+    #[must_use]
+    pub fn free_frames(&self) -> usize {
+        self.count_frames(|state| state == FrameState::Free)
+    }
+
+    fn count_frames(&self, f: impl Fn(FrameState) -> bool) -> usize {
+        self.regions
+            .iter()
+            .map(|region| region.frames().iter().filter(|&&state| f(state)).count())
+            .sum()
+    }
+
     /// Find the region and local index for a given physical address
     fn find_frame_location(regions: &[MemoryRegion], addr: u64) -> Option<RegionFrameIndex> {
         for (region_idx, region) in regions.iter().enumerate() {
@@ -334,6 +354,33 @@ mod tests {
         assert_eq!(&states[..], pmm.regions[0].frames());
     }
 
+    #[test]
+    fn test_frame_counts() {
+        let pmm = PhysicalMemoryManager::new(vec![
+            MemoryRegion::with_frames(
+                0,
+                vec![
+                    FrameState::Free,
+                    FrameState::Allocated,
+                    FrameState::Unusable,
+                ],
+            ),
+            MemoryRegion::new(0x10_0000, 4, FrameState::Free),
+        ]);
+        assert_eq!(6, pmm.usable_frames());
+        assert_eq!(5, pmm.free_frames());
+    }
+
+    #[test]
+    fn test_frame_counts_after_allocation() {
+        let mut pmm = PhysicalMemoryManager::new(vec![MemoryRegion::new(0, 4, FrameState::Free)]);
+        let frame: PhysFrame<Size4KiB> = pmm.allocate_frame().unwrap();
+        assert_eq!(4, pmm.usable_frames());
+        assert_eq!(3, pmm.free_frames());
+        pmm.deallocate_frame(frame);
+        assert_eq!(4, pmm.free_frames());
+    }
+
     #[test]
     fn test_new_no_frames() {
         let pmm = PhysicalMemoryManager::new(vec![]);
@@ -0,0 +1,113 @@
+//! The text of the files, in the formats of their Linux counterparts where
+//! that is possible, so that tools can parse them the same way.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::{CpuInfo, MemoryInfo, MemoryMapKind, MountInfo, ProcessInfo, ProcessState};
+
+pub(crate) fn status(process: &ProcessInfo) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Name:\t{}", process.name);
+    let _ = writeln!(out, "State:\t{}", state(process.state));
+    let _ = writeln!(out, "Pid:\t{}", process.pid);
+    let _ = writeln!(out, "PPid:\t{}", process.ppid);
+    let _ = writeln!(out, "SigPnd:\t{:016x}", process.pending_signals);
+    let _ = writeln!(out, "SigBlk:\t{:016x}", process.blocked_signals);
+    let _ = writeln!(out, "FDSize:\t{}", process.fds.len());
+    let _ = writeln!(out, "PageFaults:\t{}", process.page_faults);
+    out
+}
+
+fn state(state: ProcessState) -> &'static str {
+    match state {
+        ProcessState::Running => "R (running)",
+        ProcessState::Stopped => "T (stopped)",
+        ProcessState::Zombie => "Z (zombie)",
+    }
+}
+
+/// The arguments, each terminated by a NUL byte.
+pub(crate) fn cmdline(process: &ProcessInfo) -> Vec<u8> {
+    process
+        .cmdline
+        .iter()
+        .flat_map(|arg| arg.iter().copied().chain([0]))
+        .collect()
+}
+
+pub(crate) fn maps(process: &ProcessInfo) -> String {
+    let mut out = String::new();
+    for map in &process.maps {
+        let kind = match map.kind {
+            MemoryMapKind::Anonymous => "anon",
+            MemoryMapKind::Mapped => "mapped",
+            MemoryMapKind::File => "file",
+            MemoryMapKind::Shared => "shared",
+        };
+        let _ = write!(
+            out,
+            "{:016x}-{:016x} {kind:<6} {:08x}",
+            map.start,
+            map.start + map.size as u64,
+            map.offset
+        );
+        match &map.path {
+            Some(path) => {
+                let _ = writeln!(out, " {}", path.as_str());
+            }
+            None => out.push('\n'),
+        }
+    }
+    out
+}
+
+pub(crate) fn meminfo(memory: MemoryInfo) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "MemTotal:\t{} kB", memory.total / 1024);
+    let _ = writeln!(out, "MemFree:\t{} kB", memory.free / 1024);
+    out
+}
+
+pub(crate) fn cpuinfo(cpus: &[CpuInfo]) -> String {
+    let mut out = String::new();
+    for cpu in cpus {
+        let _ = writeln!(out, "processor\t: {}", cpu.id);
+        let _ = writeln!(out, "vendor_id\t: {}", cpu.vendor);
+        let _ = writeln!(out, "model name\t: {}", cpu.model);
+        let _ = writeln!(out, "apicid\t\t: {}", cpu.lapic_id);
+        out.push('\n');
+    }
+    out
+}
+
+/// The uptime in seconds, with two decimal places.
+pub(crate) fn uptime(uptime: Duration) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}.{:02}",
+        uptime.as_secs(),
+        uptime.subsec_millis() / 10
+    );
+    out
+}
+
+/// One line per mount, with the device, the mount point, the file system
+/// type and the options.
+pub(crate) fn mounts(mounts: &[MountInfo]) -> String {
+    let mut out = String::new();
+    for mount in mounts {
+        let source = mount.source.as_ref().map_or("none", |path| path.as_str());
+        let options = if mount.read_only { "ro" } else { "rw" };
+        let _ = writeln!(
+            out,
+            "{source} {} {} {options} 0 0",
+            mount.mount_point.as_str(),
+            mount.fs_type
+        );
+    }
+    out
+}
@@ -0,0 +1,652 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use kernel_abi::{ProcessId, S_IFDIR, S_IFLNK, S_IFREG};
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use kernel_vfs::path::{OwnedPath, Path};
+use kernel_vfs::{
+    CloseError, CreateError, FsError, FsyncError, OpenError, ReadError, ReadlinkError, Stat,
+    StatError, UnlinkError, WriteError,
+};
+
+use crate::content;
+use crate::kernel::{Kernel, ProcessInfo};
+use crate::node::{Entry, Global, MAX_PID, Node};
+
+/// A read-only file system that shows the processes and the state of the
+/// kernel, usually mounted at `/proc`.
+///
+/// The root directory has a directory for every process, named after its
+/// pid, next to `cpuinfo`, `meminfo`, `mounts`, `uptime` and a `self`
+/// symlink to the directory of the calling process. The directory of a
+/// process has `status`, `cmdline`, `maps`, the `exe` and `cwd` symlinks,
+/// and an `fd` directory with a symlink to the file of every open file
+/// descriptor.
+///
+/// A file is generated when it is read at offset 0, and later reads at
+/// other offsets continue in that snapshot, so that reading a file from
+/// start to end sees consistent content.
+pub struct ProcFs<K> {
+    kernel: K,
+    handles: BTreeMap<FsHandle, OpenNode>,
+    next_handle: u64,
+}
+
+struct OpenNode {
+    node: Node,
+    content: Option<Vec<u8>>,
+}
+
+impl<K> ProcFs<K>
+where
+    K: Kernel,
+{
+    pub fn new(kernel: K) -> Self {
+        Self {
+            kernel,
+            handles: BTreeMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn kernel(&self) -> &K {
+        &self.kernel
+    }
+
+    fn process(&self, pid: ProcessId) -> Result<ProcessInfo, OpenError> {
+        if pid.as_u64() > MAX_PID {
+            return Err(OpenError::NotFound);
+        }
+        self.kernel.process(pid).ok_or(OpenError::NotFound)
+    }
+
+    /// Returns the node with the id `id` if it still exists.
+    fn node(&self, id: FsNodeId) -> Result<Node, OpenError> {
+        let node = Node::from_id(id).ok_or(OpenError::NotFound)?;
+        if let Node::Process(pid, entry) = node {
+            let process = self.process(pid)?;
+            if let Entry::Fd(num) = entry
+                && !process.fds.iter().any(|fd| fd.num == num)
+            {
+                return Err(OpenError::NotFound);
+            }
+        }
+        Ok(node)
+    }
+
+    fn generate(&self, node: Node) -> Option<Vec<u8>> {
+        Some(match node {
+            Node::Global(Global::CpuInfo) => content::cpuinfo(&self.kernel.cpus()).into_bytes(),
+            Node::Global(Global::MemInfo) => content::meminfo(self.kernel.memory()).into_bytes(),
+            Node::Global(Global::Mounts) => content::mounts(&self.kernel.mounts()).into_bytes(),
+            Node::Global(Global::Uptime) => content::uptime(self.kernel.uptime()).into_bytes(),
+            Node::Process(pid, Entry::Status) => {
+                content::status(&self.process(pid).ok()?).into_bytes()
+            }
+            Node::Process(pid, Entry::Cmdline) => content::cmdline(&self.process(pid).ok()?),
+            Node::Process(pid, Entry::Maps) => content::maps(&self.process(pid).ok()?).into_bytes(),
+            Node::Root | Node::Global(Global::SelfLink) | Node::Process(..) => Vec::new(),
+        })
+    }
+
+    fn handle_node(&mut self, handle: FsHandle) -> Result<&mut OpenNode, FsError> {
+        self.handles.get_mut(&handle).ok_or(FsError::InvalidHandle)
+    }
+}
+
+/// Parses `name` as a number if it is written the way that the directory
+/// lists it, so that `01` doesn't exist next to `1`.
+fn canonical_number<T>(name: &str) -> Option<T>
+where
+    T: FromStr + ToString,
+{
+    name.parse::<T>()
+        .ok()
+        .filter(|number| number.to_string() == name)
+}
+
+impl<K> FileSystem for ProcFs<K>
+where
+    K: Kernel,
+{
+    fn root(&self) -> FsNodeId {
+        Node::Root.id()
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let node = match self.node(dir)? {
+            Node::Root => Global::ALL
+                .into_iter()
+                .find(|(global, _)| *global == name)
+                .map(|(_, global)| Node::Global(global))
+                .or_else(|| {
+                    let pid = canonical_number::<u64>(name)?;
+                    Some(Node::Process(pid.into(), Entry::Dir))
+                }),
+            Node::Process(pid, Entry::Dir) => Entry::ALL
+                .into_iter()
+                .find(|(entry, _)| *entry == name)
+                .map(|(_, entry)| Node::Process(pid, entry)),
+            Node::Process(pid, Entry::FdDir) => {
+                canonical_number::<i32>(name).map(|num| Node::Process(pid, Entry::Fd(num)))
+            }
+            Node::Global(_) | Node::Process(..) => return Err(OpenError::NotDirectory),
+        }
+        .ok_or(OpenError::NotFound)?;
+
+        // a pid or fd that parses doesn't have to exist
+        self.node(node.id())?;
+        Ok((node.id(), node.kind()))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        match self.node(dir)? {
+            Node::Root => {
+                let mut pids = self
+                    .kernel
+                    .processes()
+                    .into_iter()
+                    .filter(|pid| pid.as_u64() <= MAX_PID)
+                    .collect::<Vec<_>>();
+                pids.sort();
+                Ok(Global::ALL
+                    .into_iter()
+                    .map(|(name, _)| name.to_string())
+                    .chain(pids.into_iter().map(|pid| pid.to_string()))
+                    .collect())
+            }
+            Node::Process(_, Entry::Dir) => Ok(Entry::ALL
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .collect()),
+            Node::Process(pid, Entry::FdDir) => Ok(self
+                .process(pid)?
+                .fds
+                .iter()
+                .filter(|fd| fd.num >= 0)
+                .map(|fd| fd.num.to_string())
+                .collect()),
+            Node::Global(_) | Node::Process(..) => Err(OpenError::NotDirectory),
+        }
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        let node = self.node(node)?;
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.handles.insert(
+            handle,
+            OpenNode {
+                node,
+                content: None,
+            },
+        );
+        Ok(handle)
+    }
+
+    fn readlink(&mut self, node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        let node = self.node(node)?;
+        let target = match node {
+            Node::Global(Global::SelfLink) => {
+                let pid = self
+                    .kernel
+                    .current_process()
+                    .ok_or(ReadlinkError::NotFound)?;
+                return Ok(OwnedPath::new(pid.to_string()));
+            }
+            Node::Process(pid, Entry::Exe) => self
+                .process(pid)?
+                .executable
+                .ok_or(ReadlinkError::NotFound)?,
+            Node::Process(pid, Entry::Cwd) => self.process(pid)?.cwd,
+            Node::Process(pid, Entry::Fd(num)) => {
+                self.process(pid)?
+                    .fds
+                    .into_iter()
+                    .find(|fd| fd.num == num)
+                    .ok_or(ReadlinkError::NotFound)?
+                    .path
+            }
+            Node::Root | Node::Global(_) | Node::Process(..) => {
+                return Err(ReadlinkError::NotSymlink);
+            }
+        };
+        Ok(OwnedPath::new(target.as_str()))
+    }
+
+    fn create(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::ReadOnly)
+    }
+
+    fn symlink(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _target: &Path,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::ReadOnly)
+    }
+
+    fn unlink(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::ReadOnly)
+    }
+
+    fn rmdir(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::ReadOnly)
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        self.handles
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or(CloseError::NotOpen)
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let node = self.handle_node(handle)?.node;
+        if node.kind() != FsNodeKind::File {
+            return Err(ReadError::NotReadable);
+        }
+        if offset == 0 || self.handle_node(handle)?.content.is_none() {
+            // the process may have exited since the file was opened
+            let content = self.generate(node).ok_or(ReadError::ReadFailed)?;
+            self.handle_node(handle)?.content = Some(content);
+        }
+
+        let content = self.handle_node(handle)?.content.as_deref().unwrap();
+        if offset >= content.len() {
+            return Err(ReadError::EndOfFile);
+        }
+        let len = buf.len().min(content.len() - offset);
+        buf[..len].copy_from_slice(&content[offset..offset + len]);
+        Ok(len)
+    }
+
+    fn write(
+        &mut self,
+        handle: FsHandle,
+        _buf: &[u8],
+        _offset: usize,
+    ) -> Result<usize, WriteError> {
+        self.handle_node(handle)?;
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let open = self.handle_node(handle)?;
+        let node = open.node;
+        // like on Linux, generated files have no size until they are read,
+        // because generating them just for the size would be wasted work
+        let size = open.content.as_ref().map_or(0, Vec::len);
+        let (mode, nlink) = match node.kind() {
+            FsNodeKind::Directory => (S_IFDIR | 0o555, 2),
+            FsNodeKind::Symlink => (S_IFLNK | 0o777, 1),
+            FsNodeKind::File => (S_IFREG | 0o444, 1),
+        };
+        *stat = Stat {
+            size,
+            ino: node.id().get(),
+            mode,
+            nlink,
+            blocks: 0,
+        };
+        Ok(())
+    }
+
+    fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
+        // there's nothing to write back
+        self.handle_node(handle)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    use kernel_abi::{ProcessId, S_IFDIR, S_IFREG};
+    use kernel_vfs::fs::{FileSystem, FsNodeKind};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, OwnedPath, ROOT};
+    use kernel_vfs::{CreateError, OpenError, ReadError, Stat, Vfs, WriteError};
+
+    use super::ProcFs;
+    use crate::{
+        CpuInfo, FdInfo, Kernel, MemoryInfo, MemoryMap, MemoryMapKind, MountInfo, ProcessInfo,
+        ProcessState,
+    };
+
+    struct TestKernel {
+        processes: Vec<ProcessInfo>,
+    }
+
+    fn path(path: &str) -> AbsoluteOwnedPath {
+        AbsoluteOwnedPath::try_from(path).unwrap()
+    }
+
+    impl Kernel for TestKernel {
+        fn processes(&self) -> Vec<ProcessId> {
+            self.processes.iter().map(|process| process.pid).collect()
+        }
+
+        fn process(&self, pid: ProcessId) -> Option<ProcessInfo> {
+            self.processes
+                .iter()
+                .find(|process| process.pid == pid)
+                .cloned()
+        }
+
+        fn current_process(&self) -> Option<ProcessId> {
+            Some(ProcessId::from(7_u64))
+        }
+
+        fn memory(&self) -> MemoryInfo {
+            MemoryInfo {
+                total: 256 * 1024 * 1024,
+                free: 200 * 1024 * 1024,
+            }
+        }
+
+        fn cpus(&self) -> Vec<CpuInfo> {
+            (0..2)
+                .map(|id| CpuInfo {
+                    id,
+                    lapic_id: id * 2,
+                    vendor: String::from("GenuineIntel"),
+                    model: String::from("QEMU Virtual CPU"),
+                })
+                .collect()
+        }
+
+        fn uptime(&self) -> Duration {
+            Duration::from_millis(12_345)
+        }
+
+        fn mounts(&self) -> Vec<MountInfo> {
+            vec![
+                MountInfo {
+                    source: Some(path("/dev/blk0")),
+                    mount_point: path("/"),
+                    fs_type: String::from("ext2"),
+                    read_only: false,
+                },
+                MountInfo {
+                    source: None,
+                    mount_point: path("/proc"),
+                    fs_type: String::from("proc"),
+                    read_only: true,
+                },
+            ]
+        }
+    }
+
+    fn kernel() -> TestKernel {
+        let root = ProcessInfo {
+            pid: ProcessId::from(0_u64),
+            ppid: ProcessId::from(0_u64),
+            name: String::from("root"),
+            state: ProcessState::Running,
+            pending_signals: 0,
+            blocked_signals: 0,
+            cmdline: vec![],
+            executable: None,
+            cwd: path("/"),
+            fds: vec![],
+            maps: vec![],
+            page_faults: 0,
+        };
+        let init = ProcessInfo {
+            pid: ProcessId::from(7_u64),
+            ppid: ProcessId::from(0_u64),
+            name: String::from("/bin/init"),
+            state: ProcessState::Stopped,
+            pending_signals: 1 << 14,
+            blocked_signals: 1 << 1,
+            cmdline: vec![b"/bin/init".to_vec(), b"--verbose".to_vec()],
+            executable: Some(path("/bin/init")),
+            cwd: path("/var"),
+            fds: vec![
+                FdInfo {
+                    num: 0,
+                    path: path("/dev/null"),
+                },
+                FdInfo {
+                    num: 3,
+                    path: path("/var/hello.txt"),
+                },
+            ],
+            maps: vec![
+                MemoryMap {
+                    start: 0x20_0000,
+                    size: 0x3000,
+                    kind: MemoryMapKind::File,
+                    path: Some(path("/bin/init")),
+                    offset: 0x1000,
+                },
+                MemoryMap {
+                    start: 0x1_0000_0000,
+                    size: 0x10_0000,
+                    kind: MemoryMapKind::Anonymous,
+                    path: None,
+                    offset: 0,
+                },
+            ],
+            page_faults: 42,
+        };
+        TestKernel {
+            processes: vec![init, root],
+        }
+    }
+
+    fn vfs() -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, ProcFs::new(kernel())).unwrap();
+        vfs
+    }
+
+    fn read(vfs: &Vfs, file: &str) -> String {
+        let node = vfs.open(AbsolutePath::try_new(file).unwrap()).unwrap();
+        let mut content = vec![];
+        let mut buf = [0; 16];
+        loop {
+            match node.read(&mut buf[..], content.len()) {
+                Ok(n) => content.extend_from_slice(&buf[..n]),
+                Err(ReadError::EndOfFile) => break,
+                Err(e) => panic!("reading {file} failed: {e:?}"),
+            }
+        }
+        String::from_utf8(content).unwrap()
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let mut fs = ProcFs::new(kernel());
+        let root = fs.root();
+        assert_eq!(
+            vec!["cpuinfo", "meminfo", "mounts", "self", "uptime", "0", "7"],
+            fs.read_dir(root).unwrap()
+        );
+
+        let (init, kind) = fs.lookup(root, "7").unwrap();
+        assert_eq!(FsNodeKind::Directory, kind);
+        assert_eq!(
+            vec!["cmdline", "cwd", "exe", "fd", "maps", "status"],
+            fs.read_dir(init).unwrap()
+        );
+        let (fd, _) = fs.lookup(init, "fd").unwrap();
+        assert_eq!(vec!["0", "3"], fs.read_dir(fd).unwrap());
+        assert_eq!(
+            Ok(FsNodeKind::Symlink),
+            fs.lookup(fd, "3").map(|(_, kind)| kind)
+        );
+    }
+
+    #[test]
+    fn test_lookup_missing() {
+        let mut fs = ProcFs::new(kernel());
+        let root = fs.root();
+        for name in ["1", "07", "-7", "foo", ""] {
+            assert_eq!(Err(OpenError::NotFound), fs.lookup(root, name), "{name}");
+        }
+        let (init, _) = fs.lookup(root, "7").unwrap();
+        let (fd, _) = fs.lookup(init, "fd").unwrap();
+        assert_eq!(Err(OpenError::NotFound), fs.lookup(fd, "1"));
+        let (status, _) = fs.lookup(init, "status").unwrap();
+        assert_eq!(Err(OpenError::NotDirectory), fs.lookup(status, "x"));
+        assert_eq!(Err(OpenError::NotDirectory), fs.read_dir(status));
+    }
+
+    #[test]
+    fn test_status() {
+        let vfs = vfs();
+        assert_eq!(
+            "Name:\t/bin/init\n\
+             State:\tT (stopped)\n\
+             Pid:\t7\n\
+             PPid:\t0\n\
+             SigPnd:\t0000000000004000\n\
+             SigBlk:\t0000000000000002\n\
+             FDSize:\t2\n\
+             PageFaults:\t42\n",
+            read(&vfs, "/7/status")
+        );
+        assert!(read(&vfs, "/0/status").starts_with("Name:\troot\nState:\tR (running)\n"));
+    }
+
+    #[test]
+    fn test_self() {
+        let vfs = vfs();
+        assert_eq!(
+            Ok(OwnedPath::new("7")),
+            vfs.readlink(AbsolutePath::try_new("/self").unwrap())
+        );
+        assert_eq!(read(&vfs, "/7/status"), read(&vfs, "/self/status"));
+    }
+
+    #[test]
+    fn test_cmdline() {
+        assert_eq!("/bin/init\0--verbose\0", read(&vfs(), "/7/cmdline"));
+        assert_eq!("", read(&vfs(), "/0/cmdline"));
+    }
+
+    #[test]
+    fn test_links() {
+        let vfs = vfs();
+        for (link, target) in [
+            ("/7/exe", "/bin/init"),
+            ("/7/cwd", "/var"),
+            ("/7/fd/0", "/dev/null"),
+            ("/7/fd/3", "/var/hello.txt"),
+        ] {
+            assert_eq!(
+                Ok(OwnedPath::new(target)),
+                vfs.readlink(AbsolutePath::try_new(link).unwrap()),
+                "{link}"
+            );
+        }
+        // the root process has no executable
+        assert!(
+            vfs.readlink(AbsolutePath::try_new("/0/exe").unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_maps() {
+        assert_eq!(
+            "0000000000200000-0000000000203000 file   00001000 /bin/init\n\
+             0000000100000000-0000000100100000 anon   00000000\n",
+            read(&vfs(), "/7/maps")
+        );
+    }
+
+    #[test]
+    fn test_global_files() {
+        let vfs = vfs();
+        assert_eq!(
+            "MemTotal:\t262144 kB\nMemFree:\t204800 kB\n",
+            read(&vfs, "/meminfo")
+        );
+        assert_eq!("12.34\n", read(&vfs, "/uptime"));
+        assert_eq!(
+            "/dev/blk0 / ext2 rw 0 0\nnone /proc proc ro 0 0\n",
+            read(&vfs, "/mounts")
+        );
+        assert_eq!(
+            "processor\t: 0\n\
+             vendor_id\t: GenuineIntel\n\
+             model name\t: QEMU Virtual CPU\n\
+             apicid\t\t: 0\n\
+             \n\
+             processor\t: 1\n\
+             vendor_id\t: GenuineIntel\n\
+             model name\t: QEMU Virtual CPU\n\
+             apicid\t\t: 2\n\
+             \n",
+            read(&vfs, "/cpuinfo")
+        );
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut fs = ProcFs::new(kernel());
+        let root = fs.root();
+        let (init, _) = fs.lookup(root, "7").unwrap();
+        let (status, _) = fs.lookup(init, "status").unwrap();
+        let handle = fs.open(status).unwrap();
+
+        let mut stat = Stat::default();
+        fs.stat(handle, &mut stat).unwrap();
+        assert_eq!(S_IFREG | 0o444, stat.mode);
+        assert_eq!(0, stat.size);
+
+        let mut buf = [0; 8];
+        assert_eq!(Ok(8), fs.read(handle, &mut buf, 0));
+        assert_eq!(b"Name:\t/b", &buf);
+
+        // the process exits, but the snapshot stays readable
+        fs.kernel.processes.retain(|process| process.pid != 7_u64);
+        assert_eq!(Ok(8), fs.read(handle, &mut buf, 8));
+        assert_eq!(b"in/init\n", &buf);
+        fs.stat(handle, &mut stat).unwrap();
+        assert_ne!(0, stat.size);
+        assert_eq!(Err(ReadError::ReadFailed), fs.read(handle, &mut buf, 0));
+        fs.close(handle).unwrap();
+
+        assert_eq!(Err(OpenError::NotFound), fs.lookup(root, "7"));
+        assert_eq!(Err(OpenError::NotFound), fs.open(init));
+    }
+
+    #[test]
+    fn test_read_only() {
+        let mut fs = ProcFs::new(kernel());
+        let root = fs.root();
+        assert_eq!(
+            Err(CreateError::ReadOnly),
+            fs.create(root, "new", FsNodeKind::File)
+        );
+
+        let handle = fs.open(root).unwrap();
+        let mut stat = Stat::default();
+        fs.stat(handle, &mut stat).unwrap();
+        assert_eq!(S_IFDIR | 0o555, stat.mode);
+        assert_eq!(Err(ReadError::NotReadable), fs.read(handle, &mut [0; 4], 0));
+        assert_eq!(Err(WriteError::NotWritable), fs.write(handle, b"x", 0));
+        fs.close(handle).unwrap();
+    }
+}
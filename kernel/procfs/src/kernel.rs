@@ -0,0 +1,114 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use kernel_abi::{ProcessId, SigSet};
+use kernel_vfs::path::AbsoluteOwnedPath;
+
+/// The kernel state that [`ProcFs`](crate::ProcFs) shows.
+///
+/// The file system asks for the state every time that a file is read, so
+/// the files always show a fresh snapshot.
+pub trait Kernel: Send + Sync {
+    /// The ids of all processes, in any order.
+    fn processes(&self) -> Vec<ProcessId>;
+
+    /// A snapshot of the process `pid`, or `None` if there is no such
+    /// process.
+    fn process(&self, pid: ProcessId) -> Option<ProcessInfo>;
+
+    /// The process that is accessing the file system, which `self` points
+    /// to, or `None` if the access doesn't come from a process.
+    fn current_process(&self) -> Option<ProcessId>;
+
+    fn memory(&self) -> MemoryInfo;
+
+    fn cpus(&self) -> Vec<CpuInfo>;
+
+    /// The time since the kernel booted.
+    fn uptime(&self) -> Duration;
+
+    /// All mounts, ordered by their mount point.
+    fn mounts(&self) -> Vec<MountInfo>;
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: ProcessId,
+    pub ppid: ProcessId,
+    pub name: String,
+    pub state: ProcessState,
+    pub pending_signals: SigSet,
+    pub blocked_signals: SigSet,
+    /// The arguments that the current image was started with, starting
+    /// with `argv[0]`.
+    pub cmdline: Vec<Vec<u8>>,
+    /// The root process has no executable.
+    pub executable: Option<AbsoluteOwnedPath>,
+    pub cwd: AbsoluteOwnedPath,
+    pub fds: Vec<FdInfo>,
+    pub maps: Vec<MemoryMap>,
+    pub page_faults: usize,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ProcessState {
+    Running,
+    Stopped,
+    /// The process has exited, but hasn't been reaped yet.
+    Zombie,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FdInfo {
+    pub num: i32,
+    pub path: AbsoluteOwnedPath,
+}
+
+/// A region of the virtual address space of a process.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MemoryMap {
+    pub start: u64,
+    pub size: usize,
+    pub kind: MemoryMapKind,
+    /// The file that backs the region, if any.
+    pub path: Option<AbsoluteOwnedPath>,
+    /// The offset into `path` that the region starts at.
+    pub offset: usize,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MemoryMapKind {
+    /// Zeroed memory that is mapped on the first access.
+    Anonymous,
+    /// Memory that is mapped in its entirety.
+    Mapped,
+    /// A private copy of a file that is read on the first access.
+    File,
+    /// The memory of a device or file, shared with everyone mapping it.
+    Shared,
+}
+
+/// Physical memory usage, in bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MemoryInfo {
+    pub total: usize,
+    pub free: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CpuInfo {
+    pub id: u32,
+    pub lapic_id: u32,
+    pub vendor: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MountInfo {
+    /// The device that the file system was mounted from, if any.
+    pub source: Option<AbsoluteOwnedPath>,
+    pub mount_point: AbsoluteOwnedPath,
+    pub fs_type: String,
+    pub read_only: bool,
+}
@@ -0,0 +1,10 @@
+#![no_std]
+extern crate alloc;
+
+mod content;
+mod fs;
+mod kernel;
+mod node;
+
+pub use fs::*;
+pub use kernel::*;
@@ -0,0 +1,171 @@
+use kernel_abi::ProcessId;
+use kernel_vfs::fs::{FsNodeId, FsNodeKind};
+
+/// The bit of the low half of a node id that marks the link of an open
+/// file descriptor, whose number is in the bits below it.
+const FD_BIT: u64 = 1 << 31;
+
+/// The largest pid that fits into the upper half of a node id. Processes
+/// with a larger one don't show up.
+pub(crate) const MAX_PID: u64 = u32::MAX as u64 - 1;
+
+/// A node of the file system.
+///
+/// The id of a node encodes the node itself, so the file system has no
+/// state besides its open handles. The upper half of the id is the pid
+/// plus one, or zero for nodes that don't belong to a process, and the
+/// lower half is the entry in that directory.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Node {
+    Root,
+    Global(Global),
+    Process(ProcessId, Entry),
+}
+
+/// The files in the root directory that don't belong to a process.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Global {
+    CpuInfo = 2,
+    MemInfo,
+    Mounts,
+    SelfLink,
+    Uptime,
+}
+
+/// The nodes in and below the directory of a process.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Entry {
+    Dir,
+    Cmdline,
+    Cwd,
+    Exe,
+    FdDir,
+    Fd(i32),
+    Maps,
+    Status,
+}
+
+impl Global {
+    pub const ALL: [(&str, Global); 5] = [
+        ("cpuinfo", Global::CpuInfo),
+        ("meminfo", Global::MemInfo),
+        ("mounts", Global::Mounts),
+        ("self", Global::SelfLink),
+        ("uptime", Global::Uptime),
+    ];
+
+    fn from_id(id: u64) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .map(|(_, global)| global)
+            .find(|global| *global as u64 == id)
+    }
+}
+
+impl Entry {
+    /// The entries of the directory of a process.
+    pub const ALL: [(&str, Entry); 6] = [
+        ("cmdline", Entry::Cmdline),
+        ("cwd", Entry::Cwd),
+        ("exe", Entry::Exe),
+        ("fd", Entry::FdDir),
+        ("maps", Entry::Maps),
+        ("status", Entry::Status),
+    ];
+
+    fn id(self) -> u64 {
+        match self {
+            Entry::Dir => 0,
+            Entry::Cmdline => 1,
+            Entry::Cwd => 2,
+            Entry::Exe => 3,
+            Entry::FdDir => 4,
+            Entry::Maps => 5,
+            Entry::Status => 6,
+            Entry::Fd(num) => FD_BIT | u64::from(num.cast_unsigned()),
+        }
+    }
+
+    fn from_id(id: u64) -> Option<Self> {
+        if id & FD_BIT != 0 {
+            return i32::try_from(id & !FD_BIT).ok().map(Entry::Fd);
+        }
+        Some(match id {
+            0 => Entry::Dir,
+            1 => Entry::Cmdline,
+            2 => Entry::Cwd,
+            3 => Entry::Exe,
+            4 => Entry::FdDir,
+            5 => Entry::Maps,
+            6 => Entry::Status,
+            _ => return None,
+        })
+    }
+}
+
+impl Node {
+    pub const ROOT_ID: u64 = 1;
+
+    pub fn id(self) -> FsNodeId {
+        FsNodeId::from(match self {
+            Node::Root => Self::ROOT_ID,
+            Node::Global(global) => global as u64,
+            Node::Process(pid, entry) => ((pid.as_u64() + 1) << 32) | entry.id(),
+        })
+    }
+
+    pub fn from_id(id: FsNodeId) -> Option<Self> {
+        let id = id.get();
+        match (id >> 32, id & 0xFFFF_FFFF) {
+            (0, Self::ROOT_ID) => Some(Node::Root),
+            (0, global) => Global::from_id(global).map(Node::Global),
+            (pid, entry) => {
+                Entry::from_id(entry).map(|entry| Node::Process((pid - 1).into(), entry))
+            }
+        }
+    }
+
+    pub fn kind(self) -> FsNodeKind {
+        match self {
+            Node::Root | Node::Process(_, Entry::Dir | Entry::FdDir) => FsNodeKind::Directory,
+            Node::Global(Global::SelfLink)
+            | Node::Process(_, Entry::Cwd | Entry::Exe | Entry::Fd(_)) => FsNodeKind::Symlink,
+            Node::Global(_) | Node::Process(..) => FsNodeKind::File,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_round_trip() {
+        let mut nodes = alloc::vec![Node::Root];
+        nodes.extend(Global::ALL.map(|(_, global)| Node::Global(global)));
+        for pid in [0, 1, 42, MAX_PID] {
+            let pid = ProcessId::from(pid);
+            nodes.push(Node::Process(pid, Entry::Dir));
+            nodes.extend(Entry::ALL.map(|(_, entry)| Node::Process(pid, entry)));
+            for fd in [0, 1, 1023, i32::MAX] {
+                nodes.push(Node::Process(pid, Entry::Fd(fd)));
+            }
+        }
+
+        for node in &nodes {
+            assert_eq!(Some(*node), Node::from_id(node.id()), "{node:?}");
+        }
+        for (i, a) in nodes.iter().enumerate() {
+            for b in &nodes[i + 1..] {
+                assert_ne!(a.id(), b.id(), "{a:?} and {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_ids() {
+        for id in [0, 7, 0x1_0000_0007] {
+            assert_eq!(None, Node::from_id(FsNodeId::from(id)), "{id:#x}");
+        }
+    }
+}
@@ -3,6 +3,7 @@ use kernel_abi::{Errno, ProcessId};
 mod cwd;
 mod file;
 mod mem;
+mod mount;
 mod process;
 mod region;
 mod signal;
@@ -10,6 +11,7 @@ mod signal;
 pub use cwd::*;
 pub use file::*;
 pub use mem::*;
+pub use mount::*;
 pub use process::*;
 pub use region::*;
 pub use signal::*;
@@ -1,7 +1,7 @@
 use core::ffi::c_int;
 
-use kernel_abi::{EINVAL, ENOSYS, ENOTTY, Errno, IoctlRequest, Stat};
-use kernel_vfs::path::AbsolutePath;
+use kernel_abi::{EINVAL, ENOSYS, ENOTTY, Errno, IoctlRequest, PollEvents, Stat};
+use kernel_vfs::path::{AbsolutePath, OwnedPath};
 
 pub trait FileInfo {}
 
@@ -9,14 +9,47 @@ pub trait FileAccess {
     type FileInfo: FileInfo;
     type Fd: From<c_int> + Into<c_int> + Clone + core::fmt::Debug;
     type OpenError;
-    type ReadError;
-    type WriteError;
+    type ReadError: Into<Errno>;
+    type WriteError: Into<Errno>;
     type CloseError;
 
-    fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo>;
+    /// Looks up the file at `path`, following symlinks.
+    ///
+    /// # Errors
+    /// Returns the errno describing why `path` could not be resolved,
+    /// such as `ENOENT`, `ENOTDIR` or `ELOOP`.
+    fn file_info(&self, path: &AbsolutePath) -> Result<Self::FileInfo, Errno>;
+
+    /// Returns the target of the symlink at `path` without following it.
+    ///
+    /// # Errors
+    /// Returns `EINVAL` if `path` is not a symlink, or `ENOSYS` when the
+    /// context does not implement symlinks.
+    fn readlink(&self, path: &AbsolutePath) -> Result<OwnedPath, Errno> {
+        let _ = path;
+        Err(ENOSYS)
+    }
+
+    /// Creates an empty regular file at `path`.
+    ///
+    /// # Errors
+    /// Returns `EEXIST` if something already exists at `path`, or `ENOSYS`
+    /// when the context can't create files.
+    fn create(&self, path: &AbsolutePath) -> Result<(), Errno> {
+        let _ = path;
+        Err(ENOSYS)
+    }
 
-    fn open(&self, info: &Self::FileInfo) -> Result<Self::Fd, Self::OpenError>;
+    /// Opens the file described by `info` and returns its new descriptor.
+    /// `oflag` holds the flags passed to `open`, of which the file status
+    /// flags like `O_NONBLOCK` stick to the new open file description.
+    fn open(&self, info: &Self::FileInfo, oflag: i32) -> Result<Self::Fd, Self::OpenError>;
 
+    /// Reads from the current position of `fd`.
+    ///
+    /// # Errors
+    /// A blocking descriptor without data waits for it, a non-blocking one
+    /// fails with `EAGAIN` instead.
     fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, Self::ReadError>;
 
     fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, Self::WriteError>;
@@ -32,6 +65,17 @@ pub trait FileAccess {
         Err(ENOTTY)
     }
 
+    /// Returns which of `events` the open file is ready for, plus errors
+    /// and hang-ups. Never blocks.
+    ///
+    /// # Errors
+    /// Returns `EBADF` if `fd` is not open, or `ENOSYS` when the context
+    /// can't poll.
+    fn poll(&self, fd: Self::Fd, events: PollEvents) -> Result<PollEvents, Errno> {
+        let _ = (fd, events);
+        Err(ENOSYS)
+    }
+
     /// Flushes an open file's pending writes to its backing device.
     ///
     /// # Errors
@@ -79,8 +123,8 @@ pub mod testing {
     use core::sync::atomic::AtomicUsize;
     use core::sync::atomic::Ordering::Relaxed;
 
-    use kernel_abi::{EBADF, Errno, Stat};
-    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+    use kernel_abi::{EBADF, EEXIST, EINVAL, ENOENT, Errno, PollEvents, Stat};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, OwnedPath};
     use spin::mutex::Mutex;
     use spin::rwlock::RwLock;
 
@@ -89,6 +133,7 @@ pub mod testing {
     #[derive(Default)]
     pub struct MemoryFileAccess {
         pub files: BTreeMap<AbsoluteOwnedPath, Arc<MemoryFile>>,
+        pub symlinks: BTreeMap<AbsoluteOwnedPath, OwnedPath>,
         open_fds: BTreeMap<MemoryFd, Arc<MemoryFile>>,
     }
 
@@ -155,22 +200,44 @@ pub mod testing {
         type FileInfo = MemoryFileInfo;
         type Fd = MemoryFd;
         type OpenError = ();
-        type ReadError = ();
-        type WriteError = ();
+        type ReadError = Errno;
+        type WriteError = Errno;
         type CloseError = ();
 
-        fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo> {
+        fn file_info(&self, path: &AbsolutePath) -> Result<Self::FileInfo, Errno> {
             let guard = self.lock();
             if guard.files.contains_key(path) {
-                Some(Self::FileInfo {
+                Ok(Self::FileInfo {
                     path: path.to_owned(),
                 })
             } else {
-                None
+                Err(ENOENT)
+            }
+        }
+
+        fn readlink(&self, path: &AbsolutePath) -> Result<OwnedPath, Errno> {
+            let guard = self.lock();
+            if let Some(target) = guard.symlinks.get(path) {
+                Ok(target.clone())
+            } else if guard.files.contains_key(path) {
+                Err(EINVAL)
+            } else {
+                Err(ENOENT)
+            }
+        }
+
+        fn create(&self, path: &AbsolutePath) -> Result<(), Errno> {
+            let mut guard = self.lock();
+            if guard.files.contains_key(path) {
+                return Err(EEXIST);
             }
+            guard
+                .files
+                .insert(path.to_owned(), Arc::new(MemoryFile::new(Vec::new())));
+            Ok(())
         }
 
-        fn open(&self, info: &Self::FileInfo) -> Result<Self::Fd, ()> {
+        fn open(&self, info: &Self::FileInfo, _oflag: i32) -> Result<Self::Fd, ()> {
             let mut guard = self.lock();
 
             if let Some(file) = guard.files.get(&info.path).cloned() {
@@ -186,7 +253,7 @@ pub mod testing {
             }
         }
 
-        fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, ()> {
+        fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, Errno> {
             let guard = self.lock();
 
             if let Some(file) = guard.open_fds.get(&fd) {
@@ -195,11 +262,11 @@ pub mod testing {
                 buf[..len].copy_from_slice(&data[..len]);
                 Ok(len)
             } else {
-                Err(())
+                Err(EBADF)
             }
         }
 
-        fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, ()> {
+        fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, Errno> {
             let guard = self.lock();
 
             if let Some(file) = guard.open_fds.get(&fd) {
@@ -212,7 +279,7 @@ pub mod testing {
                 let _ = fd.position.fetch_add(buf.len(), Relaxed);
                 Ok(buf.len())
             } else {
-                Err(())
+                Err(EBADF)
             }
         }
 
@@ -226,11 +293,18 @@ pub mod testing {
             }
         }
 
+        fn poll(&self, fd: Self::Fd, events: PollEvents) -> Result<PollEvents, Errno> {
+            let guard = self.lock();
+            guard.open_fds.get(&fd).ok_or(EBADF)?;
+            Ok(events & (PollEvents::POLLIN | PollEvents::POLLOUT))
+        }
+
         fn fstat(&self, fd: Self::Fd) -> Result<Stat, Errno> {
             let guard = self.lock();
             let file = guard.open_fds.get(&fd).ok_or(EBADF)?;
             Ok(Stat {
                 size: file.data.read().len() as u64,
+                ..Stat::default()
             })
         }
 
@@ -0,0 +1,31 @@
+use alloc::string::String;
+
+use kernel_abi::{Errno, MountFlags};
+use kernel_vfs::path::AbsolutePath;
+
+pub trait MountAccess {
+    /// Mounts a new file system of the type `fs_type` at `target`. `source`
+    /// is the block device that the file system lives on, if it needs one.
+    ///
+    /// # Errors
+    /// Returns `ENODEV` if there is no driver for `fs_type`, and `EBUSY`
+    /// if `target` is already a mount point.
+    fn mount(
+        &self,
+        fs_type: &str,
+        source: Option<&AbsolutePath>,
+        target: &AbsolutePath,
+        flags: MountFlags,
+    ) -> Result<(), Errno>;
+
+    /// Unmounts the file system mounted at `target`.
+    ///
+    /// # Errors
+    /// Returns `EINVAL` if `target` is not a mount point, and `EBUSY` if
+    /// the file system is still in use.
+    fn unmount(&self, target: &AbsolutePath) -> Result<(), Errno>;
+
+    /// Returns the mount table with one mount per line, in the format of
+    /// `/proc/mounts`.
+    fn mount_table(&self) -> String;
+}
@@ -20,5 +20,17 @@ pub trait ProcessesAccess {
 
 pub trait ProcessAccess {
     fn process_id(&self) -> ProcessId;
+    fn parent_process_id(&self) -> ProcessId;
     fn process_group_id(&self) -> ProcessId;
+    fn session_id(&self) -> ProcessId;
+}
+
+pub trait JobControlAccess {
+    /// Moves `pid` into the process group `pgid` of the same session, which
+    /// is created if `pgid` is `pid` itself.
+    fn set_process_group(&self, pid: ProcessId, pgid: ProcessId);
+
+    /// Makes the calling process the leader of a new session and of a new
+    /// process group in it, without a controlling terminal.
+    fn create_session(&self);
 }
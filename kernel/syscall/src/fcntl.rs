@@ -2,8 +2,8 @@ use alloc::borrow::ToOwned;
 use core::ffi::c_int;
 use core::slice::from_raw_parts;
 
-use kernel_abi::{EINVAL, ENAMETOOLONG, ENOENT, Errno, PATH_MAX};
-use kernel_vfs::path::{AbsolutePath, Path};
+use kernel_abi::{EEXIST, EINVAL, ENAMETOOLONG, ENOENT, Errno, O_CREAT, O_EXCL, PATH_MAX};
+use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, Path};
 use tracing::{Level, debug, instrument};
 
 use crate::access::{CwdAccess, FileAccess};
@@ -14,41 +14,56 @@ pub fn sys_open<Cx: CwdAccess + FileAccess>(
     cx: &Cx,
     path: UserspacePtr<u8>,
     path_len: usize,
-    _oflag: i32,
+    oflag: i32,
     _mode: i32,
 ) -> Result<usize, Errno> {
-    if path_len > PATH_MAX {
-        return Err(ENAMETOOLONG);
-    }
-
-    let path = {
-        let path_bytes = unsafe { from_raw_parts(path.as_ptr(), path_len) };
-        let path = core::str::from_utf8(path_bytes).map_err(|_| EINVAL)?;
-        let path = Path::new(path);
-        if let Ok(p) = AbsolutePath::try_new(path) {
-            p.to_owned()
-        } else {
-            let mut p = cx.current_working_directory().read().clone();
-            p.push(path);
-            p
-        }
-    };
+    let path = user_path(cx, path, path_len)?;
 
     debug!(?path, "open");
 
-    let info = cx.file_info(path.as_ref()).ok_or(ENOENT)?;
-    let fd = cx.open(&info).map_err(|_| EINVAL)?; // TODO: check error
+    let info = match cx.file_info(path.as_ref()) {
+        Ok(_) if oflag & O_CREAT != 0 && oflag & O_EXCL != 0 => return Err(EEXIST),
+        Err(ENOENT) if oflag & O_CREAT != 0 => {
+            cx.create(path.as_ref())?;
+            cx.file_info(path.as_ref())?
+        }
+        info => info?,
+    };
+    let fd = cx.open(&info, oflag).map_err(|_| EINVAL)?; // TODO: check error
     let fd_num = Into::<c_int>::into(fd);
     Ok(fd_num as usize)
 }
 
+/// Reads a path from userspace and makes it absolute against the current
+/// working directory.
+pub(crate) fn user_path<Cx: CwdAccess>(
+    cx: &Cx,
+    path: UserspacePtr<u8>,
+    path_len: usize,
+) -> Result<AbsoluteOwnedPath, Errno> {
+    if path_len > PATH_MAX {
+        return Err(ENAMETOOLONG);
+    }
+
+    let path_bytes = unsafe { from_raw_parts(path.as_ptr(), path_len) };
+    let path = core::str::from_utf8(path_bytes).map_err(|_| EINVAL)?;
+    let path = Path::new(path);
+    if let Ok(p) = AbsolutePath::try_new(path) {
+        Ok(p.to_owned())
+    } else {
+        let mut p = cx.current_working_directory().read().clone();
+        p.push(path);
+        Ok(p)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::borrow::ToOwned;
     use alloc::sync::Arc;
     use alloc::vec;
 
-    use kernel_abi::ENOENT;
+    use kernel_abi::{EEXIST, ENOENT, Errno, O_CREAT, O_EXCL};
     use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
     use spin::mutex::Mutex;
     use spin::rwlock::RwLock;
@@ -95,12 +110,16 @@ mod tests {
         type WriteError = F::WriteError;
         type CloseError = F::CloseError;
 
-        fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo> {
+        fn file_info(&self, path: &AbsolutePath) -> Result<Self::FileInfo, Errno> {
             self.file_access.file_info(path)
         }
 
-        fn open(&self, info: &Self::FileInfo) -> Result<Self::Fd, Self::OpenError> {
-            self.file_access.open(info)
+        fn create(&self, path: &AbsolutePath) -> Result<(), Errno> {
+            self.file_access.create(path)
+        }
+
+        fn open(&self, info: &Self::FileInfo, oflag: i32) -> Result<Self::Fd, Self::OpenError> {
+            self.file_access.open(info, oflag)
         }
 
         fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, Self::ReadError> {
@@ -164,4 +183,30 @@ mod tests {
             "opening a file descriptor must return the lowest currently available fd number, so consecutive open calls must return consecutive fd numbers"
         );
     }
+
+    #[test]
+    fn test_open_create() {
+        let cx = TestOpenCx::new(
+            AbsoluteOwnedPath::try_from("/tmp").unwrap(),
+            Mutex::new(MemoryFileAccess::default()),
+        );
+
+        let path = "foo.txt";
+        let p = UserspacePtr::try_from(path.as_ptr()).unwrap();
+
+        assert_eq!(sys_open(&cx, p, path.len(), 0, 0), Err(ENOENT));
+        assert_eq!(sys_open(&cx, p, path.len(), O_CREAT, 0), Ok(0));
+        assert!(
+            cx.file_access
+                .lock()
+                .files
+                .contains_key(AbsolutePath::try_new("/tmp/foo.txt").unwrap())
+        );
+        // an existing file is opened again, unless O_EXCL is set
+        assert_eq!(sys_open(&cx, p, path.len(), O_CREAT, 0), Ok(1));
+        assert_eq!(
+            sys_open(&cx, p, path.len(), O_CREAT | O_EXCL, 0),
+            Err(EEXIST)
+        );
+    }
 }
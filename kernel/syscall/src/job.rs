@@ -0,0 +1,318 @@
+use kernel_abi::{EPERM, ESRCH, Errno, ProcessId};
+use tracing::{Level, instrument};
+
+use crate::access::{JobControlAccess, PermissionAccess, ProcessAccess, ProcessesAccess};
+
+/// Looks up `pid`, or the calling process for the root id.
+fn process_or_caller<Cx: PermissionAccess + ProcessesAccess>(
+    cx: &Cx,
+    pid: ProcessId,
+) -> Result<Cx::Process, Errno> {
+    let pid = if pid.is_root() {
+        cx.current_identity().process_id
+    } else {
+        pid
+    };
+    cx.process_by_id(pid).ok_or(ESRCH)
+}
+
+fn to_usize(pid: ProcessId) -> usize {
+    pid.as_u64() as usize
+}
+
+pub fn sys_getppid<Cx: PermissionAccess + ProcessesAccess>(cx: &Cx) -> Result<usize, Errno> {
+    let process = process_or_caller(cx, ProcessId::from(0_u64))?;
+    Ok(to_usize(process.parent_process_id()))
+}
+
+/// # Errors
+/// `ESRCH` if no process `pid` exists.
+pub fn sys_getpgid<Cx: PermissionAccess + ProcessesAccess>(
+    cx: &Cx,
+    pid: ProcessId,
+) -> Result<usize, Errno> {
+    Ok(to_usize(process_or_caller(cx, pid)?.process_group_id()))
+}
+
+/// # Errors
+/// `ESRCH` if no process `pid` exists.
+pub fn sys_getsid<Cx: PermissionAccess + ProcessesAccess>(
+    cx: &Cx,
+    pid: ProcessId,
+) -> Result<usize, Errno> {
+    Ok(to_usize(process_or_caller(cx, pid)?.session_id()))
+}
+
+/// Moves the process `pid` into the process group `pgid`. The root id stands
+/// for the caller as `pid` and for `pid` as `pgid`, which makes `pid` the
+/// leader of a new group.
+///
+/// # Errors
+/// `ESRCH` if `pid` is neither the caller nor one of its children. `EPERM`
+/// if `pid` leads a session, is in another session than the caller, or if
+/// `pgid` is a new group other than `pid` or a group in another session.
+#[instrument(level = Level::TRACE, skip(cx))]
+pub fn sys_setpgid<Cx: PermissionAccess + ProcessesAccess + JobControlAccess>(
+    cx: &Cx,
+    pid: ProcessId,
+    pgid: ProcessId,
+) -> Result<usize, Errno> {
+    let caller = process_or_caller(cx, ProcessId::from(0_u64))?;
+    let target = process_or_caller(cx, pid)?;
+    if target.process_id() != caller.process_id()
+        && target.parent_process_id() != caller.process_id()
+    {
+        return Err(ESRCH);
+    }
+    if target.session_id() == target.process_id() || target.session_id() != caller.session_id() {
+        return Err(EPERM);
+    }
+
+    let pgid = if pgid.is_root() {
+        target.process_id()
+    } else {
+        pgid
+    };
+    if pgid != target.process_id()
+        && !cx
+            .processes_in_group(pgid)
+            .any(|p| p.session_id() == caller.session_id())
+    {
+        return Err(EPERM);
+    }
+
+    cx.set_process_group(target.process_id(), pgid);
+    Ok(0)
+}
+
+/// Makes the caller the leader of a new session and process group and
+/// returns the id of both, which is the caller's pid.
+///
+/// # Errors
+/// `EPERM` if a process group with the caller's pid exists already, which
+/// includes the case that the caller leads its group.
+#[instrument(level = Level::TRACE, skip(cx))]
+pub fn sys_setsid<Cx: PermissionAccess + ProcessesAccess + JobControlAccess>(
+    cx: &Cx,
+) -> Result<usize, Errno> {
+    let pid = cx.current_identity().process_id;
+    if cx.processes_in_group(pid).next().is_some() {
+        return Err(EPERM);
+    }
+    cx.create_session();
+    Ok(to_usize(pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use kernel_abi::{EPERM, ESRCH, Errno, ProcessId};
+
+    use super::*;
+    use crate::access::{
+        Capability, Identity, JobControlAccess, PermissionAccess, ProcessAccess, ProcessesAccess,
+    };
+
+    macro_rules! pid {
+        ($n:expr) => {
+            ProcessId::from($n as u64)
+        };
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct TestProcess {
+        pid: ProcessId,
+        ppid: ProcessId,
+        pgid: ProcessId,
+        sid: ProcessId,
+    }
+
+    impl ProcessAccess for TestProcess {
+        fn process_id(&self) -> ProcessId {
+            self.pid
+        }
+
+        fn parent_process_id(&self) -> ProcessId {
+            self.ppid
+        }
+
+        fn process_group_id(&self) -> ProcessId {
+            self.pgid
+        }
+
+        fn session_id(&self) -> ProcessId {
+            self.sid
+        }
+    }
+
+    struct TestContext {
+        current: ProcessId,
+        processes: RefCell<Vec<TestProcess>>,
+    }
+
+    impl TestContext {
+        /// The caller is pid 2, a child of the kernel in the kernel's
+        /// session and its own process group, like every process that the
+        /// kernel starts.
+        fn new() -> Self {
+            let cx = Self {
+                current: pid!(2),
+                processes: RefCell::new(vec![]),
+            };
+            cx.add(0, 0, 0, 0);
+            cx.add(2, 0, 2, 0);
+            cx
+        }
+
+        fn add(&self, pid: u64, ppid: u64, pgid: u64, sid: u64) {
+            self.processes.borrow_mut().push(TestProcess {
+                pid: pid!(pid),
+                ppid: pid!(ppid),
+                pgid: pid!(pgid),
+                sid: pid!(sid),
+            });
+        }
+
+        fn get(&self, pid: u64) -> TestProcess {
+            self.process_by_id(pid!(pid)).unwrap()
+        }
+    }
+
+    impl PermissionAccess for TestContext {
+        fn current_identity(&self) -> Identity {
+            let current = self.process_by_id(self.current).unwrap();
+            Identity {
+                process_id: current.pid,
+                user_id: 0,
+                process_group_id: current.pgid,
+            }
+        }
+
+        fn check_permission(&self, _: ProcessId, _: Capability) -> Result<(), Errno> {
+            Ok(())
+        }
+    }
+
+    impl ProcessesAccess for TestContext {
+        type Process = TestProcess;
+
+        fn all_processes(&self) -> impl Iterator<Item = Self::Process> {
+            self.processes.borrow().clone().into_iter()
+        }
+    }
+
+    impl JobControlAccess for TestContext {
+        fn set_process_group(&self, pid: ProcessId, pgid: ProcessId) {
+            let mut processes = self.processes.borrow_mut();
+            let process = processes.iter_mut().find(|p| p.pid == pid).unwrap();
+            process.pgid = pgid;
+        }
+
+        fn create_session(&self) {
+            let mut processes = self.processes.borrow_mut();
+            let process = processes
+                .iter_mut()
+                .find(|p| p.pid == self.current)
+                .unwrap();
+            process.pgid = self.current;
+            process.sid = self.current;
+        }
+    }
+
+    #[test]
+    fn test_getters() {
+        let cx = TestContext::new();
+        cx.add(3, 2, 2, 0);
+
+        assert_eq!(Ok(0), sys_getppid(&cx));
+        assert_eq!(Ok(2), sys_getpgid(&cx, pid!(0)));
+        assert_eq!(Ok(2), sys_getpgid(&cx, pid!(3)));
+        assert_eq!(Ok(0), sys_getsid(&cx, pid!(0)));
+        assert_eq!(Err(ESRCH), sys_getpgid(&cx, pid!(7)));
+        assert_eq!(Err(ESRCH), sys_getsid(&cx, pid!(7)));
+    }
+
+    #[test]
+    fn test_setpgid_new_group() {
+        let cx = TestContext::new();
+        cx.add(3, 2, 2, 0);
+
+        assert_eq!(Ok(0), sys_setpgid(&cx, pid!(3), pid!(0)));
+        assert_eq!(pid!(3), cx.get(3).pgid, "the child should lead a new group");
+        assert_eq!(pid!(2), cx.get(2).pgid, "the caller should keep its group");
+    }
+
+    #[test]
+    fn test_setpgid_join_group() {
+        let cx = TestContext::new();
+        cx.add(4, 0, 4, 0);
+
+        assert_eq!(Ok(0), sys_setpgid(&cx, pid!(0), pid!(4)));
+        assert_eq!(pid!(4), cx.get(2).pgid);
+    }
+
+    #[test]
+    fn test_setpgid_unknown_group() {
+        let cx = TestContext::new();
+        assert_eq!(Err(EPERM), sys_setpgid(&cx, pid!(0), pid!(9)));
+        assert_eq!(pid!(2), cx.get(2).pgid);
+    }
+
+    #[test]
+    fn test_setpgid_group_in_other_session() {
+        let cx = TestContext::new();
+        cx.add(5, 0, 5, 5);
+        assert_eq!(Err(EPERM), sys_setpgid(&cx, pid!(0), pid!(5)));
+    }
+
+    #[test]
+    fn test_setpgid_session_leader() {
+        let cx = TestContext::new();
+        cx.processes.borrow_mut()[1].sid = pid!(2);
+        assert_eq!(
+            Err(EPERM),
+            sys_setpgid(&cx, pid!(0), pid!(0)),
+            "a session leader can't leave its group"
+        );
+    }
+
+    #[test]
+    fn test_setpgid_not_a_child() {
+        let cx = TestContext::new();
+        cx.add(4, 0, 4, 0);
+        assert_eq!(Err(ESRCH), sys_setpgid(&cx, pid!(4), pid!(0)));
+        assert_eq!(Err(ESRCH), sys_setpgid(&cx, pid!(9), pid!(0)));
+    }
+
+    #[test]
+    fn test_setpgid_child_in_other_session() {
+        let cx = TestContext::new();
+        cx.add(3, 2, 3, 3);
+        assert_eq!(Err(EPERM), sys_setpgid(&cx, pid!(3), pid!(2)));
+    }
+
+    #[test]
+    fn test_setsid() {
+        let cx = TestContext::new();
+        assert_eq!(
+            Err(EPERM),
+            sys_setsid(&cx),
+            "a group leader can't start a session"
+        );
+
+        cx.add(4, 0, 4, 0);
+        assert_eq!(Ok(0), sys_setpgid(&cx, pid!(0), pid!(4)));
+        assert_eq!(Ok(2), sys_setsid(&cx));
+        let caller = cx.get(2);
+        assert_eq!(pid!(2), caller.pgid);
+        assert_eq!(pid!(2), caller.sid);
+        assert_eq!(
+            Err(EPERM),
+            sys_setpgid(&cx, pid!(0), pid!(4)),
+            "the group is in another session now"
+        );
+    }
+}
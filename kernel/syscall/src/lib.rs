@@ -5,7 +5,10 @@ extern crate alloc;
 pub mod access;
 pub mod exec;
 pub mod fcntl;
+pub mod job;
 pub mod mman;
+pub mod mount;
+pub mod poll;
 pub mod signal;
 pub mod unistd;
 
@@ -0,0 +1,233 @@
+use core::slice::from_raw_parts;
+
+use kernel_abi::{EINVAL, ENAMETOOLONG, ERANGE, Errno, MountArgs, MountFlags};
+use tracing::{Level, instrument};
+
+use crate::access::{CwdAccess, MountAccess};
+use crate::fcntl::user_path;
+use crate::ptr::UserspacePtr;
+
+/// The maximum length of a file system type name, such as `ext2`.
+const FS_TYPE_MAX: usize = 32;
+
+/// Mounts the file system described by `args`.
+///
+/// # Errors
+/// `EINVAL` for unknown flags or malformed strings, and any error of
+/// [`MountAccess::mount`].
+#[instrument(level = Level::TRACE, skip(cx))]
+pub fn sys_mount<Cx: CwdAccess + MountAccess>(
+    cx: &Cx,
+    args: &MountArgs<'_>,
+) -> Result<usize, Errno> {
+    let flags = MountFlags::from_bits(args.flags).ok_or(EINVAL)?;
+
+    if args.fs_type.len() > FS_TYPE_MAX {
+        return Err(ENAMETOOLONG);
+    }
+    let fs_type = unsafe { UserspacePtr::<u8>::try_from_usize(args.fs_type.ptr()) }?;
+    let fs_type = unsafe { from_raw_parts(fs_type.as_ptr(), args.fs_type.len()) };
+    let fs_type = core::str::from_utf8(fs_type).map_err(|_| EINVAL)?;
+
+    let source = if args.source.is_empty() {
+        None
+    } else {
+        let source = unsafe { UserspacePtr::try_from_usize(args.source.ptr()) }?;
+        Some(user_path(cx, source, args.source.len())?)
+    };
+
+    let target = unsafe { UserspacePtr::try_from_usize(args.target.ptr()) }?;
+    let target = user_path(cx, target, args.target.len())?;
+
+    cx.mount(
+        fs_type,
+        source.as_ref().map(AsRef::as_ref),
+        target.as_ref(),
+        flags,
+    )
+    .map(|()| 0)
+}
+
+/// Unmounts the file system mounted at `target`.
+///
+/// # Errors
+/// Any error of [`MountAccess::unmount`].
+#[instrument(level = Level::TRACE, skip(cx))]
+pub fn sys_umount<Cx: CwdAccess + MountAccess>(
+    cx: &Cx,
+    target: UserspacePtr<u8>,
+    target_len: usize,
+) -> Result<usize, Errno> {
+    let target = user_path(cx, target, target_len)?;
+    cx.unmount(target.as_ref()).map(|()| 0)
+}
+
+/// Copies the mount table into `buf` and returns the number of bytes
+/// copied. The table has one mount per line, in the format of
+/// `/proc/mounts`, and is not null-terminated.
+///
+/// # Errors
+/// `ERANGE` if `buf` is too small for the whole table.
+#[instrument(level = Level::TRACE, skip(cx, buf), fields(len = buf.len()))]
+pub fn sys_mount_table<Cx: MountAccess>(cx: &Cx, buf: &mut [u8]) -> Result<usize, Errno> {
+    let table = cx.mount_table();
+    let dst = buf.get_mut(..table.len()).ok_or(ERANGE)?;
+    dst.copy_from_slice(table.as_bytes());
+    Ok(table.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use alloc::{format, vec};
+
+    use kernel_abi::{EBUSY, EINVAL, ENODEV, ERANGE, Errno, MountArgs, MountFlags, StrSlice};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+    use spin::mutex::Mutex;
+    use spin::rwlock::RwLock;
+
+    use crate::access::{CwdAccess, MountAccess};
+    use crate::mount::{sys_mount, sys_mount_table, sys_umount};
+
+    struct TestMount {
+        fs_type: String,
+        source: Option<AbsoluteOwnedPath>,
+        target: AbsoluteOwnedPath,
+        flags: MountFlags,
+    }
+
+    struct MountCx {
+        cwd: RwLock<AbsoluteOwnedPath>,
+        mounts: Mutex<Vec<TestMount>>,
+    }
+
+    impl MountCx {
+        fn new(cwd: &str) -> Self {
+            Self {
+                cwd: RwLock::new(AbsoluteOwnedPath::try_from(cwd).unwrap()),
+                mounts: Mutex::default(),
+            }
+        }
+    }
+
+    impl CwdAccess for MountCx {
+        fn current_working_directory(&self) -> &RwLock<AbsoluteOwnedPath> {
+            &self.cwd
+        }
+    }
+
+    impl MountAccess for MountCx {
+        fn mount(
+            &self,
+            fs_type: &str,
+            source: Option<&AbsolutePath>,
+            target: &AbsolutePath,
+            flags: MountFlags,
+        ) -> Result<(), Errno> {
+            if fs_type != "ext2" {
+                return Err(ENODEV);
+            }
+            let mut mounts = self.mounts.lock();
+            if mounts
+                .iter()
+                .any(|m| AsRef::<AbsolutePath>::as_ref(&m.target) == target)
+            {
+                return Err(EBUSY);
+            }
+            mounts.push(TestMount {
+                fs_type: fs_type.into(),
+                source: source.map(ToOwned::to_owned),
+                target: target.to_owned(),
+                flags,
+            });
+            Ok(())
+        }
+
+        fn unmount(&self, target: &AbsolutePath) -> Result<(), Errno> {
+            let mut mounts = self.mounts.lock();
+            let index = mounts
+                .iter()
+                .position(|m| AsRef::<AbsolutePath>::as_ref(&m.target) == target)
+                .ok_or(EINVAL)?;
+            mounts.remove(index);
+            Ok(())
+        }
+
+        fn mount_table(&self) -> String {
+            self.mounts
+                .lock()
+                .iter()
+                .map(|m| {
+                    let source = m.source.as_ref().map_or("none", |s| s.as_str());
+                    format!("{source} {} {} rw\n", m.target.as_str(), m.fs_type)
+                })
+                .collect()
+        }
+    }
+
+    fn args<'a>(fs_type: &'a str, source: &'a str, target: &'a str, flags: u32) -> MountArgs<'a> {
+        MountArgs {
+            fs_type: fs_type.into(),
+            source: StrSlice::from(source),
+            target: target.into(),
+            flags,
+        }
+    }
+
+    #[test]
+    fn test_mount_umount() {
+        let cx = MountCx::new("/home");
+
+        assert_eq!(
+            sys_mount(&cx, &args("ext2", "/dev/blk1", "mnt", 0x1 | 0x8)),
+            Ok(0)
+        );
+        assert_eq!(
+            sys_mount(&cx, &args("ext2", "/dev/blk1", "/home/mnt", 0)),
+            Err(EBUSY)
+        );
+        assert_eq!(
+            sys_mount(&cx, &args("fat", "/dev/blk1", "/mnt", 0)),
+            Err(ENODEV)
+        );
+        assert_eq!(
+            sys_mount(&cx, &args("ext2", "", "/mnt", 0x2)),
+            Err(EINVAL),
+            "unknown flags should be rejected"
+        );
+        {
+            let mount = &cx.mounts.lock()[0];
+            assert_eq!(mount.source.as_ref().unwrap().as_str(), "/dev/blk1");
+            assert_eq!(mount.target.as_str(), "/home/mnt");
+            assert_eq!(mount.flags, MountFlags::READ_ONLY | MountFlags::NOEXEC);
+        }
+
+        let target = "/home/mnt";
+        let ptr = target.as_ptr().try_into().unwrap();
+        assert_eq!(sys_umount(&cx, ptr, target.len()), Ok(0));
+        assert_eq!(sys_umount(&cx, ptr, target.len()), Err(EINVAL));
+    }
+
+    #[test]
+    fn test_mount_without_source() {
+        let cx = MountCx::new("/");
+        assert_eq!(sys_mount(&cx, &args("ext2", "", "/tmp", 0)), Ok(0));
+        assert!(cx.mounts.lock()[0].source.is_none());
+    }
+
+    #[test]
+    fn test_mount_table() {
+        let cx = MountCx::new("/");
+        sys_mount(&cx, &args("ext2", "/dev/blk0", "/", 0)).unwrap();
+        let expected = "/dev/blk0 / ext2 rw\n";
+
+        let mut buf = vec![0_u8; expected.len() - 1];
+        assert_eq!(sys_mount_table(&cx, &mut buf), Err(ERANGE));
+
+        let mut buf = vec![0_u8; 64];
+        assert_eq!(sys_mount_table(&cx, &mut buf), Ok(expected.len()));
+        assert_eq!(&buf[..expected.len()], expected.as_bytes());
+    }
+}
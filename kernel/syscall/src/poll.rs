@@ -0,0 +1,80 @@
+use kernel_abi::{EBADF, PollEvents, PollFd};
+
+use crate::access::FileAccess;
+
+/// Fills in the `revents` of every entry in `fds` and returns how many
+/// entries have any. Entries with a negative `fd` are skipped, and those
+/// with a descriptor that isn't open get [`PollEvents::POLLNVAL`].
+///
+/// This is one pass of `poll` and never blocks. Waiting until an entry is
+/// ready or the timeout expires is up to the caller, since only it knows
+/// how to wait for a device.
+pub fn poll_fds<Cx: FileAccess>(cx: &Cx, fds: &mut [PollFd]) -> usize {
+    let mut ready = 0;
+    for pollfd in fds {
+        pollfd.revents = 0;
+        if pollfd.fd < 0 {
+            continue;
+        }
+        let events = PollEvents::from_bits_truncate(pollfd.events);
+        let revents = match cx.poll(Cx::Fd::from(pollfd.fd), events) {
+            // POLLERR and POLLHUP are always reported, wanted or not
+            Ok(revents) => revents & (events | PollEvents::POLLERR | PollEvents::POLLHUP),
+            Err(EBADF) => PollEvents::POLLNVAL,
+            Err(_) => PollEvents::POLLERR,
+        };
+        pollfd.revents = revents.bits();
+        if !revents.is_empty() {
+            ready += 1;
+        }
+    }
+    ready
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec;
+
+    use kernel_abi::{PollEvents, PollFd};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+    use spin::mutex::Mutex;
+
+    use super::poll_fds;
+    use crate::access::FileAccess;
+    use crate::access::testing::{MemoryFile, MemoryFileAccess};
+
+    fn pollfd(fd: i32, events: PollEvents) -> PollFd {
+        PollFd {
+            fd,
+            events: events.bits(),
+            revents: -1,
+        }
+    }
+
+    #[test]
+    fn test_poll_fds() {
+        let mut file_access = MemoryFileAccess::default();
+        let path = AbsoluteOwnedPath::try_from("/file").unwrap();
+        file_access
+            .files
+            .insert(path.clone(), Arc::new(MemoryFile::new(vec![1, 2, 3])));
+        let cx = Mutex::new(file_access);
+        let info = cx
+            .file_info(AbsolutePath::try_new("/file").unwrap())
+            .unwrap();
+        let fd: i32 = cx.open(&info, 0).unwrap().into();
+
+        let mut fds = [
+            pollfd(fd, PollEvents::POLLIN),
+            pollfd(-1, PollEvents::POLLIN),
+            pollfd(fd + 1, PollEvents::POLLIN),
+            pollfd(fd, PollEvents::POLLPRI),
+        ];
+        assert_eq!(2, poll_fds(&cx, &mut fds));
+        assert_eq!(PollEvents::POLLIN.bits(), fds[0].revents);
+        assert_eq!(0, fds[1].revents, "negative descriptors are skipped");
+        assert_eq!(PollEvents::POLLNVAL.bits(), fds[2].revents);
+        assert_eq!(0, fds[3].revents, "only the wanted events are reported");
+    }
+}
@@ -362,9 +362,18 @@ mod tests {
             self.pid
         }
 
+        // signal delivery doesn't care about parents and sessions
+        fn parent_process_id(&self) -> ProcessId {
+            pid!(0)
+        }
+
         fn process_group_id(&self) -> ProcessId {
             self.pgid
         }
+
+        fn session_id(&self) -> ProcessId {
+            pid!(0)
+        }
     }
 
     struct TestContext {
@@ -4,7 +4,8 @@ use kernel_abi::{EINVAL, EOVERFLOW, ERANGE, Errno, IoctlRequest, Whence};
 use tracing::{Level, instrument};
 
 use crate::access::{CwdAccess, FileAccess};
-use crate::ptr::UserspaceMutPtr;
+use crate::fcntl::user_path;
+use crate::ptr::{UserspaceMutPtr, UserspacePtr};
 
 #[instrument(level = Level::TRACE, skip(cx))]
 pub fn sys_getcwd<Cx: CwdAccess>(
@@ -38,12 +39,12 @@ pub fn sys_getcwd<Cx: CwdAccess>(
 
 #[instrument(level = Level::TRACE, skip(cx, buf), fields(len = buf.len()))]
 pub fn sys_read<Cx: FileAccess>(cx: &Cx, fildes: Cx::Fd, buf: &mut [u8]) -> Result<usize, Errno> {
-    cx.read(fildes, buf).map_err(|_| EINVAL)
+    cx.read(fildes, buf).map_err(Into::into)
 }
 
 #[instrument(level = Level::TRACE, skip(cx, buf), fields(len = buf.len()))]
 pub fn sys_write<Cx: FileAccess>(cx: &Cx, fildes: Cx::Fd, buf: &[u8]) -> Result<usize, Errno> {
-    cx.write(fildes, buf).map_err(|_| EINVAL)
+    cx.write(fildes, buf).map_err(Into::into)
 }
 
 pub fn sys_ioctl<Cx: FileAccess>(
@@ -86,19 +87,45 @@ pub fn sys_lseek<Cx: FileAccess>(
     usize::try_from(target).map_err(Into::into)
 }
 
+/// Copies the target of the symlink at `path` into `buf` without following
+/// it, and returns the number of bytes copied. As in POSIX, a target longer
+/// than `buf` is silently truncated and the result is not null-terminated.
+///
+/// # Errors
+/// `EINVAL` for an empty `buf` or if `path` is not a symlink, and any error
+/// that occurs while resolving `path`.
+#[instrument(level = Level::TRACE, skip(cx, buf), fields(len = buf.len()))]
+pub fn sys_readlink<Cx: CwdAccess + FileAccess>(
+    cx: &Cx,
+    path: UserspacePtr<u8>,
+    path_len: usize,
+    buf: &mut [u8],
+) -> Result<usize, Errno> {
+    if buf.is_empty() {
+        return Err(EINVAL);
+    }
+
+    let path = user_path(cx, path, path_len)?;
+    let target = cx.readlink(path.as_ref())?;
+    let len = target.len().min(buf.len());
+    buf[..len].copy_from_slice(&target.as_bytes()[..len]);
+    Ok(len)
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::sync::Arc;
     use alloc::vec;
 
-    use kernel_abi::{EINVAL, EOVERFLOW, ERANGE, Whence};
-    use kernel_vfs::path::AbsoluteOwnedPath;
+    use kernel_abi::{EINVAL, ENOENT, EOVERFLOW, ERANGE, Errno, Whence};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, OwnedPath};
     use spin::mutex::Mutex;
     use spin::rwlock::RwLock;
 
+    use crate::UserspacePtr;
     use crate::access::testing::{MemoryFd, MemoryFile, MemoryFileAccess};
     use crate::access::{CwdAccess, FileAccess};
-    use crate::unistd::{sys_getcwd, sys_lseek};
+    use crate::unistd::{sys_getcwd, sys_lseek, sys_readlink};
 
     #[test]
     fn test_getcwd() {
@@ -147,7 +174,7 @@ mod tests {
         let info = cx
             .file_info(path.as_ref())
             .expect("fixture file must exist");
-        let fd = cx.open(&info).expect("fixture file must open");
+        let fd = cx.open(&info, 0).expect("fixture file must open");
         (cx, fd)
     }
 
@@ -233,4 +260,83 @@ mod tests {
             "a SEEK_CUR result past the largest representable offset must be rejected"
         );
     }
+
+    #[test]
+    fn test_readlink() {
+        struct ReadlinkCx {
+            cwd: RwLock<AbsoluteOwnedPath>,
+            files: Mutex<MemoryFileAccess>,
+        }
+
+        impl CwdAccess for ReadlinkCx {
+            fn current_working_directory(&self) -> &RwLock<AbsoluteOwnedPath> {
+                &self.cwd
+            }
+        }
+
+        impl FileAccess for ReadlinkCx {
+            type FileInfo = <Mutex<MemoryFileAccess> as FileAccess>::FileInfo;
+            type Fd = MemoryFd;
+            type OpenError = ();
+            type ReadError = Errno;
+            type WriteError = Errno;
+            type CloseError = ();
+
+            fn file_info(&self, path: &AbsolutePath) -> Result<Self::FileInfo, Errno> {
+                self.files.file_info(path)
+            }
+
+            fn readlink(&self, path: &AbsolutePath) -> Result<OwnedPath, Errno> {
+                self.files.readlink(path)
+            }
+
+            fn open(&self, info: &Self::FileInfo, oflag: i32) -> Result<Self::Fd, ()> {
+                self.files.open(info, oflag)
+            }
+
+            fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, Errno> {
+                self.files.read(fd, buf)
+            }
+
+            fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, Errno> {
+                self.files.write(fd, buf)
+            }
+
+            fn close(&self, fd: Self::Fd) -> Result<(), ()> {
+                self.files.close(fd)
+            }
+        }
+
+        let mut files = MemoryFileAccess::default();
+        files.files.insert(
+            AbsoluteOwnedPath::try_from("/etc/file.txt").unwrap(),
+            Arc::new(MemoryFile::new(vec![])),
+        );
+        files.symlinks.insert(
+            AbsoluteOwnedPath::try_from("/etc/link").unwrap(),
+            OwnedPath::new("file.txt"),
+        );
+        let cx = ReadlinkCx {
+            cwd: RwLock::new(AbsoluteOwnedPath::try_from("/etc").unwrap()),
+            files: Mutex::new(files),
+        };
+
+        for (path, size, expected) in [
+            ("/etc/link", 64, Ok("file.txt")),
+            ("link", 64, Ok("file.txt")),
+            ("link", 4, Ok("file")),
+            ("link", 0, Err(EINVAL)),
+            ("file.txt", 64, Err(EINVAL)),
+            ("missing", 64, Err(ENOENT)),
+        ] {
+            let mut buf = vec![0_u8; size];
+            let ptr = UserspacePtr::try_from(path.as_ptr()).unwrap();
+            let res = sys_readlink(&cx, ptr, path.len(), &mut buf);
+            assert_eq!(
+                res.map(|len| &buf[..len]),
+                expected.map(str::as_bytes),
+                "{path}"
+            );
+        }
+    }
 }
@@ -0,0 +1,87 @@
+//! The text of the attribute files. Like on Linux, every file holds a
+//! single value followed by a newline, except for `resource`, which has a
+//! line per base address register.
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+
+use kernel_pci::BarKind;
+
+use crate::node::{BlockAttr, FramebufferAttr, PciAttr};
+use crate::{BlockDeviceInfo, DriverStatus, FramebufferInfo, PartitionInfo, PciDeviceInfo};
+
+pub(crate) fn pci(device: &PciDeviceInfo, attr: PciAttr) -> String {
+    match attr {
+        PciAttr::Dir => String::new(),
+        PciAttr::Vendor => format!("{:#06x}\n", device.vendor_id),
+        PciAttr::Device => format!("{:#06x}\n", device.device_id),
+        PciAttr::Class => format!(
+            "0x{:02x}{:02x}{:02x}\n",
+            device.class, device.subclass, device.prog_if
+        ),
+        PciAttr::Revision => format!("{:#04x}\n", device.revision_id),
+        PciAttr::SubsystemVendor => format!("{:#06x}\n", device.subsystem_vendor_id),
+        PciAttr::SubsystemDevice => format!("{:#06x}\n", device.subsystem_id),
+        PciAttr::Resource => resource(device),
+        PciAttr::Driver => device
+            .driver
+            .as_ref()
+            .map_or_else(String::new, |driver| format!("{driver}\n")),
+        PciAttr::Status => match &device.status {
+            DriverStatus::Unbound => String::from("unbound\n"),
+            DriverStatus::Bound => String::from("bound\n"),
+            DriverStatus::Failed(error) => format!("failed: {error}\n"),
+        },
+    }
+}
+
+/// One line per implemented base address register, with its number, its
+/// kind and the address that it is mapped at.
+fn resource(device: &PciDeviceInfo) -> String {
+    let mut out = String::new();
+    for bar in &device.bars {
+        let kind = match bar.kind {
+            BarKind::Io => "io",
+            BarKind::Memory32 => "mem32",
+            BarKind::Memory64 => "mem64",
+        };
+        let _ = write!(out, "{} {kind} {:#018x}", bar.index, bar.address);
+        if bar.prefetchable {
+            out.push_str(" prefetchable");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub(crate) fn block(device: &BlockDeviceInfo, attr: BlockAttr) -> String {
+    match attr {
+        BlockAttr::Dev => format!("{}\n", device.device_id),
+        BlockAttr::SectorSize => format!("{}\n", device.sector_size),
+        BlockAttr::Size => format!("{}\n", device.sector_count),
+        BlockAttr::Dir | BlockAttr::Partition | BlockAttr::Start => String::new(),
+    }
+}
+
+/// The attributes of a partition, whose sizes are in sectors of the whole
+/// device.
+pub(crate) fn partition(partition: &PartitionInfo, attr: BlockAttr) -> String {
+    match attr {
+        BlockAttr::Partition => format!("{}\n", partition.number),
+        BlockAttr::Start => format!("{}\n", partition.start),
+        BlockAttr::Size => format!("{}\n", partition.sector_count),
+        BlockAttr::Dir | BlockAttr::Dev | BlockAttr::SectorSize => String::new(),
+    }
+}
+
+pub(crate) fn framebuffer(fb: &FramebufferInfo, attr: FramebufferAttr) -> String {
+    match attr {
+        FramebufferAttr::Dir => String::new(),
+        FramebufferAttr::Dev => format!("{}\n", fb.device_id),
+        FramebufferAttr::Width => format!("{}\n", fb.width),
+        FramebufferAttr::Height => format!("{}\n", fb.height),
+        FramebufferAttr::Stride => format!("{}\n", fb.stride),
+        FramebufferAttr::BitsPerPixel => format!("{}\n", fb.bits_per_pixel),
+    }
+}
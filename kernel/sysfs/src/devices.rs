@@ -0,0 +1,82 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use kernel_pci::{Bar, PciAddress};
+
+/// The devices that [`SysFs`](crate::SysFs) shows.
+///
+/// The file system asks for the devices every time that a directory is
+/// listed or a file is read, so devices that appear later show up without
+/// notifying it.
+pub trait Devices: Send + Sync {
+    /// Every PCI function that was found during enumeration, whether or
+    /// not a driver took it, ordered by address.
+    fn pci_devices(&self) -> Vec<PciDeviceInfo>;
+
+    /// The whole block devices, ordered by their number.
+    fn block_devices(&self) -> Vec<BlockDeviceInfo>;
+
+    /// The framebuffers, ordered by their number.
+    fn framebuffers(&self) -> Vec<FramebufferInfo>;
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PciDeviceInfo {
+    pub address: PciAddress,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub revision_id: u8,
+    pub subsystem_vendor_id: u16,
+    pub subsystem_id: u16,
+    pub bars: Vec<Bar>,
+    /// The name of the driver that matched the device, if any.
+    pub driver: Option<String>,
+    pub status: DriverStatus,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DriverStatus {
+    /// No driver matched the device.
+    Unbound,
+    /// The driver initialized the device.
+    Bound,
+    /// The driver failed to initialize the device, with the error that it
+    /// returned.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlockDeviceInfo {
+    /// The `N` in the device name `blkN`.
+    pub number: u64,
+    /// The id of the device in the kernel.
+    pub device_id: u64,
+    pub sector_size: usize,
+    pub sector_count: usize,
+    pub partitions: Vec<PartitionInfo>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PartitionInfo {
+    /// The `M` in the device name `blkNpM`.
+    pub number: usize,
+    /// The first sector of the partition on the whole device.
+    pub start: usize,
+    pub sector_count: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FramebufferInfo {
+    /// The `N` in the device name `fbN`.
+    pub number: u32,
+    /// The id of the device in the kernel.
+    pub device_id: u64,
+    pub width: u32,
+    pub height: u32,
+    /// The number of bytes from the start of one row to the next.
+    pub stride: u32,
+    pub bits_per_pixel: u32,
+}
@@ -0,0 +1,644 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use kernel_abi::{S_IFDIR, S_IFREG};
+use kernel_pci::PciAddress;
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use kernel_vfs::path::{OwnedPath, Path};
+use kernel_vfs::{
+    CloseError, CreateError, FsError, FsyncError, OpenError, ReadError, ReadlinkError, Stat,
+    StatError, UnlinkError, WriteError,
+};
+
+use crate::content;
+use crate::devices::{BlockDeviceInfo, Devices, FramebufferInfo, PartitionInfo, PciDeviceInfo};
+use crate::node::{BlockAttr, Dir, FramebufferAttr, Node, PciAttr};
+
+/// A read-only file system that shows the devices that the kernel found,
+/// usually mounted at `/sys`.
+///
+/// - `bus/pci/devices` has a directory for every PCI function, named like
+///   `0000:00:04.0`, with its ids, its base address registers in
+///   `resource`, the name of the matching driver in `driver`, and in
+///   `status` whether the driver initialized the device.
+/// - `block` has a directory for every block device, like `blk0`, with its
+///   kernel device id in `dev` and its geometry, and a subdirectory for
+///   every partition, like `blk0p1`.
+/// - `class/graphics` has a directory for every framebuffer, like `fb0`,
+///   with its kernel device id and its mode.
+///
+/// A file is generated when it is read at offset 0, and later reads at
+/// other offsets continue in that snapshot.
+pub struct SysFs<D> {
+    devices: D,
+    handles: BTreeMap<FsHandle, OpenNode>,
+    next_handle: u64,
+}
+
+struct OpenNode {
+    node: Node,
+    content: Option<Vec<u8>>,
+}
+
+fn pci_name(addr: PciAddress) -> String {
+    // there's only one pci segment, so the domain is always 0
+    format!("0000:{addr}")
+}
+
+fn block_name(disk: u64) -> String {
+    format!("blk{disk}")
+}
+
+fn partition_name(disk: u64, partition: usize) -> String {
+    format!("blk{disk}p{partition}")
+}
+
+fn framebuffer_name(number: u32) -> String {
+    format!("fb{number}")
+}
+
+impl<D> SysFs<D>
+where
+    D: Devices,
+{
+    pub fn new(devices: D) -> Self {
+        Self {
+            devices,
+            handles: BTreeMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn devices(&self) -> &D {
+        &self.devices
+    }
+
+    fn pci(&self, addr: PciAddress) -> Result<PciDeviceInfo, OpenError> {
+        self.devices
+            .pci_devices()
+            .into_iter()
+            .find(|device| device.address == addr)
+            .ok_or(OpenError::NotFound)
+    }
+
+    fn block(&self, disk: u32) -> Result<BlockDeviceInfo, OpenError> {
+        self.devices
+            .block_devices()
+            .into_iter()
+            .find(|device| device.number == u64::from(disk))
+            .ok_or(OpenError::NotFound)
+    }
+
+    fn partition(&self, disk: u32, partition: u16) -> Result<PartitionInfo, OpenError> {
+        self.block(disk)?
+            .partitions
+            .into_iter()
+            .find(|p| p.number == usize::from(partition))
+            .ok_or(OpenError::NotFound)
+    }
+
+    fn framebuffer(&self, number: u32) -> Result<FramebufferInfo, OpenError> {
+        self.devices
+            .framebuffers()
+            .into_iter()
+            .find(|fb| fb.number == number)
+            .ok_or(OpenError::NotFound)
+    }
+
+    /// Returns the node with the id `id` if its device still exists.
+    fn node(&self, id: FsNodeId) -> Result<Node, OpenError> {
+        let node = Node::from_id(id).ok_or(OpenError::NotFound)?;
+        match node {
+            Node::Dir(_) => {}
+            Node::Pci(addr, _) => {
+                self.pci(addr)?;
+            }
+            Node::Block(disk, 0, _) => {
+                self.block(disk)?;
+            }
+            Node::Block(disk, partition, _) => {
+                self.partition(disk, partition)?;
+            }
+            Node::Framebuffer(number, _) => {
+                self.framebuffer(number)?;
+            }
+        }
+        Ok(node)
+    }
+
+    fn generate(&self, node: Node) -> Option<Vec<u8>> {
+        let content = match node {
+            Node::Dir(_) => String::new(),
+            Node::Pci(addr, attr) => content::pci(&self.pci(addr).ok()?, attr),
+            Node::Block(disk, 0, attr) => content::block(&self.block(disk).ok()?, attr),
+            Node::Block(disk, partition, attr) => {
+                content::partition(&self.partition(disk, partition).ok()?, attr)
+            }
+            Node::Framebuffer(number, attr) => {
+                content::framebuffer(&self.framebuffer(number).ok()?, attr)
+            }
+        };
+        Some(content.into_bytes())
+    }
+
+    /// The entries of the directory `dir`, with their nodes.
+    fn entries(&self, dir: Node) -> Result<Vec<(String, Node)>, OpenError> {
+        Ok(match dir {
+            Node::Dir(Dir::PciDevices) => self
+                .devices
+                .pci_devices()
+                .into_iter()
+                .map(|device| {
+                    (
+                        pci_name(device.address),
+                        Node::Pci(device.address, PciAttr::Dir),
+                    )
+                })
+                .collect(),
+            Node::Dir(Dir::Block) => self
+                .devices
+                .block_devices()
+                .into_iter()
+                .filter_map(|device| {
+                    let disk = u32::try_from(device.number).ok()?;
+                    Some((
+                        block_name(device.number),
+                        Node::Block(disk, 0, BlockAttr::Dir),
+                    ))
+                })
+                .collect(),
+            Node::Dir(Dir::Graphics) => self
+                .devices
+                .framebuffers()
+                .into_iter()
+                .map(|fb| {
+                    (
+                        framebuffer_name(fb.number),
+                        Node::Framebuffer(fb.number, FramebufferAttr::Dir),
+                    )
+                })
+                .collect(),
+            Node::Dir(dir) => dir
+                .children()
+                .iter()
+                .map(|(name, child)| (name.to_string(), Node::Dir(*child)))
+                .collect(),
+            Node::Pci(addr, PciAttr::Dir) => PciAttr::ALL
+                .into_iter()
+                .map(|(name, attr)| (name.to_string(), Node::Pci(addr, attr)))
+                .collect(),
+            Node::Block(disk, 0, BlockAttr::Dir) => {
+                let partitions = self
+                    .block(disk)?
+                    .partitions
+                    .into_iter()
+                    .filter_map(|partition| {
+                        let number = u16::try_from(partition.number).ok()?;
+                        Some((
+                            partition_name(u64::from(disk), partition.number),
+                            Node::Block(disk, number, BlockAttr::Dir),
+                        ))
+                    });
+                BlockAttr::DISK
+                    .into_iter()
+                    .map(|(name, attr)| (name.to_string(), Node::Block(disk, 0, attr)))
+                    .chain(partitions)
+                    .collect()
+            }
+            Node::Block(disk, partition, BlockAttr::Dir) => BlockAttr::PARTITION
+                .into_iter()
+                .map(|(name, attr)| (name.to_string(), Node::Block(disk, partition, attr)))
+                .collect(),
+            Node::Framebuffer(number, FramebufferAttr::Dir) => FramebufferAttr::ALL
+                .into_iter()
+                .map(|(name, attr)| (name.to_string(), Node::Framebuffer(number, attr)))
+                .collect(),
+            Node::Pci(..) | Node::Block(..) | Node::Framebuffer(..) => {
+                return Err(OpenError::NotDirectory);
+            }
+        })
+    }
+
+    fn handle_node(&mut self, handle: FsHandle) -> Result<&mut OpenNode, FsError> {
+        self.handles.get_mut(&handle).ok_or(FsError::InvalidHandle)
+    }
+}
+
+impl<D> FileSystem for SysFs<D>
+where
+    D: Devices,
+{
+    fn root(&self) -> FsNodeId {
+        Node::Dir(Dir::Root).id()
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let dir = self.node(dir)?;
+        let node = self
+            .entries(dir)?
+            .into_iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, node)| node)
+            .ok_or(OpenError::NotFound)?;
+        Ok((node.id(), node.kind()))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        let dir = self.node(dir)?;
+        Ok(self
+            .entries(dir)?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        let node = self.node(node)?;
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.handles.insert(
+            handle,
+            OpenNode {
+                node,
+                content: None,
+            },
+        );
+        Ok(handle)
+    }
+
+    fn readlink(&mut self, node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        self.node(node)?;
+        Err(ReadlinkError::NotSymlink)
+    }
+
+    fn create(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::ReadOnly)
+    }
+
+    fn symlink(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _target: &Path,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::ReadOnly)
+    }
+
+    fn unlink(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::ReadOnly)
+    }
+
+    fn rmdir(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::ReadOnly)
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        self.handles
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or(CloseError::NotOpen)
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let node = self.handle_node(handle)?.node;
+        if node.kind() != FsNodeKind::File {
+            return Err(ReadError::NotReadable);
+        }
+        if offset == 0 || self.handle_node(handle)?.content.is_none() {
+            // the device may have gone away since the file was opened
+            let content = self.generate(node).ok_or(ReadError::ReadFailed)?;
+            self.handle_node(handle)?.content = Some(content);
+        }
+
+        let content = self.handle_node(handle)?.content.as_deref().unwrap();
+        if offset >= content.len() {
+            return Err(ReadError::EndOfFile);
+        }
+        let len = buf.len().min(content.len() - offset);
+        buf[..len].copy_from_slice(&content[offset..offset + len]);
+        Ok(len)
+    }
+
+    fn write(
+        &mut self,
+        handle: FsHandle,
+        _buf: &[u8],
+        _offset: usize,
+    ) -> Result<usize, WriteError> {
+        self.handle_node(handle)?;
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let open = self.handle_node(handle)?;
+        let node = open.node;
+        // like in procfs, files have no size until they are read
+        let size = open.content.as_ref().map_or(0, Vec::len);
+        let (mode, nlink) = match node.kind() {
+            FsNodeKind::Directory => (S_IFDIR | 0o555, 2),
+            FsNodeKind::File | FsNodeKind::Symlink => (S_IFREG | 0o444, 1),
+        };
+        *stat = Stat {
+            size,
+            ino: node.id().get(),
+            mode,
+            nlink,
+            blocks: 0,
+        };
+        Ok(())
+    }
+
+    fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
+        // there's nothing to write back
+        self.handle_node(handle)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use kernel_pci::{Bar, BarKind, PciAddress};
+    use kernel_vfs::fs::{FileSystem, FsNodeKind};
+    use kernel_vfs::path::{AbsolutePath, ROOT};
+    use kernel_vfs::{CreateError, OpenError, ReadError, Vfs, WriteError};
+
+    use super::SysFs;
+    use crate::{
+        BlockDeviceInfo, Devices, DriverStatus, FramebufferInfo, PartitionInfo, PciDeviceInfo,
+    };
+
+    struct TestDevices {
+        pci: Vec<PciDeviceInfo>,
+        block: Vec<BlockDeviceInfo>,
+    }
+
+    fn pci_device(address: PciAddress, device_id: u16, status: DriverStatus) -> PciDeviceInfo {
+        PciDeviceInfo {
+            address,
+            vendor_id: 0x1af4,
+            device_id,
+            class: 0x01,
+            subclass: 0x00,
+            prog_if: 0x00,
+            revision_id: 0x01,
+            subsystem_vendor_id: 0x1af4,
+            subsystem_id: 0x0002,
+            bars: vec![],
+            driver: None,
+            status,
+        }
+    }
+
+    impl Devices for TestDevices {
+        fn pci_devices(&self) -> Vec<PciDeviceInfo> {
+            self.pci.clone()
+        }
+
+        fn block_devices(&self) -> Vec<BlockDeviceInfo> {
+            self.block.clone()
+        }
+
+        fn framebuffers(&self) -> Vec<FramebufferInfo> {
+            vec![FramebufferInfo {
+                number: 0,
+                device_id: 5,
+                width: 1280,
+                height: 800,
+                stride: 5120,
+                bits_per_pixel: 32,
+            }]
+        }
+    }
+
+    fn devices() -> TestDevices {
+        let bridge = pci_device(PciAddress::new(0, 0, 0), 0x1237, DriverStatus::Unbound);
+        let block = PciDeviceInfo {
+            bars: vec![
+                Bar {
+                    index: 0,
+                    kind: BarKind::Io,
+                    address: 0xc040,
+                    prefetchable: false,
+                },
+                Bar {
+                    index: 4,
+                    kind: BarKind::Memory64,
+                    address: 0xfe00_0000,
+                    prefetchable: true,
+                },
+            ],
+            driver: Some(String::from("virtio-blk")),
+            ..pci_device(PciAddress::new(0, 4, 0), 0x1001, DriverStatus::Bound)
+        };
+        let broken = PciDeviceInfo {
+            driver: Some(String::from("virtio-gpu")),
+            ..pci_device(
+                PciAddress::new(0, 0x1f, 2),
+                0x1050,
+                DriverStatus::Failed(String::from("no memory")),
+            )
+        };
+        TestDevices {
+            pci: vec![bridge, block, broken],
+            block: vec![BlockDeviceInfo {
+                number: 0,
+                device_id: 3,
+                sector_size: 512,
+                sector_count: 131_072,
+                partitions: vec![PartitionInfo {
+                    number: 1,
+                    start: 2048,
+                    sector_count: 129_024,
+                }],
+            }],
+        }
+    }
+
+    fn vfs() -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, SysFs::new(devices())).unwrap();
+        vfs
+    }
+
+    fn read(vfs: &Vfs, file: &str) -> String {
+        let node = vfs.open(AbsolutePath::try_new(file).unwrap()).unwrap();
+        let mut content = vec![];
+        let mut buf = [0; 16];
+        loop {
+            match node.read(&mut buf[..], content.len()) {
+                Ok(n) => content.extend_from_slice(&buf[..n]),
+                Err(ReadError::EndOfFile) => break,
+                Err(e) => panic!("reading {file} failed: {e:?}"),
+            }
+        }
+        String::from_utf8(content).unwrap()
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let mut fs = SysFs::new(devices());
+        let root = fs.root();
+        assert_eq!(vec!["block", "bus", "class"], fs.read_dir(root).unwrap());
+
+        let (bus, _) = fs.lookup(root, "bus").unwrap();
+        let (pci, _) = fs.lookup(bus, "pci").unwrap();
+        let (devices, _) = fs.lookup(pci, "devices").unwrap();
+        assert_eq!(
+            vec!["0000:00:00.0", "0000:00:04.0", "0000:00:1f.2"],
+            fs.read_dir(devices).unwrap()
+        );
+        let (device, kind) = fs.lookup(devices, "0000:00:04.0").unwrap();
+        assert_eq!(FsNodeKind::Directory, kind);
+        assert_eq!(
+            vec![
+                "class",
+                "device",
+                "driver",
+                "resource",
+                "revision",
+                "status",
+                "subsystem_device",
+                "subsystem_vendor",
+                "vendor"
+            ],
+            fs.read_dir(device).unwrap()
+        );
+
+        let (block, _) = fs.lookup(root, "block").unwrap();
+        assert_eq!(vec!["blk0"], fs.read_dir(block).unwrap());
+        let (blk0, _) = fs.lookup(block, "blk0").unwrap();
+        assert_eq!(
+            vec!["dev", "sector_size", "size", "blk0p1"],
+            fs.read_dir(blk0).unwrap()
+        );
+        let (blk0p1, _) = fs.lookup(blk0, "blk0p1").unwrap();
+        assert_eq!(
+            vec!["partition", "size", "start"],
+            fs.read_dir(blk0p1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_lookup_missing() {
+        let mut fs = SysFs::new(devices());
+        let root = fs.root();
+        for name in ["pci", "blk0", ""] {
+            assert_eq!(Err(OpenError::NotFound), fs.lookup(root, name), "{name}");
+        }
+        let (block, _) = fs.lookup(root, "block").unwrap();
+        let (blk0, _) = fs.lookup(block, "blk0").unwrap();
+        assert_eq!(Err(OpenError::NotFound), fs.lookup(blk0, "start"));
+        assert_eq!(Err(OpenError::NotFound), fs.lookup(blk0, "blk0p2"));
+        let (size, _) = fs.lookup(blk0, "size").unwrap();
+        assert_eq!(Err(OpenError::NotDirectory), fs.lookup(size, "x"));
+        assert_eq!(Err(OpenError::NotDirectory), fs.read_dir(size));
+    }
+
+    #[test]
+    fn test_pci_attributes() {
+        let vfs = vfs();
+        let dir = "/bus/pci/devices/0000:00:04.0";
+        for (attr, value) in [
+            ("vendor", "0x1af4\n"),
+            ("device", "0x1001\n"),
+            ("class", "0x010000\n"),
+            ("revision", "0x01\n"),
+            ("subsystem_vendor", "0x1af4\n"),
+            ("subsystem_device", "0x0002\n"),
+            ("driver", "virtio-blk\n"),
+            ("status", "bound\n"),
+            (
+                "resource",
+                "0 io 0x000000000000c040\n4 mem64 0x00000000fe000000 prefetchable\n",
+            ),
+        ] {
+            assert_eq!(value, read(&vfs, &alloc::format!("{dir}/{attr}")), "{attr}");
+        }
+    }
+
+    #[test]
+    fn test_driver_status() {
+        let vfs = vfs();
+        assert_eq!("", read(&vfs, "/bus/pci/devices/0000:00:00.0/driver"));
+        assert_eq!(
+            "unbound\n",
+            read(&vfs, "/bus/pci/devices/0000:00:00.0/status")
+        );
+        assert_eq!(
+            "virtio-gpu\n",
+            read(&vfs, "/bus/pci/devices/0000:00:1f.2/driver")
+        );
+        assert_eq!(
+            "failed: no memory\n",
+            read(&vfs, "/bus/pci/devices/0000:00:1f.2/status")
+        );
+    }
+
+    #[test]
+    fn test_block_attributes() {
+        let vfs = vfs();
+        assert_eq!("3\n", read(&vfs, "/block/blk0/dev"));
+        assert_eq!("512\n", read(&vfs, "/block/blk0/sector_size"));
+        assert_eq!("131072\n", read(&vfs, "/block/blk0/size"));
+        assert_eq!("1\n", read(&vfs, "/block/blk0/blk0p1/partition"));
+        assert_eq!("2048\n", read(&vfs, "/block/blk0/blk0p1/start"));
+        assert_eq!("129024\n", read(&vfs, "/block/blk0/blk0p1/size"));
+    }
+
+    #[test]
+    fn test_framebuffer_attributes() {
+        let vfs = vfs();
+        assert_eq!("5\n", read(&vfs, "/class/graphics/fb0/dev"));
+        assert_eq!("1280\n", read(&vfs, "/class/graphics/fb0/width"));
+        assert_eq!("800\n", read(&vfs, "/class/graphics/fb0/height"));
+        assert_eq!("5120\n", read(&vfs, "/class/graphics/fb0/stride"));
+        assert_eq!("32\n", read(&vfs, "/class/graphics/fb0/bits_per_pixel"));
+    }
+
+    #[test]
+    fn test_device_removed() {
+        let mut fs = SysFs::new(devices());
+        let root = fs.root();
+        let (block, _) = fs.lookup(root, "block").unwrap();
+        let (blk0, _) = fs.lookup(block, "blk0").unwrap();
+        let (size, _) = fs.lookup(blk0, "size").unwrap();
+        let handle = fs.open(size).unwrap();
+
+        fs.devices.block.clear();
+        assert_eq!(Err(ReadError::ReadFailed), fs.read(handle, &mut [0; 8], 0));
+        fs.close(handle).unwrap();
+        assert_eq!(Err(OpenError::NotFound), fs.open(size));
+        assert!(fs.read_dir(block).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_only() {
+        let mut fs = SysFs::new(devices());
+        let root = fs.root();
+        assert_eq!(
+            Err(CreateError::ReadOnly),
+            fs.create(root, "new", FsNodeKind::File)
+        );
+        let handle = fs.open(root).unwrap();
+        assert_eq!(Err(ReadError::NotReadable), fs.read(handle, &mut [0; 4], 0));
+        assert_eq!(Err(WriteError::NotWritable), fs.write(handle, b"x", 0));
+        fs.close(handle).unwrap();
+    }
+}
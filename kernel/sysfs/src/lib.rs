@@ -0,0 +1,10 @@
+#![no_std]
+extern crate alloc;
+
+mod content;
+mod devices;
+mod fs;
+mod node;
+
+pub use devices::*;
+pub use fs::*;
@@ -0,0 +1,301 @@
+use kernel_pci::PciAddress;
+use kernel_vfs::fs::{FsNodeId, FsNodeKind};
+
+const CLASS_SHIFT: u64 = 56;
+const DEVICE_SHIFT: u64 = 8;
+const DEVICE_MASK: u64 = (1 << (CLASS_SHIFT - DEVICE_SHIFT)) - 1;
+
+const CLASS_DIR: u64 = 0;
+const CLASS_PCI: u64 = 1;
+const CLASS_BLOCK: u64 = 2;
+const CLASS_FRAMEBUFFER: u64 = 3;
+
+/// A node of the file system.
+///
+/// The id of a node encodes the node itself, so the file system has no
+/// state besides its open handles. The top byte of the id is the class of
+/// the node, the bits below it identify the device, and the lowest byte is
+/// the attribute of the device, which is zero for the directory of the
+/// device itself.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Node {
+    Dir(Dir),
+    Pci(PciAddress, PciAttr),
+    /// A block device and a partition of it, where partition 0 is the
+    /// whole device.
+    Block(u32, u16, BlockAttr),
+    Framebuffer(u32, FramebufferAttr),
+}
+
+/// The directories that exist independently of the devices.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Dir {
+    Root = 1,
+    Block,
+    Bus,
+    Pci,
+    PciDevices,
+    Class,
+    Graphics,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum PciAttr {
+    Dir,
+    Class,
+    Device,
+    Driver,
+    Resource,
+    Revision,
+    Status,
+    SubsystemDevice,
+    SubsystemVendor,
+    Vendor,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum BlockAttr {
+    Dir,
+    Dev,
+    Partition,
+    SectorSize,
+    Size,
+    Start,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum FramebufferAttr {
+    Dir,
+    BitsPerPixel,
+    Dev,
+    Height,
+    Stride,
+    Width,
+}
+
+impl Dir {
+    const ALL: [Dir; 7] = [
+        Dir::Root,
+        Dir::Block,
+        Dir::Bus,
+        Dir::Pci,
+        Dir::PciDevices,
+        Dir::Class,
+        Dir::Graphics,
+    ];
+
+    /// The directories in this one, which are all of its entries unless it
+    /// lists devices.
+    pub fn children(self) -> &'static [(&'static str, Dir)] {
+        match self {
+            Dir::Root => &[
+                ("block", Dir::Block),
+                ("bus", Dir::Bus),
+                ("class", Dir::Class),
+            ],
+            Dir::Bus => &[("pci", Dir::Pci)],
+            Dir::Pci => &[("devices", Dir::PciDevices)],
+            Dir::Class => &[("graphics", Dir::Graphics)],
+            Dir::Block | Dir::PciDevices | Dir::Graphics => &[],
+        }
+    }
+}
+
+impl PciAttr {
+    pub const ALL: [(&str, PciAttr); 9] = [
+        ("class", PciAttr::Class),
+        ("device", PciAttr::Device),
+        ("driver", PciAttr::Driver),
+        ("resource", PciAttr::Resource),
+        ("revision", PciAttr::Revision),
+        ("status", PciAttr::Status),
+        ("subsystem_device", PciAttr::SubsystemDevice),
+        ("subsystem_vendor", PciAttr::SubsystemVendor),
+        ("vendor", PciAttr::Vendor),
+    ];
+
+    fn from_id(id: u64) -> Option<Self> {
+        if id == PciAttr::Dir as u64 {
+            return Some(PciAttr::Dir);
+        }
+        Self::ALL
+            .into_iter()
+            .map(|(_, attr)| attr)
+            .find(|attr| *attr as u64 == id)
+    }
+}
+
+impl BlockAttr {
+    /// The attributes of a whole device.
+    pub const DISK: [(&str, BlockAttr); 3] = [
+        ("dev", BlockAttr::Dev),
+        ("sector_size", BlockAttr::SectorSize),
+        ("size", BlockAttr::Size),
+    ];
+
+    /// The attributes of a partition.
+    pub const PARTITION: [(&str, BlockAttr); 3] = [
+        ("partition", BlockAttr::Partition),
+        ("size", BlockAttr::Size),
+        ("start", BlockAttr::Start),
+    ];
+
+    pub fn all(partition: u16) -> &'static [(&'static str, BlockAttr)] {
+        if partition == 0 {
+            &Self::DISK
+        } else {
+            &Self::PARTITION
+        }
+    }
+
+    fn from_id(id: u64, partition: u16) -> Option<Self> {
+        if id == BlockAttr::Dir as u64 {
+            return Some(BlockAttr::Dir);
+        }
+        Self::all(partition)
+            .iter()
+            .map(|(_, attr)| *attr)
+            .find(|attr| *attr as u64 == id)
+    }
+}
+
+impl FramebufferAttr {
+    pub const ALL: [(&str, FramebufferAttr); 5] = [
+        ("bits_per_pixel", FramebufferAttr::BitsPerPixel),
+        ("dev", FramebufferAttr::Dev),
+        ("height", FramebufferAttr::Height),
+        ("stride", FramebufferAttr::Stride),
+        ("width", FramebufferAttr::Width),
+    ];
+
+    fn from_id(id: u64) -> Option<Self> {
+        if id == FramebufferAttr::Dir as u64 {
+            return Some(FramebufferAttr::Dir);
+        }
+        Self::ALL
+            .into_iter()
+            .map(|(_, attr)| attr)
+            .find(|attr| *attr as u64 == id)
+    }
+}
+
+fn pci_key(addr: PciAddress) -> u64 {
+    (u64::from(addr.bus) << 8) | (u64::from(addr.device & 0x1F) << 3) | u64::from(addr.function & 7)
+}
+
+fn pci_address(key: u64) -> Option<PciAddress> {
+    let key = u16::try_from(key).ok()?;
+    let [bus, slot] = key.to_be_bytes();
+    Some(PciAddress::new(bus, slot >> 3, slot & 7))
+}
+
+impl Node {
+    pub fn id(self) -> FsNodeId {
+        let (class, device, attr) = match self {
+            Node::Dir(dir) => (CLASS_DIR, 0, dir as u64),
+            Node::Pci(addr, attr) => (CLASS_PCI, pci_key(addr), attr as u64),
+            Node::Block(disk, partition, attr) => (
+                CLASS_BLOCK,
+                (u64::from(disk) << 16) | u64::from(partition),
+                attr as u64,
+            ),
+            Node::Framebuffer(number, attr) => (CLASS_FRAMEBUFFER, u64::from(number), attr as u64),
+        };
+        FsNodeId::from((class << CLASS_SHIFT) | (device << DEVICE_SHIFT) | attr)
+    }
+
+    pub fn from_id(id: FsNodeId) -> Option<Self> {
+        let id = id.get();
+        let device = (id >> DEVICE_SHIFT) & DEVICE_MASK;
+        let attr = id & 0xFF;
+        match id >> CLASS_SHIFT {
+            CLASS_DIR if device == 0 => Dir::ALL
+                .into_iter()
+                .find(|dir| *dir as u64 == attr)
+                .map(Node::Dir),
+            CLASS_PCI => Some(Node::Pci(pci_address(device)?, PciAttr::from_id(attr)?)),
+            CLASS_BLOCK => {
+                let disk = u32::try_from(device >> 16).ok()?;
+                let partition = (device & 0xFFFF) as u16;
+                Some(Node::Block(
+                    disk,
+                    partition,
+                    BlockAttr::from_id(attr, partition)?,
+                ))
+            }
+            CLASS_FRAMEBUFFER => Some(Node::Framebuffer(
+                u32::try_from(device).ok()?,
+                FramebufferAttr::from_id(attr)?,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn kind(self) -> FsNodeKind {
+        match self {
+            Node::Dir(_)
+            | Node::Pci(_, PciAttr::Dir)
+            | Node::Block(_, _, BlockAttr::Dir)
+            | Node::Framebuffer(_, FramebufferAttr::Dir) => FsNodeKind::Directory,
+            Node::Pci(..) | Node::Block(..) | Node::Framebuffer(..) => FsNodeKind::File,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_round_trip() {
+        let mut nodes = alloc::vec::Vec::new();
+        nodes.extend(Dir::ALL.map(Node::Dir));
+        for addr in [
+            PciAddress::new(0, 0, 0),
+            PciAddress::new(0, 4, 0),
+            PciAddress::new(0xFF, 31, 7),
+        ] {
+            nodes.push(Node::Pci(addr, PciAttr::Dir));
+            nodes.extend(PciAttr::ALL.map(|(_, attr)| Node::Pci(addr, attr)));
+        }
+        for disk in [0, 1, u32::MAX] {
+            for partition in [0, 1, 128, u16::MAX] {
+                nodes.push(Node::Block(disk, partition, BlockAttr::Dir));
+                nodes.extend(
+                    BlockAttr::all(partition)
+                        .iter()
+                        .map(|(_, attr)| Node::Block(disk, partition, *attr)),
+                );
+            }
+        }
+        for number in [0, 1, u32::MAX] {
+            nodes.push(Node::Framebuffer(number, FramebufferAttr::Dir));
+            nodes.extend(FramebufferAttr::ALL.map(|(_, attr)| Node::Framebuffer(number, attr)));
+        }
+
+        for node in &nodes {
+            assert_eq!(Some(*node), Node::from_id(node.id()), "{node:?}");
+        }
+        for (i, a) in nodes.iter().enumerate() {
+            for b in &nodes[i + 1..] {
+                assert_ne!(a.id(), b.id(), "{a:?} and {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_ids() {
+        let partition_attr_of_disk =
+            (CLASS_BLOCK << CLASS_SHIFT) | (1 << 16 << DEVICE_SHIFT) | BlockAttr::Start as u64;
+        for id in [
+            0,
+            8,
+            0x100 | 1,
+            0xFF,
+            partition_attr_of_disk,
+            4 << CLASS_SHIFT,
+        ] {
+            assert_eq!(None, Node::from_id(FsNodeId::from(id)), "{id:#x}");
+        }
+    }
+}
@@ -0,0 +1,579 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use kernel_vfs::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use kernel_vfs::path::{OwnedPath, Path};
+use kernel_vfs::{
+    CloseError, CreateError, FsError, FsyncError, MmapError, MmapRegion, OpenError, PAGE_SIZE,
+    ReadError, ReadlinkError, Stat, StatError, UnlinkError, WriteError,
+};
+
+use crate::node::{TmpNode, TmpNodeKind};
+use crate::page::{FileData, PageAllocator};
+
+const ROOT_NODE_ID: u64 = 0;
+
+/// An in-memory file system with regular files, directories and symlinks.
+///
+/// File contents live in pages from a [`PageAllocator`], and all files
+/// together may not take up more than the size that the file system was
+/// created with.
+pub struct TmpFs<A: PageAllocator> {
+    allocator: A,
+    max_size: usize,
+    used: usize,
+    nodes: BTreeMap<FsNodeId, TmpNode>,
+    next_node_id: u64,
+    handles: BTreeMap<FsHandle, FsNodeId>,
+    next_handle: u64,
+}
+
+impl<A> TmpFs<A>
+where
+    A: PageAllocator,
+{
+    /// Creates an empty file system whose files may take up at most
+    /// `max_size` bytes. File contents are charged in whole pages.
+    pub fn new(allocator: A, max_size: usize) -> Self {
+        let mut nodes = BTreeMap::new();
+        // everybody may create files in the root, but only remove their own
+        nodes.insert(
+            FsNodeId::from(ROOT_NODE_ID),
+            TmpNode::new(TmpNodeKind::Directory(BTreeMap::new()), 0o1777),
+        );
+        Self {
+            allocator,
+            max_size,
+            used: 0,
+            nodes,
+            next_node_id: 1,
+            handles: BTreeMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// Returns the number of bytes that the contents of all files and
+    /// symlinks take up.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    #[must_use]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    fn node(&self, id: FsNodeId) -> Result<&TmpNode, OpenError> {
+        self.nodes.get(&id).ok_or(OpenError::NotFound)
+    }
+
+    fn node_mut(&mut self, id: FsNodeId) -> Result<&mut TmpNode, OpenError> {
+        self.nodes.get_mut(&id).ok_or(OpenError::NotFound)
+    }
+
+    fn handle_node(&self, handle: FsHandle) -> Result<FsNodeId, FsError> {
+        self.handles
+            .get(&handle)
+            .copied()
+            .ok_or(FsError::InvalidHandle)
+    }
+
+    fn directory_mut(
+        &mut self,
+        dir: FsNodeId,
+    ) -> Result<&mut BTreeMap<String, FsNodeId>, OpenError> {
+        self.node_mut(dir)?
+            .directory_mut()
+            .ok_or(OpenError::NotDirectory)
+    }
+
+    /// Charges `bytes` against the size limit of the file system.
+    fn reserve(&mut self, bytes: usize) -> bool {
+        if self.used + bytes > self.max_size {
+            return false;
+        }
+        self.used += bytes;
+        true
+    }
+
+    /// Adds `node` to the directory `dir` under `name`.
+    fn insert(
+        &mut self,
+        dir: FsNodeId,
+        name: &str,
+        node: TmpNode,
+    ) -> Result<FsNodeId, CreateError> {
+        let is_directory = node.directory().is_some();
+        let id = FsNodeId::from(self.next_node_id);
+        let entries = self.directory_mut(dir)?;
+        if entries.contains_key(name) {
+            return Err(CreateError::AlreadyExists);
+        }
+
+        entries.insert(name.to_string(), id);
+        self.next_node_id += 1;
+        if is_directory {
+            // the `..` entry of the new directory
+            self.node_mut(dir)?.nlink += 1;
+        }
+        self.nodes.insert(id, node);
+        Ok(id)
+    }
+
+    /// Removes the entry `name` from `dir` and returns the node that it
+    /// referred to.
+    fn remove(&mut self, dir: FsNodeId, name: &str) -> Result<FsNodeId, UnlinkError> {
+        self.directory_mut(dir)?
+            .remove(name)
+            .ok_or(UnlinkError::NotFound)
+    }
+
+    /// Frees `id` if it is neither linked anywhere nor open anymore.
+    fn release(&mut self, id: FsNodeId) {
+        if self
+            .nodes
+            .get(&id)
+            .is_none_or(|node| node.nlink > 0 || node.open > 0)
+        {
+            return;
+        }
+        let Some(mut node) = self.nodes.remove(&id) else {
+            return;
+        };
+        match &mut node.kind {
+            TmpNodeKind::File(data) => {
+                self.used -= data.page_count() * PAGE_SIZE;
+                data.free(&self.allocator);
+            }
+            TmpNodeKind::Symlink(target) => self.used -= target.len(),
+            TmpNodeKind::Directory(_) => {}
+        }
+    }
+}
+
+impl<A> Drop for TmpFs<A>
+where
+    A: PageAllocator,
+{
+    fn drop(&mut self) {
+        for node in self.nodes.values_mut() {
+            if let TmpNodeKind::File(data) = &mut node.kind {
+                data.free(&self.allocator);
+            }
+        }
+    }
+}
+
+impl<A> FileSystem for TmpFs<A>
+where
+    A: PageAllocator,
+{
+    fn root(&self) -> FsNodeId {
+        FsNodeId::from(ROOT_NODE_ID)
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let id = *self
+            .node(dir)?
+            .directory()
+            .ok_or(OpenError::NotDirectory)?
+            .get(name)
+            .ok_or(OpenError::NotFound)?;
+        Ok((id, self.node(id)?.fs_node_kind()))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        Ok(self
+            .node(dir)?
+            .directory()
+            .ok_or(OpenError::NotDirectory)?
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        self.node_mut(node)?.open += 1;
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.handles.insert(handle, node);
+        Ok(handle)
+    }
+
+    fn readlink(&mut self, node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        match &self.node(node)?.kind {
+            TmpNodeKind::Symlink(target) => Ok(target.clone()),
+            _ => Err(ReadlinkError::NotSymlink),
+        }
+    }
+
+    fn create(
+        &mut self,
+        dir: FsNodeId,
+        name: &str,
+        kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        let node = match kind {
+            FsNodeKind::File => TmpNode::new(TmpNodeKind::File(FileData::default()), 0o644),
+            FsNodeKind::Directory => TmpNode::new(TmpNodeKind::Directory(BTreeMap::new()), 0o755),
+            FsNodeKind::Symlink => return Err(CreateError::NotSupported),
+        };
+        self.insert(dir, name, node)
+    }
+
+    fn symlink(
+        &mut self,
+        dir: FsNodeId,
+        name: &str,
+        target: &Path,
+    ) -> Result<FsNodeId, CreateError> {
+        if !self.reserve(target.len()) {
+            return Err(CreateError::NoSpace);
+        }
+        let node = TmpNode::new(TmpNodeKind::Symlink(target.to_owned()), 0o777);
+        self.insert(dir, name, node).inspect_err(|_| {
+            self.used -= target.len();
+        })
+    }
+
+    fn unlink(&mut self, dir: FsNodeId, name: &str) -> Result<(), UnlinkError> {
+        let (id, kind) = self.lookup(dir, name)?;
+        if kind == FsNodeKind::Directory {
+            return Err(UnlinkError::IsDirectory);
+        }
+        self.remove(dir, name)?;
+        self.node_mut(id)?.nlink -= 1;
+        self.release(id);
+        Ok(())
+    }
+
+    fn rmdir(&mut self, dir: FsNodeId, name: &str) -> Result<(), UnlinkError> {
+        let (id, _) = self.lookup(dir, name)?;
+        let entries = self
+            .node(id)?
+            .directory()
+            .ok_or(UnlinkError::NotDirectory)?;
+        if !entries.is_empty() {
+            return Err(UnlinkError::NotEmpty);
+        }
+        self.remove(dir, name)?;
+        self.node_mut(dir)?.nlink -= 1;
+        self.node_mut(id)?.nlink = 0;
+        self.release(id);
+        Ok(())
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        let id = self.handles.remove(&handle).ok_or(CloseError::NotOpen)?;
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.open -= 1;
+        }
+        self.release(id);
+        Ok(())
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let id = self.handle_node(handle)?;
+        let TmpNodeKind::File(data) = &self.nodes[&id].kind else {
+            return Err(ReadError::NotReadable);
+        };
+        if offset >= data.len() {
+            return Err(ReadError::EndOfFile);
+        }
+        Ok(data.read(buf, offset))
+    }
+
+    fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
+        let id = self.handle_node(handle)?;
+        let end = offset.checked_add(buf.len()).ok_or(WriteError::NoSpace)?;
+        let node = self.nodes.get_mut(&id).ok_or(FsError::InvalidHandle)?;
+        let TmpNodeKind::File(data) = &mut node.kind else {
+            return Err(WriteError::NotWritable);
+        };
+
+        let missing = end.div_ceil(PAGE_SIZE).saturating_sub(data.page_count()) * PAGE_SIZE;
+        if self.used + missing > self.max_size {
+            return Err(WriteError::NoSpace);
+        }
+        let pages_before = data.page_count();
+        let grown = data.grow(end, &self.allocator);
+        self.used += (data.page_count() - pages_before) * PAGE_SIZE;
+        if !grown {
+            return Err(WriteError::NoSpace);
+        }
+
+        data.write(buf, offset);
+        Ok(buf.len())
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let id = self.handle_node(handle)?;
+        let node = &self.nodes[&id];
+        let (size, pages) = match &node.kind {
+            TmpNodeKind::File(data) => (data.len(), data.page_count()),
+            TmpNodeKind::Directory(entries) => (entries.len(), 0),
+            TmpNodeKind::Symlink(target) => (target.len(), 0),
+        };
+        *stat = Stat {
+            size,
+            ino: id.get(),
+            mode: node.mode(),
+            nlink: node.nlink,
+            blocks: (pages * PAGE_SIZE / 512) as u64,
+        };
+        Ok(())
+    }
+
+    fn mmap(&mut self, handle: FsHandle) -> Result<MmapRegion, MmapError> {
+        let id = self.handle_node(handle)?;
+        match &self.nodes[&id].kind {
+            TmpNodeKind::File(data) => Ok(data.mmap()),
+            _ => Err(MmapError::NotSupported),
+        }
+    }
+
+    fn fsync(&mut self, handle: FsHandle) -> Result<(), FsyncError> {
+        self.handle_node(handle)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::alloc::{Layout, alloc_zeroed, dealloc};
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::ptr::NonNull;
+
+    use kernel_abi::{MountFlags, S_IFDIR, S_IFLNK, S_IFREG};
+    use kernel_vfs::fs::{FileSystem, FsNodeKind};
+    use kernel_vfs::path::{AbsolutePath, Path, ROOT};
+    use kernel_vfs::{
+        CreateError, MountOptions, PAGE_SIZE, ReadError, Stat, UnlinkError, Vfs, WriteError,
+    };
+    use spin::RwLock;
+
+    use super::TmpFs;
+    use crate::PageAllocator;
+
+    // SAFETY: PAGE_SIZE is a power of two and a multiple of itself
+    const LAYOUT: Layout = unsafe { Layout::from_size_align_unchecked(PAGE_SIZE, PAGE_SIZE) };
+
+    struct HeapPageAllocator;
+
+    impl PageAllocator for HeapPageAllocator {
+        fn allocate(&self) -> Option<NonNull<u8>> {
+            NonNull::new(unsafe { alloc_zeroed(LAYOUT) })
+        }
+
+        unsafe fn deallocate(&self, page: NonNull<u8>) {
+            unsafe { dealloc(page.as_ptr(), LAYOUT) };
+        }
+    }
+
+    fn path(path: &str) -> &AbsolutePath {
+        AbsolutePath::try_new(path).unwrap()
+    }
+
+    fn create_file(fs: &mut TmpFs<HeapPageAllocator>, name: &str, data: &[u8]) {
+        let node = fs.create(fs.root(), name, FsNodeKind::File).unwrap();
+        let handle = fs.open(node).unwrap();
+        assert_eq!(fs.write(handle, data, 0), Ok(data.len()));
+        fs.close(handle).unwrap();
+    }
+
+    #[test]
+    fn test_write_read() {
+        let mut fs = TmpFs::new(HeapPageAllocator, 4 * PAGE_SIZE);
+        let node = fs.create(fs.root(), "file", FsNodeKind::File).unwrap();
+        let handle = fs.open(node).unwrap();
+
+        // across a page boundary, and leaving a hole that reads as zeroes
+        let data = (0..=u8::MAX).cycle().take(PAGE_SIZE).collect::<Vec<_>>();
+        assert_eq!(fs.write(handle, &data, PAGE_SIZE / 2), Ok(PAGE_SIZE));
+
+        let mut buf = vec![0xff_u8; 2 * PAGE_SIZE];
+        assert_eq!(fs.read(handle, &mut buf, 0), Ok(PAGE_SIZE / 2 + PAGE_SIZE));
+        assert!(buf[..PAGE_SIZE / 2].iter().all(|&b| b == 0));
+        assert_eq!(&buf[PAGE_SIZE / 2..PAGE_SIZE / 2 + PAGE_SIZE], &data[..]);
+        assert_eq!(
+            fs.read(handle, &mut buf, PAGE_SIZE / 2 + PAGE_SIZE),
+            Err(ReadError::EndOfFile)
+        );
+        assert_eq!(fs.used(), 2 * PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let mut fs = TmpFs::new(HeapPageAllocator, 2 * PAGE_SIZE);
+        let node = fs.create(fs.root(), "file", FsNodeKind::File).unwrap();
+        let handle = fs.open(node).unwrap();
+
+        assert_eq!(fs.write(handle, &[1; PAGE_SIZE], PAGE_SIZE), Ok(PAGE_SIZE));
+        assert_eq!(
+            fs.write(handle, &[1], 2 * PAGE_SIZE),
+            Err(WriteError::NoSpace)
+        );
+        assert_eq!(
+            fs.symlink(fs.root(), "link", Path::new("file")),
+            Err(CreateError::NoSpace)
+        );
+        // the pages are already allocated, so overwriting doesn't need space
+        assert_eq!(fs.write(handle, &[2; PAGE_SIZE], 0), Ok(PAGE_SIZE));
+    }
+
+    #[test]
+    fn test_directories() {
+        let mut fs = TmpFs::new(HeapPageAllocator, PAGE_SIZE);
+        let dir = fs.create(fs.root(), "dir", FsNodeKind::Directory).unwrap();
+        fs.create(dir, "file", FsNodeKind::File).unwrap();
+        fs.create(dir, "sub", FsNodeKind::Directory).unwrap();
+        assert_eq!(
+            fs.create(dir, "file", FsNodeKind::Directory),
+            Err(CreateError::AlreadyExists)
+        );
+
+        assert_eq!(fs.read_dir(dir).unwrap(), vec!["file", "sub"]);
+        assert_eq!(fs.lookup(dir, "sub").unwrap().1, FsNodeKind::Directory);
+
+        assert_eq!(fs.unlink(fs.root(), "dir"), Err(UnlinkError::IsDirectory));
+        assert_eq!(fs.rmdir(fs.root(), "dir"), Err(UnlinkError::NotEmpty));
+        assert_eq!(fs.rmdir(dir, "file"), Err(UnlinkError::NotDirectory));
+        fs.unlink(dir, "file").unwrap();
+        fs.rmdir(dir, "sub").unwrap();
+        fs.rmdir(fs.root(), "dir").unwrap();
+        assert!(fs.read_dir(fs.root()).unwrap().is_empty());
+        assert_eq!(fs.unlink(fs.root(), "dir"), Err(UnlinkError::NotFound));
+    }
+
+    #[test]
+    fn test_stat() {
+        let mut fs = TmpFs::new(HeapPageAllocator, 2 * PAGE_SIZE);
+        create_file(&mut fs, "file", b"hello");
+        let dir = fs.create(fs.root(), "dir", FsNodeKind::Directory).unwrap();
+        fs.create(dir, "sub", FsNodeKind::Directory).unwrap();
+        let link = fs.symlink(fs.root(), "link", Path::new("file")).unwrap();
+        assert_eq!(fs.readlink(link).unwrap().as_str(), "file");
+
+        let mut stat = |name: &str| {
+            let (node, _) = fs.lookup(fs.root(), name).unwrap();
+            let handle = fs.open(node).unwrap();
+            let mut stat = Stat::default();
+            fs.stat(handle, &mut stat).unwrap();
+            fs.close(handle).unwrap();
+            (node.get(), stat)
+        };
+
+        let (ino, file) = stat("file");
+        assert_eq!(
+            file,
+            Stat {
+                size: 5,
+                ino,
+                mode: S_IFREG | 0o644,
+                nlink: 1,
+                blocks: (PAGE_SIZE / 512) as u64,
+            }
+        );
+        let (_, dir) = stat("dir");
+        assert_eq!((dir.mode, dir.nlink), (S_IFDIR | 0o755, 3));
+        let (_, link) = stat("link");
+        assert_eq!((link.mode, link.size), (S_IFLNK | 0o777, 4));
+    }
+
+    #[test]
+    fn test_unlink_while_open() {
+        let mut fs = TmpFs::new(HeapPageAllocator, PAGE_SIZE);
+        create_file(&mut fs, "file", b"hello");
+        let (node, _) = fs.lookup(fs.root(), "file").unwrap();
+        let handle = fs.open(node).unwrap();
+
+        fs.unlink(fs.root(), "file").unwrap();
+        assert!(fs.lookup(fs.root(), "file").is_err());
+
+        // the file is still usable until it is closed
+        let mut buf = [0; 5];
+        assert_eq!(fs.read(handle, &mut buf, 0), Ok(5));
+        assert_eq!(&buf, b"hello");
+        assert_eq!(fs.used(), PAGE_SIZE);
+
+        fs.close(handle).unwrap();
+        assert_eq!(fs.used(), 0);
+        assert!(fs.open(node).is_err());
+    }
+
+    #[test]
+    fn test_mmap() {
+        let mut fs = TmpFs::new(HeapPageAllocator, 2 * PAGE_SIZE);
+        let node = fs.create(fs.root(), "file", FsNodeKind::File).unwrap();
+        let handle = fs.open(node).unwrap();
+        fs.write(handle, &[7; PAGE_SIZE + 1], 0).unwrap();
+
+        let region = fs.mmap(handle).unwrap();
+        assert_eq!(region.len(), PAGE_SIZE + 1);
+        assert_eq!(region.pages().len(), 2);
+        // writes through the mapping show up in the file
+        unsafe { region.pages()[1].write(9) };
+        let mut buf = [0; 1];
+        fs.read(handle, &mut buf, PAGE_SIZE).unwrap();
+        assert_eq!(buf, [9]);
+    }
+
+    #[test]
+    fn test_vfs() {
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, TmpFs::new(HeapPageAllocator, 2 * PAGE_SIZE))
+            .unwrap();
+
+        vfs.create(path("/dir"), FsNodeKind::Directory).unwrap();
+        vfs.create(path("/dir/file"), FsNodeKind::File).unwrap();
+        vfs.symlink(path("/link"), Path::new("dir")).unwrap();
+        assert_eq!(
+            vfs.create(path("/link/file"), FsNodeKind::File),
+            Err(CreateError::AlreadyExists)
+        );
+        assert_eq!(
+            vfs.create(path("/missing/file"), FsNodeKind::File),
+            Err(CreateError::NotFound)
+        );
+
+        let node = vfs.open(path("/link/file")).unwrap();
+        node.write(b"hello", 0).unwrap();
+        vfs.unlink(path("/dir/file")).unwrap();
+        let mut buf = [0; 5];
+        node.read(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"hello");
+        assert_eq!(vfs.rmdir(path("/link")), Err(UnlinkError::NotDirectory));
+        vfs.unlink(path("/link")).unwrap();
+        vfs.rmdir(path("/dir")).unwrap();
+    }
+
+    #[test]
+    fn test_vfs_read_only() {
+        let mut vfs = Vfs::new();
+        vfs.mount_with(
+            ROOT,
+            Arc::new(RwLock::new(TmpFs::new(HeapPageAllocator, PAGE_SIZE))),
+            MountOptions {
+                flags: MountFlags::READ_ONLY,
+                ..MountOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vfs.create(path("/file"), FsNodeKind::File),
+            Err(CreateError::ReadOnly)
+        );
+        assert_eq!(vfs.unlink(path("/file")), Err(UnlinkError::ReadOnly));
+    }
+}
@@ -0,0 +1,9 @@
+#![no_std]
+extern crate alloc;
+
+mod fs;
+mod node;
+mod page;
+
+pub use fs::*;
+pub use page::*;
@@ -0,0 +1,72 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use kernel_abi::{S_IFDIR, S_IFLNK, S_IFREG};
+use kernel_vfs::fs::{FsNodeId, FsNodeKind};
+use kernel_vfs::path::OwnedPath;
+
+use crate::page::FileData;
+
+pub(crate) struct TmpNode {
+    pub(crate) kind: TmpNodeKind,
+    /// The permission bits, without the file type.
+    pub(crate) perm: u32,
+    /// The number of directory entries that refer to this node, plus the
+    /// `.` and `..` entries of directories.
+    pub(crate) nlink: u32,
+    /// The number of open handles to this node. An unlinked node is only
+    /// freed once this drops to zero.
+    pub(crate) open: usize,
+}
+
+pub(crate) enum TmpNodeKind {
+    File(FileData),
+    Directory(BTreeMap<String, FsNodeId>),
+    Symlink(OwnedPath),
+}
+
+impl TmpNode {
+    pub(crate) fn new(kind: TmpNodeKind, perm: u32) -> Self {
+        let nlink = match kind {
+            TmpNodeKind::Directory(_) => 2,
+            _ => 1,
+        };
+        Self {
+            kind,
+            perm,
+            nlink,
+            open: 0,
+        }
+    }
+
+    pub(crate) fn fs_node_kind(&self) -> FsNodeKind {
+        match self.kind {
+            TmpNodeKind::File(_) => FsNodeKind::File,
+            TmpNodeKind::Directory(_) => FsNodeKind::Directory,
+            TmpNodeKind::Symlink(_) => FsNodeKind::Symlink,
+        }
+    }
+
+    pub(crate) fn mode(&self) -> u32 {
+        let typ = match self.kind {
+            TmpNodeKind::File(_) => S_IFREG,
+            TmpNodeKind::Directory(_) => S_IFDIR,
+            TmpNodeKind::Symlink(_) => S_IFLNK,
+        };
+        typ | self.perm
+    }
+
+    pub(crate) fn directory(&self) -> Option<&BTreeMap<String, FsNodeId>> {
+        match &self.kind {
+            TmpNodeKind::Directory(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn directory_mut(&mut self) -> Option<&mut BTreeMap<String, FsNodeId>> {
+        match &mut self.kind {
+            TmpNodeKind::Directory(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+use core::slice;
+
+use kernel_vfs::{MmapRegion, PAGE_SIZE};
+
+/// Hands out the pages that a [`TmpFs`](crate::TmpFs) stores file contents
+/// in.
+///
+/// The kernel backs this with physical frames, so that file contents don't
+/// compete with the kernel heap and can be mapped into userspace.
+pub trait PageAllocator: Send + Sync {
+    /// Allocates a zeroed, page aligned page of [`PAGE_SIZE`] bytes, or
+    /// returns `None` if there is no memory left.
+    fn allocate(&self) -> Option<NonNull<u8>>;
+
+    /// Returns `page` to the allocator.
+    ///
+    /// # Safety
+    /// `page` must have been returned by [`PageAllocator::allocate`] on
+    /// this allocator, and must not be used afterwards.
+    unsafe fn deallocate(&self, page: NonNull<u8>);
+}
+
+/// The contents of a regular file. Pages are never moved once they are
+/// allocated, so a mapping of the file stays valid while the file grows.
+#[derive(Default)]
+pub(crate) struct FileData {
+    pages: Vec<NonNull<u8>>,
+    len: usize,
+}
+
+// SAFETY: the pages are owned by this file and only accessed through it.
+unsafe impl Send for FileData {}
+unsafe impl Sync for FileData {}
+
+impl FileData {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Copies the bytes at `offset` into `buf` and returns how many were
+    /// copied, which is less than `buf.len()` at the end of the file.
+    pub(crate) fn read(&self, buf: &mut [u8], offset: usize) -> usize {
+        let len = buf.len().min(self.len.saturating_sub(offset));
+        let mut done = 0;
+        while done < len {
+            let (page, in_page) = self.page_at(offset + done);
+            let n = (len - done).min(PAGE_SIZE - in_page);
+            buf[done..done + n].copy_from_slice(&page[in_page..in_page + n]);
+            done += n;
+        }
+        len
+    }
+
+    /// Copies `buf` into the file at `offset`. The file must already have
+    /// enough pages, see [`FileData::grow`].
+    pub(crate) fn write(&mut self, buf: &[u8], offset: usize) {
+        let mut done = 0;
+        while done < buf.len() {
+            let (page, in_page) = self.page_at_mut(offset + done);
+            let n = (buf.len() - done).min(PAGE_SIZE - in_page);
+            page[in_page..in_page + n].copy_from_slice(&buf[done..done + n]);
+            done += n;
+        }
+        self.len = self.len.max(offset + buf.len());
+    }
+
+    /// Allocates pages until the file can hold `len` bytes.
+    ///
+    /// Returns `false` if the allocator runs out of memory. The pages that
+    /// were allocated until then stay with the file.
+    pub(crate) fn grow(&mut self, len: usize, allocator: &impl PageAllocator) -> bool {
+        while self.pages.len() * PAGE_SIZE < len {
+            let Some(page) = allocator.allocate() else {
+                return false;
+            };
+            self.pages.push(page);
+        }
+        true
+    }
+
+    /// Returns all pages to `allocator`.
+    pub(crate) fn free(&mut self, allocator: &impl PageAllocator) {
+        for page in self.pages.drain(..) {
+            // SAFETY: every page came from `allocator` in `grow`, and the
+            // file is empty afterwards, so nothing uses the page anymore
+            unsafe { allocator.deallocate(page) };
+        }
+        self.len = 0;
+    }
+
+    pub(crate) fn mmap(&self) -> MmapRegion {
+        MmapRegion::from_pages(self.pages.clone(), self.len)
+    }
+
+    fn page_at(&self, offset: usize) -> (&[u8], usize) {
+        let page = self.pages[offset / PAGE_SIZE];
+        // SAFETY: the allocator hands out pages of PAGE_SIZE bytes that we
+        // own. Only shared mappings of the file write to them behind our
+        // back, which can't change more than the bytes themselves.
+        let page = unsafe { slice::from_raw_parts(page.as_ptr(), PAGE_SIZE) };
+        (page, offset % PAGE_SIZE)
+    }
+
+    fn page_at_mut(&mut self, offset: usize) -> (&mut [u8], usize) {
+        let page = self.pages[offset / PAGE_SIZE];
+        // SAFETY: as above, and `&mut self` makes the access exclusive
+        let page = unsafe { slice::from_raw_parts_mut(page.as_ptr(), PAGE_SIZE) };
+        (page, offset % PAGE_SIZE)
+    }
+}
@@ -0,0 +1,447 @@
+//! The line discipline of a terminal, independent of the device that bytes
+//! arrive from and leave through.
+//!
+//! A driver feeds every received byte to [`LineDiscipline::receive`], which
+//! edits the current line, echoes and reports signal characters. Processes
+//! read the finished input with [`LineDiscipline::read`], and their writes go
+//! through [`LineDiscipline::write`] for output processing. Nothing here
+//! blocks. Waiting for input and delivering signals are up to the caller.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use kernel_abi::{
+    InputFlags, LocalFlags, OutputFlags, Signal, Termios, VEOF, VERASE, VINTR, VKILL, VMIN, VQUIT,
+    VSUSP,
+};
+
+/// Input that didn't fit into the buffers of a discipline is dropped.
+pub const MAX_INPUT: usize = 4096;
+
+pub struct LineDiscipline {
+    termios: Termios,
+    /// The line being edited in canonical mode.
+    line: Vec<u8>,
+    /// Input that can be read.
+    ready: VecDeque<u8>,
+    /// The lengths of the lines in `ready` in canonical mode, where a read
+    /// stops at the end of each. A line of length zero is an end of file.
+    lines: VecDeque<usize>,
+}
+
+impl Default for LineDiscipline {
+    fn default() -> Self {
+        Self::new(Termios::default())
+    }
+}
+
+impl LineDiscipline {
+    #[must_use]
+    pub fn new(termios: Termios) -> Self {
+        Self {
+            termios,
+            line: Vec::new(),
+            ready: VecDeque::new(),
+            lines: VecDeque::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn termios(&self) -> &Termios {
+        &self.termios
+    }
+
+    /// Changes the attributes right away. Switching out of canonical mode
+    /// makes the partial line readable, switching into it turns what is
+    /// readable into one line.
+    pub fn set_termios(&mut self, termios: Termios) {
+        let was_canonical = self.is_canonical();
+        self.termios = termios;
+        match (was_canonical, self.is_canonical()) {
+            (true, false) => {
+                self.ready.extend(self.line.drain(..));
+                self.lines.clear();
+            }
+            (false, true) if !self.ready.is_empty() => {
+                self.lines.push_back(self.ready.len());
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether [`LineDiscipline::read`] would return something right now.
+    #[must_use]
+    pub fn is_readable(&self) -> bool {
+        if self.is_canonical() {
+            !self.lines.is_empty()
+        } else {
+            !self.ready.is_empty() || self.termios.cc[VMIN] == 0
+        }
+    }
+
+    /// Processes one byte received from the device. Echoed bytes are
+    /// handed to `echo`, already processed for output.
+    ///
+    /// Returns the signal that the byte generated for the foreground
+    /// process group, if any. Generating a signal discards all input.
+    pub fn receive(&mut self, byte: u8, mut echo: impl FnMut(&[u8])) -> Option<Signal> {
+        let Termios {
+            iflag, lflag, cc, ..
+        } = self.termios;
+
+        let byte = match byte {
+            b'\r' if iflag.contains(InputFlags::IGNCR) => return None,
+            b'\r' if iflag.contains(InputFlags::ICRNL) => b'\n',
+            b'\n' if iflag.contains(InputFlags::INLCR) => b'\r',
+            byte => byte,
+        };
+        let is = |index: usize| cc[index] != 0 && cc[index] == byte;
+        let echoes = lflag.contains(LocalFlags::ECHO);
+
+        if lflag.contains(LocalFlags::ISIG) {
+            let signal = if is(VINTR) {
+                Some(Signal::Interrupt)
+            } else if is(VQUIT) {
+                Some(Signal::Quit)
+            } else if is(VSUSP) {
+                Some(Signal::TerminalStop)
+            } else {
+                None
+            };
+            if let Some(signal) = signal {
+                self.flush_input();
+                if echoes {
+                    self.echo_control(byte, &mut echo);
+                }
+                return Some(signal);
+            }
+        }
+
+        if !self.is_canonical() {
+            if self.ready.len() < MAX_INPUT {
+                self.ready.push_back(byte);
+                if echoes {
+                    self.write(&[byte], echo);
+                }
+            }
+            return None;
+        }
+
+        let erases = echoes && lflag.contains(LocalFlags::ECHOE);
+        if is(VERASE) {
+            if self.line.pop().is_some() && erases {
+                echo(b"\x08 \x08");
+            }
+        } else if is(VKILL) {
+            if erases {
+                for _ in 0..self.line.len() {
+                    echo(b"\x08 \x08");
+                }
+            } else if echoes {
+                self.echo_control(byte, &mut echo);
+                if lflag.contains(LocalFlags::ECHOK) {
+                    self.write(b"\n", &mut echo);
+                }
+            }
+            self.line.clear();
+        } else if is(VEOF) {
+            self.finish_line();
+        } else if byte == b'\n' {
+            // the line may exceed the limit by its newline, so that a full
+            // line can still be finished
+            self.line.push(b'\n');
+            self.finish_line();
+            if echoes || lflag.contains(LocalFlags::ECHONL) {
+                self.write(b"\n", echo);
+            }
+        } else if self.line.len() + self.ready.len() < MAX_INPUT {
+            self.line.push(byte);
+            if echoes {
+                self.write(&[byte], echo);
+            }
+        }
+        None
+    }
+
+    /// Moves input into `buf`. In canonical mode, a read stops at the end
+    /// of a line.
+    ///
+    /// Returns `None` if there is no input yet, and `Some(0)` at the end of
+    /// file, or without input when the minimum read size is zero.
+    pub fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        if !self.is_canonical() {
+            if self.ready.is_empty() {
+                return (self.termios.cc[VMIN] == 0).then_some(0);
+            }
+            let n = buf.len().min(self.ready.len());
+            return Some(self.take(buf, n));
+        }
+
+        let line = self.lines.front_mut()?;
+        if *line == 0 {
+            self.lines.pop_front();
+            return Some(0);
+        }
+        let n = buf.len().min(*line);
+        *line -= n;
+        if *line == 0 {
+            self.lines.pop_front();
+        }
+        Some(self.take(buf, n))
+    }
+
+    /// Processes `buf` for output and hands the result to `out`.
+    pub fn write(&self, buf: &[u8], mut out: impl FnMut(&[u8])) {
+        let oflag = self.termios.oflag;
+        if !oflag.contains(OutputFlags::OPOST | OutputFlags::ONLCR) {
+            out(buf);
+            return;
+        }
+        for chunk in buf.split_inclusive(|&b| b == b'\n') {
+            match chunk.split_last() {
+                Some((b'\n', line)) => {
+                    out(line);
+                    out(b"\r\n");
+                }
+                _ => out(chunk),
+            }
+        }
+    }
+
+    /// Returns how many bytes from the start of `buf` fit into `room` bytes
+    /// of output once [`LineDiscipline::write`] processed them.
+    #[must_use]
+    pub fn output_fits(&self, buf: &[u8], room: usize) -> usize {
+        if !self
+            .termios
+            .oflag
+            .contains(OutputFlags::OPOST | OutputFlags::ONLCR)
+        {
+            return buf.len().min(room);
+        }
+        let mut used = 0;
+        for (index, &byte) in buf.iter().enumerate() {
+            used += if byte == b'\n' { 2 } else { 1 };
+            if used > room {
+                return index;
+            }
+        }
+        buf.len()
+    }
+
+    fn is_canonical(&self) -> bool {
+        self.termios.lflag.contains(LocalFlags::ICANON)
+    }
+
+    fn take(&mut self, buf: &mut [u8], n: usize) -> usize {
+        for (dst, src) in buf.iter_mut().zip(self.ready.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+
+    fn finish_line(&mut self) {
+        self.lines.push_back(self.line.len());
+        self.ready.extend(self.line.drain(..));
+    }
+
+    fn flush_input(&mut self) {
+        self.line.clear();
+        self.ready.clear();
+        self.lines.clear();
+    }
+
+    /// Echoes a control character as `^C`.
+    fn echo_control(&self, byte: u8, echo: impl FnMut(&[u8])) {
+        self.write(&[b'^', byte ^ 0x40], echo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    /// Feeds `input` and returns everything that was echoed.
+    fn feed(ld: &mut LineDiscipline, input: &[u8]) -> (Vec<u8>, Vec<Signal>) {
+        let mut echoed = vec![];
+        let mut signals = vec![];
+        for &byte in input {
+            if let Some(signal) = ld.receive(byte, |b| echoed.extend_from_slice(b)) {
+                signals.push(signal);
+            }
+        }
+        (echoed, signals)
+    }
+
+    fn read_all(ld: &mut LineDiscipline) -> Option<Vec<u8>> {
+        let mut buf = [0; 64];
+        ld.read(&mut buf).map(|n| buf[..n].to_vec())
+    }
+
+    fn raw() -> Termios {
+        let mut termios = Termios::default();
+        termios.make_raw();
+        termios
+    }
+
+    #[test]
+    fn test_canonical_reads_one_line_at_a_time() {
+        let mut ld = LineDiscipline::default();
+        let (echoed, _) = feed(&mut ld, b"ls\rpwd");
+        assert_eq!(b"ls\r\npwd", echoed.as_slice(), "CR is echoed as CR-NL");
+
+        assert_eq!(Some(b"ls\n".to_vec()), read_all(&mut ld));
+        assert_eq!(None, read_all(&mut ld), "pwd is not finished yet");
+        assert!(!ld.is_readable());
+
+        feed(&mut ld, b"\n");
+        let mut buf = [0; 2];
+        assert_eq!(Some(2), ld.read(&mut buf));
+        assert_eq!(b"pw", &buf);
+        assert_eq!(Some(b"d\n".to_vec()), read_all(&mut ld));
+    }
+
+    #[test]
+    fn test_canonical_erase_and_kill() {
+        let mut ld = LineDiscipline::default();
+        let (echoed, _) = feed(&mut ld, b"cat\x7f\x7fd");
+        assert_eq!(b"cat\x08 \x08\x08 \x08d", echoed.as_slice());
+
+        let (echoed, _) = feed(&mut ld, b"\x15echo\n");
+        assert_eq!(b"\x08 \x08\x08 \x08echo\r\n", echoed.as_slice());
+        assert_eq!(Some(b"echo\n".to_vec()), read_all(&mut ld));
+
+        let (echoed, _) = feed(&mut ld, b"\x7f");
+        assert!(echoed.is_empty(), "erasing an empty line does nothing");
+    }
+
+    #[test]
+    fn test_kill_without_echoe() {
+        let mut termios = Termios::default();
+        termios.lflag.remove(LocalFlags::ECHOE);
+        let mut ld = LineDiscipline::new(termios);
+        let (echoed, _) = feed(&mut ld, b"ab\x15");
+        assert_eq!(b"ab^U\r\n", echoed.as_slice());
+    }
+
+    #[test]
+    fn test_eof() {
+        let mut ld = LineDiscipline::default();
+        let (echoed, _) = feed(&mut ld, b"abc\x04\x04");
+        assert_eq!(b"abc", echoed.as_slice(), "EOF is not echoed");
+
+        assert_eq!(Some(b"abc".to_vec()), read_all(&mut ld));
+        assert_eq!(Some(vec![]), read_all(&mut ld), "EOF on an empty line");
+        assert_eq!(None, read_all(&mut ld));
+    }
+
+    #[test]
+    fn test_signals_discard_input() {
+        let mut ld = LineDiscipline::default();
+        feed(&mut ld, b"done\n");
+        let (echoed, signals) = feed(&mut ld, b"half\x03");
+        assert_eq!(vec![Signal::Interrupt], signals);
+        assert_eq!(b"half^C", echoed.as_slice());
+        assert!(!ld.is_readable(), "the signal discarded all input");
+
+        let (_, signals) = feed(&mut ld, b"\x1c\x1a");
+        assert_eq!(vec![Signal::Quit, Signal::TerminalStop], signals);
+
+        let mut termios = Termios::default();
+        termios.lflag.remove(LocalFlags::ISIG);
+        ld.set_termios(termios);
+        let (_, signals) = feed(&mut ld, b"\x03\n");
+        assert!(signals.is_empty());
+        assert_eq!(Some(b"\x03\n".to_vec()), read_all(&mut ld));
+    }
+
+    #[test]
+    fn test_raw_mode() {
+        let mut ld = LineDiscipline::new(raw());
+        let (echoed, signals) = feed(&mut ld, b"a\r\x03\x7f");
+        assert!(echoed.is_empty(), "raw mode doesn't echo");
+        assert!(signals.is_empty(), "raw mode doesn't generate signals");
+        assert_eq!(Some(b"a\r\x03\x7f".to_vec()), read_all(&mut ld));
+        assert_eq!(None, read_all(&mut ld));
+
+        let mut termios = raw();
+        termios.cc[VMIN] = 0;
+        ld.set_termios(termios);
+        assert_eq!(Some(vec![]), read_all(&mut ld), "VMIN 0 polls");
+    }
+
+    #[test]
+    fn test_switching_modes_keeps_input() {
+        let mut ld = LineDiscipline::default();
+        feed(&mut ld, b"line\npartial");
+        ld.set_termios(raw());
+        assert_eq!(Some(b"line\npartial".to_vec()), read_all(&mut ld));
+
+        feed(&mut ld, b"typed");
+        ld.set_termios(Termios::default());
+        assert_eq!(Some(b"typed".to_vec()), read_all(&mut ld));
+    }
+
+    #[test]
+    fn test_input_translation() {
+        let mut termios = Termios {
+            iflag: InputFlags::IGNCR,
+            ..Termios::default()
+        };
+        let mut ld = LineDiscipline::new(termios);
+        feed(&mut ld, b"a\r\n");
+        assert_eq!(Some(b"a\n".to_vec()), read_all(&mut ld));
+
+        termios.iflag = InputFlags::INLCR;
+        termios.lflag.remove(LocalFlags::ICANON);
+        ld.set_termios(termios);
+        feed(&mut ld, b"\n");
+        assert_eq!(Some(b"\r".to_vec()), read_all(&mut ld));
+    }
+
+    #[test]
+    fn test_output_processing() {
+        let mut out = vec![];
+        let ld = LineDiscipline::default();
+        ld.write(b"one\ntwo\n\nthree", |b| out.extend_from_slice(b));
+        assert_eq!(b"one\r\ntwo\r\n\r\nthree", out.as_slice());
+
+        out.clear();
+        let ld = LineDiscipline::new(raw());
+        ld.write(b"one\ntwo", |b| out.extend_from_slice(b));
+        assert_eq!(b"one\ntwo", out.as_slice());
+    }
+
+    #[test]
+    fn test_output_fits() {
+        let ld = LineDiscipline::default();
+        assert_eq!(3, ld.output_fits(b"one", 10));
+        assert_eq!(2, ld.output_fits(b"one", 2));
+        // the newline turns into two bytes, which don't fit
+        assert_eq!(3, ld.output_fits(b"one\ntwo", 4));
+        assert_eq!(4, ld.output_fits(b"one\ntwo", 5));
+        assert_eq!(0, ld.output_fits(b"one", 0));
+
+        let ld = LineDiscipline::new(raw());
+        assert_eq!(4, ld.output_fits(b"one\ntwo", 4));
+    }
+
+    #[test]
+    fn test_input_limit() {
+        let mut ld = LineDiscipline::default();
+        feed(&mut ld, &[b'x'; MAX_INPUT + 10]);
+        feed(&mut ld, b"\n");
+        let mut buf = vec![0; MAX_INPUT + 10];
+        assert_eq!(
+            Some(MAX_INPUT + 1),
+            ld.read(&mut buf),
+            "only the newline exceeds the limit"
+        );
+    }
+}
@@ -1,9 +1,12 @@
-use kernel_abi::IoctlRequest;
+use alloc::string::String;
+use alloc::vec::Vec;
 
-use crate::path::AbsolutePath;
+use kernel_abi::{IoctlRequest, PollEvents};
+
+use crate::path::{OwnedPath, Path};
 use crate::{
-    CloseError, FsyncError, IoctlError, MmapError, MmapRegion, OpenError, ReadError, Stat,
-    StatError, WriteError,
+    CloseError, CreateError, FsError, FsyncError, IoctlError, MmapError, MmapRegion, OpenError,
+    ReadError, ReadlinkError, Stat, StatError, UnlinkError, WriteError,
 };
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -15,11 +18,125 @@ impl From<u64> for FsHandle {
     }
 }
 
+/// Identifies a node within a single file system, such as an inode number.
+/// The id of a node must not change for as long as the node exists.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct FsNodeId(u64);
+
+impl From<u64> for FsNodeId {
+    fn from(id: u64) -> Self {
+        FsNodeId(id)
+    }
+}
+
+impl FsNodeId {
+    #[must_use]
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FsNodeKind {
+    File,
+    Directory,
+    Symlink,
+}
+
 pub trait FileSystem: Send + Sync {
+    /// Returns the id of the root directory of this file system.
+    fn root(&self) -> FsNodeId;
+
+    /// Looks up the entry called `name` in the directory `dir`.
+    ///
+    /// Paths are walked one component at a time by the [`Vfs`](crate::Vfs),
+    /// which also resolves `.`, `..` and symlinks, so `name` is never one
+    /// of those.
+    ///
+    /// # Errors
+    /// Returns [`OpenError::NotDirectory`] if `dir` is not a directory and
+    /// [`OpenError::NotFound`] if there is no such entry.
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError>;
+
+    /// Returns the names of all entries in the directory `dir`, without
+    /// `.` and `..`.
+    ///
     /// # Errors
-    /// Returns an error if the path does not point to a file, or if there
+    /// Returns [`OpenError::NotDirectory`] if `dir` is not a directory.
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError>;
+
+    /// # Errors
+    /// Returns an error if the node can't be opened, or if there
     /// was an underlying error during opening (such as a hardware error).
-    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError>;
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError>;
+
+    /// Returns the target of the symlink `node`.
+    ///
+    /// The default impl is for file systems without symlinks.
+    ///
+    /// # Errors
+    /// Returns [`ReadlinkError::NotSymlink`] if `node` is not a symlink.
+    fn readlink(&mut self, _node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        Err(ReadlinkError::NotSymlink)
+    }
+
+    /// Creates an empty file or directory called `name` in the directory
+    /// `dir`. Symlinks are created with [`FileSystem::symlink`].
+    ///
+    /// The default impl is for file systems that can't be modified.
+    ///
+    /// # Errors
+    /// Returns [`CreateError::AlreadyExists`] if `dir` already has an entry
+    /// called `name`, and [`CreateError::NoSpace`] if the file system is full.
+    fn create(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _kind: FsNodeKind,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::NotSupported)
+    }
+
+    /// Creates a symlink called `name` in the directory `dir` that points
+    /// to `target`.
+    ///
+    /// The default impl is for file systems without symlinks.
+    ///
+    /// # Errors
+    /// See [`FileSystem::create`].
+    fn symlink(
+        &mut self,
+        _dir: FsNodeId,
+        _name: &str,
+        _target: &Path,
+    ) -> Result<FsNodeId, CreateError> {
+        Err(CreateError::NotSupported)
+    }
+
+    /// Removes the entry called `name` from the directory `dir`. The entry
+    /// must not be a directory.
+    ///
+    /// The node itself must stay readable and writable through handles that
+    /// are still open, and is only freed once the last one is closed.
+    ///
+    /// The default impl is for file systems that can't be modified.
+    ///
+    /// # Errors
+    /// Returns [`UnlinkError::IsDirectory`] if the entry is a directory.
+    fn unlink(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::NotSupported)
+    }
+
+    /// Removes the empty directory called `name` from the directory `dir`.
+    ///
+    /// The default impl is for file systems that can't be modified.
+    ///
+    /// # Errors
+    /// Returns [`UnlinkError::NotDirectory`] if the entry is not a directory
+    /// and [`UnlinkError::NotEmpty`] if it still has entries.
+    fn rmdir(&mut self, _dir: FsNodeId, _name: &str) -> Result<(), UnlinkError> {
+        Err(UnlinkError::NotSupported)
+    }
 
     /// # Errors
     /// Returns an error if the handle is invalid or already closed,
@@ -74,6 +191,18 @@ pub trait FileSystem: Send + Sync {
         Err(IoctlError::NotSupported)
     }
 
+    /// Returns which of `events` the handle is ready for, plus
+    /// [`PollEvents::POLLERR`] and [`PollEvents::POLLHUP`] if they apply.
+    ///
+    /// The default impl reports the handle as always readable and writable,
+    /// which holds for files that are stored rather than streamed.
+    ///
+    /// # Errors
+    /// Returns an error if the handle is invalid or already closed.
+    fn poll(&mut self, _handle: FsHandle, events: PollEvents) -> Result<PollEvents, FsError> {
+        Ok(events & (PollEvents::POLLIN | PollEvents::POLLOUT))
+    }
+
     /// Commits any pending writes for the given handle to the underlying
     /// device.
     ///
@@ -4,12 +4,35 @@ use thiserror::Error;
 pub enum MountError {
     #[error("the mount point is already used by another mount")]
     AlreadyMounted,
+    #[error("the mount point does not exist")]
+    NotFound,
+    #[error("the mount point is not a directory")]
+    NotDirectory,
+    #[error("the mount point is not empty")]
+    NotEmpty,
+    #[error("too many levels of symbolic links")]
+    TooManySymlinks,
+    #[error("input/output error")]
+    Io,
+}
+
+impl From<OpenError> for MountError {
+    fn from(value: OpenError) -> Self {
+        match value {
+            OpenError::NotFound => Self::NotFound,
+            OpenError::NotDirectory | OpenError::IsDirectory => Self::NotDirectory,
+            OpenError::TooManySymlinks => Self::TooManySymlinks,
+            OpenError::Io => Self::Io,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
 pub enum UnmountError {
     #[error("not mounted")]
     NotMounted,
+    #[error("the file system is busy")]
+    Busy,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -19,6 +42,105 @@ pub enum ExistsError {}
 pub enum OpenError {
     #[error("not found")]
     NotFound,
+    #[error("not a directory")]
+    NotDirectory,
+    #[error("is a directory")]
+    IsDirectory,
+    #[error("too many levels of symbolic links")]
+    TooManySymlinks,
+    #[error("input/output error")]
+    Io,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum CreateError {
+    #[error("not found")]
+    NotFound,
+    #[error("not a directory")]
+    NotDirectory,
+    #[error("too many levels of symbolic links")]
+    TooManySymlinks,
+    #[error("file exists")]
+    AlreadyExists,
+    #[error("no space left on the file system")]
+    NoSpace,
+    #[error("read-only file system")]
+    ReadOnly,
+    #[error("the file system does not support this operation")]
+    NotSupported,
+    #[error("input/output error")]
+    Io,
+}
+
+impl From<OpenError> for CreateError {
+    fn from(value: OpenError) -> Self {
+        match value {
+            OpenError::NotFound => Self::NotFound,
+            OpenError::NotDirectory | OpenError::IsDirectory => Self::NotDirectory,
+            OpenError::TooManySymlinks => Self::TooManySymlinks,
+            OpenError::Io => Self::Io,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum UnlinkError {
+    #[error("not found")]
+    NotFound,
+    #[error("not a directory")]
+    NotDirectory,
+    #[error("is a directory")]
+    IsDirectory,
+    #[error("too many levels of symbolic links")]
+    TooManySymlinks,
+    #[error("the directory is not empty")]
+    NotEmpty,
+    #[error("the file is a mount point")]
+    Busy,
+    #[error("read-only file system")]
+    ReadOnly,
+    #[error("the file system does not support this operation")]
+    NotSupported,
+    #[error("input/output error")]
+    Io,
+}
+
+impl From<OpenError> for UnlinkError {
+    fn from(value: OpenError) -> Self {
+        match value {
+            OpenError::NotFound => Self::NotFound,
+            OpenError::NotDirectory => Self::NotDirectory,
+            OpenError::IsDirectory => Self::IsDirectory,
+            OpenError::TooManySymlinks => Self::TooManySymlinks,
+            OpenError::Io => Self::Io,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReadlinkError {
+    #[error("not found")]
+    NotFound,
+    #[error("not a directory")]
+    NotDirectory,
+    #[error("too many levels of symbolic links")]
+    TooManySymlinks,
+    #[error("not a symbolic link")]
+    NotSymlink,
+    #[error("read failed")]
+    ReadFailed,
+}
+
+impl From<OpenError> for ReadlinkError {
+    fn from(value: OpenError) -> Self {
+        match value {
+            OpenError::NotFound => Self::NotFound,
+            OpenError::NotDirectory => Self::NotDirectory,
+            OpenError::IsDirectory => Self::NotSymlink,
+            OpenError::TooManySymlinks => Self::TooManySymlinks,
+            OpenError::Io => Self::ReadFailed,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -49,6 +171,12 @@ pub enum ReadError {
     ReadFailed,
     #[error("file is not readable")]
     NotReadable,
+    #[error("operation would block")]
+    WouldBlock,
+    #[error("interrupted by a signal")]
+    Interrupted,
+    #[error("input/output error")]
+    Io,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -63,6 +191,14 @@ pub enum WriteError {
     WriteFailed,
     #[error("file is not writable")]
     NotWritable,
+    #[error("no space left on the file system")]
+    NoSpace,
+    #[error("operation would block")]
+    WouldBlock,
+    #[error("interrupted by a signal")]
+    Interrupted,
+    #[error("input/output error")]
+    Io,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -87,4 +223,8 @@ pub enum IoctlError {
     NotSupported,
     #[error("invalid ioctl argument")]
     InvalidArgument,
+    #[error("operation not permitted")]
+    NotPermitted,
+    #[error("interrupted by a signal")]
+    Interrupted,
 }
@@ -1,19 +1,66 @@
+use alloc::vec::Vec;
 use core::ptr::NonNull;
 
 use thiserror::Error;
 
 use crate::FsError;
 
-/// A memory region produced by [`crate::node::VfsNode::mmap`]: a raw pointer
-/// to the file's backing bytes plus the length of the region.
+/// The granularity of an [`MmapRegion`].
+pub const PAGE_SIZE: usize = 4096;
+
+/// A memory region produced by [`crate::node::VfsNode::mmap`]: raw pointers
+/// to the pages backing the file, in file order, plus the length of the
+/// region.
 ///
-/// The pointer's lifetime is tied to the underlying device or filesystem,
-/// not to any Rust borrow — turning the region into a slice is the caller's
-/// responsibility and is `unsafe`.
-#[derive(Debug, Copy, Clone)]
+/// The pages don't have to be contiguous, which is what allows file systems
+/// to hand out memory one page at a time. The pointers' lifetime is tied to
+/// the underlying device or filesystem, not to any Rust borrow — turning the
+/// region into a slice is the caller's responsibility and is `unsafe`.
+#[derive(Debug, Clone)]
 pub struct MmapRegion {
-    pub ptr: NonNull<u8>,
-    pub len: usize,
+    pages: Vec<NonNull<u8>>,
+    len: usize,
+}
+
+impl MmapRegion {
+    /// Creates a region over `len` contiguous bytes starting at the page
+    /// aligned `ptr`, such as a framebuffer.
+    #[must_use]
+    pub fn contiguous(ptr: NonNull<u8>, len: usize) -> Self {
+        let pages = (0..len.div_ceil(PAGE_SIZE))
+            .map(|page| ptr.map_addr(|addr| addr.saturating_add(page * PAGE_SIZE)))
+            .collect();
+        Self { pages, len }
+    }
+
+    /// Creates a region over `len` bytes that are stored in `pages`, each
+    /// of which is [`PAGE_SIZE`] bytes large and page aligned.
+    ///
+    /// # Panics
+    /// Panics if `pages` can't hold `len` bytes.
+    #[must_use]
+    pub fn from_pages(pages: Vec<NonNull<u8>>, len: usize) -> Self {
+        assert!(
+            pages.len() * PAGE_SIZE >= len,
+            "pages must cover the region"
+        );
+        Self { pages, len }
+    }
+
+    #[must_use]
+    pub fn pages(&self) -> &[NonNull<u8>] {
+        &self.pages
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 }
 
 unsafe impl Send for MmapRegion {}
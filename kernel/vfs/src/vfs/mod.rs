@@ -1,16 +1,22 @@
 use alloc::borrow::ToOwned;
-use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
 
 pub use error::*;
+use kernel_abi::MountFlags;
+pub use mount::{Mount, MountOptions};
 use spin::RwLock;
 
-use crate::fs::FileSystem;
+use crate::fs::{FileSystem, FsNodeId, FsNodeKind};
 use crate::node::VfsNode;
-use crate::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath, OwnedPath, Path, ROOT};
+use crate::vfs::mount::MountNode;
 
 mod error;
 mod mmap;
+mod mount;
 pub mod node;
 mod stat;
 pub use mmap::*;
@@ -19,10 +25,12 @@ pub use stat::*;
 #[cfg(test)]
 pub mod testing;
 
-type Fs = Arc<RwLock<dyn FileSystem>>;
+/// The maximum number of symlinks that are followed while resolving a
+/// single path before giving up with [`OpenError::TooManySymlinks`].
+pub const MAX_SYMLINKS: usize = 40;
 
 pub struct Vfs {
-    file_systems: BTreeMap<AbsoluteOwnedPath, Fs>, // TODO: maybe a trie would be better here?
+    mounts: MountNode,
 }
 
 impl Default for Vfs {
@@ -31,16 +39,35 @@ impl Default for Vfs {
     }
 }
 
+/// One resolved component of a path during a walk.
+struct Step<'a> {
+    name: String,
+    /// The node in the mount trie for the path up to here, if any mount
+    /// point starts with this path.
+    mounts: Option<&'a MountNode>,
+    /// Where this component lives. This is only `None` for components that
+    /// lead to a mount point but don't exist themselves, for example `/`
+    /// before a root file system is mounted.
+    location: Option<Location>,
+}
+
+#[derive(Clone)]
+struct Location {
+    mount: Arc<Mount>,
+    node: FsNodeId,
+    kind: FsNodeKind,
+}
+
 impl Vfs {
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            file_systems: BTreeMap::new(),
+            mounts: MountNode::new(),
         }
     }
 
-    /// Mounts a file system at the given mount point.
-    /// The mount point must point to an empty directory.
+    /// Mounts a file system at the given mount point with default
+    /// [`MountOptions`]. See [`Vfs::mount_with`].
     ///
     /// # Errors
     /// This function returns an error if the mount point is already mounted,
@@ -50,36 +77,80 @@ impl Vfs {
         P: AsRef<AbsolutePath>,
         F: FileSystem + 'static,
     {
-        let mount_point = mount_point.as_ref();
-        let mount_point = mount_point.to_owned();
-        if self.file_systems.contains_key(&mount_point) {
+        self.mount_with(
+            mount_point,
+            Arc::new(RwLock::new(fs)),
+            MountOptions::default(),
+        )
+    }
+
+    /// Mounts a file system at the given mount point.
+    ///
+    /// The mount point must point to an empty directory. The exception are
+    /// paths that no file system is mounted at or above yet, which is what
+    /// allows mounting `/dev` before the root file system. Symlinks in the
+    /// mount point are resolved before mounting.
+    ///
+    /// # Errors
+    /// This function returns an error if the mount point is already mounted,
+    /// not an empty directory or if another error occurs during mounting.
+    pub fn mount_with<P>(
+        &mut self,
+        mount_point: P,
+        fs: Arc<RwLock<dyn FileSystem>>,
+        options: MountOptions,
+    ) -> Result<(), MountError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let mount_point = self.check_mount_point(mount_point.as_ref())?;
+        let node = self.mounts.get_or_insert(mount_point.as_ref());
+        if node.mount.is_some() {
             return Err(MountError::AlreadyMounted);
         }
 
-        // TODO: check whether the mount_point is a directory
-
-        self.file_systems
-            .insert(mount_point, Arc::new(RwLock::new(fs)));
+        node.mount = Some(Arc::new(Mount::new(mount_point, fs, options)));
         Ok(())
     }
 
     /// Unmounts the file system at the given mount point.
     ///
     /// # Errors
-    /// This function returns an error if the mount point is not mounted,
-    /// or if another error occurs during unmounting.
+    /// This function returns [`UnmountError::Busy`] if there are still
+    /// nodes open in the file system, or if other file systems are mounted
+    /// below it.
     pub fn unmount<P>(&mut self, mount_point: P) -> Result<(), UnmountError>
     where
         P: AsRef<AbsolutePath>,
     {
-        let owned = mount_point.as_ref().to_owned();
-        self.file_systems
-            .remove(&owned)
-            .map(|_| ())
-            .ok_or(UnmountError::NotMounted)
+        let steps = self
+            .walk(mount_point.as_ref(), true)
+            .map_err(|_| UnmountError::NotMounted)?;
+        let mount_point = Self::path_of(&steps);
+
+        let node = self
+            .mounts
+            .get(mount_point.as_ref())
+            .ok_or(UnmountError::NotMounted)?;
+        let mount = node.mount.as_ref().ok_or(UnmountError::NotMounted)?;
+        if !node.children.is_empty() || mount.has_open_nodes() {
+            return Err(UnmountError::Busy);
+        }
+
+        self.mounts.remove(mount_point.as_ref());
+        Ok(())
+    }
+
+    /// Returns all mounts, ordered by their mount point.
+    #[must_use]
+    pub fn mounts(&self) -> Vec<Arc<Mount>> {
+        let mut mounts = Vec::new();
+        self.mounts.collect(&mut mounts);
+        mounts
     }
 
-    /// Opens a file at the given path.
+    /// Opens a file at the given path, following symlinks. If the file is
+    /// already open, the returned node shares its state with the open one.
     ///
     /// # Errors
     /// This function returns an error if the file does not exist,
@@ -88,45 +159,330 @@ impl Vfs {
     where
         P: AsRef<AbsolutePath>,
     {
-        // FIXME: reuse already open VfsNodes
+        let steps = self.walk(path.as_ref(), true)?;
+        let location = steps
+            .last()
+            .and_then(|step| step.location.as_ref())
+            .ok_or(OpenError::NotFound)?;
+        location.mount.open(location.node, Self::path_of(&steps))
+    }
 
-        let path = path.as_ref();
-        let (mount_path, fs) = self.find_mount(path).ok_or(OpenError::NotFound)?;
-        let relative_path = if mount_path == ROOT {
-            path
-        } else {
-            path.strip_prefix(&***mount_path).unwrap()
+    /// Returns the target of the symlink at the given path. Only the last
+    /// component of the path is not followed.
+    ///
+    /// # Errors
+    /// This function returns an error if the path does not exist or does
+    /// not point to a symlink.
+    pub fn readlink<P>(&self, path: P) -> Result<OwnedPath, ReadlinkError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let steps = self.walk(path.as_ref(), false)?;
+        let location = steps
+            .last()
+            .and_then(|step| step.location.as_ref())
+            .ok_or(ReadlinkError::NotFound)?;
+        if location.kind != FsNodeKind::Symlink {
+            return Err(ReadlinkError::NotSymlink);
+        }
+        location.mount.fs().write().readlink(location.node)
+    }
+
+    /// Creates an empty file or directory at the given path. Symlinks in
+    /// the parent directories are followed.
+    ///
+    /// # Errors
+    /// This function returns an error if the parent directory doesn't
+    /// exist, something already exists at `path`, or the file system is
+    /// read-only or doesn't support creating files.
+    pub fn create<P>(&self, path: P, kind: FsNodeKind) -> Result<(), CreateError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let (dir, name) = self.parent_for_create(path.as_ref())?;
+        dir.mount.fs().write().create(dir.node, name, kind)?;
+        Ok(())
+    }
+
+    /// Creates a symlink at the given path that points to `target`.
+    ///
+    /// # Errors
+    /// See [`Vfs::create`].
+    pub fn symlink<P>(&self, path: P, target: &Path) -> Result<(), CreateError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let (dir, name) = self.parent_for_create(path.as_ref())?;
+        dir.mount.fs().write().symlink(dir.node, name, target)?;
+        Ok(())
+    }
+
+    /// Removes the file or symlink at the given path. Nodes that are still
+    /// open stay usable until they are closed.
+    ///
+    /// # Errors
+    /// This function returns an error if there is nothing at `path`, it is
+    /// a directory or a mount point, or the file system is read-only.
+    pub fn unlink<P>(&self, path: P) -> Result<(), UnlinkError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let (dir, name) = self.parent_for_unlink(path.as_ref())?;
+        dir.mount.fs().write().unlink(dir.node, name)
+    }
+
+    /// Removes the empty directory at the given path.
+    ///
+    /// # Errors
+    /// This function returns an error if there is no directory at `path`,
+    /// it is not empty or a mount point, or the file system is read-only.
+    pub fn rmdir<P>(&self, path: P) -> Result<(), UnlinkError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let (dir, name) = self.parent_for_unlink(path.as_ref())?;
+        dir.mount.fs().write().rmdir(dir.node, name)
+    }
+
+    fn parent_for_create<'p>(
+        &self,
+        path: &'p AbsolutePath,
+    ) -> Result<(Location, &'p str), CreateError> {
+        let name = Self::last_name(path).ok_or(CreateError::AlreadyExists)?;
+        let (dir, mount_point) = self.parent(path, name)?;
+        if mount_point {
+            return Err(CreateError::AlreadyExists);
+        }
+        if dir.mount.flags().contains(MountFlags::READ_ONLY) {
+            return Err(CreateError::ReadOnly);
+        }
+        Ok((dir, name))
+    }
+
+    fn parent_for_unlink<'p>(
+        &self,
+        path: &'p AbsolutePath,
+    ) -> Result<(Location, &'p str), UnlinkError> {
+        let name = Self::last_name(path).ok_or(UnlinkError::Busy)?;
+        let (dir, mount_point) = self.parent(path, name)?;
+        if mount_point {
+            return Err(UnlinkError::Busy);
+        }
+        if dir.mount.flags().contains(MountFlags::READ_ONLY) {
+            return Err(UnlinkError::ReadOnly);
+        }
+        Ok((dir, name))
+    }
+
+    /// Returns the last component of `path`, unless that is the root, `.`
+    /// or `..`, which can't be created or removed.
+    fn last_name(path: &AbsolutePath) -> Option<&str> {
+        path.file_name()
+            .filter(|name| *name != "." && *name != "..")
+    }
+
+    /// Resolves the directory that contains `name`, the last component of
+    /// `path`, and whether `name` is a mount point.
+    fn parent(&self, path: &AbsolutePath, name: &str) -> Result<(Location, bool), OpenError> {
+        let steps = self.walk(path.parent().unwrap_or(ROOT), true)?;
+        let last = steps.last().unwrap();
+        let dir = last.location.clone().ok_or(OpenError::NotFound)?;
+        if dir.kind != FsNodeKind::Directory {
+            return Err(OpenError::NotDirectory);
+        }
+        let mount_point = last
+            .mounts
+            .and_then(|mounts| mounts.children.get(name))
+            .is_some_and(|mounts| mounts.mount.is_some());
+        Ok((dir, mount_point))
+    }
+
+    /// Walks `path` one component at a time, starting at the root.
+    ///
+    /// A component that is a mount point continues in the root of the
+    /// mounted file system, and `..` continues in whatever directory the
+    /// walk came from, so it leaves a mount through its mount point.
+    /// Symlinks are resolved here as well, so their targets may point
+    /// into other mounts. The last component is only followed if
+    /// `follow_last` is set.
+    fn walk(&self, path: &AbsolutePath, follow_last: bool) -> Result<Vec<Step<'_>>, OpenError> {
+        let mut steps = vec![self.root_step()];
+        let mut remaining = path
+            .filenames()
+            .rev()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let mut followed = 0;
+        while let Some(component) = remaining.pop() {
+            match component.as_str() {
+                "." | ".." => {
+                    // like any other component, these must be looked up in a
+                    // directory, so `/etc/passwd/..` doesn't resolve to `/etc`
+                    if steps
+                        .last()
+                        .unwrap()
+                        .location
+                        .as_ref()
+                        .is_some_and(|location| location.kind != FsNodeKind::Directory)
+                    {
+                        return Err(OpenError::NotDirectory);
+                    }
+                    if component == ".." && steps.len() > 1 {
+                        steps.pop();
+                    }
+                }
+                _ => {
+                    let step = Self::step(steps.last().unwrap(), component)?;
+                    let Some(location) = step
+                        .location
+                        .as_ref()
+                        .filter(|location| location.kind == FsNodeKind::Symlink)
+                        .filter(|_| follow_last || !remaining.is_empty())
+                    else {
+                        steps.push(step);
+                        continue;
+                    };
+
+                    followed += 1;
+                    if followed > MAX_SYMLINKS {
+                        return Err(OpenError::TooManySymlinks);
+                    }
+
+                    let target = location
+                        .mount
+                        .fs()
+                        .write()
+                        .readlink(location.node)
+                        .map_err(|e| match e {
+                            ReadlinkError::ReadFailed => OpenError::Io,
+                            _ => OpenError::NotFound,
+                        })?;
+                    if target.is_absolute() {
+                        steps.truncate(1);
+                    }
+                    remaining.extend(target.filenames().rev().map(ToString::to_string));
+                }
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Resolves `mount_point` and checks that a file system may be mounted
+    /// there.
+    fn check_mount_point(
+        &self,
+        mount_point: &AbsolutePath,
+    ) -> Result<AbsoluteOwnedPath, MountError> {
+        let Some(name) = mount_point.file_name() else {
+            return Ok(mount_point.to_owned());
         };
-        let relative_path = unsafe { AbsolutePath::new_unchecked((&relative_path).as_ref()) };
-        let mut guard = fs.write();
-        guard
-            .open(relative_path)
-            .map(|handle| VfsNode::new(path.to_owned(), handle, Arc::downgrade(&fs)))
+
+        let parent = self.walk(mount_point.parent().unwrap_or(ROOT), true)?;
+        if name != "." && name != ".." && parent.last().unwrap().location.is_none() {
+            // no file system covers this path yet, so there is nothing to check
+            let mut path = Self::path_of(&parent);
+            path.push(name);
+            return Ok(path);
+        }
+
+        let steps = self.walk(mount_point, true)?;
+        let last = steps.last().unwrap();
+        if last.mounts.is_some_and(|mounts| mounts.mount.is_some()) {
+            return Err(MountError::AlreadyMounted);
+        }
+        if let Some(location) = &last.location {
+            if location.kind != FsNodeKind::Directory {
+                return Err(MountError::NotDirectory);
+            }
+            if !location
+                .mount
+                .fs()
+                .write()
+                .read_dir(location.node)?
+                .is_empty()
+            {
+                return Err(MountError::NotEmpty);
+            }
+        }
+        Ok(Self::path_of(&steps))
     }
 
-    fn find_mount<'a>(&'a self, path: &'a AbsolutePath) -> Option<(&'a AbsolutePath, Fs)> {
-        let mut current = path;
-        if let Some(fs) = self.file_systems.get(current) {
-            return Some((path, fs.clone()));
+    fn root_step(&self) -> Step<'_> {
+        Step {
+            name: String::new(),
+            mounts: Some(&self.mounts),
+            location: self.mounts.mount.as_ref().map(Self::mount_root),
         }
-        while let Some(parent) = current.parent() {
-            if let Some(fs) = self.file_systems.get(parent) {
-                return Some((parent, fs.clone()));
+    }
+
+    fn step<'a>(current: &Step<'a>, name: String) -> Result<Step<'a>, OpenError> {
+        let mounts = current
+            .mounts
+            .and_then(|mounts| mounts.children.get(name.as_str()));
+
+        // a mount shadows whatever is at the mount point in the parent
+        let location = if let Some(mount) = mounts.and_then(|mounts| mounts.mount.as_ref()) {
+            Some(Self::mount_root(mount))
+        } else if let Some(current) = &current.location {
+            if current.kind != FsNodeKind::Directory {
+                return Err(OpenError::NotDirectory);
             }
-            current = parent;
+            match current.mount.fs().write().lookup(current.node, &name) {
+                Ok((node, kind)) => Some(Location {
+                    mount: current.mount.clone(),
+                    node,
+                    kind,
+                }),
+                // the path may still lead to a mount point further down
+                Err(_) if mounts.is_some() => None,
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
+
+        if location.is_none() && mounts.is_none() {
+            return Err(OpenError::NotFound);
+        }
+        Ok(Step {
+            name,
+            mounts,
+            location,
+        })
+    }
+
+    fn mount_root(mount: &Arc<Mount>) -> Location {
+        Location {
+            mount: mount.clone(),
+            node: mount.fs().read().root(),
+            kind: FsNodeKind::Directory,
+        }
+    }
+
+    fn path_of(steps: &[Step<'_>]) -> AbsoluteOwnedPath {
+        let mut path = AbsoluteOwnedPath::new();
+        for step in &steps[1..] {
+            path.push(step.name.as_str());
         }
-        self.file_systems.get(ROOT).cloned().map(|v| (ROOT, v))
+        path
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
     use alloc::vec;
     use alloc::vec::Vec;
 
-    use crate::path::{AbsolutePath, ROOT};
+    use kernel_abi::MountFlags;
+    use spin::RwLock;
+
+    use crate::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
     use crate::testing::TestFs;
-    use crate::{Stat, Vfs};
+    use crate::{
+        MountError, MountOptions, OpenError, ReadlinkError, Stat, UnmountError, Vfs, WriteError,
+    };
 
     #[test]
     fn test_read() {
@@ -161,6 +517,357 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_dot_and_dotdot() {
+        let mut fs = TestFs::default();
+        fs.insert_file(
+            AbsolutePath::try_new("/foo/bar.txt").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, fs).unwrap();
+
+        for path in [
+            "/foo/./bar.txt",
+            "/foo/../foo/bar.txt",
+            "/../../foo/bar.txt",
+        ] {
+            let node = vfs.open(AbsolutePath::try_new(path).unwrap()).unwrap();
+            assert_eq!(
+                node.path(),
+                AbsolutePath::try_new("/foo/bar.txt").unwrap(),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_symlink() {
+        let mut fs = TestFs::default();
+        fs.insert_file(
+            AbsolutePath::try_new("/foo/bar.txt").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+        fs.insert_symlink(AbsolutePath::try_new("/abs").unwrap(), "/foo/bar.txt");
+        fs.insert_symlink(AbsolutePath::try_new("/foo/rel").unwrap(), "bar.txt");
+        fs.insert_symlink(AbsolutePath::try_new("/dir").unwrap(), "foo/../foo");
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, fs).unwrap();
+
+        for path in ["/abs", "/foo/rel", "/dir/bar.txt", "/dir/rel"] {
+            let node = vfs.open(AbsolutePath::try_new(path).unwrap()).unwrap();
+            assert_eq!(
+                node.path(),
+                AbsolutePath::try_new("/foo/bar.txt").unwrap(),
+                "{path}"
+            );
+        }
+
+        assert_eq!(
+            vfs.readlink(AbsolutePath::try_new("/dir/rel").unwrap())
+                .unwrap()
+                .as_str(),
+            "bar.txt"
+        );
+        assert_eq!(
+            vfs.readlink(AbsolutePath::try_new("/foo/bar.txt").unwrap()),
+            Err(ReadlinkError::NotSymlink)
+        );
+    }
+
+    #[test]
+    fn test_symlink_across_mounts() {
+        let mut root = TestFs::default();
+        root.insert_symlink(AbsolutePath::try_new("/link").unwrap(), "/mnt/baz.txt");
+        root.insert_symlink(AbsolutePath::try_new("/up").unwrap(), "..");
+        let mut mnt = TestFs::default();
+        mnt.insert_file(
+            AbsolutePath::try_new("/baz.txt").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+        mnt.insert_symlink(AbsolutePath::try_new("/back").unwrap(), "../link");
+        root.insert_dir(AbsolutePath::try_new("/mnt").unwrap());
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, root).unwrap();
+        vfs.mount(AbsolutePath::try_new("/mnt").unwrap(), mnt)
+            .unwrap();
+
+        for path in ["/link", "/mnt/back", "/up/mnt/baz.txt"] {
+            let node = vfs.open(AbsolutePath::try_new(path).unwrap()).unwrap();
+            assert_eq!(
+                node.path(),
+                AbsolutePath::try_new("/mnt/baz.txt").unwrap(),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dotdot_after_file() {
+        let mut fs = TestFs::default();
+        fs.insert_file(
+            AbsolutePath::try_new("/etc/passwd").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+        fs.insert_symlink(AbsolutePath::try_new("/file").unwrap(), "etc/passwd");
+        fs.insert_symlink(AbsolutePath::try_new("/up").unwrap(), "etc/passwd/..");
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, fs).unwrap();
+
+        for path in ["/etc/passwd/..", "/etc/passwd/.", "/file/..", "/up"] {
+            assert_eq!(
+                vfs.open(AbsolutePath::try_new(path).unwrap()).err(),
+                Some(OpenError::NotDirectory),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_symlink_loop() {
+        let mut fs = TestFs::default();
+        fs.insert_symlink(AbsolutePath::try_new("/a").unwrap(), "b");
+        fs.insert_symlink(AbsolutePath::try_new("/b").unwrap(), "/a");
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, fs).unwrap();
+
+        assert_eq!(
+            vfs.open(AbsolutePath::try_new("/a").unwrap()).err(),
+            Some(OpenError::TooManySymlinks)
+        );
+        assert_eq!(
+            vfs.readlink(AbsolutePath::try_new("/a").unwrap())
+                .unwrap()
+                .as_str(),
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_dotdot_across_mounts() {
+        let mut root = TestFs::default();
+        root.insert_file(
+            AbsolutePath::try_new("/etc/hosts").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+        root.insert_dir(AbsolutePath::try_new("/mnt/disk").unwrap());
+        let mut mnt = TestFs::default();
+        mnt.insert_file(
+            AbsolutePath::try_new("/sub/baz.txt").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, root).unwrap();
+        vfs.mount(AbsolutePath::try_new("/mnt/disk").unwrap(), mnt)
+            .unwrap();
+
+        for (path, expected) in [
+            ("/mnt/disk/sub/../../../etc/hosts", "/etc/hosts"),
+            ("/mnt/disk/../disk/sub/baz.txt", "/mnt/disk/sub/baz.txt"),
+            ("/mnt/disk/../../etc/hosts", "/etc/hosts"),
+        ] {
+            let node = vfs.open(AbsolutePath::try_new(path).unwrap()).unwrap();
+            assert_eq!(node.path(), AbsolutePath::try_new(expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_mount_without_root() {
+        let mut dev = TestFs::default();
+        dev.insert_file(
+            AbsolutePath::try_new("/null").unwrap(),
+            vec![],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        vfs.mount(AbsolutePath::try_new("/dev").unwrap(), dev)
+            .unwrap();
+
+        let node = vfs
+            .open(AbsolutePath::try_new("/dev/null").unwrap())
+            .unwrap();
+        assert_eq!(
+            node.mount().unwrap().mount_point(),
+            AbsolutePath::try_new("/dev").unwrap()
+        );
+        assert_eq!(
+            vfs.open(AbsolutePath::try_new("/foo").unwrap()).err(),
+            Some(OpenError::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_mount_below_symlink() {
+        let mut root = TestFs::default();
+        root.insert_symlink(AbsolutePath::try_new("/media").unwrap(), "/run/media");
+        root.insert_dir(AbsolutePath::try_new("/run/media/usb").unwrap());
+        let mut usb = TestFs::default();
+        usb.insert_file(
+            AbsolutePath::try_new("/file.txt").unwrap(),
+            vec![],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, root).unwrap();
+        // the mount point is resolved, so the mount ends up at /run/media/usb
+        vfs.mount(AbsolutePath::try_new("/media/usb").unwrap(), usb)
+            .unwrap();
+        assert_eq!(
+            vfs.mounts()[1].mount_point(),
+            AbsolutePath::try_new("/run/media/usb").unwrap()
+        );
+
+        let node = vfs
+            .open(AbsolutePath::try_new("/media/usb/file.txt").unwrap())
+            .unwrap();
+        assert_eq!(
+            node.path(),
+            AbsolutePath::try_new("/run/media/usb/file.txt").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unmount() {
+        let mut mnt = TestFs::default();
+        mnt.insert_file(
+            AbsolutePath::try_new("/file.txt").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        let mount_point = AbsolutePath::try_new("/mnt").unwrap();
+        vfs.mount(mount_point, mnt).unwrap();
+        let path = AbsolutePath::try_new("/mnt/file.txt").unwrap();
+        let node = vfs.open(path).unwrap();
+        let clone = node.clone();
+
+        assert_eq!(vfs.unmount(mount_point), Err(UnmountError::Busy));
+        drop(node);
+        assert_eq!(vfs.unmount(mount_point), Err(UnmountError::Busy));
+        drop(clone);
+
+        vfs.unmount(mount_point).unwrap();
+        assert_eq!(vfs.unmount(mount_point), Err(UnmountError::NotMounted));
+        assert_eq!(vfs.open(path).err(), Some(OpenError::NotFound));
+    }
+
+    #[test]
+    fn test_unmount_with_child_mounts() {
+        // the same order as during boot, /dev doesn't exist in the root fs
+        let mut vfs = Vfs::new();
+        vfs.mount(AbsolutePath::try_new("/dev").unwrap(), TestFs::default())
+            .unwrap();
+        vfs.mount(ROOT, TestFs::default()).unwrap();
+
+        assert_eq!(vfs.unmount(ROOT), Err(UnmountError::Busy));
+        vfs.unmount(AbsolutePath::try_new("/dev").unwrap()).unwrap();
+        vfs.unmount(ROOT).unwrap();
+    }
+
+    #[test]
+    fn test_mount_point_checks() {
+        let mut root = TestFs::default();
+        root.insert_file(
+            AbsolutePath::try_new("/file").unwrap(),
+            vec![],
+            Stat::default(),
+        );
+        root.insert_file(
+            AbsolutePath::try_new("/full/file").unwrap(),
+            vec![],
+            Stat::default(),
+        );
+        root.insert_dir(AbsolutePath::try_new("/empty").unwrap());
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, root).unwrap();
+
+        for (path, expected) in [
+            ("/file", MountError::NotDirectory),
+            ("/full", MountError::NotEmpty),
+            ("/missing", MountError::NotFound),
+            ("/file/sub", MountError::NotDirectory),
+            ("/", MountError::AlreadyMounted),
+        ] {
+            let result = vfs.mount(AbsolutePath::try_new(path).unwrap(), TestFs::default());
+            assert_eq!(result, Err(expected), "{path}");
+        }
+
+        let empty = AbsolutePath::try_new("/empty").unwrap();
+        vfs.mount(empty, TestFs::default()).unwrap();
+        assert_eq!(
+            vfs.mount(empty, TestFs::default()),
+            Err(MountError::AlreadyMounted)
+        );
+    }
+
+    #[test]
+    fn test_mount_read_only() {
+        let mut fs = TestFs::default();
+        fs.insert_file(
+            AbsolutePath::try_new("/file").unwrap(),
+            vec![0x00; 1],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        vfs.mount_with(
+            ROOT,
+            Arc::new(RwLock::new(fs)),
+            MountOptions {
+                fs_type: "testfs".into(),
+                source: None,
+                flags: MountFlags::READ_ONLY | MountFlags::NOEXEC,
+            },
+        )
+        .unwrap();
+
+        let node = vfs.open(AbsolutePath::try_new("/file").unwrap()).unwrap();
+        assert_eq!(node.write([0x01], 0), Err(WriteError::NotWritable));
+        let mut buf = [0xff; 1];
+        assert_eq!(node.read(&mut buf, 0), Ok(1));
+        assert_eq!(buf, [0x00]);
+    }
+
+    #[test]
+    fn test_mounts() {
+        let mut vfs = Vfs::new();
+        vfs.mount(AbsolutePath::try_new("/dev").unwrap(), TestFs::default())
+            .unwrap();
+        vfs.mount_with(
+            ROOT,
+            Arc::new(RwLock::new(TestFs::default())),
+            MountOptions {
+                fs_type: "ext2".into(),
+                source: Some(AbsoluteOwnedPath::try_from("/dev/blk0").unwrap()),
+                flags: MountFlags::READ_ONLY,
+            },
+        )
+        .unwrap();
+
+        let table = vfs
+            .mounts()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(table, ["/dev/blk0 / ext2 ro", "none /dev none rw"]);
+    }
+
     #[test]
     fn test_mount() {
         let mut fs = TestFs::default();
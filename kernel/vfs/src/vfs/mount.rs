@@ -0,0 +1,211 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use kernel_abi::MountFlags;
+use spin::{Mutex, RwLock};
+
+use crate::OpenError;
+use crate::fs::{FileSystem, FsNodeId};
+use crate::node::{Inner, VfsNode};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath};
+
+/// Describes how a file system is mounted.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MountOptions {
+    /// The name of the file system driver, such as `ext2`.
+    pub fs_type: String,
+    /// The device that the file system was mounted from, if any.
+    pub source: Option<AbsoluteOwnedPath>,
+    pub flags: MountFlags,
+}
+
+/// A file system that is mounted somewhere in the [`Vfs`](crate::Vfs).
+pub struct Mount {
+    mount_point: AbsoluteOwnedPath,
+    fs: Arc<RwLock<dyn FileSystem>>,
+    options: MountOptions,
+    /// All nodes of this mount that are currently open, so that opening
+    /// the same node again shares its state instead of opening a second
+    /// handle in the file system.
+    open_nodes: Mutex<BTreeMap<FsNodeId, Weak<Inner>>>,
+}
+
+impl Mount {
+    pub(crate) fn new(
+        mount_point: AbsoluteOwnedPath,
+        fs: Arc<RwLock<dyn FileSystem>>,
+        options: MountOptions,
+    ) -> Self {
+        Self {
+            mount_point,
+            fs,
+            options,
+            open_nodes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn fs_type(&self) -> &str {
+        &self.options.fs_type
+    }
+
+    #[must_use]
+    pub fn source(&self) -> Option<&AbsolutePath> {
+        self.options.source.as_ref().map(AsRef::as_ref)
+    }
+
+    #[must_use]
+    pub fn flags(&self) -> MountFlags {
+        self.options.flags
+    }
+
+    #[must_use]
+    pub fn mount_point(&self) -> &AbsolutePath {
+        self.mount_point.as_ref()
+    }
+
+    #[must_use]
+    pub fn fs(&self) -> &Arc<RwLock<dyn FileSystem>> {
+        &self.fs
+    }
+
+    /// Returns whether `node` is currently open.
+    #[cfg(test)]
+    #[must_use]
+    pub(crate) fn is_open(&self, node: FsNodeId) -> bool {
+        self.open_nodes
+            .lock()
+            .get(&node)
+            .is_some_and(|inner| inner.strong_count() > 0)
+    }
+
+    /// Returns whether any node of this mount is currently open.
+    #[must_use]
+    pub fn has_open_nodes(&self) -> bool {
+        self.open_nodes
+            .lock()
+            .values()
+            .any(|inner| inner.strong_count() > 0)
+    }
+
+    /// Returns the open [`VfsNode`] for `node`, or opens it in the file
+    /// system if nobody has it open yet.
+    pub(crate) fn open(
+        self: &Arc<Self>,
+        node: FsNodeId,
+        path: AbsoluteOwnedPath,
+    ) -> Result<VfsNode, OpenError> {
+        let mut open_nodes = self.open_nodes.lock();
+        if let Some(inner) = open_nodes.get(&node).and_then(Weak::upgrade) {
+            return Ok(VfsNode::from(inner));
+        }
+
+        let handle = self.fs.write().open(node)?;
+        let vfs_node = VfsNode::new(path, node, handle, Arc::downgrade(self));
+        open_nodes.insert(node, vfs_node.downgrade());
+        Ok(vfs_node)
+    }
+
+    /// Removes `node` from the open nodes, unless it has been opened again
+    /// in the meantime.
+    pub(crate) fn forget(&self, node: FsNodeId) {
+        let mut open_nodes = self.open_nodes.lock();
+        if open_nodes
+            .get(&node)
+            .is_some_and(|inner| inner.strong_count() == 0)
+        {
+            open_nodes.remove(&node);
+        }
+    }
+}
+
+/// Formats the mount like a line of `/proc/mounts`, without the trailing
+/// newline, e.g. `/dev/blk0 / ext2 ro,noexec`.
+impl Display for Mount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.source() {
+            Some(source) => write!(f, "{source}")?,
+            None => write!(f, "none")?,
+        }
+        let fs_type = if self.fs_type().is_empty() {
+            "none"
+        } else {
+            self.fs_type()
+        };
+        let rw = if self.flags().contains(MountFlags::READ_ONLY) {
+            "ro"
+        } else {
+            "rw"
+        };
+        write!(f, " {} {fs_type} {rw}", self.mount_point())?;
+        if self.flags().contains(MountFlags::NOEXEC) {
+            write!(f, ",noexec")?;
+        }
+        Ok(())
+    }
+}
+
+/// A trie over the components of all mount points. Every node corresponds to
+/// one path, and holds the mount at that path, if there is one.
+///
+/// Mount points don't have to exist in the parent file system, which is what
+/// allows mounting `/dev` before there is a root file system.
+#[derive(Default)]
+pub(crate) struct MountNode {
+    pub(crate) mount: Option<Arc<Mount>>,
+    pub(crate) children: BTreeMap<String, MountNode>,
+}
+
+impl MountNode {
+    pub(crate) const fn new() -> Self {
+        Self {
+            mount: None,
+            children: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn get(&self, path: &AbsolutePath) -> Option<&MountNode> {
+        path.filenames()
+            .try_fold(self, |node, component| node.children.get(component))
+    }
+
+    /// Collects all mounts in and below this node, parents before their
+    /// children.
+    pub(crate) fn collect(&self, mounts: &mut Vec<Arc<Mount>>) {
+        mounts.extend(self.mount.iter().cloned());
+        for child in self.children.values() {
+            child.collect(mounts);
+        }
+    }
+
+    pub(crate) fn get_or_insert(&mut self, path: &AbsolutePath) -> &mut MountNode {
+        path.filenames().fold(self, |node, component| {
+            node.children.entry(component.into()).or_default()
+        })
+    }
+
+    /// Removes the mount at `path` and prunes nodes that no longer lead
+    /// to any mount.
+    pub(crate) fn remove(&mut self, path: &AbsolutePath) -> Option<Arc<Mount>> {
+        let mut components = path.filenames();
+        self.remove_inner(&mut components)
+    }
+
+    fn remove_inner<'a>(
+        &mut self,
+        components: &mut impl Iterator<Item = &'a str>,
+    ) -> Option<Arc<Mount>> {
+        let Some(component) = components.next() else {
+            return self.mount.take();
+        };
+        let child = self.children.get_mut(component)?;
+        let removed = child.remove_inner(components);
+        if child.mount.is_none() && child.children.is_empty() {
+            self.children.remove(component);
+        }
+        removed
+    }
+}
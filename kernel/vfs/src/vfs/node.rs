@@ -2,14 +2,14 @@ use alloc::sync::{Arc, Weak};
 use core::fmt::{Debug, Formatter};
 use core::ops::Deref;
 
-use kernel_abi::IoctlRequest;
+use kernel_abi::{IoctlRequest, MountFlags, PollEvents};
 use spin::RwLock;
 
-use crate::fs::{FileSystem, FsHandle};
-use crate::path::AbsoluteOwnedPath;
+use crate::fs::{FileSystem, FsHandle, FsNodeId};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath};
 use crate::vfs::stat::Stat;
 use crate::{
-    FsError, FsyncError, IoctlError, MmapError, MmapRegion, ReadError, StatError, WriteError,
+    FsError, FsyncError, IoctlError, MmapError, MmapRegion, Mount, ReadError, StatError, WriteError,
 };
 
 #[derive(Clone)]
@@ -28,15 +28,25 @@ impl Debug for VfsNode {
 
 pub struct Inner {
     path: AbsoluteOwnedPath,
+    node: FsNodeId,
     fs_handle: FsHandle,
-    fs: Weak<RwLock<dyn FileSystem>>,
+    mount: Weak<Mount>,
+}
+
+impl Inner {
+    fn fs(&self) -> Result<Arc<RwLock<dyn FileSystem>>, FsError> {
+        self.mount
+            .upgrade()
+            .map(|mount| mount.fs().clone())
+            .ok_or(FsError::FileSystemNotOpen)
+    }
 }
 
 impl Drop for Inner {
     fn drop(&mut self) {
-        if let Some(fs) = self.fs.upgrade() {
-            let mut guard = fs.write();
-            let _ = guard.close(self.fs_handle);
+        if let Some(mount) = self.mount.upgrade() {
+            mount.forget(self.node);
+            let _ = mount.fs().write().close(self.fs_handle);
         }
     }
 }
@@ -49,21 +59,48 @@ impl Deref for VfsNode {
     }
 }
 
+impl From<Arc<Inner>> for VfsNode {
+    fn from(inner: Arc<Inner>) -> Self {
+        Self { inner }
+    }
+}
+
 impl VfsNode {
     pub(crate) fn new(
         path: AbsoluteOwnedPath,
+        node: FsNodeId,
         fs_handle: FsHandle,
-        fs: Weak<RwLock<dyn FileSystem>>,
+        mount: Weak<Mount>,
     ) -> Self {
         Self {
             inner: Arc::new(Inner {
                 path,
+                node,
                 fs_handle,
-                fs,
+                mount,
             }),
         }
     }
 
+    pub(crate) fn downgrade(&self) -> Weak<Inner> {
+        Arc::downgrade(&self.inner)
+    }
+
+    /// Returns the mount that this node belongs to, or `None` if it
+    /// has been unmounted.
+    #[must_use]
+    pub fn mount(&self) -> Option<Arc<Mount>> {
+        self.inner.mount.upgrade()
+    }
+
+    /// Returns the path that this node was first opened at, with all
+    /// symlinks resolved. Opening the same node through another path,
+    /// e.g. a hard link, shares this node and its path.
+    #[must_use]
+    pub fn path(&self) -> &AbsolutePath {
+        self.inner.path.as_ref()
+    }
+
     /// Reads up to `buf.len()` bytes from the file at the given
     /// `offset` into `buf` and returns the number of bytes read.
     ///
@@ -75,7 +112,7 @@ impl VfsNode {
     where
         B: AsMut<[u8]>,
     {
-        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+        let fs = self.fs()?;
         let buf = buf.as_mut();
 
         let mut guard = fs.write();
@@ -86,15 +123,18 @@ impl VfsNode {
     where
         B: AsRef<[u8]>,
     {
-        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+        let mount = self.mount().ok_or(FsError::FileSystemNotOpen)?;
+        if mount.flags().contains(MountFlags::READ_ONLY) {
+            return Err(WriteError::NotWritable);
+        }
         let buf = buf.as_ref();
 
-        let mut guard = fs.write();
+        let mut guard = mount.fs().write();
         guard.write(self.fs_handle, buf, offset)
     }
 
     pub fn stat(&self, stat: &mut Stat) -> Result<(), StatError> {
-        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+        let fs = self.fs()?;
 
         let mut guard = fs.write();
         guard.stat(self.fs_handle, stat)
@@ -106,7 +146,7 @@ impl VfsNode {
     /// Returns [`MmapError::NotSupported`] if the underlying filesystem or
     /// device file does not support `mmap`.
     pub fn mmap(&self) -> Result<MmapRegion, MmapError> {
-        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+        let fs = self.fs()?;
 
         let mut guard = fs.write();
         guard.mmap(self.fs_handle)
@@ -118,18 +158,30 @@ impl VfsNode {
     /// Returns [`IoctlError::NotSupported`] if the underlying filesystem or
     /// device file does not support the request.
     pub fn ioctl(&self, request: IoctlRequest, arg: &mut [u8]) -> Result<usize, IoctlError> {
-        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+        let fs = self.fs()?;
 
         let mut guard = fs.write();
         guard.ioctl(self.fs_handle, request, arg)
     }
 
+    /// Returns which of `events` this file is ready for, plus errors and
+    /// hang-ups. Never blocks.
+    ///
+    /// # Errors
+    /// Returns an error if the file system is no longer mounted.
+    pub fn poll(&self, events: PollEvents) -> Result<PollEvents, FsError> {
+        let fs = self.fs()?;
+
+        let mut guard = fs.write();
+        guard.poll(self.fs_handle, events)
+    }
+
     /// Commits any pending writes for this file to the underlying device.
     ///
     /// # Errors
     /// Returns an error if the underlying device fails to commit.
     pub fn fsync(&self) -> Result<(), FsyncError> {
-        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+        let fs = self.fs()?;
 
         let mut guard = fs.write();
         guard.fsync(self.fs_handle)
@@ -159,7 +211,7 @@ mod tests {
         let node = vfs
             .open(AbsolutePath::try_new("/foo/bar.txt").unwrap())
             .unwrap();
-        let fs = node.fs.upgrade().expect("file system should still exist");
+        let fs = node.fs().expect("file system should still exist");
 
         // save the fs_handle so that we can try to close it after drop
         let fs_handle = node.fs_handle;
@@ -189,7 +241,7 @@ mod tests {
         let node = vfs
             .open(AbsolutePath::try_new("/foo/bar.txt").unwrap())
             .unwrap();
-        let fs = node.fs.upgrade().expect("file system should still exist");
+        let fs = node.fs().expect("file system should still exist");
 
         // closing the node's fs_handle must not return an error now, because the
         // node hasn't been dropped yet
@@ -197,4 +249,46 @@ mod tests {
 
         drop(node);
     }
+
+    #[test]
+    fn test_open_shares_node() {
+        let mut fs = TestFs::default();
+        fs.insert_file(
+            AbsolutePath::try_new("/foo/bar.txt").unwrap(),
+            vec![0_u8; 1],
+            Stat::default(),
+        );
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, fs).unwrap();
+
+        let node = vfs
+            .open(AbsolutePath::try_new("/foo/bar.txt").unwrap())
+            .unwrap();
+        let other = vfs
+            .open(AbsolutePath::try_new("/foo/../foo/bar.txt").unwrap())
+            .unwrap();
+        assert_eq!(node.fs_handle, other.fs_handle);
+
+        let mount = node.mount().unwrap();
+        assert!(mount.is_open(node.node));
+        drop(node);
+        assert!(mount.is_open(other.node));
+
+        let id = other.node;
+        let fs_handle = other.fs_handle;
+        drop(other);
+        assert!(!mount.is_open(id));
+        assert!(!mount.has_open_nodes());
+
+        // the old handle has been closed, so opening again must not reuse it
+        let node = vfs
+            .open(AbsolutePath::try_new("/foo/bar.txt").unwrap())
+            .unwrap();
+        assert_ne!(fs_handle, node.fs_handle);
+        assert_eq!(
+            CloseError::NotOpen,
+            mount.fs().write().close(fs_handle).unwrap_err()
+        );
+    }
 }
@@ -1,4 +1,12 @@
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Stat {
     pub size: usize,
+    /// The id of the node within its file system, see
+    /// [`FsNodeId`](crate::fs::FsNodeId).
+    pub ino: u64,
+    /// The file type (`S_IF*` from `kernel_abi`) and permission bits.
+    pub mode: u32,
+    pub nlink: u32,
+    /// The number of 512 byte blocks allocated for the file.
+    pub blocks: u64,
 }
@@ -1,21 +1,45 @@
 use alloc::borrow::ToOwned;
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec;
 use alloc::vec::Vec;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use spin::RwLock;
 
-use crate::fs::{FileSystem, FsHandle};
-use crate::path::{AbsoluteOwnedPath, AbsolutePath};
-use crate::{CloseError, FsError, FsyncError, OpenError, ReadError, Stat, StatError, WriteError};
+use crate::fs::{FileSystem, FsHandle, FsNodeId, FsNodeKind};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath, OwnedPath};
+use crate::{
+    CloseError, FsError, FsyncError, OpenError, ReadError, ReadlinkError, Stat, StatError,
+    WriteError,
+};
 
-#[derive(Default)]
 pub struct TestFs {
     handle_counter: AtomicU64,
     files: BTreeMap<AbsoluteOwnedPath, RwLock<Vec<u8>>>,
     stats: BTreeMap<AbsoluteOwnedPath, Stat>,
     open_files: BTreeMap<FsHandle, AbsoluteOwnedPath>,
+    symlinks: BTreeMap<AbsoluteOwnedPath, OwnedPath>,
+    /// Directories without any entries. All other directories exist
+    /// implicitly as parents of files.
+    dirs: BTreeSet<AbsoluteOwnedPath>,
+    /// The path of every node that has been looked up, indexed by node id.
+    nodes: Vec<AbsoluteOwnedPath>,
+}
+
+impl Default for TestFs {
+    fn default() -> Self {
+        Self {
+            handle_counter: AtomicU64::default(),
+            files: BTreeMap::default(),
+            stats: BTreeMap::default(),
+            open_files: BTreeMap::default(),
+            symlinks: BTreeMap::default(),
+            dirs: BTreeSet::default(),
+            nodes: vec![AbsoluteOwnedPath::new()],
+        }
+    }
 }
 
 impl TestFs {
@@ -24,20 +48,109 @@ impl TestFs {
         self.files.insert(path.clone(), RwLock::new(data));
         self.stats.insert(path, stat);
     }
+
+    pub fn insert_symlink(&mut self, path: impl AsRef<AbsolutePath>, target: &str) {
+        self.symlinks
+            .insert(path.as_ref().to_owned(), OwnedPath::new(target));
+    }
+
+    pub fn insert_dir(&mut self, path: impl AsRef<AbsolutePath>) {
+        self.dirs.insert(path.as_ref().to_owned());
+    }
+
+    fn node_path(&self, node: FsNodeId) -> Result<&AbsoluteOwnedPath, OpenError> {
+        usize::try_from(node.get())
+            .ok()
+            .and_then(|index| self.nodes.get(index))
+            .ok_or(OpenError::NotFound)
+    }
+
+    fn kind_of(&self, path: &AbsoluteOwnedPath) -> Option<FsNodeKind> {
+        if self.files.contains_key(path) {
+            Some(FsNodeKind::File)
+        } else if self.symlinks.contains_key(path) {
+            Some(FsNodeKind::Symlink)
+        } else if path.as_str() == "/"
+            || self.dirs.contains(path)
+            || self.paths().any(|file| {
+                file.strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+        {
+            Some(FsNodeKind::Directory)
+        } else {
+            None
+        }
+    }
+}
+
+impl TestFs {
+    fn paths(&self) -> impl Iterator<Item = &AbsoluteOwnedPath> {
+        self.files
+            .keys()
+            .chain(self.symlinks.keys())
+            .chain(self.dirs.iter())
+    }
 }
 
 impl FileSystem for TestFs {
-    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
-        let owned = path.to_owned();
+    fn root(&self) -> FsNodeId {
+        FsNodeId::from(0)
+    }
+
+    fn lookup(&mut self, dir: FsNodeId, name: &str) -> Result<(FsNodeId, FsNodeKind), OpenError> {
+        let dir = self.node_path(dir)?;
+        if self.kind_of(dir) != Some(FsNodeKind::Directory) {
+            return Err(OpenError::NotDirectory);
+        }
+
+        let mut path = dir.clone();
+        path.push(name);
+        let kind = self.kind_of(&path).ok_or(OpenError::NotFound)?;
+        let index = self.nodes.iter().position(|node| node == &path);
+        let index = index.unwrap_or_else(|| {
+            self.nodes.push(path);
+            self.nodes.len() - 1
+        });
+        Ok((FsNodeId::from(index as u64), kind))
+    }
+
+    fn read_dir(&mut self, dir: FsNodeId) -> Result<Vec<String>, OpenError> {
+        let dir = self.node_path(dir)?;
+        if self.kind_of(dir) != Some(FsNodeKind::Directory) {
+            return Err(OpenError::NotDirectory);
+        }
+
+        let prefix = dir.as_str().trim_end_matches('/');
+        let names = self
+            .paths()
+            .filter_map(|path| path.strip_prefix(prefix)?.strip_prefix('/'))
+            .filter_map(|rest| rest.split('/').next())
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+            .collect::<BTreeSet<_>>();
+        Ok(names.into_iter().collect())
+    }
+
+    fn open(&mut self, node: FsNodeId) -> Result<FsHandle, OpenError> {
+        let owned = self.node_path(node)?.clone();
         if self.files.contains_key(&owned) {
             let handle = FsHandle::from(self.handle_counter.fetch_add(1, Relaxed));
-            self.open_files.insert(handle, owned.clone());
+            self.open_files.insert(handle, owned);
             Ok(handle)
         } else {
             Err(OpenError::NotFound)
         }
     }
 
+    fn readlink(&mut self, node: FsNodeId) -> Result<OwnedPath, ReadlinkError> {
+        let path = self.node_path(node)?;
+        self.symlinks
+            .get(path)
+            .cloned()
+            .ok_or(ReadlinkError::NotSymlink)
+    }
+
     fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
         self.open_files
             .remove(&handle)
@@ -99,7 +212,7 @@ impl FileSystem for TestFs {
 mod tests {
     use crate::CloseError;
     use crate::fs::FileSystem;
-    use crate::path::{AbsoluteOwnedPath, AbsolutePath};
+    use crate::path::AbsoluteOwnedPath;
     use crate::testing::TestFs;
 
     #[test]
@@ -110,8 +223,9 @@ mod tests {
             Default::default(),
         );
 
-        assert!(fs.open(AbsolutePath::try_new("/bar").unwrap()).is_err());
-        let handle = fs.open(AbsolutePath::try_new("/foo").unwrap()).unwrap();
+        assert!(fs.lookup(fs.root(), "bar").is_err());
+        let (node, _) = fs.lookup(fs.root(), "foo").unwrap();
+        let handle = fs.open(node).unwrap();
 
         assert!(fs.close(handle).is_ok());
         assert_eq!(Err(CloseError::NotOpen), fs.close(handle));
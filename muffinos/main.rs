@@ -145,6 +145,15 @@ continue"
     cmd.arg("-device");
     cmd.arg("virtio-blk-pci,drive=virtio-disk0");
 
+    // The kernel has no driver for the CD drive, so the ISO is attached a
+    // second time as /dev/blk1, which the kernel mounts at /boot.
+    cmd.arg("-drive");
+    cmd.arg(format!(
+        "id=virtio-boot0,file={BOOTABLE_ISO},format=raw,if=none,readonly=on"
+    ));
+    cmd.arg("-device");
+    cmd.arg("virtio-blk-pci,drive=virtio-boot0");
+
     // Prefer KVM, falling back to TCG so the runner still boots on hosts
     // without /dev/kvm instead of failing to launch QEMU.
     #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use minilib::{O_RDWR, open_with, println, tcgetwinsize, write};
+
+minilib::entry!(main);
+
+fn main() -> i32 {
+    let Ok(fd) = open_with("/dev/tty0", O_RDWR) else {
+        println!("console: FAIL open");
+        return 1;
+    };
+
+    // The window size is the screen in cells of 8x16 pixels.
+    let Ok(size) = tcgetwinsize(fd) else {
+        println!("console: FAIL tcgetwinsize");
+        return 1;
+    };
+    if size.rows == 0
+        || size.cols == 0
+        || size.cols != size.xpixel / 8
+        || size.rows != size.ypixel / 16
+    {
+        println!(
+            "console: FAIL size {}x{} for {}x{} pixels",
+            size.cols, size.rows, size.xpixel, size.ypixel
+        );
+        return 1;
+    }
+    println!("console: size ok");
+
+    // colors, cursor movement and erasing all end up on the screen
+    let text = b"\x1b[2J\x1b[H\x1b[1;32mmuffin\x1b[0m\x1b[5;10Hconsole\x1b[K\r\n";
+    match write(fd, text) {
+        Ok(n) if n == text.len() => println!("console: write ok"),
+        Ok(n) => {
+            println!("console: FAIL short write {n}");
+            return 1;
+        }
+        Err(_) => {
+            println!("console: FAIL write");
+            return 1;
+        }
+    }
+    0
+}
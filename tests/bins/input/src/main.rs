@@ -0,0 +1,127 @@
+#![no_std]
+#![no_main]
+
+use core::ffi::c_int;
+
+use minilib::{
+    AbsoluteAxis, EAGAIN, EINVAL, EventType, InputEvent, KeyCode, O_NONBLOCK, O_RDWR, PollEvents,
+    PollFd, RelativeAxis, SYN_REPORT, input_abs_info, input_capabilities, input_name, open_with,
+    poll, posix_openpt, println, ptsname, read, write,
+};
+
+minilib::entry!(main);
+
+fn pollfd(fd: c_int, events: PollEvents) -> PollFd {
+    PollFd {
+        fd,
+        events: events.bits(),
+        revents: 0,
+    }
+}
+
+fn main() -> i32 {
+    let Ok(keyboard) = open_with("/dev/input/event0", O_NONBLOCK) else {
+        println!("input: FAIL open /dev/input/event0");
+        return 1;
+    };
+    let name = input_name(keyboard);
+    let capabilities = input_capabilities(keyboard);
+    match (&name, capabilities) {
+        (Ok(name), Ok(capabilities))
+            if name == "PS/2 keyboard"
+                && capabilities.contains(EventType::Sync, SYN_REPORT)
+                && capabilities.contains(EventType::Key, KeyCode::A.number())
+                && !capabilities.contains(EventType::Relative, 0) => {}
+        _ => {
+            println!("input: FAIL device {name:?}");
+            return 1;
+        }
+    }
+    if input_abs_info(keyboard, AbsoluteAxis::X) != Err(EINVAL) {
+        println!("input: FAIL a keyboard has no absolute axes");
+        return 1;
+    }
+    println!("input: device ok");
+
+    // the mouse comes up after the keyboard, with whatever buttons QEMU
+    // unlocks beyond the first three
+    let Ok(mouse) = open_with("/dev/input/event1", O_NONBLOCK) else {
+        println!("input: FAIL open /dev/input/event1");
+        return 1;
+    };
+    let name = input_name(mouse);
+    let capabilities = input_capabilities(mouse);
+    match (&name, capabilities) {
+        (Ok(name), Ok(capabilities))
+            if name.starts_with("PS/2")
+                && name.ends_with("mouse")
+                && capabilities.contains(EventType::Relative, RelativeAxis::X as u16)
+                && capabilities.contains(EventType::Relative, RelativeAxis::Y as u16)
+                && capabilities.contains(EventType::Key, KeyCode::BtnLeft.number())
+                && capabilities.contains(EventType::Key, KeyCode::BtnMiddle.number())
+                && !capabilities.contains(EventType::Key, KeyCode::A.number()) => {}
+        _ => {
+            println!("input: FAIL mouse {name:?}");
+            return 1;
+        }
+    }
+    let mut buf = [0_u8; InputEvent::SIZE];
+    if read(mouse, &mut buf) != Err(EAGAIN) {
+        println!("input: FAIL mouse read");
+        return 1;
+    }
+    println!("input: mouse ok");
+
+    // nobody types, so there is never anything to read
+    let mut buf = [0_u8; 4 * InputEvent::SIZE];
+    if read(keyboard, &mut buf) != Err(EAGAIN) || read(keyboard, &mut buf[..1]) != Err(EINVAL) {
+        println!("input: FAIL read");
+        return 1;
+    }
+    println!("input: read ok");
+
+    let mut fds = [pollfd(keyboard, PollEvents::POLLIN)];
+    if poll(&mut fds, 0) != Ok(0) || poll(&mut fds, 50) != Ok(0) || fds[0].revents != 0 {
+        println!("input: FAIL poll timeout");
+        return 1;
+    }
+    println!("input: poll timeout ok");
+
+    let Ok(master) = posix_openpt() else {
+        println!("input: FAIL posix_openpt");
+        return 1;
+    };
+    let Some(slave) = ptsname(master)
+        .ok()
+        .and_then(|name| open_with(&name, O_RDWR).ok())
+    else {
+        println!("input: FAIL open the slave");
+        return 1;
+    };
+    let mut fds = [
+        pollfd(slave, PollEvents::POLLIN | PollEvents::POLLOUT),
+        pollfd(master, PollEvents::POLLIN),
+        pollfd(-1, PollEvents::POLLIN),
+        pollfd(slave + 1, PollEvents::POLLIN),
+    ];
+    // a partial line isn't readable yet, but echo is
+    if write(master, b"hi").is_err()
+        || poll(&mut fds, 1000) != Ok(3)
+        || fds[0].revents != PollEvents::POLLOUT.bits()
+        || fds[1].revents != PollEvents::POLLIN.bits()
+        || fds[2].revents != 0
+        || fds[3].revents != PollEvents::POLLNVAL.bits()
+    {
+        println!("input: FAIL poll partial line");
+        return 1;
+    }
+    if write(master, b"\r").is_err()
+        || poll(&mut fds[..1], 1000) != Ok(1)
+        || fds[0].revents != (PollEvents::POLLIN | PollEvents::POLLOUT).bits()
+    {
+        println!("input: FAIL poll line");
+        return 1;
+    }
+    println!("input: poll pty ok");
+    0
+}
@@ -0,0 +1,178 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use minilib::{
+    EINTR, EIO, ENOTTY, EPERM, LocalFlags, SaFlags, SigAction, SigHandler, SigMaskHow, SigSet,
+    Signal, Timespec, getpgid, getpid, getppid, getsid, install_handler, nanosleep, println, read,
+    setpgid, setsid, sigaction, sigprocmask, tcgetattr, tcgetpgrp, tcsetattr, tcsetpgrp, write,
+};
+
+minilib::entry!(main);
+
+/// Roughly 14ms of spin per pump.
+const PUMP_SPIN: u64 = 2_000_000;
+const HANDLER_BUDGET: u32 = 60;
+/// Polls of 10ms each while waiting to lose the foreground.
+const FOREGROUND_BUDGET: u32 = 3000;
+
+static TTIN: AtomicBool = AtomicBool::new(false);
+static TTOU: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn ttin_handler(_: Signal) {
+    TTIN.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn ttou_handler(_: Signal) {
+    TTOU.store(true, Ordering::SeqCst);
+}
+
+/// Handlers run at timer ticks that land in user mode, so give the ticks a
+/// user frame to land on.
+fn pump() {
+    let mut counter: u64 = 0;
+    while counter < PUMP_SPIN {
+        counter = unsafe { core::ptr::read_volatile(&counter) } + 1;
+    }
+}
+
+fn handled(flag: &AtomicBool) -> bool {
+    for _ in 0..HANDLER_BUDGET {
+        if flag.load(Ordering::SeqCst) {
+            return true;
+        }
+        pump();
+    }
+    false
+}
+
+fn block_ttou(how: SigMaskHow) {
+    let set: SigSet = Signal::TerminalOutput.bit();
+    let _ = sigprocmask(how, Some(&set), None);
+}
+
+/// Both instances share the serial terminal. The first one started owns its
+/// foreground process group, the second one runs in the background.
+fn main() -> i32 {
+    match (tcgetpgrp(0), getpgid(0)) {
+        (Ok(foreground), Ok(own)) if foreground == own => foreground_job(own),
+        (Ok(foreground), Ok(_)) => background_job(foreground),
+        _ => {
+            println!("jobs: FAIL tcgetpgrp");
+            1
+        }
+    }
+}
+
+/// Waits until the background instance takes over the terminal.
+fn foreground_job(own: i64) -> i32 {
+    println!("jobs: foreground ok");
+    let tick = Timespec {
+        tv_sec: 0,
+        tv_nsec: 10_000_000,
+    };
+    for _ in 0..FOREGROUND_BUDGET {
+        if tcgetpgrp(0) != Ok(own) {
+            println!("jobs: lost foreground");
+            return 0;
+        }
+        let _ = nanosleep(&tick, None);
+    }
+    println!("jobs: FAIL still in the foreground");
+    1
+}
+
+fn background_job(foreground: i64) -> i32 {
+    let pid = getpid();
+    if getppid() != 0 || getsid(0) != Ok(0) || getpgid(0) != Ok(pid) {
+        println!("jobs: FAIL ids");
+        return 1;
+    }
+    if setsid() != Err(EPERM) || setpgid(0, 4242) != Err(EPERM) {
+        println!("jobs: FAIL group leader");
+        return 1;
+    }
+    println!("jobs: ids ok");
+
+    // Reading from the background raises SIGTTIN, or fails once it's
+    // ignored.
+    let mut buf = [0u8; 16];
+    let _ = install_handler(Signal::TerminalInput, ttin_handler);
+    if read(0, &mut buf) != Err(EINTR) || !handled(&TTIN) {
+        println!("jobs: FAIL background read");
+        return 1;
+    }
+    let ignore = SigAction {
+        handler: SigHandler::IGNORE,
+        mask: 0,
+        flags: SaFlags::default(),
+        restorer: 0,
+    };
+    let _ = sigaction(Signal::TerminalInput, Some(&ignore), None);
+    if read(0, &mut buf) != Err(EIO) {
+        println!("jobs: FAIL ignored background read");
+        return 1;
+    }
+    println!("jobs: ttin ok");
+
+    // Changing the terminal from the background raises SIGTTOU, and so does
+    // writing once TOSTOP is set. Printing needs SIGTTOU blocked meanwhile.
+    let _ = install_handler(Signal::TerminalOutput, ttou_handler);
+    let Ok(original) = tcgetattr(0) else {
+        println!("jobs: FAIL tcgetattr");
+        return 1;
+    };
+    let mut tostop = original;
+    tostop.lflag.insert(LocalFlags::TOSTOP);
+    if tcsetattr(0, &tostop) != Err(EINTR) || !handled(&TTOU) {
+        println!("jobs: FAIL background tcsetattr");
+        return 1;
+    }
+    TTOU.store(false, Ordering::SeqCst);
+    block_ttou(SigMaskHow::Block);
+    if tcsetattr(0, &tostop).is_err() {
+        println!("jobs: FAIL blocked tcsetattr");
+        return 1;
+    }
+    block_ttou(SigMaskHow::Unblock);
+    let written = write(1, b"jobs: UNREACHABLE\n");
+    let signaled = handled(&TTOU);
+    block_ttou(SigMaskHow::Block);
+    if written != Err(EINTR) || !signaled {
+        println!("jobs: FAIL background write");
+        return 1;
+    }
+    if tcsetattr(0, &original).is_err() {
+        println!("jobs: FAIL restore termios");
+        return 1;
+    }
+    println!("jobs: ttou ok");
+
+    // Moving into the foreground makes reads work.
+    if tcsetpgrp(0, pid) != Ok(()) {
+        println!("jobs: FAIL tcsetpgrp");
+        return 1;
+    }
+    println!("jobs: ready foreground");
+    match read(0, &mut buf) {
+        Ok(n) if &buf[..n] == b"fg\n" => println!("jobs: foreground read ok"),
+        _ => {
+            println!("jobs: FAIL foreground read");
+            return 1;
+        }
+    }
+
+    // Once it left its own group, it may lead a new session, which has no
+    // controlling terminal.
+    if setpgid(0, foreground) != Ok(()) || getpgid(0) != Ok(foreground) {
+        println!("jobs: FAIL join group");
+        return 1;
+    }
+    if setsid() != Ok(pid) || getsid(0) != Ok(pid) || tcgetpgrp(0) != Err(ENOTTY) {
+        println!("jobs: FAIL setsid");
+        return 1;
+    }
+    println!("jobs: session ok");
+    0
+}
@@ -0,0 +1,96 @@
+#![no_std]
+#![no_main]
+
+use minilib::{EINVAL, IoctlRequest, MountFlags, ioctl, mount, open, println, read, umount};
+
+const EXPECTED: &[u8] = b"muffin says hi\n";
+
+minilib::entry!(main);
+
+/// Writes `/dev/blk{number}` into `buf`.
+fn device_path(number: usize, buf: &mut [u8; 32]) -> &str {
+    let prefix = b"/dev/blk";
+    buf[..prefix.len()].copy_from_slice(prefix);
+    let mut digits = [0u8; 20];
+    let mut len = 0;
+    let mut rest = number;
+    loop {
+        digits[len] = b'0' + (rest % 10) as u8;
+        len += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    for (i, digit) in digits[..len].iter().rev().enumerate() {
+        buf[prefix.len() + i] = *digit;
+    }
+    core::str::from_utf8(&buf[..prefix.len() + len]).unwrap()
+}
+
+fn main() -> i32 {
+    let (Ok(image), Ok(control)) = (open("/data/fat.img"), open("/dev/loop-control")) else {
+        println!("loop: FAIL open");
+        return 1;
+    };
+
+    // only regular files can back a loop device
+    let mut not_regular = control;
+    if ioctl(control, IoctlRequest::LoopAttach, &mut not_regular) != Err(EINVAL) {
+        println!("loop: FAIL attach device file");
+        return 1;
+    }
+
+    let mut fd = image;
+    let Ok(number) = ioctl(control, IoctlRequest::LoopAttach, &mut fd) else {
+        println!("loop: FAIL attach");
+        return 1;
+    };
+    println!("loop: attached blk{number}");
+
+    let mut path_buf = [0u8; 32];
+    let device = device_path(number, &mut path_buf);
+    if mount("vfat", device, "/data/mnt", MountFlags::READ_ONLY).is_err() {
+        println!("loop: FAIL mount");
+        return 1;
+    }
+
+    let Ok(file) = open("/data/mnt/hello.txt") else {
+        println!("loop: FAIL open file");
+        return 1;
+    };
+    let mut buf = [0u8; 64];
+    let mut filled = 0;
+    while let Ok(n @ 1..) = read(file, &mut buf[filled..]) {
+        filled += n;
+    }
+    if &buf[..filled] != EXPECTED {
+        println!("loop: FAIL content");
+        return 1;
+    }
+    println!("loop: content ok");
+
+    if umount("/data/mnt").is_err() {
+        println!("loop: FAIL umount");
+        return 1;
+    }
+    let mut detach = number as u32;
+    if ioctl(control, IoctlRequest::LoopDetach, &mut detach).is_err() {
+        println!("loop: FAIL detach");
+        return 1;
+    }
+    if open(device).is_ok() || ioctl(control, IoctlRequest::LoopDetach, &mut detach) != Err(EINVAL)
+    {
+        println!("loop: FAIL detached device still there");
+        return 1;
+    }
+    // the disk that the test runs from isn't a loop device
+    let mut root = 0_u32;
+    if ioctl(control, IoctlRequest::LoopDetach, &mut root) != Err(EINVAL) {
+        println!("loop: FAIL detach non-loop device");
+        return 1;
+    }
+    println!("loop: detach ok");
+
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::format;
+
+use minilib::{IoctlRequest, O_CREAT, O_RDWR, execve, ioctl, open, open_with, println, write};
+
+const IMAGE_SIZE: usize = 2 * 1024 * 1024;
+
+minilib::entry!(main);
+
+fn main() -> i32 {
+    let Ok(image) = open_with("/tmp/scratch.img", O_CREAT | O_RDWR) else {
+        println!("mkfs-loop: FAIL create image");
+        return 1;
+    };
+    let chunk = [0_u8; 4096];
+    let mut written = 0;
+    while written < IMAGE_SIZE {
+        match write(image, &chunk) {
+            Ok(n @ 1..) => written += n,
+            _ => {
+                println!("mkfs-loop: FAIL write image");
+                return 1;
+            }
+        }
+    }
+
+    let Ok(control) = open("/dev/loop-control") else {
+        println!("mkfs-loop: FAIL open loop-control");
+        return 1;
+    };
+    let mut fd = image;
+    let Ok(number) = ioctl(control, IoctlRequest::LoopAttach, &mut fd) else {
+        println!("mkfs-loop: FAIL attach");
+        return 1;
+    };
+    let device = format!("/dev/blk{number}");
+    println!("mkfs-loop: formatting {device}");
+
+    let errno = execve("/bin/mkfs", &["mkfs", "-L", "scratch", &device], &[]);
+    println!("mkfs-loop: FAIL execve {errno}");
+    1
+}
@@ -7,6 +7,7 @@ mod check;
 mod exec;
 mod fd;
 mod mem;
+mod mount;
 mod process;
 mod signal;
 mod time;
@@ -21,6 +22,7 @@ fn main() -> i32 {
     process::run();
     signal::run();
     time::run();
+    mount::run();
     exec::run();
 
     minilib::println!("posix: all checks passed");
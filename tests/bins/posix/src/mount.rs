@@ -0,0 +1,79 @@
+use minilib::{
+    EBUSY, EEXIST, EINVAL, ENODEV, ENOTDIR, ENOTEMPTY, ERANGE, MountFlags, O_CREAT, O_EXCL, S_IFMT,
+    S_IFREG, Stat, fstat, mount, mount_table, open, open_with, umount, write,
+};
+
+use crate::check;
+
+pub fn run() {
+    check::group("mount");
+
+    let mut buf = [0_u8; 512];
+    let len = check::unwrap_or_fail("mount/table", mount_table(&mut buf));
+    let table = core::str::from_utf8(&buf[..len]).unwrap_or("");
+    check::require("mount/table_root", table.contains("/dev/blk0 / ext2 rw\n"));
+    check::require("mount/table_dev", table.contains("none /dev devfs rw\n"));
+    check::require("mount/table_tmp", table.contains("none /tmp tmpfs rw\n"));
+    check::expect_err("mount/table_too_small", mount_table(&mut buf[..1]), ERANGE);
+
+    let none = MountFlags::empty();
+    check::expect_err(
+        "mount/unknown_type",
+        mount("nope", "", "/data/dir", none),
+        ENODEV,
+    );
+    check::expect_err(
+        "mount/not_empty",
+        mount("devfs", "", "/data", none),
+        ENOTEMPTY,
+    );
+    check::expect_err(
+        "mount/not_directory",
+        mount("devfs", "", "/data/hello.txt", none),
+        ENOTDIR,
+    );
+    check::expect_err("mount/umount_not_mounted", umount("/data/dir"), EINVAL);
+    // stdin, stdout and stderr are open on /dev
+    check::expect_err("mount/umount_busy_dev", umount("/dev"), EBUSY);
+
+    check::expect_ok(
+        "mount/devfs",
+        mount("devfs", "", "/data/dir", MountFlags::NOEXEC),
+        (),
+    );
+    check::expect_err(
+        "mount/already_mounted",
+        mount("devfs", "", "/data/dir", none),
+        EBUSY,
+    );
+    let len = check::unwrap_or_fail("mount/table_after_mount", mount_table(&mut buf));
+    let table = core::str::from_utf8(&buf[..len]).unwrap_or("");
+    check::require(
+        "mount/table_new_mount",
+        table.contains("none /data/dir devfs rw,noexec\n"),
+    );
+
+    check::expect_ok("mount/umount", umount("/data/dir"), ());
+
+    // there is no close yet, so the node stays open and the mount busy
+    check::expect_ok("mount/remount", mount("devfs", "", "/data/dir", none), ());
+    check::unwrap_or_fail("mount/open_in_mount", open("/data/dir/null"));
+    check::expect_err("mount/umount_busy", umount("/data/dir"), EBUSY);
+
+    tmp();
+}
+
+/// `/tmp` is a tmpfs, so files can be created there.
+fn tmp() {
+    let fd = check::unwrap_or_fail("mount/tmp_create", open_with("/tmp/scratch", O_CREAT));
+    check::expect_ok("mount/tmp_write", write(fd, b"hello"), 5);
+    let mut stat = Stat::default();
+    check::expect_ok("mount/tmp_fstat", fstat(fd, &mut stat), ());
+    check::require("mount/tmp_size", stat.size == 5);
+    check::require("mount/tmp_mode", stat.mode & S_IFMT == S_IFREG);
+    check::expect_err(
+        "mount/tmp_create_excl",
+        open_with("/tmp/scratch", O_CREAT | O_EXCL),
+        EEXIST,
+    );
+}
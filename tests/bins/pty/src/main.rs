@@ -0,0 +1,205 @@
+#![no_std]
+#![no_main]
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use minilib::{
+    EAGAIN, O_NONBLOCK, O_RDWR, Signal, Timespec, WinSize, getpgid, getpid, getsid,
+    install_handler, nanosleep, open_with, posix_openpt, println, ptsname, read, setpgid, setsid,
+    tcgetpgrp, tcgetwinsize, tcsetwinsize, write,
+};
+
+minilib::entry!(main);
+
+/// Roughly 14ms of spin per pump.
+const PUMP_SPIN: u64 = 2_000_000;
+const HANDLER_BUDGET: u32 = 60;
+/// Polls of 10ms each while the holder waits for the other instance.
+const HOLD_BUDGET: u32 = 3000;
+
+static RESIZED: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn resize_handler(_: Signal) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn interrupt_handler(_: Signal) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Handlers run at timer ticks that land in user mode, so give the ticks a
+/// user frame to land on.
+fn pump() {
+    let mut counter: u64 = 0;
+    while counter < PUMP_SPIN {
+        counter = unsafe { core::ptr::read_volatile(&counter) } + 1;
+    }
+}
+
+fn handled(flag: &AtomicBool) -> bool {
+    for _ in 0..HANDLER_BUDGET {
+        if flag.load(Ordering::SeqCst) {
+            return true;
+        }
+        pump();
+    }
+    false
+}
+
+fn read_is(fd: c_int, expected: &[u8]) -> bool {
+    let mut buf = [0u8; 32];
+    matches!(read(fd, &mut buf), Ok(n) if &buf[..n] == expected)
+}
+
+/// More than the master buffers of the slave's output.
+const LARGE_WRITE: usize = 10_000;
+
+/// Writes more than the master buffers through `slave`, which is
+/// nonblocking, and checks that the master reads every byte of it.
+fn backpressure(master: c_int, slave: c_int) -> bool {
+    let data = [b'x'; LARGE_WRITE];
+    // the first write fills the master up, so the next one has no room
+    let Ok(first) = write(slave, &data) else {
+        return false;
+    };
+    if first == 0 || first == LARGE_WRITE || write(slave, &data[first..]) != Err(EAGAIN) {
+        return false;
+    }
+
+    let mut written = first;
+    let mut received = 0;
+    let mut buf = [0u8; 512];
+    while received < LARGE_WRITE {
+        while received < written {
+            let len = buf.len().min(written - received);
+            match read(master, &mut buf[..len]) {
+                Ok(n) if n > 0 && buf[..n].iter().all(|&b| b == b'x') => received += n,
+                _ => return false,
+            }
+        }
+        if written < LARGE_WRITE {
+            match write(slave, &data[written..]) {
+                Ok(n) => written += n,
+                Err(_) => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Both instances start in their own process group on the serial terminal.
+/// The first one only holds a process group that the second one joins, so
+/// that the second one can start a session of its own.
+fn main() -> i32 {
+    match (tcgetpgrp(0), getpgid(0)) {
+        (Ok(foreground), Ok(own)) if foreground == own => holder(),
+        (Ok(foreground), Ok(_)) => pty_session(foreground),
+        _ => {
+            println!("pty: FAIL tcgetpgrp");
+            1
+        }
+    }
+}
+
+/// Waits until the other instance, which was started right after this one,
+/// leads its own session.
+fn holder() -> i32 {
+    let other = getpid() + 1;
+    let tick = Timespec {
+        tv_sec: 0,
+        tv_nsec: 10_000_000,
+    };
+    for _ in 0..HOLD_BUDGET {
+        if getsid(other) == Ok(other) {
+            return 0;
+        }
+        let _ = nanosleep(&tick, None);
+    }
+    println!("pty: FAIL the other instance never started a session");
+    1
+}
+
+fn pty_session(holder_group: i64) -> i32 {
+    let pid = getpid();
+    if setpgid(0, holder_group).is_err() || setsid() != Ok(pid) {
+        println!("pty: FAIL setsid");
+        return 1;
+    }
+
+    // The session leader has no controlling terminal, so the slave becomes
+    // its controlling terminal when it opens it.
+    let Ok(master) = posix_openpt() else {
+        println!("pty: FAIL posix_openpt");
+        return 1;
+    };
+    let Ok(name) = ptsname(master) else {
+        println!("pty: FAIL ptsname");
+        return 1;
+    };
+    let Ok(slave) = open_with(&name, O_RDWR) else {
+        println!("pty: FAIL open {name}");
+        return 1;
+    };
+    if !name.starts_with("/dev/pts/") || tcgetpgrp(slave) != Ok(pid) {
+        println!("pty: FAIL controlling terminal");
+        return 1;
+    }
+    println!("pty: open ok");
+
+    // What the master writes is typed into the slave, which echoes it back.
+    if write(master, b"hi\r") != Ok(3) || !read_is(slave, b"hi\n") || !read_is(master, b"hi\r\n") {
+        println!("pty: FAIL input");
+        return 1;
+    }
+    println!("pty: input ok");
+
+    if write(slave, b"out\n") != Ok(4) || !read_is(master, b"out\r\n") {
+        println!("pty: FAIL output");
+        return 1;
+    }
+    println!("pty: output ok");
+
+    // Output that the master can't take yet isn't dropped, the slave waits
+    // for room, which a nonblocking slave reports as EAGAIN.
+    let Ok(nonblocking) = open_with(&name, O_RDWR | O_NONBLOCK) else {
+        println!("pty: FAIL open {name} nonblocking");
+        return 1;
+    };
+    if !backpressure(master, nonblocking) {
+        println!("pty: FAIL backpressure");
+        return 1;
+    }
+    println!("pty: backpressure ok");
+
+    let _ = install_handler(Signal::WindowChanged, resize_handler);
+    let size = WinSize {
+        rows: 24,
+        cols: 80,
+        xpixel: 0,
+        ypixel: 0,
+    };
+    if tcsetwinsize(master, &size).is_err() || !handled(&RESIZED) || tcgetwinsize(slave) != Ok(size)
+    {
+        println!("pty: FAIL window size");
+        return 1;
+    }
+    println!("pty: winsize ok");
+
+    let _ = install_handler(Signal::Interrupt, interrupt_handler);
+    if write(master, b"\x03").is_err() || !handled(&INTERRUPTED) {
+        println!("pty: FAIL interrupt");
+        return 1;
+    }
+    println!("pty: interrupt ok");
+
+    match posix_openpt().and_then(ptsname) {
+        Ok(second) if second != name => println!("pty: second ok"),
+        _ => {
+            println!("pty: FAIL second pty");
+            return 1;
+        }
+    }
+    0
+}
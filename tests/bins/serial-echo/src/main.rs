@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use minilib::{EAGAIN, O_NONBLOCK, open, open_with, println, read};
+
+minilib::entry!(main);
+
+fn main() -> i32 {
+    let Ok(fd) = open("/dev/serial") else {
+        println!("serial-echo: FAIL open");
+        return 1;
+    };
+
+    // The host only starts typing once it sees this line, so every byte
+    // below has to come through the receive interrupt.
+    println!("serial-echo: ready");
+
+    let mut buf = [0u8; 64];
+    let mut filled = 0usize;
+    while !buf[..filled].contains(&b'\n') {
+        if filled >= buf.len() {
+            println!("serial-echo: FAIL line too long");
+            return 1;
+        }
+        match read(fd, &mut buf[filled..]) {
+            Ok(0) => {
+                println!("serial-echo: FAIL end of file");
+                return 1;
+            }
+            Ok(n) => filled += n,
+            Err(_) => {
+                println!("serial-echo: FAIL read");
+                return 1;
+            }
+        }
+    }
+    let Ok(line) = core::str::from_utf8(&buf[..filled]) else {
+        println!("serial-echo: FAIL not utf-8");
+        return 1;
+    };
+    println!("serial-echo: got {}", line.trim_end());
+
+    // Everything typed so far was consumed, so a non-blocking read must
+    // fail instead of waiting.
+    let Ok(fd) = open_with("/dev/serial", O_NONBLOCK) else {
+        println!("serial-echo: FAIL open nonblocking");
+        return 1;
+    };
+    if read(fd, &mut buf) != Err(EAGAIN) {
+        println!("serial-echo: FAIL nonblocking read");
+        return 1;
+    }
+    println!("serial-echo: eagain ok");
+
+    0
+}
@@ -0,0 +1,130 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use minilib::{EINTR, LocalFlags, Signal, install_handler, println, read, tcgetattr, tcsetattr};
+
+minilib::entry!(main);
+
+/// Roughly 14ms of spin per pump.
+const PUMP_SPIN: u64 = 2_000_000;
+const HANDLER_BUDGET: u32 = 60;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn interrupt_handler(_: Signal) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Handlers run at timer ticks that land in user mode, so give the ticks a
+/// user frame to land on.
+fn pump() {
+    let mut counter: u64 = 0;
+    while counter < PUMP_SPIN {
+        counter = unsafe { core::ptr::read_volatile(&counter) } + 1;
+    }
+}
+
+/// Reads from stdin until `buf` is full or a read returns nothing.
+fn read_exact(buf: &mut [u8]) -> Result<usize, &'static str> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match read(0, &mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(_) => return Err("read"),
+        }
+    }
+    Ok(filled)
+}
+
+fn main() -> i32 {
+    let Ok(canonical) = tcgetattr(0) else {
+        println!("tty: FAIL tcgetattr");
+        return 1;
+    };
+    if !canonical
+        .lflag
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO)
+    {
+        println!("tty: FAIL not canonical by default");
+        return 1;
+    }
+
+    // The host types "helo", erases the "o" and finishes with CR. A
+    // canonical read returns the edited line, with CR turned into NL.
+    println!("tty: ready line");
+    let mut buf = [0u8; 64];
+    match read(0, &mut buf) {
+        Ok(n) if &buf[..n] == b"hello\n" => println!("tty: line ok"),
+        Ok(n) => {
+            println!("tty: FAIL line {:?}", &buf[..n]);
+            return 1;
+        }
+        Err(_) => {
+            println!("tty: FAIL read line");
+            return 1;
+        }
+    }
+
+    // In raw mode, bytes arrive as they were typed, without a line to wait
+    // for and without CR translation.
+    let mut raw = canonical;
+    raw.make_raw();
+    if tcsetattr(0, &raw).is_err() {
+        println!("tty: FAIL tcsetattr raw");
+        return 1;
+    }
+    println!("tty: ready raw");
+    let mut raw_buf = [0u8; 3];
+    match read_exact(&mut raw_buf) {
+        Ok(3) if &raw_buf == b"a\x7f\r" => println!("tty: raw ok"),
+        Ok(n) => {
+            println!("tty: FAIL raw {:?}", &raw_buf[..n]);
+            return 1;
+        }
+        Err(e) => {
+            println!("tty: FAIL raw {e}");
+            return 1;
+        }
+    }
+
+    if tcsetattr(0, &canonical).is_err() || tcgetattr(0) != Ok(canonical) {
+        println!("tty: FAIL restore");
+        return 1;
+    }
+
+    // EOF at the start of a line reads as end of file.
+    println!("tty: ready eof");
+    match read(0, &mut buf) {
+        Ok(0) => println!("tty: eof ok"),
+        _ => {
+            println!("tty: FAIL eof");
+            return 1;
+        }
+    }
+
+    // The interrupt character signals the foreground process, which is us,
+    // and interrupts the blocked read.
+    if install_handler(Signal::Interrupt, interrupt_handler).is_err() {
+        println!("tty: FAIL install handler");
+        return 1;
+    }
+    println!("tty: ready interrupt");
+    // The handler only runs at a timer tick, so it can't have consumed the
+    // signal before the read unless it set the flag already.
+    if !INTERRUPTED.load(Ordering::SeqCst) && read(0, &mut buf) != Err(EINTR) {
+        println!("tty: FAIL read not interrupted");
+        return 1;
+    }
+    for _ in 0..HANDLER_BUDGET {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            println!("tty: interrupt ok");
+            return 0;
+        }
+        pump();
+    }
+    println!("tty: FAIL handler did not run");
+    1
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::format;
+use core::ffi::c_int;
+
+use minilib::{
+    AbsoluteAxis, EAGAIN, EventType, InputEvent, KeyCode, O_NONBLOCK, input_abs_info,
+    input_capabilities, input_name, open_with, println, read,
+};
+
+minilib::entry!(main);
+
+/// QEMU scales tablet positions to this maximum on both axes.
+const TABLET_MAX: i32 = 0x7fff;
+
+/// Opens the input device called `name`.
+fn find(name: &str) -> Option<c_int> {
+    // the numbers are handed out without gaps
+    (0..)
+        .map_while(|number| open_with(&format!("/dev/input/event{number}"), O_NONBLOCK).ok())
+        .find(|&fd| input_name(fd).is_ok_and(|n| n == name))
+}
+
+fn main() -> i32 {
+    let Some(keyboard) = find("QEMU Virtio Keyboard") else {
+        println!("virtio-input: FAIL no keyboard");
+        return 1;
+    };
+    let keys = input_capabilities(keyboard).is_ok_and(|capabilities| {
+        capabilities.contains(EventType::Key, KeyCode::A.number())
+            && capabilities.contains(EventType::Key, KeyCode::Enter.number())
+            && !capabilities.contains(EventType::Absolute, AbsoluteAxis::X as u16)
+    });
+    if !keys {
+        println!("virtio-input: FAIL keyboard capabilities");
+        return 1;
+    }
+    println!("virtio-input: keyboard ok");
+
+    let Some(tablet) = find("QEMU Virtio Tablet") else {
+        println!("virtio-input: FAIL no tablet");
+        return 1;
+    };
+    let pointer = input_capabilities(tablet).is_ok_and(|capabilities| {
+        capabilities.contains(EventType::Key, KeyCode::BtnLeft.number())
+            && capabilities.contains(EventType::Absolute, AbsoluteAxis::X as u16)
+            && capabilities.contains(EventType::Absolute, AbsoluteAxis::Y as u16)
+    });
+    let axes = [AbsoluteAxis::X, AbsoluteAxis::Y].into_iter().all(|axis| {
+        input_abs_info(tablet, axis).is_ok_and(|abs| abs.minimum == 0 && abs.maximum == TABLET_MAX)
+    });
+    if !pointer || !axes {
+        println!("virtio-input: FAIL tablet capabilities");
+        return 1;
+    }
+    println!("virtio-input: tablet ok");
+
+    // nothing moves the pointer, so there is nothing to read
+    let mut buf = [0_u8; InputEvent::SIZE];
+    if read(tablet, &mut buf) != Err(EAGAIN) {
+        println!("virtio-input: FAIL read");
+        return 1;
+    }
+    println!("virtio-input: read ok");
+    0
+}
@@ -0,0 +1,19 @@
+//! End-to-end test for the framebuffer console.
+//!
+//! Boots the generic `test-kernel` with `/bin/console` in the `/spawn`
+//! manifest on QEMU's standard VGA, whose framebuffer Limine hands to the
+//! kernel. The binary opens `/dev/tty0`, checks that its window size matches
+//! the screen and writes text with escape sequences to it.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 2] = ["console: size ok", "console: write ok"];
+
+#[test]
+fn console() {
+    let report = KernelTest::new("console", host_env!()).run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_no_line_contains("console: FAIL");
+    report.assert_exit_code(0, 0);
+}
@@ -5,7 +5,8 @@
 //! binary opens `/dev/fb0`, issues an `FbGetScreenInfo` ioctl, and prints the
 //! reported geometry, then confirms the `ENOTTY` default path on a regular
 //! file. The test asserts on its serial output and exit code at two
-//! resolutions.
+//! resolutions, and once without a virtio-gpu device, where `/dev/fb0` is the
+//! framebuffer that the firmware set up on QEMU's standard VGA.
 
 use test_support::{KernelTest, host_env};
 
@@ -36,3 +37,14 @@ fn fb_ioctl_800x600() {
 fn fb_ioctl_1280x720() {
     query_at("fb_ioctl_1280x720", 1280, 720);
 }
+
+#[test]
+fn fb_ioctl_firmware() {
+    // the firmware picks the mode, so only the pixel size is known
+    let report = KernelTest::new("fb_ioctl_firmware", host_env!()).run();
+
+    report.assert_line_contains("fb-ioctl: info ");
+    report.assert_line_contains(" bpp=32");
+    report.assert_line_contains("fb-ioctl: enotty ok");
+    report.assert_exit_code(0, 0);
+}
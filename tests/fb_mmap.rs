@@ -8,6 +8,9 @@
 //! memory rather than a private copy. It also confirms that shared mmap of a
 //! regular file is rejected, and that a `MAP_PRIVATE` mapping of the same
 //! device sees the framebuffer content but never writes back to it.
+//!
+//! It runs once more without a virtio-gpu device, where `/dev/fb0` is the
+//! framebuffer that the firmware set up on QEMU's standard VGA.
 
 use test_support::{KernelTest, host_env};
 
@@ -25,3 +28,11 @@ fn fb_mmap() {
     report.assert_line_contains("fb-mmap: ok");
     report.assert_exit_code(0, 0);
 }
+
+#[test]
+fn fb_mmap_firmware() {
+    let report = KernelTest::new("fb_mmap_firmware", host_env!()).run();
+
+    report.assert_line_contains("fb-mmap: ok");
+    report.assert_exit_code(0, 0);
+}
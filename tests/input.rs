@@ -0,0 +1,28 @@
+//! End-to-end test for input devices and `poll`.
+//!
+//! Boots the generic `test-kernel` with `/bin/input` in the `/spawn`
+//! manifest. It checks the name and capabilities of the PS/2 keyboard that
+//! QEMU always emulates and of the PS/2 mouse next to it, that reading
+//! them without input fails instead of blocking, and that `poll` times out
+//! on the keyboard. A pseudo-terminal then gives
+//! `poll` something to report: a slave that becomes readable once a whole
+//! line was typed and a master that reads the echo.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 5] = [
+    "input: device ok",
+    "input: mouse ok",
+    "input: read ok",
+    "input: poll timeout ok",
+    "input: poll pty ok",
+];
+
+#[test]
+fn input() {
+    let report = KernelTest::new("input", host_env!()).run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_no_line_contains("input: FAIL");
+    report.assert_exit_code(0, 0);
+}
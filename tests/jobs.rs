@@ -0,0 +1,34 @@
+//! End-to-end test for job control on the serial terminal.
+//!
+//! Boots the generic `test-kernel` with `/bin/jobs` twice in the `/spawn`
+//! manifest. The first instance owns the foreground process group of the
+//! terminal and waits to lose it. The second one starts in the background:
+//! it checks its ids, gets `SIGTTIN` for reading and `SIGTTOU` for changing
+//! the terminal and for writing with `TOSTOP`, then moves itself into the
+//! foreground, reads the line the harness types and finally starts its own
+//! session.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 6] = [
+    "jobs: ids ok",
+    "jobs: ttin ok",
+    "jobs: ttou ok",
+    "jobs: ready foreground",
+    "jobs: foreground read ok",
+    "jobs: session ok",
+];
+
+#[test]
+fn jobs() {
+    let report = KernelTest::new("jobs", host_env!())
+        .serial_input("jobs: ready foreground", "fg\r")
+        .run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_markers_in_order(&["jobs: foreground ok", "jobs: lost foreground"]);
+    report.assert_no_line_contains("jobs: FAIL");
+    report.assert_no_line_contains("jobs: UNREACHABLE");
+    report.assert_exit_code(0, 0);
+    report.assert_exit_code(1, 0);
+}
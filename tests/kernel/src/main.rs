@@ -8,20 +8,17 @@ use alloc::vec;
 use core::ffi::c_void;
 use core::ptr;
 
-use kernel::driver::block::BlockDevices;
-use kernel::file::ext2::VirtualExt2Fs;
 use kernel::file::vfs;
 use kernel::limine::BASE_REVISION;
-use kernel::mcore;
 use kernel::mcore::mtask::process::{ExitOutcome, ParkOutcome, Process};
 use kernel::mcore::mtask::scheduler::global::GlobalTaskQueue;
 use kernel::mcore::mtask::task::Task;
-use kernel_ext2::Ext2Fs;
+use kernel::{console, file, mcore, serial};
 use kernel_vfs::Stat;
-use kernel_vfs::path::{AbsolutePath, ROOT};
+use kernel_vfs::path::AbsolutePath;
 use tracing::info;
 
-/// Boots the real kernel, mounts the root ext2 filesystem, then spawns one
+/// Boots the real kernel, mounts the root filesystem, then spawns one
 /// process per line of the `/spawn` manifest baked into the disk image. Each
 /// manifest line is an absolute in-OS path spawned in order, so the first
 /// entry becomes pid 1. This is the generic test kernel shared by every
@@ -34,16 +31,7 @@ unsafe extern "C" fn main() -> ! {
 
     {
         info!("mounting root filesystem");
-        let root_block_device = BlockDevices::by_id(0).expect("should have block device with id 0");
-        vfs()
-            .write()
-            .mount(
-                ROOT,
-                VirtualExt2Fs::from(
-                    Ext2Fs::try_new(root_block_device).expect("should be able to create ext2fs"),
-                ),
-            )
-            .expect("should be able to mount ext2fs at /");
+        file::root::mount_root().expect("should be able to mount the root file system");
     }
 
     // A kernel stack overflow cannot be provoked from userspace, so the trigger has
@@ -61,6 +49,9 @@ unsafe extern "C" fn main() -> ! {
     let attach_exec_sibling = AbsolutePath::try_new("/exec-sibling")
         .is_ok_and(|marker| vfs().write().open(marker).is_ok());
 
+    // the spawned processes own the screen, which may be `/dev/fb0`
+    console::set_log_mirror(false);
+
     {
         info!("reading spawn manifest");
         let manifest_path = AbsolutePath::try_new("/spawn").expect("should be a valid path");
@@ -154,6 +145,11 @@ fn rust_panic(info: &core::panic::PanicInfo) -> ! {
 fn handle_panic(info: &core::panic::PanicInfo) {
     use tracing::error;
 
+    // the panic may have struck in the middle of a log record, with the
+    // serial port or the console locked
+    // Safety: this is the panic handler, which never returns
+    unsafe { serial::force_unlock() };
+    console::mirror_panic();
     if let Some(location) = info.location() {
         error!(
             "kernel panicked at {}:{}:{}:",
@@ -0,0 +1,19 @@
+//! End-to-end test for loop block devices.
+//!
+//! Boots the generic `test-kernel` under QEMU with `/bin/loop` in the
+//! `/spawn` manifest and a FAT image stored as a regular file on the disk.
+//! The binary attaches a loop device to the image through
+//! `/dev/loop-control`, mounts it, reads a file from it, and detaches the
+//! device again. The test asserts on its serial output and exit code.
+
+use test_support::{KernelTest, host_env};
+
+#[test]
+fn loop_device() {
+    let report = KernelTest::new("loop_device", host_env!()).run();
+
+    report.assert_line_contains("loop: attached blk");
+    report.assert_line_contains("loop: content ok");
+    report.assert_line_contains("loop: detach ok");
+    report.assert_exit_code(0, 0);
+}
@@ -0,0 +1,18 @@
+//! End-to-end test for the `mkfs` utility.
+//!
+//! Boots the generic `test-kernel` under QEMU with `/bin/mkfs-loop` in the
+//! `/spawn` manifest. The binary creates an empty image in `/tmp`, attaches
+//! a loop device to it and executes `/bin/mkfs` on the device. The test
+//! asserts on the summary that `mkfs` prints and on its exit code.
+
+use test_support::{KernelTest, host_env};
+
+#[test]
+fn mkfs() {
+    let report = KernelTest::new("mkfs", host_env!()).run();
+
+    report.assert_line_contains("mkfs-loop: formatting /dev/blk");
+    report.assert_line_contains("1024 bytes, 512 inodes, 1 block groups");
+    report.assert_line_contains("mkfs: /dev/blk");
+    report.assert_exit_code(0, 0);
+}
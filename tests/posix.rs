@@ -27,6 +27,7 @@ fn posix() {
         "posix: group process",
         "posix: group signal",
         "posix: group time",
+        "posix: group mount",
         "posix: group execve",
         "posix: all checks passed",
     ]);
@@ -0,0 +1,32 @@
+//! End-to-end test for pseudo-terminals.
+//!
+//! Boots the generic `test-kernel` with `/bin/pty` twice in the `/spawn`
+//! manifest. The first instance only holds a process group for the second
+//! one, which joins it to be allowed to start a session. The session leader
+//! then opens `/dev/ptmx` and its slave, which becomes its controlling
+//! terminal, and drives the slave through the master: typed input with
+//! echo, output translation, a large write that has to wait for the master
+//! to read, a window size change that raises `SIGWINCH` and the interrupt
+//! character that raises `SIGINT`.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 7] = [
+    "pty: open ok",
+    "pty: input ok",
+    "pty: output ok",
+    "pty: backpressure ok",
+    "pty: winsize ok",
+    "pty: interrupt ok",
+    "pty: second ok",
+];
+
+#[test]
+fn pty() {
+    let report = KernelTest::new("pty", host_env!()).run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_no_line_contains("pty: FAIL");
+    report.assert_exit_code(0, 0);
+    report.assert_exit_code(1, 0);
+}
@@ -0,0 +1,27 @@
+//! End-to-end test for interrupt-driven serial input.
+//!
+//! Boots the generic `test-kernel` under QEMU with `/bin/serial-echo` in the
+//! `/spawn` manifest. The binary announces that it is ready and blocks in a
+//! read on `/dev/serial`, and the harness types a line into QEMU's stdin once
+//! it sees the announcement. The line has to travel through the COM1 receive
+//! interrupt and wake the reader. A second, non-blocking read on the drained
+//! port must fail with `EAGAIN`.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 3] = [
+    "serial-echo: ready",
+    "serial-echo: got hello from the host",
+    "serial-echo: eagain ok",
+];
+
+#[test]
+fn serial_input() {
+    let report = KernelTest::new("serial_input", host_env!())
+        .serial_input("serial-echo: ready", "hello from the host\n")
+        .run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_no_line_contains("serial-echo: FAIL");
+    report.assert_exit_code(0, 0);
+}
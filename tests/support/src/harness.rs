@@ -17,7 +17,8 @@
 //! leading log prefix (timestamp, level, target) by anchoring on a substring
 //! inside the payload rather than matching from the start.
 
-use std::io::{BufRead, BufReader};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
@@ -68,6 +69,14 @@ pub struct KernelTest {
     env: HostEnv,
     deadline: Duration,
     qemu_args: Vec<String>,
+    serial_input: VecDeque<SerialInput>,
+}
+
+/// Bytes to type into the guest's serial port once a transcript line
+/// contains `after`.
+struct SerialInput {
+    after: String,
+    bytes: Vec<u8>,
 }
 
 impl KernelTest {
@@ -78,6 +87,7 @@ impl KernelTest {
             env,
             deadline: DEFAULT_DEADLINE,
             qemu_args: vec![],
+            serial_input: VecDeque::new(),
         }
     }
 
@@ -99,6 +109,18 @@ impl KernelTest {
         self
     }
 
+    /// Sends `bytes` to the guest's serial port once a transcript line
+    /// contains `after`. Inputs are sent in the order they were added, each
+    /// waiting for its marker after the previous one was sent.
+    #[must_use]
+    pub fn serial_input(mut self, after: &str, bytes: impl Into<Vec<u8>>) -> Self {
+        self.serial_input.push_back(SerialInput {
+            after: after.to_owned(),
+            bytes: bytes.into(),
+        });
+        self
+    }
+
     /// Boots the test kernel under QEMU and returns the collected transcript
     /// and per-process outcomes.
     ///
@@ -107,7 +129,7 @@ impl KernelTest {
     /// spawned process reports an outcome, if the deadline expires first, or if
     /// any serial line contains `kernel panicked`.
     #[must_use]
-    pub fn run(self) -> RunReport {
+    pub fn run(mut self) -> RunReport {
         // Runfiles are read-only build outputs. Booting them directly fails to
         // open and would corrupt the action cache.
         let out_dir = self.env.work_dir.join(self.name);
@@ -156,13 +178,19 @@ impl KernelTest {
             .arg("-device")
             .arg("virtio-blk-pci,drive=virtio-disk0")
             .args(&self.qemu_args)
-            .stdin(Stdio::null())
+            // QEMU reads the guest's serial input from stdin.
+            .stdin(if self.serial_input.is_empty() {
+                Stdio::null()
+            } else {
+                Stdio::piped()
+            })
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit())
             .spawn()
             .expect("failed to spawn qemu-system-x86_64");
 
         let stdout = child.stdout.take().expect("child stdout was not captured");
+        let mut stdin = child.stdin.take();
 
         let (tx, rx) = mpsc::channel::<String>();
         let reader = thread::spawn(move || {
@@ -230,6 +258,20 @@ impl KernelTest {
                     {
                         proc.outcome = Some(Outcome::Signaled(name));
                     }
+                    if let Some(input) = self.serial_input.front()
+                        && line.contains(&input.after)
+                    {
+                        let stdin = stdin.as_mut().expect("child stdin was not captured");
+                        if let Err(e) = stdin.write_all(&input.bytes).and_then(|()| stdin.flush()) {
+                            transcript.push(line);
+                            fail(
+                                &transcript,
+                                &mut child,
+                                format!("failed to send serial input: {e}"),
+                            );
+                        }
+                        self.serial_input.pop_front();
+                    }
                     transcript.push(line);
 
                     // Completion requires every announced process to have
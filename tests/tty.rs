@@ -0,0 +1,35 @@
+//! End-to-end test for the serial terminal.
+//!
+//! Boots the generic `test-kernel` with `/bin/tty` in the `/spawn` manifest.
+//! The binary reads from its standard input, which is the serial terminal,
+//! and announces every step before the harness types the input for it: an
+//! edited line in canonical mode, bytes in raw mode, an end of file and
+//! finally the interrupt character, which has to raise `SIGINT` in the
+//! foreground process and interrupt its blocked read.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 8] = [
+    "tty: ready line",
+    "tty: line ok",
+    "tty: ready raw",
+    "tty: raw ok",
+    "tty: ready eof",
+    "tty: eof ok",
+    "tty: ready interrupt",
+    "tty: interrupt ok",
+];
+
+#[test]
+fn tty() {
+    let report = KernelTest::new("tty", host_env!())
+        .serial_input("tty: ready line", "helo\x7flo\r")
+        .serial_input("tty: ready raw", "a\x7f\r")
+        .serial_input("tty: ready eof", "\x04")
+        .serial_input("tty: ready interrupt", "\x03")
+        .run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_no_line_contains("tty: FAIL");
+    report.assert_exit_code(0, 0);
+}
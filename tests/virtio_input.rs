@@ -0,0 +1,31 @@
+//! End-to-end test for virtio-input devices.
+//!
+//! Boots the generic `test-kernel` with `/bin/virtio-input` in the `/spawn`
+//! manifest and a virtio keyboard and tablet next to the PS/2 keyboard. The
+//! binary finds both by the names that their configuration space reports
+//! and checks the keys and axes that they registered with the input layer.
+
+use test_support::{KernelTest, host_env};
+
+const MARKERS: [&str; 3] = [
+    "virtio-input: keyboard ok",
+    "virtio-input: tablet ok",
+    "virtio-input: read ok",
+];
+
+#[test]
+fn virtio_input() {
+    let report = KernelTest::new("virtio_input", host_env!())
+        .qemu_args([
+            "-device",
+            "virtio-keyboard-pci",
+            "-device",
+            "virtio-tablet-pci",
+        ])
+        .run();
+
+    report.assert_markers_in_order(&MARKERS);
+    report.assert_no_line_contains("virtio-input: FAIL");
+    report.assert_no_line_contains("failed to init driver virtio-input");
+    report.assert_exit_code(0, 0);
+}
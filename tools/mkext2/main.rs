@@ -0,0 +1,564 @@
+//! Builds an ext2 image on the host through `kernel_ext2`, the same crate the
+//! kernel mounts it with.
+//!
+//! The image depends on nothing but the inputs. Every inode gets the same
+//! time stamp, modes are derived from the kind of entry and the executable
+//! bit of its source, and entries are created in sorted order, so inode and
+//! block numbers come out the same on every host.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::os::unix::fs::{FileExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::Parser;
+use kernel_device::block::BlockDevice;
+use kernel_ext2::{Directory, Ext2Fs, Ext2FsId, FormatOptions, Inode, Permissions, format};
+
+const SECTOR_SIZE: usize = 512;
+
+/// File contents are written in pieces of this size, so that large files
+/// don't have to be copied as a whole.
+const WRITE_CHUNK_SIZE: usize = 1 << 20;
+
+const MANIFEST_HELP: &str = "\
+A manifest has one entry per line, with tab separated fields:
+
+  dir      <path>
+  file     <path>  <source file>
+  symlink  <path>  <target>
+
+Parent directories are created as needed. Files are 0755 if their source is
+executable and 0644 otherwise, directories 0755 and symlinks 0777. Empty lines
+and lines starting with '#' are ignored.";
+
+#[derive(Parser)]
+#[command(
+    about = "Build an ext2 image from a directory tree or a manifest",
+    after_help = MANIFEST_HELP
+)]
+struct Args {
+    #[arg(
+        long,
+        help = "Size of the image ('64M', '1G', plain bytes etc.)",
+        default_value = "64M",
+        value_parser = parse_size
+    )]
+    size: u64,
+    #[arg(long, help = "Block size in bytes, chosen from the size by default")]
+    block_size: Option<u32>,
+    #[arg(
+        long,
+        help = "Bytes of space per inode, chosen from the size by default"
+    )]
+    bytes_per_inode: Option<u32>,
+    #[arg(long, help = "Volume label", default_value = "")]
+    label: String,
+    #[arg(
+        long,
+        help = "File system UUID, derived from the entries by default",
+        value_parser = parse_fsid
+    )]
+    uuid: Option<Ext2FsId>,
+    #[arg(
+        long,
+        help = "Time stamp of every inode, in seconds since the epoch",
+        default_value_t = 0
+    )]
+    timestamp: u32,
+    #[arg(long, help = "Directory whose contents become the root of the image")]
+    dir: Option<PathBuf>,
+    #[arg(long, help = "Manifest of entries to add to the image")]
+    manifest: Option<PathBuf>,
+    #[arg(help = "Image file to create or overwrite")]
+    output: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mkext2: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let mut tree = Tree::default();
+    if let Some(dir) = &args.dir {
+        tree.add_dir_contents(dir, "")?;
+    }
+    if let Some(manifest) = &args.manifest {
+        tree.add_manifest(manifest)?;
+    }
+
+    let options = FormatOptions {
+        block_size: args.block_size,
+        bytes_per_inode: args.bytes_per_inode,
+        label: args.label.clone(),
+        fsid: args.uuid.unwrap_or_else(|| tree.derive_fsid(&args.label)),
+        timestamp: args.timestamp,
+        ..FormatOptions::default()
+    };
+
+    let output = args.output.display();
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&args.output)
+        .map_err(|e| format!("{output}: {e}"))?;
+    file.set_len(args.size)
+        .map_err(|e| format!("{output}: {e}"))?;
+    let mut device = FileBlockDevice {
+        file,
+        size: args.size as usize,
+    };
+    format(&mut device, &options).map_err(|e| format!("{output}: {e}"))?;
+    let fs = Ext2Fs::try_new(device).map_err(|e| format!("{output}: {e}"))?;
+
+    tree.populate(fs, args.timestamp)
+}
+
+/// An entry of the image, as read from a directory or a manifest.
+#[derive(Debug, Eq, PartialEq)]
+enum Entry {
+    Dir,
+    File { source: PathBuf, executable: bool },
+    SymLink(String),
+}
+
+/// The entries of the image by their path, without a leading slash.
+///
+/// The order of the map puts every directory before its contents, so the
+/// entries can be created in it.
+#[derive(Default)]
+struct Tree(BTreeMap<String, Entry>);
+
+impl Tree {
+    fn insert(&mut self, path: &str, entry: Entry) -> Result<(), String> {
+        let path = path.trim_matches('/');
+        if path
+            .split('/')
+            .any(|component| matches!(component, "" | "." | ".."))
+        {
+            return Err(format!("{path}: invalid path in the image"));
+        }
+
+        // the parents are directories, whether they are listed or not
+        for (i, _) in path.match_indices('/') {
+            let parent = &path[..i];
+            match self.0.get(parent) {
+                None => {
+                    self.0.insert(parent.to_string(), Entry::Dir);
+                }
+                Some(Entry::Dir) => {}
+                Some(_) => return Err(format!("{parent}: not a directory")),
+            }
+        }
+
+        match self.0.get(path) {
+            None => {
+                self.0.insert(path.to_string(), entry);
+                Ok(())
+            }
+            Some(Entry::Dir) if entry == Entry::Dir => Ok(()),
+            Some(_) => Err(format!("{path}: listed more than once")),
+        }
+    }
+
+    /// Adds everything below `dir` as `prefix/...`.
+    fn add_dir_contents(&mut self, dir: &Path, prefix: &str) -> Result<(), String> {
+        let mut children = fs::read_dir(dir)
+            .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("{}: {e}", dir.display()))?;
+        children.sort_by_key(|child| child.file_name());
+
+        for child in children {
+            let source = child.path();
+            let name = child.file_name();
+            let name = name
+                .to_str()
+                .ok_or_else(|| format!("{}: name is not UTF-8", source.display()))?;
+            let path = format!("{prefix}/{name}");
+
+            let metadata =
+                fs::symlink_metadata(&source).map_err(|e| format!("{}: {e}", source.display()))?;
+            if metadata.is_dir() {
+                self.insert(&path, Entry::Dir)?;
+                self.add_dir_contents(&source, &path)?;
+            } else if metadata.is_symlink() {
+                let target =
+                    fs::read_link(&source).map_err(|e| format!("{}: {e}", source.display()))?;
+                let target = target
+                    .to_str()
+                    .ok_or_else(|| format!("{}: target is not UTF-8", source.display()))?;
+                self.insert(&path, Entry::SymLink(target.to_string()))?;
+            } else if metadata.is_file() {
+                let executable = metadata.permissions().mode() & 0o111 != 0;
+                self.insert(&path, Entry::File { source, executable })?;
+            } else {
+                return Err(format!("{}: unsupported file type", source.display()));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_manifest(&mut self, manifest: &Path) -> Result<(), String> {
+        let text =
+            fs::read_to_string(manifest).map_err(|e| format!("{}: {e}", manifest.display()))?;
+        for (number, line) in text.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at = || format!("{}:{}", manifest.display(), number + 1);
+            match line.split('\t').collect::<Vec<_>>()[..] {
+                ["dir", path] => self.insert(path, Entry::Dir),
+                ["file", path, source] => fs::metadata(source)
+                    .map_err(|e| format!("{source}: {e}"))
+                    .and_then(|metadata| {
+                        self.insert(
+                            path,
+                            Entry::File {
+                                source: PathBuf::from(source),
+                                executable: metadata.permissions().mode() & 0o111 != 0,
+                            },
+                        )
+                    }),
+                ["symlink", path, target] => self.insert(path, Entry::SymLink(target.to_string())),
+                _ => Err(format!("invalid entry: {line:?}")),
+            }
+            .map_err(|e| format!("{}: {e}", at()))?;
+        }
+        Ok(())
+    }
+
+    /// A UUID that only depends on the label and the entries, so that the
+    /// same inputs give the same image but different images can still be
+    /// told apart.
+    fn derive_fsid(&self, label: &str) -> Ext2FsId {
+        // two independent 64-bit FNV-1a hashes
+        let mut hashes = [0xcbf2_9ce4_8422_2325_u64, 0x6c62_272e_07bb_0142];
+        let mut hash = |bytes: &[u8]| {
+            for hash in &mut hashes {
+                for byte in bytes.iter().chain([&0]) {
+                    *hash = (*hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+                }
+            }
+        };
+
+        hash(label.as_bytes());
+        for (path, entry) in &self.0 {
+            hash(path.as_bytes());
+            match entry {
+                Entry::Dir => hash(b"d"),
+                Entry::File { source, .. } => {
+                    let len = fs::metadata(source).map_or(0, |m| m.len());
+                    hash(&len.to_le_bytes());
+                }
+                Entry::SymLink(target) => hash(target.as_bytes()),
+            }
+        }
+
+        let mut bytes = [0_u8; 16];
+        bytes[..8].copy_from_slice(&hashes[0].to_be_bytes());
+        bytes[8..].copy_from_slice(&hashes[1].to_be_bytes());
+        // version 8, the layout for UUIDs that aren't generated the usual ways
+        bytes[6] = bytes[6] & 0x0F | 0x80;
+        bytes[8] = bytes[8] & 0x3F | 0x80;
+        Ext2FsId::from(bytes)
+    }
+
+    fn populate<T: BlockDevice>(&self, mut fs: Ext2Fs<T>, timestamp: u32) -> Result<(), String> {
+        let root = fs.read_root_inode().map_err(|e| e.to_string())?;
+        let mut dirs = BTreeMap::from([(String::new(), root)]);
+
+        for (path, entry) in &self.0 {
+            let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+            // the parents sort before their contents, so they already exist
+            let parent: &mut Directory = dirs.get_mut(parent).unwrap();
+            let at = |e: kernel_ext2::Error| format!("{path}: {e}");
+
+            match entry {
+                Entry::Dir => {
+                    let mut dir = fs.create_directory(parent, name).map_err(at)?;
+                    stamp(dir.inode_mut(), 0o755, timestamp);
+                    fs.write_inode(dir.inode_address(), &dir).map_err(at)?;
+                    dirs.insert(path.clone(), dir);
+                }
+                Entry::File { source, executable } => {
+                    let data =
+                        fs::read(source).map_err(|e| format!("{}: {e}", source.display()))?;
+                    let mut file = fs.create_regular_file(parent, name).map_err(at)?;
+                    for (i, chunk) in data.chunks(WRITE_CHUNK_SIZE).enumerate() {
+                        fs.write_to_file(&mut file, i * WRITE_CHUNK_SIZE, chunk)
+                            .map_err(at)?;
+                    }
+                    let mode = if *executable { 0o755 } else { 0o644 };
+                    stamp(file.inode_mut(), mode, timestamp);
+                    fs.write_inode(file.inode_address(), &file).map_err(at)?;
+                }
+                Entry::SymLink(target) => {
+                    let mut link = fs.create_symlink(parent, name, target).map_err(at)?;
+                    stamp(link.inode_mut(), 0o777, timestamp);
+                    fs.write_inode(link.inode_address(), &link).map_err(at)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stamp(inode: &mut Inode, mode: u16, timestamp: u32) {
+    inode.set_perm(Permissions::from_bits_truncate(mode));
+    *inode.creation_time_mut() = timestamp;
+    *inode.last_access_time_mut() = timestamp;
+    *inode.last_modification_time_mut() = timestamp;
+}
+
+/// The image file.
+struct FileBlockDevice {
+    file: File,
+    size: usize,
+}
+
+impl BlockDevice for FileBlockDevice {
+    type Error = std::io::Error;
+
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn sector_count(&self) -> usize {
+        self.size / SECTOR_SIZE
+    }
+
+    fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_at(sector_index * SECTOR_SIZE, buf)
+    }
+
+    fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_at(sector_index * SECTOR_SIZE, buf)
+    }
+
+    // The file takes care of transfers that aren't aligned to sectors, so
+    // they are passed on as they are instead of a sector at a time.
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.file.read_exact_at(buf, offset as u64)?;
+        Ok(buf.len())
+    }
+
+    fn write_at(&mut self, offset: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.file.write_all_at(buf, offset as u64)?;
+        Ok(buf.len())
+    }
+}
+
+/// Parses a size with an optional `K`, `M` or `G` suffix.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (digits, unit) = match s.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&s[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&s[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("invalid size: {s}"))
+}
+
+fn parse_fsid(s: &str) -> Result<Ext2FsId, String> {
+    s.parse().map_err(|_| format!("invalid UUID: {s}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for the files of one test.
+    fn scratch(name: &str) -> PathBuf {
+        let root = std::env::var_os("TEST_TMPDIR").map_or_else(std::env::temp_dir, PathBuf::from);
+        let dir = root.join(format!("mkext2-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file(source: &Path, executable: bool) -> Entry {
+        Entry::File {
+            source: source.to_path_buf(),
+            executable,
+        }
+    }
+
+    #[test]
+    fn test_parse_size() {
+        for (s, expected) in [
+            ("4096", 4096),
+            ("0", 0),
+            ("2k", 2 << 10),
+            ("2K", 2 << 10),
+            ("64M", 64 << 20),
+            ("1G", 1 << 30),
+            ("1g", 1 << 30),
+        ] {
+            assert_eq!(parse_size(s), Ok(expected), "{s}");
+        }
+
+        for s in ["", "M", "-1", "1.5M", "12X", "1 M", "18446744073709551615K"] {
+            assert_eq!(parse_size(s), Err(format!("invalid size: {s}")), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree = Tree::default();
+        tree.insert("/bin/sh", Entry::SymLink("busybox".into()))
+            .unwrap();
+        tree.insert("etc/", Entry::Dir).unwrap();
+        tree.insert("etc", Entry::Dir).unwrap();
+        tree.insert("bin", Entry::Dir).unwrap();
+        assert_eq!(
+            tree.0.into_iter().collect::<Vec<_>>(),
+            [
+                ("bin".to_string(), Entry::Dir),
+                ("bin/sh".to_string(), Entry::SymLink("busybox".into())),
+                ("etc".to_string(), Entry::Dir),
+            ]
+        );
+
+        let mut tree = Tree::default();
+        tree.insert("a/file", file(Path::new("x"), false)).unwrap();
+        for (path, entry, error) in [
+            ("a/file", Entry::Dir, "a/file: listed more than once"),
+            (
+                "a/file",
+                file(Path::new("x"), false),
+                "a/file: listed more than once",
+            ),
+            ("a", file(Path::new("x"), false), "a: listed more than once"),
+            ("a/file/b", Entry::Dir, "a/file: not a directory"),
+            ("", Entry::Dir, ": invalid path in the image"),
+            ("/", Entry::Dir, ": invalid path in the image"),
+            ("..", Entry::Dir, "..: invalid path in the image"),
+            ("a/../b", Entry::Dir, "a/../b: invalid path in the image"),
+            ("a/./b", Entry::Dir, "a/./b: invalid path in the image"),
+            ("a//b", Entry::Dir, "a//b: invalid path in the image"),
+        ] {
+            assert_eq!(tree.insert(path, entry), Err(error.to_string()), "{path}");
+        }
+        // the rejected entries left nothing behind
+        assert_eq!(tree.0.len(), 2);
+    }
+
+    #[test]
+    fn test_manifest() {
+        let dir = scratch("manifest");
+        let plain = dir.join("plain");
+        let executable = dir.join("executable");
+        fs::write(&plain, "plain").unwrap();
+        fs::write(&executable, "executable").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o700)).unwrap();
+
+        let manifest = dir.join("manifest");
+        fs::write(
+            &manifest,
+            format!(
+                "# comment\n\
+                 \n\
+                 dir\tvar/empty\n\
+                 file\tetc/motd\t{}\n\
+                 file\tbin/init\t{}\n\
+                 symlink\tbin/sh\tinit\n",
+                plain.display(),
+                executable.display()
+            ),
+        )
+        .unwrap();
+        let mut tree = Tree::default();
+        tree.add_manifest(&manifest).unwrap();
+        assert_eq!(
+            tree.0.into_iter().collect::<Vec<_>>(),
+            [
+                ("bin".to_string(), Entry::Dir),
+                ("bin/init".to_string(), file(&executable, true)),
+                ("bin/sh".to_string(), Entry::SymLink("init".into())),
+                ("etc".to_string(), Entry::Dir),
+                ("etc/motd".to_string(), file(&plain, false)),
+                ("var".to_string(), Entry::Dir),
+                ("var/empty".to_string(), Entry::Dir),
+            ]
+        );
+
+        let missing = dir.join("missing");
+        for (line, error) in [
+            ("dir", "invalid entry: \"dir\""),
+            ("dir a b", "invalid entry: \"dir a b\""),
+            ("dir\ta\tb", "invalid entry: \"dir\\ta\\tb\""),
+            ("file\ta", "invalid entry: \"file\\ta\""),
+            ("socket\ta", "invalid entry: \"socket\\ta\""),
+            ("symlink\t..\tb", "..: invalid path in the image"),
+        ] {
+            fs::write(&manifest, format!("dir\tok\n{line}\n")).unwrap();
+            assert_eq!(
+                Tree::default().add_manifest(&manifest),
+                Err(format!("{}:2: {error}", manifest.display())),
+                "{line:?}"
+            );
+        }
+
+        fs::write(&manifest, format!("file\ta\t{}\n", missing.display())).unwrap();
+        let error = Tree::default().add_manifest(&manifest).unwrap_err();
+        assert!(
+            error.starts_with(&format!(
+                "{}:1: {}: ",
+                manifest.display(),
+                missing.display()
+            )),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let dir = scratch("reproducible");
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("etc/empty")).unwrap();
+        fs::write(root.join("etc/motd"), "hello\n").unwrap();
+        fs::write(root.join("bin/init"), vec![0x7f; 3 * WRITE_CHUNK_SIZE / 2]).unwrap();
+        fs::set_permissions(root.join("bin/init"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("init", root.join("bin/sh")).unwrap();
+
+        let build = |output: &str| {
+            let output = dir.join(output);
+            let args = Args {
+                size: 8 << 20,
+                block_size: None,
+                bytes_per_inode: None,
+                label: "test".to_string(),
+                uuid: None,
+                timestamp: 1_700_000_000,
+                dir: Some(root.clone()),
+                manifest: None,
+                output: output.clone(),
+            };
+            run(&args).unwrap();
+            fs::read(output).unwrap()
+        };
+
+        let first = build("first.img");
+        // rewriting a source changes its mtime, which must not show up in the image
+        fs::write(root.join("etc/motd"), "hello\n").unwrap();
+        let second = build("second.img");
+        assert_eq!(first.len(), 8 << 20);
+        assert!(first == second, "the images differ");
+    }
+}
@@ -8,6 +8,8 @@ mod io;
 mod panic;
 mod start;
 
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::arch::asm;
 use core::arch::x86_64::_mm_pause;
@@ -15,13 +17,19 @@ use core::ffi::c_int;
 
 pub use io::{Stderr, Stdout};
 pub use kernel_abi::{
-    ARG_MAX, CLOCK_MONOTONIC, CLOCK_REALTIME, DefaultAction, E2BIG, EACCES, EBADF, EFAULT, EINTR,
-    EINVAL, EISDIR, ENAMETOOLONG, ENOENT, ENOEXEC, ENOMEM, ENOTDIR, ENOTTY, EOVERFLOW, EPERM,
-    ERANGE, ESPIPE, ESRCH, Errno, FbScreenInfo, IoctlRequest, MapFlags, PATH_MAX, ProtFlags,
-    SYS_CLOCK_GETTIME, SYS_EXE_PATH, SYS_EXECVE, SYS_EXIT, SYS_FSTAT, SYS_FSYNC, SYS_GETCWD,
-    SYS_GETPID, SYS_IOCTL, SYS_KILL, SYS_LSEEK, SYS_MMAP, SYS_NANOSLEEP, SYS_OPEN, SYS_READ,
-    SYS_SIGACTION, SYS_SIGPENDING, SYS_SIGPROCMASK, SYS_SIGRETURN, SYS_WRITE, SaFlags, SigAction,
-    SigHandler, SigMaskHow, SigSet, Signal, Stat, StrSlice, Timespec, Whence,
+    ARG_MAX, AbsInfo, AbsoluteAxis, CLOCK_MONOTONIC, CLOCK_REALTIME, DefaultAction, E2BIG, EACCES,
+    EAGAIN, EBADF, EBUSY, EEXIST, EFAULT, EINTR, EINVAL, EIO, EISDIR, ENAMETOOLONG, ENODEV, ENOENT,
+    ENOEXEC, ENOMEM, ENOTDIR, ENOTEMPTY, ENOTTY, EOVERFLOW, EPERM, ERANGE, ESPIPE, ESRCH, Errno,
+    EventType, FbScreenInfo, INPUT_NAME_SIZE, InputCapabilities, InputEvent, InputFlags,
+    IoctlRequest, KEY_PRESSED, KEY_RELEASED, KeyCode, LocalFlags, MapFlags, MountArgs, MountFlags,
+    O_CREAT, O_EXCL, O_NONBLOCK, O_RDWR, OutputFlags, PATH_MAX, PollEvents, PollFd, ProtFlags,
+    RelativeAxis, S_IFDIR, S_IFMT, S_IFREG, SYN_DROPPED, SYN_REPORT, SYS_CLOCK_GETTIME,
+    SYS_EXE_PATH, SYS_EXECVE, SYS_EXIT, SYS_FSTAT, SYS_FSYNC, SYS_GETCWD, SYS_GETPGID, SYS_GETPID,
+    SYS_GETPPID, SYS_GETSID, SYS_IOCTL, SYS_KILL, SYS_LSEEK, SYS_MMAP, SYS_MOUNT, SYS_MOUNT_TABLE,
+    SYS_NANOSLEEP, SYS_OPEN, SYS_POLL, SYS_READ, SYS_READLINK, SYS_SETPGID, SYS_SETSID,
+    SYS_SIGACTION, SYS_SIGPENDING, SYS_SIGPROCMASK, SYS_SIGRETURN, SYS_UMOUNT, SYS_WRITE, SaFlags,
+    SigAction, SigHandler, SigMaskHow, SigSet, Signal, Stat, StrSlice, Termios, Timespec, VEOF,
+    VERASE, VINTR, VKILL, VMIN, VQUIT, VSUSP, VTIME, Whence, WinSize,
 };
 pub use panic::catch_unwind;
 pub use start::{__muffin_start_inner, args, env};
@@ -85,6 +93,99 @@ pub fn ioctl<T>(fd: c_int, request: IoctlRequest, arg: &mut T) -> Result<usize,
     ))
 }
 
+/// Reads the attributes of the terminal open under `fd`.
+pub fn tcgetattr(fd: c_int) -> Result<Termios, Errno> {
+    let mut bytes = [0_u8; Termios::SIZE];
+    ioctl(fd, IoctlRequest::TcGetAttr, &mut bytes)?;
+    Ok(Termios::from_bytes(&bytes))
+}
+
+/// Changes the attributes of the terminal open under `fd`. They apply right
+/// away, to input that is already buffered, too.
+pub fn tcsetattr(fd: c_int, termios: &Termios) -> Result<(), Errno> {
+    let mut bytes = termios.to_bytes();
+    ioctl(fd, IoctlRequest::TcSetAttr, &mut bytes).map(|_| ())
+}
+
+/// Returns the foreground process group of the terminal open under `fd`,
+/// which must be the caller's controlling terminal.
+pub fn tcgetpgrp(fd: c_int) -> Result<i64, Errno> {
+    let mut unused = 0_u64;
+    ioctl(fd, IoctlRequest::TcGetPgrp, &mut unused).map(|pgid| pgid as i64)
+}
+
+/// Makes `pgid`, a process group in the caller's session, the foreground
+/// process group of the terminal open under `fd`.
+pub fn tcsetpgrp(fd: c_int, pgid: i64) -> Result<(), Errno> {
+    let mut pgid = pgid as u64;
+    ioctl(fd, IoctlRequest::TcSetPgrp, &mut pgid).map(|_| ())
+}
+
+/// Reads the window size of the terminal open under `fd`.
+pub fn tcgetwinsize(fd: c_int) -> Result<WinSize, Errno> {
+    let mut bytes = [0_u8; WinSize::SIZE];
+    ioctl(fd, IoctlRequest::TcGetWinSize, &mut bytes)?;
+    Ok(WinSize::from_bytes(&bytes))
+}
+
+/// Changes the window size of the terminal open under `fd`, which sends
+/// `SIGWINCH` to its foreground process group.
+pub fn tcsetwinsize(fd: c_int, size: &WinSize) -> Result<(), Errno> {
+    let mut bytes = size.to_bytes();
+    ioctl(fd, IoctlRequest::TcSetWinSize, &mut bytes).map(|_| ())
+}
+
+/// Opens the master of a new pseudo-terminal.
+pub fn posix_openpt() -> Result<c_int, Errno> {
+    open_with("/dev/ptmx", O_RDWR)
+}
+
+/// Returns the path of the slave that belongs to the pseudo-terminal
+/// master open under `fd`.
+pub fn ptsname(fd: c_int) -> Result<String, Errno> {
+    let mut unused = 0_u32;
+    let number = ioctl(fd, IoctlRequest::PtyGetNumber, &mut unused)?;
+    Ok(format!("/dev/pts/{number}"))
+}
+
+/// Returns the name of the input device open under `fd`.
+pub fn input_name(fd: c_int) -> Result<String, Errno> {
+    let mut bytes = [0_u8; INPUT_NAME_SIZE];
+    let len = ioctl(fd, IoctlRequest::InputGetName, &mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+/// Returns which events the input device open under `fd` reports.
+pub fn input_capabilities(fd: c_int) -> Result<InputCapabilities, Errno> {
+    let mut bytes = [0_u8; InputCapabilities::SIZE];
+    ioctl(fd, IoctlRequest::InputGetCapabilities, &mut bytes)?;
+    Ok(InputCapabilities::from_bytes(&bytes))
+}
+
+/// Returns the range and last position of an absolute axis of the input
+/// device open under `fd`.
+pub fn input_abs_info(fd: c_int, axis: AbsoluteAxis) -> Result<AbsInfo, Errno> {
+    let mut bytes = AbsInfo {
+        axis: axis as u32,
+        ..AbsInfo::default()
+    }
+    .to_bytes();
+    ioctl(fd, IoctlRequest::InputGetAbsInfo, &mut bytes)?;
+    Ok(AbsInfo::from_bytes(&bytes))
+}
+
+/// Waits until one of `fds` is ready, for at most `timeout` milliseconds,
+/// or without limit if it is negative. Returns how many entries have
+/// `revents` set, 0 if the timeout expired.
+pub fn poll(fds: &mut [PollFd], timeout: c_int) -> Result<usize, Errno> {
+    ret(syscall3(
+        SYS_POLL,
+        fds.as_mut_ptr() as usize,
+        fds.len(),
+        timeout as usize,
+    ))
+}
+
 pub fn fsync(fd: c_int) -> Result<(), Errno> {
     ret(syscall1(SYS_FSYNC, fd as usize)).map(|_| ())
 }
@@ -156,6 +257,31 @@ pub fn getpid() -> i64 {
     syscall0(SYS_GETPID) as i64
 }
 
+pub fn getppid() -> i64 {
+    syscall0(SYS_GETPPID) as i64
+}
+
+/// Returns the process group of `pid`, or of the caller for 0.
+pub fn getpgid(pid: i64) -> Result<i64, Errno> {
+    ret(syscall1(SYS_GETPGID, pid as usize)).map(|pgid| pgid as i64)
+}
+
+/// Moves `pid` into the process group `pgid`. 0 stands for the caller as
+/// `pid`, and for `pid` as `pgid`.
+pub fn setpgid(pid: i64, pgid: i64) -> Result<(), Errno> {
+    ret(syscall2(SYS_SETPGID, pid as usize, pgid as usize)).map(|_| ())
+}
+
+/// Returns the session of `pid`, or of the caller for 0.
+pub fn getsid(pid: i64) -> Result<i64, Errno> {
+    ret(syscall1(SYS_GETSID, pid as usize)).map(|sid| sid as i64)
+}
+
+/// Starts a new session without a controlling terminal and returns its id.
+pub fn setsid() -> Result<i64, Errno> {
+    ret(syscall0(SYS_SETSID)).map(|sid| sid as i64)
+}
+
 pub fn install_handler(signo: Signal, handler: extern "C" fn(Signal)) -> Result<(), Errno> {
     let action = SigAction {
         handler: SigHandler::new(handler as usize),
@@ -276,12 +402,31 @@ pub fn mmap(
     .map(|a| a as *mut u8)
 }
 
+/// Returns the number of target bytes written into `buf`. The target is
+/// truncated to `buf.len()` and not null-terminated.
+pub fn readlink(path: &str, buf: &mut [u8]) -> Result<usize, Errno> {
+    ret(syscall6(
+        SYS_READLINK,
+        path.as_ptr() as usize,
+        path.len(),
+        buf.as_mut_ptr() as usize,
+        buf.len(),
+        0,
+        0,
+    ))
+}
+
 pub fn open(path: &str) -> Result<c_int, Errno> {
+    open_with(path, 0)
+}
+
+/// Opens `path` with the `O_*` flags in `oflag`, such as [`O_CREAT`].
+pub fn open_with(path: &str, oflag: c_int) -> Result<c_int, Errno> {
     ret(syscall6(
         SYS_OPEN,
         path.as_ptr() as usize,
         path.len(),
-        0,
+        oflag as usize,
         0,
         0,
         0,
@@ -289,6 +434,33 @@ pub fn open(path: &str) -> Result<c_int, Errno> {
     .map(|fd| fd as c_int)
 }
 
+/// Mounts a file system of the type `fs_type` at `target`. An empty `source`
+/// is for file systems that don't live on a block device.
+pub fn mount(fs_type: &str, source: &str, target: &str, flags: MountFlags) -> Result<(), Errno> {
+    let args = MountArgs {
+        fs_type: fs_type.into(),
+        source: source.into(),
+        target: target.into(),
+        flags: flags.bits(),
+    };
+    ret(syscall1(SYS_MOUNT, &raw const args as usize)).map(|_| ())
+}
+
+pub fn umount(target: &str) -> Result<(), Errno> {
+    ret(syscall2(SYS_UMOUNT, target.as_ptr() as usize, target.len())).map(|_| ())
+}
+
+/// Copies the mount table into `buf`, one mount per line in the format of
+/// `/proc/mounts`, and returns its length. Fails with `ERANGE` if `buf` is
+/// too small.
+pub fn mount_table(buf: &mut [u8]) -> Result<usize, Errno> {
+    ret(syscall2(
+        SYS_MOUNT_TABLE,
+        buf.as_mut_ptr() as usize,
+        buf.len(),
+    ))
+}
+
 /// Never returns on success, so the result is always the failure reason.
 pub fn execve(path: &str, argv: &[&str], envp: &[&str]) -> Errno {
     let argv_v = argv.iter().map(|&s| StrSlice::from(s)).collect::<Vec<_>>();
@@ -0,0 +1,172 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::ffi::c_int;
+
+use kernel_device::block::BlockDevice;
+use kernel_ext2::{Ext2FsId, FormatOptions, format};
+use minilib::{
+    CLOCK_MONOTONIC, CLOCK_REALTIME, EIO, Errno, O_RDWR, Stat, Timespec, Whence, args,
+    clock_gettime, eprintln, fstat, fsync, lseek, open_with, println, read, write,
+};
+
+const USAGE: &str = "usage: mkfs [-b block-size] [-i bytes-per-inode] [-L label] device";
+
+const SECTOR_SIZE: usize = 512;
+
+minilib::entry!(main);
+
+/// A device file, such as `/dev/blk1`, or an image file.
+struct FileBlockDevice {
+    fd: c_int,
+    size: usize,
+}
+
+impl BlockDevice for FileBlockDevice {
+    type Error = Errno;
+
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn sector_count(&self) -> usize {
+        self.size / SECTOR_SIZE
+    }
+
+    fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_at(sector_index * SECTOR_SIZE, buf)
+    }
+
+    fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_at(sector_index * SECTOR_SIZE, buf)
+    }
+
+    // The file takes care of transfers that aren't aligned to sectors, so
+    // they are passed on as they are instead of a sector at a time.
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        lseek(self.fd, offset as i64, Whence::Set)?;
+        let mut done = 0;
+        while done < buf.len() {
+            match read(self.fd, &mut buf[done..])? {
+                0 => return Err(EIO),
+                n => done += n,
+            }
+        }
+        Ok(done)
+    }
+
+    fn write_at(&mut self, offset: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        lseek(self.fd, offset as i64, Whence::Set)?;
+        let mut done = 0;
+        while done < buf.len() {
+            match write(self.fd, &buf[done..])? {
+                0 => return Err(EIO),
+                n => done += n,
+            }
+        }
+        Ok(done)
+    }
+}
+
+fn main() -> i32 {
+    let Some((device_path, mut options)) = parse_args() else {
+        eprintln!("{USAGE}");
+        return 2;
+    };
+
+    let fd = match open_with(device_path, O_RDWR) {
+        Ok(fd) => fd,
+        Err(e) => {
+            eprintln!("mkfs: {device_path}: {e}");
+            return 1;
+        }
+    };
+    let mut stat = Stat::default();
+    if let Err(e) = fstat(fd, &mut stat) {
+        eprintln!("mkfs: {device_path}: {e}");
+        return 1;
+    }
+    let mut device = FileBlockDevice {
+        fd,
+        size: stat.size as usize,
+    };
+
+    let now = now();
+    options.timestamp = now.tv_sec as u32;
+    options.fsid = generate_fsid(&now);
+    let superblock = match format(&mut device, &options) {
+        Ok(superblock) => superblock,
+        Err(e) => {
+            eprintln!("mkfs: {device_path}: {e}");
+            return 1;
+        }
+    };
+    if let Err(e) = fsync(fd) {
+        eprintln!("mkfs: {device_path}: {e}");
+        return 1;
+    }
+
+    let num_groups = (superblock.num_blocks() - superblock.superblock_block_number())
+        .div_ceil(superblock.blocks_per_group());
+    println!(
+        "mkfs: {device_path}: {} blocks of {} bytes, {} inodes, {num_groups} block groups",
+        superblock.num_blocks(),
+        superblock.block_size(),
+        superblock.num_inodes(),
+    );
+    println!("mkfs: {device_path}: uuid {}", superblock.fsid());
+    0
+}
+
+/// Parses `[-b block-size] [-i bytes-per-inode] [-L label] device`.
+fn parse_args() -> Option<(&'static str, FormatOptions)> {
+    fn text(arg: Option<&'static [u8]>) -> Option<&'static str> {
+        core::str::from_utf8(arg?).ok()
+    }
+
+    let mut options = FormatOptions::default();
+    let mut device = None;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg {
+            b"-b" => options.block_size = Some(text(args.next())?.parse().ok()?),
+            b"-i" => options.bytes_per_inode = Some(text(args.next())?.parse().ok()?),
+            b"-L" => options.label = String::from(text(args.next())?),
+            _ if arg.starts_with(b"-") || device.is_some() => return None,
+            _ => device = Some(text(Some(arg))?),
+        }
+    }
+    Some((device?, options))
+}
+
+fn now() -> Timespec {
+    let mut now = Timespec::default();
+    let _ = clock_gettime(CLOCK_REALTIME, &mut now);
+    now
+}
+
+/// A version 4 UUID. There is no source of randomness, so the bits are
+/// mixed from the clocks, which is good enough to tell file systems apart.
+fn generate_fsid(now: &Timespec) -> Ext2FsId {
+    let mut uptime = Timespec::default();
+    let _ = clock_gettime(CLOCK_MONOTONIC, &mut uptime);
+    let mut state =
+        (now.tv_sec as u64) << 32 ^ now.tv_nsec as u64 ^ (uptime.tv_nsec as u64).rotate_left(29);
+
+    let mut bytes = [0_u8; 16];
+    for chunk in bytes.as_chunks_mut::<8>().0 {
+        // splitmix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        *chunk = (z ^ (z >> 31)).to_le_bytes();
+    }
+    bytes[6] = bytes[6] & 0x0F | 0x40;
+    bytes[8] = bytes[8] & 0x3F | 0x80;
+    Ext2FsId::from(bytes)
+}